@@ -0,0 +1,30 @@
+#!/usr/bin/env python3
+"""Convert an equation screenshot to LaTeX with pix2tex (LaTeX-OCR) for Stellar.
+
+Prints the LaTeX, without delimiters, on stdout.
+
+Usage: stellar-pix2tex <image>
+"""
+
+import sys
+
+from PIL import Image
+from pix2tex.cli import LatexOCR
+
+
+def main():
+    if len(sys.argv) != 2:
+        print("Usage: stellar-pix2tex <image>", file=sys.stderr)
+        sys.exit(2)
+
+    image = Image.open(sys.argv[1]).convert("RGB")
+    latex = LatexOCR()(image)
+    if not latex or not latex.strip():
+        print("No equation recognised", file=sys.stderr)
+        sys.exit(1)
+
+    sys.stdout.write(latex.strip())
+
+
+if __name__ == "__main__":
+    main()
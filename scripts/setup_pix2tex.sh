@@ -0,0 +1,34 @@
+#!/bin/bash
+
+# Setup script for local equation recognition with pix2tex (LaTeX-OCR)
+
+echo "Setting up pix2tex equation recognition..."
+
+# Check if Python 3.8+ is installed
+if ! command -v python3 &> /dev/null; then
+    echo "Python 3 is required but not installed. Please install Python 3.8+ first."
+    exit 1
+fi
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+
+# Create virtual environment
+python3 -m venv pix2tex_env
+source pix2tex_env/bin/activate
+
+# Install pix2tex and its dependencies
+pip install -U "pix2tex" pillow
+
+# Wrapper the app runs with the screenshot as its only argument
+cat > pix2tex_env/bin/stellar-pix2tex <<WRAPPER
+#!/bin/bash
+exec "$(pwd)/pix2tex_env/bin/python" "$SCRIPT_DIR/pix2tex_latex.py" "\$@"
+WRAPPER
+chmod +x pix2tex_env/bin/stellar-pix2tex
+
+# Download the model weights now rather than on the first conversion
+python -c "from pix2tex.cli import LatexOCR; LatexOCR()"
+
+echo "pix2tex setup complete!"
+echo ""
+echo "Stellar finds pix2tex_env/bin/stellar-pix2tex automatically, or set STELLAR_PIX2TEX_BIN to its path."
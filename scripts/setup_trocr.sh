@@ -0,0 +1,35 @@
+#!/bin/bash
+
+# Setup script for local handwriting recognition with TrOCR
+
+echo "Setting up TrOCR handwriting recognition..."
+
+# Check if Python 3.8+ is installed
+if ! command -v python3 &> /dev/null; then
+    echo "Python 3 is required but not installed. Please install Python 3.8+ first."
+    exit 1
+fi
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+
+# Create virtual environment
+python3 -m venv trocr_env
+source trocr_env/bin/activate
+
+# Install TrOCR's dependencies
+pip install -U torch transformers pillow
+
+# Wrapper the app runs with the photo as its only argument
+cat > trocr_env/bin/stellar-trocr <<WRAPPER
+#!/bin/bash
+exec "$(pwd)/trocr_env/bin/python" "$SCRIPT_DIR/trocr_ocr.py" "\$@"
+WRAPPER
+chmod +x trocr_env/bin/stellar-trocr
+
+# Download the model now rather than on the first import
+python -c "from transformers import TrOCRProcessor, VisionEncoderDecoderModel; m='microsoft/trocr-base-handwritten'; TrOCRProcessor.from_pretrained(m); VisionEncoderDecoderModel.from_pretrained(m)"
+
+echo "TrOCR setup complete!"
+echo ""
+echo "Stellar finds trocr_env/bin/stellar-trocr automatically, or set STELLAR_TROCR_BIN to its path."
+echo "Set STELLAR_TROCR_MODEL to use another TrOCR checkpoint (e.g. microsoft/trocr-large-handwritten)."
@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+"""Read handwriting in a photo with TrOCR for Stellar's handwritten notes import.
+
+TrOCR recognises one line at a time, so the page is first cut into text lines using the
+horizontal ink profile. Prints {"lines": [{"text": ..., "confidence": 0-1}]} on stdout.
+
+Usage: stellar-trocr <image>
+"""
+
+import json
+import os
+import sys
+
+import torch
+from PIL import Image, ImageOps
+from transformers import TrOCRProcessor, VisionEncoderDecoderModel
+
+MODEL = os.environ.get("STELLAR_TROCR_MODEL", "microsoft/trocr-base-handwritten")
+MIN_LINE_HEIGHT = 12  # Pixels at the working width; shorter bands are specks or underlines
+WORKING_WIDTH = 1600
+
+
+def split_lines(image):
+    """Bands of rows containing ink, as (top, bottom) pairs."""
+    gray = ImageOps.grayscale(image)
+    if gray.width > WORKING_WIDTH:
+        scale = WORKING_WIDTH / gray.width
+        gray = gray.resize((WORKING_WIDTH, int(gray.height * scale)))
+    else:
+        scale = 1.0
+
+    pixels = gray.load()
+    threshold = sum(gray.getdata()) / (gray.width * gray.height) * 0.75  # Ink is darker than the page
+    row_ink = [
+        sum(1 for x in range(0, gray.width, 2) if pixels[x, y] < threshold)
+        for y in range(gray.height)
+    ]
+    min_ink = max(2, gray.width // 200)
+
+    bands, top = [], None
+    for y, ink in enumerate(row_ink + [0]):
+        if ink >= min_ink and top is None:
+            top = y
+        elif ink < min_ink and top is not None:
+            if y - top >= MIN_LINE_HEIGHT:
+                bands.append((int(top / scale), int(y / scale)))
+            top = None
+    return bands
+
+
+def main():
+    if len(sys.argv) != 2:
+        print("Usage: stellar-trocr <image>", file=sys.stderr)
+        sys.exit(2)
+
+    image = ImageOps.exif_transpose(Image.open(sys.argv[1])).convert("RGB")
+    processor = TrOCRProcessor.from_pretrained(MODEL)
+    model = VisionEncoderDecoderModel.from_pretrained(MODEL)
+    model.eval()
+
+    lines = []
+    for top, bottom in split_lines(image):
+        margin = max(4, (bottom - top) // 4)
+        crop = image.crop((0, max(0, top - margin), image.width, min(image.height, bottom + margin)))
+        pixel_values = processor(images=crop, return_tensors="pt").pixel_values
+        with torch.no_grad():
+            output = model.generate(pixel_values, max_new_tokens=96, output_scores=True, return_dict_in_generate=True)
+        text = processor.batch_decode(output.sequences, skip_special_tokens=True)[0].strip()
+        if not text:
+            continue
+
+        # Mean probability of the chosen tokens
+        scores = model.compute_transition_scores(output.sequences, output.scores, normalize_logits=True)
+        confidence = float(torch.exp(scores[0]).mean()) if scores.numel() else 0.0
+        lines.append({"text": text, "confidence": round(confidence, 4)})
+
+    json.dump({"lines": lines}, sys.stdout)
+
+
+if __name__ == "__main__":
+    main()
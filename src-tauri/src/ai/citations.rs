@@ -0,0 +1,110 @@
+//! Citations in RAG answers. Excerpts are shown to the model numbered [1], [2]...; the [n]
+//! markers it writes after claims are rewritten as markdown footnotes ([^1]) numbered in order
+//! of first citation, with a definition per footnote naming the document and page, so every
+//! claim can be checked against the passage it came from.
+
+use std::collections::HashMap;
+
+/// Appended to RAG system prompts after the numbered excerpts
+pub const CITATION_INSTRUCTIONS: &str = "Cite the excerpt that supports each claim with its number in square brackets \
+    right after the claim, e.g. [2] or [1][3]. Only cite excerpts that actually say what you claim, and never make up \
+    numbers. Don't add a list of sources at the end; it is added for you.";
+
+// A paragraph prefix this long is specific enough to find where an excerpt sits in its document
+const LOCATE_PREFIX_CHARS: usize = 80;
+// Shorter paragraphs (headings, list markers) match in too many places
+const MIN_LOCATE_CHARS: usize = 20;
+
+/// An answer with its citations rewritten as footnotes
+#[derive(Debug, Clone, PartialEq)]
+pub struct FootnotedAnswer {
+    pub content: String,
+    pub footnotes: Vec<(usize, usize)>, // (footnote number, 0-based excerpt index), by footnote number
+}
+
+/// Char range of `excerpt` within `content`. Chunks start with an overlap from the previous
+/// chunk that may not appear verbatim, so the first paragraph that can be found anchors the
+/// range.
+pub fn locate_excerpt(content: &str, excerpt: &str) -> Option<(usize, usize)> {
+    let excerpt_chars = excerpt.chars().count();
+    let mut skipped_chars = 0;
+
+    for paragraph in excerpt.split("\n\n") {
+        let trimmed = paragraph.trim();
+        if trimmed.chars().count() >= MIN_LOCATE_CHARS {
+            let prefix: String = trimmed.chars().take(LOCATE_PREFIX_CHARS).collect();
+            if let Some(byte_index) = content.find(&prefix) {
+                let start = content[..byte_index].chars().count();
+                let end = (start + excerpt_chars.saturating_sub(skipped_chars)).min(content.chars().count());
+                return Some((start, end));
+            }
+        }
+        skipped_chars += paragraph.chars().count() + 2;
+    }
+    None
+}
+
+/// Rewrite the [n] citations of `answer` (1-based excerpt numbers, also [1, 3]) as footnote
+/// references and append a definition for each from `labels`, one per excerpt. Numbers
+/// without an excerpt and links like [1](...) are left alone.
+pub fn footnote_answer(answer: &str, labels: &[String]) -> FootnotedAnswer {
+    let mut footnote_for_excerpt: HashMap<usize, usize> = HashMap::new();
+    let mut footnotes = Vec::new();
+    let mut content = String::with_capacity(answer.len());
+    let mut rest = answer;
+
+    while let Some(open) = rest.find('[') {
+        content.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let citation = after.find(']')
+            .filter(|close| !after[close + 1..].starts_with('('))
+            .and_then(|close| parse_citation(&after[..close], labels.len()).map(|numbers| (close, numbers)));
+
+        match citation {
+            Some((close, numbers)) => {
+                for number in numbers {
+                    let next = footnotes.len() + 1;
+                    let footnote = *footnote_for_excerpt.entry(number - 1).or_insert_with(|| {
+                        footnotes.push((next, number - 1));
+                        next
+                    });
+                    content.push_str(&format!("[^{}]", footnote));
+                }
+                rest = &after[close + 1..];
+            }
+            None => {
+                content.push('[');
+                rest = after;
+            }
+        }
+    }
+    content.push_str(rest);
+
+    if !footnotes.is_empty() {
+        let content_end = content.trim_end().len();
+        content.truncate(content_end);
+        content.push_str("\n\n");
+        let definitions: Vec<String> = footnotes.iter()
+            .map(|(footnote, excerpt)| format!("[^{}]: {}", footnote, labels[*excerpt]))
+            .collect();
+        content.push_str(&definitions.join("\n"));
+    }
+
+    FootnotedAnswer { content, footnotes }
+}
+
+// "2" or "1, 3" -> excerpt numbers, when every one is in 1..=count
+fn parse_citation(inner: &str, count: usize) -> Option<Vec<usize>> {
+    let numbers: Vec<usize> = inner.split(',')
+        .map(|part| part.trim().parse::<usize>().ok().filter(|number| (1..=count).contains(number)))
+        .collect::<Option<_>>()?;
+    (!numbers.is_empty()).then_some(numbers)
+}
+
+/// "Title, p. 4" when the page is known
+pub fn source_label(title: &str, page: Option<i32>) -> String {
+    match page {
+        Some(page) => format!("{}, p. {}", title, page),
+        None => title.to_string(),
+    }
+}
@@ -0,0 +1,161 @@
+//! Keeps chat requests inside the model's context window. Token counts are estimated rather
+//! than exact (providers use different tokenizers), so the budget leaves some headroom.
+
+use super::types::{ChatCompletionRequest, ChatMessage};
+
+/// Reserved for the reply when the request doesn't set max_tokens
+const DEFAULT_RESERVED_OUTPUT: u32 = 1024;
+/// Role markers and separators each message adds on top of its content
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+/// Estimates can undershoot; only fill this share of the window
+const BUDGET_RATIO: f64 = 0.9;
+
+/// Rough token count: ~4 characters per token for Latin text, one per character otherwise
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text.chars().fold((0usize, 0usize), |(ascii, other), c| {
+        if c.is_ascii() { (ascii + 1, other) } else { (ascii, other + 1) }
+    });
+    ascii.div_ceil(4) + other
+}
+
+pub fn estimate_message_tokens(messages: &[ChatMessage]) -> usize {
+    messages.iter()
+        .map(|m| estimate_tokens(&m.content) + MESSAGE_OVERHEAD_TOKENS)
+        .sum()
+}
+
+#[derive(Debug, Clone)]
+pub struct ContextPruning {
+    pub dropped_messages: usize,
+    pub estimated_tokens: usize, // After pruning
+    pub budget: usize,
+}
+
+impl ContextPruning {
+    pub fn warning(&self) -> String {
+        format!(
+            "{} earlier message{} omitted to fit the model's context window (~{} of {} tokens used)",
+            self.dropped_messages,
+            if self.dropped_messages == 1 { " was" } else { "s were" },
+            self.estimated_tokens,
+            self.budget
+        )
+    }
+}
+
+/// Drop the oldest conversation messages until the request fits `context_window`, keeping
+/// leading system messages and the latest message. A short system note tells the model that
+/// history was cut. Returns None when nothing had to be dropped.
+pub fn fit_to_context(request: &mut ChatCompletionRequest, context_window: u32) -> Result<Option<ContextPruning>, String> {
+    let reserved = request.max_tokens.unwrap_or(DEFAULT_RESERVED_OUTPUT).min(context_window / 2);
+    let budget = ((context_window - reserved) as f64 * BUDGET_RATIO) as usize;
+
+    if estimate_message_tokens(&request.messages) <= budget {
+        return Ok(None);
+    }
+
+    let leading_system = request.messages.iter().take_while(|m| m.role == "system").count();
+    let last = request.messages.len().saturating_sub(1);
+    let note = |dropped: usize| ChatMessage {
+        role: "system".to_string(),
+        content: format!("Note: the {} oldest messages of this conversation were omitted for length.", dropped),
+    };
+
+    // Earliest droppable message first
+    let mut dropped = 0;
+    let droppable = last.saturating_sub(leading_system);
+    while dropped < droppable {
+        dropped += 1;
+        let remaining = estimate_message_tokens(&request.messages[..leading_system])
+            + estimate_message_tokens(&request.messages[leading_system + dropped..])
+            + estimate_message_tokens(std::slice::from_ref(&note(dropped)));
+        if remaining <= budget {
+            break;
+        }
+    }
+
+    let mut messages: Vec<ChatMessage> = request.messages[..leading_system].to_vec();
+    if dropped > 0 {
+        messages.push(note(dropped));
+    }
+    messages.extend_from_slice(&request.messages[leading_system + dropped..]);
+
+    let estimated_tokens = estimate_message_tokens(&messages);
+    if estimated_tokens > budget {
+        return Err(format!(
+            "Request is too long for this model even after dropping history (~{} tokens, limit ~{}). Shorten the message or use a model with a larger context window.",
+            estimated_tokens, budget
+        ));
+    }
+
+    request.messages = messages;
+    Ok(Some(ContextPruning { dropped_messages: dropped, estimated_tokens, budget }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage { role: role.to_string(), content: content.to_string() }
+    }
+
+    fn request(messages: Vec<ChatMessage>, max_tokens: Option<u32>) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            messages,
+            model: "test".to_string(),
+            temperature: None,
+            max_tokens,
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stream: None,
+        }
+    }
+
+    #[test]
+    fn leaves_requests_that_fit_untouched() {
+        let mut req = request(vec![message("system", "Be brief."), message("user", "Hi")], None);
+        assert!(fit_to_context(&mut req, 8192).unwrap().is_none());
+        assert_eq!(req.messages.len(), 2);
+    }
+
+    #[test]
+    fn drops_oldest_history_but_keeps_system_and_latest_message() {
+        let filler = "word ".repeat(200); // ~250 tokens each
+        let mut req = request(vec![
+            message("system", "Be brief."),
+            message("user", &format!("first {}", filler)),
+            message("assistant", &format!("second {}", filler)),
+            message("user", &format!("third {}", filler)),
+            message("user", "latest question"),
+        ], Some(100));
+
+        let pruning = fit_to_context(&mut req, 600).unwrap().expect("history should be pruned");
+
+        assert_eq!(pruning.dropped_messages, 2);
+        let roles_and_starts: Vec<(&str, &str)> = req.messages.iter()
+            .map(|m| (m.role.as_str(), m.content.split_whitespace().next().unwrap_or("")))
+            .collect();
+        assert_eq!(roles_and_starts, vec![
+            ("system", "Be"),
+            ("system", "Note:"),
+            ("user", "third"),
+            ("user", "latest"),
+        ]);
+        assert!(pruning.estimated_tokens <= pruning.budget);
+    }
+
+    #[test]
+    fn rejects_a_latest_message_that_cannot_fit() {
+        let mut req = request(vec![
+            message("user", "earlier"),
+            message("user", &"word ".repeat(2000)),
+        ], None);
+
+        let error = fit_to_context(&mut req, 1024).unwrap_err();
+        assert!(error.contains("too long for this model"));
+        // The request is left as it was
+        assert_eq!(req.messages.len(), 2);
+    }
+}
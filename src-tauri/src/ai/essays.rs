@@ -0,0 +1,163 @@
+//! Essay feedback against a rubric. Long essays are cut into chunks at paragraph breaks and
+//! each chunk is assessed against every criterion (map); the assessments are then merged
+//! into one score per criterion for the whole essay (reduce). Short essays take one call.
+
+use serde::{Deserialize, Serialize};
+
+use super::reading::json_object;
+use super::types::ChatMessage;
+use crate::database::{CriterionFeedback, RubricCriterion};
+
+// Roughly 2,000 words, enough for most essays in one call
+pub const MAX_ESSAY_CHUNK_CHARS: usize = 12_000;
+// About 20,000 words; longer texts are theses, not essays
+pub const MAX_ESSAY_CHUNKS: usize = 10;
+
+/// One criterion as scored by the model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CriterionAssessment {
+    pub criterion: String,
+    pub score: f64,
+    #[serde(default)]
+    pub comment: String,
+}
+
+/// The model's assessment of an essay, or of one chunk of it
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EssayAssessment {
+    #[serde(default)]
+    pub criteria: Vec<CriterionAssessment>,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub strengths: Vec<String>,
+    #[serde(default)]
+    pub improvements: Vec<String>,
+}
+
+/// Split an essay into chunks of at most `max_chars` at paragraph breaks; a single longer
+/// paragraph becomes a chunk of its own
+pub fn split_essay(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|paragraph| !paragraph.is_empty()) {
+        if !current.is_empty() && current.len() + paragraph.len() + 2 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn describe_rubric(criteria: &[RubricCriterion]) -> String {
+    criteria.iter()
+        .map(|criterion| {
+            let mut line = format!("- {} (0-{} points): {}", criterion.name, criterion.max_points, criterion.description);
+            for level in &criterion.levels {
+                line.push_str(&format!("\n  - {}", level));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+const ASSESSMENT_FORMAT: &str = "{\"criteria\": [{\"criterion\": \"<name as in the rubric>\", \"score\": 0, \"comment\": \"...\"}], \
+    \"summary\": \"...\", \"strengths\": [\"...\"], \"improvements\": [\"...\"]}";
+
+fn system_message() -> ChatMessage {
+    ChatMessage {
+        role: "system".to_string(),
+        content: "You are a fair, specific writing tutor who grades essays against a rubric. Quote or point to passages in comments. \
+                  Respond with JSON only, no prose and no code fences.".to_string(),
+    }
+}
+
+/// Assess `chunk` (part `index` of `total`) against the rubric
+pub fn build_essay_assessment_messages(
+    title: &str,
+    criteria: &[RubricCriterion],
+    chunk: &str,
+    index: usize,
+    total: usize,
+) -> Vec<ChatMessage> {
+    let part = if total > 1 {
+        format!(
+            "Below is part {} of {} of the essay \"{}\". Score what this part shows for each criterion; criteria that depend on the whole \
+             essay (such as overall structure) can be judged from what is visible.",
+            index + 1, total, title
+        )
+    } else {
+        format!("Below is the essay \"{}\".", title)
+    };
+
+    vec![
+        system_message(),
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "{} Grade it against this rubric:\n{}\n\n\
+                 Give every criterion a score and a comment of one to three sentences, then a short summary, up to three strengths \
+                 and up to three concrete improvements.\n\
+                 Respond with a JSON object of the form {}.\n\n{}",
+                part, describe_rubric(criteria), ASSESSMENT_FORMAT, chunk
+            ),
+        },
+    ]
+}
+
+/// Merge the assessments of consecutive parts into one for the whole essay
+pub fn build_essay_merge_messages(title: &str, criteria: &[RubricCriterion], assessments: &[EssayAssessment]) -> Vec<ChatMessage> {
+    let assessments = serde_json::to_string(assessments).unwrap_or_else(|_| "[]".to_string());
+
+    vec![
+        system_message(),
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Below are assessments of consecutive parts of the essay \"{}\", as a JSON array, made with this rubric:\n{}\n\n\
+                 Combine them into one assessment of the whole essay: one score per criterion that reflects the essay as a whole \
+                 (not a sum of the parts), merged comments, a summary, and the three most important strengths and improvements.\n\
+                 Respond with a JSON object of the form {}.\n\n{}",
+                title, describe_rubric(criteria), ASSESSMENT_FORMAT, assessments
+            ),
+        },
+    ]
+}
+
+pub fn parse_essay_assessment_response(text: &str) -> Result<EssayAssessment, String> {
+    let mut assessment: EssayAssessment = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse essay feedback JSON: {}", e))?;
+    if assessment.criteria.is_empty() {
+        return Err("The model returned no rubric scores".to_string());
+    }
+    assessment.strengths.retain(|item| !item.trim().is_empty());
+    assessment.improvements.retain(|item| !item.trim().is_empty());
+    Ok(assessment)
+}
+
+/// Scores in rubric order, matched by criterion name and clamped to each criterion's range.
+/// Criteria the model left out score 0.
+pub fn score_criteria(criteria: &[RubricCriterion], assessment: &EssayAssessment) -> Vec<CriterionFeedback> {
+    criteria.iter()
+        .map(|criterion| {
+            let assessed = assessment.criteria.iter()
+                .find(|assessed| assessed.criterion.trim().eq_ignore_ascii_case(criterion.name.trim()));
+            CriterionFeedback {
+                criterion: criterion.name.clone(),
+                score: assessed.map_or(0.0, |assessed| assessed.score.clamp(0.0, criterion.max_points)),
+                max_points: criterion.max_points,
+                comment: assessed
+                    .map(|assessed| assessed.comment.trim().to_string())
+                    .unwrap_or_else(|| "Not assessed".to_string()),
+            }
+        })
+        .collect()
+}
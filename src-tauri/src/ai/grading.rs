@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use super::types::ChatMessage;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelGrade {
+    pub quality: i32, // 0-5 on the SM-2 scale
+    pub feedback: Option<String>,
+}
+
+/// Map embedding similarity between the typed answer and the card back onto the SM-2 0-5 scale
+pub fn similarity_to_quality(similarity: f32) -> i32 {
+    match similarity {
+        s if s >= 0.92 => 5,
+        s if s >= 0.85 => 4,
+        s if s >= 0.75 => 3,
+        s if s >= 0.60 => 2,
+        s if s >= 0.45 => 1,
+        _ => 0,
+    }
+}
+
+/// Response label matching the quality, as stored on flashcard reviews
+pub fn quality_to_response(quality: i32) -> &'static str {
+    match quality {
+        4..=5 => "correct",
+        2..=3 => "partial",
+        _ => "incorrect",
+    }
+}
+
+pub fn build_grading_messages(front: &str, back: &str, user_answer: &str, similarity: Option<f32>) -> Vec<ChatMessage> {
+    let similarity_hint = similarity
+        .map(|s| format!("\nSemantic similarity to the expected answer: {:.2}", s))
+        .unwrap_or_default();
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You grade flashcard answers typed by a student. Judge meaning, not wording; ignore spelling mistakes that don't change meaning. Respond with JSON only: {\"quality\": 0-5, \"feedback\": \"one short sentence\"}. 5 = perfect, 4 = correct with minor gaps, 3 = mostly correct, 2 = partially correct, 1 = mostly wrong but related, 0 = wrong or blank.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Question: {}\nExpected answer: {}\nStudent answer: {}{}",
+                front, back, user_answer, similarity_hint
+            ),
+        },
+    ]
+}
+
+pub fn parse_grading_response(text: &str) -> Result<ModelGrade, String> {
+    let start = text.find('{').ok_or("Model response did not contain a JSON object")?;
+    let end = text.rfind('}').ok_or("Model response did not contain a JSON object")?;
+    if end < start {
+        return Err("Model response did not contain a JSON object".to_string());
+    }
+
+    let mut grade: ModelGrade = serde_json::from_str(&text[start..=end])
+        .map_err(|e| format!("Failed to parse grading JSON: {}", e))?;
+    grade.quality = grade.quality.clamp(0, 5);
+
+    Ok(grade)
+}
@@ -0,0 +1,61 @@
+use super::types::ChatMessage;
+
+/// Number of most recent messages sent verbatim alongside the rolling summary
+pub const DEFAULT_KEEP_LAST_MESSAGES: usize = 12;
+
+/// Fold older messages into the summary once this many have fallen out of the recent window
+pub const SUMMARIZE_AFTER_MESSAGES: usize = 8;
+
+/// Build the prompt for a continued conversation: an optional system prompt, the rolling
+/// summary of everything older, and the last `keep_last` messages verbatim
+pub fn build_context_messages(
+    system_prompt: Option<&str>,
+    summary: Option<&str>,
+    history: &[ChatMessage],
+    keep_last: usize,
+) -> Vec<ChatMessage> {
+    let mut messages = Vec::new();
+
+    if let Some(prompt) = system_prompt.filter(|p| !p.trim().is_empty()) {
+        messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: prompt.to_string(),
+        });
+    }
+
+    if let Some(summary) = summary.filter(|s| !s.trim().is_empty()) {
+        messages.push(ChatMessage {
+            role: "system".to_string(),
+            content: format!("Summary of the earlier conversation:\n{}", summary),
+        });
+    }
+
+    let start = history.len().saturating_sub(keep_last);
+    messages.extend(history[start..].iter().filter(|m| m.role != "system").cloned());
+
+    messages
+}
+
+/// Build the request that folds `messages` into `previous_summary`
+pub fn build_summary_messages(previous_summary: Option<&str>, messages: &[ChatMessage]) -> Vec<ChatMessage> {
+    let transcript = messages
+        .iter()
+        .map(|m| format!("{}: {}", m.role, m.content))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You maintain a running summary of a tutoring conversation. Keep the facts the student learned, open questions, their misconceptions, and any goals they stated. Write at most 300 words of plain prose.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Current summary:\n{}\n\nNew messages to fold in:\n{}\n\nReturn the updated summary only.",
+                previous_summary.unwrap_or("(none yet)"),
+                transcript
+            ),
+        },
+    ]
+}
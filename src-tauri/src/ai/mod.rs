@@ -1,5 +1,22 @@
 pub mod types;
 pub mod providers;
+pub mod practice;
+pub mod memory;
+pub mod profiles;
+pub mod grading;
+pub mod models_dev;
+pub mod streams;
+pub mod context;
+pub mod guardrails;
+pub mod tagging;
+pub mod reading;
+pub mod transcripts;
+pub mod question_bank;
+pub mod essays;
+pub mod vision;
+pub mod citations;
+pub mod registry;
 
 pub use types::*;
-pub use providers::*; 
\ No newline at end of file
+pub use providers::*;
+pub use registry::{chat_provider, register_chat_provider, ChatProvider}; 
\ No newline at end of file
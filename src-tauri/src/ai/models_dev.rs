@@ -0,0 +1,101 @@
+//! Model limits from the models.dev catalog, used instead of guessing context windows for
+//! models that provider APIs list without any metadata.
+
+use super::types::AIModel;
+use crate::database::ModelCapability;
+use crate::network::{shared_client, HttpPurpose};
+use chrono::Utc;
+use std::time::Duration;
+
+pub const MODELS_DEV_URL: &str = "https://models.dev/api.json";
+
+// The catalog changes slowly; refresh it when older than this
+pub const CATALOG_MAX_AGE_HOURS: i64 = 7 * 24;
+
+pub async fn fetch_catalog() -> Result<serde_json::Value, String> {
+    crate::network::ensure_online("Fetching the models.dev catalog")?;
+    let client = shared_client(HttpPurpose::Ai)?;
+
+    let response = client
+        .get(MODELS_DEV_URL)
+        .timeout(Duration::from_secs(30))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch models.dev data: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API request failed with status: {}", response.status()));
+    }
+
+    response.json().await
+        .map_err(|e| format!("Failed to parse JSON response: {}", e))
+}
+
+/// Flatten `{ provider: { models: { id: {...} } } }` into one capability per provider/model
+pub fn parse_catalog(catalog: &serde_json::Value) -> Vec<ModelCapability> {
+    let now = Utc::now();
+    let Some(providers) = catalog.as_object() else {
+        return Vec::new();
+    };
+
+    providers
+        .iter()
+        .flat_map(|(provider_id, provider)| {
+            provider["models"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(move |(model_id, model)| ModelCapability {
+                    provider_id: provider_id.clone(),
+                    model_id: model["id"].as_str().unwrap_or(model_id).to_string(),
+                    name: model["name"].as_str().unwrap_or(model_id).to_string(),
+                    context_window: model["limit"]["context"].as_i64().filter(|&n| n > 0),
+                    max_output_tokens: model["limit"]["output"].as_i64().filter(|&n| n > 0),
+                    supports_tools: model["tool_call"].as_bool().unwrap_or(false),
+                    supports_reasoning: model["reasoning"].as_bool().unwrap_or(false),
+                    supports_attachments: model["attachment"].as_bool().unwrap_or(false),
+                    input_modalities: model["modalities"]["input"]
+                        .as_array()
+                        .map(|inputs| inputs.iter().filter_map(|m| m.as_str().map(String::from)).collect())
+                        .unwrap_or_else(|| vec!["text".to_string()]),
+                    cost_input: model["cost"]["input"].as_f64(),
+                    cost_output: model["cost"]["output"].as_f64(),
+                    updated_at: now,
+                })
+        })
+        .collect()
+}
+
+/// Candidate catalog ids for a model name as a provider reports it, most specific first.
+/// Ollama tags ("llama3.1:8b") and org prefixes ("openai/gpt-4o") are stripped.
+pub fn lookup_ids(model_id: &str) -> Vec<String> {
+    let mut ids = vec![model_id.to_string()];
+    let untagged = model_id.split(':').next().unwrap_or(model_id);
+    let unprefixed = untagged.rsplit('/').next().unwrap_or(untagged);
+    for id in [untagged, unprefixed] {
+        if !ids.iter().any(|existing| existing == id) {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+/// Overwrite the defaulted limits and features of `model` with catalog data
+pub fn apply_capability(model: &mut AIModel, capability: &ModelCapability) {
+    if let Some(context_window) = capability.context_window {
+        model.context_window = context_window.min(u32::MAX as i64) as u32;
+    }
+    if let Some(max_output_tokens) = capability.max_output_tokens {
+        model.max_tokens = max_output_tokens.min(u32::MAX as i64) as u32;
+    }
+    model.supports_tools = capability.supports_tools;
+
+    let mut capabilities = vec!["text".to_string()];
+    if capability.input_modalities.iter().any(|m| m == "image") {
+        capabilities.push("vision".to_string());
+    }
+    if capability.supports_reasoning {
+        capabilities.push("reasoning".to_string());
+    }
+    model.capabilities = capabilities;
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use super::types::ChatMessage;
+
+// Keep prompts bounded; the start of a document is usually enough for a handful of questions
+const MAX_PRACTICE_SOURCE_CHARS: usize = 12_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GeneratedPractice {
+    #[serde(default)]
+    pub questions: Vec<GeneratedQuestion>,
+    #[serde(default)]
+    pub flashcards: Vec<GeneratedFlashcard>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedQuestion {
+    pub question: String,
+    pub answer: String,
+    pub explanation: Option<String>,
+    #[serde(default)]
+    pub concepts: Vec<String>, // Short names of the ideas the question tests
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratedFlashcard {
+    pub front: String,
+    pub back: String,
+    #[serde(default)]
+    pub concepts: Vec<String>,
+}
+
+/// Build the chat messages asking the model for practice questions and flashcards as JSON,
+/// each tagged with the concepts it tests. `focus_concepts` are the student's weak concepts,
+/// which the questions should favour when the document covers them.
+pub fn build_practice_messages(title: &str, content: &str, question_count: i32, flashcard_count: i32, focus_concepts: &[String]) -> Vec<ChatMessage> {
+    let source: String = content.chars().take(MAX_PRACTICE_SOURCE_CHARS).collect();
+    let focus = if focus_concepts.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "The student struggles with these concepts; where the document covers them, spend more questions and flashcards on them \
+             and reuse their names exactly: {}.\n",
+            focus_concepts.join("; ")
+        )
+    };
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You are a tutor who writes practice material for students. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Write {} practice questions and {} flashcards that test understanding of the document below. \
+                 List the one to three concepts each item tests, as short names (e.g. \"photosynthesis\").\n{}\
+                 Respond with a JSON object of the form \
+                 {{\"questions\": [{{\"question\": \"...\", \"answer\": \"...\", \"explanation\": \"...\", \"concepts\": [\"...\"]}}], \
+                 \"flashcards\": [{{\"front\": \"...\", \"back\": \"...\", \"concepts\": [\"...\"]}}]}}.\n\n\
+                 Title: {}\n\n{}",
+                question_count, flashcard_count, focus, title, source
+            ),
+        },
+    ]
+}
+
+/// Parse the model output, tolerating code fences or text around the JSON object
+pub fn parse_practice_response(text: &str) -> Result<GeneratedPractice, String> {
+    let start = text.find('{').ok_or("Model response did not contain a JSON object")?;
+    let end = text.rfind('}').ok_or("Model response did not contain a JSON object")?;
+    if end < start {
+        return Err("Model response did not contain a JSON object".to_string());
+    }
+
+    let mut practice: GeneratedPractice = serde_json::from_str(&text[start..=end])
+        .map_err(|e| format!("Failed to parse practice JSON: {}", e))?;
+
+    practice.questions.retain(|q| !q.question.trim().is_empty() && !q.answer.trim().is_empty());
+    practice.flashcards.retain(|c| !c.front.trim().is_empty() && !c.back.trim().is_empty());
+
+    Ok(practice)
+}
@@ -0,0 +1,24 @@
+use super::types::{ChatCompletionRequest, ChatMessage};
+use crate::database::AIProfile;
+
+/// Apply a persona profile to an outgoing request: its system prompt goes first, and its
+/// default model and temperature fill in whatever the caller left unset
+pub fn apply_ai_profile(profile: &AIProfile, model: &mut String, request: &mut ChatCompletionRequest) {
+    if !profile.system_prompt.trim().is_empty() {
+        request.messages.insert(0, ChatMessage {
+            role: "system".to_string(),
+            content: profile.system_prompt.clone(),
+        });
+    }
+
+    if model.trim().is_empty() {
+        if let Some(default_model) = &profile.default_model {
+            *model = default_model.clone();
+            request.model = default_model.clone();
+        }
+    }
+
+    if request.temperature.is_none() {
+        request.temperature = profile.temperature;
+    }
+}
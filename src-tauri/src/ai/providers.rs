@@ -1,22 +1,60 @@
 use super::types::*;
-use tauri::{AppHandle, Emitter};
 use futures_util::StreamExt;
 use uuid::Uuid;
 use std::time::{Duration, Instant};
+use crate::network::{shared_client, HttpPurpose};
 use serde_json::json;
 
+/// `{base_url}/{path}`, with Azure's api-version query parameter when the provider has one
+pub(super) fn provider_url(provider: &AIProvider, path: &str) -> String {
+    let url = format!("{}/{}", provider.base_url.trim_end_matches('/'), path);
+    match provider.api_version.as_deref().filter(|v| !v.is_empty()) {
+        Some(version) => format!("{}?api-version={}", url, version),
+        None => url,
+    }
+}
+
+pub(super) trait ProviderHeaders {
+    /// OpenAI-style auth plus the provider's organization and extra headers
+    fn openai_auth(self, provider: &AIProvider, api_key: &str) -> Self;
+    fn extra_headers(self, provider: &AIProvider) -> Self;
+}
+
+impl ProviderHeaders for reqwest::RequestBuilder {
+    fn openai_auth(self, provider: &AIProvider, api_key: &str) -> Self {
+        let builder = if provider.api_version.as_deref().is_some_and(|v| !v.is_empty()) {
+            self.header("api-key", api_key)
+        } else {
+            self.header("Authorization", format!("Bearer {}", api_key))
+        };
+        let builder = match provider.organization.as_deref().filter(|o| !o.is_empty()) {
+            Some(organization) => builder.header("OpenAI-Organization", organization),
+            None => builder,
+        };
+        builder.extra_headers(provider)
+    }
+
+    fn extra_headers(self, provider: &AIProvider) -> Self {
+        provider.extra_headers.iter().fold(self, |builder, (name, value)| builder.header(name, value))
+    }
+}
+
+// Offline mode only lets requests through to providers running on this machine (e.g. Ollama)
+pub(crate) fn ensure_provider_reachable(provider: &AIProvider) -> Result<(), String> {
+    crate::network::ensure_reachable(&provider.base_url, &format!("AI provider '{}'", provider.id))
+        .map_err(String::from)
+}
+
 // Provider-specific implementations
 pub async fn test_openai_connection(provider: &AIProvider, api_key: Option<String>) -> Result<bool, String> {
+    ensure_provider_reachable(provider)?;
     let api_key = api_key.ok_or("API key required for OpenAI provider")?;
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = shared_client(HttpPurpose::Ai)?;
     
     let response = client
-        .get(&format!("{}/models", provider.base_url))
-        .header("Authorization", format!("Bearer {}", api_key))
+        .get(provider_url(provider, "models"))
+        .timeout(Duration::from_secs(30))
+        .openai_auth(provider, &api_key)
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
@@ -25,17 +63,16 @@ pub async fn test_openai_connection(provider: &AIProvider, api_key: Option<Strin
 }
 
 pub async fn test_anthropic_connection(provider: &AIProvider, api_key: Option<String>) -> Result<bool, String> {
+    ensure_provider_reachable(provider)?;
     let api_key = api_key.ok_or("API key required for Anthropic provider")?;
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = shared_client(HttpPurpose::Ai)?;
     
     let response = client
         .get(&format!("{}/models", provider.base_url))
+        .timeout(Duration::from_secs(30))
         .header("x-api-key", api_key)
         .header("anthropic-version", "2023-06-01")
+        .extra_headers(provider)
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
@@ -44,14 +81,13 @@ pub async fn test_anthropic_connection(provider: &AIProvider, api_key: Option<St
 }
 
 pub async fn test_ollama_connection(provider: &AIProvider) -> Result<bool, String> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    ensure_provider_reachable(provider)?;
+    let client = shared_client(HttpPurpose::Ai)?;
     
     let response = client
         .get(&format!("{}/api/tags", provider.base_url))
+        .timeout(Duration::from_secs(30))
+        .extra_headers(provider)
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
@@ -65,12 +101,9 @@ pub async fn openai_chat_completion(
     request: &ChatCompletionRequest,
     api_key: Option<String>,
 ) -> Result<ChatCompletionResponse, String> {
+    ensure_provider_reachable(provider)?;
     let api_key = api_key.ok_or("API key required for OpenAI provider")?;
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .connect_timeout(Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = shared_client(HttpPurpose::Ai)?;
 
     println!(
         "[AI] OpenAI chat start model={} messages={} temp={:?} max_tokens={:?}",
@@ -96,8 +129,9 @@ pub async fn openai_chat_completion(
     let response = {
         let initial_body = build_body("max_tokens");
         let resp = client
-            .post(&format!("{}/chat/completions", provider.base_url))
-            .header("Authorization", format!("Bearer {}", api_key))
+            .post(provider_url(provider, "chat/completions"))
+            .timeout(Duration::from_secs(60))
+            .openai_auth(provider, &api_key)
             .header("Content-Type", "application/json")
             .json(&initial_body)
             .send()
@@ -113,8 +147,9 @@ pub async fn openai_chat_completion(
                 println!("[AI] OpenAI retrying with max_completion_tokens due to unsupported max_tokens");
                 let body_alt = build_body("max_completion_tokens");
                 client
-                    .post(&format!("{}/chat/completions", provider.base_url))
-                    .header("Authorization", format!("Bearer {}", api_key))
+                    .post(provider_url(provider, "chat/completions"))
+                    .timeout(Duration::from_secs(60))
+                    .openai_auth(provider, &api_key)
                     .header("Content-Type", "application/json")
                     .json(&body_alt)
                     .send()
@@ -153,6 +188,7 @@ pub async fn openai_chat_completion(
             completion_tokens: openai_response["usage"]["completion_tokens"].as_u64().unwrap_or(0) as u32,
             total_tokens: openai_response["usage"]["total_tokens"].as_u64().unwrap_or(0) as u32,
         },
+        warning: None,
     };
     println!(
         "[AI] OpenAI chat done model={} elapsed={}ms usage={{prompt:{}, completion:{}, total:{}}}",
@@ -170,20 +206,17 @@ pub async fn openai_chat_completion_stream(
     model: &str,
     request: &ChatCompletionRequest,
     api_key: Option<String>,
-    event_name: &str,
-    app: &AppHandle,
+    on_chunk: &ChunkSink,
 ) -> Result<(), String> {
+    ensure_provider_reachable(provider)?;
     // Route GPT-5 models to the Responses API streaming handler
     if model.contains("gpt-5") {
-        return openai_responses_stream(provider, model, request, api_key, event_name, app).await;
+        return openai_responses_stream(provider, model, request, api_key, on_chunk).await;
     }
 
     let api_key = api_key.ok_or("API key required for OpenAI provider")?;
-    // For streaming, avoid a global timeout; keep a connect timeout only
-    let client = reqwest::Client::builder()
-        .connect_timeout(Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    // Streams get no overall timeout; the shared client only limits connecting
+    let client = shared_client(HttpPurpose::Ai)?;
 
     println!(
         "[AI] OpenAI stream start model={} messages={} temp={:?} max_tokens={:?}",
@@ -209,8 +242,8 @@ pub async fn openai_chat_completion_stream(
     let response = {
         let initial_body = build_body("max_tokens");
         let resp = client
-            .post(&format!("{}/chat/completions", provider.base_url))
-            .header("Authorization", format!("Bearer {}", api_key))
+            .post(provider_url(provider, "chat/completions"))
+            .openai_auth(provider, &api_key)
             .header("Content-Type", "application/json")
             .json(&initial_body)
             .send()
@@ -226,8 +259,8 @@ pub async fn openai_chat_completion_stream(
                 println!("[AI] OpenAI stream retrying with max_completion_tokens due to unsupported max_tokens");
                 let body_alt = build_body("max_completion_tokens");
                 client
-                    .post(&format!("{}/chat/completions", provider.base_url))
-                    .header("Authorization", format!("Bearer {}", api_key))
+                    .post(provider_url(provider, "chat/completions"))
+                    .openai_auth(provider, &api_key)
                     .header("Content-Type", "application/json")
                     .json(&body_alt)
                     .send()
@@ -273,7 +306,7 @@ pub async fn openai_chat_completion_stream(
                         finish_reason: Some("stop".to_string()),
                     }],
                 };
-                let _ = app.emit(event_name, completion_chunk);
+                on_chunk(completion_chunk);
                 break;
             }
 
@@ -298,7 +331,7 @@ pub async fn openai_chat_completion_stream(
                                 }],
                             };
 
-                            let _ = app.emit(event_name, chunk);
+                            on_chunk(chunk);
                         }
                     }
                 }
@@ -320,14 +353,10 @@ async fn openai_responses_stream(
     model: &str,
     request: &ChatCompletionRequest,
     api_key: Option<String>,
-    event_name: &str,
-    app: &AppHandle,
+    on_chunk: &ChunkSink,
 ) -> Result<(), String> {
     let api_key = api_key.ok_or("API key required for OpenAI provider")?;
-    let client = reqwest::Client::builder()
-        .connect_timeout(Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = shared_client(HttpPurpose::Ai)?;
 
     println!(
         "[AI] OpenAI Responses stream start model={} messages={} temp={:?} max_tokens={:?}",
@@ -373,8 +402,8 @@ async fn openai_responses_stream(
     if let Some(max_tokens) = request.max_tokens { body["max_output_tokens"] = max_tokens.into(); }
 
     let response = client
-        .post(&format!("{}/responses", provider.base_url))
-        .header("Authorization", format!("Bearer {}", api_key))
+        .post(provider_url(provider, "responses"))
+        .openai_auth(provider, &api_key)
         .header("Content-Type", "application/json")
         .json(&body)
         .send()
@@ -420,7 +449,7 @@ async fn openai_responses_stream(
                                     finish_reason: None,
                                 }],
                             };
-                            let _ = app.emit(event_name, chunk);
+                            on_chunk(chunk);
                         }
                     }
                     "response.completed" | "response.output_text.done" => {
@@ -431,7 +460,7 @@ async fn openai_responses_stream(
                                 finish_reason: Some("stop".to_string()),
                             }],
                         };
-                        let _ = app.emit(event_name, chunk);
+                        on_chunk(chunk);
                     }
                     _ => {
                         // Ignore other event types for now
@@ -451,12 +480,9 @@ pub async fn anthropic_chat_completion(
     request: &ChatCompletionRequest,
     api_key: Option<String>,
 ) -> Result<ChatCompletionResponse, String> {
+    ensure_provider_reachable(provider)?;
     let api_key = api_key.ok_or("API key required for Anthropic provider")?;
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .connect_timeout(Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    let client = shared_client(HttpPurpose::Ai)?;
 
     println!(
         "[AI] Anthropic chat start model={} messages={} temp={:?} max_tokens={:?}",
@@ -490,8 +516,10 @@ pub async fn anthropic_chat_completion(
 
     let response = client
         .post(&format!("{}/messages", provider.base_url))
+        .timeout(Duration::from_secs(60))
         .header("x-api-key", api_key)
         .header("anthropic-version", "2023-06-01")
+        .extra_headers(provider)
         .header("Content-Type", "application/json")
         .json(&body)
         .send()
@@ -526,6 +554,7 @@ pub async fn anthropic_chat_completion(
             total_tokens: (anthropic_response["usage"]["input_tokens"].as_u64().unwrap_or(0)
                 + anthropic_response["usage"]["output_tokens"].as_u64().unwrap_or(0)) as u32,
         },
+        warning: None,
     };
     println!(
         "[AI] Anthropic chat done model={} elapsed={}ms usage={{prompt:{}, completion:{}, total:{}}}",
@@ -543,11 +572,8 @@ pub async fn ollama_chat_completion(
     model: &str,
     request: &ChatCompletionRequest,
 ) -> Result<ChatCompletionResponse, String> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .connect_timeout(Duration::from_secs(10))
-        .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+    ensure_provider_reachable(provider)?;
+    let client = shared_client(HttpPurpose::Ai)?;
     
     let body = serde_json::json!({
         "model": model,
@@ -557,6 +583,8 @@ pub async fn ollama_chat_completion(
 
     let response = client
         .post(&format!("{}/api/chat", provider.base_url))
+        .timeout(Duration::from_secs(30))
+        .extra_headers(provider)
         .header("Content-Type", "application/json")
         .json(&body)
         .send()
@@ -587,16 +615,84 @@ pub async fn ollama_chat_completion(
             completion_tokens: 0,
             total_tokens: 0,
         },
+        warning: None,
     })
 }
 
+/// Non-streaming chat completion through the registered backend for `provider.r#type`
+pub async fn chat_completion_for_provider(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    api_key: Option<String>,
+) -> Result<ChatCompletionResponse, String> {
+    super::registry::chat_provider(&provider.r#type)?
+        .complete(provider, model, request, api_key)
+        .await
+}
+
+/// Text-to-speech through the backend for `provider.r#type`; only OpenAI-compatible APIs have one
+pub async fn text_to_speech_for_provider(
+    provider: &AIProvider,
+    model: &str,
+    voice: &str,
+    text: &str,
+    api_key: Option<String>,
+) -> Result<Vec<u8>, String> {
+    match provider.r#type.as_str() {
+        "openai" | "custom" => openai_text_to_speech(provider, model, voice, text, api_key).await,
+        other => Err(format!("Text-to-speech is not supported for {} providers", other)),
+    }
+}
+
+/// Synthesize speech through an OpenAI-compatible `/audio/speech` endpoint, returning MP3 bytes
+pub async fn openai_text_to_speech(
+    provider: &AIProvider,
+    model: &str,
+    voice: &str,
+    text: &str,
+    api_key: Option<String>,
+) -> Result<Vec<u8>, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for text-to-speech")?;
+    let client = shared_client(HttpPurpose::Ai)?;
+
+    let response = client
+        .post(provider_url(provider, "audio/speech"))
+        .timeout(Duration::from_secs(60))
+        .openai_auth(provider, &api_key)
+        .header("Content-Type", "application/json")
+        .json(&json!({
+            "model": model,
+            "voice": voice,
+            "input": text,
+            "response_format": "mp3",
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Text-to-speech failed ({}): {}", status, body));
+    }
+
+    let bytes = response.bytes().await
+        .map_err(|e| format!("Failed to read speech audio: {}", e))?;
+
+    Ok(bytes.to_vec())
+}
+
 pub async fn get_openai_models(provider: &AIProvider, api_key: Option<String>) -> Result<Vec<AIModel>, String> {
+    ensure_provider_reachable(provider)?;
     let api_key = api_key.ok_or("API key required for OpenAI provider")?;
-    let client = reqwest::Client::new();
+    let client = shared_client(HttpPurpose::Ai)?;
     
     let response = client
-        .get(&format!("{}/models", provider.base_url))
-        .header("Authorization", format!("Bearer {}", api_key))
+        .get(provider_url(provider, "models"))
+        .timeout(Duration::from_secs(30))
+        .openai_auth(provider, &api_key)
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
@@ -620,7 +716,7 @@ pub async fn get_openai_models(provider: &AIProvider, api_key: Option<String>) -
                 name: id.clone(),
                 id: id.clone(),
                 provider_id: provider.id.clone(),
-                context_window: 4096, // Default, should be updated based on model
+                context_window: 4096, // Fallback; ai_get_models fills in models.dev limits
                 max_tokens: 2048,
                 supports_streaming: true,
                 supports_tools: true,
@@ -649,10 +745,13 @@ pub async fn get_anthropic_models(provider: &AIProvider, _api_key: Option<String
 }
 
 pub async fn get_ollama_models(provider: &AIProvider) -> Result<Vec<AIModel>, String> {
-    let client = reqwest::Client::new();
+    ensure_provider_reachable(provider)?;
+    let client = shared_client(HttpPurpose::Ai)?;
     
     let response = client
         .get(&format!("{}/api/tags", provider.base_url))
+        .timeout(Duration::from_secs(30))
+        .extra_headers(provider)
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
@@ -676,7 +775,7 @@ pub async fn get_ollama_models(provider: &AIProvider) -> Result<Vec<AIModel>, St
                 id: name.clone(),
                 name: name.clone(),
                 provider_id: provider.id.clone(),
-                context_window: 4096, // Default
+                context_window: 4096, // Fallback; ai_get_models fills in models.dev limits
                 max_tokens: 2048,
                 supports_streaming: true,
                 supports_tools: false,
@@ -686,4 +785,262 @@ pub async fn get_ollama_models(provider: &AIProvider) -> Result<Vec<AIModel>, St
         .collect();
 
     Ok(models)
-} 
\ No newline at end of file
+}
+
+pub async fn test_gemini_connection(provider: &AIProvider, api_key: Option<String>) -> Result<bool, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for Gemini provider")?;
+    let client = shared_client(HttpPurpose::Ai)?;
+
+    let response = client
+        .get(provider_url(provider, "models"))
+        .timeout(Duration::from_secs(30))
+        .header("x-goog-api-key", api_key)
+        .extra_headers(provider)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    Ok(response.status().is_success())
+}
+
+// Gemini takes the system prompt separately and calls the assistant role "model"
+pub(super) fn gemini_body(request: &ChatCompletionRequest) -> serde_json::Value {
+    let system: Vec<&str> = request.messages.iter()
+        .filter(|m| m.role == "system")
+        .map(|m| m.content.as_str())
+        .collect();
+    let contents: Vec<serde_json::Value> = request.messages.iter()
+        .filter(|m| m.role != "system")
+        .map(|m| json!({
+            "role": if m.role == "assistant" { "model" } else { "user" },
+            "parts": [{ "text": m.content }],
+        }))
+        .collect();
+
+    let mut generation_config = json!({});
+    if let Some(temp) = request.temperature { generation_config["temperature"] = temp.into(); }
+    if let Some(max_tokens) = request.max_tokens { generation_config["maxOutputTokens"] = max_tokens.into(); }
+    if let Some(top_p) = request.top_p { generation_config["topP"] = top_p.into(); }
+
+    let mut body = json!({
+        "contents": contents,
+        "generationConfig": generation_config,
+    });
+    if !system.is_empty() {
+        body["systemInstruction"] = json!({ "parts": [{ "text": system.join("\n\n") }] });
+    }
+    body
+}
+
+// Text of the first candidate; Gemini may split it over several parts
+pub(super) fn gemini_candidate_text(response: &serde_json::Value) -> String {
+    response["candidates"][0]["content"]["parts"]
+        .as_array()
+        .map(|parts| parts.iter().filter_map(|part| part["text"].as_str()).collect())
+        .unwrap_or_default()
+}
+
+pub(super) fn gemini_model_path(model: &str) -> String {
+    format!("models/{}", model.trim_start_matches("models/"))
+}
+
+pub async fn gemini_chat_completion(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    api_key: Option<String>,
+) -> Result<ChatCompletionResponse, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for Gemini provider")?;
+    let client = shared_client(HttpPurpose::Ai)?;
+
+    println!(
+        "[AI] Gemini chat start model={} messages={} temp={:?} max_tokens={:?}",
+        model,
+        request.messages.len(),
+        request.temperature,
+        request.max_tokens
+    );
+    let started_at = Instant::now();
+
+    let response = client
+        .post(provider_url(provider, &format!("{}:generateContent", gemini_model_path(model))))
+        .timeout(Duration::from_secs(60))
+        .header("x-goog-api-key", api_key)
+        .extra_headers(provider)
+        .header("Content-Type", "application/json")
+        .json(&gemini_body(request))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("API error: {}", error_text));
+    }
+
+    let gemini_response: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    let usage = &gemini_response["usageMetadata"];
+    let result = ChatCompletionResponse {
+        id: gemini_response["responseId"].as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("gemini-{}", Uuid::new_v4())),
+        choices: vec![ChatChoice {
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content: gemini_candidate_text(&gemini_response),
+            },
+            finish_reason: gemini_response["candidates"][0]["finishReason"].as_str().unwrap_or("STOP").to_lowercase(),
+        }],
+        usage: ChatUsage {
+            prompt_tokens: usage["promptTokenCount"].as_u64().unwrap_or(0) as u32,
+            completion_tokens: usage["candidatesTokenCount"].as_u64().unwrap_or(0) as u32,
+            total_tokens: usage["totalTokenCount"].as_u64().unwrap_or(0) as u32,
+        },
+        warning: None,
+    };
+    println!(
+        "[AI] Gemini chat done model={} elapsed={}ms usage={{prompt:{}, completion:{}, total:{}}}",
+        model,
+        started_at.elapsed().as_millis(),
+        result.usage.prompt_tokens,
+        result.usage.completion_tokens,
+        result.usage.total_tokens
+    );
+    Ok(result)
+}
+
+pub async fn gemini_chat_completion_stream(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    api_key: Option<String>,
+    on_chunk: &ChunkSink,
+) -> Result<(), String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for Gemini provider")?;
+    let client = shared_client(HttpPurpose::Ai)?;
+
+    println!("[AI] Gemini stream start model={} messages={}", model, request.messages.len());
+
+    let response = client
+        .post(provider_url(provider, &format!("{}:streamGenerateContent", gemini_model_path(model))))
+        .query(&[("alt", "sse")])
+        .header("x-goog-api-key", api_key)
+        .extra_headers(provider)
+        .header("Content-Type", "application/json")
+        .json(&gemini_body(request))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        println!("[AI] Gemini stream API error: body={}", error_text);
+        return Err(format!("API error: {}", error_text));
+    }
+
+    let stream_id = format!("gemini-{}", Uuid::new_v4());
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+    let mut finish_reason = None;
+
+    while let Some(chunk_result) = stream.next().await {
+        let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+        while let Some(line_end) = buffer.find('\n') {
+            let line = buffer[..line_end].trim().to_string();
+            buffer = buffer[line_end + 1..].to_string();
+
+            let Some(data) = line.strip_prefix("data:") else {
+                continue;
+            };
+            let Ok(json) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+                continue; // Skip malformed JSON
+            };
+
+            let text = gemini_candidate_text(&json);
+            if let Some(reason) = json["candidates"][0]["finishReason"].as_str() {
+                finish_reason = Some(reason.to_lowercase());
+            }
+            if !text.is_empty() {
+                on_chunk(ChatCompletionStreamChunk {
+                    id: stream_id.clone(),
+                    choices: vec![ChatStreamChoice {
+                        delta: ChatStreamDelta { role: None, content: Some(text) },
+                        finish_reason: None,
+                    }],
+                });
+            }
+        }
+    }
+
+    // Gemini has no [DONE] marker; the stream just ends
+    on_chunk(ChatCompletionStreamChunk {
+        id: stream_id,
+        choices: vec![ChatStreamChoice {
+            delta: ChatStreamDelta { role: None, content: None },
+            finish_reason: Some(finish_reason.unwrap_or_else(|| "stop".to_string())),
+        }],
+    });
+
+    println!("[AI] Gemini stream complete model={}", model);
+    Ok(())
+}
+
+pub async fn get_gemini_models(provider: &AIProvider, api_key: Option<String>) -> Result<Vec<AIModel>, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for Gemini provider")?;
+    let client = shared_client(HttpPurpose::Ai)?;
+
+    let response = client
+        .get(provider_url(provider, "models"))
+        .query(&[("pageSize", "1000")])
+        .timeout(Duration::from_secs(30))
+        .header("x-goog-api-key", api_key)
+        .extra_headers(provider)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err("Failed to fetch models".to_string());
+    }
+
+    let models_response: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    // Embedding and other non-chat models are listed too
+    let models = models_response["models"]
+        .as_array()
+        .unwrap_or(&vec![])
+        .iter()
+        .filter(|model| {
+            model["supportedGenerationMethods"].as_array()
+                .is_some_and(|methods| methods.iter().any(|method| method == "generateContent"))
+        })
+        .map(|model| {
+            let id = model["name"].as_str().unwrap_or("").trim_start_matches("models/").to_string();
+            AIModel {
+                name: model["displayName"].as_str().map(str::to_string).unwrap_or_else(|| id.clone()),
+                id,
+                provider_id: provider.id.clone(),
+                context_window: model["inputTokenLimit"].as_u64().unwrap_or(32768) as u32,
+                max_tokens: model["outputTokenLimit"].as_u64().unwrap_or(8192) as u32,
+                supports_streaming: true,
+                supports_tools: true,
+                capabilities: vec!["text".to_string(), "vision".to_string()],
+            }
+        })
+        .collect();
+
+    Ok(models)
+}
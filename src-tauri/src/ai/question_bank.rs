@@ -0,0 +1,206 @@
+//! Questions out of past exam papers. Numbered questions are found with patterns first
+//! ("Question 3", "Q3.", "3)"); the model is only asked to split the paper when the patterns
+//! find too little, and to name the topic of each question.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use super::reading::json_object;
+use super::types::ChatMessage;
+
+// Fewer than this and the paper probably isn't numbered the way the patterns expect
+pub const MIN_DETECTED_QUESTIONS: usize = 2;
+// The model splits at most this much of a paper; longer papers are usually numbered anyway
+pub const MAX_EXTRACTION_CHARS: usize = 20_000;
+// Questions shown per call when naming topics
+pub const TOPIC_BATCH_SIZE: usize = 25;
+const MAX_TOPIC_QUESTION_CHARS: usize = 600;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct DetectedQuestion {
+    #[serde(default)]
+    pub number: String, // As printed, e.g. "3"
+    pub text: String, // Sub-parts like (a) and (b) stay in the text
+    #[serde(default)]
+    pub marks: Option<i32>,
+    #[serde(default)]
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct ExtractedQuestions {
+    #[serde(default)]
+    questions: Vec<DetectedQuestion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct QuestionTopic {
+    index: usize,
+    topic: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+struct QuestionTopics {
+    #[serde(default)]
+    topics: Vec<QuestionTopic>,
+}
+
+// "Question 3", "QUESTION 3:", "Q3." and "Q. 3)"
+fn question_heading_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"(?i)^\s*(?:question|q\.?)\s*(\d{1,3})\s*[.:)]?\s*(.*)$").expect("valid question pattern")
+    })
+}
+
+// "3." or "3)" at the start of a line; only taken as a question when it is the next number
+fn numbered_line_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^\s*(\d{1,3})\s*[.)]\s+(\S.*)$").expect("valid numbered line pattern"))
+}
+
+// "[5 marks]", "(10 marks)", "[1 mark]"
+fn marks_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?i)[(\[]\s*(\d{1,3})\s*marks?\s*[)\]]").expect("valid marks pattern"))
+}
+
+fn year_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(19[5-9]\d|20\d{2})\b").expect("valid year pattern"))
+}
+
+/// Split exam text into numbered questions. Text before the first question (instructions,
+/// the cover page) is dropped.
+pub fn detect_questions(text: &str) -> Vec<DetectedQuestion> {
+    let mut questions: Vec<DetectedQuestion> = Vec::new();
+    let mut current: Option<DetectedQuestion> = None;
+    let mut last_number = 0u32;
+
+    for line in text.lines() {
+        let heading = question_heading_pattern().captures(line)
+            .or_else(|| numbered_line_pattern().captures(line)
+                .filter(|captures| captures[1].parse::<u32>().ok() == Some(last_number + 1)));
+
+        match heading {
+            Some(captures) => {
+                if let Some(question) = current.take() {
+                    questions.push(question);
+                }
+                last_number = captures[1].parse().unwrap_or(last_number + 1);
+                current = Some(DetectedQuestion {
+                    number: captures[1].to_string(),
+                    text: captures.get(2).map_or("", |m| m.as_str()).trim().to_string(),
+                    ..Default::default()
+                });
+            }
+            None => {
+                if let Some(question) = current.as_mut() {
+                    let line = line.trim();
+                    if !line.is_empty() {
+                        if !question.text.is_empty() {
+                            question.text.push('\n');
+                        }
+                        question.text.push_str(line);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(question) = current {
+        questions.push(question);
+    }
+
+    for question in questions.iter_mut() {
+        let marks: i32 = marks_pattern().captures_iter(&question.text)
+            .filter_map(|captures| captures[1].parse::<i32>().ok())
+            .sum();
+        question.marks = (marks > 0).then_some(marks);
+    }
+    questions.retain(|question| !question.text.trim().is_empty());
+    questions
+}
+
+/// The exam year, from the title first and then the start of the paper
+pub fn detect_exam_year(title: &str, text: &str) -> Option<i32> {
+    let start: String = text.chars().take(2_000).collect();
+    [title, start.as_str()].iter()
+        .find_map(|source| year_pattern().captures(source))
+        .and_then(|captures| captures[1].parse().ok())
+}
+
+pub fn build_question_extraction_messages(exam_title: &str, text: &str) -> Vec<ChatMessage> {
+    let text: String = text.chars().take(MAX_EXTRACTION_CHARS).collect();
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You split exam papers into their questions. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Below is the text of the exam paper \"{}\". List every question in order, with its number as printed, its full text \
+                 (keep sub-parts such as (a) and (b) in the text) and the marks it is worth if shown. Leave out instructions and cover pages.\n\
+                 Respond with a JSON object of the form \
+                 {{\"questions\": [{{\"number\": \"1\", \"text\": \"...\", \"marks\": 10}}]}}.\n\n{}",
+                exam_title, text
+            ),
+        },
+    ]
+}
+
+pub fn parse_question_extraction_response(text: &str) -> Result<Vec<DetectedQuestion>, String> {
+    let extracted: ExtractedQuestions = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse exam questions JSON: {}", e))?;
+    Ok(extracted.questions.into_iter()
+        .filter(|question| !question.text.trim().is_empty())
+        .collect())
+}
+
+/// Ask for the topic of each question, reusing `known_topics` where they fit
+pub fn build_topic_messages(exam_title: &str, questions: &[DetectedQuestion], known_topics: &[String]) -> Vec<ChatMessage> {
+    let listed = questions.iter().enumerate()
+        .map(|(index, question)| {
+            let text: String = question.text.chars().take(MAX_TOPIC_QUESTION_CHARS).collect();
+            format!("[{}] {}", index, text)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let known = if known_topics.is_empty() {
+        String::new()
+    } else {
+        format!("Topics already used for this course, to reuse where they fit: {}.\n", known_topics.join("; "))
+    };
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You classify exam questions by topic. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Name the topic each question from the exam \"{}\" tests, in two to four words (e.g. \"Thermodynamics\", \
+                 \"Linear regression\").\n{}\
+                 Respond with a JSON object of the form {{\"topics\": [{{\"index\": 0, \"topic\": \"...\"}}]}}.\n\n{}",
+                exam_title, known, listed
+            ),
+        },
+    ]
+}
+
+/// Topics by question index; indexes outside `question_count` are ignored
+pub fn parse_topic_response(text: &str, question_count: usize) -> Result<Vec<Option<String>>, String> {
+    let parsed: QuestionTopics = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse question topics JSON: {}", e))?;
+
+    let mut topics = vec![None; question_count];
+    for QuestionTopic { index, topic } in parsed.topics {
+        if index < question_count && !topic.trim().is_empty() {
+            topics[index] = Some(topic.trim().to_string());
+        }
+    }
+    Ok(topics)
+}
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+use super::types::ChatMessage;
+use crate::database::OutlineEntry;
+
+// Long sections are summarized from their start; a chapter rarely needs more than this
+pub const MAX_SECTION_CHARS: usize = 24_000;
+// Text around a selection that is sent along so the explanation fits its context
+pub const SELECTION_CONTEXT_CHARS: usize = 1_500;
+pub const MAX_SELECTION_CHARS: usize = 6_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SectionSummary {
+    pub summary: String,
+    #[serde(default)]
+    pub key_points: Vec<String>,
+    #[serde(default)]
+    pub key_terms: Vec<KeyTerm>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyTerm {
+    pub term: String,
+    pub definition: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SelectionExplanation {
+    pub explanation: String,
+    pub example: Option<String>,
+    #[serde(default)]
+    pub key_terms: Vec<KeyTerm>,
+    #[serde(default)]
+    pub follow_up_questions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionSummaryResult {
+    pub document_id: String,
+    pub heading_path: Vec<String>, // Titles from the top-level heading down to the section
+    pub entry: OutlineEntry,
+    pub truncated: bool, // Only the start of a long section was summarized
+    pub summary: SectionSummary,
+}
+
+/// Char offsets into a document's content
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectionExplanationResult {
+    pub document_id: String,
+    pub range: TextRange,
+    pub selection: String,
+    pub section_title: Option<String>,
+    pub level: ExplanationLevel,
+    pub explanation: SelectionExplanation,
+}
+
+/// How much the reader already knows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExplanationLevel {
+    Beginner,
+    #[default]
+    Intermediate,
+    Expert,
+}
+
+impl ExplanationLevel {
+    fn audience(self) -> &'static str {
+        match self {
+            ExplanationLevel::Beginner => "someone new to the subject: avoid jargon, define every term and use an everyday example",
+            ExplanationLevel::Intermediate => "a student taking a course on the subject: assume the basics and focus on the reasoning",
+            ExplanationLevel::Expert => "an expert: be concise and precise, and point out subtleties and assumptions",
+        }
+    }
+}
+
+pub fn build_section_summary_messages(document_title: &str, heading_path: &[String], section: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You are a tutor who summarizes textbook sections for students. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Summarize the section below in a short paragraph, list its key points, and define the terms it introduces.\n\
+                 Respond with a JSON object of the form \
+                 {{\"summary\": \"...\", \"key_points\": [\"...\"], \"key_terms\": [{{\"term\": \"...\", \"definition\": \"...\"}}]}}.\n\n\
+                 Document: {}\nSection: {}\n\n{}",
+                document_title, heading_path.join(" > "), section
+            ),
+        },
+    ]
+}
+
+pub fn parse_section_summary_response(text: &str) -> Result<SectionSummary, String> {
+    let mut summary: SectionSummary = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse summary JSON: {}", e))?;
+    if summary.summary.trim().is_empty() {
+        return Err("The model returned an empty summary".to_string());
+    }
+
+    summary.key_points.retain(|point| !point.trim().is_empty());
+    summary.key_terms.retain(|term| !term.term.trim().is_empty());
+    Ok(summary)
+}
+
+/// `before` and `after` are the text around the selection, which is only there for context
+pub fn build_explanation_messages(
+    document_title: &str,
+    section_title: Option<&str>,
+    before: &str,
+    selection: &str,
+    after: &str,
+    level: ExplanationLevel,
+) -> Vec<ChatMessage> {
+    let section = section_title.map(|title| format!("\nSection: {}", title)).unwrap_or_default();
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: format!(
+                "You are a tutor who explains passages of study material to {}. Respond with JSON only, no prose and no code fences.",
+                level.audience()
+            ),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Explain the passage marked with <selection> tags; the text around it is context only.\n\
+                 Respond with a JSON object of the form \
+                 {{\"explanation\": \"...\", \"example\": \"... or null\", \"key_terms\": [{{\"term\": \"...\", \"definition\": \"...\"}}], \
+                 \"follow_up_questions\": [\"...\"]}}.\n\n\
+                 Document: {}{}\n\n{}<selection>{}</selection>{}",
+                document_title, section, before, selection, after
+            ),
+        },
+    ]
+}
+
+pub fn parse_explanation_response(text: &str) -> Result<SelectionExplanation, String> {
+    let mut explanation: SelectionExplanation = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse explanation JSON: {}", e))?;
+    if explanation.explanation.trim().is_empty() {
+        return Err("The model returned an empty explanation".to_string());
+    }
+
+    explanation.example = explanation.example.filter(|example| !example.trim().is_empty());
+    explanation.key_terms.retain(|term| !term.term.trim().is_empty());
+    explanation.follow_up_questions.retain(|question| !question.trim().is_empty());
+    Ok(explanation)
+}
+
+// The JSON object in the model output, tolerating code fences or text around it
+pub(crate) fn json_object(text: &str) -> Result<&str, String> {
+    let start = text.find('{').ok_or("Model response did not contain a JSON object")?;
+    let end = text.rfind('}').ok_or("Model response did not contain a JSON object")?;
+    if end < start {
+        return Err("Model response did not contain a JSON object".to_string());
+    }
+    Ok(&text[start..=end])
+}
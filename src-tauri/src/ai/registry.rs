@@ -0,0 +1,185 @@
+//! Chat backends behind one `ChatProvider` trait, looked up by `AIProvider.type`. Adding a
+//! backend means implementing the trait and listing it in `builtin_providers` (or calling
+//! `register_chat_provider`); callers go through `chat_provider` and never match on the type.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use super::providers::*;
+use super::types::*;
+use super::vision::*;
+
+#[async_trait]
+pub trait ChatProvider: Send + Sync {
+    async fn test_connection(&self, provider: &AIProvider, api_key: Option<String>) -> Result<bool, String>;
+
+    async fn complete(
+        &self,
+        provider: &AIProvider,
+        model: &str,
+        request: &ChatCompletionRequest,
+        api_key: Option<String>,
+    ) -> Result<ChatCompletionResponse, String>;
+
+    async fn stream(
+        &self,
+        _provider: &AIProvider,
+        _model: &str,
+        _request: &ChatCompletionRequest,
+        _api_key: Option<String>,
+        _on_chunk: &ChunkSink,
+    ) -> Result<(), String> {
+        Err("Streaming not supported for this provider".to_string())
+    }
+
+    /// Text of the reply with `image` attached to the last user message
+    async fn complete_with_image(
+        &self,
+        _provider: &AIProvider,
+        _model: &str,
+        _request: &ChatCompletionRequest,
+        _image: &ImageInput,
+        _api_key: Option<String>,
+    ) -> Result<String, String> {
+        Err("Image input not supported for this provider".to_string())
+    }
+
+    async fn list_models(&self, provider: &AIProvider, api_key: Option<String>) -> Result<Vec<AIModel>, String>;
+}
+
+/// OpenAI and any OpenAI-compatible endpoint ("custom"), including Azure
+pub struct OpenAiChatProvider;
+
+#[async_trait]
+impl ChatProvider for OpenAiChatProvider {
+    async fn test_connection(&self, provider: &AIProvider, api_key: Option<String>) -> Result<bool, String> {
+        test_openai_connection(provider, api_key).await
+    }
+
+    async fn complete(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, api_key: Option<String>) -> Result<ChatCompletionResponse, String> {
+        openai_chat_completion(provider, model, request, api_key).await
+    }
+
+    async fn stream(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, api_key: Option<String>, on_chunk: &ChunkSink) -> Result<(), String> {
+        openai_chat_completion_stream(provider, model, request, api_key, on_chunk).await
+    }
+
+    async fn complete_with_image(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, image: &ImageInput, api_key: Option<String>) -> Result<String, String> {
+        openai_complete_with_image(provider, model, request, image, api_key).await
+    }
+
+    async fn list_models(&self, provider: &AIProvider, api_key: Option<String>) -> Result<Vec<AIModel>, String> {
+        get_openai_models(provider, api_key).await
+    }
+}
+
+pub struct AnthropicChatProvider;
+
+#[async_trait]
+impl ChatProvider for AnthropicChatProvider {
+    async fn test_connection(&self, provider: &AIProvider, api_key: Option<String>) -> Result<bool, String> {
+        test_anthropic_connection(provider, api_key).await
+    }
+
+    async fn complete(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, api_key: Option<String>) -> Result<ChatCompletionResponse, String> {
+        anthropic_chat_completion(provider, model, request, api_key).await
+    }
+
+    async fn complete_with_image(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, image: &ImageInput, api_key: Option<String>) -> Result<String, String> {
+        anthropic_complete_with_image(provider, model, request, image, api_key).await
+    }
+
+    async fn list_models(&self, provider: &AIProvider, api_key: Option<String>) -> Result<Vec<AIModel>, String> {
+        get_anthropic_models(provider, api_key).await
+    }
+}
+
+/// Ollama's native API; needs no key
+pub struct OllamaChatProvider;
+
+#[async_trait]
+impl ChatProvider for OllamaChatProvider {
+    async fn test_connection(&self, provider: &AIProvider, _api_key: Option<String>) -> Result<bool, String> {
+        test_ollama_connection(provider).await
+    }
+
+    async fn complete(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, _api_key: Option<String>) -> Result<ChatCompletionResponse, String> {
+        ollama_chat_completion(provider, model, request).await
+    }
+
+    async fn complete_with_image(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, image: &ImageInput, _api_key: Option<String>) -> Result<String, String> {
+        ollama_complete_with_image(provider, model, request, image).await
+    }
+
+    async fn list_models(&self, provider: &AIProvider, _api_key: Option<String>) -> Result<Vec<AIModel>, String> {
+        get_ollama_models(provider).await
+    }
+}
+
+/// Google's Gemini API, with https://generativelanguage.googleapis.com/v1beta as base URL
+pub struct GeminiChatProvider;
+
+#[async_trait]
+impl ChatProvider for GeminiChatProvider {
+    async fn test_connection(&self, provider: &AIProvider, api_key: Option<String>) -> Result<bool, String> {
+        test_gemini_connection(provider, api_key).await
+    }
+
+    async fn complete(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, api_key: Option<String>) -> Result<ChatCompletionResponse, String> {
+        gemini_chat_completion(provider, model, request, api_key).await
+    }
+
+    async fn stream(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, api_key: Option<String>, on_chunk: &ChunkSink) -> Result<(), String> {
+        gemini_chat_completion_stream(provider, model, request, api_key, on_chunk).await
+    }
+
+    async fn complete_with_image(&self, provider: &AIProvider, model: &str, request: &ChatCompletionRequest, image: &ImageInput, api_key: Option<String>) -> Result<String, String> {
+        gemini_complete_with_image(provider, model, request, image, api_key).await
+    }
+
+    async fn list_models(&self, provider: &AIProvider, api_key: Option<String>) -> Result<Vec<AIModel>, String> {
+        get_gemini_models(provider, api_key).await
+    }
+}
+
+type Registry = HashMap<String, Arc<dyn ChatProvider>>;
+
+static REGISTRY: RwLock<Option<Registry>> = RwLock::new(None);
+
+fn builtin_providers() -> Registry {
+    let openai: Arc<dyn ChatProvider> = Arc::new(OpenAiChatProvider);
+    let mut registry: Registry = HashMap::new();
+    registry.insert("openai".to_string(), openai.clone());
+    registry.insert("custom".to_string(), openai);
+    registry.insert("anthropic".to_string(), Arc::new(AnthropicChatProvider));
+    registry.insert("ollama".to_string(), Arc::new(OllamaChatProvider));
+    registry.insert("gemini".to_string(), Arc::new(GeminiChatProvider));
+    registry
+}
+
+/// The backend for an `AIProvider.type`
+pub fn chat_provider(provider_type: &str) -> Result<Arc<dyn ChatProvider>, String> {
+    if let Ok(registry) = REGISTRY.read() {
+        if let Some(registry) = registry.as_ref() {
+            return registry.get(provider_type).cloned().ok_or_else(|| unsupported(provider_type));
+        }
+    }
+
+    let mut registry = REGISTRY.write().map_err(|_| "Provider registry is unavailable".to_string())?;
+    registry.get_or_insert_with(builtin_providers)
+        .get(provider_type)
+        .cloned()
+        .ok_or_else(|| unsupported(provider_type))
+}
+
+/// Serve `provider_type` with `backend`, replacing a built-in one of the same type
+pub fn register_chat_provider(provider_type: &str, backend: Arc<dyn ChatProvider>) {
+    if let Ok(mut registry) = REGISTRY.write() {
+        registry.get_or_insert_with(builtin_providers).insert(provider_type.to_string(), backend);
+    }
+}
+
+fn unsupported(provider_type: &str) -> String {
+    format!("Unsupported provider type: {}", provider_type)
+}
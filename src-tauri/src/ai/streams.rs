@@ -0,0 +1,107 @@
+//! Registry of in-flight streamed completions. Each stream gets its own `ai-stream:{id}`
+//! event channel, so concurrent chats in different windows never see each other's chunks.
+
+use super::types::ChatCompletionStreamChunk;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::task::AbortHandle;
+
+pub fn channel_name(stream_id: &str) -> String {
+    format!("ai-stream:{}", stream_id)
+}
+
+/// Payload of every event on a stream channel: one `start`, any number of `chunk`s, then
+/// exactly one of `end` or `error`. With the output filter on, a `filtered` event carrying the
+/// cleaned reply comes right before `end` whenever the filter changed or flagged something.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StreamEvent {
+    Start {
+        #[serde(rename = "streamId")]
+        stream_id: String,
+        model: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        warning: Option<String>, // History was pruned to fit the context window
+    },
+    Chunk {
+        #[serde(rename = "streamId")]
+        stream_id: String,
+        chunk: ChatCompletionStreamChunk,
+    },
+    Filtered {
+        #[serde(rename = "streamId")]
+        stream_id: String,
+        content: String, // Replaces everything streamed so far
+        flags: Vec<String>,
+    },
+    End {
+        #[serde(rename = "streamId")]
+        stream_id: String,
+        cancelled: bool,
+    },
+    Error {
+        #[serde(rename = "streamId")]
+        stream_id: String,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveStream {
+    #[serde(rename = "streamId")]
+    pub stream_id: String,
+    #[serde(rename = "providerId")]
+    pub provider_id: String,
+    pub model: String,
+    #[serde(rename = "startedAt")]
+    pub started_at: DateTime<Utc>,
+}
+
+struct Entry {
+    info: ActiveStream,
+    abort: AbortHandle,
+}
+
+static ACTIVE_STREAMS: Mutex<Option<HashMap<String, Entry>>> = Mutex::new(None);
+
+fn with_registry<T>(f: impl FnOnce(&mut HashMap<String, Entry>) -> T) -> T {
+    let mut guard = ACTIVE_STREAMS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    f(guard.get_or_insert_with(HashMap::new))
+}
+
+/// Record a stream together with the handle of its task, so it can be cancelled as soon as
+/// it's visible; fails if the id is already in use. The task should wait for this to succeed
+/// before it starts streaming.
+pub fn register(info: ActiveStream, abort: AbortHandle) -> Result<(), String> {
+    with_registry(|streams| {
+        if streams.contains_key(&info.stream_id) {
+            return Err(format!("Stream {} is already active", info.stream_id));
+        }
+        streams.insert(info.stream_id.clone(), Entry { info, abort });
+        Ok(())
+    })
+}
+
+/// Forget a stream; returns false if it was already gone (e.g. cancelled)
+pub fn finish(stream_id: &str) -> bool {
+    with_registry(|streams| streams.remove(stream_id).is_some())
+}
+
+/// Abort a stream's task and drop it from the registry
+pub fn cancel(stream_id: &str) -> bool {
+    with_registry(|streams| match streams.remove(stream_id) {
+        Some(entry) => {
+            entry.abort.abort();
+            true
+        }
+        None => false,
+    })
+}
+
+pub fn active() -> Vec<ActiveStream> {
+    let mut streams: Vec<ActiveStream> = with_registry(|streams| streams.values().map(|entry| entry.info.clone()).collect());
+    streams.sort_by_key(|stream| stream.started_at);
+    streams
+}
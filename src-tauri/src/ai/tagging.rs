@@ -0,0 +1,62 @@
+use super::types::ChatMessage;
+
+// Notes are short; longer documents are tagged from their opening
+const MAX_TAGGING_SOURCE_CHARS: usize = 4_000;
+const MAX_SUGGESTED_TAGS: usize = 5;
+// Offered to the model so it reuses the library's vocabulary instead of inventing synonyms
+const MAX_EXISTING_TAGS: usize = 200;
+
+/// Build the chat messages asking the model for a few topic tags as a JSON array
+pub fn build_tagging_messages(title: &str, content: &str, existing_tags: &[String]) -> Vec<ChatMessage> {
+    let source: String = content.chars().take(MAX_TAGGING_SOURCE_CHARS).collect();
+    let existing = existing_tags.iter()
+        .take(MAX_EXISTING_TAGS)
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You file a student's notes by topic. Respond with a JSON array of strings only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Suggest up to {} short lowercase topic tags for the note below. \
+                 Prefer tags the library already uses when they fit: {}.\n\n\
+                 Title: {}\n\n{}",
+                MAX_SUGGESTED_TAGS,
+                if existing.is_empty() { "(none yet)" } else { &existing },
+                title,
+                source
+            ),
+        },
+    ]
+}
+
+/// Parse the model output into clean, de-duplicated tags, tolerating text around the array
+pub fn parse_tagging_response(text: &str) -> Result<Vec<String>, String> {
+    let start = text.find('[').ok_or("Model response did not contain a JSON array")?;
+    let end = text.rfind(']').ok_or("Model response did not contain a JSON array")?;
+    if end < start {
+        return Err("Model response did not contain a JSON array".to_string());
+    }
+
+    let raw: Vec<String> = serde_json::from_str(&text[start..=end])
+        .map_err(|e| format!("Failed to parse tag JSON: {}", e))?;
+
+    let mut tags: Vec<String> = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if tag.is_empty() || tag.len() > 40 || tags.contains(&tag) {
+            continue;
+        }
+        tags.push(tag);
+        if tags.len() == MAX_SUGGESTED_TAGS {
+            break;
+        }
+    }
+
+    Ok(tags)
+}
@@ -0,0 +1,215 @@
+//! Lecture transcripts into outlined notes. The transcript is cut into segments at line
+//! boundaries, keeping the timestamp each segment starts at; every segment is turned into
+//! topic notes on its own (map), then the notes of all segments are merged into one outline
+//! with a summary and action items (reduce).
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use super::reading::json_object;
+use super::types::ChatMessage;
+use crate::database::Document;
+
+// Large enough for a few minutes of speech per call, small enough for any chat model's context
+pub const MAX_SEGMENT_CHARS: usize = 8_000;
+// About three hours of lecture; anything after is left out and the result marked truncated
+pub const MAX_SEGMENTS: usize = 30;
+
+/// Part of a transcript, starting at `start` (as written in the transcript) when it has
+/// timestamps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NoteTopic {
+    pub heading: String,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub bullets: Vec<String>,
+}
+
+/// What one segment covers
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SegmentNotes {
+    #[serde(default)]
+    pub topics: Vec<NoteTopic>,
+    #[serde(default)]
+    pub action_items: Vec<String>, // Assignments, readings and deadlines mentioned
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StructuredNotes {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub sections: Vec<NoteTopic>,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StructuredTranscriptResult {
+    pub transcript_document_id: String,
+    pub document: Document, // The new notes document, linked to the transcript in both metadata
+    pub notes: StructuredNotes,
+    pub segments: usize,
+    pub truncated: bool, // The transcript had more than MAX_SEGMENTS segments; the rest was left out
+}
+
+// [00:12:34], 00:12:34.500, 12:34 and the SRT/VTT "00:12:34,500 --> 00:12:38,000" form
+fn timestamp_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^\s*[\[(]?((?:\d{1,2}:)?\d{1,2}:\d{2})(?:[.,]\d{1,3})?[\])]?(?:\s*-->\s*[\d:.,]+)?\s*[-–:]?\s*")
+            .expect("valid timestamp pattern")
+    })
+}
+
+/// Split a transcript into segments of at most `max_chars` (a single longer line becomes a
+/// segment of its own). Timestamps are taken off the lines and kept as segment starts; SRT
+/// cue numbers and WEBVTT headers are dropped.
+pub fn split_transcript(transcript: &str, max_chars: usize) -> Vec<TranscriptSegment> {
+    let mut segments = Vec::new();
+    let mut current = TranscriptSegment { start: None, text: String::new() };
+    let mut last_timestamp: Option<String> = None;
+
+    for line in transcript.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed == "WEBVTT" || trimmed.chars().all(|c| c.is_ascii_digit()) {
+            continue;
+        }
+
+        let text = match timestamp_pattern().captures(trimmed) {
+            Some(captures) => {
+                last_timestamp = captures.get(1).map(|m| m.as_str().to_string());
+                trimmed[captures.get(0).map_or(0, |m| m.end())..].trim()
+            }
+            None => trimmed,
+        };
+        if text.is_empty() {
+            continue;
+        }
+
+        if !current.text.is_empty() && current.text.len() + text.len() + 1 > max_chars {
+            segments.push(std::mem::replace(&mut current, TranscriptSegment { start: None, text: String::new() }));
+        }
+        if current.text.is_empty() {
+            current.start = last_timestamp.clone();
+        } else {
+            current.text.push('\n');
+        }
+        current.text.push_str(text);
+    }
+
+    if !current.text.is_empty() {
+        segments.push(current);
+    }
+    segments
+}
+
+pub fn build_segment_notes_messages(document_title: &str, segment: &TranscriptSegment, index: usize, total: usize) -> Vec<ChatMessage> {
+    let start = segment.start.as_deref().map(|start| format!(", starting at {}", start)).unwrap_or_default();
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You turn lecture transcripts into study notes. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Below is part {} of {} of the transcript of \"{}\"{}. List the topics it covers in order, each with a short heading, \
+                 the timestamp where it starts if the transcript shows one, and concise bullet points of what was said. \
+                 Skip small talk and filler. Also list action items the lecturer mentions (assignments, readings, deadlines).\n\
+                 Respond with a JSON object of the form \
+                 {{\"topics\": [{{\"heading\": \"...\", \"timestamp\": \"... or null\", \"bullets\": [\"...\"]}}], \"action_items\": [\"...\"]}}.\n\n{}",
+                index + 1, total, document_title, start, segment.text
+            ),
+        },
+    ]
+}
+
+pub fn parse_segment_notes_response(text: &str) -> Result<SegmentNotes, String> {
+    let mut notes: SegmentNotes = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse segment notes JSON: {}", e))?;
+    clean_topics(&mut notes.topics);
+    notes.action_items.retain(|item| !item.trim().is_empty());
+    Ok(notes)
+}
+
+/// Merge the notes of every segment, in order, into one outline
+pub fn build_outline_messages(document_title: &str, segment_notes: &[SegmentNotes]) -> Vec<ChatMessage> {
+    let notes = serde_json::to_string(segment_notes).unwrap_or_else(|_| "[]".to_string());
+
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You turn lecture transcripts into study notes. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Below are notes taken on consecutive parts of the lecture \"{}\", as a JSON array. Merge them into one outline: \
+                 combine topics that continue across parts, keep the order and the earliest timestamp of each topic, and remove \
+                 repetition. Write a short summary of the whole lecture and a deduplicated list of action items.\n\
+                 Respond with a JSON object of the form \
+                 {{\"title\": \"...\", \"summary\": \"...\", \"sections\": [{{\"heading\": \"...\", \"timestamp\": \"... or null\", \"bullets\": [\"...\"]}}], \
+                 \"action_items\": [\"...\"]}}.\n\n{}",
+                document_title, notes
+            ),
+        },
+    ]
+}
+
+pub fn parse_outline_response(text: &str) -> Result<StructuredNotes, String> {
+    let mut notes: StructuredNotes = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse outline JSON: {}", e))?;
+    clean_topics(&mut notes.sections);
+    if notes.sections.is_empty() {
+        return Err("The model returned an empty outline".to_string());
+    }
+    notes.action_items.retain(|item| !item.trim().is_empty());
+    Ok(notes)
+}
+
+fn clean_topics(topics: &mut Vec<NoteTopic>) {
+    for topic in topics.iter_mut() {
+        topic.timestamp = topic.timestamp.take().filter(|timestamp| !timestamp.trim().is_empty() && timestamp != "null");
+        topic.bullets.retain(|bullet| !bullet.trim().is_empty());
+    }
+    topics.retain(|topic| !topic.heading.trim().is_empty());
+}
+
+/// The notes as a markdown document
+pub fn render_structured_notes(notes: &StructuredNotes) -> String {
+    let mut markdown = format!("# {}\n\n", notes.title.trim());
+    if !notes.summary.trim().is_empty() {
+        markdown.push_str(&format!("## Summary\n\n{}\n\n", notes.summary.trim()));
+    }
+
+    for section in &notes.sections {
+        match &section.timestamp {
+            Some(timestamp) => markdown.push_str(&format!("## {} ({})\n\n", section.heading.trim(), timestamp.trim())),
+            None => markdown.push_str(&format!("## {}\n\n", section.heading.trim())),
+        }
+        for bullet in &section.bullets {
+            markdown.push_str(&format!("- {}\n", bullet.trim()));
+        }
+        markdown.push('\n');
+    }
+
+    if !notes.action_items.is_empty() {
+        markdown.push_str("## Action items\n\n");
+        for item in &notes.action_items {
+            markdown.push_str(&format!("- [ ] {}\n", item.trim()));
+        }
+    }
+    markdown.trim_end().to_string() + "\n"
+}
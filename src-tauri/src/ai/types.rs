@@ -10,6 +10,43 @@ pub struct AIProvider {
     pub base_url: String,
     #[serde(rename = "apiKey", skip_serializing_if = "Option::is_none")]
     pub api_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>, // Sent as OpenAI-Organization
+    #[serde(rename = "apiVersion", default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>, // Azure OpenAI: appended as ?api-version= and auth switches to the api-key header
+    #[serde(rename = "extraHeaders", default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra_headers: HashMap<String, String>, // Sent with every request, e.g. proxy auth
+}
+
+/// Connection options saved per provider, merged into `AIProvider` when the caller leaves them unset
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderConnectionSettings {
+    #[serde(default)]
+    pub organization: Option<String>,
+    #[serde(rename = "apiVersion", default)]
+    pub api_version: Option<String>,
+    #[serde(rename = "extraHeaders", default)]
+    pub extra_headers: HashMap<String, String>,
+    #[serde(rename = "contextWindow", default)]
+    pub context_window: Option<u32>, // Overrides the models.dev limit, e.g. for local or fine-tuned models
+}
+
+impl AIProvider {
+    pub fn settings_key(provider_id: &str) -> String {
+        format!("ai_provider_connection:{}", provider_id)
+    }
+
+    pub fn apply_connection_settings(&mut self, settings: ProviderConnectionSettings) {
+        if self.organization.is_none() {
+            self.organization = settings.organization;
+        }
+        if self.api_version.is_none() {
+            self.api_version = settings.api_version;
+        }
+        for (name, value) in settings.extra_headers {
+            self.extra_headers.entry(name).or_insert(value);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +93,8 @@ pub struct ChatCompletionResponse {
     pub id: String,
     pub choices: Vec<ChatChoice>,
     pub usage: ChatUsage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>, // Set when history was pruned to fit the context window
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -81,6 +120,9 @@ pub struct ChatCompletionStreamChunk {
     pub choices: Vec<ChatStreamChoice>,
 }
 
+/// Receives each chunk of a streamed completion; the caller decides how to deliver it
+pub type ChunkSink = dyn Fn(ChatCompletionStreamChunk) + Send + Sync;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatStreamChoice {
     pub delta: ChatStreamDelta,
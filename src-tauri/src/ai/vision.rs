@@ -0,0 +1,219 @@
+//! Completions with one image attached to the last user message, for providers whose models
+//! can see (GPT-4o, Claude, Gemini, llava-style Ollama models). Each backend sends the image the
+//! way its API expects; see `ChatProvider::complete_with_image`.
+
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+use super::providers::{ensure_provider_reachable, gemini_body, gemini_candidate_text, gemini_model_path, provider_url, ProviderHeaders};
+use super::types::*;
+use crate::network::{shared_client, HttpPurpose};
+
+// Photos take longer to read than text
+const VISION_TIMEOUT_SECS: u64 = 120;
+
+/// An encoded image (PNG, JPEG, ...) to send along with a request
+#[derive(Debug, Clone)]
+pub struct ImageInput {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl ImageInput {
+    fn base64(&self) -> String {
+        general_purpose::STANDARD.encode(&self.data)
+    }
+
+    fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.base64())
+    }
+}
+
+/// Text of the reply to `request` with `image` attached, through the registered backend for
+/// `provider.r#type`
+pub async fn complete_with_image_for_provider(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    image: &ImageInput,
+    api_key: Option<String>,
+) -> Result<String, String> {
+    let started_at = Instant::now();
+    let text = super::registry::chat_provider(&provider.r#type)?
+        .complete_with_image(provider, model, request, image, api_key)
+        .await?;
+    println!("[AI] Vision request done provider={} model={} elapsed={}ms", provider.r#type, model, started_at.elapsed().as_millis());
+    Ok(text)
+}
+
+// Index of the message the image goes with
+fn last_user_message(request: &ChatCompletionRequest) -> Result<usize, String> {
+    request.messages.iter()
+        .rposition(|message| message.role == "user")
+        .ok_or_else(|| "An image request needs a user message".to_string())
+}
+
+async fn send_json(builder: reqwest::RequestBuilder, body: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let response = builder
+        .timeout(Duration::from_secs(VISION_TIMEOUT_SECS))
+        .header("Content-Type", "application/json")
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("API error: {}", error_text));
+    }
+
+    response.json().await.map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// OpenAI and compatible endpoints take content parts with a data URL
+pub async fn openai_complete_with_image(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    image: &ImageInput,
+    api_key: Option<String>,
+) -> Result<String, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for OpenAI provider")?;
+    let image_index = last_user_message(request)?;
+
+    let messages: Vec<serde_json::Value> = request.messages.iter().enumerate()
+        .map(|(index, message)| if index == image_index {
+            json!({
+                "role": message.role,
+                "content": [
+                    { "type": "text", "text": message.content },
+                    { "type": "image_url", "image_url": { "url": image.data_url() } },
+                ],
+            })
+        } else {
+            json!({ "role": message.role, "content": message.content })
+        })
+        .collect();
+    let mut body = json!({ "model": model, "messages": messages });
+    if let Some(temp) = request.temperature { body["temperature"] = temp.into(); }
+    if let Some(max_tokens) = request.max_tokens { body["max_tokens"] = max_tokens.into(); }
+
+    let client = shared_client(HttpPurpose::Ai)?;
+    let response = send_json(
+        client.post(provider_url(provider, "chat/completions")).openai_auth(provider, &api_key),
+        &body,
+    ).await?;
+
+    Ok(response["choices"][0]["message"]["content"].as_str().unwrap_or("").to_string())
+}
+
+/// Anthropic takes a base64 image block before the text
+pub async fn anthropic_complete_with_image(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    image: &ImageInput,
+    api_key: Option<String>,
+) -> Result<String, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for Anthropic provider")?;
+    let image_index = last_user_message(request)?;
+
+    let system: Vec<&str> = request.messages.iter()
+        .filter(|m| m.role == "system")
+        .map(|m| m.content.as_str())
+        .collect();
+    let messages: Vec<serde_json::Value> = request.messages.iter().enumerate()
+        .filter(|(_, message)| message.role != "system")
+        .map(|(index, message)| if index == image_index {
+            json!({
+                "role": "user",
+                "content": [
+                    { "type": "image", "source": { "type": "base64", "media_type": image.mime_type, "data": image.base64() } },
+                    { "type": "text", "text": message.content },
+                ],
+            })
+        } else {
+            json!({ "role": message.role, "content": message.content })
+        })
+        .collect();
+
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "max_tokens": request.max_tokens.unwrap_or(2048),
+    });
+    if !system.is_empty() { body["system"] = system.join("\n\n").into(); }
+    if let Some(temp) = request.temperature { body["temperature"] = temp.into(); }
+
+    let client = shared_client(HttpPurpose::Ai)?;
+    let response = send_json(
+        client.post(format!("{}/messages", provider.base_url))
+            .header("x-api-key", api_key)
+            .header("anthropic-version", "2023-06-01")
+            .extra_headers(provider),
+        &body,
+    ).await?;
+
+    Ok(response["content"][0]["text"].as_str().unwrap_or("").to_string())
+}
+
+/// Gemini takes the image as an inline_data part of the user turn
+pub async fn gemini_complete_with_image(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    image: &ImageInput,
+    api_key: Option<String>,
+) -> Result<String, String> {
+    ensure_provider_reachable(provider)?;
+    let api_key = api_key.ok_or("API key required for Gemini provider")?;
+    let mut body = gemini_body(request);
+    body["contents"].as_array_mut()
+        .and_then(|contents| contents.iter_mut().rev().find(|content| content["role"] == "user"))
+        .and_then(|content| content["parts"].as_array_mut())
+        .ok_or("An image request needs a user message")?
+        .push(json!({ "inline_data": { "mime_type": image.mime_type, "data": image.base64() } }));
+
+    let client = shared_client(HttpPurpose::Ai)?;
+    let response = send_json(
+        client.post(provider_url(provider, &format!("{}:generateContent", gemini_model_path(model))))
+            .header("x-goog-api-key", api_key)
+            .extra_headers(provider),
+        &body,
+    ).await?;
+
+    Ok(gemini_candidate_text(&response))
+}
+
+/// Ollama takes base64 images next to the message text
+pub async fn ollama_complete_with_image(
+    provider: &AIProvider,
+    model: &str,
+    request: &ChatCompletionRequest,
+    image: &ImageInput,
+) -> Result<String, String> {
+    ensure_provider_reachable(provider)?;
+    let image_index = last_user_message(request)?;
+
+    let messages: Vec<serde_json::Value> = request.messages.iter().enumerate()
+        .map(|(index, message)| {
+            let mut value = json!({ "role": message.role, "content": message.content });
+            if index == image_index {
+                value["images"] = json!([image.base64()]);
+            }
+            value
+        })
+        .collect();
+    let body = json!({ "model": model, "messages": messages, "stream": false });
+
+    let client = shared_client(HttpPurpose::Ai)?;
+    let response = send_json(
+        client.post(format!("{}/api/chat", provider.base_url)).extra_headers(provider),
+        &body,
+    ).await?;
+
+    Ok(response["message"]["content"].as_str().unwrap_or("").to_string())
+}
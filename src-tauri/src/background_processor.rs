@@ -1,20 +1,40 @@
 use std::sync::Arc;
 use std::time::Duration;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use tokio::sync::Mutex;
 use tokio::time;
 use chrono::Utc;
 
 
-use crate::database::{Database, ProcessingJob, ProcessingJobUpdate, CreateDocumentRequest, CreateProcessingJobRequest};
-use crate::pdf_processor::{PdfProcessor, MarkerOptions};
+use crate::database::{Database, ProcessingJob, ProcessingJobUpdate, CreateDocumentRequest, CreateProcessingJobRequest, CreateQuizRequest, CreateQuizQuestionRequest, CreateFlashcardRequest, BulkDocumentChanges, ConceptItemType};
+use crate::ai::{AIProvider, ChatCompletionRequest, ProviderConnectionSettings, chat_completion_for_provider};
+use crate::ai::practice::{build_practice_messages, parse_practice_response};
+use crate::ai::tagging::{build_tagging_messages, parse_tagging_response};
+use crate::pdf_processor::{PdfProcessor, PdfError, MarkerOptions, MarkerLlmService, MarkerProgress, MarkerProgressCallback, ExtractOptions, ExtractionMethod, ExtractionResult, EXTRACTION_QUALITY_THRESHOLD};
 use crate::embeddings::VectorService;
+use crate::importers::{self, ImportFormat};
+use crate::scripting;
+use crate::services::DocumentService;
+use crate::services::ai::redact_chat_request;
+use crate::temp_files::{TempFileGuard, TempKind};
+
+const BACKGROUND_MARKER_TIMEOUT_SECS: u64 = 6000;
+
+// Set once the processor is started, so shutdown can stop it
+pub type BackgroundProcessorState = Arc<Mutex<Option<BackgroundProcessor>>>;
+
+// What a URL job's link led to
+enum UrlDownload {
+    Pdf(TempFileGuard),
+    WebPage { url: String, html: String },
+}
 
 pub struct BackgroundProcessor {
     database: Arc<Mutex<Option<Database>>>,
     vector_service: Arc<Mutex<Option<VectorService>>>,
     pdf_processor: PdfProcessor,
     running: Arc<Mutex<bool>>,
+    current_job: Arc<Mutex<Option<String>>>, // Id of the job being processed right now
 }
 
 impl BackgroundProcessor {
@@ -24,13 +44,14 @@ impl BackgroundProcessor {
     ) -> Self {
         // Use a longer timeout for background processing to support very large PDFs
         // Default marker URL matches PdfProcessor::new()
-        let pdf_processor = PdfProcessor::with_config("http://localhost:8001".to_string(), 6000);
+        let pdf_processor = PdfProcessor::with_config("http://localhost:8001".to_string(), BACKGROUND_MARKER_TIMEOUT_SECS);
 
         Self {
             database,
             vector_service,
             pdf_processor,
             running: Arc::new(Mutex::new(false)),
+            current_job: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -45,6 +66,15 @@ impl BackgroundProcessor {
 
         println!("🚀 Starting background PDF processor...");
 
+        // Nothing is running yet, so any job still marked 'processing' was interrupted
+        if let Some(database) = self.database.lock().await.as_ref() {
+            match database.requeue_interrupted_jobs().await {
+                Ok(0) => {}
+                Ok(requeued) => println!("🔁 Requeued {} jobs interrupted by the last shutdown", requeued),
+                Err(e) => eprintln!("⚠️ Failed to requeue interrupted jobs: {}", e),
+            }
+        }
+
         // Start the processing loop
         let processor = self.clone();
         tokio::spawn(async move {
@@ -59,9 +89,33 @@ impl BackgroundProcessor {
         println!("🛑 Stopping background PDF processor...");
     }
 
+    /// Stop the loop and give the job in flight up to `grace` to finish. A job still running
+    /// after that is put back in the queue to start over on the next launch.
+    pub async fn shutdown(&self, grace: Duration) {
+        self.stop().await;
+
+        let deadline = time::Instant::now() + grace;
+        while self.current_job.lock().await.is_some() && time::Instant::now() < deadline {
+            time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let Some(job_id) = self.current_job.lock().await.clone() else {
+            return;
+        };
+        let db_guard = self.database.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            match database.requeue_processing_job(&job_id).await {
+                Ok(true) => println!("🔁 Job {} was interrupted by shutdown and will resume on the next start", job_id),
+                Ok(false) => {}
+                Err(e) => eprintln!("⚠️ Failed to requeue interrupted job {}: {}", job_id, e),
+            }
+        }
+    }
+
     /// Main processing loop
     async fn processing_loop(&self) {
         let mut interval = time::interval(Duration::from_secs(5)); // Check every 5 seconds
+        let mut paused_for: Option<&'static str> = None;
         
         loop {
             interval.tick().await;
@@ -73,6 +127,22 @@ impl BackgroundProcessor {
             }
             drop(is_running);
 
+            // Wait while on battery or during a review (see throttle.rs)
+            let pause_reason = match self.database.lock().await.as_ref() {
+                Some(database) => crate::throttle::pause_reason(database).await,
+                None => None,
+            };
+            if pause_reason != paused_for {
+                match pause_reason {
+                    Some(reason) => println!("⏸️ Pausing background processing: {}", reason),
+                    None => println!("▶️ Resuming background processing"),
+                }
+                paused_for = pause_reason;
+            }
+            if pause_reason.is_some() {
+                continue;
+            }
+
             // Process next job
             if let Err(e) = self.process_next_job().await {
                 eprintln!("❌ Error processing job: {}", e);
@@ -113,33 +183,57 @@ impl BackgroundProcessor {
             .map_err(|e| format!("Failed to update job status: {}", e))?;
         drop(db_guard);
 
-        // Process the job based on its type
         let job_id = job.id.clone();
         let job_type = job.job_type.clone();
-        
-        match job_type.as_str() {
+        *self.current_job.lock().await = Some(job_id.clone());
+        let result = self.run_job(&job, &job_id, &job_type).await;
+        *self.current_job.lock().await = None;
+        result
+    }
+
+    /// Process the job based on its type
+    async fn run_job(&self, job: &ProcessingJob, job_id: &str, job_type: &str) -> Result<(), String> {
+        match job_type {
             "pdf_processing" => {
-                if let Err(e) = self.process_pdf_job(&job).await {
+                if let Err(e) = self.process_pdf_job(job).await {
                     eprintln!("❌ PDF processing failed: {}", e);
-                    self.mark_job_failed(&job_id, &e).await?;
+                    self.mark_job_failed(job_id, &e).await?;
                 }
             }
             "pdf_content_extraction" => {
-                if let Err(e) = self.process_document_content_extraction_job(&job).await {
+                if let Err(e) = self.process_document_content_extraction_job(job).await {
                     eprintln!("❌ Document content extraction failed: {}", e);
-                    self.mark_job_failed(&job_id, &e).await?;
+                    self.mark_job_failed(job_id, &e).await?;
                 }
             }
             "document_content_extraction" => {
-                if let Err(e) = self.process_document_content_extraction_job(&job).await {
+                if let Err(e) = self.process_document_content_extraction_job(job).await {
                     eprintln!("❌ Document content extraction failed: {}", e);
-                    self.mark_job_failed(&job_id, &e).await?;
+                    self.mark_job_failed(job_id, &e).await?;
+                }
+            }
+            "practice_generation" => {
+                if let Err(e) = self.process_practice_generation_job(job).await {
+                    eprintln!("❌ Practice generation failed: {}", e);
+                    self.mark_job_failed(job_id, &e).await?;
+                }
+            }
+            "auto_tagging" => {
+                if let Err(e) = self.process_auto_tagging_job(job).await {
+                    eprintln!("❌ Auto-tagging failed: {}", e);
+                    self.mark_job_failed(job_id, &e).await?;
+                }
+            }
+            "web_clip" => {
+                if let Err(e) = self.process_web_clip_job(job).await {
+                    eprintln!("❌ Web clip failed: {}", e);
+                    self.mark_job_failed(job_id, &e).await?;
                 }
             }
             _ => {
                 let error = format!("Unknown job type: {}", job_type);
                 eprintln!("❌ {}", error);
-                self.mark_job_failed(&job_id, &error).await?;
+                self.mark_job_failed(job_id, &error).await?;
             }
         }
 
@@ -151,13 +245,25 @@ impl BackgroundProcessor {
         // Update progress
         self.update_job_progress(&job.id, 20).await?;
 
-        // Get source file path
+        // Get source file path; a download is deleted when `download` drops, whether or not
+        // the job succeeds
+        let mut download = None;
         let source_path = match job.source_type.as_str() {
             "file" => job.source_path.clone().ok_or("No source path provided")?,
             "url" => {
                 // Download file first
                 self.update_job_progress(&job.id, 30).await?;
-                self.download_file_from_url(&job.source_path.clone().ok_or("No URL provided")?).await?
+                match self.download_file_from_url(&job.source_path.clone().ok_or("No URL provided")?).await? {
+                    UrlDownload::Pdf(file) => {
+                        let path = file.path().to_string_lossy().to_string();
+                        download = Some(file);
+                        path
+                    }
+                    UrlDownload::WebPage { url, html } => {
+                        println!("📰 {} is a web page, clipping it instead", url);
+                        return self.clip_downloaded_page(job, &url, &html).await;
+                    }
+                }
             }
             "data" => {
                 // File should already be in temp storage
@@ -179,31 +285,17 @@ impl BackgroundProcessor {
             .and_then(|opts| serde_json::from_value(opts.clone()).ok())
             .unwrap_or_default();
 
-        let marker_options = processing_options;
+        let processing_options = self.attach_llm_key(processing_options).await;
+        let extract_options = ExtractOptions::from_marker_options(&processing_options, BACKGROUND_MARKER_TIMEOUT_SECS);
 
         self.update_job_progress(&job.id, 50).await?;
 
-        // Process the PDF with Marker; fall back to basic extraction on failure
-        let content = match self
-            .pdf_processor
-            .extract_with_marker(&source_path, marker_options)
+        // Walk the extraction chain (Marker first by default) until a method succeeds
+        let extraction = self
+            .extract_tracked(&job.id, &source_path, &extract_options)
             .await
-        {
-            Ok(text) => text,
-            Err(e) => {
-                eprintln!(
-                    "❌ Marker extraction failed, falling back to basic text extraction: {:?}",
-                    e
-                );
-                self
-                    .pdf_processor
-                    .extract_text_from_pdf(&source_path)
-                    .map_err(|e2| format!(
-                        "PDF processing failed (Marker and basic extraction): {:?}",
-                        e2
-                    ))?
-            }
-        };
+            .map_err(|e| format!("PDF processing failed: {}", e))?;
+        let content = extraction.content.clone();
 
         self.update_job_progress(&job.id, 70).await?;
 
@@ -235,6 +327,18 @@ impl BackgroundProcessor {
         let database = db_guard.as_ref().ok_or("Database not initialized")?;
         let document = database.create_document(request).await
             .map_err(|e| format!("Failed to create document: {}", e))?;
+        database.set_document_metadata_field(&document.id, "extraction", extraction.to_metadata()).await
+            .map_err(|e| format!("Failed to record extraction method: {}", e))?;
+        if let Some(source_url) = &job.source_url {
+            database.set_document_source_url(&document.id, source_url).await
+                .map_err(|e| format!("Failed to record source URL: {}", e))?;
+        }
+        if Self::queue_reextraction_if_needed(database, job, &document.id, &source_path, &extraction).await {
+            // The re-extraction job reads the download and deletes it when done
+            if let Some(download) = download.take() {
+                download.keep();
+            }
+        }
         drop(db_guard);
 
         self.update_job_progress(&job.id, 90).await?;
@@ -276,6 +380,7 @@ impl BackgroundProcessor {
         drop(db_guard);
 
         println!("✅ Completed processing job: {} -> Document: {}", job.id, document.id);
+        self.document_imported(&document.id).await;
 
         Ok(())
     }
@@ -292,8 +397,10 @@ impl BackgroundProcessor {
             .and_then(|id| id.as_str())
             .ok_or("No existing document ID found in job metadata")?;
 
-        // Get source file path
+        // Get source file path; temp files (a download handed over by the job that queued this
+        // one) are deleted when this job finishes
         let source_path = job.source_path.clone().ok_or("No source path provided")?;
+        let mut source_file = TempFileGuard::adopt(Path::new(&source_path));
 
         // Check if file exists
         if !std::path::Path::new(&source_path).exists() {
@@ -308,34 +415,20 @@ impl BackgroundProcessor {
             .and_then(|opts| serde_json::from_value(opts.clone()).ok())
             .unwrap_or_default();
 
-        let marker_options = processing_options;
+        let processing_options = self.attach_llm_key(processing_options).await;
+        let extract_options = ExtractOptions::from_marker_options(&processing_options, BACKGROUND_MARKER_TIMEOUT_SECS);
 
         self.update_job_progress(&job.id, 50).await?;
 
-        let content = if Self::is_pdf_file(&source_path, &job.original_filename) {
-            // Process PDF with Marker; fall back to basic extraction on failure
-            match self
-                .pdf_processor
-                .extract_with_marker(&source_path, marker_options)
+        let (content, extraction) = if Self::is_pdf_file(&source_path, &job.original_filename) {
+            // Walk the extraction chain (Marker first by default) until a method succeeds
+            let extraction = self
+                .extract_tracked(&job.id, &source_path, &extract_options)
                 .await
-            {
-                Ok(text) => text,
-                Err(e) => {
-                    eprintln!(
-                        "❌ Marker extraction failed, falling back to basic text extraction: {:?}",
-                        e
-                    );
-                    self
-                        .pdf_processor
-                        .extract_text_from_pdf(&source_path)
-                        .map_err(|e2| format!(
-                            "PDF processing failed (Marker and basic extraction): {:?}",
-                            e2
-                        ))?
-                }
-            }
+                .map_err(|e| format!("PDF processing failed: {}", e))?;
+            (extraction.content.clone(), Some(extraction))
         } else {
-            self.extract_non_pdf_markdown(&source_path).await?
+            (self.extract_non_pdf_markdown(&source_path).await?, None)
         };
 
         self.update_job_progress(&job.id, 70).await?;
@@ -361,6 +454,15 @@ impl BackgroundProcessor {
 
         database.update_document(existing_document_id, update_request).await
             .map_err(|e| format!("Failed to update document: {}", e))?;
+        if let Some(extraction) = &extraction {
+            database.set_document_metadata_field(existing_document_id, "extraction", extraction.to_metadata()).await
+                .map_err(|e| format!("Failed to record extraction method: {}", e))?;
+            if Self::queue_reextraction_if_needed(database, job, existing_document_id, &source_path, extraction).await {
+                if let Some(source_file) = source_file.take() {
+                    source_file.keep();
+                }
+            }
+        }
 
         self.update_job_progress(&job.id, 90).await?;
 
@@ -383,6 +485,263 @@ impl BackgroundProcessor {
         drop(db_guard);
 
         println!("✅ Completed content extraction job: {} -> Updated document: {}", job.id, existing_document_id);
+        let is_reextraction = job.metadata.as_ref()
+            .and_then(|meta| meta.get("reextraction"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if !is_reextraction {
+            self.document_imported(existing_document_id).await;
+        }
+
+        Ok(())
+    }
+
+    /// Generate practice questions and flashcards for a completed document
+    async fn process_practice_generation_job(&self, job: &ProcessingJob) -> Result<(), String> {
+        let document_id = job.metadata
+            .as_ref()
+            .and_then(|meta| meta.get("document_id"))
+            .and_then(|id| id.as_str())
+            .ok_or("No document ID found in job metadata")?
+            .to_string();
+
+        // Gather everything needed for the AI call, then release the lock while it runs
+        let (document, settings, api_key, connection, weak_concepts) = {
+            let db_guard = self.database.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+            let document = database.get_document(&document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or("Document not found")?;
+            let category_id = document.category_id.clone().ok_or("Document has no category")?;
+            let settings = database.get_practice_settings(&category_id).await
+                .map_err(|e| format!("Failed to get practice settings: {}", e))?
+                .ok_or("Practice generation is not configured for this category")?;
+            let provider_id = settings.provider_id.clone().ok_or("No AI provider configured for practice generation")?;
+            let api_key = database.get_api_key(&provider_id).await
+                .map_err(|e| format!("Failed to get API key: {}", e))?;
+            let connection: ProviderConnectionSettings = database.get_typed_setting(&AIProvider::settings_key(&provider_id)).await
+                .map_err(|e| format!("Failed to get provider settings: {}", e))?;
+            // Steer the new material towards what the student keeps getting wrong
+            let weak_concepts = database.get_weak_concepts(Some(&category_id), 10).await
+                .map_err(|e| format!("Failed to get weak concepts: {}", e))?
+                .into_iter()
+                .map(|weak| weak.concept.name)
+                .collect::<Vec<_>>();
+
+            (document, settings, api_key, connection, weak_concepts)
+        };
+
+        self.update_job_progress(&job.id, 30).await?;
+
+        let question_count = job.processing_options.as_ref()
+            .and_then(|opts| opts.get("question_count"))
+            .and_then(|v| v.as_i64())
+            .map(|v| v as i32)
+            .unwrap_or(settings.question_count);
+        let flashcard_count = job.processing_options.as_ref()
+            .and_then(|opts| opts.get("flashcard_count"))
+            .and_then(|v| v.as_i64())
+            .map(|v| v as i32)
+            .unwrap_or(settings.flashcard_count);
+
+        let provider = AIProvider {
+            id: settings.provider_id.clone().unwrap_or_default(),
+            r#type: settings.provider_type.clone().unwrap_or_else(|| "openai".to_string()),
+            base_url: settings.base_url.clone().unwrap_or_default(),
+            api_key: None,
+            organization: connection.organization,
+            api_version: connection.api_version,
+            extra_headers: connection.extra_headers,
+        };
+        let model = settings.model.clone().ok_or("No model configured for practice generation")?;
+
+        let mut request = ChatCompletionRequest {
+            messages: build_practice_messages(&document.title, &document.content, question_count, flashcard_count, &weak_concepts),
+            model: model.clone(),
+            temperature: Some(0.4),
+            max_tokens: Some(4096),
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stream: Some(false),
+        };
+
+        {
+            let db_guard = self.database.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+            redact_chat_request(database, &provider, &mut request).await?;
+        }
+
+        let response = chat_completion_for_provider(&provider, &model, &request, api_key).await?;
+        let text = response.choices.first()
+            .map(|choice| choice.message.content.clone())
+            .unwrap_or_default();
+        let practice = parse_practice_response(&text)?;
+
+        self.update_job_progress(&job.id, 70).await?;
+
+        let db_guard = self.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        let questions: Vec<_> = practice.questions.into_iter()
+            .take(question_count.max(0) as usize)
+            .collect();
+        let question_concepts: Vec<Vec<String>> = questions.iter().map(|q| q.concepts.clone()).collect();
+
+        let quiz = database.create_quiz(CreateQuizRequest {
+            title: format!("Practice: {}", document.title),
+            document_id: Some(document.id.clone()),
+            category_id: document.category_id.clone(),
+            questions: questions.into_iter()
+                .map(|q| CreateQuizQuestionRequest {
+                    question: q.question,
+                    answer: q.answer,
+                    explanation: q.explanation,
+                })
+                .collect(),
+            metadata: Some(serde_json::json!({
+                "generated_by": "practice_generation",
+                "job_id": job.id,
+                "model": model,
+            })),
+        }).await
+            .map_err(|e| format!("Failed to save quiz: {}", e))?;
+
+        // Questions come back in the order they were given
+        for (question, concepts) in quiz.questions.iter().zip(&question_concepts) {
+            database.link_concepts(ConceptItemType::QuizQuestion, &question.id, document.category_id.as_deref(), concepts).await
+                .map_err(|e| format!("Failed to link concepts: {}", e))?;
+        }
+
+        for card in practice.flashcards.into_iter().take(flashcard_count.max(0) as usize) {
+            let flashcard = database.create_flashcard(CreateFlashcardRequest {
+                front: card.front,
+                back: card.back,
+                source_document_id: Some(document.id.clone()),
+                source_text: None,
+                difficulty: None,
+                tags: vec!["practice".to_string()],
+                category_id: document.category_id.clone(),
+                card_type: None,
+                deck_id: None,
+                metadata: Some(serde_json::json!({ "generated_by": "practice_generation", "quiz_id": quiz.id })),
+            }).await
+                .map_err(|e| format!("Failed to save flashcard: {}", e))?;
+            database.link_concepts(ConceptItemType::Flashcard, &flashcard.id, document.category_id.as_deref(), &card.concepts).await
+                .map_err(|e| format!("Failed to link concepts: {}", e))?;
+        }
+
+        let update = ProcessingJobUpdate {
+            id: job.id.clone(),
+            status: Some("completed".to_string()),
+            progress: Some(100),
+            result_document_id: Some(document.id.clone()),
+            completed_at: Some(Utc::now()),
+            metadata: Some(serde_json::json!({
+                "document_id": document.id,
+                "quiz_id": quiz.id,
+            })),
+            ..Default::default()
+        };
+
+        database.update_processing_job(update).await
+            .map_err(|e| format!("Failed to update job completion: {}", e))?;
+
+        println!("✅ Generated practice quiz {} ({} questions) for document: {}", quiz.id, quiz.questions.len(), document.id);
+
+        Ok(())
+    }
+
+    /// Ask the model for topic tags and add them to the document
+    async fn process_auto_tagging_job(&self, job: &ProcessingJob) -> Result<(), String> {
+        let document_id = job.metadata
+            .as_ref()
+            .and_then(|meta| meta.get("document_id"))
+            .and_then(|id| id.as_str())
+            .ok_or("No document ID found in job metadata")?
+            .to_string();
+        let options = job.processing_options.as_ref().ok_or("No tagging options found in job")?;
+        let mut provider: AIProvider = options.get("provider")
+            .cloned()
+            .and_then(|provider| serde_json::from_value(provider).ok())
+            .ok_or("No AI provider configured for auto-tagging")?;
+        let model = options.get("model")
+            .and_then(|model| model.as_str())
+            .ok_or("No model configured for auto-tagging")?
+            .to_string();
+
+        // Gather everything needed for the AI call, then release the lock while it runs
+        let (document, existing_tags, api_key) = {
+            let db_guard = self.database.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+            let document = database.get_document(&document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or("Document not found")?;
+            let existing_tags = database.get_all_document_tags().await
+                .map_err(|e| format!("Failed to get library tags: {}", e))?;
+            let api_key = database.get_api_key(&provider.id).await
+                .map_err(|e| format!("Failed to get API key: {}", e))?;
+            let connection: ProviderConnectionSettings = database.get_typed_setting(&AIProvider::settings_key(&provider.id)).await
+                .map_err(|e| format!("Failed to get provider settings: {}", e))?;
+            provider.apply_connection_settings(connection);
+
+            (document, existing_tags, api_key)
+        };
+
+        self.update_job_progress(&job.id, 30).await?;
+
+        let mut request = ChatCompletionRequest {
+            messages: build_tagging_messages(&document.title, &document.content, &existing_tags),
+            model: model.clone(),
+            temperature: Some(0.2),
+            max_tokens: Some(200),
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stream: Some(false),
+        };
+
+        {
+            let db_guard = self.database.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+            redact_chat_request(database, &provider, &mut request).await?;
+        }
+
+        let response = chat_completion_for_provider(&provider, &model, &request, api_key).await?;
+        let text = response.choices.first()
+            .map(|choice| choice.message.content.clone())
+            .unwrap_or_default();
+        let tags = parse_tagging_response(&text)?;
+
+        let db_guard = self.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        let changes = BulkDocumentChanges {
+            add_tags: tags.clone(),
+            ..Default::default()
+        };
+        database.bulk_update_documents(std::slice::from_ref(&document.id), &changes).await
+            .map_err(|e| format!("Failed to save tags: {}", e))?;
+
+        let update = ProcessingJobUpdate {
+            id: job.id.clone(),
+            status: Some("completed".to_string()),
+            progress: Some(100),
+            result_document_id: Some(document.id.clone()),
+            completed_at: Some(Utc::now()),
+            metadata: Some(serde_json::json!({
+                "document_id": document.id,
+                "tags": tags,
+            })),
+            ..Default::default()
+        };
+
+        database.update_processing_job(update).await
+            .map_err(|e| format!("Failed to update job completion: {}", e))?;
+
+        println!("🏷️ Tagged document {} with {:?}", document.id, tags);
 
         Ok(())
     }
@@ -403,118 +762,176 @@ impl BackgroundProcessor {
     }
 
     async fn extract_non_pdf_markdown(&self, source_path: &str) -> Result<String, String> {
-        let extension = Path::new(source_path)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or_default()
-            .to_lowercase();
-
-        if matches!(extension.as_str(), "txt" | "md" | "markdown" | "csv" | "tsv") {
-            let content = std::fs::read_to_string(source_path)
-                .map_err(|e| format!("Failed to read text document: {}", e))?;
-            return Ok(content.replace("\r\n", "\n"));
+        // HTML, images and plain text have dedicated converters; everything else goes to MarkItDown
+        if ImportFormat::from_path(Path::new(source_path)).is_some() {
+            return importers::convert_file(Path::new(source_path)).await.map(|imported| imported.content);
         }
 
-        let markitdown_command = Self::resolve_markitdown_command();
-        let output = tokio::process::Command::new(&markitdown_command)
-            .arg(source_path)
-            .output()
+        self.pdf_processor
+            .extract_with_markitdown(source_path)
             .await
-            .map_err(|e| {
-                let details = format!(
-                    "Failed to run MarkItDown converter at '{}': {}",
-                    markitdown_command.display(),
-                    e
-                );
-                if e.kind() == std::io::ErrorKind::NotFound {
-                    format!(
-                        "{}. Install it with ./scripts/setup_markitdown.sh or set STELLAR_MARKITDOWN_BIN.",
-                        details
-                    )
-                } else {
-                    details
-                }
-            })?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let details = if !stderr.trim().is_empty() {
-                stderr.trim().to_string()
-            } else if !stdout.trim().is_empty() {
-                stdout.trim().to_string()
-            } else {
-                "no error output".to_string()
-            };
-
-            return Err(format!(
-                "MarkItDown conversion failed for '{}': {}",
-                source_path, details
-            ));
-        }
+            .map_err(|e| e.to_string())
+    }
 
-        let markdown = String::from_utf8(output.stdout)
-            .map_err(|e| format!("MarkItDown produced non-UTF8 output: {}", e))?;
+    /// Save a page or text selection sent by the browser extension as a document. Pages are
+    /// clipped to their article text and recorded under their URL; selections become notes
+    /// that only mention the page, so the page itself can still be imported later.
+    async fn process_web_clip_job(&self, job: &ProcessingJob) -> Result<(), String> {
+        let source_path = job.source_path.clone().ok_or("No clipped content provided")?;
+        let page_url = job.metadata
+            .as_ref()
+            .and_then(|meta| meta.get("page_url"))
+            .and_then(|url| url.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let is_selection = job.metadata
+            .as_ref()
+            .and_then(|meta| meta.get("selection"))
+            .and_then(|selection| selection.as_bool())
+            .unwrap_or(false);
 
-        if markdown.trim().is_empty() {
-            return Err(format!(
-                "MarkItDown returned empty output for '{}'.",
-                source_path
-            ));
-        }
+        let raw = tokio::fs::read_to_string(&source_path).await
+            .map_err(|e| format!("Failed to read clipped content: {}", e))?;
 
-        Ok(markdown.replace("\r\n", "\n"))
-    }
+        self.update_job_progress(&job.id, 30).await?;
 
-    fn resolve_markitdown_command() -> PathBuf {
-        if let Ok(explicit_command) = std::env::var("STELLAR_MARKITDOWN_BIN") {
-            let explicit_path = PathBuf::from(explicit_command);
-            if explicit_path.exists() {
-                return explicit_path;
+        let (page_title, content, stored_filename) = if is_selection {
+            let text = raw.trim();
+            if text.is_empty() {
+                return Err("The selection is empty".to_string());
             }
-        }
+            let content = if page_url.is_empty() { text.to_string() } else { format!("{}\n\nSource: {}", text, page_url) };
+            (None, content, None)
+        } else {
+            let article = importers::html::extract_article(&raw);
+            if article.markdown.trim().is_empty() {
+                return Err(format!("{} is a web page with no readable article text", page_url));
+            }
+            // The page itself is kept in storage like any other imported original
+            let stored_filename = crate::commands::pdf::generate_pdf_filename("page.html");
+            let stored_path = crate::commands::pdf::get_pdf_storage_dir()?.join(&stored_filename);
+            crate::storage::ensure_space_for_copy(Path::new(&source_path), &stored_path)?;
+            std::fs::copy(&source_path, &stored_path)
+                .map_err(|e| format!("Failed to save page to storage: {}", e))?;
+            (article.title, article.markdown, Some(stored_filename))
+        };
 
-        let candidates = [
-            PathBuf::from("markitdown_env/bin/markitdown"),
-            PathBuf::from("../markitdown_env/bin/markitdown"),
-            PathBuf::from("markitdown_env/Scripts/markitdown.exe"),
-            PathBuf::from("../markitdown_env/Scripts/markitdown.exe"),
-        ];
+        self.update_job_progress(&job.id, 60).await?;
+
+        let title = job.title.clone()
+            .or(page_title)
+            .unwrap_or_else(|| if page_url.is_empty() { job.original_filename.clone() } else { page_url.clone() });
+        let request = CreateDocumentRequest {
+            title,
+            content,
+            content_hash: None,
+            file_path: stored_filename,
+            doc_type: if is_selection { "note".to_string() } else { "markdown".to_string() },
+            tags: job.tags.clone(),
+            status: Some("ready".to_string()),
+            category_id: job.category_id.clone(),
+        };
 
-        for candidate in candidates {
-            if candidate.exists() {
-                return candidate;
+        let db_guard = self.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+        let document = database.create_document(request).await
+            .map_err(|e| format!("Failed to create document: {}", e))?;
+        database.set_document_metadata_field(&document.id, "import", serde_json::json!({
+            "format": if is_selection { ImportFormat::Text } else { ImportFormat::Html },
+            "method": if is_selection { "browser-selection" } else { "browser-extension" },
+            "original_filename": job.original_filename,
+            "details": { "source_url": page_url },
+            "imported_at": Utc::now().to_rfc3339(),
+        })).await
+            .map_err(|e| format!("Failed to record import details: {}", e))?;
+        match (&job.source_url, is_selection) {
+            (Some(source_url), false) => {
+                database.set_document_source_url(&document.id, source_url).await
+                    .map_err(|e| format!("Failed to record source URL: {}", e))?;
+            }
+            _ if !page_url.is_empty() => {
+                database.set_document_metadata_field(&document.id, "capture_source_url", serde_json::json!(page_url)).await
+                    .map_err(|e| format!("Failed to record source URL: {}", e))?;
             }
+            _ => {}
         }
+        drop(db_guard);
 
-        PathBuf::from("markitdown")
-    }
+        self.update_job_progress(&job.id, 90).await?;
+        self.process_embeddings(&document.id).await?;
 
-    /// Download file from URL
-    async fn download_file_from_url(&self, url: &str) -> Result<String, String> {
-        let client = reqwest::Client::new();
-        let response = client.get(url).send().await
-            .map_err(|e| format!("Failed to download file: {}", e))?;
+        let db_guard = self.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+        let update = ProcessingJobUpdate {
+            id: job.id.clone(),
+            status: Some("completed".to_string()),
+            progress: Some(100),
+            result_document_id: Some(document.id.clone()),
+            completed_at: Some(Utc::now()),
+            ..Default::default()
+        };
+        database.update_processing_job(update).await
+            .map_err(|e| format!("Failed to update job completion: {}", e))?;
+        drop(db_guard);
+
+        // Kept until now so a failed clip can be retried; the startup sweep removes those
+        drop(TempFileGuard::adopt(Path::new(&source_path)));
 
-        if !response.status().is_success() {
-            return Err(format!("HTTP error: {}", response.status()));
+        println!("✅ Clipped {} -> Document: {}", if page_url.is_empty() { &job.original_filename } else { &page_url }, document.id);
+        self.document_imported(&document.id).await;
+        Ok(())
+    }
+
+    // Background imports are only done once content is extracted, so automation scripts fire
+    // here rather than when the placeholder document was created
+    async fn document_imported(&self, document_id: &str) {
+        let document = {
+            let db_guard = self.database.lock().await;
+            let Some(database) = db_guard.as_ref() else {
+                return;
+            };
+            database.get_document(document_id).await.ok().flatten()
+        };
+        if let Some(document) = document {
+            scripting::document_imported(self.database.clone(), self.vector_service.clone(), &document);
+            DocumentService::new(self.database.clone(), self.vector_service.clone()).generate_cover_in_background(document.id);
         }
+    }
 
-        let bytes = response.bytes().await
-            .map_err(|e| format!("Failed to read response: {}", e))?;
+    /// Download the PDF behind a URL into a temp file, or return the page when it leads to an
+    /// article rather than a PDF
+    async fn download_file_from_url(&self, url: &str) -> Result<UrlDownload, String> {
+        let url = match crate::importers::url::resolve_url(url).await? {
+            crate::importers::url::ResolvedUrl::Pdf { url } => url,
+            crate::importers::url::ResolvedUrl::WebPage { url, html } => {
+                return Ok(UrlDownload::WebPage { url, html });
+            }
+        };
 
-        // Save to temporary file
-        let temp_dir = std::env::temp_dir().join("stellar_downloads");
-        std::fs::create_dir_all(&temp_dir)
-            .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+        let filename = match url.split('/').last().filter(|name| !name.is_empty()) {
+            Some(name) if name.ends_with(".pdf") => name.to_string(),
+            Some(name) => format!("{}.pdf", name),
+            None => "download.pdf".to_string(),
+        };
+        let file = TempFileGuard::new(TempKind::Downloads, &filename)?;
 
-        let filename = url.split('/').last().unwrap_or("download.pdf");
-        let file_path = temp_dir.join(filename);
+        crate::downloads::download_to_file(&url, file.path(), crate::downloads::DEFAULT_MAX_DOWNLOAD_BYTES, |_| {}).await?;
 
-        std::fs::write(&file_path, bytes)
-            .map_err(|e| format!("Failed to save file: {}", e))?;
+        Ok(UrlDownload::Pdf(file))
+    }
 
-        Ok(file_path.to_string_lossy().to_string())
+    /// Finish a URL job whose link turned out to be an article through the web clipper, so the
+    /// job still ends with a document
+    async fn clip_downloaded_page(&self, job: &ProcessingJob, page_url: &str, html: &str) -> Result<(), String> {
+        let page = TempFileGuard::new(TempKind::Clips, "clip.html")?;
+        crate::storage::ensure_space(page.path(), html.len() as u64)?;
+        std::fs::write(page.path(), html)
+            .map_err(|e| format!("Failed to save downloaded page: {}", e))?;
+
+        let mut clip = job.clone();
+        clip.source_path = Some(page.path().to_string_lossy().to_string());
+        clip.metadata = Some(serde_json::json!({ "page_url": page_url, "selection": false }));
+        self.process_web_clip_job(&clip).await
     }
 
     /// Process embeddings for a document
@@ -538,6 +955,141 @@ impl BackgroundProcessor {
         Ok(())
     }
 
+    /// Run the extraction chain, mapping marker_single's progress onto the 50-70% range of the job
+    async fn extract_tracked(
+        &self,
+        job_id: &str,
+        source_path: &str,
+        options: &ExtractOptions,
+    ) -> Result<ExtractionResult, PdfError> {
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel::<MarkerProgress>();
+        let on_progress: MarkerProgressCallback = Arc::new(move |progress| {
+            let _ = progress_tx.send(progress);
+        });
+
+        // Marker runs several stages that each count to 100%, so only ever move forward
+        let database = self.database.clone();
+        let tracked_job_id = job_id.to_string();
+        let progress_task = tokio::spawn(async move {
+            let mut last_progress = 50;
+            while let Some(progress) = progress_rx.recv().await {
+                let job_progress = 50 + (progress.percent as i32 * 20) / 100;
+                if job_progress <= last_progress {
+                    continue;
+                }
+                last_progress = job_progress;
+
+                let db_guard = database.lock().await;
+                if let Some(db) = db_guard.as_ref() {
+                    let update = ProcessingJobUpdate {
+                        id: tracked_job_id.clone(),
+                        progress: Some(job_progress),
+                        ..Default::default()
+                    };
+                    if let Err(e) = db.update_processing_job(update).await {
+                        eprintln!("⚠️ Failed to record marker progress for job {}: {}", tracked_job_id, e);
+                    }
+                }
+            }
+        });
+
+        let result = self
+            .pdf_processor
+            .extract_with_options_progress(source_path, options, Some(on_progress))
+            .await;
+
+        // The callback (and its sender) is dropped with the extraction, which ends the task
+        let _ = progress_task.await;
+        result
+    }
+
+    /// Queue a stronger re-extraction (forced OCR, plus Marker's LLM mode when a key is stored)
+    /// for documents whose extraction scored below the quality threshold. Re-extraction jobs
+    /// never queue another one. Returns whether a job was queued.
+    async fn queue_reextraction_if_needed(
+        database: &Database,
+        job: &ProcessingJob,
+        document_id: &str,
+        source_path: &str,
+        extraction: &ExtractionResult,
+    ) -> bool {
+        if extraction.quality.is_acceptable() {
+            return false;
+        }
+
+        let is_reextraction = job.metadata
+            .as_ref()
+            .and_then(|meta| meta.get("reextraction"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if is_reextraction {
+            println!("⚠️ Re-extraction of document {} still scored {:.2}; keeping result", document_id, extraction.quality.score);
+            return false;
+        }
+
+        let mut available_service = None;
+        for service in [MarkerLlmService::Gemini, MarkerLlmService::OpenAI] {
+            if database.get_api_key(service.api_key_provider_id()).await.ok().flatten().is_some() {
+                available_service = Some(service);
+                break;
+            }
+        }
+
+        let options = MarkerOptions {
+            force_ocr: true,
+            preferred_methods: Some(vec![ExtractionMethod::Marker]),
+            use_llm: available_service.is_some(),
+            llm_service: available_service.unwrap_or_default(),
+            // Poor text can still be enough to tell the language, which helps OCR
+            ocr_language: crate::language::detect_language(&extraction.content),
+            ..Default::default()
+        };
+
+        let request = CreateProcessingJobRequest {
+            job_type: "pdf_content_extraction".to_string(),
+            source_type: "file".to_string(),
+            source_path: Some(source_path.to_string()),
+            original_filename: job.original_filename.clone(),
+            title: job.title.clone(),
+            tags: job.tags.clone(),
+            category_id: job.category_id.clone(),
+            processing_options: Some(serde_json::to_value(options).unwrap_or_default()),
+            metadata: Some(serde_json::json!({
+                "existing_document_id": document_id,
+                "reextraction": true,
+                "previous_method": extraction.method,
+                "previous_quality": extraction.quality.score,
+            })),
+            source_url: None,
+        };
+
+        match database.create_processing_job(request).await {
+            Ok(new_job) => {
+                println!(
+                    "🔁 Extraction quality {:.2} below {:.2} for document {}; queued re-extraction job {}",
+                    extraction.quality.score, EXTRACTION_QUALITY_THRESHOLD, document_id, new_job.id
+                );
+                true
+            }
+            Err(e) => {
+                eprintln!("⚠️ Failed to queue re-extraction for document {}: {}", document_id, e);
+                false
+            }
+        }
+    }
+
+    /// Look up the API key for Marker's LLM mode, which is never stored on the job itself
+    async fn attach_llm_key(&self, options: MarkerOptions) -> MarkerOptions {
+        if !options.use_llm {
+            return options;
+        }
+        let db_guard = self.database.lock().await;
+        match db_guard.as_ref() {
+            Some(database) => options.with_llm_key(database).await,
+            None => options,
+        }
+    }
+
     /// Update job progress
     async fn update_job_progress(&self, job_id: &str, progress: i32) -> Result<(), String> {
         let update = ProcessingJobUpdate {
@@ -583,6 +1135,7 @@ impl Clone for BackgroundProcessor {
             vector_service: Arc::clone(&self.vector_service),
             pdf_processor,
             running: Arc::clone(&self.running),
+            current_job: Arc::clone(&self.current_job),
         }
     }
 }
@@ -616,6 +1169,10 @@ pub async fn create_pdf_processing_job(
     let options_json = processing_options.map(|opts| 
         serde_json::to_value(opts).unwrap_or_default()
     );
+    let source_url = match (source_type, source_path.as_deref()) {
+        ("url", Some(url)) => Some(crate::importers::url::normalize_source_url(url)),
+        _ => None,
+    };
 
     let request = CreateProcessingJobRequest {
         job_type: "pdf_processing".to_string(),
@@ -627,8 +1184,56 @@ pub async fn create_pdf_processing_job(
         category_id,
         processing_options: options_json,
         metadata: None,
+        source_url,
     };
 
     database.create_processing_job(request).await
         .map_err(|e| format!("Failed to create processing job: {}", e))
 } 
+
+/// Helper function to queue a page or selection sent by the browser extension. The content is
+/// written to a temporary file so large pages stay out of the jobs table.
+pub async fn create_web_clip_job(
+    database: &Database,
+    page_url: &str,
+    content: &str,
+    is_selection: bool,
+    title: Option<String>,
+    tags: Vec<String>,
+) -> Result<ProcessingJob, String> {
+    let extension = if is_selection { "txt" } else { "html" };
+    let source_file = TempFileGuard::new(TempKind::Clips, &format!("clip.{}", extension))?;
+    crate::storage::ensure_space(source_file.path(), content.len() as u64)?;
+    std::fs::write(source_file.path(), content)
+        .map_err(|e| format!("Failed to save clipped content: {}", e))?;
+
+    let original_filename = reqwest::Url::parse(page_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| format!("clip.{}", extension));
+    // Selections don't count as an import of the page; see process_web_clip_job
+    let source_url = (!is_selection && !page_url.is_empty())
+        .then(|| crate::importers::url::normalize_source_url(page_url));
+
+    let request = CreateProcessingJobRequest {
+        job_type: "web_clip".to_string(),
+        source_type: "data".to_string(),
+        source_path: Some(source_file.path().to_string_lossy().to_string()),
+        original_filename,
+        title,
+        tags,
+        category_id: None,
+        processing_options: None,
+        metadata: Some(serde_json::json!({
+            "page_url": page_url,
+            "selection": is_selection,
+        })),
+        source_url,
+    };
+
+    let job = database.create_processing_job(request).await
+        .map_err(|e| format!("Failed to create processing job: {}", e))?;
+    // The web clip job deletes the file once the page is imported
+    source_file.keep();
+    Ok(job)
+}
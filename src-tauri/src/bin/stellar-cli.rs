@@ -0,0 +1,310 @@
+//! Headless command line interface over the same library as the app, for scripting bulk imports
+//! and cron backups, including against a copy of the data dir on a server or NAS.
+//!
+//! Run `stellar-cli help` for usage. Close the app first when pointing the CLI at the data dir it
+//! is using.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use tokio::sync::Mutex;
+
+use stellar_lib::database::{Database, MaintenanceSettings};
+use stellar_lib::embeddings::{EmbeddingConfig, EmbeddingProvider, VectorService};
+use stellar_lib::maintenance::{backup_database, MAINTENANCE_SETTINGS_KEY};
+use stellar_lib::{paths, profiles};
+use stellar_lib::{ExtractionMethod, Services};
+
+const USAGE: &str = "\
+Usage: stellar-cli [--data-dir DIR] [--profile ID] <command> [options]
+
+Commands:
+  import <file>... [--tags a,b] [--category ID] [--embed]
+                              Import files as documents
+  search <query> [--limit N] [--math]
+                              Search document titles, content and tags; --math
+                              also matches equations written in any notation
+  export <dir> [--category ID]
+                              Write documents to <dir> as markdown files
+  export <file> --deck ID     Write a flashcard deck as a .stellardeck package
+  backup [--out FILE]         Back up the database (to the backups dir by default)
+  compress                    Compress the content of large documents imported
+                              before compression was added, then vacuum
+  review-stats                Show flashcard review statistics
+  reprocess <id>... [--method marker|markitdown|enhanced|basic] [--embed]
+                              Re-run extraction on documents' stored PDFs
+
+Options:
+  --data-dir DIR              Use DIR instead of ~/stellar_data (or set STELLAR_DATA_DIR)
+  --profile ID                Work in this profile instead of the active one (or set STELLAR_PROFILE)
+  --embed                     Load the embedding model and (re)build embeddings
+";
+
+/// Command line split into positional arguments and `--flag [value]` options
+struct Args {
+    positional: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+// Options that never take a value
+const SWITCHES: &[&str] = &["embed", "help", "math"];
+
+impl Args {
+    fn parse(raw: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut raw = raw.peekable();
+
+        while let Some(arg) = raw.next() {
+            match arg.strip_prefix("--") {
+                Some(name) if SWITCHES.contains(&name) => options.push((name.to_string(), None)),
+                Some(name) => {
+                    let (name, value) = match name.split_once('=') {
+                        Some((name, value)) => (name.to_string(), value.to_string()),
+                        None => {
+                            let value = raw.next().ok_or_else(|| format!("--{} needs a value", name))?;
+                            (name.to_string(), value)
+                        }
+                    };
+                    options.push((name, Some(value)));
+                }
+                None => positional.push(arg),
+            }
+        }
+
+        Ok(Self { positional, options })
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let Some(command) = args.positional.first().cloned() else {
+        eprint!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    if command == "help" || args.flag("help") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    if let Some(dir) = args.value("data-dir") {
+        std::env::set_var(paths::DATA_DIR_ENV, dir);
+    }
+    if let Some(profile) = args.value("profile") {
+        std::env::set_var(profiles::PROFILE_ENV, profile);
+    }
+
+    match run(&command, &args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: &str, args: &Args) -> Result<(), String> {
+    let operands = &args.positional[1..];
+    let profile = profiles::active_profile_id();
+    if !profiles::list_profiles()?.iter().any(|info| info.profile.id == profile) {
+        return Err(format!("Profile not found: {}", profile));
+    }
+
+    let database = Database::new(&paths::database_url()?).await
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+
+    // Loading the embedding model is slow, so it only happens when asked for
+    let vector_service = if args.flag("embed") {
+        Some(open_vector_service().await?)
+    } else {
+        None
+    };
+    let database = Arc::new(Mutex::new(Some(database)));
+    let services = Services::new(database.clone(), Arc::new(Mutex::new(vector_service)));
+
+    match command {
+        "import" => import(&services, operands, args).await,
+        "search" => search(&services, operands, args).await,
+        "export" => export(&services, operands, args).await,
+        "backup" => backup(&database, args).await,
+        "compress" => compress(&database).await,
+        "review-stats" => review_stats(&services).await,
+        "reprocess" => reprocess(&services, operands, args).await,
+        _ => Err(format!("Unknown command: {}\n\n{}", command, USAGE)),
+    }
+}
+
+// Same embedding setup the app starts with
+async fn open_vector_service() -> Result<VectorService, String> {
+    let config = EmbeddingConfig {
+        provider: EmbeddingProvider::RustBert,
+        model: "default".to_string(),
+        api_key: None,
+        base_url: None,
+        dimensions: 384,
+    };
+    VectorService::new(&paths::database_path()?.to_string_lossy(), config).await
+        .map_err(|e| format!("Failed to initialize vector service: {}", e))
+}
+
+async fn import(services: &Services, files: &[String], args: &Args) -> Result<(), String> {
+    if files.is_empty() {
+        return Err("import needs at least one file".to_string());
+    }
+    let tags: Vec<String> = args.value("tags")
+        .map(|tags| tags.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect())
+        .unwrap_or_default();
+    let category_id = args.value("category").map(|id| id.to_string());
+
+    let mut failed = 0;
+    for file in files {
+        let document = match services.documents.import_file(Path::new(file), None, tags.clone(), category_id.clone()).await {
+            Ok(document) => document,
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if let Err(e) = services.documents.embed_document(&document).await {
+            eprintln!("{}: imported, but embedding failed: {}", file, e);
+        }
+        println!("{}\t{}", document.id, document.title);
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!("{} of {} files failed to import", failed, files.len())),
+    }
+}
+
+async fn search(services: &Services, operands: &[String], args: &Args) -> Result<(), String> {
+    let query = operands.join(" ");
+    if query.trim().is_empty() {
+        return Err("search needs a query".to_string());
+    }
+    let limit = args.value("limit")
+        .map(|limit| limit.parse::<i64>().map_err(|_| format!("Invalid --limit: {}", limit)))
+        .transpose()?;
+
+    for document in services.documents.search_documents(&query, limit, args.flag("math")).await? {
+        println!("{}\t{}\t{}", document.id, document.doc_type, document.title);
+    }
+    Ok(())
+}
+
+async fn export(services: &Services, operands: &[String], args: &Args) -> Result<(), String> {
+    let [target] = operands else {
+        return Err("export needs one output path".to_string());
+    };
+
+    if let Some(deck_id) = args.value("deck") {
+        let output = services.flashcards.export_deck_package(deck_id, target, None).await?;
+        println!("{}", output.display());
+        return Ok(());
+    }
+
+    let written = services.documents.export_markdown(Path::new(target), args.value("category")).await?;
+    println!("Exported {} documents to {}", written.len(), target);
+    Ok(())
+}
+
+async fn backup(database: &Mutex<Option<Database>>, args: &Args) -> Result<(), String> {
+    let db_guard = database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    match args.value("out") {
+        Some(out) => {
+            let out = PathBuf::from(out);
+            if out.exists() {
+                return Err(format!("{} already exists", out.display()));
+            }
+            database.backup_to(&out).await
+                .map_err(|e| format!("Backup failed: {}", e))?;
+            println!("Backed up to {}", out.display());
+        }
+        None => {
+            let settings: MaintenanceSettings = database.get_typed_setting(MAINTENANCE_SETTINGS_KEY).await
+                .map_err(|e| format!("Failed to load maintenance settings: {}", e))?;
+            println!("{}", backup_database(database, settings.backup_retention).await?);
+        }
+    }
+    Ok(())
+}
+
+async fn compress(database: &Mutex<Option<Database>>) -> Result<(), String> {
+    let db_guard = database.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let result = database.compress_document_contents().await
+        .map_err(|e| format!("Compression failed: {}", e))?;
+    println!(
+        "Compressed {} documents: {:.1} MB -> {:.1} MB",
+        result.documents_compressed,
+        result.bytes_before as f64 / (1024.0 * 1024.0),
+        result.bytes_after as f64 / (1024.0 * 1024.0)
+    );
+    Ok(())
+}
+
+async fn review_stats(services: &Services) -> Result<(), String> {
+    let stats = services.flashcards.get_stats().await?;
+
+    println!("Cards:          {}", stats.total_cards);
+    println!("  due:          {}", stats.cards_due);
+    println!("  new:          {}", stats.cards_new);
+    println!("  learning:     {}", stats.cards_learning);
+    println!("  mastered:     {}", stats.cards_mastered);
+    println!("Reviews:        {} ({} today)", stats.total_reviews, stats.daily_review_count);
+    println!("Success rate:   {:.0}%", stats.average_success_rate * 100.0);
+    println!("Study streak:   {} days", stats.study_streak);
+    Ok(())
+}
+
+async fn reprocess(services: &Services, ids: &[String], args: &Args) -> Result<(), String> {
+    if ids.is_empty() {
+        return Err("reprocess needs at least one document id".to_string());
+    }
+    let method = args.value("method")
+        .map(|method| serde_json::from_value::<ExtractionMethod>(serde_json::json!(method))
+            .map_err(|_| format!("Unknown extraction method: {}", method)))
+        .transpose()?;
+
+    let mut failed = 0;
+    for id in ids {
+        match services.documents.reprocess_document(id, method, None).await {
+            Ok(result) => println!(
+                "{}\t{}\tquality {:.2}\t{} highlights orphaned",
+                id, result.method, result.quality_score, result.highlights_orphaned
+            ),
+            Err(e) => {
+                eprintln!("{}: {}", id, e);
+                failed += 1;
+            }
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!("{} of {} documents failed to reprocess", failed, ids.len())),
+    }
+}
@@ -0,0 +1,141 @@
+//! Opt-in clipboard watcher. While enabled it polls the system clipboard and emits a
+//! `clipboard-capture` event when a URL or a long block of text is copied, so the frontend can
+//! offer to import it as a document or save it as a quick capture note.
+
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::task::AbortHandle;
+use tokio::time;
+
+use crate::commands::database::DatabaseState;
+use crate::database::ClipboardWatcherSettings;
+
+pub const CLIPBOARD_WATCHER_SETTINGS_KEY: &str = "clipboard_watcher";
+pub const CLIPBOARD_CAPTURE_EVENT: &str = "clipboard-capture";
+
+const POLL_INTERVAL_MILLIS: u64 = 1000;
+const PREVIEW_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardCaptureKind {
+    Url,
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipboardCapture {
+    pub kind: ClipboardCaptureKind,
+    pub text: String,
+    pub preview: String,
+    pub char_count: usize,
+    pub existing_document_id: Option<String>, // URL was already imported as this document
+}
+
+/// Managed state holding the running poll task, if any
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    task: StdMutex<Option<AbortHandle>>,
+}
+
+impl ClipboardWatcher {
+    pub fn is_running(&self) -> bool {
+        self.task.lock().map(|task| task.is_some()).unwrap_or(false)
+    }
+
+    /// Start watching with `settings`, replacing a watcher that is already running
+    pub fn start(&self, app: AppHandle, settings: ClipboardWatcherSettings) {
+        self.stop();
+        println!("📋 Starting clipboard watcher...");
+
+        let handle = tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_millis(POLL_INTERVAL_MILLIS));
+            // Whatever is on the clipboard when watching starts isn't offered
+            let mut last_seen = app.clipboard().read_text().ok();
+
+            loop {
+                interval.tick().await;
+
+                // Images and other non-text contents read as errors
+                let Ok(text) = app.clipboard().read_text() else {
+                    continue;
+                };
+                if last_seen.as_deref() == Some(text.as_str()) {
+                    continue;
+                }
+                last_seen = Some(text.clone());
+
+                let Some(mut capture) = classify_clipboard_text(&text, &settings) else {
+                    continue;
+                };
+                if matches!(capture.kind, ClipboardCaptureKind::Url) {
+                    capture.existing_document_id = find_imported_url(&app, &capture.text).await;
+                }
+                let _ = app.emit(CLIPBOARD_CAPTURE_EVENT, capture);
+            }
+        });
+
+        if let Ok(mut task) = self.task.lock() {
+            *task = Some(handle.abort_handle());
+        }
+    }
+
+    pub fn stop(&self) {
+        if let Some(handle) = self.task.lock().ok().and_then(|mut task| task.take()) {
+            handle.abort();
+            println!("📋 Stopped clipboard watcher");
+        }
+    }
+}
+
+/// A copied URL or a passage long enough to be worth keeping; anything else is ignored
+fn classify_clipboard_text(text: &str, settings: &ClipboardWatcherSettings) -> Option<ClipboardCapture> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+
+    let is_url = !trimmed.contains(char::is_whitespace)
+        && reqwest::Url::parse(trimmed).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+    let char_count = trimmed.chars().count();
+
+    let kind = if is_url {
+        if !settings.detect_urls {
+            return None;
+        }
+        ClipboardCaptureKind::Url
+    } else if settings.min_text_chars > 0 && char_count >= settings.min_text_chars {
+        ClipboardCaptureKind::Text
+    } else {
+        return None;
+    };
+
+    let mut preview: String = trimmed.chars().take(PREVIEW_CHARS).collect();
+    if char_count > PREVIEW_CHARS {
+        preview.push('…');
+    }
+
+    Some(ClipboardCapture {
+        kind,
+        text: trimmed.to_string(),
+        preview,
+        char_count,
+        existing_document_id: None,
+    })
+}
+
+async fn find_imported_url(app: &AppHandle, url: &str) -> Option<String> {
+    let state = app.state::<DatabaseState>();
+    let db_guard = state.lock().await;
+    let database = db_guard.as_ref()?;
+    match database.find_document_by_source_url(url).await {
+        Ok(document) => document.map(|document| document.id),
+        Err(e) => {
+            eprintln!("⚠️ Failed to look up copied URL: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Code blocks in document text: finding fenced blocks in markdown content and guessing the
+//! language of untagged ones, so textbook listings can be indexed as code snippets.
+
+// Shorter blocks are inline fragments rather than listings worth indexing
+const MIN_SNIPPET_CHARS: usize = 8;
+// Markers a language needs before it's considered; ties between languages are left untagged
+const MIN_LANGUAGE_SCORE: usize = 1;
+
+/// A fenced code block, with char offsets of the whole block (fences included) in the content
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub content: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Telltale fragments per language, each worth a point when found in a block
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    ("rust", &["fn ", "let mut ", "impl ", "pub fn", "println!", "-> ", "::", "&mut ", "match ", "use std::", "Vec<", "Option<"]),
+    ("python", &["def ", "import ", "elif ", "self.", "print(", "None", "True", "lambda ", "__init__", "from "]),
+    ("javascript", &["function ", "const ", "=> ", "console.log", "let ", "var ", "===", "document.", "require("]),
+    ("typescript", &["interface ", ": string", ": number", ": boolean", "export type ", "implements ", "readonly "]),
+    ("java", &["public class ", "public static void main", "System.out", "private ", "new ", "extends ", "@Override", "String[]"]),
+    ("c", &["#include <stdio.h>", "#include", "printf(", "int main(", "malloc(", "->", "NULL", "sizeof("]),
+    ("cpp", &["std::", "#include <iostream>", "cout <<", "template<", "template <", "nullptr", "namespace "]),
+    ("go", &["func ", "package ", ":= ", "fmt.", "go func", "chan ", "defer "]),
+    ("sql", &["SELECT ", "FROM ", "WHERE ", "INSERT INTO", "CREATE TABLE", "JOIN ", "GROUP BY"]),
+    ("bash", &["#!/bin/", "echo ", "fi\n", "done\n", "$(", "sudo ", "export ", "then\n"]),
+    ("html", &["<div", "</", "<html", "<body", "<p>", "href="]),
+    ("haskell", &[" :: ", "where\n", "module ", "data ", "instance ", "<- ", "let "]),
+];
+
+/// Canonical name for a fence info string such as "py", "C++" or "shell"
+pub fn normalize_language(tag: &str) -> Option<String> {
+    let tag = tag.split(|c: char| c.is_whitespace() || c == '{' || c == ',').next()?.trim().to_lowercase();
+    let language = match tag.as_str() {
+        "" | "text" | "plain" | "plaintext" | "txt" | "output" => return None,
+        "py" | "python3" => "python",
+        "js" | "jsx" | "node" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "rs" => "rust",
+        "c++" | "cc" | "cxx" | "hpp" => "cpp",
+        "h" => "c",
+        "golang" => "go",
+        "sh" | "shell" | "zsh" | "console" => "bash",
+        "hs" => "haskell",
+        "htm" | "xml" => "html",
+        "postgres" | "postgresql" | "mysql" | "sqlite" => "sql",
+        other => other,
+    };
+    Some(language.to_string())
+}
+
+/// Markers found for the language the code looks most like, 0 for prose
+pub fn language_score(code: &str) -> usize {
+    LANGUAGE_MARKERS.iter()
+        .map(|(_, markers)| markers.iter().filter(|marker| code.contains(*marker)).count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Best guess at the language of a code block, None when nothing stands out
+pub fn guess_language(code: &str) -> Option<String> {
+    let mut scores: Vec<(&str, usize)> = LANGUAGE_MARKERS.iter()
+        .map(|(language, markers)| (*language, markers.iter().filter(|marker| code.contains(*marker)).count()))
+        .filter(|(_, score)| *score >= MIN_LANGUAGE_SCORE)
+        .collect();
+    scores.sort_by(|a, b| b.1.cmp(&a.1));
+
+    match scores.as_slice() {
+        // C++ listings also match most C markers; the C++-only ones decide
+        [("c", _), ..] if code.contains("std::") || code.contains("cout") => Some("cpp".to_string()),
+        [(first, score), (second, runner_up), ..] if score == runner_up => match (*first, *second) {
+            // TypeScript is JavaScript with types, so it shares most of its markers
+            ("typescript", "javascript") | ("javascript", "typescript") => Some("typescript".to_string()),
+            _ => None,
+        },
+        [(language, _), ..] => Some(language.to_string()),
+        [] => None,
+    }
+}
+
+/// Fenced (``` or ~~~) blocks in markdown content. Blocks separated only by blank lines and
+/// in the same language are merged, since PDF extraction fences listings line by line.
+pub fn find_code_blocks(content: &str) -> Vec<CodeBlock> {
+    let mut blocks: Vec<CodeBlock> = Vec::new();
+    let mut open: Option<(String, Option<String>, usize)> = None; // (fence, tag, start)
+    let mut body: Vec<&str> = Vec::new();
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.chars().count();
+        let trimmed = line.trim();
+
+        match &open {
+            None => {
+                let fence: String = trimmed.chars().take_while(|c| *c == '`' || *c == '~').collect();
+                if fence.len() >= 3 && fence.chars().all(|c| c == fence.chars().next().unwrap_or('`')) {
+                    open = Some((fence.clone(), normalize_language(&trimmed[fence.len()..]), line_start));
+                    body.clear();
+                }
+            }
+            Some((fence, tag, start)) => {
+                if trimmed.starts_with(fence.as_str()) && trimmed.trim_start_matches(fence.chars().next().unwrap_or('`')).is_empty() {
+                    let code = body.concat().trim_end_matches('\n').to_string();
+                    push_block(&mut blocks, content, CodeBlock { language: tag.clone(), content: code, start: *start, end: offset });
+                    open = None;
+                } else {
+                    body.push(line);
+                }
+            }
+        }
+    }
+
+    blocks.into_iter()
+        .filter(|block| block.content.trim().chars().count() >= MIN_SNIPPET_CHARS)
+        .map(|block| CodeBlock {
+            language: block.language.clone().or_else(|| guess_language(&block.content)),
+            ..block
+        })
+        .collect()
+}
+
+fn push_block(blocks: &mut Vec<CodeBlock>, content: &str, block: CodeBlock) {
+    if let Some(previous) = blocks.last_mut() {
+        let between: String = content.chars().skip(previous.end).take(block.start.saturating_sub(previous.end)).collect();
+        if between.trim().is_empty() && previous.language == block.language {
+            previous.content.push('\n');
+            previous.content.push_str(&block.content);
+            previous.end = block.end;
+            return;
+        }
+    }
+    blocks.push(block);
+}
@@ -1,9 +1,13 @@
 use tauri::State;
 use crate::database::{
-    CreateActionRequest, CreateSessionRequest, UserAction, StudySession, ActionStats
+    CreateActionRequest, CreateSessionRequest, UserAction, StudySession, ActionStats, SessionTimeline, SessionFocusAnalysis,
+    SessionType, SessionTemplate, SaveSessionTemplateRequest, SessionSegmentationSettings, SessionSegmentationResult,
+    DailyActivity, AnalyticsExportKind, AnalyticsExportResult, DateRange
 };
 use crate::commands::database::DatabaseState;
 
+pub const SESSION_SEGMENTATION_SETTINGS_KEY: &str = "session_segmentation";
+
 // ======================== Sessions Commands ========================
 
 #[tauri::command]
@@ -11,6 +15,7 @@ pub async fn create_study_session(
     state: State<'_, DatabaseState>,
     req: CreateSessionRequest
 ) -> Result<StudySession, String> {
+    let _span = crate::metrics::command_span("create_study_session");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -22,6 +27,7 @@ pub async fn create_study_session(
 pub async fn get_active_session(
     state: State<'_, DatabaseState>
 ) -> Result<Option<StudySession>, String> {
+    let _span = crate::metrics::command_span("get_active_session");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -34,6 +40,7 @@ pub async fn end_study_session(
     state: State<'_, DatabaseState>,
     session_id: String
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("end_study_session");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -46,6 +53,7 @@ pub async fn get_study_session(
     state: State<'_, DatabaseState>,
     session_id: String
 ) -> Result<Option<StudySession>, String> {
+    let _span = crate::metrics::command_span("get_study_session");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -59,6 +67,7 @@ pub async fn get_study_sessions(
     limit: Option<i64>,
     offset: Option<i64>
 ) -> Result<Vec<StudySession>, String> {
+    let _span = crate::metrics::command_span("get_study_sessions");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -66,6 +75,19 @@ pub async fn get_study_sessions(
         .map_err(|e| format!("Failed to get sessions: {}", e))
 }
 
+#[tauri::command]
+pub async fn get_session_timeline(
+    state: State<'_, DatabaseState>,
+    session_id: String
+) -> Result<Option<SessionTimeline>, String> {
+    let _span = crate::metrics::command_span("get_session_timeline");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    
+    database.get_session_timeline(&session_id).await
+        .map_err(|e| format!("Failed to get session timeline: {}", e))
+}
+
 // ======================== Actions Commands ========================
 
 #[tauri::command]
@@ -73,8 +95,19 @@ pub async fn record_user_action(
     state: State<'_, DatabaseState>,
     req: CreateActionRequest
 ) -> Result<UserAction, String> {
+    let _span = crate::metrics::command_span("record_user_action");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let segmentation: SessionSegmentationSettings = database.get_typed_setting(SESSION_SEGMENTATION_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get session segmentation settings: {}", e))?;
+    if segmentation.enabled {
+        let mut req = req;
+        req.session_id = database.resolve_action_session(Some(&req.session_id), &segmentation).await
+            .map_err(|e| format!("Failed to resolve session for action: {}", e))?;
+        return database.record_action(req).await
+            .map_err(|e| format!("Failed to record action: {}", e));
+    }
     
     // Validate that the session exists before recording action
     match database.get_session(&req.session_id).await {
@@ -87,8 +120,10 @@ pub async fn record_user_action(
             // Session doesn't exist, create a default session and record action
             let session_req = CreateSessionRequest {
                 title: "Auto-created Study Session".to_string(),
-                session_type: Some("mixed".to_string()),
+                session_type: Some(SessionType::Mixed),
                 metadata: None,
+                template_id: None,
+                target_duration: None,
             };
             
             match database.create_session(session_req).await {
@@ -112,6 +147,7 @@ pub async fn get_actions_by_session(
     state: State<'_, DatabaseState>,
     session_id: String
 ) -> Result<Vec<UserAction>, String> {
+    let _span = crate::metrics::command_span("get_actions_by_session");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -124,6 +160,7 @@ pub async fn get_actions_by_document(
     state: State<'_, DatabaseState>,
     document_id: String
 ) -> Result<Vec<UserAction>, String> {
+    let _span = crate::metrics::command_span("get_actions_by_document");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -136,6 +173,7 @@ pub async fn get_recent_actions(
     state: State<'_, DatabaseState>,
     limit: i64
 ) -> Result<Vec<UserAction>, String> {
+    let _span = crate::metrics::command_span("get_recent_actions");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -149,6 +187,7 @@ pub async fn get_recent_actions(
 pub async fn get_action_statistics(
     state: State<'_, DatabaseState>
 ) -> Result<ActionStats, String> {
+    let _span = crate::metrics::command_span("get_action_statistics");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -156,14 +195,28 @@ pub async fn get_action_statistics(
         .map_err(|e| format!("Failed to get action statistics: {}", e))
 }
 
+#[tauri::command]
+pub async fn analyze_session_focus(
+    state: State<'_, DatabaseState>,
+    session_id: String
+) -> Result<Option<SessionFocusAnalysis>, String> {
+    let _span = crate::metrics::command_span("analyze_session_focus");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    
+    database.analyze_session_focus(&session_id).await
+        .map_err(|e| format!("Failed to analyze session focus: {}", e))
+}
+
 // ======================== Convenience Commands ========================
 
 #[tauri::command]
 pub async fn start_new_session(
     state: State<'_, DatabaseState>,
     title: String,
-    session_type: Option<String>
+    session_type: Option<SessionType>
 ) -> Result<StudySession, String> {
+    let _span = crate::metrics::command_span("start_new_session");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -177,6 +230,8 @@ pub async fn start_new_session(
         title,
         session_type,
         metadata: None,
+        template_id: None,
+        target_duration: None,
     };
 
     database.create_session(req).await
@@ -190,24 +245,35 @@ pub async fn record_simple_action(
     document_id: Option<String>,
     data: Option<serde_json::Value>
 ) -> Result<UserAction, String> {
+    let _span = crate::metrics::command_span("record_simple_action");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
+    let segmentation: SessionSegmentationSettings = database.get_typed_setting(SESSION_SEGMENTATION_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get session segmentation settings: {}", e))?;
+
     // Get or create active session
-    let session_id = match database.get_active_session().await {
-        Ok(Some(session)) => session.id,
-        Ok(None) => {
-            // Create a default session
-            let req = CreateSessionRequest {
-                title: "Study Session".to_string(),
-                session_type: Some("mixed".to_string()),
-                metadata: None,
-            };
-            database.create_session(req).await
-                .map_err(|e| format!("Failed to create default session: {}", e))?
-                .id
+    let session_id = if segmentation.enabled {
+        database.resolve_action_session(None, &segmentation).await
+            .map_err(|e| format!("Failed to resolve session for action: {}", e))?
+    } else {
+        match database.get_active_session().await {
+            Ok(Some(session)) => session.id,
+            Ok(None) => {
+                // Create a default session
+                let req = CreateSessionRequest {
+                    title: "Study Session".to_string(),
+                    session_type: Some(SessionType::Mixed),
+                    metadata: None,
+                    template_id: None,
+                    target_duration: None,
+                };
+                database.create_session(req).await
+                    .map_err(|e| format!("Failed to create default session: {}", e))?
+                    .id
+            }
+            Err(e) => return Err(format!("Failed to get active session: {}", e)),
         }
-        Err(e) => return Err(format!("Failed to get active session: {}", e)),
     };
 
     let req = CreateActionRequest {
@@ -224,12 +290,212 @@ pub async fn record_simple_action(
         .map_err(|e| format!("Failed to record simple action: {}", e))
 }
 
+/// Minutes by activity type for one local day (YYYY-MM-DD), for the dashboard
+#[tauri::command]
+pub async fn get_daily_activity(
+    state: State<'_, DatabaseState>,
+    date: String
+) -> Result<DailyActivity, String> {
+    let _span = crate::metrics::command_span("get_daily_activity");
+    let date = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_daily_activity(date).await
+        .map_err(|e| format!("Failed to get daily activity: {}", e))
+}
+
+/// Write flashcard reviews, study sessions or user actions in `date_range` (local days,
+/// inclusive) to a CSV file at `path`, for analysis in R, Python or a spreadsheet
+#[tauri::command]
+pub async fn export_analytics_csv(
+    state: State<'_, DatabaseState>,
+    kind: AnalyticsExportKind,
+    path: String,
+    date_range: Option<DateRange>,
+) -> Result<AnalyticsExportResult, String> {
+    let _span = crate::metrics::command_span("export_analytics_csv");
+    let date_range = date_range.unwrap_or_default();
+    if let (Some(start), Some(end)) = (date_range.start, date_range.end) {
+        if start > end {
+            return Err("The date range starts after it ends".to_string());
+        }
+    }
+
+    let (header, mut rows, redactor) = {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+        let (header, rows) = database.get_analytics_export_rows(kind, &date_range).await
+            .map_err(|e| format!("Failed to read analytics data: {}", e))?;
+        (header, rows, crate::services::settings::export_redactor(database).await?)
+    };
+
+    // Action payloads and session notes can carry the same identifiers as documents
+    if let Some(redactor) = &redactor {
+        let mut redacted = 0;
+        for field in rows.iter_mut().flatten() {
+            redactor.redact_in_place(field, &mut redacted);
+        }
+        if redacted > 0 {
+            println!("🔒 Redacted {} personal identifiers from the analytics export", redacted);
+        }
+    }
+
+    let mut output = std::path::PathBuf::from(&path);
+    if output.extension().is_none() {
+        output.set_extension("csv");
+    }
+    let mut csv = csv_line(&header);
+    for row in &rows {
+        csv.push_str(&csv_line(row));
+    }
+    std::fs::write(&output, csv)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+
+    println!("📊 Exported {} rows to {}", rows.len(), output.display());
+    Ok(AnalyticsExportResult {
+        kind,
+        path: output.to_string_lossy().to_string(),
+        rows: rows.len(),
+    })
+}
+
+// One RFC 4180 record: fields with commas, quotes or line breaks are quoted
+fn csv_line(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+// ======================== Session Segmentation Commands ========================
+
+#[tauri::command]
+pub async fn get_session_segmentation_settings(
+    state: State<'_, DatabaseState>
+) -> Result<SessionSegmentationSettings, String> {
+    let _span = crate::metrics::command_span("get_session_segmentation_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(SESSION_SEGMENTATION_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get session segmentation settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_session_segmentation_settings(
+    state: State<'_, DatabaseState>,
+    settings: SessionSegmentationSettings
+) -> Result<SessionSegmentationSettings, String> {
+    let _span = crate::metrics::command_span("update_session_segmentation_settings");
+    if settings.idle_gap_minutes < 1 {
+        return Err("Idle gap must be at least one minute".to_string());
+    }
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.set_typed_setting(SESSION_SEGMENTATION_SETTINGS_KEY, &settings).await
+        .map_err(|e| format!("Failed to update session segmentation settings: {}", e))?;
+
+    Ok(settings)
+}
+
+/// Split already recorded sessions at idle gaps and day boundaries, using the current settings
+/// even when automatic segmentation is turned off
+#[tauri::command]
+pub async fn segment_study_sessions(
+    state: State<'_, DatabaseState>
+) -> Result<SessionSegmentationResult, String> {
+    let _span = crate::metrics::command_span("segment_study_sessions");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let settings: SessionSegmentationSettings = database.get_typed_setting(SESSION_SEGMENTATION_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get session segmentation settings: {}", e))?;
+
+    database.segment_sessions(&settings).await
+        .map_err(|e| format!("Failed to segment sessions: {}", e))
+}
+
+// ======================== Session Template Commands ========================
+
+#[tauri::command]
+pub async fn get_session_templates(
+    state: State<'_, DatabaseState>
+) -> Result<Vec<SessionTemplate>, String> {
+    let _span = crate::metrics::command_span("get_session_templates");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_session_templates().await
+        .map_err(|e| format!("Failed to get session templates: {}", e))
+}
+
+#[tauri::command]
+pub async fn save_session_template(
+    state: State<'_, DatabaseState>,
+    req: SaveSessionTemplateRequest
+) -> Result<SessionTemplate, String> {
+    let _span = crate::metrics::command_span("save_session_template");
+    if req.name.trim().is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+    if req.target_duration_minutes <= 0 {
+        return Err("Target duration must be at least one minute".to_string());
+    }
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.save_session_template(req).await
+        .map_err(|e| format!("Failed to save session template: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_session_template(
+    state: State<'_, DatabaseState>,
+    template_id: String
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_session_template");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.delete_session_template(&template_id).await
+        .map_err(|e| format!("Failed to delete session template: {}", e))
+}
+
+/// Start a session with the template's type, target duration and attached documents and decks,
+/// ending the active one first
+#[tauri::command]
+pub async fn start_session_from_template(
+    state: State<'_, DatabaseState>,
+    template_id: String
+) -> Result<StudySession, String> {
+    let _span = crate::metrics::command_span("start_session_from_template");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.start_session_from_template(&template_id).await
+        .map_err(|e| format!("Failed to start session from template: {}", e))?
+        .ok_or_else(|| format!("Session template not found: {}", template_id))
+}
+
 // ======================== Debug Commands ========================
 
 #[tauri::command]
 pub async fn debug_database_state(
     state: State<'_, DatabaseState>
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("debug_database_state");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -1,30 +1,27 @@
 use crate::ai::*;
-use crate::database::Database;
+use crate::database::{Database, AIProfile, CreateAIProfileRequest};
+use crate::services::{AiService, PreparedChat};
 use tauri::{State, AppHandle, Emitter};
 use tokio::sync::Mutex;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex as StdMutex};
+
+pub use crate::services::ai::{ContextExcerpt, PreparedContext};
+pub(crate) use crate::services::ai::{fit_request_to_context, load_provider_settings, load_guardrail_settings, load_ai_profile, redact_chat_request};
 
 // Database state type
 type DatabaseState = Arc<Mutex<Option<Database>>>;
 
+fn ai_service(state: &State<'_, DatabaseState>) -> AiService {
+    AiService::new(state.inner().clone())
+}
+
 #[tauri::command]
 pub async fn ai_test_connection(
     state: State<'_, DatabaseState>,
     provider: AIProvider,
 ) -> Result<bool, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    let api_key = database.get_api_key(&provider.id).await
-        .map_err(|e| format!("Failed to get API key: {}", e))?;
-    drop(db_state);
-
-    // Test connection based on provider type
-    match provider.r#type.as_str() {
-        "openai" | "custom" => test_openai_connection(&provider, api_key).await,
-        "anthropic" => test_anthropic_connection(&provider, api_key).await,
-        "ollama" => test_ollama_connection(&provider).await,
-        _ => Err("Unsupported provider type".to_string()),
-    }
+    let _span = crate::metrics::command_span("ai_test_connection");
+    ai_service(&state).test_connection(provider).await
 }
 
 #[tauri::command]
@@ -33,7 +30,9 @@ pub async fn ai_chat_completion(
     provider: AIProvider,
     model: String,
     request: ChatCompletionRequest,
+    profile_id: Option<String>,
 ) -> Result<ChatCompletionResponse, String> {
+    let _span = crate::metrics::command_span("ai_chat_completion");
     println!(
         "[AI][CMD] chat_completion provider={} type={} model={} messages={} stream={}",
         provider.id,
@@ -42,20 +41,12 @@ pub async fn ai_chat_completion(
         request.messages.len(),
         false
     );
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    let api_key = database.get_api_key(&provider.id).await
-        .map_err(|e| format!("Failed to get API key: {}", e))?;
-    drop(db_state);
-
-    match provider.r#type.as_str() {
-        "openai" | "custom" => openai_chat_completion(&provider, &model, &request, api_key).await,
-        "anthropic" => anthropic_chat_completion(&provider, &model, &request, api_key).await,
-        "ollama" => ollama_chat_completion(&provider, &model, &request).await,
-        _ => Err("Unsupported provider type".to_string()),
-    }
+    ai_service(&state).chat_completion(provider, model, request, profile_id.as_deref()).await
 }
 
+/// Start a streamed completion and return its stream id. Events arrive on the
+/// `ai-stream:{id}` channel as start, chunk..., then end or error. When `event_name` is given,
+/// raw chunks and `{event_name}_error` are also emitted there for older callers.
 #[tauri::command]
 pub async fn ai_chat_completion_stream(
     app: AppHandle,
@@ -63,35 +54,123 @@ pub async fn ai_chat_completion_stream(
     provider: AIProvider,
     model: String,
     request: ChatCompletionRequest,
-    event_name: String,
-) -> Result<(), String> {
+    event_name: Option<String>,
+    stream_id: Option<String>,
+    profile_id: Option<String>,
+) -> Result<String, String> {
+    let _span = crate::metrics::command_span("ai_chat_completion_stream");
+    let stream_id = stream_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
     println!(
-        "[AI][CMD] chat_completion_stream provider={} type={} model={} messages={} event=\"{}\"",
+        "[AI][CMD] chat_completion_stream provider={} type={} model={} messages={} stream={}",
         provider.id,
         provider.r#type,
         model,
         request.messages.len(),
-        event_name
+        stream_id
     );
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    let api_key = database.get_api_key(&provider.id).await
-        .map_err(|e| format!("Failed to get API key: {}", e))?;
-    drop(db_state);
-
-    // Spawn async task for streaming
-    tokio::spawn(async move {
-        let result = match provider.r#type.as_str() {
-            "openai" | "custom" => openai_chat_completion_stream(&provider, &model, &request, api_key, &event_name, &app).await,
-            _ => Err("Streaming not supported for this provider".to_string()),
+    let PreparedChat { provider, model, request, api_key, warning } = ai_service(&state)
+        .prepare_chat(provider, model, request, profile_id.as_deref()).await?;
+    ensure_provider_reachable(&provider)?;
+    let filter_output = ai_service(&state).get_guardrail_settings().await?.filter_output;
+
+    let channel = streams::channel_name(&stream_id);
+    let active_stream = streams::ActiveStream {
+        stream_id: stream_id.clone(),
+        provider_id: provider.id.clone(),
+        model: model.clone(),
+        started_at: chrono::Utc::now(),
+    };
+
+    // Spawn async task for streaming. It waits until the stream is registered with its abort
+    // handle, so a cancel can never find the stream without a task to stop.
+    let (registered_tx, registered_rx) = tokio::sync::oneshot::channel::<()>();
+    let task_stream_id = stream_id.clone();
+    let task = tokio::spawn(async move {
+        if registered_rx.await.is_err() {
+            return;
+        }
+        let _ = app.emit(&channel, streams::StreamEvent::Start { stream_id: task_stream_id.clone(), model: model.clone(), warning });
+
+        let sink_app = app.clone();
+        let sink_channel = channel.clone();
+        let sink_stream_id = task_stream_id.clone();
+        let legacy_event = event_name.clone();
+        // Chunks go out as they arrive; the filter can only judge the finished reply
+        let reply = Arc::new(StdMutex::new(String::new()));
+        let sink_reply = reply.clone();
+        let on_chunk = move |chunk: ChatCompletionStreamChunk| {
+            if filter_output {
+                if let (Ok(mut reply), Some(content)) = (sink_reply.lock(), chunk.choices.first().and_then(|c| c.delta.content.as_deref())) {
+                    reply.push_str(content);
+                }
+            }
+            if let Some(name) = &legacy_event {
+                let _ = sink_app.emit(name, chunk.clone());
+            }
+            let _ = sink_app.emit(&sink_channel, streams::StreamEvent::Chunk { stream_id: sink_stream_id.clone(), chunk });
         };
 
-        if let Err(error) = result {
-            let _ = app.emit(&format!("{}_error", event_name), error);
+        let result = match chat_provider(&provider.r#type) {
+            Ok(backend) => backend.stream(&provider, &model, &request, api_key, &on_chunk).await,
+            Err(e) => Err(e),
+        };
+
+        // A cancelled stream has already been removed and sent its end event
+        if !streams::finish(&task_stream_id) {
+            return;
+        }
+        match result {
+            Ok(()) => {
+                if filter_output {
+                    let reply = reply.lock().map(|reply| reply.clone()).unwrap_or_default();
+                    let system_prompt = request.messages.iter().find(|m| m.role == "system").map(|m| m.content.as_str());
+                    let report = guardrails::filter_output(&reply, system_prompt);
+                    if !report.flags.is_empty() {
+                        println!("🛡️ Output filter flagged streamed reply from {}: {:?}", model, report.flags);
+                        let _ = app.emit(&channel, streams::StreamEvent::Filtered {
+                            stream_id: task_stream_id.clone(),
+                            content: report.content,
+                            flags: report.flags,
+                        });
+                    }
+                }
+                let _ = app.emit(&channel, streams::StreamEvent::End { stream_id: task_stream_id, cancelled: false });
+            }
+            Err(error) => {
+                if let Err(e) = crate::crash_reports::record_command_error("ai_chat_completion_stream", &error) {
+                    eprintln!("⚠️ {}", e);
+                }
+                if let Some(name) = &event_name {
+                    let _ = app.emit(&format!("{}_error", name), error.clone());
+                }
+                let _ = app.emit(&channel, streams::StreamEvent::Error { stream_id: task_stream_id, message: error });
+            }
         }
     });
+    if let Err(e) = streams::register(active_stream, task.abort_handle()) {
+        task.abort();
+        return Err(e);
+    }
+    let _ = registered_tx.send(());
+
+    Ok(stream_id)
+}
+
+#[tauri::command]
+pub async fn cancel_ai_stream(app: AppHandle, stream_id: String) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("cancel_ai_stream");
+    let cancelled = streams::cancel(&stream_id);
+    if cancelled {
+        println!("[AI][CMD] cancelled stream {}", stream_id);
+        let _ = app.emit(&streams::channel_name(&stream_id), streams::StreamEvent::End { stream_id, cancelled: true });
+    }
+    Ok(cancelled)
+}
 
-    Ok(())
+#[tauri::command]
+pub async fn list_active_ai_streams() -> Result<Vec<streams::ActiveStream>, String> {
+    let _span = crate::metrics::command_span("list_active_ai_streams");
+    Ok(streams::active())
 }
 
 #[tauri::command]
@@ -99,16 +178,128 @@ pub async fn ai_get_models(
     state: State<'_, DatabaseState>,
     provider: AIProvider,
 ) -> Result<Vec<AIModel>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    let api_key = database.get_api_key(&provider.id).await
-        .map_err(|e| format!("Failed to get API key: {}", e))?;
-    drop(db_state);
-
-    match provider.r#type.as_str() {
-        "openai" | "custom" => get_openai_models(&provider, api_key).await,
-        "anthropic" => get_anthropic_models(&provider, api_key).await,
-        "ollama" => get_ollama_models(&provider).await,
-        _ => Err("Unsupported provider type".to_string()),
-    }
-} 
\ No newline at end of file
+    let _span = crate::metrics::command_span("ai_get_models");
+    ai_service(&state).get_models(provider).await
+}
+
+#[tauri::command]
+pub async fn get_ai_provider_settings(
+    state: State<'_, DatabaseState>,
+    provider_id: String,
+) -> Result<ProviderConnectionSettings, String> {
+    let _span = crate::metrics::command_span("get_ai_provider_settings");
+    ai_service(&state).get_provider_settings(&provider_id).await
+}
+
+#[tauri::command]
+pub async fn update_ai_provider_settings(
+    state: State<'_, DatabaseState>,
+    provider_id: String,
+    settings: ProviderConnectionSettings,
+) -> Result<ProviderConnectionSettings, String> {
+    let _span = crate::metrics::command_span("update_ai_provider_settings");
+    ai_service(&state).update_provider_settings(&provider_id, settings).await
+}
+
+#[tauri::command]
+pub async fn get_ai_guardrail_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<guardrails::GuardrailSettings, String> {
+    let _span = crate::metrics::command_span("get_ai_guardrail_settings");
+    ai_service(&state).get_guardrail_settings().await
+}
+
+#[tauri::command]
+pub async fn update_ai_guardrail_settings(
+    state: State<'_, DatabaseState>,
+    settings: guardrails::GuardrailSettings,
+) -> Result<guardrails::GuardrailSettings, String> {
+    let _span = crate::metrics::command_span("update_ai_guardrail_settings");
+    ai_service(&state).update_guardrail_settings(settings).await
+}
+
+/// Sanitize and wrap retrieved excerpts for RAG prompts built on the frontend
+#[tauri::command]
+pub async fn prepare_rag_context(
+    state: State<'_, DatabaseState>,
+    excerpts: Vec<ContextExcerpt>,
+) -> Result<PreparedContext, String> {
+    let _span = crate::metrics::command_span("prepare_rag_context");
+    ai_service(&state).prepare_rag_context(&excerpts).await
+}
+
+/// Run the output filter on a finished reply (e.g. after a stream), regardless of the setting
+#[tauri::command]
+pub async fn filter_ai_output(
+    content: String,
+    system_prompt: Option<String>,
+) -> Result<guardrails::OutputFilterReport, String> {
+    let _span = crate::metrics::command_span("filter_ai_output");
+    Ok(guardrails::filter_output(&content, system_prompt.as_deref()))
+}
+
+// ===== AI Profile Commands =====
+
+#[tauri::command]
+pub async fn create_ai_profile(
+    state: State<'_, DatabaseState>,
+    request: CreateAIProfileRequest,
+) -> Result<AIProfile, String> {
+    let _span = crate::metrics::command_span("create_ai_profile");
+    ai_service(&state).create_profile(request).await
+}
+
+#[tauri::command]
+pub async fn get_ai_profiles(
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<AIProfile>, String> {
+    let _span = crate::metrics::command_span("get_ai_profiles");
+    ai_service(&state).get_profiles().await
+}
+
+#[tauri::command]
+pub async fn get_ai_profile(
+    state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<Option<AIProfile>, String> {
+    let _span = crate::metrics::command_span("get_ai_profile");
+    ai_service(&state).get_profile(&id).await
+}
+
+#[tauri::command]
+pub async fn update_ai_profile(
+    state: State<'_, DatabaseState>,
+    id: String,
+    request: CreateAIProfileRequest,
+) -> Result<Option<AIProfile>, String> {
+    let _span = crate::metrics::command_span("update_ai_profile");
+    ai_service(&state).update_profile(&id, request).await
+}
+
+#[tauri::command]
+pub async fn delete_ai_profile(
+    state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_ai_profile");
+    ai_service(&state).delete_profile(&id).await
+}
+
+/// Export profiles as portable JSON (no IDs or timestamps) for sharing
+#[tauri::command]
+pub async fn export_ai_profiles(
+    state: State<'_, DatabaseState>,
+) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("export_ai_profiles");
+    ai_service(&state).export_profiles().await
+}
+
+/// Import profiles exported by `export_ai_profiles`; profiles with a matching name are updated
+#[tauri::command]
+pub async fn import_ai_profiles(
+    state: State<'_, DatabaseState>,
+    data: serde_json::Value,
+) -> Result<Vec<AIProfile>, String> {
+    let _span = crate::metrics::command_span("import_ai_profiles");
+    ai_service(&state).import_profiles(data).await
+}
@@ -1,6 +1,7 @@
 use crate::background_processor::create_pdf_processing_job;
 use crate::database::{Database, ProcessingJob, ProcessingJobStats};
-use crate::pdf_processor::MarkerOptions;
+use crate::pdf_processor::{MarkerOptions, MarkerLlmService, ExtractionMethod};
+use crate::throttle;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
@@ -11,9 +12,7 @@ type DatabaseState = Arc<Mutex<Option<Database>>>;
 
 // Helper function to get PDF storage directory
 fn get_pdf_storage_dir() -> Result<PathBuf, String> {
-    let home_dir = dirs::home_dir().ok_or("Could not find home directory")?;
-
-    let storage_dir = home_dir.join("stellar_data").join("pdfs");
+    let storage_dir = crate::paths::app_data_dir()?.join("pdfs");
 
     std::fs::create_dir_all(&storage_dir)
         .map_err(|e| format!("Failed to create PDF storage directory: {}", e))?;
@@ -29,9 +28,14 @@ pub async fn create_background_pdf_job_from_file(
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
-    _use_llm: Option<bool>, // Disabled
+    use_llm: Option<bool>,
     force_ocr: Option<bool>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
+    format_lines: Option<bool>,
+    inline_math: Option<bool>,
+    llm_service: Option<MarkerLlmService>,
 ) -> Result<ProcessingJob, String> {
+    let _span = crate::metrics::command_span("create_background_pdf_job_from_file");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -45,14 +49,23 @@ pub async fn create_background_pdf_job_from_file(
     let stored_filename = format!("{}-{}", uuid::Uuid::new_v4(), original_filename);
     let stored_path = storage_dir.join(&stored_filename);
 
-    // Copy file to storage
-    std::fs::copy(&file_path, &stored_path).map_err(|e| format!("Failed to copy file: {}", e))?;
+    // Copy file to storage through a .part file, so a quit mid-copy never leaves a truncated PDF
+    let partial_path = storage_dir.join(format!("{}.part", stored_filename));
+    crate::storage::ensure_space_for_copy(std::path::Path::new(&file_path), &partial_path)?;
+    std::fs::copy(&file_path, &partial_path).map_err(|e| format!("Failed to copy file: {}", e))?;
+    std::fs::rename(&partial_path, &stored_path).map_err(|e| format!("Failed to copy file: {}", e))?;
 
     // Get processing options
     let processing_options = MarkerOptions {
         extract_images: false,
         force_ocr: force_ocr.unwrap_or(false),
         prefer_marker: true,
+        preferred_methods: extraction_methods,
+        use_llm: use_llm.unwrap_or(false),
+        format_lines: format_lines.unwrap_or(true),
+        inline_math: inline_math.unwrap_or(false),
+        llm_service: llm_service.unwrap_or_default(),
+        ..Default::default()
     };
 
     let job = create_pdf_processing_job(
@@ -79,9 +92,14 @@ pub async fn create_background_pdf_job_from_data(
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
-    _use_llm: Option<bool>, // Disabled
+    use_llm: Option<bool>,
     force_ocr: Option<bool>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
+    format_lines: Option<bool>,
+    inline_math: Option<bool>,
+    llm_service: Option<MarkerLlmService>,
 ) -> Result<ProcessingJob, String> {
+    let _span = crate::metrics::command_span("create_background_pdf_job_from_data");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -90,14 +108,23 @@ pub async fn create_background_pdf_job_from_data(
     let stored_filename = format!("{}-{}", uuid::Uuid::new_v4(), file_name);
     let stored_path = storage_dir.join(&stored_filename);
 
-    // Save file data
-    std::fs::write(&stored_path, &file_data).map_err(|e| format!("Failed to save file: {}", e))?;
+    // Save file data through a .part file, like the copy above
+    let partial_path = storage_dir.join(format!("{}.part", stored_filename));
+    crate::storage::ensure_space(&partial_path, file_data.len() as u64)?;
+    std::fs::write(&partial_path, &file_data).map_err(|e| format!("Failed to save file: {}", e))?;
+    std::fs::rename(&partial_path, &stored_path).map_err(|e| format!("Failed to save file: {}", e))?;
 
     // Get processing options
     let processing_options = MarkerOptions {
         extract_images: false,
         force_ocr: force_ocr.unwrap_or(false),
         prefer_marker: true,
+        preferred_methods: extraction_methods,
+        use_llm: use_llm.unwrap_or(false),
+        format_lines: format_lines.unwrap_or(true),
+        inline_math: inline_math.unwrap_or(false),
+        llm_service: llm_service.unwrap_or_default(),
+        ..Default::default()
     };
 
     let job = create_pdf_processing_job(
@@ -123,12 +150,33 @@ pub async fn create_background_pdf_job_from_url(
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
-    _use_llm: Option<bool>, // Disabled
+    use_llm: Option<bool>,
     force_ocr: Option<bool>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
+    format_lines: Option<bool>,
+    inline_math: Option<bool>,
+    llm_service: Option<MarkerLlmService>,
+    force: Option<bool>,
 ) -> Result<ProcessingJob, String> {
+    let _span = crate::metrics::command_span("create_background_pdf_job_from_url");
+    crate::network::ensure_reachable(&url, "Importing from a URL")?;
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
+    // Queuing the same URL twice returns the first job unless forced
+    if !force.unwrap_or(false) {
+        let source_url = crate::importers::url::normalize_source_url(&url);
+        if let Some(job) = database.find_processing_job_by_source_url(&source_url).await
+            .map_err(|e| format!("Failed to look up source URL: {}", e))? {
+            println!("♻️ {} is already queued or imported (job {})", url, job.id);
+            return Ok(job);
+        }
+        if let Some(document) = database.find_document_by_source_url(&source_url).await
+            .map_err(|e| format!("Failed to look up source URL: {}", e))? {
+            return Err(format!("{} was already imported as \"{}\" ({}); pass force to import it again", url, document.title, document.id));
+        }
+    }
+
     // Extract filename from URL
     let filename = url
         .split('/')
@@ -147,6 +195,12 @@ pub async fn create_background_pdf_job_from_url(
         extract_images: false,
         force_ocr: force_ocr.unwrap_or(false),
         prefer_marker: true,
+        preferred_methods: extraction_methods,
+        use_llm: use_llm.unwrap_or(false),
+        format_lines: format_lines.unwrap_or(true),
+        inline_math: inline_math.unwrap_or(false),
+        llm_service: llm_service.unwrap_or_default(),
+        ..Default::default()
     };
 
     let job = create_pdf_processing_job(
@@ -171,6 +225,7 @@ pub async fn get_processing_jobs(
     limit: Option<i64>,
     offset: Option<i64>,
 ) -> Result<Vec<ProcessingJob>, String> {
+    let _span = crate::metrics::command_span("get_processing_jobs");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -186,6 +241,7 @@ pub async fn get_processing_jobs_by_status(
     db_state: State<'_, DatabaseState>,
     status: String,
 ) -> Result<Vec<ProcessingJob>, String> {
+    let _span = crate::metrics::command_span("get_processing_jobs_by_status");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -201,6 +257,7 @@ pub async fn get_processing_job(
     db_state: State<'_, DatabaseState>,
     job_id: String,
 ) -> Result<Option<ProcessingJob>, String> {
+    let _span = crate::metrics::command_span("get_processing_job");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -216,6 +273,7 @@ pub async fn delete_processing_job(
     db_state: State<'_, DatabaseState>,
     job_id: String,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_processing_job");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -230,6 +288,7 @@ pub async fn delete_processing_job(
 pub async fn get_processing_job_stats(
     db_state: State<'_, DatabaseState>,
 ) -> Result<ProcessingJobStats, String> {
+    let _span = crate::metrics::command_span("get_processing_job_stats");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -245,6 +304,7 @@ pub async fn cancel_processing_job(
     db_state: State<'_, DatabaseState>,
     job_id: String,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("cancel_processing_job");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -270,6 +330,7 @@ pub async fn retry_processing_job(
     db_state: State<'_, DatabaseState>,
     job_id: String,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("retry_processing_job");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -297,6 +358,7 @@ pub async fn get_document_processing_status(
     db_state: State<'_, DatabaseState>,
     document_id: String,
 ) -> Result<Option<ProcessingJob>, String> {
+    let _span = crate::metrics::command_span("get_document_processing_status");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -322,6 +384,7 @@ pub async fn get_processing_jobs_by_document_id(
     db_state: State<'_, DatabaseState>,
     document_id: String,
 ) -> Result<Vec<ProcessingJob>, String> {
+    let _span = crate::metrics::command_span("get_processing_jobs_by_document_id");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -330,3 +393,39 @@ pub async fn get_processing_jobs_by_document_id(
         .await
         .map_err(|e| format!("Failed to get processing jobs by document ID: {}", e))
 }
+
+#[tauri::command]
+pub async fn get_resource_settings() -> Result<throttle::ResourceSettings, String> {
+    let _span = crate::metrics::command_span("get_resource_settings");
+    Ok(throttle::resource_settings())
+}
+
+/// Limits on background work: concurrent embedding batches, OCR niceness, and pausing on
+/// battery or during reviews. Persisted and applied right away.
+#[tauri::command]
+pub async fn update_resource_settings(
+    db_state: State<'_, DatabaseState>,
+    settings: throttle::ResourceSettings,
+) -> Result<throttle::ResourceSettings, String> {
+    let _span = crate::metrics::command_span("update_resource_settings");
+    throttle::set_resource_settings(settings.clone())?;
+
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    database.set_typed_setting(throttle::RESOURCE_SETTINGS_KEY, &settings).await
+        .map_err(|e| format!("Failed to save resource limits: {}", e))?;
+
+    Ok(settings)
+}
+
+/// Why background processing is paused right now, or None while it runs
+#[tauri::command]
+pub async fn get_background_pause_reason(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<String>, String> {
+    let _span = crate::metrics::command_span("get_background_pause_reason");
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    Ok(throttle::pause_reason(database).await.map(str::to_string))
+}
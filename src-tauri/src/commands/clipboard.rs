@@ -0,0 +1,66 @@
+use tauri::{AppHandle, State};
+use crate::clipboard::{ClipboardWatcher, CLIPBOARD_WATCHER_SETTINGS_KEY};
+use crate::database::ClipboardWatcherSettings;
+use crate::commands::database::DatabaseState;
+
+// ===== Clipboard Watcher Commands =====
+
+#[tauri::command]
+pub async fn get_clipboard_watcher_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<ClipboardWatcherSettings, String> {
+    let _span = crate::metrics::command_span("get_clipboard_watcher_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(CLIPBOARD_WATCHER_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get clipboard watcher settings: {}", e))
+}
+
+/// Save the settings and start, restart or stop the watcher to match
+#[tauri::command]
+pub async fn update_clipboard_watcher_settings(
+    app: AppHandle,
+    state: State<'_, DatabaseState>,
+    watcher: State<'_, ClipboardWatcher>,
+    settings: ClipboardWatcherSettings,
+) -> Result<ClipboardWatcherSettings, String> {
+    let _span = crate::metrics::command_span("update_clipboard_watcher_settings");
+    {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+        database.set_typed_setting(CLIPBOARD_WATCHER_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to update clipboard watcher settings: {}", e))?;
+    }
+
+    if settings.enabled {
+        watcher.start(app, settings.clone());
+    } else {
+        watcher.stop();
+    }
+
+    Ok(settings)
+}
+
+/// Turn the watcher on or off, keeping the other settings
+#[tauri::command]
+pub async fn toggle_clipboard_watcher(
+    app: AppHandle,
+    state: State<'_, DatabaseState>,
+    watcher: State<'_, ClipboardWatcher>,
+    enabled: bool,
+) -> Result<ClipboardWatcherSettings, String> {
+    let _span = crate::metrics::command_span("toggle_clipboard_watcher");
+    let mut settings = get_clipboard_watcher_settings(state.clone()).await?;
+    settings.enabled = enabled;
+    update_clipboard_watcher_settings(app, state, watcher, settings).await
+}
+
+#[tauri::command]
+pub async fn is_clipboard_watcher_running(
+    watcher: State<'_, ClipboardWatcher>,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("is_clipboard_watcher_running");
+    Ok(watcher.is_running())
+}
@@ -0,0 +1,81 @@
+use tauri::State;
+use crate::companion::{load_companion_settings, CompanionServer, COMPANION_SETTINGS_KEY};
+use crate::database::CompanionServerSettings;
+use crate::commands::database::DatabaseState;
+use crate::local_server;
+
+// ===== Browser Extension Endpoint Commands =====
+
+#[tauri::command]
+pub async fn get_companion_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<CompanionServerSettings, String> {
+    let _span = crate::metrics::command_span("get_companion_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    load_companion_settings(database).await
+}
+
+/// Save the settings and start, restart or stop the endpoint to match. The token can only be
+/// changed with `regenerate_companion_token`.
+#[tauri::command]
+pub async fn update_companion_settings(
+    state: State<'_, DatabaseState>,
+    server: State<'_, CompanionServer>,
+    mut settings: CompanionServerSettings,
+) -> Result<CompanionServerSettings, String> {
+    let _span = crate::metrics::command_span("update_companion_settings");
+    local_server::validate_port(settings.port)?;
+
+    {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+        settings.token = load_companion_settings(database).await?.token;
+        database.set_typed_setting(COMPANION_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to update companion settings: {}", e))?;
+    }
+
+    if settings.enabled {
+        server.start(state.inner().clone(), &settings).await?;
+    } else {
+        server.stop().await;
+    }
+
+    Ok(settings)
+}
+
+/// Replace the token; the extension has to be given the new one
+#[tauri::command]
+pub async fn regenerate_companion_token(
+    state: State<'_, DatabaseState>,
+    server: State<'_, CompanionServer>,
+) -> Result<CompanionServerSettings, String> {
+    let _span = crate::metrics::command_span("regenerate_companion_token");
+    let settings = {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+        let mut settings = load_companion_settings(database).await?;
+        settings.token = Some(local_server::generate_token());
+        database.set_typed_setting(COMPANION_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to update companion settings: {}", e))?;
+        settings
+    };
+
+    // A running server still has the old token
+    if server.is_running() {
+        server.start(state.inner().clone(), &settings).await?;
+    }
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn is_companion_server_running(
+    server: State<'_, CompanionServer>,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("is_companion_server_running");
+    Ok(server.is_running())
+}
@@ -0,0 +1,725 @@
+use tauri::State;
+use crate::database::{
+    Database, Conversation, CreateConversationRequest, ConversationMessage,
+    CreateConversationMessageRequest, MessageSource, ContinueConversationRequest,
+    CreateMessageSourceRequest, DocumentChatResponse, MessageFootnote
+};
+use crate::database::sections::page_at;
+use crate::embeddings::{EmbeddingSearchResult, VectorService};
+use crate::ai::{AIProvider, ChatMessage, ChatCompletionRequest, chat_completion_for_provider};
+use crate::ai::profiles::apply_ai_profile;
+use crate::commands::ai::{load_provider_settings, load_guardrail_settings, fit_request_to_context, redact_chat_request};
+use crate::ai::{citations, guardrails};
+use crate::ai::memory::{build_context_messages, build_summary_messages, DEFAULT_KEEP_LAST_MESSAGES, SUMMARIZE_AFTER_MESSAGES};
+use tokio::sync::Mutex;
+use std::sync::Arc;
+
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+// Excerpts retrieved for document-grounded chat
+const DOCUMENT_CHAT_CHUNK_LIMIT: usize = 6;
+// Used when the document has no embeddings yet
+const DOCUMENT_CHAT_FALLBACK_CHARS: usize = 8_000;
+// Excerpts retrieved from the whole library for a question
+const LIBRARY_QUESTION_CHUNK_LIMIT: usize = 8;
+const CONVERSATION_TITLE_MAX_CHARS: usize = 60;
+
+// ===== Conversation Commands =====
+
+#[tauri::command]
+pub async fn create_conversation(
+    state: State<'_, DatabaseState>,
+    request: CreateConversationRequest,
+) -> Result<Conversation, String> {
+    let _span = crate::metrics::command_span("create_conversation");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.create_conversation(request).await
+        .map_err(|e| format!("Failed to create conversation: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_conversation(
+    state: State<'_, DatabaseState>,
+    conversation_id: String,
+) -> Result<Option<Conversation>, String> {
+    let _span = crate::metrics::command_span("get_conversation");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_conversation(&conversation_id).await
+        .map_err(|e| format!("Failed to get conversation: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_conversations(
+    state: State<'_, DatabaseState>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<Conversation>, String> {
+    let _span = crate::metrics::command_span("get_conversations");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_conversations(limit, offset).await
+        .map_err(|e| format!("Failed to get conversations: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_conversation(
+    state: State<'_, DatabaseState>,
+    conversation_id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_conversation");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.delete_conversation(&conversation_id).await
+        .map_err(|e| format!("Failed to delete conversation: {}", e))
+}
+
+// ===== Message Commands =====
+
+#[tauri::command]
+pub async fn add_conversation_message(
+    state: State<'_, DatabaseState>,
+    request: CreateConversationMessageRequest,
+) -> Result<ConversationMessage, String> {
+    let _span = crate::metrics::command_span("add_conversation_message");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    if database.get_conversation(&request.conversation_id).await
+        .map_err(|e| format!("Failed to get conversation: {}", e))?
+        .is_none()
+    {
+        return Err(format!("Conversation not found: {}", request.conversation_id));
+    }
+
+    database.add_conversation_message(request).await
+        .map_err(|e| format!("Failed to add message: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_conversation_messages(
+    state: State<'_, DatabaseState>,
+    conversation_id: String,
+) -> Result<Vec<ConversationMessage>, String> {
+    let _span = crate::metrics::command_span("get_conversation_messages");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_conversation_messages(&conversation_id).await
+        .map_err(|e| format!("Failed to get messages: {}", e))
+}
+
+/// Get the documents, chunks, and highlights a message was grounded on
+#[tauri::command]
+pub async fn get_message_sources(
+    state: State<'_, DatabaseState>,
+    message_id: String,
+) -> Result<Vec<MessageSource>, String> {
+    let _span = crate::metrics::command_span("get_message_sources");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_message_sources(&message_id).await
+        .map_err(|e| format!("Failed to get message sources: {}", e))
+}
+
+// ===== Conversation Memory Commands =====
+
+/// Send a new user message in a stored conversation. The model sees the rolling summary plus
+/// the recent messages, and older messages are folded into the summary once enough pile up.
+#[tauri::command]
+pub async fn ai_continue_conversation(
+    state: State<'_, DatabaseState>,
+    mut provider: AIProvider,
+    model: String,
+    request: ContinueConversationRequest,
+) -> Result<ConversationMessage, String> {
+    let _span = crate::metrics::command_span("ai_continue_conversation");
+    let keep_last = request.keep_last.unwrap_or(DEFAULT_KEEP_LAST_MESSAGES).max(1);
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let conversation = database.get_conversation(&request.conversation_id).await
+        .map_err(|e| format!("Failed to get conversation: {}", e))?
+        .ok_or_else(|| format!("Conversation not found: {}", request.conversation_id))?;
+    let api_key = database.get_api_key(&provider.id).await
+        .map_err(|e| format!("Failed to get API key: {}", e))?;
+    load_provider_settings(database, &mut provider).await?;
+    let profile = crate::commands::ai::load_ai_profile(database, request.profile_id.as_deref()).await?;
+    let guardrail_settings = load_guardrail_settings(database).await?;
+
+    database.add_conversation_message(CreateConversationMessageRequest {
+        id: None,
+        conversation_id: conversation.id.clone(),
+        role: "user".to_string(),
+        content: request.content.clone(),
+        model: None,
+        provider_id: None,
+        sources: request.sources,
+        metadata: None,
+    }).await
+        .map_err(|e| format!("Failed to save message: {}", e))?;
+
+    let history = database.get_conversation_messages(&conversation.id).await
+        .map_err(|e| format!("Failed to get messages: {}", e))?;
+    drop(db_state);
+
+    // Only messages not yet folded into the summary are sent verbatim
+    let unsummarized: Vec<ChatMessage> = history.iter()
+        .skip(conversation.summarized_message_count.max(0) as usize)
+        .map(|m| ChatMessage { role: m.role.clone(), content: m.content.clone() })
+        .collect();
+
+    let mut model = model;
+    let mut chat_request = ChatCompletionRequest {
+        messages: build_context_messages(
+            request.system_prompt.as_deref(),
+            conversation.summary.as_deref(),
+            &unsummarized,
+            keep_last + SUMMARIZE_AFTER_MESSAGES,
+        ),
+        model: model.clone(),
+        temperature: request.temperature,
+        max_tokens: request.max_tokens,
+        top_p: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        stream: Some(false),
+    };
+    if let Some(profile) = &profile {
+        apply_ai_profile(profile, &mut model, &mut chat_request);
+    }
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let context_warning = fit_request_to_context(database, &provider, &model, &mut chat_request).await?;
+    redact_chat_request(database, &provider, &mut chat_request).await?;
+    drop(db_state);
+
+    let response = chat_completion_for_provider(&provider, &model, &chat_request, api_key.clone()).await?;
+    let mut reply = response.choices.first()
+        .map(|choice| choice.message.content.clone())
+        .unwrap_or_default();
+    let mut output_flags = Vec::new();
+    if guardrail_settings.filter_output {
+        let system_prompt = chat_request.messages.iter().find(|m| m.role == "system").map(|m| m.content.as_str());
+        let report = guardrails::filter_output(&reply, system_prompt);
+        reply = report.content;
+        output_flags = report.flags;
+    }
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let assistant_message = database.add_conversation_message(CreateConversationMessageRequest {
+        id: None,
+        conversation_id: conversation.id.clone(),
+        role: "assistant".to_string(),
+        content: reply,
+        model: Some(model.clone()),
+        provider_id: Some(provider.id.clone()),
+        sources: Vec::new(),
+        metadata: Some(serde_json::json!({
+            "prompt_tokens": response.usage.prompt_tokens,
+            "completion_tokens": response.usage.completion_tokens,
+            "context_warning": context_warning,
+            "output_filter_flags": output_flags,
+        })),
+    }).await
+        .map_err(|e| format!("Failed to save reply: {}", e))?;
+    drop(db_state);
+
+    // unsummarized + the reply we just stored
+    if unsummarized.len() + 1 > keep_last + SUMMARIZE_AFTER_MESSAGES {
+        if let Err(e) = fold_conversation_summary(&state, &provider, &model, api_key, &conversation.id, keep_last).await {
+            eprintln!("⚠️ Failed to update summary for conversation {}: {}", conversation.id, e);
+        }
+    }
+
+    Ok(assistant_message)
+}
+
+/// Fold everything but the last `keep_last` messages into the conversation summary now
+#[tauri::command]
+pub async fn summarize_conversation(
+    state: State<'_, DatabaseState>,
+    mut provider: AIProvider,
+    model: String,
+    conversation_id: String,
+    keep_last: Option<usize>,
+) -> Result<Option<String>, String> {
+    let _span = crate::metrics::command_span("summarize_conversation");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let api_key = database.get_api_key(&provider.id).await
+        .map_err(|e| format!("Failed to get API key: {}", e))?;
+    load_provider_settings(database, &mut provider).await?;
+    drop(db_state);
+
+    fold_conversation_summary(
+        &state,
+        &provider,
+        &model,
+        api_key,
+        &conversation_id,
+        keep_last.unwrap_or(DEFAULT_KEEP_LAST_MESSAGES),
+    ).await
+}
+
+// Fold the messages between the current summary and the recent window into the summary
+async fn fold_conversation_summary(
+    state: &State<'_, DatabaseState>,
+    provider: &AIProvider,
+    model: &str,
+    api_key: Option<String>,
+    conversation_id: &str,
+    keep_last: usize,
+) -> Result<Option<String>, String> {
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let conversation = database.get_conversation(conversation_id).await
+        .map_err(|e| format!("Failed to get conversation: {}", e))?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let history = database.get_conversation_messages(conversation_id).await
+        .map_err(|e| format!("Failed to get messages: {}", e))?;
+    drop(db_state);
+
+    let already_summarized = conversation.summarized_message_count.max(0) as usize;
+    let fold_until = history.len().saturating_sub(keep_last);
+    if fold_until <= already_summarized {
+        return Ok(conversation.summary);
+    }
+
+    let to_fold: Vec<ChatMessage> = history[already_summarized..fold_until].iter()
+        .map(|m| ChatMessage { role: m.role.clone(), content: m.content.clone() })
+        .collect();
+
+    let mut request = ChatCompletionRequest {
+        messages: build_summary_messages(conversation.summary.as_deref(), &to_fold),
+        model: model.to_string(),
+        temperature: Some(0.2),
+        max_tokens: Some(800),
+        top_p: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        stream: Some(false),
+    };
+    {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+        redact_chat_request(database, provider, &mut request).await?;
+    }
+
+    let response = chat_completion_for_provider(provider, model, &request, api_key).await?;
+    let summary = response.choices.first()
+        .map(|choice| choice.message.content.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .ok_or("Model returned an empty summary")?;
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    database.update_conversation_summary(conversation_id, &summary, fold_until as i64).await
+        .map_err(|e| format!("Failed to save summary: {}", e))?;
+
+    println!("🧠 Folded {} messages into summary for conversation {}", to_fold.len(), conversation_id);
+
+    Ok(Some(summary))
+}
+
+// ===== Document Chat Commands =====
+
+/// Chat about a single document. Retrieval is restricted to that document's chunks so
+/// context from other documents never leaks into the answer. Citations in the answer become
+/// footnotes pointing at the excerpt and page they came from.
+#[tauri::command]
+pub async fn ai_chat_about_document(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    mut provider: AIProvider,
+    model: String,
+    document_id: String,
+    message: String,
+    conversation_id: Option<String>,
+) -> Result<DocumentChatResponse, String> {
+    let _span = crate::metrics::command_span("ai_chat_about_document");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let document = database.get_document(&document_id).await
+        .map_err(|e| format!("Failed to get document: {}", e))?
+        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+    let category_name = match &document.category_id {
+        Some(category_id) => database.get_category(category_id).await
+            .map_err(|e| format!("Failed to get category: {}", e))?
+            .map(|c| c.name),
+        None => None,
+    };
+    let api_key = database.get_api_key(&provider.id).await
+        .map_err(|e| format!("Failed to get API key: {}", e))?;
+    load_provider_settings(database, &mut provider).await?;
+
+    let conversation = match conversation_id {
+        Some(id) => database.get_conversation(&id).await
+            .map_err(|e| format!("Failed to get conversation: {}", e))?
+            .ok_or_else(|| format!("Conversation not found: {}", id))?,
+        None => database.create_conversation(CreateConversationRequest {
+            id: None,
+            title: format!("Chat: {}", document.title),
+            model: Some(model.clone()),
+            provider_id: Some(provider.id.clone()),
+            session_id: None,
+            conversation_type: Some("question".to_string()),
+            metadata: Some(serde_json::json!({ "document_id": document.id })),
+        }).await
+            .map_err(|e| format!("Failed to create conversation: {}", e))?,
+    };
+
+    database.add_conversation_message(CreateConversationMessageRequest {
+        id: None,
+        conversation_id: conversation.id.clone(),
+        role: "user".to_string(),
+        content: message.clone(),
+        model: None,
+        provider_id: None,
+        sources: Vec::new(),
+        metadata: Some(serde_json::json!({ "document_id": document.id })),
+    }).await
+        .map_err(|e| format!("Failed to save message: {}", e))?;
+    drop(db_state);
+
+    // Retrieve only from this document's chunks
+    let mut sources: Vec<CreateMessageSourceRequest> = Vec::new();
+    {
+        let mut vector_guard = vector_state.lock().await;
+        if let Some(vector_service) = vector_guard.as_mut() {
+            match vector_service.search_similar(&message, DOCUMENT_CHAT_CHUNK_LIMIT, Some(std::slice::from_ref(&document.id))).await {
+                Ok(results) => {
+                    sources = results.into_iter()
+                        .map(|result| chunk_source(result, Some(&document.content)))
+                        .collect();
+                }
+                Err(e) => eprintln!("⚠️ Document-scoped search failed, using document text instead: {}", e),
+            }
+        }
+    }
+
+    if sources.is_empty() {
+        let excerpt: String = document.content.chars().take(DOCUMENT_CHAT_FALLBACK_CHARS).collect();
+        sources.push(CreateMessageSourceRequest {
+            source_type: "document".to_string(),
+            document_id: Some(document.id.clone()),
+            chunk_id: None,
+            end_offset: Some(excerpt.chars().count() as i64),
+            content: Some(excerpt),
+            start_offset: Some(0),
+            score: None,
+            metadata: Some(serde_json::json!({ "page": page_at(&document.content, 0) })),
+        });
+    }
+    let titles = vec![document.title.clone(); sources.len()];
+
+    let prompt_intro = format!(
+        "You are helping a student with one document from their library.\n\
+         Title: {}\nType: {}\nCategory: {}\nTags: {}\nStatus: {}\n\n\
+         Answer using only the excerpts below from this document. If they don't cover the question, say so instead of drawing on other material.",
+        document.title,
+        document.doc_type,
+        category_name.as_deref().unwrap_or("Uncategorized"),
+        if document.tags.is_empty() { "none".to_string() } else { document.tags.join(", ") },
+        document.status,
+    );
+
+    answer_from_sources(
+        &state,
+        &provider,
+        &model,
+        api_key,
+        &conversation.id,
+        &prompt_intro,
+        sources,
+        &titles,
+        serde_json::json!({ "document_id": document.id }),
+    ).await
+}
+
+/// Answer a question from the whole library (or only `document_ids`), with every claim
+/// footnoted to the document, page and chunk it came from. Documents in archived categories
+/// are left out unless listed.
+#[tauri::command]
+pub async fn ai_ask_library(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    mut provider: AIProvider,
+    model: String,
+    question: String,
+    conversation_id: Option<String>,
+    document_ids: Option<Vec<String>>,
+) -> Result<DocumentChatResponse, String> {
+    let _span = crate::metrics::command_span("ai_ask_library");
+    if question.trim().is_empty() {
+        return Err("Ask a question first".to_string());
+    }
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let api_key = database.get_api_key(&provider.id).await
+        .map_err(|e| format!("Failed to get API key: {}", e))?;
+    load_provider_settings(database, &mut provider).await?;
+    let archived_document_ids = match document_ids {
+        Some(_) => Vec::new(),
+        None => database.get_archived_document_ids().await
+            .map_err(|e| format!("Failed to get archived documents: {}", e))?,
+    };
+
+    let conversation = match conversation_id {
+        Some(id) => database.get_conversation(&id).await
+            .map_err(|e| format!("Failed to get conversation: {}", e))?
+            .ok_or_else(|| format!("Conversation not found: {}", id))?,
+        None => {
+            let mut title: String = question.trim().chars().take(CONVERSATION_TITLE_MAX_CHARS).collect();
+            if question.trim().chars().count() > CONVERSATION_TITLE_MAX_CHARS {
+                title.push('…');
+            }
+            database.create_conversation(CreateConversationRequest {
+                id: None,
+                title,
+                model: Some(model.clone()),
+                provider_id: Some(provider.id.clone()),
+                session_id: None,
+                conversation_type: Some("question".to_string()),
+                metadata: Some(serde_json::json!({ "document_ids": document_ids })),
+            }).await
+                .map_err(|e| format!("Failed to create conversation: {}", e))?
+        }
+    };
+
+    database.add_conversation_message(CreateConversationMessageRequest {
+        id: None,
+        conversation_id: conversation.id.clone(),
+        role: "user".to_string(),
+        content: question.clone(),
+        model: None,
+        provider_id: None,
+        sources: Vec::new(),
+        metadata: None,
+    }).await
+        .map_err(|e| format!("Failed to save message: {}", e))?;
+    drop(db_state);
+
+    let results = {
+        let mut vector_guard = vector_state.lock().await;
+        let vector_service = vector_guard.as_mut().ok_or("Vector service not initialized")?;
+        match &document_ids {
+            Some(document_ids) => vector_service.search_similar(&question, LIBRARY_QUESTION_CHUNK_LIMIT, Some(document_ids.as_slice())).await,
+            None => vector_service.search_similar_excluding(&question, LIBRARY_QUESTION_CHUNK_LIMIT, &archived_document_ids).await,
+        }
+        .map_err(|e| format!("Search failed: {}", e))?
+    };
+
+    // Each document is loaded once, for its title and to find the cited pages
+    let mut sources = Vec::new();
+    let mut titles = Vec::new();
+    {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+        let mut documents = std::collections::HashMap::new();
+        for result in results {
+            let document_id = result.chunk.document_id.clone();
+            if !documents.contains_key(&document_id) {
+                let document = database.get_document(&document_id).await
+                    .map_err(|e| format!("Failed to get document: {}", e))?;
+                documents.insert(document_id.clone(), document);
+            }
+            // Chunks of deleted documents linger until the next cleanup
+            let Some(document) = documents.get(&document_id).and_then(|document| document.as_ref()) else {
+                continue;
+            };
+            titles.push(document.title.clone());
+            sources.push(chunk_source(result, Some(&document.content)));
+        }
+    }
+
+    let prompt_intro = "You are helping a student with questions about their study library.\n\
+        Answer using only the excerpts below from their documents. If they don't cover the question, say so instead of drawing on other material.";
+
+    answer_from_sources(
+        &state,
+        &provider,
+        &model,
+        api_key,
+        &conversation.id,
+        prompt_intro,
+        sources,
+        &titles,
+        serde_json::json!({ "document_ids": document_ids }),
+    ).await
+}
+
+/// Footnotes of an answer, each pointing at the document, page and passage it cites
+#[tauri::command]
+pub async fn get_message_footnotes(
+    state: State<'_, DatabaseState>,
+    message_id: String,
+) -> Result<Vec<MessageFootnote>, String> {
+    let _span = crate::metrics::command_span("get_message_footnotes");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_message_footnotes(&message_id).await
+        .map_err(|e| format!("Failed to get message footnotes: {}", e))
+}
+
+// A retrieved chunk as a message source, located in its document so a citation of it can
+// point at the passage and page
+fn chunk_source(result: EmbeddingSearchResult, document_content: Option<&str>) -> CreateMessageSourceRequest {
+    let range = document_content.and_then(|content| citations::locate_excerpt(content, &result.chunk.content));
+    let page = document_content.zip(range).and_then(|(content, (start, _))| page_at(content, start));
+    CreateMessageSourceRequest {
+        source_type: "chunk".to_string(),
+        document_id: Some(result.chunk.document_id),
+        chunk_id: Some(result.chunk.id),
+        content: Some(result.chunk.content),
+        start_offset: range.map(|(start, _)| start as i64),
+        end_offset: range.map(|(_, end)| end as i64),
+        score: Some(result.score as f64),
+        metadata: Some(serde_json::json!({ "chunk_index": result.chunk.chunk_index, "page": page })),
+    }
+}
+
+// What document chat and library questions share once the excerpts are retrieved: show them
+// to the model numbered, footnote the citations in its reply and store the reply with its
+// sources. `titles` has the document title of each source.
+#[allow(clippy::too_many_arguments)]
+async fn answer_from_sources(
+    state: &State<'_, DatabaseState>,
+    provider: &AIProvider,
+    model: &str,
+    api_key: Option<String>,
+    conversation_id: &str,
+    prompt_intro: &str,
+    mut sources: Vec<CreateMessageSourceRequest>,
+    titles: &[String],
+    metadata: serde_json::Value,
+) -> Result<DocumentChatResponse, String> {
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let guardrail_settings = load_guardrail_settings(database).await?;
+    let conversation = database.get_conversation(conversation_id).await
+        .map_err(|e| format!("Failed to get conversation: {}", e))?
+        .ok_or_else(|| format!("Conversation not found: {}", conversation_id))?;
+    let history = database.get_conversation_messages(conversation_id).await
+        .map_err(|e| format!("Failed to get messages: {}", e))?;
+    drop(db_state);
+
+    let labels: Vec<String> = sources.iter()
+        .zip(titles)
+        .map(|(source, title)| {
+            let page = source.metadata.as_ref()
+                .and_then(|metadata| metadata.get("page"))
+                .and_then(|page| page.as_i64())
+                .map(|page| page as i32);
+            citations::source_label(title, page)
+        })
+        .collect();
+    let excerpts = sources.iter()
+        .zip(&labels)
+        .enumerate()
+        .map(|(i, (source, label))| guardrails::prepare_context_block(
+            &guardrail_settings,
+            &format!("[{}] {}", i + 1, label),
+            source.content.as_deref().unwrap_or_default(),
+        ))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let system_prompt = format!(
+        "{} {}\n{}\n\n{}",
+        prompt_intro,
+        citations::CITATION_INSTRUCTIONS,
+        guardrails::CONTEXT_GUARD_INSTRUCTIONS,
+        excerpts
+    );
+
+    let unsummarized: Vec<ChatMessage> = history.iter()
+        .skip(conversation.summarized_message_count.max(0) as usize)
+        .map(|m| ChatMessage { role: m.role.clone(), content: m.content.clone() })
+        .collect();
+
+    let mut chat_request = ChatCompletionRequest {
+        messages: build_context_messages(
+            Some(&system_prompt),
+            conversation.summary.as_deref(),
+            &unsummarized,
+            DEFAULT_KEEP_LAST_MESSAGES + SUMMARIZE_AFTER_MESSAGES,
+        ),
+        model: model.to_string(),
+        temperature: None,
+        max_tokens: None,
+        top_p: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        stream: Some(false),
+    };
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let context_warning = fit_request_to_context(database, provider, model, &mut chat_request).await?;
+    redact_chat_request(database, provider, &mut chat_request).await?;
+    drop(db_state);
+
+    let response = chat_completion_for_provider(provider, model, &chat_request, api_key).await?;
+    let mut reply = response.choices.first()
+        .map(|choice| choice.message.content.clone())
+        .unwrap_or_default();
+    let mut output_flags = Vec::new();
+    if guardrail_settings.filter_output {
+        let report = guardrails::filter_output(&reply, Some(&system_prompt));
+        reply = report.content;
+        output_flags = report.flags;
+    }
+
+    // Footnote numbers go on the cited sources; uncited ones are kept as what the model was shown
+    let answer = citations::footnote_answer(&reply, &labels);
+    for (footnote, index) in &answer.footnotes {
+        if let Some(source_metadata) = sources[*index].metadata.get_or_insert_with(|| serde_json::json!({})).as_object_mut() {
+            source_metadata.insert("footnote".to_string(), serde_json::json!(footnote));
+        }
+    }
+
+    let mut metadata = metadata;
+    metadata["context_warning"] = serde_json::json!(context_warning);
+    metadata["output_filter_flags"] = serde_json::json!(output_flags);
+    metadata["footnote_count"] = serde_json::json!(answer.footnotes.len());
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let assistant_message = database.add_conversation_message(CreateConversationMessageRequest {
+        id: None,
+        conversation_id: conversation.id.clone(),
+        role: "assistant".to_string(),
+        content: answer.content,
+        model: Some(model.to_string()),
+        provider_id: Some(provider.id.clone()),
+        sources,
+        metadata: Some(metadata),
+    }).await
+        .map_err(|e| format!("Failed to save reply: {}", e))?;
+
+    let sources = database.get_message_sources(&assistant_message.id).await
+        .map_err(|e| format!("Failed to get message sources: {}", e))?;
+    let footnotes = database.get_message_footnotes(&assistant_message.id).await
+        .map_err(|e| format!("Failed to get message footnotes: {}", e))?;
+
+    Ok(DocumentChatResponse {
+        conversation_id: conversation.id,
+        message: assistant_message,
+        sources,
+        footnotes,
+    })
+}
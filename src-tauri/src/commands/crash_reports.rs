@@ -0,0 +1,25 @@
+use crate::crash_reports::{self, CrashReport};
+
+// ===== Crash Report Commands =====
+
+/// Panics and reported command errors, newest first
+#[tauri::command]
+pub async fn get_crash_reports() -> Result<Vec<CrashReport>, String> {
+    let _span = crate::metrics::command_span("get_crash_reports");
+    crash_reports::get_crash_reports()
+}
+
+#[tauri::command]
+pub async fn clear_crash_reports() -> Result<usize, String> {
+    let _span = crate::metrics::command_span("clear_crash_reports");
+    let removed = crash_reports::clear_crash_reports()?;
+    println!("🧹 Cleared {} crash reports", removed);
+    Ok(removed)
+}
+
+/// Called by the frontend when an `invoke` fails, so command errors end up next to panics
+#[tauri::command]
+pub async fn report_command_error(command: String, error: String) -> Result<CrashReport, String> {
+    let _span = crate::metrics::command_span("report_command_error");
+    crash_reports::record_command_error(&command, &error)
+}
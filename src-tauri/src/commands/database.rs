@@ -1,37 +1,37 @@
-use crate::database::{Database, Document, CreateDocumentRequest, Category, CreateCategoryRequest};
-use crate::commands::pdf::delete_pdf_file;
+use crate::database::{
+    Database, Document, CreateDocumentRequest, Category, CreateCategoryRequest,
+    BulkDocumentChanges, BulkUpdateResult, BulkDeleteResult,
+    DocumentSection, SplitDocumentRequest, SplitDocumentResult, CodeSnippet,
+    DocumentOutline, OutlineSectionContent, DocumentContentRange, QuickSearchResults,
+    NoteTemplate, SaveNoteTemplateRequest,
+};
+use std::collections::HashMap;
+use crate::ai::AIProvider;
+use crate::commands::embeddings::EMBEDDINGS_OPTIMIZATION_KEY;
+use crate::embeddings::VectorService;
+use crate::services::DocumentService;
 use tauri::State;
 use tokio::sync::Mutex;
 use std::sync::Arc;
 
 pub type DatabaseState = Arc<Mutex<Option<Database>>>;
+type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+pub use crate::services::documents::INBOX_TAG;
+
+fn document_service(state: &State<'_, DatabaseState>, vector_state: &State<'_, VectorServiceState>) -> DocumentService {
+    DocumentService::new(state.inner().clone(), vector_state.inner().clone())
+}
 
 #[tauri::command]
 pub async fn init_database(state: State<'_, DatabaseState>) -> Result<(), String> {
+    let _span = crate::metrics::command_span("init_database");
     println!("DEBUG: Starting database initialization...");
     
     let mut db_state = state.lock().await;
     
-    // Use the user's home directory for app data to remain consistent with existing installs/data
-    let home_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?;
-    
-    let app_data_dir = home_dir.join("stellar_data");
-    let db_path = app_data_dir.join("documents.db");
-    
-    println!("DEBUG: Database directory: {:?}", app_data_dir);
-    println!("DEBUG: Database path: {:?}", db_path);
-    
-    // Ensure directory exists
-    if let Err(e) = std::fs::create_dir_all(&app_data_dir) {
-        let error_msg = format!("Failed to create app directory: {}", e);
-        println!("DEBUG: {}", error_msg);
-        return Err(error_msg);
-    }
-    println!("DEBUG: Directory created successfully");
-    
-    // Try using the proper SQLite URL format with connection options
-    let database_url = format!("sqlite://{}?mode=rwc", db_path.to_string_lossy());
+    // ~/stellar_data unless STELLAR_DATA_DIR overrides it; created if missing
+    let database_url = crate::paths::database_url()?;
     println!("DEBUG: Database URL: {}", database_url);
     
     let database = Database::new(&database_url).await
@@ -50,75 +50,108 @@ pub async fn init_database(state: State<'_, DatabaseState>) -> Result<(), String
 #[tauri::command]
 pub async fn create_document(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     request: CreateDocumentRequest,
 ) -> Result<Document, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.create_document(request).await
-        .map_err(|e| format!("Failed to create document: {}", e))
+    let _span = crate::metrics::command_span("create_document");
+    document_service(&state, &vector_state).create_document(request).await
 }
 
+/// Save a short note tagged "inbox" without opening the editor, e.g. from a global shortcut while
+/// reading. With a provider and model, an auto-tagging job is queued to add topic tags.
 #[tauri::command]
-pub async fn get_all_documents(state: State<'_, DatabaseState>) -> Result<Vec<Document>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_all_documents().await
-        .map_err(|e| format!("Failed to get documents: {}", e))
+pub async fn quick_capture(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    text: String,
+    source_url: Option<String>,
+    provider: Option<AIProvider>,
+    model: Option<String>,
+) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("quick_capture");
+    document_service(&state, &vector_state).quick_capture(&text, source_url, provider, model).await
 }
 
 #[tauri::command]
-pub async fn get_document(state: State<'_, DatabaseState>, id: String) -> Result<Option<Document>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_document(&id).await
-        .map_err(|e| format!("Failed to get document: {}", e))
+pub async fn get_all_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<Document>, String> {
+    let _span = crate::metrics::command_span("get_all_documents");
+    document_service(&state, &vector_state).get_all_documents(include_archived.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn get_document(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+    include_content: Option<bool>,
+) -> Result<Option<Document>, String> {
+    let _span = crate::metrics::command_span("get_document");
+    let documents = document_service(&state, &vector_state);
+    if include_content.unwrap_or(true) {
+        documents.get_document(&id).await
+    } else {
+        documents.get_document_without_content(&id).await
+    }
+}
+
+#[tauri::command]
+pub async fn get_document_content_range(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+    offset: i64,
+    length: i64,
+) -> Result<DocumentContentRange, String> {
+    let _span = crate::metrics::command_span("get_document_content_range");
+    document_service(&state, &vector_state).get_document_content_range(&id, offset, length).await
 }
 
 #[tauri::command]
 pub async fn update_document(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
     request: CreateDocumentRequest,
 ) -> Result<Option<Document>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.update_document(&id, request).await
-        .map_err(|e| format!("Failed to update document: {}", e))
+    let _span = crate::metrics::command_span("update_document");
+    document_service(&state, &vector_state).update_document(&id, request).await
 }
 
 #[tauri::command]
-pub async fn delete_document(state: State<'_, DatabaseState>, id: String) -> Result<bool, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    // First, get the document to check if it has a PDF file to clean up
-    let document = database.get_document(&id).await
-        .map_err(|e| format!("Failed to get document for deletion: {}", e))?;
-    
-    // Delete the document from the database
-    let deleted = database.delete_document(&id).await
-        .map_err(|e| format!("Failed to delete document: {}", e))?;
-    
-    // If document was deleted and it's a PDF with a file_path, clean up the PDF file
-    if deleted {
-        if let Some(doc) = document {
-            if doc.doc_type == "pdf" {
-                if let Some(file_path) = doc.file_path {
-                    // Attempt to delete the PDF file, but don't fail the entire operation if this fails
-                    match delete_pdf_file(file_path).await {
-                        Ok(_) => println!("DEBUG: Successfully cleaned up PDF file for document {}", id),
-                        Err(e) => println!("DEBUG: Failed to clean up PDF file for document {}: {}", id, e),
-                    }
-                }
-            }
-        }
-    }
-    
-    Ok(deleted)
+pub async fn delete_document(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_document");
+    document_service(&state, &vector_state).delete_document(&id).await
+}
+
+/// Move, retag, or change the status of many documents at once
+#[tauri::command]
+pub async fn bulk_update_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    ids: Vec<String>,
+    changes: BulkDocumentChanges,
+) -> Result<BulkUpdateResult, String> {
+    let _span = crate::metrics::command_span("bulk_update_documents");
+    document_service(&state, &vector_state).bulk_update_documents(ids, &changes).await
+}
+
+/// Delete many documents at once, then remove their PDF files and embeddings
+#[tauri::command]
+pub async fn bulk_delete_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    ids: Vec<String>,
+) -> Result<BulkDeleteResult, String> {
+    let _span = crate::metrics::command_span("bulk_delete_documents");
+    document_service(&state, &vector_state).bulk_delete_documents(ids).await
 }
 
 #[tauri::command]
@@ -127,6 +160,7 @@ pub async fn store_api_key(
     provider_id: String,
     api_key: String,
 ) -> Result<(), String> {
+    let _span = crate::metrics::command_span("store_api_key");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -139,6 +173,7 @@ pub async fn get_api_key(
     state: State<'_, DatabaseState>,
     provider_id: String,
 ) -> Result<Option<String>, String> {
+    let _span = crate::metrics::command_span("get_api_key");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -151,6 +186,7 @@ pub async fn delete_api_key(
     state: State<'_, DatabaseState>,
     provider_id: String,
 ) -> Result<(), String> {
+    let _span = crate::metrics::command_span("delete_api_key");
     let db_state = state.lock().await;
     let database = db_state.as_ref().ok_or("Database not initialized")?;
     
@@ -164,109 +200,302 @@ pub async fn delete_api_key(
 #[tauri::command]
 pub async fn create_category(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     request: CreateCategoryRequest,
 ) -> Result<Category, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.create_category(request).await
-        .map_err(|e| format!("Failed to create category: {}", e))
+    let _span = crate::metrics::command_span("create_category");
+    document_service(&state, &vector_state).create_category(request).await
 }
 
 #[tauri::command]
-pub async fn get_all_categories(state: State<'_, DatabaseState>) -> Result<Vec<Category>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_all_categories().await
-        .map_err(|e| format!("Failed to get categories: {}", e))
+pub async fn get_all_categories(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<Vec<Category>, String> {
+    let _span = crate::metrics::command_span("get_all_categories");
+    document_service(&state, &vector_state).get_all_categories().await
 }
 
 #[tauri::command]
-pub async fn get_category(state: State<'_, DatabaseState>, id: String) -> Result<Option<Category>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_category(&id).await
-        .map_err(|e| format!("Failed to get category: {}", e))
+pub async fn get_category(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+) -> Result<Option<Category>, String> {
+    let _span = crate::metrics::command_span("get_category");
+    document_service(&state, &vector_state).get_category(&id).await
 }
 
 #[tauri::command]
 pub async fn update_category(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
     request: CreateCategoryRequest,
 ) -> Result<Option<Category>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.update_category(&id, request).await
-        .map_err(|e| format!("Failed to update category: {}", e))
+    let _span = crate::metrics::command_span("update_category");
+    document_service(&state, &vector_state).update_category(&id, request).await
 }
 
+// Note templates
+
 #[tauri::command]
-pub async fn delete_category(state: State<'_, DatabaseState>, id: String) -> Result<bool, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.delete_category(&id).await
-        .map_err(|e| format!("Failed to delete category: {}", e))
+pub async fn get_note_templates(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<Vec<NoteTemplate>, String> {
+    let _span = crate::metrics::command_span("get_note_templates");
+    document_service(&state, &vector_state).get_note_templates().await
+}
+
+#[tauri::command]
+pub async fn save_note_template(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    request: SaveNoteTemplateRequest,
+) -> Result<NoteTemplate, String> {
+    let _span = crate::metrics::command_span("save_note_template");
+    document_service(&state, &vector_state).save_note_template(request).await
+}
+
+#[tauri::command]
+pub async fn delete_note_template(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    template_id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_note_template");
+    document_service(&state, &vector_state).delete_note_template(&template_id).await
+}
+
+/// Create a note from a template, filling date, time, course and lecture number automatically;
+/// `variables` overrides those and fills the template's own placeholders (e.g. "topic")
+#[tauri::command]
+pub async fn create_note_from_template(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    template_id: String,
+    variables: Option<HashMap<String, String>>,
+    category_id: Option<String>,
+) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("create_note_from_template");
+    document_service(&state, &vector_state)
+        .create_note_from_template(&template_id, variables.unwrap_or_default(), category_id).await
+}
+
+#[tauri::command]
+pub async fn archive_category(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+) -> Result<Option<Category>, String> {
+    let _span = crate::metrics::command_span("archive_category");
+    document_service(&state, &vector_state).archive_category(&id).await
+}
+
+#[tauri::command]
+pub async fn unarchive_category(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+) -> Result<Option<Category>, String> {
+    let _span = crate::metrics::command_span("unarchive_category");
+    document_service(&state, &vector_state).unarchive_category(&id).await
+}
+
+#[tauri::command]
+pub async fn delete_category(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_category");
+    document_service(&state, &vector_state).delete_category(&id).await
 }
 
 #[tauri::command]
 pub async fn get_documents_by_category(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     category_id: String,
 ) -> Result<Vec<Document>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_documents_by_category(&category_id).await
-        .map_err(|e| format!("Failed to get documents by category: {}", e))
+    let _span = crate::metrics::command_span("get_documents_by_category");
+    document_service(&state, &vector_state).get_documents_by_category(&category_id).await
 }
 
 #[tauri::command]
-pub async fn get_uncategorized_documents(state: State<'_, DatabaseState>) -> Result<Vec<Document>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_uncategorized_documents().await
-        .map_err(|e| format!("Failed to get uncategorized documents: {}", e))
+pub async fn get_uncategorized_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<Vec<Document>, String> {
+    let _span = crate::metrics::command_span("get_uncategorized_documents");
+    document_service(&state, &vector_state).get_uncategorized_documents().await
+}
+
+// Document section commands
+#[tauri::command]
+pub async fn split_document(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+    request: SplitDocumentRequest,
+) -> Result<SplitDocumentResult, String> {
+    let _span = crate::metrics::command_span("split_document");
+    document_service(&state, &vector_state).split_document(&document_id, &request).await
+}
+
+#[tauri::command]
+pub async fn get_document_sections(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+) -> Result<Vec<DocumentSection>, String> {
+    let _span = crate::metrics::command_span("get_document_sections");
+    document_service(&state, &vector_state).get_document_sections(&document_id).await
+}
+
+#[tauri::command]
+pub async fn get_document_outline(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+) -> Result<DocumentOutline, String> {
+    let _span = crate::metrics::command_span("get_document_outline");
+    document_service(&state, &vector_state).get_document_outline(&document_id).await
+}
+
+#[tauri::command]
+pub async fn get_document_outline_section(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+    entry_index: i32,
+) -> Result<OutlineSectionContent, String> {
+    let _span = crate::metrics::command_span("get_document_outline_section");
+    document_service(&state, &vector_state).get_document_outline_section(&document_id, entry_index).await
+}
+
+/// Cover thumbnail of a document as a PNG data URL that fits in `size` x `size` pixels
+/// (256 by default), or null when the document has no cover
+#[tauri::command]
+pub async fn get_document_thumbnail(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+    size: Option<u32>,
+) -> Result<Option<String>, String> {
+    let _span = crate::metrics::command_span("get_document_thumbnail");
+    document_service(&state, &vector_state).get_document_thumbnail(&id, size).await
+}
+
+#[tauri::command]
+pub async fn get_child_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+) -> Result<Vec<Document>, String> {
+    let _span = crate::metrics::command_span("get_child_documents");
+    document_service(&state, &vector_state).get_child_documents(&document_id).await
+}
+
+// Recent & pinned document commands
+#[tauri::command]
+pub async fn touch_document(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("touch_document");
+    document_service(&state, &vector_state).touch_document(&id).await
+}
+
+#[tauri::command]
+pub async fn get_recent_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    limit: Option<i64>,
+) -> Result<Vec<Document>, String> {
+    let _span = crate::metrics::command_span("get_recent_documents");
+    document_service(&state, &vector_state).get_recent_documents(limit).await
+}
+
+#[tauri::command]
+pub async fn pin_document(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+    pinned: Option<bool>,
+) -> Result<Option<Document>, String> {
+    let _span = crate::metrics::command_span("pin_document");
+    document_service(&state, &vector_state).pin_document(&id, pinned.unwrap_or(true)).await
+}
+
+#[tauri::command]
+pub async fn list_pinned_documents(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<Vec<Document>, String> {
+    let _span = crate::metrics::command_span("list_pinned_documents");
+    document_service(&state, &vector_state).list_pinned_documents().await
 }
 
 // Search commands
 #[tauri::command]
 pub async fn search_documents(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     query: String,
     limit: Option<i64>,
+    math: Option<bool>,
 ) -> Result<Vec<Document>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
+    let _span = crate::metrics::command_span("search_documents");
+    document_service(&state, &vector_state).search_documents(&query, limit, math.unwrap_or(false)).await
+}
 
-    if query.trim().is_empty() {
-        return Ok(vec![]);
-    }
+#[tauri::command]
+pub async fn quick_search(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    prefix: String,
+    limit: Option<i64>,
+) -> Result<QuickSearchResults, String> {
+    let _span = crate::metrics::command_span("quick_search");
+    document_service(&state, &vector_state).quick_search(&prefix, limit).await
+}
+
+#[tauri::command]
+pub async fn search_code_snippets(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    query: String,
+    language: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<CodeSnippet>, String> {
+    let _span = crate::metrics::command_span("search_code_snippets");
+    document_service(&state, &vector_state).search_code_snippets(&query, language.as_deref(), limit).await
+}
 
-    let limit_val = limit.unwrap_or(25);
-    database
-        .search_documents(&query, limit_val)
-        .await
-        .map_err(|e| format!("Failed to search documents: {}", e))
+#[tauri::command]
+pub async fn get_document_code_snippets(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+) -> Result<Vec<CodeSnippet>, String> {
+    let _span = crate::metrics::command_span("get_document_code_snippets");
+    document_service(&state, &vector_state).get_document_code_snippets(&document_id).await
 }
 
 // Data cleanup commands for app uninstall/data reset
 
+/// Remove the whole data directory, every profile included
 #[tauri::command]
 pub async fn cleanup_all_data(confirm_deletion: bool) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("cleanup_all_data");
     if !confirm_deletion {
         return Err("Deletion not confirmed".to_string());
     }
     
-    let home_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?;
-    
-    let app_data_dir = home_dir.join("stellar_data");
+    let app_data_dir = crate::paths::root_data_dir()?;
     
     if app_data_dir.exists() {
         std::fs::remove_dir_all(&app_data_dir)
@@ -302,14 +531,12 @@ pub async fn cleanup_all_data(confirm_deletion: bool) -> Result<bool, String> {
 
 #[tauri::command]
 pub async fn cleanup_database_only(confirm_deletion: bool) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("cleanup_database_only");
     if !confirm_deletion {
         return Err("Deletion not confirmed".to_string());
     }
     
-    let home_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?;
-    
-    let app_data_dir = home_dir.join("stellar_data");
+    let app_data_dir = crate::paths::app_data_dir()?;
     
     // Only remove database files, keep PDFs
     let db_files = vec!["documents.db", "embeddings.db"];
@@ -327,29 +554,41 @@ pub async fn cleanup_database_only(confirm_deletion: bool) -> Result<bool, Strin
 }
 
 #[tauri::command]
-pub async fn get_data_usage_info() -> Result<serde_json::Value, String> {
-    let home_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?;
-    
-    let app_data_dir = home_dir.join("stellar_data");
+pub async fn get_data_usage_info(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_data_usage_info");
+    let app_data_dir = crate::paths::app_data_dir()?;
     
     let mut total_size = 0u64;
     let mut database_size = 0u64;
     let mut pdf_size = 0u64;
     let mut pdf_count = 0;
+    let mut embeddings_size = 0u64;
     
     if app_data_dir.exists() {
         // Calculate total directory size
         total_size = calculate_dir_size(&app_data_dir)
             .map_err(|e| format!("Failed to calculate directory size: {}", e))?;
+        // The default profile's directory is the data root, which also holds the other profiles
+        let other_profiles = crate::profiles::profiles_root(&app_data_dir);
+        if crate::profiles::active_profile_id() == crate::profiles::DEFAULT_PROFILE_ID && other_profiles.exists() {
+            total_size = total_size.saturating_sub(calculate_dir_size(&other_profiles)
+                .map_err(|e| format!("Failed to calculate directory size: {}", e))?);
+        }
         
         // Calculate database size
         for db_file in &["documents.db", "embeddings.db"] {
             let db_path = app_data_dir.join(db_file);
             if db_path.exists() {
-                database_size += db_path.metadata()
+                let size = db_path.metadata()
                     .map_err(|e| format!("Failed to get metadata for {}: {}", db_file, e))?
                     .len();
+                database_size += size;
+                if *db_file == "embeddings.db" {
+                    embeddings_size = size;
+                }
             }
         }
         
@@ -369,6 +608,23 @@ pub async fn get_data_usage_info() -> Result<serde_json::Value, String> {
         }
     }
     
+    let (embedding_chunks, embedded_documents) = {
+        let vector_guard = vector_state.lock().await;
+        match vector_guard.as_ref().and_then(|service| service.get_stats().ok()) {
+            Some(stats) => (stats["total_chunks"].as_i64().unwrap_or(0), stats["total_documents"].as_i64().unwrap_or(0)),
+            None => (0, 0),
+        }
+    };
+    let last_embeddings_optimization = {
+        let db_state = state.lock().await;
+        match db_state.as_ref() {
+            Some(database) => database.get_setting(EMBEDDINGS_OPTIMIZATION_KEY).await.unwrap_or(None),
+            None => None,
+        }
+    };
+    
+    let available_space = crate::storage::available_space(&app_data_dir);
+
     Ok(serde_json::json!({
         "dataDirectory": app_data_dir.to_string_lossy(),
         "exists": app_data_dir.exists(),
@@ -376,12 +632,27 @@ pub async fn get_data_usage_info() -> Result<serde_json::Value, String> {
         "databaseSize": database_size,
         "pdfSize": pdf_size,
         "pdfCount": pdf_count,
-        "totalSizeFormatted": format_size(total_size),
-        "databaseSizeFormatted": format_size(database_size),
-        "pdfSizeFormatted": format_size(pdf_size)
+        "totalSizeFormatted": crate::storage::format_size(total_size),
+        "databaseSizeFormatted": crate::storage::format_size(database_size),
+        "pdfSizeFormatted": crate::storage::format_size(pdf_size),
+        "embeddingsSize": embeddings_size,
+        "embeddingsSizeFormatted": crate::storage::format_size(embeddings_size),
+        "embeddingChunks": embedding_chunks,
+        "embeddedDocuments": embedded_documents,
+        "lastEmbeddingsOptimization": last_embeddings_optimization,
+        "availableSpace": available_space,
+        "availableSpaceFormatted": available_space.map(crate::storage::format_size)
     }))
 }
 
+/// What the data dir is spent on, by category (library database, embeddings, PDFs, ...), with
+/// the free space left on its disk
+#[tauri::command]
+pub async fn get_storage_breakdown() -> Result<crate::storage::StorageBreakdown, String> {
+    let _span = crate::metrics::command_span("get_storage_breakdown");
+    crate::storage::storage_breakdown()
+}
+
 // Helper function to calculate directory size recursively
 fn calculate_dir_size(dir: &std::path::Path) -> Result<u64, std::io::Error> {
     let mut total_size = 0u64;
@@ -399,21 +670,3 @@ fn calculate_dir_size(dir: &std::path::Path) -> Result<u64, std::io::Error> {
     
     Ok(total_size)
 }
-
-// Helper function to format file sizes
-fn format_size(size: u64) -> String {
-    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
-    let mut size_f = size as f64;
-    let mut unit_index = 0;
-    
-    while size_f >= 1024.0 && unit_index < UNITS.len() - 1 {
-        size_f /= 1024.0;
-        unit_index += 1;
-    }
-    
-    if unit_index == 0 {
-        format!("{} {}", size, UNITS[unit_index])
-    } else {
-        format!("{:.1} {}", size_f, UNITS[unit_index])
-    }
-}
\ No newline at end of file
@@ -0,0 +1,14 @@
+use tauri::State;
+use crate::deep_link::{DeepLinkState, DeepLinkTarget};
+
+// ===== Deep Link Commands =====
+
+/// Links that arrived before the frontend was listening, such as the one that launched the
+/// app. Later links are delivered as `deep-link` events.
+#[tauri::command]
+pub async fn take_pending_deep_links(
+    state: State<'_, DeepLinkState>,
+) -> Result<Vec<DeepLinkTarget>, String> {
+    let _span = crate::metrics::command_span("take_pending_deep_links");
+    Ok(state.take_pending())
+}
@@ -1,13 +1,36 @@
-use crate::embeddings::{VectorService, EmbeddingConfig, EmbeddingProvider, DocumentChunk, EmbeddingSearchResult, create_embedding_generator};
+use crate::embeddings::{onnx, VectorService, EmbeddingConfig, EmbeddingProvider, DocumentChunk, EmbeddingSearchResult, EmbeddingsCompactionReport, ProviderProbe, probe_embedding_provider};
 use crate::commands::database::DatabaseState;
+use crate::database::{DocumentLanguageSummary, EmbeddingFallbackSettings, QueryEmbeddingCacheSettings, SelectedEmbeddingProvider};
+use crate::language;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tauri::State;
-use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, State};
+use std::collections::{HashMap, HashSet};
 
 // Reference to the vector service state
 type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
 
+pub const EMBEDDING_FALLBACK_SETTINGS_KEY: &str = "embedding_fallback";
+pub const EMBEDDING_SELECTION_KEY: &str = "embedding_provider_selection";
+pub const EMBEDDINGS_OPTIMIZATION_KEY: &str = "embeddings_optimization";
+pub const QUERY_EMBEDDING_CACHE_KEY: &str = "query_embedding_cache";
+/// Model name for a fastembed candidate that picks the English or multilingual model
+pub const AUTO_LOCAL_MODEL: &str = "auto";
+
+fn parse_provider(name: &str) -> Option<EmbeddingProvider> {
+    match name {
+        "openai" => Some(EmbeddingProvider::OpenAI),
+        "openai-compatible" => Some(EmbeddingProvider::OpenAICompatible),
+        "local" => Some(EmbeddingProvider::LocalModel),
+        "ollama" => Some(EmbeddingProvider::Ollama),
+        "gemini" => Some(EmbeddingProvider::Gemini),
+        "voyage" => Some(EmbeddingProvider::Voyage),
+        "fastembed" => Some(EmbeddingProvider::FastEmbed),
+        "rust-bert" => Some(EmbeddingProvider::RustBert),
+        _ => None,
+    }
+}
+
 #[tauri::command]
 pub async fn init_vector_service(
     state: State<'_, VectorServiceState>,
@@ -17,13 +40,9 @@ pub async fn init_vector_service(
     api_key: Option<String>,
     base_url: Option<String>,
 ) -> Result<bool, String> {
-    let provider = match embedding_provider.as_str() {
-        "openai" => EmbeddingProvider::OpenAI,
-        "openai-compatible" => EmbeddingProvider::OpenAICompatible,
-        "local" => EmbeddingProvider::LocalModel,
-        "ollama" => EmbeddingProvider::Ollama,
-        _ => return Err("Invalid embedding provider".to_string()),
-    };
+    let _span = crate::metrics::command_span("init_vector_service");
+    let provider = parse_provider(&embedding_provider)
+        .ok_or_else(|| "Invalid embedding provider".to_string())?;
     
     let config = EmbeddingConfig {
         provider,
@@ -51,6 +70,7 @@ pub async fn process_document_embeddings(
     doc_type: String,
     file_path: Option<String>,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("process_document_embeddings");
     let mut guard = state.lock().await;
     let service = guard.as_mut()
         .ok_or("Vector service not initialized")?;
@@ -91,23 +111,35 @@ pub async fn process_document_embeddings(
     Ok(true)
 }
 
+/// Without `document_ids`, documents in archived categories are left out
 #[tauri::command]
 pub async fn search_document_embeddings(
     state: State<'_, VectorServiceState>,
+    db_state: State<'_, DatabaseState>,
     query: String,
     limit: Option<usize>,
     threshold: Option<f32>,
     document_ids: Option<Vec<String>>,
 ) -> Result<Vec<EmbeddingSearchResult>, String> {
+    let _span = crate::metrics::command_span("search_document_embeddings");
+    let archived_document_ids = match document_ids {
+        Some(_) => Vec::new(),
+        None => {
+            let db_guard = db_state.lock().await;
+            let database = db_guard.as_ref().ok_or("Database not initialized")?;
+            database.get_archived_document_ids().await
+                .map_err(|e| format!("Failed to get archived documents: {}", e))?
+        }
+    };
+
     let mut guard = state.lock().await;
     let service = guard.as_mut()
         .ok_or("Vector service not initialized")?;
     
-    let results = service.search_similar(
-        &query, 
-        limit.unwrap_or(10),
-        document_ids.as_ref().map(|v| v.as_slice())
-    ).await
+    let results = match &document_ids {
+        Some(document_ids) => service.search_similar(&query, limit.unwrap_or(10), Some(document_ids)).await,
+        None => service.search_similar_excluding(&query, limit.unwrap_or(10), &archived_document_ids).await,
+    }
     .map_err(|e| format!("Search failed: {}", e))?;
 
     // Apply threshold filter if specified
@@ -122,11 +154,49 @@ pub async fn search_document_embeddings(
     Ok(filtered_results)
 }
 
+/// Search only within a category's documents, resolving the ids here instead of in the frontend.
+/// Subcategories are included unless `include_subcategories` is false.
+#[tauri::command]
+pub async fn search_embeddings_by_category(
+    state: State<'_, VectorServiceState>,
+    db_state: State<'_, DatabaseState>,
+    category_id: String,
+    query: String,
+    limit: Option<usize>,
+    threshold: Option<f32>,
+    include_subcategories: Option<bool>,
+) -> Result<Vec<EmbeddingSearchResult>, String> {
+    let _span = crate::metrics::command_span("search_embeddings_by_category");
+    let document_ids = {
+        let db_guard = db_state.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+        database.get_category_document_ids(&category_id, include_subcategories.unwrap_or(true)).await
+            .map_err(|e| format!("Failed to get category documents: {}", e))?
+    };
+
+    if document_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut guard = state.lock().await;
+    let service = guard.as_mut()
+        .ok_or("Vector service not initialized")?;
+
+    let results = service.search_similar(&query, limit.unwrap_or(10), Some(&document_ids)).await
+        .map_err(|e| format!("Search failed: {}", e))?;
+
+    Ok(match threshold {
+        Some(threshold) => results.into_iter().filter(|r| r.score >= threshold).collect(),
+        None => results,
+    })
+}
+
 #[tauri::command]
 pub async fn delete_document_embeddings(
     state: State<'_, VectorServiceState>,
     document_id: String,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_document_embeddings");
     let mut guard = state.lock().await;
     let service = guard.as_mut()
         .ok_or("Vector service not initialized")?;
@@ -141,6 +211,7 @@ pub async fn delete_document_embeddings(
 pub async fn get_embedding_stats(
     state: State<'_, VectorServiceState>,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_embedding_stats");
     let guard = state.lock().await;
     let service = guard.as_ref()
         .ok_or("Vector service not initialized")?;
@@ -149,267 +220,313 @@ pub async fn get_embedding_stats(
         .map_err(|e| format!("Failed to get stats: {}", e))
 }
 
+/// Drop chunks of deleted documents, then VACUUM/ANALYZE the embeddings database. The report
+/// is stored so get_data_usage_info can show when space was last reclaimed.
+#[tauri::command]
+pub async fn optimize_embeddings_database(
+    state: State<'_, VectorServiceState>,
+    db_state: State<'_, DatabaseState>,
+) -> Result<EmbeddingsCompactionReport, String> {
+    let _span = crate::metrics::command_span("optimize_embeddings_database");
+    let document_ids: HashSet<String> = {
+        let db_guard = db_state.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+        database.get_all_document_ids().await
+            .map_err(|e| format!("Failed to load document ids: {}", e))?
+            .into_iter()
+            .collect()
+    };
+
+    let report = {
+        let mut guard = state.lock().await;
+        let service = guard.as_mut()
+            .ok_or("Vector service not initialized")?;
+
+        let size_before = service.database_size();
+        let orphaned_chunks_removed = service.delete_orphaned_chunks(&document_ids, 0)
+            .map_err(|e| format!("Failed to remove orphaned embeddings: {}", e))?;
+        service.vacuum()
+            .map_err(|e| format!("Failed to compact embeddings database: {}", e))?;
+
+        EmbeddingsCompactionReport {
+            orphaned_chunks_removed,
+            size_before,
+            size_after: service.database_size(),
+            optimized_at: chrono::Utc::now(),
+        }
+    };
+
+    println!(
+        "🗜️ Embeddings database compacted: {} orphaned chunks removed, {} -> {} bytes",
+        report.orphaned_chunks_removed, report.size_before, report.size_after
+    );
+
+    let db_guard = db_state.lock().await;
+    if let Some(database) = db_guard.as_ref() {
+        if let Err(e) = database.set_typed_setting(EMBEDDINGS_OPTIMIZATION_KEY, &report).await {
+            eprintln!("⚠️ Failed to record embeddings optimization: {}", e);
+        }
+    }
+
+    Ok(report)
+}
+
 #[tauri::command]
 pub async fn check_embedding_health(
     state: State<'_, VectorServiceState>,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("check_embedding_health");
     let guard = state.lock().await;
-    let _service = guard.as_ref()
+    let service = guard.as_ref()
         .ok_or("Vector service not initialized")?;
 
-    // For now, just check if the service exists
-    Ok(true)
+    match service.health_check().await {
+        Ok(_) => Ok(true),
+        Err(e) => {
+            println!("⚠️ Embedding health check failed: {}", e);
+            Ok(false)
+        }
+    }
 }
 
-// Initialize the VectorService (sqlite-vec based) for backward compatibility
+// Initialize the VectorService (sqlite-vec based) for backward compatibility. Providers are
+// tried in the order stored under EMBEDDING_FALLBACK_SETTINGS_KEY, starting with the one
+// selected last time, and the first that passes a health check is persisted as the selection.
 #[tauri::command]
 pub async fn init_embedding_service(
     state: State<'_, VectorServiceState>,
     db_state: State<'_, DatabaseState>,
     _legacy_url: Option<String>,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("init_embedding_service");
     // Use the same data directory as the main database
-    let home_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?;
-    
-    let app_data_dir = home_dir.join("stellar_data");
-    let db_path = app_data_dir.join("embeddings.db");
-    
-    // Ensure directory exists
-    if let Err(e) = std::fs::create_dir_all(&app_data_dir) {
-        return Err(format!("Failed to create app directory: {}", e));
+    let db_path = crate::paths::app_data_dir()?.join("embeddings.db");
+
+    let (settings, previous, language_counts, query_cache) = {
+        let db_guard = db_state.lock().await;
+        match db_guard.as_ref() {
+            Some(database) => {
+                let settings: EmbeddingFallbackSettings = database
+                    .get_typed_setting(EMBEDDING_FALLBACK_SETTINGS_KEY).await
+                    .unwrap_or_default();
+                let previous = database.get_setting(EMBEDDING_SELECTION_KEY).await
+                    .unwrap_or(None)
+                    .and_then(|value| serde_json::from_value::<SelectedEmbeddingProvider>(value).ok());
+                let language_counts = database.get_document_language_counts().await.unwrap_or_default();
+                let query_cache: QueryEmbeddingCacheSettings = database
+                    .get_typed_setting(QUERY_EMBEDDING_CACHE_KEY).await
+                    .unwrap_or_default();
+                (settings, previous, language_counts, query_cache)
+            }
+            None => (EmbeddingFallbackSettings::default(), None, HashMap::new(), QueryEmbeddingCacheSettings::default()),
+        }
+    };
+
+    // Retry last run's choice first, as long as it is still part of the configured order
+    let mut candidates = settings.order.clone();
+    if let Some(previous) = previous {
+        if let Some(index) = candidates.iter().position(|candidate| *candidate == previous.candidate) {
+            let candidate = candidates.remove(index);
+            candidates.insert(0, candidate);
+        }
     }
-    
-    // Try different providers in order of preference
-    let mut last_error = String::new();
-    let provider_used: String;
-    
-    // Try Ollama first with the correct URL and a model that exists
-    println!("🔍 Trying to initialize Ollama embedding service...");
-    match init_vector_service(
-        state.clone(),
-        db_path.to_string_lossy().to_string(),
-        "ollama".to_string(),
-        "mxbai-embed-large".to_string(), // Use the model we know exists
-        None,
-        Some("http://localhost:11434".to_string()), // Force correct Ollama URL
-    ).await {
-        Ok(_) => {
-            // Test the connection by trying to generate a simple embedding
-            println!("✅ Ollama service initialized, testing connection...");
-            match process_document_embeddings(
-                state.clone(),
-                "connection_test".to_string(),
-                "Connection Test".to_string(),
-                "This is a test embedding to verify Ollama connectivity.".to_string(),
-                "test".to_string(),
-                None,
-            ).await {
-                Ok(_) => {
-                    // Connection test successful, clean up the test document
-                    let _ = delete_document_embeddings(state.clone(), "connection_test".to_string()).await;
-                    provider_used = "ollama".to_string();
-                    println!("✅ Ollama connection test successful");
-                },
-                Err(e) => {
-                    // Connection test failed, clean up and fallback
-                    let _ = delete_document_embeddings(state.clone(), "connection_test".to_string()).await;
-                    last_error = format!("Ollama connection test failed: {}", e);
-                    println!("⚠️ Ollama connection test failed: {}, trying OpenAI fallback...", e);
-                    
-                    // Try OpenAI as fallback if API key is available
-                    let db_guard = db_state.lock().await;
-                    let openai_api_key = if let Some(database) = db_guard.as_ref() {
-                        database.get_api_key("openai-default").await
-                            .unwrap_or(None)
-                    } else {
-                        None
-                    };
-                    drop(db_guard);
-                    
-                    if let Some(api_key) = openai_api_key {
-                        println!("🔍 Found OpenAI API key, trying OpenAI embeddings...");
-                        match init_vector_service(
-                            state.clone(),
-                            db_path.to_string_lossy().to_string(),
-                            "openai".to_string(),
-                            "text-embedding-3-small".to_string(), // Efficient OpenAI model
-                            Some(api_key),
-                            None,
-                        ).await {
-                            Ok(_) => {
-                                provider_used = "openai".to_string();
-                                println!("✅ OpenAI embedding service initialized successfully");
-                            },
-                            Err(e2) => {
-                                last_error = format!("Ollama failed: {}, OpenAI failed: {}", last_error, e2);
-                                println!("⚠️ OpenAI failed: {}, trying rust-bert fallback...", e2);
-                                
-                                // Final fallback to rust-bert
-                                match init_vector_service(
-                                    state,
-                                    db_path.to_string_lossy().to_string(),
-                                    "rust-bert".to_string(),
-                                    "fallback".to_string(),
-                                    None,
-                                    None,
-                                ).await {
-                                    Ok(_) => {
-                                        provider_used = "rust-bert".to_string();
-                                        println!("✅ Rust-bert fallback embedding service initialized");
-                                    },
-                                    Err(e3) => {
-                                        last_error = format!("Ollama failed: {}, OpenAI failed: {}, Rust-bert failed: {}", last_error, e2, e3);
-                                        return Err(format!("All embedding providers failed. {}", last_error));
-                                    }
-                                }
-                            }
-                        }
-                    } else {
-                        println!("⚠️ No OpenAI API key found, trying rust-bert fallback...");
-                        
-                        // Fallback to rust-bert
-                        match init_vector_service(
-                            state,
-                            db_path.to_string_lossy().to_string(),
-                            "rust-bert".to_string(),
-                            "fallback".to_string(),
-                            None,
-                            None,
-                        ).await {
-                            Ok(_) => {
-                                provider_used = "rust-bert".to_string();
-                                println!("✅ Rust-bert fallback embedding service initialized");
-                            },
-                            Err(e2) => {
-                                last_error = format!("Ollama failed: {}, Rust-bert failed: {}", last_error, e2);
-                                return Err(format!("All embedding providers failed. {}", last_error));
-                            }
-                        }
-                    }
-                    
-                    // Early return since we've handled the fallback
-                    return Ok(serde_json::json!({
-                        "success": true,
-                        "provider": provider_used,
-                        "model": match provider_used.as_str() {
-                            "ollama" => "mxbai-embed-large",
-                            "openai" => "text-embedding-3-small",
-                            _ => "fallback"
-                        },
-                        "base_url": match provider_used.as_str() {
-                            "ollama" => "http://localhost:11434",
-                            "openai" => "https://api.openai.com/v1",
-                            _ => "local"
-                        },
-                        "message": format!("Embedding service initialized with {}", provider_used),
-                        "fallback_used": provider_used != "ollama",
-                        "last_error": if !last_error.is_empty() { Some(last_error) } else { None }
-                    }));
+    if candidates.is_empty() {
+        return Err("No embedding providers configured".to_string());
+    }
+
+    let mut errors: Vec<String> = Vec::new();
+    let mut selected = None;
+
+    for candidate in &candidates {
+        // A local model set to "auto" follows the library's languages
+        let model = if candidate.provider == "fastembed" && candidate.model == AUTO_LOCAL_MODEL {
+            language::recommended_embedding_model(&language_counts).to_string()
+        } else {
+            candidate.model.clone()
+        };
+        println!("🔍 Trying to initialize {} embedding service ({})...", candidate.provider, model);
+
+        let api_key = match &candidate.api_key_id {
+            Some(key_id) => {
+                let db_guard = db_state.lock().await;
+                let api_key = match db_guard.as_ref() {
+                    Some(database) => database.get_api_key(key_id).await.unwrap_or(None),
+                    None => None,
+                };
+                if api_key.is_none() {
+                    println!("⚠️ No API key found for {}, skipping", candidate.provider);
+                    errors.push(format!("{}: no API key configured", candidate.provider));
+                    continue;
                 }
+                api_key
             }
-        },
-        Err(e) => {
-            last_error = format!("Ollama initialization failed: {}", e);
-            println!("⚠️ Ollama initialization failed: {}, trying OpenAI fallback...", e);
-            
-            // Try OpenAI as fallback if API key is available
-            let db_guard = db_state.lock().await;
-            let openai_api_key = if let Some(database) = db_guard.as_ref() {
-                database.get_api_key("openai-default").await
-                    .unwrap_or(None)
-            } else {
-                None
+            None => None,
+        };
+
+        if let Err(e) = init_vector_service(
+            state.clone(),
+            db_path.to_string_lossy().to_string(),
+            candidate.provider.clone(),
+            model,
+            api_key,
+            candidate.base_url.clone(),
+        ).await {
+            println!("⚠️ {} initialization failed: {}", candidate.provider, e);
+            errors.push(format!("{} initialization failed: {}", candidate.provider, e));
+            continue;
+        }
+
+        // Test the connection by trying to generate a simple embedding
+        let health = {
+            let guard = state.lock().await;
+            match guard.as_ref() {
+                Some(service) => service.health_check().await,
+                None => Err("Vector service not initialized".to_string()),
+            }
+        };
+        match health {
+            Ok((latency_ms, _)) => {
+                println!("✅ {} connection test successful ({}ms)", candidate.provider, latency_ms);
+                selected = Some(candidate.clone());
+                break;
+            }
+            Err(e) => {
+                println!("⚠️ {} connection test failed: {}", candidate.provider, e);
+                errors.push(format!("{} connection test failed: {}", candidate.provider, e));
+            }
+        }
+    }
+
+    let selected = match selected {
+        Some(selected) => selected,
+        None => return Err(format!("All embedding providers failed. {}", errors.join(", "))),
+    };
+
+    if query_cache.persist {
+        let mut guard = state.lock().await;
+        if let Some(service) = guard.as_mut() {
+            if let Err(e) = service.set_persist_query_embeddings(true) {
+                eprintln!("⚠️ Failed to enable the persistent query embedding cache: {}", e);
+            }
+        }
+    }
+
+    {
+        let db_guard = db_state.lock().await;
+        if let Some(database) = db_guard.as_ref() {
+            let selection = SelectedEmbeddingProvider {
+                candidate: selected.clone(),
+                selected_at: chrono::Utc::now(),
             };
-            drop(db_guard);
-            
-            if let Some(api_key) = openai_api_key {
-                println!("🔍 Found OpenAI API key, trying OpenAI embeddings...");
-                match init_vector_service(
-                    state.clone(),
-                    db_path.to_string_lossy().to_string(),
-                    "openai".to_string(),
-                    "text-embedding-3-small".to_string(), // Efficient OpenAI model
-                    Some(api_key),
-                    None,
-                ).await {
-                    Ok(_) => {
-                        provider_used = "openai".to_string();
-                        println!("✅ OpenAI embedding service initialized successfully");
-                    },
-                    Err(e2) => {
-                        last_error = format!("Ollama failed: {}, OpenAI failed: {}", last_error, e2);
-                        println!("⚠️ OpenAI failed: {}, trying rust-bert fallback...", e2);
-                        
-                        // Final fallback to rust-bert
-                        match init_vector_service(
-                            state,
-                            db_path.to_string_lossy().to_string(),
-                            "rust-bert".to_string(),
-                            "fallback".to_string(),
-                            None,
-                            None,
-                        ).await {
-                            Ok(_) => {
-                                provider_used = "rust-bert".to_string();
-                                println!("✅ Rust-bert fallback embedding service initialized");
-                            },
-                            Err(e3) => {
-                                last_error = format!("Ollama failed: {}, OpenAI failed: {}, Rust-bert failed: {}", last_error, e2, e3);
-                                return Err(format!("All embedding providers failed. {}", last_error));
-                            }
-                        }
-                    }
-                }
-            } else {
-                println!("⚠️ No OpenAI API key found, trying rust-bert fallback...");
-                
-                // Fallback to rust-bert
-                match init_vector_service(
-                    state,
-                    db_path.to_string_lossy().to_string(),
-                    "rust-bert".to_string(),
-                    "fallback".to_string(),
-                    None,
-                    None,
-                ).await {
-                    Ok(_) => {
-                        provider_used = "rust-bert".to_string();
-                        println!("✅ Rust-bert fallback embedding service initialized");
-                    },
-                    Err(e2) => {
-                        last_error = format!("Ollama failed: {}, Rust-bert failed: {}", last_error, e2);
-                        return Err(format!("All embedding providers failed. {}", last_error));
-                    }
-                }
+            if let Err(e) = database.set_typed_setting(EMBEDDING_SELECTION_KEY, &selection).await {
+                eprintln!("⚠️ Failed to persist embedding provider selection: {}", e);
             }
         }
     }
-    
+
+    let last_error = errors.join(", ");
     Ok(serde_json::json!({
         "success": true,
-        "provider": provider_used,
-        "model": match provider_used.as_str() {
-            "ollama" => "mxbai-embed-large",
-            "openai" => "text-embedding-3-small",
-            _ => "fallback"
-        },
-        "base_url": match provider_used.as_str() {
-            "ollama" => "http://localhost:11434",
-            "openai" => "https://api.openai.com/v1",
-            _ => "local"
-        },
-        "message": format!("Embedding service initialized with {}", provider_used),
-        "fallback_used": provider_used == "rust-bert",
+        "provider": selected.provider,
+        "model": selected.model,
+        "base_url": selected.base_url,
+        "message": format!("Embedding service initialized with {}", selected.provider),
+        "fallback_used": settings.order.first() != Some(&selected),
         "last_error": if !last_error.is_empty() { Some(last_error) } else { None }
     }))
 }
 
+#[tauri::command]
+pub async fn get_embedding_fallback_settings(
+    db_state: State<'_, DatabaseState>,
+) -> Result<EmbeddingFallbackSettings, String> {
+    let _span = crate::metrics::command_span("get_embedding_fallback_settings");
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(EMBEDDING_FALLBACK_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get embedding fallback settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_embedding_fallback_settings(
+    db_state: State<'_, DatabaseState>,
+    settings: EmbeddingFallbackSettings,
+) -> Result<EmbeddingFallbackSettings, String> {
+    let _span = crate::metrics::command_span("update_embedding_fallback_settings");
+    if settings.order.is_empty() {
+        return Err("At least one embedding provider is required".to_string());
+    }
+    if let Some(candidate) = settings.order.iter().find(|candidate| parse_provider(&candidate.provider).is_none()) {
+        return Err(format!("Invalid embedding provider: {}", candidate.provider));
+    }
+
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database.set_typed_setting(EMBEDDING_FALLBACK_SETTINGS_KEY, &settings).await
+        .map_err(|e| format!("Failed to update embedding fallback settings: {}", e))?;
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_query_embedding_cache_settings(
+    db_state: State<'_, DatabaseState>,
+) -> Result<QueryEmbeddingCacheSettings, String> {
+    let _span = crate::metrics::command_span("get_query_embedding_cache_settings");
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(QUERY_EMBEDDING_CACHE_KEY).await
+        .map_err(|e| format!("Failed to get query embedding cache settings: {}", e))
+}
+
+/// Save the settings and apply them to the running vector service
+#[tauri::command]
+pub async fn update_query_embedding_cache_settings(
+    state: State<'_, VectorServiceState>,
+    db_state: State<'_, DatabaseState>,
+    settings: QueryEmbeddingCacheSettings,
+) -> Result<QueryEmbeddingCacheSettings, String> {
+    let _span = crate::metrics::command_span("update_query_embedding_cache_settings");
+    {
+        let db_guard = db_state.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+        database.set_typed_setting(QUERY_EMBEDDING_CACHE_KEY, &settings).await
+            .map_err(|e| format!("Failed to update query embedding cache settings: {}", e))?;
+    }
+
+    let mut guard = state.lock().await;
+    if let Some(service) = guard.as_mut() {
+        service.set_persist_query_embeddings(settings.persist)
+            .map_err(|e| format!("Failed to apply query embedding cache settings: {}", e))?;
+    }
+
+    Ok(settings)
+}
+
+/// Drop cached query embeddings, e.g. after the provider started returning different vectors
+/// for the same model name. Returns how many persisted ones were removed.
+#[tauri::command]
+pub async fn clear_query_embedding_cache(
+    state: State<'_, VectorServiceState>,
+) -> Result<usize, String> {
+    let _span = crate::metrics::command_span("clear_query_embedding_cache");
+    let mut guard = state.lock().await;
+    let service = guard.as_mut()
+        .ok_or("Vector service not initialized")?;
+
+    service.clear_query_cache()
+        .map_err(|e| format!("Failed to clear query embedding cache: {}", e))
+}
+
 // Debug command to check vector service status and stats
 #[tauri::command]
 pub async fn debug_embedding_service(
     state: State<'_, VectorServiceState>,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("debug_embedding_service");
     let guard = state.lock().await;
     
     if let Some(service) = guard.as_ref() {
@@ -433,6 +550,7 @@ pub async fn debug_embedding_service(
 pub async fn list_embedded_documents(
     state: State<'_, VectorServiceState>,
 ) -> Result<Vec<serde_json::Value>, String> {
+    let _span = crate::metrics::command_span("list_embedded_documents");
     let guard = state.lock().await;
     let service = guard.as_ref()
         .ok_or("Vector service not initialized")?;
@@ -447,6 +565,7 @@ pub async fn get_document_embedding_info(
     state: State<'_, VectorServiceState>,
     document_id: String,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_document_embedding_info");
     let guard = state.lock().await;
     let service = guard.as_ref()
         .ok_or("Vector service not initialized")?;
@@ -460,6 +579,7 @@ pub async fn get_document_embedding_info(
 pub async fn get_embedding_database_info(
     state: State<'_, VectorServiceState>,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_embedding_database_info");
     let guard = state.lock().await;
     
     if let Some(service) = guard.as_ref() {
@@ -488,6 +608,7 @@ pub async fn bulk_reprocess_documents_for_embeddings(
     vector_state: State<'_, VectorServiceState>,
     db_state: State<'_, DatabaseState>,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("bulk_reprocess_documents_for_embeddings");
     let mut guard = vector_state.lock().await;
     let vector_service = guard.as_mut()
         .ok_or("Vector service not initialized")?;
@@ -594,6 +715,7 @@ pub async fn copy_document_embeddings(
     source_document_id: String,
     target_document_id: String,
 ) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("copy_document_embeddings");
     let mut guard = state.lock().await;
     let service = guard.as_mut()
         .ok_or("Vector service not initialized")?;
@@ -624,132 +746,174 @@ pub async fn copy_document_embeddings(
     Ok(true)
 } 
 
+/// Embed a probe text with each provider in the configured fallback order, timing the round
+/// trip and checking the returned dimensions
 #[tauri::command]
 pub async fn test_embedding_provider_availability(
     db_state: State<'_, DatabaseState>,
 ) -> Result<serde_json::Value, String> {
-    let mut available_providers = Vec::new();
-    let mut test_results = Vec::new();
-    
-    // Test Ollama
-    println!("🔍 Testing Ollama availability...");
-    let ollama_config = EmbeddingConfig {
-        provider: EmbeddingProvider::Ollama,
-        model: "mxbai-embed-large".to_string(),
-        api_key: None,
-        base_url: Some("http://localhost:11434".to_string()),
-        dimensions: 1024,
-    };
-    
-    match create_embedding_generator(&ollama_config) {
-        Ok(_) => {
-            available_providers.push("ollama");
-            test_results.push(serde_json::json!({
-                "provider": "ollama",
-                "available": true,
-                "model": "mxbai-embed-large",
-                "base_url": "http://localhost:11434"
-            }));
-        }
-        Err(e) => {
-            test_results.push(serde_json::json!({
-                "provider": "ollama",
-                "available": false,
-                "error": e.to_string()
-            }));
+    let _span = crate::metrics::command_span("test_embedding_provider_availability");
+
+    // Gather the candidates and their keys, then release the lock while the probes run
+    let (settings, candidates) = {
+        let db_guard = db_state.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+        let settings: EmbeddingFallbackSettings = database.get_typed_setting(EMBEDDING_FALLBACK_SETTINGS_KEY).await
+            .map_err(|e| format!("Failed to get embedding fallback settings: {}", e))?;
+        let language_counts = database.get_document_language_counts().await.unwrap_or_default();
+
+        let mut candidates = Vec::new();
+        for candidate in &settings.order {
+            let api_key = match &candidate.api_key_id {
+                Some(key_id) => database.get_api_key(key_id).await
+                    .map_err(|e| format!("Failed to get API key: {}", e))?,
+                None => None,
+            };
+            let model = if candidate.provider == "fastembed" && candidate.model == AUTO_LOCAL_MODEL {
+                language::recommended_embedding_model(&language_counts).to_string()
+            } else {
+                candidate.model.clone()
+            };
+            candidates.push((candidate.clone(), model, api_key));
         }
-    }
-    
-    // Test OpenAI
-    println!("🔍 Testing OpenAI availability...");
-    let db_guard = db_state.lock().await;
-    let openai_api_key = if let Some(database) = db_guard.as_ref() {
-        database.get_api_key("openai-default").await
-            .unwrap_or(None)
-    } else {
-        None
+        (settings, candidates)
     };
-    drop(db_guard);
-    
-    if let Some(api_key) = openai_api_key {
-        let openai_config = EmbeddingConfig {
-            provider: EmbeddingProvider::OpenAI,
-            model: "text-embedding-3-small".to_string(),
-            api_key: Some(api_key),
-            base_url: None,
-            dimensions: 1536,
+
+    let mut test_results: Vec<ProviderProbe> = Vec::new();
+    for (candidate, model, api_key) in candidates {
+        println!("🔍 Testing {} availability...", candidate.provider);
+        let unavailable = |error: &str| ProviderProbe {
+            provider: candidate.provider.clone(),
+            model: model.clone(),
+            available: false,
+            latency_ms: None,
+            dimensions: None,
+            expected_dimensions: 0,
+            error: Some(error.to_string()),
+            has_api_key: None,
         };
-        
-        match create_embedding_generator(&openai_config) {
-            Ok(_) => {
-                available_providers.push("openai");
-                test_results.push(serde_json::json!({
-                    "provider": "openai",
-                    "available": true,
-                    "model": "text-embedding-3-small",
-                    "has_api_key": true
-                }));
-            }
-            Err(e) => {
-                test_results.push(serde_json::json!({
-                    "provider": "openai",
-                    "available": false,
-                    "error": e.to_string(),
-                    "has_api_key": true
-                }));
-            }
+
+        let Some(provider) = parse_provider(&candidate.provider) else {
+            test_results.push(unavailable("Unknown embedding provider"));
+            continue;
+        };
+        // Without a key the generator would silently fall back to rust-bert
+        if candidate.api_key_id.is_some() && api_key.is_none() {
+            let mut probe = unavailable("No API key configured");
+            probe.has_api_key = Some(false);
+            test_results.push(probe);
+            continue;
         }
-    } else {
-        test_results.push(serde_json::json!({
-            "provider": "openai",
-            "available": false,
-            "error": "No API key configured",
-            "has_api_key": false
-        }));
-    }
-    
-    // Test rust-bert fallback
-    println!("🔍 Testing rust-bert fallback...");
-    let rustbert_config = EmbeddingConfig {
-        provider: EmbeddingProvider::RustBert,
-        model: "fallback".to_string(),
-        api_key: None,
-        base_url: None,
-        dimensions: 384,
-    };
-    
-    match create_embedding_generator(&rustbert_config) {
-        Ok(_) => {
-            available_providers.push("rust-bert");
-            test_results.push(serde_json::json!({
-                "provider": "rust-bert",
-                "available": true,
-                "model": "fallback"
-            }));
+
+        let config = EmbeddingConfig {
+            provider,
+            model: model.clone(),
+            api_key,
+            base_url: candidate.base_url.clone(),
+            dimensions: 0, // The generator reports the model's dimensions
+        };
+        let mut probe = probe_embedding_provider(&candidate.provider, &config).await;
+        if candidate.api_key_id.is_some() {
+            probe.has_api_key = Some(true);
         }
-        Err(e) => {
-            test_results.push(serde_json::json!({
-                "provider": "rust-bert",
-                "available": false,
-                "error": e.to_string()
-            }));
+        test_results.push(probe);
+    }
+
+    for probe in &test_results {
+        match (&probe.error, probe.latency_ms) {
+            (None, Some(latency)) => println!("✅ {} responded in {}ms", probe.provider, latency),
+            (Some(error), _) => println!("❌ {} unavailable: {}", probe.provider, error),
+            _ => {}
         }
     }
-    
-    let recommended_provider = if available_providers.contains(&"ollama") {
-        "ollama"
-    } else if available_providers.contains(&"openai") {
-        "openai"
-    } else if available_providers.contains(&"rust-bert") {
-        "rust-bert"
-    } else {
-        "none"
-    };
-    
+
+    let available_providers: Vec<&str> = test_results
+        .iter()
+        .filter(|probe| probe.available)
+        .map(|probe| probe.provider.as_str())
+        .collect();
+    // Results are already in fallback order
+    let recommended_provider = available_providers.first().copied().unwrap_or("none");
+    let fallback_order: Vec<&str> = settings.order.iter().map(|candidate| candidate.provider.as_str()).collect();
+
     Ok(serde_json::json!({
         "available_providers": available_providers,
         "recommended_provider": recommended_provider,
         "test_results": test_results,
-        "fallback_order": ["ollama", "openai", "rust-bert"]
+        "fallback_order": fallback_order,
     }))
-} 
\ No newline at end of file
+}
+
+/// Local ONNX embedding models and whether each has been downloaded yet
+#[tauri::command]
+pub async fn list_local_embedding_models() -> Result<Vec<onnx::LocalModelStatus>, String> {
+    let _span = crate::metrics::command_span("list_local_embedding_models");
+    onnx::model_statuses()
+}
+
+/// Fetch a local embedding model ahead of time so the first indexing run doesn't stall on it,
+/// emitting `model-download-progress` events with the cache size while fastembed downloads
+#[tauri::command]
+pub async fn download_local_embedding_model(app: AppHandle, model: String) -> Result<onnx::LocalModelStatus, String> {
+    let _span = crate::metrics::command_span("download_local_embedding_model");
+    let spec = onnx::find_model(&model)
+        .ok_or_else(|| format!("Unsupported local embedding model '{}'", model))?;
+    let id = spec.id;
+    let cache_path = onnx::model_cache_path(&spec)?;
+
+    println!("📥 Downloading local embedding model {}", id);
+    let download = tokio::task::spawn_blocking(move || onnx::load_model(&spec).map(|_| ()));
+    tokio::pin!(download);
+
+    // fastembed has no progress callback, so report how much of the cache has landed on disk
+    let mut ticker = tokio::time::interval(std::time::Duration::from_millis(500));
+    let result = loop {
+        tokio::select! {
+            result = &mut download => break result,
+            _ = ticker.tick() => {
+                let _ = app.emit(super::models::MODEL_DOWNLOAD_PROGRESS_EVENT, crate::models::DownloadProgress {
+                    model_id: id.to_string(),
+                    downloaded_bytes: crate::models::directory_size(&cache_path),
+                    done: false,
+                });
+            }
+        }
+    };
+    result.map_err(|e| format!("Model download task failed: {}", e))??;
+
+    let status = onnx::model_statuses()?
+        .into_iter()
+        .find(|status| status.id == id)
+        .ok_or_else(|| format!("Model '{}' missing after download", id))?;
+    let _ = app.emit(super::models::MODEL_DOWNLOAD_PROGRESS_EVENT, crate::models::DownloadProgress {
+        model_id: id.to_string(),
+        downloaded_bytes: status.size_bytes,
+        done: true,
+    });
+    Ok(status)
+}
+
+#[tauri::command]
+pub async fn delete_local_embedding_model(model: String) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_local_embedding_model");
+    let spec = onnx::find_model(&model)
+        .ok_or_else(|| format!("Unsupported local embedding model '{}'", model))?;
+    onnx::delete_model(&spec)
+}
+
+/// How many documents are in each detected language, and the local embedding model that fits
+#[tauri::command]
+pub async fn get_document_languages(
+    db_state: State<'_, DatabaseState>,
+) -> Result<DocumentLanguageSummary, String> {
+    let _span = crate::metrics::command_span("get_document_languages");
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let counts = database.get_document_language_counts().await
+        .map_err(|e| format!("Failed to get document languages: {}", e))?;
+    Ok(DocumentLanguageSummary {
+        recommended_local_model: language::recommended_embedding_model(&counts).to_string(),
+        counts,
+    })
+}
@@ -0,0 +1,22 @@
+use tauri::State;
+use crate::ai::AIProvider;
+use crate::media::equations::{EquationEngine, LatexConversion};
+use crate::services::{DatabaseState, EquationService};
+
+// ===== Equation Commands =====
+
+/// LaTeX of the equation in a screenshot (data URL or base64), for inserting into notes and
+/// flashcards. `engine` defaults to a vision model, which needs `provider` and `model`;
+/// "pix2tex" reads the image locally.
+#[tauri::command]
+pub async fn convert_image_to_latex(
+    state: State<'_, DatabaseState>,
+    image: String,
+    engine: Option<EquationEngine>,
+    provider: Option<AIProvider>,
+    model: Option<String>,
+) -> Result<LatexConversion, String> {
+    let _span = crate::metrics::command_span("convert_image_to_latex");
+    EquationService::new(state.inner().clone())
+        .convert_image_to_latex(&image, engine.unwrap_or_default(), provider, model).await
+}
@@ -0,0 +1,67 @@
+use tauri::State;
+use crate::ai::AIProvider;
+use crate::database::{EssayFeedback, Rubric, SaveRubricRequest};
+use crate::services::{DatabaseState, EssayService};
+use crate::services::essays::EssayGradeResult;
+
+// ===== Essay Feedback Commands =====
+
+/// Grade an essay against a rubric, given either its text or the document holding it.
+/// Feedback on a document is kept for comparing revisions.
+#[tauri::command]
+pub async fn grade_essay(
+    state: State<'_, DatabaseState>,
+    provider: AIProvider,
+    model: String,
+    text: Option<String>,
+    document_id: Option<String>,
+    rubric_id: String,
+) -> Result<EssayGradeResult, String> {
+    let _span = crate::metrics::command_span("grade_essay");
+    EssayService::new(state.inner().clone())
+        .grade_essay(provider, model, text, document_id, &rubric_id).await
+}
+
+#[tauri::command]
+pub async fn get_essay_feedback(
+    state: State<'_, DatabaseState>,
+    document_id: String,
+) -> Result<Vec<EssayFeedback>, String> {
+    let _span = crate::metrics::command_span("get_essay_feedback");
+    EssayService::new(state.inner().clone()).get_essay_feedback(&document_id).await
+}
+
+#[tauri::command]
+pub async fn delete_essay_feedback(
+    state: State<'_, DatabaseState>,
+    feedback_id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_essay_feedback");
+    EssayService::new(state.inner().clone()).delete_essay_feedback(&feedback_id).await
+}
+
+#[tauri::command]
+pub async fn get_rubrics(
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<Rubric>, String> {
+    let _span = crate::metrics::command_span("get_rubrics");
+    EssayService::new(state.inner().clone()).get_rubrics().await
+}
+
+#[tauri::command]
+pub async fn save_rubric(
+    state: State<'_, DatabaseState>,
+    request: SaveRubricRequest,
+) -> Result<Rubric, String> {
+    let _span = crate::metrics::command_span("save_rubric");
+    EssayService::new(state.inner().clone()).save_rubric(request).await
+}
+
+#[tauri::command]
+pub async fn delete_rubric(
+    state: State<'_, DatabaseState>,
+    rubric_id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_rubric");
+    EssayService::new(state.inner().clone()).delete_rubric(&rubric_id).await
+}
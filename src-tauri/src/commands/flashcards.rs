@@ -1,14 +1,25 @@
 use tauri::State;
 use crate::database::{
     Database, 
-    Flashcard, FlashcardDeck, FlashcardReview, FlashcardStats, FlashcardReviewSession,
-    CreateFlashcardRequest, CreateFlashcardDeckRequest, CreateFlashcardReviewRequest
+    Flashcard, FlashcardDeck, FlashcardDeckStats, NewCardOrder, CustomReviewFilter,
+    PersistedReviewSession, ResumedReviewSession, StartReviewSessionRequest, FlashcardDetailStats, FlashcardReview, FlashcardReviewResult, FlashcardStats, FlashcardReviewSession, AdaptiveReviewSession,
+    CreateFlashcardRequest, CreateFlashcardDeckRequest, CreateFlashcardReviewRequest, TypedAnswerGrade,
+    CreateImageOcclusionRequest
 };
+use crate::ai::AIProvider;
+use crate::embeddings::VectorService;
+use crate::media::{to_data_url, DeckPackageAuthor};
+use crate::services::FlashcardService;
 use tokio::sync::Mutex;
 use std::sync::Arc;
 
 // Use the same DatabaseState pattern as other commands
 type DatabaseState = Arc<Mutex<Option<Database>>>;
+type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+fn flashcard_service(state: &State<'_, DatabaseState>, vector_state: &State<'_, VectorServiceState>) -> FlashcardService {
+    FlashcardService::new(state.inner().clone(), vector_state.inner().clone())
+}
 
 // 🧠 PHASE 2: Flashcard System - Tauri Commands
 
@@ -17,107 +28,143 @@ type DatabaseState = Arc<Mutex<Option<Database>>>;
 #[tauri::command]
 pub async fn create_flashcard(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     request: CreateFlashcardRequest,
 ) -> Result<Flashcard, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.create_flashcard(request)
-        .await
-        .map_err(|e| format!("Failed to create flashcard: {}", e))
+    let _span = crate::metrics::command_span("create_flashcard");
+    flashcard_service(&state, &vector_state).create_flashcard(request).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
 ) -> Result<Option<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard(&id)
-        .await
-        .map_err(|e| format!("Failed to get flashcard: {}", e))
+    let _span = crate::metrics::command_span("get_flashcard");
+    flashcard_service(&state, &vector_state).get_flashcard(&id).await
 }
 
 #[tauri::command]
 pub async fn get_flashcards(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     limit: Option<i32>,
     offset: Option<i32>,
 ) -> Result<Vec<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcards(limit, offset)
-        .await
-        .map_err(|e| format!("Failed to get flashcards: {}", e))
+    let _span = crate::metrics::command_span("get_flashcards");
+    flashcard_service(&state, &vector_state).get_flashcards(limit, offset).await
 }
 
 #[tauri::command]
 pub async fn get_flashcards_by_deck(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     deck_id: String,
 ) -> Result<Vec<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcards_by_deck(&deck_id)
-        .await
-        .map_err(|e| format!("Failed to get flashcards by deck: {}", e))
+    let _span = crate::metrics::command_span("get_flashcards_by_deck");
+    flashcard_service(&state, &vector_state).get_flashcards_by_deck(&deck_id).await
 }
 
 #[tauri::command]
 pub async fn get_flashcards_by_category(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     category_id: String,
 ) -> Result<Vec<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcards_by_category(&category_id)
-        .await
-        .map_err(|e| format!("Failed to get flashcards by category: {}", e))
+    let _span = crate::metrics::command_span("get_flashcards_by_category");
+    flashcard_service(&state, &vector_state).get_flashcards_by_category(&category_id).await
 }
 
 #[tauri::command]
 pub async fn get_flashcards_by_document(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     document_id: String,
 ) -> Result<Vec<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcards_by_document(&document_id)
-        .await
-        .map_err(|e| format!("Failed to get flashcards by document: {}", e))
+    let _span = crate::metrics::command_span("get_flashcards_by_document");
+    flashcard_service(&state, &vector_state).get_flashcards_by_document(&document_id).await
 }
 
 #[tauri::command]
 pub async fn update_flashcard(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
     request: CreateFlashcardRequest,
 ) -> Result<Option<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.update_flashcard(&id, request)
-        .await
-        .map_err(|e| format!("Failed to update flashcard: {}", e))
+    let _span = crate::metrics::command_span("update_flashcard");
+    flashcard_service(&state, &vector_state).update_flashcard(&id, request).await
 }
 
 #[tauri::command]
 pub async fn delete_flashcard(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
 ) -> Result<bool, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.delete_flashcard(&id)
-        .await
-        .map_err(|e| format!("Failed to delete flashcard: {}", e))
+    let _span = crate::metrics::command_span("delete_flashcard");
+    flashcard_service(&state, &vector_state).delete_flashcard(&id).await
+}
+
+// === IMAGE OCCLUSION COMMANDS ===
+
+/// Store the source image once and create one 'image_occlusion' card per mask.
+/// Every card shares the image and full mask list in metadata and points at its own mask.
+#[tauri::command]
+pub async fn create_image_occlusion_cards(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    request: CreateImageOcclusionRequest,
+) -> Result<Vec<Flashcard>, String> {
+    let _span = crate::metrics::command_span("create_image_occlusion_cards");
+    flashcard_service(&state, &vector_state).create_image_occlusion_cards(request).await
+}
+
+/// Composite the card's image with its masks painted on, as a PNG data URL.
+/// Pass `reveal: true` for the answer side, which uncovers the card's own mask.
+#[tauri::command]
+pub async fn get_occluded_image(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    flashcard_id: String,
+    reveal: Option<bool>,
+) -> Result<String, String> {
+    let _span = crate::metrics::command_span("get_occluded_image");
+    let png = flashcard_service(&state, &vector_state).occluded_image(&flashcard_id, reveal.unwrap_or(false)).await?;
+    Ok(to_data_url(&png, "image/png"))
+}
+
+// === FLASHCARD AUDIO COMMANDS ===
+
+/// Save a recording (base64 or data URL) for the front or back of a card
+#[tauri::command]
+pub async fn record_card_audio(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    flashcard_id: String,
+    side: String,
+    audio_data: String,
+    extension: Option<String>,
+) -> Result<Flashcard, String> {
+    let _span = crate::metrics::command_span("record_card_audio");
+    flashcard_service(&state, &vector_state).record_card_audio(&flashcard_id, &side, &audio_data, extension).await
+}
+
+/// Get a card side's audio as a data URL. When nothing was recorded and a provider is given,
+/// the side's text is synthesized with text-to-speech and saved for next time.
+#[tauri::command]
+pub async fn get_card_audio(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    flashcard_id: String,
+    side: String,
+    provider: Option<AIProvider>,
+    model: Option<String>,
+    voice: Option<String>,
+) -> Result<Option<String>, String> {
+    let _span = crate::metrics::command_span("get_card_audio");
+    flashcard_service(&state, &vector_state).get_card_audio(&flashcard_id, &side, provider, model, voice).await
 }
 
 // === FLASHCARD DECK COMMANDS ===
@@ -125,66 +172,90 @@ pub async fn delete_flashcard(
 #[tauri::command]
 pub async fn create_flashcard_deck(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     request: CreateFlashcardDeckRequest,
 ) -> Result<FlashcardDeck, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.create_flashcard_deck(request)
-        .await
-        .map_err(|e| format!("Failed to create flashcard deck: {}", e))
+    let _span = crate::metrics::command_span("create_flashcard_deck");
+    flashcard_service(&state, &vector_state).create_deck(request).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard_deck(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
 ) -> Result<Option<FlashcardDeck>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard_deck(&id)
-        .await
-        .map_err(|e| format!("Failed to get flashcard deck: {}", e))
+    let _span = crate::metrics::command_span("get_flashcard_deck");
+    flashcard_service(&state, &vector_state).get_deck(&id).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard_decks(
-    state: State<'_, DatabaseState>
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    include_archived: Option<bool>,
 ) -> Result<Vec<FlashcardDeck>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard_decks()
-        .await
-        .map_err(|e| format!("Failed to get flashcard decks: {}", e))
+    let _span = crate::metrics::command_span("get_flashcard_decks");
+    flashcard_service(&state, &vector_state).get_decks(include_archived.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn get_flashcard_deck_stats(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    deck_id: String,
+) -> Result<FlashcardDeckStats, String> {
+    let _span = crate::metrics::command_span("get_flashcard_deck_stats");
+    flashcard_service(&state, &vector_state).get_deck_stats(&deck_id).await
 }
 
 #[tauri::command]
 pub async fn update_flashcard_deck(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
     request: CreateFlashcardDeckRequest,
 ) -> Result<Option<FlashcardDeck>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.update_flashcard_deck(&id, request)
-        .await
-        .map_err(|e| format!("Failed to update flashcard deck: {}", e))
+    let _span = crate::metrics::command_span("update_flashcard_deck");
+    flashcard_service(&state, &vector_state).update_deck(&id, request).await
 }
 
 #[tauri::command]
 pub async fn delete_flashcard_deck(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     id: String,
 ) -> Result<bool, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.delete_flashcard_deck(&id)
-        .await
-        .map_err(|e| format!("Failed to delete flashcard deck: {}", e))
+    let _span = crate::metrics::command_span("delete_flashcard_deck");
+    flashcard_service(&state, &vector_state).delete_deck(&id).await
+}
+
+// === DECK PACKAGE COMMANDS ===
+
+/// Export a deck and its media as a `.stellardeck` package (see `media::package` for the format)
+#[tauri::command]
+pub async fn export_deck_package(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    deck_id: String,
+    path: String,
+    author: Option<DeckPackageAuthor>,
+) -> Result<String, String> {
+    let _span = crate::metrics::command_span("export_deck_package");
+    let output = flashcard_service(&state, &vector_state).export_deck_package(&deck_id, &path, author).await?;
+    Ok(output.to_string_lossy().to_string())
+}
+
+/// Import a `.stellardeck` package as a new deck, copying its media into the attachments dir
+#[tauri::command]
+pub async fn import_deck_package(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    path: String,
+    category_id: Option<String>,
+) -> Result<FlashcardDeck, String> {
+    let _span = crate::metrics::command_span("import_deck_package");
+    flashcard_service(&state, &vector_state).import_deck_package(std::path::Path::new(&path), category_id).await
 }
 
 // === FLASHCARD REVIEW COMMANDS ===
@@ -192,90 +263,156 @@ pub async fn delete_flashcard_deck(
 #[tauri::command]
 pub async fn record_flashcard_review(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     request: CreateFlashcardReviewRequest,
-) -> Result<FlashcardReview, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.record_flashcard_review(request)
-        .await
-        .map_err(|e| format!("Failed to record flashcard review: {}", e))
+) -> Result<FlashcardReviewResult, String> {
+    let _span = crate::metrics::command_span("record_flashcard_review");
+    flashcard_service(&state, &vector_state).record_review(request).await
+}
+
+/// Grade a typed answer against the card back. Uses the chat model when a provider is given,
+/// otherwise (or if the model fails) falls back to embedding similarity, then to exact matching.
+#[tauri::command]
+pub async fn grade_typed_answer(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    flashcard_id: String,
+    user_answer: String,
+    provider: Option<AIProvider>,
+    model: Option<String>,
+) -> Result<TypedAnswerGrade, String> {
+    let _span = crate::metrics::command_span("grade_typed_answer");
+    flashcard_service(&state, &vector_state).grade_typed_answer(flashcard_id, &user_answer, provider, model).await
 }
 
 #[tauri::command]
 pub async fn get_due_flashcards(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     limit: Option<i32>,
 ) -> Result<Vec<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_due_flashcards(limit)
-        .await
-        .map_err(|e| format!("Failed to get due flashcards: {}", e))
+    let _span = crate::metrics::command_span("get_due_flashcards");
+    flashcard_service(&state, &vector_state).get_due_flashcards(limit).await
 }
 
+/// New cards, optionally from one deck. `order` overrides the deck's new-card order; without a
+/// deck it defaults to creation order.
 #[tauri::command]
 pub async fn get_new_flashcards(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     limit: Option<i32>,
+    deck_id: Option<String>,
+    order: Option<NewCardOrder>,
 ) -> Result<Vec<Flashcard>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_new_flashcards(limit)
-        .await
-        .map_err(|e| format!("Failed to get new flashcards: {}", e))
+    let _span = crate::metrics::command_span("get_new_flashcards");
+    flashcard_service(&state, &vector_state).get_new_flashcards(limit, deck_id.as_deref(), order).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard_review_session(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     session_limit: i32,
     mix_strategy: String,
 ) -> Result<FlashcardReviewSession, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard_review_session(session_limit, &mix_strategy)
-        .await
-        .map_err(|e| format!("Failed to get flashcard review session: {}", e))
+    let _span = crate::metrics::command_span("get_flashcard_review_session");
+    flashcard_service(&state, &vector_state).get_review_session(session_limit, &mix_strategy).await
+}
+
+/// A session sized to fit `minutes_available`, using the user's historical time per card
+#[tauri::command]
+pub async fn get_adaptive_review_session(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    minutes_available: i32,
+) -> Result<AdaptiveReviewSession, String> {
+    let _span = crate::metrics::command_span("get_adaptive_review_session");
+    flashcard_service(&state, &vector_state).get_adaptive_review_session(minutes_available).await
+}
+
+/// Save a drawn review session so it can be resumed if the app closes mid-session. Pass the
+/// returned id as `review_session_id` when recording reviews.
+#[tauri::command]
+pub async fn start_review_session(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    request: StartReviewSessionRequest,
+) -> Result<PersistedReviewSession, String> {
+    let _span = crate::metrics::command_span("start_review_session");
+    flashcard_service(&state, &vector_state).start_review_session(request).await
+}
+
+/// The unfinished review session to continue, if one hasn't expired
+#[tauri::command]
+pub async fn resume_review_session(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<Option<ResumedReviewSession>, String> {
+    let _span = crate::metrics::command_span("resume_review_session");
+    flashcard_service(&state, &vector_state).resume_review_session().await
+}
+
+/// Stop tracking a review session; `abandoned` marks one the user quit rather than finished
+#[tauri::command]
+pub async fn finish_review_session(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+    abandoned: Option<bool>,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("finish_review_session");
+    flashcard_service(&state, &vector_state).finish_review_session(&id, abandoned.unwrap_or(false)).await
+}
+
+/// Build a review session from a filter (tags, deck, category, source document, recent
+/// failures, due soon) instead of the regular schedule
+#[tauri::command]
+pub async fn get_custom_review_session(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    filter: CustomReviewFilter,
+) -> Result<FlashcardReviewSession, String> {
+    let _span = crate::metrics::command_span("get_custom_review_session");
+    flashcard_service(&state, &vector_state).get_custom_review_session(&filter).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard_stats(
-    state: State<'_, DatabaseState>
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
 ) -> Result<FlashcardStats, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard_stats()
-        .await
-        .map_err(|e| format!("Failed to get flashcard stats: {}", e))
+    let _span = crate::metrics::command_span("get_flashcard_stats");
+    flashcard_service(&state, &vector_state).get_stats().await
+}
+
+/// Review history, interval growth, answer time and lapses for one card
+#[tauri::command]
+pub async fn get_flashcard_detail_stats(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    card_id: String,
+) -> Result<FlashcardDetailStats, String> {
+    let _span = crate::metrics::command_span("get_flashcard_detail_stats");
+    flashcard_service(&state, &vector_state).get_detail_stats(&card_id).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard_reviews(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     flashcard_id: String,
 ) -> Result<Vec<FlashcardReview>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard_reviews(&flashcard_id)
-        .await
-        .map_err(|e| format!("Failed to get flashcard reviews: {}", e))
+    let _span = crate::metrics::command_span("get_flashcard_reviews");
+    flashcard_service(&state, &vector_state).get_reviews(&flashcard_id).await
 }
 
 #[tauri::command]
 pub async fn get_flashcard_reviews_by_session(
     state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     session_id: String,
 ) -> Result<Vec<FlashcardReview>, String> {
-    let db_state = state.lock().await;
-    let database = db_state.as_ref().ok_or("Database not initialized")?;
-    
-    database.get_flashcard_reviews_by_session(&session_id)
-        .await
-        .map_err(|e| format!("Failed to get flashcard reviews by session: {}", e))
-} 
\ No newline at end of file
+    let _span = crate::metrics::command_span("get_flashcard_reviews_by_session");
+    flashcard_service(&state, &vector_state).get_reviews_by_session(&session_id).await
+}
@@ -0,0 +1,235 @@
+use crate::database::{Database, Document};
+use crate::embeddings::VectorService;
+use crate::ai::AIProvider;
+use crate::importers::{self, ImportFormat, ImportedContent};
+use crate::importers::handwriting::HandwritingEngine;
+use crate::importers::paste::{detect_paste_kind, PasteKind};
+use crate::commands::pdf::{get_pdf_storage_dir, generate_pdf_filename, process_document_embeddings_with_fallback, upload_and_process_pdf_from_url};
+use crate::scripting;
+use crate::services::DocumentService;
+use crate::services::documents::{HandwritingImport, SmartPasteResult};
+use crate::services::documents::save_imported_document;
+use tauri::{AppHandle, State};
+use tokio::sync::Mutex;
+use std::sync::Arc;
+use std::path::Path;
+
+// State types
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+/// Save a web page reached through a URL import as a clipped article; the raw HTML is kept
+/// in storage like any other imported original. `source_url` is the normalized URL the user
+/// asked for, recorded so importing it again finds this document.
+pub(crate) async fn import_web_page(
+    db_state: &State<'_, DatabaseState>,
+    vector_state: &State<'_, VectorServiceState>,
+    url: &str,
+    html: &str,
+    title: Option<String>,
+    tags: Vec<String>,
+    category_id: Option<String>,
+    source_url: &str,
+) -> Result<Document, String> {
+    let article = importers::html::extract_article(html);
+    if article.markdown.trim().is_empty() {
+        return Err(format!("{} is a web page with no readable article text", url));
+    }
+
+    let stored_filename = generate_pdf_filename("page.html");
+    let stored_path = get_pdf_storage_dir()?.join(&stored_filename);
+    crate::storage::ensure_space(&stored_path, html.len() as u64)?;
+    std::fs::write(&stored_path, html)
+        .map_err(|e| format!("Failed to save page to storage: {}", e))?;
+
+    let imported = ImportedContent {
+        format: ImportFormat::Html,
+        title: article.title,
+        content: article.markdown,
+        method: "web-clipper".to_string(),
+        tags: Vec::new(),
+        details: Some(serde_json::json!({ "source_url": url })),
+    };
+
+    let document = {
+        let db_guard = db_state.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        let mut document = save_imported_document(
+            database,
+            &imported,
+            title.or(imported.title.clone()).unwrap_or_else(|| url.to_string()),
+            tags,
+            category_id,
+            Some(stored_filename),
+            url,
+        ).await?;
+        database.set_document_source_url(&document.id, source_url).await
+            .map_err(|e| format!("Failed to record source URL: {}", e))?;
+        document.source_url = Some(source_url.to_string());
+        document
+    };
+
+    process_document_embeddings_with_fallback(vector_state, db_state, &document, &None).await?;
+    scripting::document_imported(db_state.inner().clone(), vector_state.inner().clone(), &document);
+    DocumentService::new(db_state.inner().clone(), vector_state.inner().clone()).generate_cover_in_background(document.id.clone());
+
+    println!("✅ Clipped {} -> Document: {}", url, document.id);
+    Ok(document)
+}
+
+/// Import any supported file (PDF, HTML, image, markdown/text, Office documents, .eml, .tex) as a
+/// document. The original is kept in storage, the converted markdown becomes the document
+/// content and the converter used is recorded under the "import" key of its metadata.
+#[tauri::command]
+pub async fn import_file(
+    db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    path: String,
+    title: Option<String>,
+    tags: Option<Vec<String>>,
+    category_id: Option<String>,
+) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("import_file");
+    let document = DocumentService::new(db_state.inner().clone(), vector_state.inner().clone())
+        .import_file(Path::new(&path), title, tags.unwrap_or_default(), category_id).await?;
+
+    process_document_embeddings_with_fallback(&vector_state, &db_state, &document, &None).await?;
+
+    println!("✅ Imported {} -> Document: {}", path, document.id);
+    Ok(document)
+}
+
+/// Import a photo of handwritten notes as an editable note. `engine` defaults to a vision model,
+/// which needs `provider` and `model`; "trocr" reads the photo locally instead. The photo is
+/// kept and can be shown next to the note with `get_handwriting_image`.
+#[tauri::command]
+pub async fn import_handwritten_notes(
+    db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    path: String,
+    engine: Option<HandwritingEngine>,
+    provider: Option<AIProvider>,
+    model: Option<String>,
+    title: Option<String>,
+    tags: Option<Vec<String>>,
+    category_id: Option<String>,
+) -> Result<HandwritingImport, String> {
+    let _span = crate::metrics::command_span("import_handwritten_notes");
+    let imported = DocumentService::new(db_state.inner().clone(), vector_state.inner().clone())
+        .import_handwritten_notes(
+            Path::new(&path),
+            engine.unwrap_or_default(),
+            provider,
+            model,
+            title,
+            tags.unwrap_or_default(),
+            category_id,
+        ).await?;
+
+    process_document_embeddings_with_fallback(&vector_state, &db_state, &imported.document, &None).await?;
+
+    println!("✅ Imported handwritten notes {} -> Document: {}", path, imported.document.id);
+    Ok(imported)
+}
+
+#[tauri::command]
+pub async fn get_handwriting_image(
+    db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+) -> Result<Option<String>, String> {
+    let _span = crate::metrics::command_span("get_handwriting_image");
+    DocumentService::new(db_state.inner().clone(), vector_state.inner().clone())
+        .get_handwriting_image(&document_id).await
+}
+
+/// Import pasted text as whatever it turns out to be: a URL goes through the URL import (PDFs,
+/// landing pages and articles), BibTeX entries become reference notes, delimited text a table,
+/// code a snippet note, and plain text a quick capture in the inbox
+#[tauri::command]
+pub async fn smart_paste(
+    app: AppHandle,
+    db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    payload: String,
+    title: Option<String>,
+    tags: Option<Vec<String>>,
+    category_id: Option<String>,
+) -> Result<SmartPasteResult, String> {
+    let _span = crate::metrics::command_span("smart_paste");
+    if payload.trim().is_empty() {
+        return Err("Nothing to paste".to_string());
+    }
+
+    let kind = detect_paste_kind(&payload);
+    println!("📋 Smart paste detected {}", kind.as_str());
+    if kind == PasteKind::Url {
+        let document = upload_and_process_pdf_from_url(
+            app, db_state, vector_state, payload.trim().to_string(), title, tags, category_id, None, None,
+        ).await?;
+        return Ok(SmartPasteResult { kind, documents: vec![document] });
+    }
+
+    let documents = DocumentService::new(db_state.inner().clone(), vector_state.inner().clone())
+        .paste_content(kind, &payload, title, tags.unwrap_or_default(), category_id).await?;
+    for document in &documents {
+        if let Err(e) = process_document_embeddings_with_fallback(&vector_state, &db_state, document, &None).await {
+            eprintln!("⚠️ Failed to embed pasted document {}: {}", document.id, e);
+        }
+        scripting::document_imported(db_state.inner().clone(), vector_state.inner().clone(), document);
+    }
+
+    Ok(SmartPasteResult { kind, documents })
+}
+
+/// Import every message of an .mbox archive as its own document, tagged with sender and date
+#[tauri::command]
+pub async fn import_mailbox(
+    db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    path: String,
+    tags: Option<Vec<String>>,
+    category_id: Option<String>,
+) -> Result<Vec<Document>, String> {
+    let _span = crate::metrics::command_span("import_mailbox");
+    let source_path = Path::new(&path);
+    let messages = importers::convert_mailbox(source_path)?;
+    let original_filename = source_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("mailbox.mbox")
+        .to_string();
+
+    println!("📥 Importing {} messages from {}", messages.len(), path);
+
+    let mut documents = Vec::new();
+    {
+        let db_guard = db_state.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        for message in &messages {
+            let title = message.title.clone().unwrap_or_else(|| "(no subject)".to_string());
+            let document = save_imported_document(
+                database,
+                message,
+                title,
+                tags.clone().unwrap_or_default(),
+                category_id.clone(),
+                None, // The archive itself isn't kept; each message's text is the document
+                &original_filename,
+            ).await?;
+            documents.push(document);
+        }
+    }
+
+    for document in &documents {
+        if let Err(e) = process_document_embeddings_with_fallback(&vector_state, &db_state, document, &None).await {
+            eprintln!("⚠️ Failed to embed imported message {}: {}", document.id, e);
+        }
+        scripting::document_imported(db_state.inner().clone(), vector_state.inner().clone(), document);
+    }
+
+    println!("✅ Imported {} messages from {}", documents.len(), path);
+    Ok(documents)
+}
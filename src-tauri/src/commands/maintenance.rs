@@ -0,0 +1,123 @@
+use tauri::State;
+use crate::database::{ContentCompressionResult, Database, JobPruneResult, MaintenanceRun, MaintenanceSettings};
+use crate::embeddings::VectorService;
+use crate::maintenance::{prune_jobs, run_maintenance, MAINTENANCE_SETTINGS_KEY};
+use tokio::sync::Mutex;
+use std::sync::Arc;
+
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+// ===== Maintenance Commands =====
+
+#[tauri::command]
+pub async fn run_maintenance_now(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<MaintenanceRun, String> {
+    let _span = crate::metrics::command_span("run_maintenance_now");
+    run_maintenance(state.inner(), vector_state.inner(), "manual").await
+}
+
+#[tauri::command]
+pub async fn get_maintenance_history(
+    state: State<'_, DatabaseState>,
+    limit: Option<i64>,
+) -> Result<Vec<MaintenanceRun>, String> {
+    let _span = crate::metrics::command_span("get_maintenance_history");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_maintenance_runs(limit.unwrap_or(20)).await
+        .map_err(|e| format!("Failed to get maintenance history: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_maintenance_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<MaintenanceSettings, String> {
+    let _span = crate::metrics::command_span("get_maintenance_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(MAINTENANCE_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get maintenance settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_maintenance_settings(
+    state: State<'_, DatabaseState>,
+    settings: MaintenanceSettings,
+) -> Result<MaintenanceSettings, String> {
+    let _span = crate::metrics::command_span("update_maintenance_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    if settings.interval_hours < 1 {
+        return Err("Maintenance interval must be at least one hour".to_string());
+    }
+    if settings.job_retention_days < 0 || settings.failed_job_retention_days < 0 {
+        return Err("Job retention cannot be negative".to_string());
+    }
+
+    database.set_typed_setting(MAINTENANCE_SETTINGS_KEY, &settings).await
+        .map_err(|e| format!("Failed to update maintenance settings: {}", e))?;
+
+    Ok(settings)
+}
+
+/// Remove finished processing jobs older than `older_than_days`. `statuses` defaults to
+/// completed and failed; jobs that are still pending or running can't be pruned. Removed jobs
+/// are archived when the maintenance settings say so, unless `archive` overrides it.
+#[tauri::command]
+pub async fn prune_processing_jobs(
+    state: State<'_, DatabaseState>,
+    older_than_days: i64,
+    statuses: Option<Vec<String>>,
+    archive: Option<bool>,
+) -> Result<JobPruneResult, String> {
+    let _span = crate::metrics::command_span("prune_processing_jobs");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    if older_than_days < 0 {
+        return Err("older_than_days cannot be negative".to_string());
+    }
+    let statuses = statuses.unwrap_or_else(|| vec!["completed".to_string(), "failed".to_string()]);
+    if let Some(status) = statuses.iter().find(|s| !matches!(s.as_str(), "completed" | "failed")) {
+        return Err(format!("Cannot prune jobs with status '{}'; only completed and failed jobs can be pruned", status));
+    }
+
+    let archive = match archive {
+        Some(archive) => archive,
+        None => database.get_typed_setting::<MaintenanceSettings>(MAINTENANCE_SETTINGS_KEY).await
+            .map_err(|e| format!("Failed to load maintenance settings: {}", e))?
+            .archive_pruned_jobs,
+    };
+
+    prune_jobs(database, older_than_days, &statuses, archive).await
+}
+
+/// Compress the content of large documents stored before compression was added. New and
+/// updated documents are compressed as they're written.
+#[tauri::command]
+pub async fn compress_document_contents(
+    state: State<'_, DatabaseState>,
+) -> Result<ContentCompressionResult, String> {
+    let _span = crate::metrics::command_span("compress_document_contents");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let result = database.compress_document_contents().await
+        .map_err(|e| format!("Failed to compress document contents: {}", e))?;
+
+    if result.documents_compressed > 0 {
+        println!(
+            "🗜️ Compressed {} documents ({} MB -> {} MB)",
+            result.documents_compressed,
+            result.bytes_before / (1024 * 1024),
+            result.bytes_after / (1024 * 1024)
+        );
+    }
+    Ok(result)
+}
@@ -0,0 +1,105 @@
+use tauri::State;
+use crate::mcp::{load_mcp_settings, McpServer, MCP_SETTINGS_KEY};
+use crate::database::McpServerSettings;
+use crate::commands::database::DatabaseState;
+use crate::local_server;
+
+// ===== MCP Server Commands =====
+
+#[tauri::command]
+pub async fn get_mcp_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<McpServerSettings, String> {
+    let _span = crate::metrics::command_span("get_mcp_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    load_mcp_settings(database).await
+}
+
+/// Save the settings and start, restart or stop the server to match. The token can only be
+/// changed with `regenerate_mcp_token`.
+#[tauri::command]
+pub async fn update_mcp_settings(
+    state: State<'_, DatabaseState>,
+    server: State<'_, McpServer>,
+    mut settings: McpServerSettings,
+) -> Result<McpServerSettings, String> {
+    let _span = crate::metrics::command_span("update_mcp_settings");
+    local_server::validate_port(settings.port)?;
+
+    {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+        settings.token = load_mcp_settings(database).await?.token;
+        database.set_typed_setting(MCP_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to update MCP settings: {}", e))?;
+    }
+
+    if settings.enabled {
+        server.start(state.inner().clone(), &settings).await?;
+    } else {
+        server.stop().await;
+    }
+
+    Ok(settings)
+}
+
+/// Replace the token; connected clients have to be given the new one
+#[tauri::command]
+pub async fn regenerate_mcp_token(
+    state: State<'_, DatabaseState>,
+    server: State<'_, McpServer>,
+) -> Result<McpServerSettings, String> {
+    let _span = crate::metrics::command_span("regenerate_mcp_token");
+    let settings = {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+        let mut settings = load_mcp_settings(database).await?;
+        settings.token = Some(local_server::generate_token());
+        database.set_typed_setting(MCP_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to update MCP settings: {}", e))?;
+        settings
+    };
+
+    // A running server still has the old token
+    if server.is_running() {
+        server.start(state.inner().clone(), &settings).await?;
+    }
+
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn is_mcp_server_running(
+    server: State<'_, McpServer>,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("is_mcp_server_running");
+    Ok(server.is_running())
+}
+
+/// An `mcpServers` entry to paste into an MCP client's config (Claude Desktop's
+/// claude_desktop_config.json and similar), bridged to stdio with mcp-remote
+#[tauri::command]
+pub async fn get_mcp_client_config(
+    state: State<'_, DatabaseState>,
+) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_mcp_client_config");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let settings = load_mcp_settings(database).await?;
+    let url = format!("http://127.0.0.1:{}/mcp", settings.port);
+    let token = settings.token.unwrap_or_default();
+
+    Ok(serde_json::json!({
+        "mcpServers": {
+            "stellar": {
+                "command": "npx",
+                "args": ["-y", "mcp-remote", url, "--header", format!("Authorization: Bearer {}", token)],
+            }
+        }
+    }))
+}
@@ -5,6 +5,29 @@ pub mod pdf;
 pub mod embeddings;
 pub mod flashcards;
 pub mod background_processing;
+pub mod practice;
+pub mod conversations;
+pub mod maintenance;
+pub mod import;
+pub mod models;
+pub mod network;
+pub mod reminders;
+pub mod clipboard;
+pub mod deep_link;
+pub mod companion;
+pub mod mcp;
+pub mod scripting;
+pub mod profiles;
+pub mod settings;
+pub mod translation;
+pub mod reading;
+pub mod search;
+pub mod crash_reports;
+pub mod performance;
+pub mod onboarding;
+pub mod question_bank;
+pub mod essays;
+pub mod equations;
 
 pub use actions::*;
 pub use ai::*;
@@ -13,42 +36,53 @@ pub use pdf::*;
 pub use embeddings::*;
 pub use flashcards::*;
 pub use background_processing::*;
+pub use practice::*;
+pub use conversations::*;
+pub use maintenance::*;
+pub use import::*;
+pub use models::*;
+pub use network::*;
+pub use reminders::*;
+pub use clipboard::*;
+pub use deep_link::*;
+pub use companion::*;
+pub use mcp::*;
+pub use scripting::*;
+pub use profiles::*;
+pub use settings::*;
+pub use translation::*;
+pub use reading::*;
+pub use search::*;
+pub use crash_reports::*;
+pub use performance::*;
+pub use onboarding::*;
+pub use question_bank::*;
+pub use essays::*;
+pub use equations::*;
 
 // Re-export the simple commands here
 #[tauri::command]
 pub fn greet(name: &str) -> String {
+    let _span = crate::metrics::command_span("greet");
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
-// Models.dev API command - temporary debug version
+/// Fetch the models.dev catalog and cache per-model limits in the model_capabilities table
 #[tauri::command]
-pub async fn fetch_models_dev_data() -> Result<serde_json::Value, String> {
-    println!("DEBUG: Fetching models.dev data from Rust backend...");
-    
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://models.dev/api.json")
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch models.dev data: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("API request failed with status: {}", response.status()));
-    }
-    
-    let text = response.text().await
-        .map_err(|e| format!("Failed to get response text: {}", e))?;
-    
-    println!("DEBUG: Raw response length: {} characters", text.len());
-    println!("DEBUG: First 500 chars: {}", &text[..text.len().min(500)]);
-    
-    let data: serde_json::Value = serde_json::from_str(&text)
-        .map_err(|e| format!("Failed to parse JSON response: {}", e))?;
-    
-    println!("DEBUG: Successfully parsed JSON response");
-    if let Some(obj) = data.as_object() {
-        println!("DEBUG: Top-level keys: {:?}", obj.keys().collect::<Vec<_>>());
+pub async fn fetch_models_dev_data(
+    state: tauri::State<'_, DatabaseState>,
+) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("fetch_models_dev_data");
+    let data = crate::ai::models_dev::fetch_catalog().await?;
+
+    let db_state = state.lock().await;
+    if let Some(database) = db_state.as_ref() {
+        let capabilities = crate::ai::models_dev::parse_catalog(&data);
+        match database.replace_model_capabilities(&capabilities).await {
+            Ok(count) => println!("📚 Cached capabilities for {} models from models.dev", count),
+            Err(e) => eprintln!("⚠️ Failed to cache models.dev capabilities: {}", e),
+        }
     }
-    
+
     Ok(data)
 } 
\ No newline at end of file
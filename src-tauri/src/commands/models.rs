@@ -0,0 +1,26 @@
+use crate::models;
+
+pub const MODEL_DOWNLOAD_PROGRESS_EVENT: &str = "model-download-progress";
+
+/// Disk space taken by downloaded models, in total and per backend directory (e.g. fastembed)
+#[tauri::command]
+pub async fn get_models_disk_usage() -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_models_disk_usage");
+    let root = models::models_root()?;
+
+    let mut by_kind = serde_json::Map::new();
+    if let Ok(entries) = std::fs::read_dir(&root) {
+        for entry in entries.flatten().filter(|entry| entry.path().is_dir()) {
+            by_kind.insert(
+                entry.file_name().to_string_lossy().to_string(),
+                models::directory_size(&entry.path()).into(),
+            );
+        }
+    }
+
+    Ok(serde_json::json!({
+        "path": root.to_string_lossy(),
+        "total_bytes": models::directory_size(&root),
+        "by_kind": by_kind,
+    }))
+}
@@ -0,0 +1,71 @@
+use crate::database::Database;
+use crate::network;
+use tauri::State;
+use tokio::sync::Mutex;
+use std::sync::Arc;
+
+// Database state type
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+
+#[tauri::command]
+pub async fn get_offline_mode() -> Result<bool, String> {
+    let _span = crate::metrics::command_span("get_offline_mode");
+    Ok(network::is_offline())
+}
+
+/// Turn offline mode on or off; persisted so it survives restarts
+#[tauri::command]
+pub async fn set_offline_mode(
+    state: State<'_, DatabaseState>,
+    enabled: bool,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("set_offline_mode");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.set_typed_setting(network::OFFLINE_MODE_KEY, &enabled).await
+        .map_err(|e| format!("Failed to save offline mode: {}", e))?;
+    network::set_offline(enabled);
+
+    Ok(enabled)
+}
+
+/// The saved proxy settings; the password itself is never returned, only `has_password`
+#[tauri::command]
+pub async fn get_proxy_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<network::ProxySettings, String> {
+    let _span = crate::metrics::command_span("get_proxy_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let mut settings = database.get_proxy_settings().await
+        .map_err(|e| format!("Failed to get proxy settings: {}", e))?;
+    settings.password = None;
+    Ok(settings)
+}
+
+/// Save proxy settings and apply them to every HTTP client created from now on. Cloud
+/// embedding providers pick them up the next time the embedding service is initialized.
+/// The password is stored encrypted; leave it unset to keep the saved one, or empty to clear it.
+#[tauri::command]
+pub async fn update_proxy_settings(
+    state: State<'_, DatabaseState>,
+    settings: network::ProxySettings,
+) -> Result<network::ProxySettings, String> {
+    let _span = crate::metrics::command_span("update_proxy_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let mut effective = settings.clone();
+    if effective.password.is_none() {
+        effective.password = database.get_api_key(network::PROXY_PASSWORD_KEY_ID).await
+            .map_err(|e| format!("Failed to get proxy password: {}", e))?;
+    }
+    network::set_proxy_settings(effective.clone())?;
+
+    let mut saved = database.save_proxy_settings(&settings).await
+        .map_err(|e| format!("Failed to save proxy settings: {}", e))?;
+    saved.password = None;
+    Ok(saved)
+}
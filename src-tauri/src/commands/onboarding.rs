@@ -0,0 +1,27 @@
+use tauri::State;
+use crate::services::{DatabaseState, OnboardingService, VectorServiceState};
+use crate::services::onboarding::{OnboardingState, SampleContent};
+
+// ===== Onboarding Commands =====
+
+/// What is configured so far: database, embeddings, chat provider keys, Marker and the sample
+#[tauri::command]
+pub async fn get_onboarding_state(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<OnboardingState, String> {
+    let _span = crate::metrics::command_span("get_onboarding_state");
+    OnboardingService::new(state.inner().clone(), vector_state.inner().clone())
+        .get_onboarding_state().await
+}
+
+/// Create the demo document and its flashcard deck, or return them if they already exist
+#[tauri::command]
+pub async fn seed_sample_content(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+) -> Result<SampleContent, String> {
+    let _span = crate::metrics::command_span("seed_sample_content");
+    OnboardingService::new(state.inner().clone(), vector_state.inner().clone())
+        .seed_sample_content().await
+}
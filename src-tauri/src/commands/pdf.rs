@@ -1,7 +1,12 @@
-use crate::database::{Database, Document, CreateDocumentRequest};
-use crate::pdf_processor::{PdfProcessor, MarkerOptions};
+use crate::database::{Database, Document, CreateDocumentRequest, ReprocessDocumentResult};
+use crate::pdf_processor::{PdfProcessor, PdfError, MarkerOptions, MarkerLlmService, ExtractOptions, ExtractionMethod};
 use crate::embeddings::VectorService;
-use tauri::State;
+use crate::importers::url::{normalize_source_url, resolve_url, ResolvedUrl};
+use crate::commands::import::import_web_page;
+use crate::scripting;
+use crate::services::DocumentService;
+use crate::temp_files::{TempFileGuard, TempKind};
+use tauri::{AppHandle, Emitter, State};
 use tokio::sync::Mutex;
 use std::sync::Arc;
 use std::path::PathBuf;
@@ -12,11 +17,8 @@ type DatabaseState = Arc<Mutex<Option<Database>>>;
 type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
 
 // Helper function to get PDF storage directory
-fn get_pdf_storage_dir() -> Result<PathBuf, String> {
-    let home_dir = dirs::home_dir()
-        .ok_or("Could not find home directory")?;
-    
-    let storage_dir = home_dir.join("stellar_data").join("pdfs");
+pub(crate) fn get_pdf_storage_dir() -> Result<PathBuf, String> {
+    let storage_dir = crate::paths::app_data_dir()?.join("pdfs");
     
     std::fs::create_dir_all(&storage_dir)
         .map_err(|e| format!("Failed to create PDF storage directory: {}", e))?;
@@ -24,8 +26,56 @@ fn get_pdf_storage_dir() -> Result<PathBuf, String> {
     Ok(storage_dir)
 }
 
+pub const PDF_DOWNLOAD_PROGRESS_EVENT: &str = "pdf-download-progress";
+
+// Stream a PDF into PDF storage, emitting `pdf-download-progress` events.
+// Returns the name taken from the URL and the stored filename.
+async fn download_pdf_to_storage(app: &AppHandle, url: &str) -> Result<(String, String), String> {
+    // Last path segment, so arxiv.org/pdf/2401.01234 becomes 2401.01234.pdf
+    let filename = reqwest::Url::parse(url).ok()
+        .and_then(|parsed| parsed.path_segments()?.last().map(|name| name.to_string()))
+        .filter(|name| !name.is_empty())
+        .map(|name| if name.to_ascii_lowercase().ends_with(".pdf") { name } else { format!("{}.pdf", name) })
+        .unwrap_or_else(|| "downloaded.pdf".to_string());
+    let stored_filename = generate_pdf_filename(&filename);
+    let stored_path = get_pdf_storage_dir()?.join(&stored_filename);
+
+    let downloaded = crate::downloads::download_to_file(url, &stored_path, crate::downloads::DEFAULT_MAX_DOWNLOAD_BYTES, |progress| {
+        let _ = app.emit(PDF_DOWNLOAD_PROGRESS_EVENT, progress);
+    }).await
+        .map_err(|e| format!("Failed to download PDF: {}", e))?;
+
+    println!("📥 Downloaded {} ({} bytes) to {:?}", url, downloaded.size, stored_path);
+    Ok((filename, stored_filename))
+}
+
+// Look for an earlier import of any of these normalized URLs. Returns its document, or an error
+// while it is still queued and has no document yet.
+pub(crate) async fn find_existing_url_import(db_state: &State<'_, DatabaseState>, source_urls: &[&str]) -> Result<Option<Document>, String> {
+    let db_guard = db_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    for source_url in source_urls {
+        if let Some(document) = database.find_document_by_source_url(source_url).await
+            .map_err(|e| format!("Failed to look up source URL: {}", e))? {
+            return Ok(Some(document));
+        }
+        if let Some(job) = database.find_processing_job_by_source_url(source_url).await
+            .map_err(|e| format!("Failed to look up source URL: {}", e))? {
+            if let Some(document_id) = &job.result_document_id {
+                if let Some(document) = database.get_document(document_id).await
+                    .map_err(|e| format!("Failed to get document: {}", e))? {
+                    return Ok(Some(document));
+                }
+            }
+            return Err(format!("{} is already being imported (job {}); pass force to import it again", source_url, job.id));
+        }
+    }
+    Ok(None)
+}
+
 // Helper function to generate unique filename
-fn generate_pdf_filename(original_name: &str) -> String {
+pub(crate) fn generate_pdf_filename(original_name: &str) -> String {
     let uuid = Uuid::new_v4();
     let extension = std::path::Path::new(original_name)
         .extension()
@@ -47,6 +97,41 @@ fn is_pdf_filename(file_name: &str) -> bool {
     file_extension_lower(file_name) == "pdf"
 }
 
+// Helper function to build extraction options; None uses the default Marker -> MarkItDown -> Enhanced -> Basic chain
+fn extract_options_for(extraction_methods: Option<Vec<ExtractionMethod>>) -> ExtractOptions {
+    match extraction_methods {
+        Some(methods) if !methods.is_empty() => ExtractOptions::with_methods(methods),
+        _ => ExtractOptions::default(),
+    }
+}
+
+// Helper function to turn extraction failures into user-facing messages
+pub(crate) fn describe_pdf_error(error: PdfError) -> String {
+    match error {
+        PdfError::ExtractionError(msg) => {
+            if msg.contains("marker_single command is not available") {
+                "Marker PDF processor not installed. Please install it using: pip install marker-pdf".to_string()
+            } else if msg.contains("out of memory") {
+                "PDF file too large or complex. Try processing a smaller file.".to_string()
+            } else if msg.contains("API key") {
+                "Invalid or missing API key. Please check your Gemini API key in settings.".to_string()
+            } else if msg.contains("timed out") {
+                "PDF processing timed out. The file may be too large or complex.".to_string()
+            } else if msg.contains("Permission denied") {
+                "Permission denied. Please check file permissions and try again.".to_string()
+            } else {
+                format!("PDF processing failed: {}", msg)
+            }
+        }
+        PdfError::IoError(e) => {
+            format!("File system error: {}", e)
+        }
+        PdfError::NetworkError(e) => {
+            format!("Network error: {}", e)
+        }
+    }
+}
+
 #[tauri::command]
 pub async fn upload_and_process_pdf(
     db_state: State<'_, DatabaseState>,
@@ -55,7 +140,9 @@ pub async fn upload_and_process_pdf(
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("upload_and_process_pdf");
     println!("DEBUG: upload_and_process_pdf called with file_path: {}", file_path);
     
     let db_guard = db_state.lock().await;
@@ -73,48 +160,22 @@ pub async fn upload_and_process_pdf(
     let stored_path = storage_dir.join(&stored_filename);
     
     // Copy the file to persistent storage
+    crate::storage::ensure_space_for_copy(std::path::Path::new(&file_path), &stored_path)?;
     std::fs::copy(&file_path, &stored_path)
         .map_err(|e| format!("Failed to copy PDF to storage: {}", e))?;
     
     println!("DEBUG: PDF copied to persistent storage: {:?}", stored_path);
     
-    // Process the PDF using Marker
+    // Process the PDF, walking the extraction chain until a method succeeds
     let processor = PdfProcessor::new();
-    println!("DEBUG: Created PDF processor");
-    
-    let marker_options = MarkerOptions {
-        extract_images: false,
-        force_ocr: false,
-        prefer_marker: true,
-    };
-    
-    let content = processor.extract_with_marker(&file_path, marker_options).await
+    let extract_options = extract_options_for(extraction_methods);
+
+    let extraction = processor.extract_with_options(&file_path, &extract_options).await
         .map_err(|e| {
             eprintln!("❌ PDF processing error: {:?}", e);
-            match e {
-                crate::pdf_processor::PdfError::ExtractionError(msg) => {
-                    if msg.contains("marker_single command is not available") {
-                        format!("Marker PDF processor not installed. Please install it using: pip install marker-pdf")
-                    } else if msg.contains("out of memory") {
-                        format!("PDF file too large or complex. Try processing a smaller file.")
-                    } else if msg.contains("API key") {
-                        format!("Invalid or missing API key. Please check your Gemini API key in settings.")
-                    } else if msg.contains("timed out") {
-                        format!("PDF processing timed out. The file may be too large or complex.")
-                    } else if msg.contains("Permission denied") {
-                        format!("Permission denied. Please check file permissions and try again.")
-                    } else {
-                        format!("PDF processing failed: {}", msg)
-                    }
-                }
-                crate::pdf_processor::PdfError::IoError(e) => {
-                    format!("File system error: {}", e)
-                }
-                crate::pdf_processor::PdfError::NetworkError(e) => {
-                    format!("Network error: {}", e)
-                }
-            }
+            describe_pdf_error(e)
         })?;
+    let content = extraction.content.clone();
     
     println!("DEBUG: Extracted content length: {}", content.len());
     
@@ -155,17 +216,22 @@ pub async fn upload_and_process_pdf(
     // Save to database
     let document = database.create_document(request).await
         .map_err(|e| format!("Failed to save document: {}", e))?;
+    let document = database.set_document_metadata_field(&document.id, "extraction", extraction.to_metadata()).await
+        .map_err(|e| format!("Failed to record extraction method: {}", e))?
+        .unwrap_or(document);
     
     println!("DEBUG: Document saved to database: {}", document.id);
     
     // Process embeddings with proper fallback logic
     process_document_embeddings_with_fallback(&vector_state, &db_state, &document, &duplicate_check).await?;
+    scripting::document_imported(db_state.inner().clone(), vector_state.inner().clone(), &document);
+    DocumentService::new(db_state.inner().clone(), vector_state.inner().clone()).generate_cover_in_background(document.id.clone());
     
     Ok(document)
 }
 
 // Helper function to process embeddings for a document with proper fallback
-async fn process_document_embeddings_with_fallback(
+pub(crate) async fn process_document_embeddings_with_fallback(
     vector_state: &State<'_, VectorServiceState>,
     db_state: &State<'_, DatabaseState>,
     document: &crate::database::types::Document,
@@ -236,7 +302,7 @@ async fn process_document_embeddings_with_fallback(
 }
 
 // Helper function to process embeddings for a document
-async fn process_document_embeddings_internal(
+pub(crate) async fn process_document_embeddings_internal(
     vector_service: &mut crate::embeddings::VectorService,
     document: &crate::database::types::Document
 ) -> Result<(), String> {
@@ -254,6 +320,9 @@ async fn process_document_embeddings_internal(
             if let Some(path) = &document.file_path {
                 metadata.insert("file_path".to_string(), path.clone());
             }
+            if let Some(language) = &document.language {
+                metadata.insert("language".to_string(), language.clone());
+            }
             
             crate::embeddings::DocumentChunk {
                 id: format!("{}_{}", document.id, i),
@@ -292,7 +361,9 @@ pub async fn upload_and_process_pdf_from_data(
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("upload_and_process_pdf_from_data");
     println!("DEBUG: upload_and_process_pdf_from_data called with file_name: {}", file_name);
     
     let db_guard = db_state.lock().await;
@@ -306,56 +377,30 @@ pub async fn upload_and_process_pdf_from_data(
     let stored_path = storage_dir.join(&stored_filename);
     
     // Write the file data to persistent storage
+    crate::storage::ensure_space(&stored_path, file_data.len() as u64)?;
     std::fs::write(&stored_path, &file_data)
         .map_err(|e| format!("Failed to save PDF: {}", e))?;
     
     println!("DEBUG: Saved PDF to persistent storage: {:?}", stored_path);
     
-    // Create temporary file for processing (some processors might need file path)
-    let temp_dir = std::env::temp_dir().join("stellar_processing");
-    std::fs::create_dir_all(&temp_dir)
-        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
-    
-    let temp_file_path = temp_dir.join(&file_name);
+    // Create temporary file for processing (some processors might need file path); it's
+    // deleted when the guard drops, including on the error returns below
+    let temp_file = TempFileGuard::new(TempKind::Processing, &file_name)?;
+    let temp_file_path = temp_file.path().to_path_buf();
+    crate::storage::ensure_space(&temp_file_path, file_data.len() as u64)?;
     std::fs::write(&temp_file_path, &file_data)
         .map_err(|e| format!("Failed to create temp processing file: {}", e))?;
     
-    // Process the PDF using Marker
+    // Process the PDF, walking the extraction chain until a method succeeds
     let processor = PdfProcessor::new();
-    
-    let marker_options = MarkerOptions {
-        extract_images: false,
-        force_ocr: false,
-        prefer_marker: true,
-    };
-    
-    let content = processor.extract_with_marker(temp_file_path.to_str().unwrap(), marker_options).await
+    let extract_options = extract_options_for(extraction_methods);
+
+    let extraction = processor.extract_with_options(temp_file_path.to_str().unwrap(), &extract_options).await
         .map_err(|e| {
             eprintln!("❌ PDF processing error: {:?}", e);
-            match e {
-                crate::pdf_processor::PdfError::ExtractionError(msg) => {
-                    if msg.contains("marker_single command is not available") {
-                        format!("Marker PDF processor not installed. Please install it using: pip install marker-pdf")
-                    } else if msg.contains("out of memory") {
-                        format!("PDF file too large or complex. Try processing a smaller file.")
-                    } else if msg.contains("API key") {
-                        format!("Invalid or missing API key. Please check your Gemini API key in settings.")
-                    } else if msg.contains("timed out") {
-                        format!("PDF processing timed out. The file may be too large or complex.")
-                    } else if msg.contains("Permission denied") {
-                        format!("Permission denied. Please check file permissions and try again.")
-                    } else {
-                        format!("PDF processing failed: {}", msg)
-                    }
-                }
-                crate::pdf_processor::PdfError::IoError(e) => {
-                    format!("File system error: {}", e)
-                }
-                crate::pdf_processor::PdfError::NetworkError(e) => {
-                    format!("Network error: {}", e)
-                }
-            }
+            describe_pdf_error(e)
         })?;
+    let content = extraction.content.clone();
     
     println!("DEBUG: Extracted content length: {}", content.len());
     
@@ -376,7 +421,7 @@ pub async fn upload_and_process_pdf_from_data(
     }
     
     // Clean up temporary processing file
-    let _ = std::fs::remove_file(&temp_file_path);
+    drop(temp_file);
     
     let doc_title = title.unwrap_or(metadata.title);
     
@@ -396,106 +441,90 @@ pub async fn upload_and_process_pdf_from_data(
     // Save to database
     let document = database.create_document(request).await
         .map_err(|e| format!("Failed to save document: {}", e))?;
+    let document = database.set_document_metadata_field(&document.id, "extraction", extraction.to_metadata()).await
+        .map_err(|e| format!("Failed to record extraction method: {}", e))?
+        .unwrap_or(document);
     
     println!("DEBUG: Document saved to database: {}", document.id);
     
     // Process embeddings with proper fallback logic
     process_document_embeddings_with_fallback(&vector_state, &db_state, &document, &duplicate_check).await?;
+    scripting::document_imported(db_state.inner().clone(), vector_state.inner().clone(), &document);
+    DocumentService::new(db_state.inner().clone(), vector_state.inner().clone()).generate_cover_in_background(document.id.clone());
     
     Ok(document)
 }
 
 #[tauri::command]
 pub async fn upload_and_process_pdf_from_url(
+    app: AppHandle,
     db_state: State<'_, DatabaseState>,
     vector_state: State<'_, VectorServiceState>,
     url: String,
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
+    force: Option<bool>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("upload_and_process_pdf_from_url");
+    crate::network::ensure_reachable(&url, "Importing from a URL")?;
     println!("DEBUG: upload_and_process_pdf_from_url called with URL: {}", url);
     
+    // Importing the same URL twice returns the first import unless forced
+    let force = force.unwrap_or(false);
+    let source_url = normalize_source_url(&url);
+    if !force {
+        if let Some(existing) = find_existing_url_import(&db_state, &[&source_url]).await? {
+            println!("♻️ {} was already imported as document {}", url, existing.id);
+            return Ok(existing);
+        }
+    }
+
+    // Landing pages (arXiv abstracts, DOI links) are followed to their PDF; articles are clipped
+    let pdf_url = match resolve_url(&url).await? {
+        ResolvedUrl::Pdf { url: pdf_url } => pdf_url,
+        ResolvedUrl::WebPage { url: page_url, html } => {
+            println!("📰 {} is a web page, clipping it instead", page_url);
+            return import_web_page(&db_state, &vector_state, &page_url, &html, title, tags.unwrap_or_default(), category_id, &source_url).await;
+        }
+    };
+
+    // A DOI or landing page may lead to a PDF that was imported directly before
+    let pdf_source_url = normalize_source_url(&pdf_url);
+    if !force && pdf_source_url != source_url {
+        if let Some(existing) = find_existing_url_import(&db_state, &[&pdf_source_url]).await? {
+            println!("♻️ {} was already imported as document {}", pdf_url, existing.id);
+            return Ok(existing);
+        }
+    }
+
+    // Download before taking the database lock; large files can take minutes
+    let (filename, stored_filename) = download_pdf_to_storage(&app, &pdf_url).await?;
+    let stored_path = get_pdf_storage_dir()?.join(&stored_filename);
+
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
     
     println!("DEBUG: Database state obtained");
     
-    // Download PDF from URL
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await
-        .map_err(|e| format!("Failed to download PDF: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Failed to download PDF: HTTP {}", response.status()));
-    }
-    
-    // Extract filename from URL or use default
-    let filename = url
-        .split('/')
-        .last()
-        .and_then(|name| if name.ends_with(".pdf") { Some(name) } else { None })
-        .unwrap_or("downloaded.pdf");
-    
-    // Get storage directory and generate unique filename
-    let storage_dir = get_pdf_storage_dir()?;
-    let stored_filename = generate_pdf_filename(filename);
-    let stored_path = storage_dir.join(&stored_filename);
-    
-    // Download and save the file to persistent storage
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to read PDF bytes: {}", e))?;
-    
-    std::fs::write(&stored_path, &bytes)
-        .map_err(|e| format!("Failed to save PDF: {}", e))?;
-    
-    println!("DEBUG: Downloaded PDF to persistent storage: {:?}", stored_path);
-    
-    // Create temporary file for processing
-    let temp_dir = std::env::temp_dir().join("stellar_processing");
-    std::fs::create_dir_all(&temp_dir)
-        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
-    
-    let temp_file_path = temp_dir.join(filename);
-    std::fs::write(&temp_file_path, &bytes)
+    // Create temporary file for processing, deleted when the guard drops
+    let temp_file = TempFileGuard::new(TempKind::Processing, &filename)?;
+    let temp_file_path = temp_file.path().to_path_buf();
+    crate::storage::ensure_space_for_copy(&stored_path, &temp_file_path)?;
+    std::fs::copy(&stored_path, &temp_file_path)
         .map_err(|e| format!("Failed to create temp processing file: {}", e))?;
     
-    // Process the PDF using Marker
+    // Process the PDF, walking the extraction chain until a method succeeds
     let processor = PdfProcessor::new();
-    
-    let marker_options = MarkerOptions {
-        extract_images: false,
-        force_ocr: false,
-        prefer_marker: true,
-    };
-    
-    let content = processor.extract_with_marker(temp_file_path.to_str().unwrap(), marker_options).await
+    let extract_options = extract_options_for(extraction_methods);
+
+    let extraction = processor.extract_with_options(temp_file_path.to_str().unwrap(), &extract_options).await
         .map_err(|e| {
             eprintln!("❌ PDF processing error: {:?}", e);
-            match e {
-                crate::pdf_processor::PdfError::ExtractionError(msg) => {
-                    if msg.contains("marker_single command is not available") {
-                        format!("Marker PDF processor not installed. Please install it using: pip install marker-pdf")
-                    } else if msg.contains("out of memory") {
-                        format!("PDF file too large or complex. Try processing a smaller file.")
-                    } else if msg.contains("API key") {
-                        format!("Invalid or missing API key. Please check your Gemini API key in settings.")
-                    } else if msg.contains("timed out") {
-                        format!("PDF processing timed out. The file may be too large or complex.")
-                    } else if msg.contains("Permission denied") {
-                        format!("Permission denied. Please check file permissions and try again.")
-                    } else {
-                        format!("PDF processing failed: {}", msg)
-                    }
-                }
-                crate::pdf_processor::PdfError::IoError(e) => {
-                    format!("File system error: {}", e)
-                }
-                crate::pdf_processor::PdfError::NetworkError(e) => {
-                    format!("Network error: {}", e)
-                }
-            }
+            describe_pdf_error(e)
         })?;
+    let content = extraction.content.clone();
     
     println!("DEBUG: Extracted content length: {}", content.len());
     
@@ -516,7 +545,7 @@ pub async fn upload_and_process_pdf_from_url(
     }
     
     // Clean up temporary processing file
-    let _ = std::fs::remove_file(&temp_file_path);
+    drop(temp_file);
     
     let doc_title = title.unwrap_or(metadata.title);
     
@@ -536,18 +565,49 @@ pub async fn upload_and_process_pdf_from_url(
     // Save to database
     let document = database.create_document(request).await
         .map_err(|e| format!("Failed to save document: {}", e))?;
+    database.set_document_source_url(&document.id, &source_url).await
+        .map_err(|e| format!("Failed to record source URL: {}", e))?;
+    let document = database.set_document_metadata_field(&document.id, "extraction", extraction.to_metadata()).await
+        .map_err(|e| format!("Failed to record extraction method: {}", e))?
+        .unwrap_or(document);
     
     println!("DEBUG: Document saved to database: {}", document.id);
     
     // Process embeddings with proper fallback logic
     process_document_embeddings_with_fallback(&vector_state, &db_state, &document, &duplicate_check).await?;
+    scripting::document_imported(db_state.inner().clone(), vector_state.inner().clone(), &document);
+    DocumentService::new(db_state.inner().clone(), vector_state.inner().clone()).generate_cover_in_background(document.id.clone());
     
     Ok(document)
 }
 
-// New command to serve PDF files to the frontend
+/// Re-run extraction on a PDF document's stored file, optionally forcing a single method, then
+/// replace its content (re-anchoring highlights) and rebuild its embeddings.
+#[tauri::command]
+pub async fn reprocess_document(
+    db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    document_id: String,
+    method: Option<ExtractionMethod>,
+    options: Option<MarkerOptions>,
+) -> Result<ReprocessDocumentResult, String> {
+    let _span = crate::metrics::command_span("reprocess_document");
+    // The service only re-embeds with a loaded vector service, so bring one up first if needed
+    let vector_service_loaded = vector_state.lock().await.is_some();
+    if !vector_service_loaded {
+        if let Err(e) = crate::commands::embeddings::init_embedding_service(vector_state.clone(), db_state.clone(), None).await {
+            println!("❌ Failed to initialize vector service: {}", e);
+        }
+    }
+
+    DocumentService::new(db_state.inner().clone(), vector_state.inner().clone())
+        .reprocess_document(&document_id, method, options).await
+}
+
+// Serve PDF files to the frontend
 #[tauri::command]
 pub async fn get_pdf_file_path(filename: String) -> Result<String, String> {
+    let _span = crate::metrics::command_span("get_pdf_file_path");
     let storage_dir = get_pdf_storage_dir()?;
     let file_path = storage_dir.join(&filename);
     
@@ -563,6 +623,7 @@ pub async fn get_pdf_file_path(filename: String) -> Result<String, String> {
 // New command to serve PDF file content as bytes for react-pdf
 #[tauri::command]
 pub async fn get_pdf_file_content(filename: String) -> Result<Vec<u8>, String> {
+    let _span = crate::metrics::command_span("get_pdf_file_content");
     let storage_dir = get_pdf_storage_dir()?;
     let file_path = storage_dir.join(&filename);
     
@@ -577,53 +638,61 @@ pub async fn get_pdf_file_content(filename: String) -> Result<Vec<u8>, String> {
 // New command: Download PDF from URL and return document, then process in background
 #[tauri::command]
 pub async fn download_pdf_from_url_and_process_background(
+    app: AppHandle,
     db_state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
     url: String,
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
+    force: Option<bool>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("download_pdf_from_url_and_process_background");
+    crate::network::ensure_reachable(&url, "Importing from a URL")?;
     println!("DEBUG: download_pdf_from_url_and_process_background called with URL: {}", url);
     
+    // Importing the same URL twice returns the first import unless forced
+    let force = force.unwrap_or(false);
+    let source_url = normalize_source_url(&url);
+    if !force {
+        if let Some(existing) = find_existing_url_import(&db_state, &[&source_url]).await? {
+            println!("♻️ {} was already imported as document {}", url, existing.id);
+            return Ok(existing);
+        }
+    }
+
+    // Landing pages (arXiv abstracts, DOI links) are followed to their PDF; articles are clipped
+    let pdf_url = match resolve_url(&url).await? {
+        ResolvedUrl::Pdf { url: pdf_url } => pdf_url,
+        ResolvedUrl::WebPage { url: page_url, html } => {
+            println!("📰 {} is a web page, clipping it instead", page_url);
+            return import_web_page(&db_state, &vector_state, &page_url, &html, title, tags.unwrap_or_default(), category_id, &source_url).await;
+        }
+    };
+
+    // A DOI or landing page may lead to a PDF that was imported directly before
+    let pdf_source_url = normalize_source_url(&pdf_url);
+    if !force && pdf_source_url != source_url {
+        if let Some(existing) = find_existing_url_import(&db_state, &[&pdf_source_url]).await? {
+            println!("♻️ {} was already imported as document {}", pdf_url, existing.id);
+            return Ok(existing);
+        }
+    }
+
+    // Download before taking the database lock; large files can take minutes
+    let (filename, stored_filename) = download_pdf_to_storage(&app, &pdf_url).await?;
+    let stored_path = get_pdf_storage_dir()?.join(&stored_filename);
+
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
     
     println!("DEBUG: Database state obtained");
     
-    // Download PDF from URL
-    let client = reqwest::Client::new();
-    let response = client.get(&url).send().await
-        .map_err(|e| format!("Failed to download PDF: {}", e))?;
-    
-    if !response.status().is_success() {
-        return Err(format!("Failed to download PDF: HTTP {}", response.status()));
-    }
-    
-    // Extract filename from URL or use default
-    let filename = url
-        .split('/')
-        .last()
-        .and_then(|name| if name.ends_with(".pdf") { Some(name) } else { None })
-        .unwrap_or("downloaded.pdf");
-    
-    // Get storage directory and generate unique filename
-    let storage_dir = get_pdf_storage_dir()?;
-    let stored_filename = generate_pdf_filename(filename);
-    let stored_path = storage_dir.join(&stored_filename);
-    
-    // Download and save the file to persistent storage
-    let bytes = response.bytes().await
-        .map_err(|e| format!("Failed to read PDF bytes: {}", e))?;
-    
-    std::fs::write(&stored_path, &bytes)
-        .map_err(|e| format!("Failed to save PDF: {}", e))?;
-    
-    println!("DEBUG: Downloaded PDF to persistent storage: {:?}", stored_path);
-    
     // Create document record immediately so it appears in library
     let doc_title = title.unwrap_or_else(|| {
         // Extract title from filename if no title provided
-        std::path::Path::new(filename)
+        std::path::Path::new(&filename)
             .file_stem()
             .and_then(|stem| stem.to_str())
             .unwrap_or("Downloaded PDF")
@@ -641,16 +710,21 @@ pub async fn download_pdf_from_url_and_process_background(
         category_id: category_id.clone(),
     };
     
-    let document = database.create_document(document_request).await
+    let mut document = database.create_document(document_request).await
         .map_err(|e| format!("Failed to create document: {}", e))?;
+    database.set_document_source_url(&document.id, &source_url).await
+        .map_err(|e| format!("Failed to record source URL: {}", e))?;
+    document.source_url = Some(source_url.clone());
     
     println!("DEBUG: Created document record: {}", document.id);
     
     // Create a background processing job to extract content and update the document
-    let processing_options = crate::pdf_processor::MarkerOptions {
+    let processing_options = MarkerOptions {
         extract_images: false,
         force_ocr: false,
         prefer_marker: true,
+        preferred_methods: extraction_methods,
+        ..Default::default()
     };
     
     let options_json = serde_json::to_value(processing_options).unwrap_or_default();
@@ -669,6 +743,7 @@ pub async fn download_pdf_from_url_and_process_background(
             "download_completed": true,
             "existing_document_id": document.id // Reference to update existing document
         })),
+        source_url: Some(source_url),
     };
     
     let job = database.create_processing_job(job_request).await
@@ -688,7 +763,9 @@ pub async fn save_pdf_from_file_and_process_background(
     title: Option<String>,
     tags: Option<Vec<String>>, 
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("save_pdf_from_file_and_process_background");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -702,6 +779,7 @@ pub async fn save_pdf_from_file_and_process_background(
     let storage_dir = get_pdf_storage_dir()?;
     let stored_filename = generate_pdf_filename(original_filename);
     let stored_path = storage_dir.join(&stored_filename);
+    crate::storage::ensure_space_for_copy(std::path::Path::new(&file_path), &stored_path)?;
     std::fs::copy(&file_path, &stored_path)
         .map_err(|e| format!("Failed to copy PDF: {}", e))?;
 
@@ -729,7 +807,10 @@ pub async fn save_pdf_from_file_and_process_background(
         .map_err(|e| format!("Failed to create document: {}", e))?;
 
     // Enqueue background job to extract content and update this document
-    let processing_options = crate::pdf_processor::MarkerOptions::default();
+    let processing_options = MarkerOptions {
+        preferred_methods: extraction_methods,
+        ..Default::default()
+    };
     let options_json = serde_json::to_value(processing_options).unwrap_or_default();
 
     let job_request = crate::database::CreateProcessingJobRequest {
@@ -744,6 +825,7 @@ pub async fn save_pdf_from_file_and_process_background(
         metadata: Some(serde_json::json!({
             "existing_document_id": document.id
         })),
+        source_url: None,
     };
 
     database.create_processing_job(job_request).await
@@ -762,7 +844,9 @@ pub async fn save_pdf_from_data_and_process_background(
     title: Option<String>,
     tags: Option<Vec<String>>, 
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("save_pdf_from_data_and_process_background");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
@@ -770,6 +854,7 @@ pub async fn save_pdf_from_data_and_process_background(
     let storage_dir = get_pdf_storage_dir()?;
     let stored_filename = generate_pdf_filename(&file_name);
     let stored_path = storage_dir.join(&stored_filename);
+    crate::storage::ensure_space(&stored_path, file_data.len() as u64)?;
     std::fs::write(&stored_path, &file_data)
         .map_err(|e| format!("Failed to save PDF: {}", e))?;
 
@@ -797,7 +882,10 @@ pub async fn save_pdf_from_data_and_process_background(
         .map_err(|e| format!("Failed to create document: {}", e))?;
 
     // Enqueue background job to extract content and update this document
-    let processing_options = crate::pdf_processor::MarkerOptions::default();
+    let processing_options = MarkerOptions {
+        preferred_methods: extraction_methods,
+        ..Default::default()
+    };
     let options_json = serde_json::to_value(processing_options).unwrap_or_default();
 
     let job_request = crate::database::CreateProcessingJobRequest {
@@ -812,6 +900,7 @@ pub async fn save_pdf_from_data_and_process_background(
         metadata: Some(serde_json::json!({
             "existing_document_id": document.id
         })),
+        source_url: None,
     };
 
     database.create_processing_job(job_request).await
@@ -830,13 +919,16 @@ pub async fn save_document_from_data_and_process_background(
     title: Option<String>,
     tags: Option<Vec<String>>,
     category_id: Option<String>,
+    extraction_methods: Option<Vec<ExtractionMethod>>,
 ) -> Result<Document, String> {
+    let _span = crate::metrics::command_span("save_document_from_data_and_process_background");
     let db_guard = db_state.lock().await;
     let database = db_guard.as_ref().ok_or("Database not initialized")?;
 
     let storage_dir = get_pdf_storage_dir()?;
     let stored_filename = generate_pdf_filename(&file_name);
     let stored_path = storage_dir.join(&stored_filename);
+    crate::storage::ensure_space(&stored_path, file_data.len() as u64)?;
     std::fs::write(&stored_path, &file_data)
         .map_err(|e| format!("Failed to save document: {}", e))?;
 
@@ -874,7 +966,10 @@ pub async fn save_document_from_data_and_process_background(
     let document = database.create_document(request).await
         .map_err(|e| format!("Failed to create document: {}", e))?;
 
-    let processing_options = crate::pdf_processor::MarkerOptions::default();
+    let processing_options = MarkerOptions {
+        preferred_methods: extraction_methods,
+        ..Default::default()
+    };
     let options_json = serde_json::to_value(processing_options).unwrap_or_default();
 
     let job_request = crate::database::CreateProcessingJobRequest {
@@ -890,6 +985,7 @@ pub async fn save_document_from_data_and_process_background(
             "existing_document_id": document.id,
             "source_extension": file_extension_lower(&file_name)
         })),
+        source_url: None,
     };
 
     database.create_processing_job(job_request).await
@@ -901,6 +997,7 @@ pub async fn save_document_from_data_and_process_background(
 // New command to clean up PDF file when document is deleted
 #[tauri::command]
 pub async fn delete_pdf_file(filename: String) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_pdf_file");
     let storage_dir = get_pdf_storage_dir()?;
     let file_path = storage_dir.join(&filename);
     
@@ -918,6 +1015,7 @@ pub async fn delete_pdf_file(filename: String) -> Result<bool, String> {
 // Check if marker_single command is available on the system
 #[tauri::command]
 pub async fn check_marker_availability() -> Result<crate::pdf_processor::MarkerInstallationStatus, String> {
+    let _span = crate::metrics::command_span("check_marker_availability");
     let processor = PdfProcessor::new();
     let status = processor.get_marker_installation_status().await;
     Ok(status)
@@ -926,23 +1024,41 @@ pub async fn check_marker_availability() -> Result<crate::pdf_processor::MarkerI
 // Get marker configuration status
 #[tauri::command]
 pub async fn get_marker_config(
-    _db_state: State<'_, DatabaseState>, // Disabled
+    db_state: State<'_, DatabaseState>,
 ) -> Result<serde_json::Value, String> {
+    let _span = crate::metrics::command_span("get_marker_config");
     let processor = PdfProcessor::new();
     let installation_status = processor.get_marker_installation_status().await;
+    let features = if installation_status.is_available {
+        processor.get_marker_feature_support().await
+    } else {
+        Default::default()
+    };
+
+    let (has_gemini_key, has_openai_key) = {
+        let db_guard = db_state.lock().await;
+        match db_guard.as_ref() {
+            Some(database) => (
+                database.get_api_key(MarkerLlmService::Gemini.api_key_provider_id()).await.ok().flatten().is_some(),
+                database.get_api_key(MarkerLlmService::OpenAI.api_key_provider_id()).await.ok().flatten().is_some(),
+            ),
+            None => (false, false),
+        }
+    };
+    let has_llm_key = has_gemini_key || has_openai_key;
 
-    // Gemini API key functionality is disabled
-    
     Ok(serde_json::json!({
         "available": installation_status.is_available,
         "installation_status": installation_status,
-        "has_gemini_key": false, // Disabled
+        "has_gemini_key": has_gemini_key,
+        "has_openai_key": has_openai_key,
         "supported_features": {
-            "llm_processing": false, // Disabled
-            "format_lines": false, // Disabled
+            "llm_processing": features.use_llm && has_llm_key,
+            "format_lines": features.format_lines,
             "force_ocr": installation_status.is_available,
-            "inline_math": false // Disabled
-        }
+            "inline_math": features.inline_math && has_llm_key
+        },
+        "marker_flags": features
     }))
 }
 
@@ -0,0 +1,10 @@
+use crate::metrics::{self, PerformanceMetrics};
+
+// ===== Performance Commands =====
+
+/// p50/p95 per command since start and the slowest recent database statements
+#[tauri::command]
+pub async fn get_performance_metrics() -> Result<PerformanceMetrics, String> {
+    let _span = crate::metrics::command_span("get_performance_metrics");
+    Ok(metrics::performance_metrics())
+}
@@ -0,0 +1,159 @@
+use tauri::State;
+use crate::database::{
+    Database, CategoryPracticeSettings, UpdatePracticeSettingsRequest, Quiz, ProcessingJob,
+    Concept, ConceptItemType, ConceptMastery, QuizAnswer
+};
+use tokio::sync::Mutex;
+use std::sync::Arc;
+
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+
+// ===== Practice Settings Commands =====
+
+#[tauri::command]
+pub async fn get_category_practice_settings(
+    state: State<'_, DatabaseState>,
+    category_id: String,
+) -> Result<Option<CategoryPracticeSettings>, String> {
+    let _span = crate::metrics::command_span("get_category_practice_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_practice_settings(&category_id).await
+        .map_err(|e| format!("Failed to get practice settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_category_practice_settings(
+    state: State<'_, DatabaseState>,
+    category_id: String,
+    request: UpdatePracticeSettingsRequest,
+) -> Result<CategoryPracticeSettings, String> {
+    let _span = crate::metrics::command_span("update_category_practice_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.update_practice_settings(&category_id, request).await
+        .map_err(|e| format!("Failed to update practice settings: {}", e))
+}
+
+// ===== Quiz Commands =====
+
+/// Queue practice generation for a document now, using its category's settings
+#[tauri::command]
+pub async fn generate_document_practice(
+    state: State<'_, DatabaseState>,
+    document_id: String,
+) -> Result<Option<ProcessingJob>, String> {
+    let _span = crate::metrics::command_span("generate_document_practice");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    let document = database.get_document(&document_id).await
+        .map_err(|e| format!("Failed to get document: {}", e))?
+        .ok_or("Document not found")?;
+
+    database.enqueue_practice_generation(&document).await
+        .map_err(|e| format!("Failed to queue practice generation: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_document_quizzes(
+    state: State<'_, DatabaseState>,
+    document_id: String,
+) -> Result<Vec<Quiz>, String> {
+    let _span = crate::metrics::command_span("get_document_quizzes");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_quizzes_by_document(&document_id).await
+        .map_err(|e| format!("Failed to get quizzes: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_quiz(
+    state: State<'_, DatabaseState>,
+    quiz_id: String,
+) -> Result<Option<Quiz>, String> {
+    let _span = crate::metrics::command_span("get_quiz");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_quiz(&quiz_id).await
+        .map_err(|e| format!("Failed to get quiz: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_quiz(
+    state: State<'_, DatabaseState>,
+    quiz_id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_quiz");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.delete_quiz(&quiz_id).await
+        .map_err(|e| format!("Failed to delete quiz: {}", e))
+}
+
+// ===== Concept Mastery Commands =====
+
+/// Record whether a quiz question was answered correctly, counting towards its concepts
+#[tauri::command]
+pub async fn record_quiz_answer(
+    state: State<'_, DatabaseState>,
+    question_id: String,
+    correct: bool,
+) -> Result<QuizAnswer, String> {
+    let _span = crate::metrics::command_span("record_quiz_answer");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.record_quiz_answer(&question_id, correct).await
+        .map_err(|e| format!("Failed to record quiz answer: {}", e))
+}
+
+/// Tag a flashcard or quiz question with concepts, creating the ones the category doesn't have
+#[tauri::command]
+pub async fn link_concepts(
+    state: State<'_, DatabaseState>,
+    item_type: ConceptItemType,
+    item_id: String,
+    category_id: Option<String>,
+    concepts: Vec<String>,
+) -> Result<Vec<Concept>, String> {
+    let _span = crate::metrics::command_span("link_concepts");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.link_concepts(item_type, &item_id, category_id.as_deref(), &concepts).await
+        .map_err(|e| format!("Failed to link concepts: {}", e))
+}
+
+#[tauri::command]
+pub async fn get_concept_mastery(
+    state: State<'_, DatabaseState>,
+    category_id: Option<String>,
+) -> Result<Vec<ConceptMastery>, String> {
+    let _span = crate::metrics::command_span("get_concept_mastery");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_concept_mastery(category_id.as_deref()).await
+        .map_err(|e| format!("Failed to get concept mastery: {}", e))
+}
+
+/// Practiced concepts the student is weakest at, which practice generation targets
+#[tauri::command]
+pub async fn get_weak_concepts(
+    state: State<'_, DatabaseState>,
+    category_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ConceptMastery>, String> {
+    let _span = crate::metrics::command_span("get_weak_concepts");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_weak_concepts(category_id.as_deref(), limit.unwrap_or(10)).await
+        .map_err(|e| format!("Failed to get weak concepts: {}", e))
+}
@@ -0,0 +1,40 @@
+use tauri::AppHandle;
+use crate::profiles::{self, Profile, ProfileInfo};
+
+// ===== Profile Commands =====
+
+#[tauri::command]
+pub async fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
+    let _span = crate::metrics::command_span("list_profiles");
+    profiles::list_profiles()
+}
+
+#[tauri::command]
+pub async fn get_active_profile() -> Result<ProfileInfo, String> {
+    let _span = crate::metrics::command_span("get_active_profile");
+    let active = profiles::active_profile_id();
+    profiles::list_profiles()?.into_iter()
+        .find(|info| info.profile.id == active)
+        .ok_or_else(|| format!("Profile not found: {}", active))
+}
+
+/// Create an empty profile; switch to it with `switch_profile`
+#[tauri::command]
+pub async fn create_profile(name: String) -> Result<Profile, String> {
+    let _span = crate::metrics::command_span("create_profile");
+    profiles::create_profile(&name)
+}
+
+/// Make `id` the active profile and restart the app into it, so the database, storage and
+/// embeddings are all reopened from that profile's directory
+#[tauri::command]
+pub async fn switch_profile(app: AppHandle, id: String) -> Result<(), String> {
+    let _span = crate::metrics::command_span("switch_profile");
+    if id == profiles::active_profile_id() {
+        return Ok(());
+    }
+    let info = profiles::set_active_profile(&id)?;
+    println!("👤 Switching to profile '{}', restarting", info.profile.name);
+    crate::shutdown::restart(&app).await;
+    Ok(())
+}
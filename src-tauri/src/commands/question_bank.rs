@@ -0,0 +1,76 @@
+use tauri::State;
+use crate::database::{ExamPaper, ExamQuestion, QuestionBankTopic};
+use crate::services::{DatabaseState, QuestionBankService};
+use crate::services::question_bank::{ExamPaperImport, ImportExamPaperRequest};
+
+// ===== Question Bank Commands =====
+
+/// Split a past exam paper into questions and add them to the bank, with the paper's year and,
+/// when a model is given, each question's topic
+#[tauri::command]
+pub async fn import_exam_paper(
+    state: State<'_, DatabaseState>,
+    request: ImportExamPaperRequest,
+) -> Result<ExamPaperImport, String> {
+    let _span = crate::metrics::command_span("import_exam_paper");
+    QuestionBankService::new(state.inner().clone()).import_exam_paper(request).await
+}
+
+#[tauri::command]
+pub async fn get_exam_papers(
+    state: State<'_, DatabaseState>,
+    category_id: Option<String>,
+) -> Result<Vec<ExamPaper>, String> {
+    let _span = crate::metrics::command_span("get_exam_papers");
+    QuestionBankService::new(state.inner().clone()).get_exam_papers(category_id.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_exam_questions(
+    state: State<'_, DatabaseState>,
+    paper_id: String,
+) -> Result<Vec<ExamQuestion>, String> {
+    let _span = crate::metrics::command_span("get_exam_questions");
+    QuestionBankService::new(state.inner().clone()).get_exam_questions(&paper_id).await
+}
+
+#[tauri::command]
+pub async fn delete_exam_paper(
+    state: State<'_, DatabaseState>,
+    paper_id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_exam_paper");
+    QuestionBankService::new(state.inner().clone()).delete_exam_paper(&paper_id).await
+}
+
+#[tauri::command]
+pub async fn set_exam_question_topic(
+    state: State<'_, DatabaseState>,
+    question_id: String,
+    topic: Option<String>,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("set_exam_question_topic");
+    QuestionBankService::new(state.inner().clone()).set_exam_question_topic(&question_id, topic.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn get_question_bank_topics(
+    state: State<'_, DatabaseState>,
+    category_id: Option<String>,
+) -> Result<Vec<QuestionBankTopic>, String> {
+    let _span = crate::metrics::command_span("get_question_bank_topics");
+    QuestionBankService::new(state.inner().clone()).get_question_bank_topics(category_id.as_deref()).await
+}
+
+/// Random past questions on a topic (any topic when none is given)
+#[tauri::command]
+pub async fn get_practice_set(
+    state: State<'_, DatabaseState>,
+    topic: Option<String>,
+    count: Option<i32>,
+    category_id: Option<String>,
+) -> Result<Vec<ExamQuestion>, String> {
+    let _span = crate::metrics::command_span("get_practice_set");
+    QuestionBankService::new(state.inner().clone())
+        .get_practice_set(topic.as_deref(), count.unwrap_or(10), category_id.as_deref()).await
+}
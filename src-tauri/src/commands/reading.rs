@@ -0,0 +1,54 @@
+use tauri::State;
+use crate::ai::AIProvider;
+use crate::ai::reading::{ExplanationLevel, SectionSummaryResult, SelectionExplanationResult, TextRange};
+use crate::ai::transcripts::StructuredTranscriptResult;
+use crate::services::{DatabaseState, ReadingService, VectorServiceState};
+
+// ===== Reading Assistance Commands =====
+
+/// Summarize one section of a document, found by its heading titles from the top level down
+/// (e.g. ["Chapter 3", "Entropy"]). The section text never leaves the backend.
+#[tauri::command]
+pub async fn summarize_section(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    provider: AIProvider,
+    model: String,
+    document_id: String,
+    heading_path: Vec<String>,
+) -> Result<SectionSummaryResult, String> {
+    let _span = crate::metrics::command_span("summarize_section");
+    ReadingService::new(state.inner().clone(), vector_state.inner().clone())
+        .summarize_section(provider, model, &document_id, &heading_path).await
+}
+
+/// Explain the text at `range` (char offsets into the document) for a reader at `level`
+#[tauri::command]
+pub async fn explain_selection(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    provider: AIProvider,
+    model: String,
+    document_id: String,
+    range: TextRange,
+    level: Option<ExplanationLevel>,
+) -> Result<SelectionExplanationResult, String> {
+    let _span = crate::metrics::command_span("explain_selection");
+    ReadingService::new(state.inner().clone(), vector_state.inner().clone())
+        .explain_selection(provider, model, &document_id, range, level.unwrap_or_default()).await
+}
+
+/// Turn a transcript document into outlined notes (headings with timestamps, bullet summaries,
+/// action items), saved as a new note linked to the transcript
+#[tauri::command]
+pub async fn structure_transcript(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    provider: AIProvider,
+    model: String,
+    document_id: String,
+) -> Result<StructuredTranscriptResult, String> {
+    let _span = crate::metrics::command_span("structure_transcript");
+    ReadingService::new(state.inner().clone(), vector_state.inner().clone())
+        .structure_transcript(provider, model, &document_id).await
+}
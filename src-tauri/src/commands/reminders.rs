@@ -0,0 +1,54 @@
+use tauri::{AppHandle, State};
+use crate::database::ReminderSettings;
+use crate::commands::database::DatabaseState;
+use crate::reminders::{send_notification, Reminder, REMINDER_SETTINGS_KEY};
+
+// ===== Reminder Commands =====
+
+#[tauri::command]
+pub async fn get_reminder_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<ReminderSettings, String> {
+    let _span = crate::metrics::command_span("get_reminder_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(REMINDER_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get reminder settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_reminder_settings(
+    state: State<'_, DatabaseState>,
+    settings: ReminderSettings,
+) -> Result<ReminderSettings, String> {
+    let _span = crate::metrics::command_span("update_reminder_settings");
+    if settings.quiet_hours_start > 23 || settings.quiet_hours_end > 23 || settings.study_goal_nudge_hour > 23 {
+        return Err("Hours must be between 0 and 23".to_string());
+    }
+    if settings.due_review_interval_hours < 1 {
+        return Err("Due review reminders can be sent at most once an hour".to_string());
+    }
+    if settings.due_review_threshold < 0 || settings.study_goal_minutes < 0 {
+        return Err("Reminder thresholds cannot be negative".to_string());
+    }
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.set_typed_setting(REMINDER_SETTINGS_KEY, &settings).await
+        .map_err(|e| format!("Failed to update reminder settings: {}", e))?;
+
+    Ok(settings)
+}
+
+/// Show a notification right away, ignoring quiet hours, so users can check notifications work
+#[tauri::command]
+pub async fn send_test_notification(app: AppHandle) -> Result<(), String> {
+    let _span = crate::metrics::command_span("send_test_notification");
+    send_notification(&app, &Reminder {
+        title: "Stellar".to_string(),
+        body: "Notifications are working".to_string(),
+    });
+    Ok(())
+}
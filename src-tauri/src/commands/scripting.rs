@@ -0,0 +1,138 @@
+use tauri::State;
+use crate::database::{AutomationScript, SaveAutomationScriptRequest, ScriptEvent, ScriptRunResult, ScriptingSettings};
+use crate::scripting::{self, SCRIPTING_SETTINGS_KEY};
+use crate::services::{DatabaseState, Services, VectorServiceState};
+
+// ===== Automation Script Commands =====
+
+#[tauri::command]
+pub async fn get_scripting_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<ScriptingSettings, String> {
+    let _span = crate::metrics::command_span("get_scripting_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_typed_setting(SCRIPTING_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get scripting settings: {}", e))
+}
+
+#[tauri::command]
+pub async fn update_scripting_settings(
+    state: State<'_, DatabaseState>,
+    settings: ScriptingSettings,
+) -> Result<ScriptingSettings, String> {
+    let _span = crate::metrics::command_span("update_scripting_settings");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.set_typed_setting(SCRIPTING_SETTINGS_KEY, &settings).await
+        .map_err(|e| format!("Failed to update scripting settings: {}", e))?;
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn get_automation_scripts(
+    state: State<'_, DatabaseState>,
+) -> Result<Vec<AutomationScript>, String> {
+    let _span = crate::metrics::command_span("get_automation_scripts");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.get_automation_scripts().await
+        .map_err(|e| format!("Failed to get automation scripts: {}", e))
+}
+
+/// Create or update a script; scripts with syntax errors are rejected
+#[tauri::command]
+pub async fn save_automation_script(
+    state: State<'_, DatabaseState>,
+    request: SaveAutomationScriptRequest,
+) -> Result<AutomationScript, String> {
+    let _span = crate::metrics::command_span("save_automation_script");
+    if request.name.trim().is_empty() {
+        return Err("Script name is required".to_string());
+    }
+    scripting::compile_check(&request.source)?;
+
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.save_automation_script(request).await
+        .map_err(|e| format!("Failed to save automation script: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_automation_script(
+    state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<bool, String> {
+    let _span = crate::metrics::command_span("delete_automation_script");
+    let db_state = state.lock().await;
+    let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+    database.delete_automation_script(&id).await
+        .map_err(|e| format!("Failed to delete automation script: {}", e))
+}
+
+/// Run a saved script now, against `payload` or a sample event, and return its output. Runs
+/// even while scripting is turned off, so scripts can be tried out before enabling them.
+#[tauri::command]
+pub async fn run_automation_script(
+    state: State<'_, DatabaseState>,
+    vector_state: State<'_, VectorServiceState>,
+    id: String,
+    payload: Option<serde_json::Value>,
+) -> Result<ScriptRunResult, String> {
+    let _span = crate::metrics::command_span("run_automation_script");
+    let (script, settings, payload) = {
+        let db_state = state.lock().await;
+        let database = db_state.as_ref().ok_or("Database not initialized")?;
+
+        let script = database.get_automation_script(&id).await
+            .map_err(|e| format!("Failed to get automation script: {}", e))?
+            .ok_or_else(|| format!("Automation script not found: {}", id))?;
+        let settings: ScriptingSettings = database.get_typed_setting(SCRIPTING_SETTINGS_KEY).await
+            .map_err(|e| format!("Failed to get scripting settings: {}", e))?;
+
+        let payload = match payload {
+            Some(payload) => payload,
+            None => sample_payload(database, script.event).await?,
+        };
+        (script, settings, payload)
+    };
+
+    let services = Services::new(state.inner().clone(), vector_state.inner().clone());
+    let result = scripting::run_script(&services, &script, &payload, &settings).await;
+
+    let db_state = state.lock().await;
+    if let Some(database) = db_state.as_ref() {
+        database.record_automation_script_run(&script.id, result.error.as_deref()).await
+            .map_err(|e| format!("Failed to record script run: {}", e))?;
+    }
+    Ok(result)
+}
+
+// The most recent document for import scripts; a made-up tally for review scripts
+async fn sample_payload(database: &crate::database::Database, event: ScriptEvent) -> Result<serde_json::Value, String> {
+    match event {
+        ScriptEvent::DocumentImported => {
+            let documents = database.get_all_documents().await
+                .map_err(|e| format!("Failed to get documents: {}", e))?;
+            let document = documents.iter().max_by_key(|document| document.created_at)
+                .ok_or("No documents to run the script against")?;
+            Ok(scripting::document_imported_payload(document))
+        }
+        ScriptEvent::ReviewCompleted => Ok(serde_json::json!({
+            "review_session_id": "sample",
+            "study_session_id": null,
+            "cards_drawn": 10,
+            "cards_reviewed": 10,
+            "correct": 7,
+            "partial": 2,
+            "incorrect": 1,
+            "average_quality": 3.8,
+            "flashcard_ids": [],
+        })),
+    }
+}
@@ -0,0 +1,18 @@
+use tauri::State;
+use crate::database::{GlobalSearchLimits, GlobalSearchResults};
+use crate::services::{DatabaseState, SearchService};
+
+// ===== Search Commands =====
+
+/// Search documents, highlights, flashcards and conversations at once, for a single search
+/// box. `limits` caps each group (defaults apply to the ones left out).
+#[tauri::command]
+pub async fn global_search(
+    state: State<'_, DatabaseState>,
+    query: String,
+    limits: Option<GlobalSearchLimits>,
+) -> Result<GlobalSearchResults, String> {
+    let _span = crate::metrics::command_span("global_search");
+    SearchService::new(state.inner().clone())
+        .global_search(&query, limits.unwrap_or_default()).await
+}
@@ -0,0 +1,48 @@
+use std::path::Path;
+use tauri::State;
+use crate::services::SettingsService;
+use crate::services::settings::{SettingsBundle, SettingsBundleImportResult};
+use crate::services::DatabaseState;
+use crate::redaction::RedactionSettings;
+
+// ===== Settings Bundle Commands =====
+
+/// App settings, AI profiles, session templates, deck options and automation scripts as one
+/// portable JSON bundle, without library data, API keys or access tokens
+#[tauri::command]
+pub async fn export_settings_bundle(
+    state: State<'_, DatabaseState>,
+) -> Result<SettingsBundle, String> {
+    let _span = crate::metrics::command_span("export_settings_bundle");
+    SettingsService::new(state.inner().clone()).export_bundle().await
+}
+
+/// Apply a bundle saved from `export_settings_bundle`, e.g. to set up a lab machine
+#[tauri::command]
+pub async fn import_settings_bundle(
+    state: State<'_, DatabaseState>,
+    path: String,
+) -> Result<SettingsBundleImportResult, String> {
+    let _span = crate::metrics::command_span("import_settings_bundle");
+    SettingsService::new(state.inner().clone()).import_bundle(Path::new(&path)).await
+}
+
+// ===== Redaction Commands =====
+
+#[tauri::command]
+pub async fn get_redaction_settings(
+    state: State<'_, DatabaseState>,
+) -> Result<RedactionSettings, String> {
+    let _span = crate::metrics::command_span("get_redaction_settings");
+    SettingsService::new(state.inner().clone()).get_redaction_settings().await
+}
+
+/// Patterns redacted from deck packages, markdown exports and prompts sent to cloud models
+#[tauri::command]
+pub async fn update_redaction_settings(
+    state: State<'_, DatabaseState>,
+    settings: RedactionSettings,
+) -> Result<RedactionSettings, String> {
+    let _span = crate::metrics::command_span("update_redaction_settings");
+    SettingsService::new(state.inner().clone()).update_redaction_settings(settings).await
+}
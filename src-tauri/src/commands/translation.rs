@@ -0,0 +1,41 @@
+use tauri::State;
+use crate::ai::AIProvider;
+use crate::database::{DocumentTranslation, TranslationResult};
+use crate::services::{DatabaseState, TranslationService};
+
+// ===== Translation Commands =====
+
+/// Translate either `text` or the document `document_id` into `target_lang` (a language name
+/// or code the model understands, e.g. "English" or "de"). Document translations are saved
+/// as linked documents unless `save` is false.
+#[tauri::command]
+pub async fn translate_text(
+    state: State<'_, DatabaseState>,
+    provider: AIProvider,
+    model: String,
+    text: Option<String>,
+    document_id: Option<String>,
+    target_lang: String,
+    save: Option<bool>,
+) -> Result<TranslationResult, String> {
+    let _span = crate::metrics::command_span("translate_text");
+    let service = TranslationService::new(state.inner().clone());
+    match (text, document_id) {
+        (Some(text), None) => service.translate_text(provider, model, &text, &target_lang).await,
+        (None, Some(document_id)) => {
+            service.translate_document(provider, model, &document_id, &target_lang, save.unwrap_or(true)).await
+        }
+        _ => Err("Provide either text or a document id to translate".to_string()),
+    }
+}
+
+/// Translations made of a document, or the source it was translated from
+#[tauri::command]
+pub async fn get_document_translations(
+    state: State<'_, DatabaseState>,
+    document_id: String,
+) -> Result<Vec<DocumentTranslation>, String> {
+    let _span = crate::metrics::command_span("get_document_translations");
+    TranslationService::new(state.inner().clone())
+        .get_document_translations(&document_id).await
+}
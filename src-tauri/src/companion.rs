@@ -0,0 +1,269 @@
+//! Localhost endpoint for the browser extension. When enabled it listens on 127.0.0.1 only and
+//! accepts "save this page", "save selection" and "save PDF link" requests, each queued as a
+//! processing job like any other import. Every request must carry the token shown in settings
+//! as `Authorization: Bearer <token>`.
+//!
+//! Extensions call this from their background script with host permission for
+//! `http://127.0.0.1/*`, so no CORS handling is needed (or wanted: web pages can't reach it).
+
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::background_processor::{create_pdf_processing_job, create_web_clip_job};
+use crate::commands::database::DatabaseState;
+use crate::database::{CompanionServerSettings, Database};
+use crate::importers::url::normalize_source_url;
+use crate::local_server::{self, bearer_token_matches, LocalServer, LocalServerSettings};
+use crate::pdf_processor::MarkerOptions;
+
+pub const COMPANION_SETTINGS_KEY: &str = "companion_server";
+
+// Full pages with inline styles and data URIs get large
+const MAX_BODY_BYTES: usize = 25 * 1024 * 1024;
+
+type ApiResult = Result<Json<SaveResponse>, (StatusCode, String)>;
+
+#[derive(Clone)]
+struct ServerContext {
+    database: DatabaseState,
+    token: String,
+    default_tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavePageRequest {
+    url: String,
+    title: Option<String>,
+    html: String, // Rendered page as the browser sees it, so pages behind a login work
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SaveSelectionRequest {
+    url: Option<String>,
+    title: Option<String>,
+    text: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavePdfRequest {
+    url: String,
+    title: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    force: bool, // Import again even if the URL was imported before
+}
+
+#[derive(Debug, Serialize)]
+struct SaveResponse {
+    status: &'static str, // "queued", or "exists" when the URL was already imported
+    job_id: Option<String>,
+    document_id: Option<String>,
+}
+
+impl SaveResponse {
+    fn queued(job_id: String) -> Self {
+        Self { status: "queued", job_id: Some(job_id), document_id: None }
+    }
+
+    fn exists(job_id: Option<String>, document_id: Option<String>) -> Self {
+        Self { status: "exists", job_id, document_id }
+    }
+}
+
+/// Managed state holding the running server task, if any
+#[derive(Default)]
+pub struct CompanionServer(LocalServer);
+
+impl CompanionServer {
+    pub fn is_running(&self) -> bool {
+        self.0.is_running()
+    }
+
+    /// Listen with `settings`, replacing a server that is already running
+    pub async fn start(&self, database: DatabaseState, settings: &CompanionServerSettings) -> Result<(), String> {
+        let token = settings.token.clone().ok_or("No companion token has been generated")?;
+        let context = ServerContext {
+            database,
+            token,
+            default_tags: settings.default_tags.clone(),
+        };
+        let router = Router::new()
+            .route("/status", get(status))
+            .route("/save/page", post(save_page))
+            .route("/save/selection", post(save_selection))
+            .route("/save/pdf", post(save_pdf))
+            .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+            .with_state(context);
+
+        self.0.start(settings.port, router, "Browser extension endpoint").await?;
+        println!("🧩 Browser extension endpoint listening on 127.0.0.1:{}", settings.port);
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if self.0.stop().await {
+            println!("🧩 Stopped browser extension endpoint");
+        }
+    }
+}
+
+impl LocalServerSettings for CompanionServerSettings {
+    fn token_mut(&mut self) -> &mut Option<String> {
+        &mut self.token
+    }
+}
+
+/// Load the settings, generating and saving a token the first time
+pub async fn load_companion_settings(database: &Database) -> Result<CompanionServerSettings, String> {
+    local_server::load_settings(database, COMPANION_SETTINGS_KEY).await
+}
+
+fn authorize(context: &ServerContext, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    if bearer_token_matches(headers, &context.token) {
+        Ok(())
+    } else {
+        Err((StatusCode::UNAUTHORIZED, "Missing or invalid token".to_string()))
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, message.into())
+}
+
+fn internal_error(message: String) -> (StatusCode, String) {
+    eprintln!("❌ Browser extension request failed: {}", message);
+    (StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
+fn validate_web_url(url: &str) -> Result<(), (StatusCode, String)> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err(bad_request(format!("Not a web URL: {}", url))),
+    }
+}
+
+/// Request tags plus the configured defaults, without duplicates
+fn merge_tags(context: &ServerContext, tags: Vec<String>) -> Vec<String> {
+    let mut merged: Vec<String> = Vec::new();
+    for tag in context.default_tags.iter().cloned().chain(tags) {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !merged.contains(&tag) {
+            merged.push(tag);
+        }
+    }
+    merged
+}
+
+async fn status(State(context): State<ServerContext>, headers: HeaderMap) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    authorize(&context, &headers)?;
+    Ok(Json(serde_json::json!({
+        "app": "stellar",
+        "version": env!("CARGO_PKG_VERSION"),
+    })))
+}
+
+async fn save_page(State(context): State<ServerContext>, headers: HeaderMap, Json(request): Json<SavePageRequest>) -> ApiResult {
+    authorize(&context, &headers)?;
+    validate_web_url(&request.url)?;
+    if request.html.trim().is_empty() {
+        return Err(bad_request("The page has no content"));
+    }
+
+    let db_guard = context.database.lock().await;
+    let database = db_guard.as_ref().ok_or_else(|| internal_error("Database not initialized".to_string()))?;
+
+    // A page already queued or imported from the app or the extension isn't saved twice
+    let source_url = normalize_source_url(&request.url);
+    if let Some(job) = database.find_processing_job_by_source_url(&source_url).await
+        .map_err(|e| internal_error(format!("Failed to look up source URL: {}", e)))?
+    {
+        return Ok(Json(SaveResponse::exists(Some(job.id), job.result_document_id)));
+    }
+    if let Some(document) = database.find_document_by_source_url(&source_url).await
+        .map_err(|e| internal_error(format!("Failed to look up source URL: {}", e)))?
+    {
+        return Ok(Json(SaveResponse::exists(None, Some(document.id))));
+    }
+
+    let job = create_web_clip_job(database, &request.url, &request.html, false, request.title, merge_tags(&context, request.tags)).await
+        .map_err(internal_error)?;
+    println!("🧩 Queued page {} from the browser (job {})", request.url, job.id);
+    Ok(Json(SaveResponse::queued(job.id)))
+}
+
+async fn save_selection(State(context): State<ServerContext>, headers: HeaderMap, Json(request): Json<SaveSelectionRequest>) -> ApiResult {
+    authorize(&context, &headers)?;
+    let page_url = request.url.unwrap_or_default();
+    if !page_url.is_empty() {
+        validate_web_url(&page_url)?;
+    }
+    if request.text.trim().is_empty() {
+        return Err(bad_request("The selection is empty"));
+    }
+
+    let db_guard = context.database.lock().await;
+    let database = db_guard.as_ref().ok_or_else(|| internal_error("Database not initialized".to_string()))?;
+
+    let job = create_web_clip_job(database, &page_url, &request.text, true, request.title, merge_tags(&context, request.tags)).await
+        .map_err(internal_error)?;
+    println!("🧩 Queued selection from {} (job {})", if page_url.is_empty() { "the browser" } else { &page_url }, job.id);
+    Ok(Json(SaveResponse::queued(job.id)))
+}
+
+async fn save_pdf(State(context): State<ServerContext>, headers: HeaderMap, Json(request): Json<SavePdfRequest>) -> ApiResult {
+    authorize(&context, &headers)?;
+    validate_web_url(&request.url)?;
+    crate::network::ensure_reachable(&request.url, "Importing from a URL").map_err(bad_request)?;
+
+    let db_guard = context.database.lock().await;
+    let database = db_guard.as_ref().ok_or_else(|| internal_error("Database not initialized".to_string()))?;
+
+    // Same duplicate rules as URL imports from inside the app
+    if !request.force {
+        let source_url = normalize_source_url(&request.url);
+        if let Some(job) = database.find_processing_job_by_source_url(&source_url).await
+            .map_err(|e| internal_error(format!("Failed to look up source URL: {}", e)))?
+        {
+            return Ok(Json(SaveResponse::exists(Some(job.id), job.result_document_id)));
+        }
+        if let Some(document) = database.find_document_by_source_url(&source_url).await
+            .map_err(|e| internal_error(format!("Failed to look up source URL: {}", e)))?
+        {
+            return Ok(Json(SaveResponse::exists(None, Some(document.id))));
+        }
+    }
+
+    let filename = request.url
+        .split('/')
+        .last()
+        .filter(|name| name.ends_with(".pdf"))
+        .unwrap_or("download.pdf");
+    let processing_options = MarkerOptions {
+        extract_images: false,
+        prefer_marker: true,
+        ..Default::default()
+    };
+
+    let job = create_pdf_processing_job(
+        database,
+        "url",
+        Some(request.url.clone()),
+        filename,
+        request.title,
+        merge_tags(&context, request.tags),
+        None,
+        Some(processing_options),
+    )
+    .await
+    .map_err(internal_error)?;
+    println!("🧩 Queued PDF {} from the browser (job {})", request.url, job.id);
+    Ok(Json(SaveResponse::queued(job.id)))
+}
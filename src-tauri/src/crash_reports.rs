@@ -0,0 +1,164 @@
+//! Local crash reports: panics and command errors written as JSON files to the
+//! `crash_reports` directory of the data root, for users to attach to GitHub issues. Nothing is
+//! ever sent anywhere.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::path::PathBuf;
+
+use crate::paths;
+
+const CRASH_REPORTS_DIR: &str = "crash_reports";
+// Oldest reports of a kind are deleted past its cap, so a command failing in a loop can't fill
+// the disk. Each kind rotates on its own so a flood of command errors never evicts a panic.
+const MAX_PANIC_REPORTS: usize = 50;
+const MAX_COMMAND_ERROR_REPORTS: usize = 200;
+const MAX_MESSAGE_CHARS: usize = 4_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashReportKind {
+    Panic,
+    CommandError,
+}
+
+impl CrashReportKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            CrashReportKind::Panic => "panic",
+            CrashReportKind::CommandError => "command_error",
+        }
+    }
+
+    fn max_reports(self) -> usize {
+        match self {
+            CrashReportKind::Panic => MAX_PANIC_REPORTS,
+            CrashReportKind::CommandError => MAX_COMMAND_ERROR_REPORTS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub kind: CrashReportKind,
+    pub message: String,
+    pub command: Option<String>, // The failing command, for command errors
+    pub location: Option<String>, // file:line of a panic
+    pub thread: Option<String>,
+    pub backtrace: Option<String>,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CrashReport {
+    fn new(kind: CrashReportKind, message: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind,
+            message: message.chars().take(MAX_MESSAGE_CHARS).collect(),
+            command: None,
+            location: None,
+            thread: None,
+            backtrace: None,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Write a report for every panic, then run the default hook (which prints it to stderr)
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info.payload().downcast_ref::<&str>().map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+
+        let mut report = CrashReport::new(CrashReportKind::Panic, &message);
+        report.location = info.location().map(|location| format!("{}:{}", location.file(), location.line()));
+        report.thread = std::thread::current().name().map(str::to_string);
+        report.backtrace = Some(Backtrace::force_capture().to_string());
+        // A failing write must not turn the panic into an abort
+        if let Err(e) = write_report(&report) {
+            eprintln!("⚠️ Failed to write crash report: {}", e);
+        }
+
+        default_hook(info);
+    }));
+}
+
+/// Record an error returned by a command, e.g. one the frontend reports after `invoke` failed
+pub fn record_command_error(command: &str, error: &str) -> Result<CrashReport, String> {
+    let mut report = CrashReport::new(CrashReportKind::CommandError, error);
+    report.command = Some(command.to_string());
+    write_report(&report)?;
+    Ok(report)
+}
+
+/// All reports, newest first. Files that don't parse are skipped.
+pub fn get_crash_reports() -> Result<Vec<CrashReport>, String> {
+    let mut reports: Vec<CrashReport> = report_files()?
+        .into_iter()
+        .filter_map(|path| std::fs::read_to_string(&path).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    reports.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    Ok(reports)
+}
+
+/// Delete every report, returning how many were removed
+pub fn clear_crash_reports() -> Result<usize, String> {
+    let mut removed = 0;
+    for path in report_files()? {
+        std::fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete crash report {}: {}", path.display(), e))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+fn crash_reports_dir() -> Result<PathBuf, String> {
+    let dir = paths::root_data_dir()?.join(CRASH_REPORTS_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create crash report directory: {}", e))?;
+    Ok(dir)
+}
+
+// Report files sorted oldest first; names start with the timestamp
+fn report_files() -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(crash_reports_dir()?)
+        .map_err(|e| format!("Failed to read crash report directory: {}", e))?;
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn write_report(report: &CrashReport) -> Result<(), String> {
+    let short_id: String = report.id.chars().take(8).collect();
+    let file_name = format!("{}-{}-{}.json", report.created_at.format("%Y%m%dT%H%M%S%.3fZ"), report.kind.as_str(), short_id);
+    let path = crash_reports_dir()?.join(file_name);
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+    std::fs::write(&path, json)
+        .map_err(|e| format!("Failed to write crash report {}: {}", path.display(), e))?;
+
+    let marker = format!("-{}-", report.kind.as_str());
+    let files: Vec<PathBuf> = report_files()?
+        .into_iter()
+        .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.contains(&marker)))
+        .collect();
+    for old in files.iter().take(files.len().saturating_sub(report.kind.max_reports())) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+use sqlx::Row;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, Timelike, Utc};
+use std::collections::HashSet;
+use super::{Database, types::{ActivityBreakdown, DailyActivity}};
+
+// Time after an action counts toward it for at most this long; the rest of a gap is idle
+const ACTIVITY_IDLE_CAP_SECONDS: i64 = 10 * 60;
+// Today's summary is recomputed once it is older than this; finished days are cached for good
+const DAILY_ACTIVITY_CACHE_SECONDS: i64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy)]
+enum ActivityKind {
+    Reading,
+    Reviewing,
+    Chatting,
+    NoteTaking,
+    Other,
+}
+
+impl ActivityKind {
+    fn from_action_type(action_type: &str) -> Self {
+        if action_type.starts_with("document_") {
+            ActivityKind::Reading
+        } else if action_type == "flashcard_review" {
+            ActivityKind::Reviewing
+        } else if action_type.starts_with("chat_") {
+            ActivityKind::Chatting
+        } else if action_type.starts_with("note_") {
+            ActivityKind::NoteTaking
+        } else {
+            ActivityKind::Other
+        }
+    }
+}
+
+impl ActivityBreakdown {
+    fn add(&mut self, kind: ActivityKind, minutes: f64) {
+        match kind {
+            ActivityKind::Reading => self.reading += minutes,
+            ActivityKind::Reviewing => self.reviewing += minutes,
+            ActivityKind::Chatting => self.chatting += minutes,
+            ActivityKind::NoteTaking => self.note_taking += minutes,
+            ActivityKind::Other => self.other += minutes,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.reading + self.reviewing + self.chatting + self.note_taking + self.other
+    }
+}
+
+/// UTC bounds of a local calendar day
+pub(super) fn local_day_bounds(date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let midnight = |date: NaiveDate| {
+        let naive = date.and_time(NaiveTime::MIN);
+        naive.and_local_timezone(Local)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .unwrap_or_else(|| naive.and_utc())
+    };
+    (midnight(date), midnight(date + Duration::days(1)))
+}
+
+impl Database {
+    // === DAILY ACTIVITY ===
+
+    /// Minutes spent reading, reviewing, chatting and taking notes on a local day, from the
+    /// action stream and flashcard reviews. Results are cached in daily_activity_cache.
+    pub async fn get_daily_activity(&self, date: NaiveDate) -> Result<DailyActivity, sqlx::Error> {
+        let key = date.format("%Y-%m-%d").to_string();
+        let (day_start, day_end) = local_day_bounds(date);
+        let now = Utc::now();
+
+        let cached = sqlx::query("SELECT payload, computed_at FROM daily_activity_cache WHERE date = ?")
+            .bind(&key)
+            .fetch_optional(&self.pool)
+            .await?;
+        if let Some(row) = cached {
+            let computed_at = DateTime::parse_from_rfc3339(&row.get::<String, _>("computed_at"))
+                .map(|time| time.with_timezone(&Utc))
+                .ok();
+            // Actions are stamped when recorded, so a day computed after it ended can't change
+            let fresh = computed_at.is_some_and(|computed_at| {
+                computed_at >= day_end || (now - computed_at).num_seconds() < DAILY_ACTIVITY_CACHE_SECONDS
+            });
+            if fresh {
+                if let Ok(activity) = serde_json::from_str::<DailyActivity>(&row.get::<String, _>("payload")) {
+                    return Ok(activity);
+                }
+            }
+        }
+
+        let activity = self.compute_daily_activity(key, day_start, day_end).await?;
+
+        sqlx::query("INSERT OR REPLACE INTO daily_activity_cache (date, payload, computed_at) VALUES (?, ?, ?)")
+            .bind(&activity.date)
+            .bind(serde_json::to_string(&activity).unwrap_or_else(|_| "{}".to_string()))
+            .bind(activity.computed_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(activity)
+    }
+
+    async fn compute_daily_activity(&self, date: String, day_start: DateTime<Utc>, day_end: DateTime<Utc>) -> Result<DailyActivity, sqlx::Error> {
+        let now = Utc::now();
+        let actions: Vec<_> = self.get_actions_by_time_range(day_start, day_end).await?
+            .into_iter()
+            .filter(|action| action.timestamp < day_end)
+            .collect();
+
+        let review_rows = sqlx::query("SELECT session_id, timestamp, time_spent FROM flashcard_reviews WHERE timestamp >= ? AND timestamp < ? ORDER BY timestamp ASC")
+            .bind(day_start.to_rfc3339())
+            .bind(day_end.to_rfc3339())
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut sessions: HashSet<String> = HashSet::new();
+        // (when, what, seconds when known up front)
+        let mut entries: Vec<(DateTime<Utc>, ActivityKind, Option<i64>)> = Vec::new();
+
+        for action in &actions {
+            sessions.insert(action.session_id.clone());
+            // Review rows carry the time spent per card, so skip the duplicate action when both exist
+            if action.action_type == "flashcard_review" && !review_rows.is_empty() {
+                continue;
+            }
+            entries.push((action.timestamp, ActivityKind::from_action_type(&action.action_type), action.duration));
+        }
+
+        for row in &review_rows {
+            if let Some(session_id) = row.get::<Option<String>, _>("session_id") {
+                sessions.insert(session_id);
+            }
+            let Ok(timestamp) = DateTime::parse_from_rfc3339(&row.get::<String, _>("timestamp")) else {
+                continue;
+            };
+            let time_spent: i64 = row.get("time_spent");
+            entries.push((timestamp.with_timezone(&Utc), ActivityKind::Reviewing, Some(time_spent).filter(|&t| t > 0)));
+        }
+
+        entries.sort_by_key(|entry| entry.0);
+
+        // Missing durations come from the gap to the next entry, capped so idle time isn't counted
+        let period_end = day_end.min(now);
+        let next_timestamps: Vec<DateTime<Utc>> = entries.iter()
+            .skip(1)
+            .map(|entry| entry.0)
+            .chain(std::iter::once(period_end))
+            .collect();
+
+        let mut minutes_by_activity = ActivityBreakdown::default();
+        let mut hourly = vec![ActivityBreakdown::default(); 24];
+        for ((timestamp, kind, duration), next) in entries.iter().zip(next_timestamps) {
+            let seconds = match duration {
+                Some(duration) => (*duration).max(0),
+                None => (next - *timestamp).num_seconds().clamp(0, ACTIVITY_IDLE_CAP_SECONDS),
+            };
+            let minutes = seconds as f64 / 60.0;
+            minutes_by_activity.add(*kind, minutes);
+            hourly[timestamp.with_timezone(&Local).hour() as usize].add(*kind, minutes);
+        }
+
+        Ok(DailyActivity {
+            date,
+            total_minutes: minutes_by_activity.total(),
+            minutes_by_activity,
+            hourly,
+            sessions_count: sessions.len() as i64,
+            flashcards_reviewed: entries.iter().filter(|entry| matches!(entry.1, ActivityKind::Reviewing)).count() as i64,
+            first_activity: entries.first().map(|entry| entry.0),
+            last_activity: entries.last().map(|entry| entry.0),
+            computed_at: now,
+        })
+    }
+}
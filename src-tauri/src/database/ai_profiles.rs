@@ -0,0 +1,146 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{AIProfile, CreateAIProfileRequest}};
+
+impl Database {
+    pub async fn create_ai_profile(&self, req: CreateAIProfileRequest) -> Result<AIProfile, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = insert_ai_profile(&self.pool, &id, &req).await?;
+
+        Ok(AIProfile {
+            id,
+            name: req.name,
+            description: req.description,
+            system_prompt: req.system_prompt,
+            default_model: req.default_model,
+            temperature: req.temperature,
+            created_at: now,
+            updated_at: now,
+            metadata: req.metadata,
+        })
+    }
+
+    pub async fn get_ai_profile(&self, id: &str) -> Result<Option<AIProfile>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM ai_profiles WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_ai_profile(row)))
+    }
+
+    pub async fn get_ai_profile_by_name(&self, name: &str) -> Result<Option<AIProfile>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM ai_profiles WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_ai_profile(row)))
+    }
+
+    pub async fn get_ai_profiles(&self) -> Result<Vec<AIProfile>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM ai_profiles ORDER BY name ASC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_ai_profile(row)).collect())
+    }
+
+    pub async fn update_ai_profile(&self, id: &str, req: CreateAIProfileRequest) -> Result<Option<AIProfile>, sqlx::Error> {
+        if !update_ai_profile_row(&self.pool, id, &req).await? {
+            return Ok(None);
+        }
+
+        self.get_ai_profile(id).await
+    }
+
+    pub async fn delete_ai_profile(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM ai_profiles WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Helper function to convert database row to AIProfile
+    fn row_to_ai_profile(&self, row: sqlx::sqlite::SqliteRow) -> AIProfile {
+        let created_at: String = row.get("created_at");
+        let updated_at: String = row.get("updated_at");
+        let metadata: Option<String> = row.get("metadata");
+
+        AIProfile {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            system_prompt: row.get("system_prompt"),
+            default_model: row.get("default_model"),
+            temperature: row.get("temperature"),
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        }
+    }
+}
+
+/// Insert a profile, returning its creation time
+pub(super) async fn insert_ai_profile<'e, E>(executor: E, id: &str, req: &CreateAIProfileRequest) -> Result<DateTime<Utc>, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let now = Utc::now();
+    let metadata_json = req.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+
+    sqlx::query(
+        r#"
+        INSERT INTO ai_profiles (id, name, description, system_prompt, default_model, temperature, created_at, updated_at, metadata)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(id)
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(&req.system_prompt)
+    .bind(&req.default_model)
+    .bind(req.temperature)
+    .bind(now.to_rfc3339())
+    .bind(now.to_rfc3339())
+    .bind(metadata_json)
+    .execute(executor)
+    .await?;
+
+    Ok(now)
+}
+
+/// Overwrite a profile; false when there is none with `id`
+pub(super) async fn update_ai_profile_row<'e, E>(executor: E, id: &str, req: &CreateAIProfileRequest) -> Result<bool, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let metadata_json = req.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+
+    let result = sqlx::query(
+        r#"
+        UPDATE ai_profiles
+        SET name = ?, description = ?, system_prompt = ?, default_model = ?, temperature = ?, updated_at = ?, metadata = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(&req.name)
+    .bind(&req.description)
+    .bind(&req.system_prompt)
+    .bind(&req.default_model)
+    .bind(req.temperature)
+    .bind(Utc::now().to_rfc3339())
+    .bind(metadata_json)
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,78 @@
+use sqlx::Row;
+use super::{Database, activity::local_day_bounds, types::{AnalyticsExportKind, DateRange}};
+
+// Columns per export, in file order. Every value is read back as text so one reader covers
+// all three tables; JSON columns are exported as-is.
+const FLASHCARD_REVIEW_COLUMNS: &[&str] = &[
+    "id", "flashcard_id", "deck_id", "deck_name", "card_front", "session_id", "timestamp", "response",
+    "time_spent", "confidence", "quality", "previous_ef", "new_ef", "previous_interval", "new_interval",
+];
+const STUDY_SESSION_COLUMNS: &[&str] = &[
+    "id", "title", "session_type", "start_time", "end_time", "is_active", "total_duration", "target_duration",
+    "focus_score", "template_id", "documents_accessed_count", "categories_accessed_count", "conversations_count",
+];
+const USER_ACTION_COLUMNS: &[&str] = &[
+    "id", "action_type", "timestamp", "session_id", "duration", "document_ids", "category_ids", "data",
+];
+
+impl Database {
+    // === ANALYTICS EXPORT ===
+
+    /// Header and rows of one kind of study data in `range`, oldest first. Missing values are
+    /// empty strings.
+    pub async fn get_analytics_export_rows(
+        &self,
+        kind: AnalyticsExportKind,
+        range: &DateRange,
+    ) -> Result<(Vec<String>, Vec<Vec<String>>), sqlx::Error> {
+        let start = range.start.map(|date| local_day_bounds(date).0.to_rfc3339());
+        let end = range.end.map(|date| local_day_bounds(date).1.to_rfc3339());
+
+        let (columns, sql) = match kind {
+            AnalyticsExportKind::FlashcardReviews => (FLASHCARD_REVIEW_COLUMNS, r#"
+                SELECT r.id, r.flashcard_id, f.deck_id, d.name AS deck_name, f.front AS card_front, r.session_id,
+                       r.timestamp, r.response, CAST(r.time_spent AS TEXT), CAST(r.confidence AS TEXT),
+                       CAST(r.quality AS TEXT), CAST(r.previous_ef AS TEXT), CAST(r.new_ef AS TEXT),
+                       CAST(r.previous_interval AS TEXT), CAST(r.new_interval AS TEXT)
+                FROM flashcard_reviews r
+                LEFT JOIN flashcards f ON f.id = r.flashcard_id
+                LEFT JOIN flashcard_decks d ON d.id = f.deck_id
+                WHERE (? IS NULL OR r.timestamp >= ?) AND (? IS NULL OR r.timestamp < ?)
+                ORDER BY r.timestamp
+            "#),
+            AnalyticsExportKind::StudySessions => (STUDY_SESSION_COLUMNS, r#"
+                SELECT id, title, session_type, start_time, end_time, CAST(is_active AS TEXT),
+                       CAST(total_duration AS TEXT), CAST(target_duration AS TEXT), CAST(focus_score AS TEXT),
+                       template_id, CAST(json_array_length(documents_accessed) AS TEXT),
+                       CAST(json_array_length(categories_accessed) AS TEXT), CAST(json_array_length(conversation_ids) AS TEXT)
+                FROM study_sessions
+                WHERE (? IS NULL OR start_time >= ?) AND (? IS NULL OR start_time < ?)
+                ORDER BY start_time
+            "#),
+            AnalyticsExportKind::UserActions => (USER_ACTION_COLUMNS, r#"
+                SELECT id, action_type, timestamp, session_id, CAST(duration AS TEXT), document_ids, category_ids, data
+                FROM user_actions
+                WHERE (? IS NULL OR timestamp >= ?) AND (? IS NULL OR timestamp < ?)
+                ORDER BY timestamp
+            "#),
+        };
+
+        let rows = sqlx::query(sql)
+            .bind(&start)
+            .bind(&start)
+            .bind(&end)
+            .bind(&end)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let values = rows.iter()
+            .map(|row| {
+                (0..columns.len())
+                    .map(|index| row.try_get::<Option<String>, _>(index).ok().flatten().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        Ok((columns.iter().map(|column| column.to_string()).collect(), values))
+    }
+}
@@ -0,0 +1,130 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{AutomationScript, SaveAutomationScriptRequest, ScriptEvent}};
+
+impl Database {
+    // === AUTOMATION SCRIPTS ===
+
+    pub async fn get_automation_scripts(&self) -> Result<Vec<AutomationScript>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM automation_scripts ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().filter_map(|row| self.row_to_automation_script(row)).collect())
+    }
+
+    pub async fn get_automation_script(&self, id: &str) -> Result<Option<AutomationScript>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM automation_scripts WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.and_then(|row| self.row_to_automation_script(row)))
+    }
+
+    /// Enabled scripts for an event, in a stable order
+    pub async fn get_enabled_scripts_for_event(&self, event: ScriptEvent) -> Result<Vec<AutomationScript>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM automation_scripts WHERE event = ? AND enabled = TRUE ORDER BY created_at")
+            .bind(event.as_str())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().filter_map(|row| self.row_to_automation_script(row)).collect())
+    }
+
+    /// Create a script, or update the one with `req.id`
+    pub async fn save_automation_script(&self, req: SaveAutomationScriptRequest) -> Result<AutomationScript, sqlx::Error> {
+        let id = write_automation_script(&self.pool, &req).await?;
+        self.get_automation_script(&id).await?.ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn delete_automation_script(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM automation_scripts WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Note when a script last ran and how it ended
+    pub async fn record_automation_script_run(&self, id: &str, error: Option<&str>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE automation_scripts SET last_run_at = ?, last_error = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(error)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Rows with an event this build doesn't know are skipped
+    fn row_to_automation_script(&self, row: sqlx::sqlite::SqliteRow) -> Option<AutomationScript> {
+        let parse = |value: String| DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Some(AutomationScript {
+            id: row.get("id"),
+            name: row.get("name"),
+            event: ScriptEvent::parse(&row.get::<String, _>("event"))?,
+            source: row.get("source"),
+            enabled: row.get("enabled"),
+            last_run_at: row.get::<Option<String>, _>("last_run_at").map(parse),
+            last_error: row.get("last_error"),
+            created_at: parse(row.get("created_at")),
+            updated_at: parse(row.get("updated_at")),
+        })
+    }
+}
+
+/// Insert or update a script row, returning its id
+pub(super) async fn write_automation_script<'e, E>(executor: E, req: &SaveAutomationScriptRequest) -> Result<String, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let now = Utc::now().to_rfc3339();
+
+    let id = match &req.id {
+        Some(id) => {
+            let result = sqlx::query(
+                "UPDATE automation_scripts SET name = ?, event = ?, source = ?, enabled = ?, updated_at = ? WHERE id = ?",
+            )
+            .bind(&req.name)
+            .bind(req.event.as_str())
+            .bind(&req.source)
+            .bind(req.enabled)
+            .bind(&now)
+            .bind(id)
+            .execute(executor)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+            id.clone()
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                r#"
+                INSERT INTO automation_scripts (id, name, event, source, enabled, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&id)
+            .bind(&req.name)
+            .bind(req.event.as_str())
+            .bind(&req.source)
+            .bind(req.enabled)
+            .bind(&now)
+            .bind(&now)
+            .execute(executor)
+            .await?;
+            id
+        }
+    };
+
+    Ok(id)
+}
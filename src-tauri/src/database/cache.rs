@@ -0,0 +1,114 @@
+//! In-memory cache in front of the document and category queries the library views run on
+//! every navigation. Any write to documents or categories clears it: document lists and
+//! category counts depend on almost every column, and writes are rare next to reads.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use moka::sync::Cache;
+use super::types::{Category, Document, QuickSearchHit};
+
+// Weighed by content size, so a few huge textbooks can't pin gigabytes
+const MAX_CACHED_BYTES: u64 = 64 * 1024 * 1024;
+const MAX_CACHED_CATEGORY_LISTS: u64 = 16;
+const MAX_CACHED_CONTENT_SEARCHES: u64 = 256;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum DocumentListKey {
+    All,
+    Category(String),
+    Uncategorized,
+}
+
+pub struct LibraryCache {
+    documents: Cache<String, Arc<Document>>,
+    document_lists: Cache<DocumentListKey, Arc<Vec<Document>>>,
+    categories: Cache<(), Arc<Vec<Category>>>,
+    content_matches: Cache<String, Arc<Vec<QuickSearchHit>>>, // Quick search content hits by lowercased query
+    // Bumped on every write. A read only fills the cache if no write happened while it was
+    // querying, so a slow read can't put back what a write just cleared.
+    generation: AtomicU64,
+}
+
+impl LibraryCache {
+    pub fn new() -> Self {
+        Self {
+            documents: Cache::builder()
+                .weigher(|_, document: &Arc<Document>| document_weight(document))
+                .max_capacity(MAX_CACHED_BYTES / 2)
+                .build(),
+            document_lists: Cache::builder()
+                .weigher(|_, documents: &Arc<Vec<Document>>| {
+                    documents.iter().map(|document| document_weight(document)).fold(0u32, u32::saturating_add)
+                })
+                .max_capacity(MAX_CACHED_BYTES / 2)
+                .build(),
+            categories: Cache::new(MAX_CACHED_CATEGORY_LISTS),
+            content_matches: Cache::new(MAX_CACHED_CONTENT_SEARCHES),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Taken before querying and handed back to the `put_*` methods
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub fn document(&self, id: &str) -> Option<Document> {
+        self.documents.get(id).map(|document| (*document).clone())
+    }
+
+    pub fn put_document(&self, generation: u64, document: &Document) {
+        if self.generation() == generation {
+            self.documents.insert(document.id.clone(), Arc::new(document.clone()));
+        }
+    }
+
+    pub fn document_list(&self, key: &DocumentListKey) -> Option<Vec<Document>> {
+        self.document_lists.get(key).map(|documents| (*documents).clone())
+    }
+
+    pub fn put_document_list(&self, generation: u64, key: DocumentListKey, documents: &[Document]) {
+        if self.generation() == generation {
+            self.document_lists.insert(key, Arc::new(documents.to_vec()));
+        }
+    }
+
+    pub fn categories(&self) -> Option<Vec<Category>> {
+        self.categories.get(&()).map(|categories| (*categories).clone())
+    }
+
+    pub fn put_categories(&self, generation: u64, categories: &[Category]) {
+        if self.generation() == generation {
+            self.categories.insert((), Arc::new(categories.to_vec()));
+        }
+    }
+
+    pub fn content_matches(&self, query: &str) -> Option<Vec<QuickSearchHit>> {
+        self.content_matches.get(query).map(|hits| (*hits).clone())
+    }
+
+    pub fn put_content_matches(&self, generation: u64, query: &str, hits: &[QuickSearchHit]) {
+        if self.generation() == generation {
+            self.content_matches.insert(query.to_string(), Arc::new(hits.to_vec()));
+        }
+    }
+
+    /// Drop everything after documents or categories changed
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.documents.invalidate_all();
+        self.document_lists.invalidate_all();
+        self.categories.invalidate_all();
+        self.content_matches.invalidate_all();
+    }
+}
+
+impl Default for LibraryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn document_weight(document: &Document) -> u32 {
+    (document.content.len() + document.title.len() + 512).min(u32::MAX as usize) as u32
+}
@@ -1,8 +1,11 @@
 use sqlx::Row;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use std::collections::HashSet;
 use super::{Database, types::{Category, CreateCategoryRequest}};
 
+pub(crate) const ARCHIVED_CATEGORY_IDS: &str = "SELECT id FROM categories WHERE archived = 1";
+
 impl Database {
     pub async fn create_category(&self, req: CreateCategoryRequest) -> Result<Category, sqlx::Error> {
         let id = Uuid::new_v4().to_string();
@@ -15,6 +18,7 @@ impl Database {
             color: req.color.clone(),
             icon: req.icon.clone(),
             parent_id: req.parent_id.clone(),
+            archived: false,
             created_at: now,
             updated_at: now,
             document_count: 0,
@@ -36,11 +40,17 @@ impl Database {
         .bind(now.to_rfc3339())
         .execute(&self.pool)
         .await?;
+        self.cache.invalidate();
 
         Ok(category)
     }
 
     pub async fn get_all_categories(&self) -> Result<Vec<Category>, sqlx::Error> {
+        if let Some(categories) = self.cache.categories() {
+            return Ok(categories);
+        }
+        let generation = self.cache.generation();
+
         let rows = sqlx::query(
             r#"
             SELECT c.*, COUNT(d.id) as document_count 
@@ -66,6 +76,7 @@ impl Database {
                 color: row.get("color"),
                 icon: row.get("icon"),
                 parent_id: row.get("parent_id"),
+                archived: row.get::<i64, _>("archived") != 0,
                 created_at: DateTime::parse_from_rfc3339(&created_at)
                     .unwrap_or_else(|_| Utc::now().into())
                     .with_timezone(&Utc),
@@ -76,10 +87,15 @@ impl Database {
             });
         }
 
+        self.cache.put_categories(generation, &categories);
         Ok(categories)
     }
 
     pub async fn get_category(&self, id: &str) -> Result<Option<Category>, sqlx::Error> {
+        if let Some(categories) = self.cache.categories() {
+            return Ok(categories.into_iter().find(|category| category.id == id));
+        }
+
         let row = sqlx::query(
             r#"
             SELECT c.*, COUNT(d.id) as document_count 
@@ -105,6 +121,7 @@ impl Database {
                 color: row.get("color"),
                 icon: row.get("icon"),
                 parent_id: row.get("parent_id"),
+                archived: row.get::<i64, _>("archived") != 0,
                 created_at: DateTime::parse_from_rfc3339(&created_at)
                     .unwrap_or_else(|_| Utc::now().into())
                     .with_timezone(&Utc),
@@ -137,6 +154,7 @@ impl Database {
         .bind(id)
         .execute(&self.pool)
         .await?;
+        self.cache.invalidate();
 
         if result.rows_affected() > 0 {
             self.get_category(id).await
@@ -145,6 +163,72 @@ impl Database {
         }
     }
 
+    // Ids of every document in a category, optionally walking nested subcategories too
+    pub async fn get_category_document_ids(&self, category_id: &str, include_subcategories: bool) -> Result<Vec<String>, sqlx::Error> {
+        let query = if include_subcategories {
+            r#"
+            WITH RECURSIVE category_tree(id) AS (
+                SELECT ?
+                UNION
+                SELECT c.id FROM categories c JOIN category_tree t ON c.parent_id = t.id
+            )
+            SELECT d.id FROM documents d WHERE d.category_id IN (SELECT id FROM category_tree)
+            "#
+        } else {
+            "SELECT id FROM documents WHERE category_id = ?"
+        };
+
+        let rows = sqlx::query(query)
+            .bind(category_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
+
+    /// Archive or restore a category together with its subcategories, returning the category
+    pub async fn set_category_archived(&self, id: &str, archived: bool) -> Result<Option<Category>, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            WITH RECURSIVE category_tree(id) AS (
+                SELECT ?
+                UNION
+                SELECT c.id FROM categories c JOIN category_tree t ON c.parent_id = t.id
+            )
+            UPDATE categories SET archived = ?, updated_at = ? WHERE id IN (SELECT id FROM category_tree)
+            "#,
+        )
+        .bind(id)
+        .bind(archived)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        self.cache.invalidate();
+
+        if result.rows_affected() > 0 {
+            self.get_category(id).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_archived_category_ids(&self) -> Result<HashSet<String>, sqlx::Error> {
+        let rows = sqlx::query("SELECT id FROM categories WHERE archived = 1")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
+
+    /// Documents in archived categories, which default search leaves out
+    pub async fn get_archived_document_ids(&self) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query(&format!("SELECT id FROM documents WHERE category_id IN ({})", ARCHIVED_CATEGORY_IDS))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
+
     pub async fn delete_category(&self, id: &str) -> Result<bool, sqlx::Error> {
         // First, set category_id to NULL for all documents in this category
         sqlx::query("UPDATE documents SET category_id = NULL WHERE category_id = ?")
@@ -157,6 +241,7 @@ impl Database {
             .bind(id)
             .execute(&self.pool)
             .await?;
+        self.cache.invalidate();
 
         Ok(result.rows_affected() > 0)
     }
@@ -0,0 +1,122 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::code;
+use super::{Database, types::CodeSnippet};
+
+impl Database {
+    // === CODE SNIPPETS ===
+
+    /// Replace a document's code snippets with the fenced blocks in `content`, returning how
+    /// many were found
+    pub async fn index_code_snippets(&self, document_id: &str, content: &str) -> Result<usize, sqlx::Error> {
+        let blocks = code::find_code_blocks(content);
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM code_snippets WHERE document_id = ?")
+            .bind(document_id)
+            .execute(&mut *tx)
+            .await?;
+
+        for (index, block) in blocks.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO code_snippets (id, document_id, snippet_index, language, content, start_offset, end_offset, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(document_id)
+            .bind(index as i32)
+            .bind(&block.language)
+            .bind(&block.content)
+            .bind(block.start as i64)
+            .bind(block.end as i64)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(blocks.len())
+    }
+
+    /// Index documents with code fences that have no snippets yet, e.g. ones imported before
+    /// snippets were tracked
+    pub async fn index_missing_code_snippets(&self) -> Result<usize, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT document_id AS id, content FROM document_text WHERE (content LIKE '%```%' OR content LIKE '%~~~%') AND document_id NOT IN (SELECT DISTINCT document_id FROM code_snippets)"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut indexed = 0;
+        for row in rows {
+            let id: String = row.get("id");
+            let content: String = row.get("content");
+            indexed += self.index_code_snippets(&id, &content).await?;
+        }
+
+        Ok(indexed)
+    }
+
+    /// Snippets whose code contains `query` (all snippets when it's blank), optionally only
+    /// in one language
+    pub async fn search_code_snippets(&self, query: &str, language: Option<&str>, limit: i64) -> Result<Vec<CodeSnippet>, sqlx::Error> {
+        let like = format!("%{}%", query.trim());
+
+        let rows = sqlx::query(
+            r#"
+            SELECT code_snippets.*, documents.title AS document_title
+            FROM code_snippets
+            JOIN documents ON documents.id = code_snippets.document_id
+            WHERE code_snippets.content LIKE ? COLLATE NOCASE
+              AND (? IS NULL OR code_snippets.language = ?)
+            ORDER BY documents.updated_at DESC, code_snippets.snippet_index
+            LIMIT ?
+            "#,
+        )
+        .bind(&like)
+        .bind(language)
+        .bind(language)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_code_snippet(row)).collect())
+    }
+
+    pub async fn get_document_code_snippets(&self, document_id: &str) -> Result<Vec<CodeSnippet>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT code_snippets.*, documents.title AS document_title
+            FROM code_snippets
+            JOIN documents ON documents.id = code_snippets.document_id
+            WHERE code_snippets.document_id = ?
+            ORDER BY code_snippets.snippet_index
+            "#,
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_code_snippet(row)).collect())
+    }
+
+    fn row_to_code_snippet(&self, row: sqlx::sqlite::SqliteRow) -> CodeSnippet {
+        CodeSnippet {
+            id: row.get("id"),
+            document_id: row.get("document_id"),
+            document_title: row.get("document_title"),
+            snippet_index: row.get("snippet_index"),
+            language: row.get("language"),
+            content: row.get("content"),
+            start_offset: row.get("start_offset"),
+            end_offset: row.get("end_offset"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! zstd compression of large document content. Compressed rows keep an empty `content` and
+//! the compressed text in `content_zstd`; every read goes through `row_content`, so callers
+//! of the documents layer never see the difference. SQL that matches or searches document
+//! text reads the `document_text` table instead, which has the plain text of every document
+//! (see `write_document_text`), so nothing is decompressed to be searched.
+
+use sqlx::Row;
+use sqlx::sqlite::SqliteRow;
+use super::{Database, types::ContentCompressionResult};
+
+// Smaller content isn't worth it: SQLite reads it quickly and LIKE search still works on it
+pub const COMPRESSION_THRESHOLD_BYTES: usize = 64 * 1024;
+const ZSTD_LEVEL: i32 = 9;
+
+/// Content as it's written to the `content`, `content_zstd` and `content_length` columns
+pub struct StoredContent {
+    pub text: String,
+    pub compressed: Option<Vec<u8>>,
+    pub length: i64, // Characters of the uncompressed content
+}
+
+/// Compress `content` when it's above the threshold; compression failures store it as is
+pub fn store_content(content: &str) -> StoredContent {
+    let length = content.chars().count() as i64;
+    if content.len() >= COMPRESSION_THRESHOLD_BYTES {
+        match zstd::encode_all(content.as_bytes(), ZSTD_LEVEL) {
+            Ok(compressed) => return StoredContent { text: String::new(), compressed: Some(compressed), length },
+            Err(e) => eprintln!("⚠️ Failed to compress document content, storing it uncompressed: {}", e),
+        }
+    }
+    StoredContent { text: content.to_string(), compressed: None, length }
+}
+
+pub fn decompress_content(compressed: &[u8]) -> Result<String, String> {
+    let bytes = zstd::decode_all(compressed)
+        .map_err(|e| format!("Failed to decompress document content: {}", e))?;
+    String::from_utf8(bytes)
+        .map_err(|e| format!("Decompressed document content is not UTF-8: {}", e))
+}
+
+/// The content of a documents row, decompressed if needed. Rows selected without the
+/// content columns give an empty string.
+pub fn row_content(row: &SqliteRow) -> String {
+    match row.try_get::<Option<Vec<u8>>, _>("content_zstd").ok().flatten() {
+        Some(compressed) => decompress_content(&compressed).unwrap_or_else(|e| {
+            let id: String = row.try_get("id").unwrap_or_default();
+            eprintln!("⚠️ {} (document {})", e, id);
+            String::new()
+        }),
+        None => row.try_get("content").unwrap_or_default(),
+    }
+}
+
+/// Store the plain text of a document for search. Called wherever document content is
+/// written; deleting a document removes it through a trigger.
+pub(super) async fn write_document_text<'e, E>(executor: E, document_id: &str, content: &str) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        "INSERT INTO document_text (document_id, content) VALUES (?, ?) \
+         ON CONFLICT(document_id) DO UPDATE SET content = excluded.content"
+    )
+    .bind(document_id)
+    .bind(content)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+impl Database {
+    /// Fill `document_text` for documents written before it existed, decompressing as needed
+    pub(super) async fn backfill_document_text(&self) -> Result<usize, sqlx::Error> {
+        let ids: Vec<String> = sqlx::query_scalar("SELECT id FROM documents WHERE id NOT IN (SELECT document_id FROM document_text)")
+            .fetch_all(&self.pool)
+            .await?;
+
+        // One document at a time, so a library of textbooks isn't decompressed into memory at once
+        for id in &ids {
+            let row = sqlx::query("SELECT id, content, content_zstd FROM documents WHERE id = ?")
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await?;
+            write_document_text(&self.pool, id, &row_content(&row)).await?;
+        }
+        Ok(ids.len())
+    }
+
+    /// Compress the content of documents stored uncompressed above the threshold, e.g. ones
+    /// imported before compression, then VACUUM so the file actually shrinks
+    pub async fn compress_document_contents(&self) -> Result<ContentCompressionResult, sqlx::Error> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM documents WHERE content_zstd IS NULL AND length(CAST(content AS BLOB)) >= ?"
+        )
+        .bind(COMPRESSION_THRESHOLD_BYTES as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut result = ContentCompressionResult::default();
+        // One row at a time, so a library of textbooks never has to fit in memory at once
+        for id in ids {
+            let Some(content) = sqlx::query_scalar::<_, String>("SELECT content FROM documents WHERE id = ?")
+                .bind(&id)
+                .fetch_optional(&self.pool)
+                .await? else {
+                continue;
+            };
+
+            let stored = store_content(&content);
+            let Some(compressed) = &stored.compressed else {
+                continue;
+            };
+            sqlx::query("UPDATE documents SET content = ?, content_zstd = ?, content_length = ? WHERE id = ?")
+                .bind(&stored.text)
+                .bind(compressed)
+                .bind(stored.length)
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+
+            result.documents_compressed += 1;
+            result.bytes_before += content.len() as i64;
+            result.bytes_after += compressed.len() as i64;
+        }
+
+        if result.documents_compressed > 0 {
+            sqlx::query("VACUUM").execute(&self.pool).await?;
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,206 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+use super::{Database, types::{Concept, ConceptItemType, ConceptMastery, QuizAnswer}};
+
+// A result this many days old counts half as much as one from today
+const MASTERY_HALF_LIFE_DAYS: f64 = 30.0;
+// Weight of the 0.5 prior, so one lucky answer doesn't make a concept mastered
+const PRIOR_WEIGHT: f64 = 1.0;
+pub const WEAK_MASTERY_THRESHOLD: f64 = 0.6;
+
+impl Database {
+    // === CONCEPTS ===
+
+    /// Link an item to concepts by name, creating the concepts in `category_id` that don't
+    /// exist yet. Names are matched case-insensitively.
+    pub async fn link_concepts(
+        &self,
+        item_type: ConceptItemType,
+        item_id: &str,
+        category_id: Option<&str>,
+        names: &[String],
+    ) -> Result<Vec<Concept>, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        let mut concepts = Vec::new();
+
+        for name in names.iter().map(|name| name.trim()).filter(|name| !name.is_empty()) {
+            let normalized = normalize_concept_name(name);
+            sqlx::query(
+                "INSERT OR IGNORE INTO concepts (id, name, normalized_name, category_id, created_at) VALUES (?, ?, ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(name)
+            .bind(&normalized)
+            .bind(category_id)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+
+            let row = sqlx::query("SELECT * FROM concepts WHERE normalized_name = ? AND category_id IS ?")
+                .bind(&normalized)
+                .bind(category_id)
+                .fetch_one(&self.pool)
+                .await?;
+            let concept = row_to_concept(&row);
+
+            sqlx::query("INSERT OR IGNORE INTO concept_links (concept_id, item_type, item_id) VALUES (?, ?, ?)")
+                .bind(&concept.id)
+                .bind(item_type.as_str())
+                .bind(item_id)
+                .execute(&self.pool)
+                .await?;
+
+            if !concepts.iter().any(|existing: &Concept| existing.id == concept.id) {
+                concepts.push(concept);
+            }
+        }
+
+        Ok(concepts)
+    }
+
+    pub async fn get_item_concepts(&self, item_type: ConceptItemType, item_id: &str) -> Result<Vec<Concept>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.* FROM concepts c
+            JOIN concept_links l ON l.concept_id = c.id
+            WHERE l.item_type = ? AND l.item_id = ?
+            ORDER BY c.name
+            "#
+        )
+        .bind(item_type.as_str())
+        .bind(item_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_concept).collect())
+    }
+
+    pub async fn record_quiz_answer(&self, question_id: &str, correct: bool) -> Result<QuizAnswer, sqlx::Error> {
+        let answer = QuizAnswer {
+            id: Uuid::new_v4().to_string(),
+            question_id: question_id.to_string(),
+            correct,
+            answered_at: Utc::now(),
+        };
+
+        sqlx::query("INSERT INTO quiz_answers (id, question_id, correct, answered_at) VALUES (?, ?, ?, ?)")
+            .bind(&answer.id)
+            .bind(&answer.question_id)
+            .bind(answer.correct)
+            .bind(answer.answered_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(answer)
+    }
+
+    /// Mastery of every concept (in one category, or all), least mastered first. A card review
+    /// scores its quality out of 5, a quiz answer 1 or 0; scores decay with age and are pulled
+    /// towards 0.5 while there are few of them.
+    pub async fn get_concept_mastery(&self, category_id: Option<&str>) -> Result<Vec<ConceptMastery>, sqlx::Error> {
+        let category_filter = if category_id.is_some() { "WHERE c.category_id = ?" } else { "" };
+
+        let concept_sql = format!(
+            r#"
+            SELECT c.*,
+                COALESCE(SUM(CASE WHEN l.item_type = 'flashcard' THEN 1 ELSE 0 END), 0) AS flashcard_count,
+                COALESCE(SUM(CASE WHEN l.item_type = 'quiz_question' THEN 1 ELSE 0 END), 0) AS quiz_question_count
+            FROM concepts c
+            LEFT JOIN concept_links l ON l.concept_id = c.id
+            {}
+            GROUP BY c.id
+            "#,
+            category_filter
+        );
+        // (concept id, score 0-1, when); reviews of cards, then answers to quiz questions
+        let evidence_sql = format!(
+            r#"
+            SELECT l.concept_id, MIN(MAX(r.quality, 0), 5) / 5.0 AS score, r.timestamp AS at
+            FROM concept_links l
+            JOIN concepts c ON c.id = l.concept_id
+            JOIN flashcard_reviews r ON l.item_type = 'flashcard' AND r.flashcard_id = l.item_id
+            {filter}
+            UNION ALL
+            SELECT l.concept_id, CASE WHEN a.correct THEN 1.0 ELSE 0.0 END AS score, a.answered_at AS at
+            FROM concept_links l
+            JOIN concepts c ON c.id = l.concept_id
+            JOIN quiz_answers a ON l.item_type = 'quiz_question' AND a.question_id = l.item_id
+            {filter}
+            "#,
+            filter = category_filter
+        );
+
+        let mut concept_query = sqlx::query(&concept_sql);
+        let mut evidence_query = sqlx::query(&evidence_sql);
+        if let Some(category_id) = category_id {
+            concept_query = concept_query.bind(category_id);
+            evidence_query = evidence_query.bind(category_id).bind(category_id);
+        }
+        let concept_rows = concept_query.fetch_all(&self.pool).await?;
+        let evidence_rows = evidence_query.fetch_all(&self.pool).await?;
+
+        // concept id -> (weighted score, weight, count, latest)
+        let now = Utc::now();
+        let mut evidence: HashMap<String, (f64, f64, i64, Option<DateTime<Utc>>)> = HashMap::new();
+        for row in &evidence_rows {
+            let Some(at) = parse_timestamp(row.get("at")) else {
+                continue;
+            };
+            let age_days = (now - at).num_seconds().max(0) as f64 / 86_400.0;
+            let weight = 0.5f64.powf(age_days / MASTERY_HALF_LIFE_DAYS);
+            let score: f64 = row.get("score");
+
+            let entry = evidence.entry(row.get("concept_id")).or_insert((0.0, 0.0, 0, None));
+            entry.0 += weight * score;
+            entry.1 += weight;
+            entry.2 += 1;
+            entry.3 = Some(entry.3.map_or(at, |latest: DateTime<Utc>| latest.max(at)));
+        }
+
+        let mut mastery: Vec<ConceptMastery> = concept_rows.iter()
+            .map(|row| {
+                let concept = row_to_concept(row);
+                let (weighted, weight, count, latest) = evidence.remove(&concept.id).unwrap_or((0.0, 0.0, 0, None));
+                ConceptMastery {
+                    mastery: (weighted + PRIOR_WEIGHT * 0.5) / (weight + PRIOR_WEIGHT),
+                    evidence_count: count,
+                    flashcard_count: row.get("flashcard_count"),
+                    quiz_question_count: row.get("quiz_question_count"),
+                    last_practiced_at: latest,
+                    concept,
+                }
+            })
+            .collect();
+        mastery.sort_by(|a, b| a.mastery.total_cmp(&b.mastery).then_with(|| b.evidence_count.cmp(&a.evidence_count)));
+
+        Ok(mastery)
+    }
+
+    /// Practiced concepts with mastery below WEAK_MASTERY_THRESHOLD, weakest first
+    pub async fn get_weak_concepts(&self, category_id: Option<&str>, limit: usize) -> Result<Vec<ConceptMastery>, sqlx::Error> {
+        Ok(self.get_concept_mastery(category_id).await?
+            .into_iter()
+            .filter(|concept| concept.evidence_count > 0 && concept.mastery < WEAK_MASTERY_THRESHOLD)
+            .take(limit)
+            .collect())
+    }
+}
+
+fn normalize_concept_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn parse_timestamp(value: String) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&value).ok().map(|dt| dt.with_timezone(&Utc))
+}
+
+fn row_to_concept(row: &sqlx::sqlite::SqliteRow) -> Concept {
+    Concept {
+        id: row.get("id"),
+        name: row.get("name"),
+        category_id: row.get("category_id"),
+        created_at: parse_timestamp(row.get("created_at")).unwrap_or_else(Utc::now),
+    }
+}
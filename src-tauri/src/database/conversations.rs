@@ -0,0 +1,325 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{
+    Conversation, CreateConversationRequest, ConversationMessage, CreateConversationMessageRequest, MessageFootnote, MessageSource,
+}};
+
+impl Database {
+    // === CONVERSATIONS ===
+
+    pub async fn create_conversation(&self, req: CreateConversationRequest) -> Result<Conversation, sqlx::Error> {
+        let id = req.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        let now = Utc::now();
+        let conversation_type = req.conversation_type.clone().unwrap_or_else(|| "general".to_string());
+        let metadata_json = req.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+
+        sqlx::query(
+            r#"
+            INSERT INTO conversations (id, title, model, provider_id, session_id, conversation_type, created_at, updated_at, metadata)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&req.title)
+        .bind(&req.model)
+        .bind(&req.provider_id)
+        .bind(&req.session_id)
+        .bind(&conversation_type)
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .bind(metadata_json)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(Conversation {
+            id,
+            title: req.title,
+            model: req.model,
+            provider_id: req.provider_id,
+            session_id: req.session_id,
+            conversation_type,
+            created_at: now,
+            updated_at: now,
+            message_count: 0,
+            summary: None,
+            summarized_message_count: 0,
+            metadata: req.metadata,
+        })
+    }
+
+    pub async fn get_conversation(&self, conversation_id: &str) -> Result<Option<Conversation>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT c.*, (SELECT COUNT(*) FROM conversation_messages m WHERE m.conversation_id = c.id) AS message_count
+            FROM conversations c
+            WHERE c.id = ?
+            "#,
+        )
+        .bind(conversation_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_conversation(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_conversations(&self, limit: Option<i64>, offset: Option<i64>) -> Result<Vec<Conversation>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT c.*, (SELECT COUNT(*) FROM conversation_messages m WHERE m.conversation_id = c.id) AS message_count
+            FROM conversations c
+            ORDER BY c.updated_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(limit.unwrap_or(50))
+        .bind(offset.unwrap_or(0))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut conversations = Vec::new();
+        for row in rows {
+            conversations.push(self.row_to_conversation(row)?);
+        }
+
+        Ok(conversations)
+    }
+
+    pub async fn delete_conversation(&self, conversation_id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM message_sources WHERE message_id IN (SELECT id FROM conversation_messages WHERE conversation_id = ?)")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM conversation_messages WHERE conversation_id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM conversations WHERE id = ?")
+            .bind(conversation_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Store the rolling summary and how many leading messages it covers
+    pub async fn update_conversation_summary(&self, conversation_id: &str, summary: &str, summarized_message_count: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE conversations SET summary = ?, summarized_message_count = ? WHERE id = ?")
+            .bind(summary)
+            .bind(summarized_message_count)
+            .bind(conversation_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // === MESSAGES ===
+
+    // Add a message along with the documents, chunks, and highlights it was grounded on
+    pub async fn add_conversation_message(&self, req: CreateConversationMessageRequest) -> Result<ConversationMessage, sqlx::Error> {
+        let id = req.id.clone().unwrap_or_else(|| Uuid::new_v4().to_string());
+        let now = Utc::now();
+        let metadata_json = req.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO conversation_messages (id, conversation_id, role, content, model, provider_id, created_at, metadata)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(&req.conversation_id)
+        .bind(&req.role)
+        .bind(&req.content)
+        .bind(&req.model)
+        .bind(&req.provider_id)
+        .bind(now.to_rfc3339())
+        .bind(metadata_json)
+        .execute(&mut *tx)
+        .await?;
+
+        for source in &req.sources {
+            let source_metadata = source.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+            sqlx::query(
+                r#"
+                INSERT INTO message_sources (id, message_id, source_type, document_id, chunk_id, content, start_offset, end_offset, score, metadata)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&id)
+            .bind(&source.source_type)
+            .bind(&source.document_id)
+            .bind(&source.chunk_id)
+            .bind(&source.content)
+            .bind(source.start_offset)
+            .bind(source.end_offset)
+            .bind(source.score)
+            .bind(source_metadata)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("UPDATE conversations SET updated_at = ? WHERE id = ?")
+            .bind(now.to_rfc3339())
+            .bind(&req.conversation_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(ConversationMessage {
+            id,
+            conversation_id: req.conversation_id,
+            role: req.role,
+            content: req.content,
+            model: req.model,
+            provider_id: req.provider_id,
+            created_at: now,
+            source_count: req.sources.len() as i64,
+            metadata: req.metadata,
+        })
+    }
+
+    pub async fn get_conversation_messages(&self, conversation_id: &str) -> Result<Vec<ConversationMessage>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.*, (SELECT COUNT(*) FROM message_sources s WHERE s.message_id = m.id) AS source_count
+            FROM conversation_messages m
+            WHERE m.conversation_id = ?
+            ORDER BY m.created_at ASC
+            "#,
+        )
+        .bind(conversation_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut messages = Vec::new();
+        for row in rows {
+            messages.push(self.row_to_conversation_message(row)?);
+        }
+
+        Ok(messages)
+    }
+
+    // Get exactly what context the AI was shown for a message
+    pub async fn get_message_sources(&self, message_id: &str) -> Result<Vec<MessageSource>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.*, d.title AS document_title
+            FROM message_sources s
+            LEFT JOIN documents d ON d.id = s.document_id
+            WHERE s.message_id = ?
+            ORDER BY s.score DESC, s.rowid ASC
+            "#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| {
+            let metadata: Option<String> = row.get("metadata");
+            MessageSource {
+                id: row.get("id"),
+                message_id: row.get("message_id"),
+                source_type: row.get("source_type"),
+                document_id: row.get("document_id"),
+                document_title: row.get("document_title"),
+                chunk_id: row.get("chunk_id"),
+                content: row.get("content"),
+                start_offset: row.get("start_offset"),
+                end_offset: row.get("end_offset"),
+                score: row.get("score"),
+                metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+            }
+        }).collect())
+    }
+
+    /// Footnotes of an answer in order, for jumping from a citation to the passage it cites
+    pub async fn get_message_footnotes(&self, message_id: &str) -> Result<Vec<MessageFootnote>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT s.*, d.title AS document_title,
+                json_extract(s.metadata, '$.footnote') AS footnote,
+                json_extract(s.metadata, '$.page') AS page
+            FROM message_sources s
+            LEFT JOIN documents d ON d.id = s.document_id
+            WHERE s.message_id = ? AND json_valid(s.metadata) = 1 AND json_extract(s.metadata, '$.footnote') IS NOT NULL
+            ORDER BY footnote
+            "#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| MessageFootnote {
+            number: row.get("footnote"),
+            source_id: row.get("id"),
+            document_id: row.get("document_id"),
+            document_title: row.get("document_title"),
+            chunk_id: row.get("chunk_id"),
+            page: row.get("page"),
+            start_offset: row.get("start_offset"),
+            end_offset: row.get("end_offset"),
+            excerpt: row.get("content"),
+        }).collect())
+    }
+
+    // Helper function to convert database row to Conversation
+    fn row_to_conversation(&self, row: sqlx::sqlite::SqliteRow) -> Result<Conversation, sqlx::Error> {
+        let created_at: String = row.get("created_at");
+        let updated_at: String = row.get("updated_at");
+        let metadata: Option<String> = row.get("metadata");
+
+        Ok(Conversation {
+            id: row.get("id"),
+            title: row.get("title"),
+            model: row.get("model"),
+            provider_id: row.get("provider_id"),
+            session_id: row.get("session_id"),
+            conversation_type: row.get("conversation_type"),
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            message_count: row.try_get("message_count").unwrap_or(0),
+            summary: row.get("summary"),
+            summarized_message_count: row.get("summarized_message_count"),
+            metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        })
+    }
+
+    // Helper function to convert database row to ConversationMessage
+    fn row_to_conversation_message(&self, row: sqlx::sqlite::SqliteRow) -> Result<ConversationMessage, sqlx::Error> {
+        let created_at: String = row.get("created_at");
+        let metadata: Option<String> = row.get("metadata");
+
+        Ok(ConversationMessage {
+            id: row.get("id"),
+            conversation_id: row.get("conversation_id"),
+            role: row.get("role"),
+            content: row.get("content"),
+            model: row.get("model"),
+            provider_id: row.get("provider_id"),
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            source_count: row.try_get("source_count").unwrap_or(0),
+            metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        })
+    }
+}
@@ -1,15 +1,39 @@
-use sqlx::{sqlite::SqlitePool, Row};
+use sqlx::{sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions}, ConnectOptions, Row};
+use std::str::FromStr;
 use chrono::{DateTime, Utc};
 use base64::{engine::general_purpose, Engine as _};
+use super::cache::LibraryCache;
 
 pub struct Database {
     pub pool: SqlitePool,
+    pub(crate) cache: LibraryCache,
 }
 
 impl Database {
     pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
-        let pool = SqlitePool::connect(database_url).await?;
-        
+        // Statements over the threshold are reported through `log`; see metrics.rs
+        let options = SqliteConnectOptions::from_str(database_url)?
+            .log_slow_statements(log::LevelFilter::Warn, crate::metrics::SLOW_QUERY_THRESHOLD);
+        let pool = SqlitePool::connect_with(options).await?;
+        Self::from_pool(pool).await
+    }
+
+    /// A private in-memory database with the full schema, for tests (see test_support.rs)
+    pub async fn new_in_memory() -> Result<Self, sqlx::Error> {
+        // Every connection to :memory: opens a database of its own, so the pool keeps exactly
+        // one connection open for the lifetime of the pool
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await?;
+        Self::from_pool(pool).await
+    }
+
+    // Create and migrate the schema on a freshly opened pool
+    async fn from_pool(pool: SqlitePool) -> Result<Self, sqlx::Error> {
         // Check if content_hash column exists, add if missing (for existing databases)
         let add_content_hash_result = sqlx::query(
             "ALTER TABLE documents ADD COLUMN content_hash TEXT"
@@ -62,6 +86,15 @@ impl Database {
                 updated_at TEXT NOT NULL,
                 status TEXT NOT NULL DEFAULT 'draft',
                 category_id TEXT,
+                last_opened_at TEXT,
+                pinned_at TEXT,
+                parent_document_id TEXT, -- Set on sections split out of a larger document
+                metadata TEXT, -- JSON object (extraction details, etc.)
+                source_url TEXT, -- Normalized URL the document was imported from
+                language TEXT, -- ISO 639-3 code detected from the content
+                math_terms TEXT, -- Normalized math in the content, for math-aware search
+                content_zstd BLOB, -- zstd-compressed content of large documents; content is then empty
+                content_length INTEGER, -- Characters in the uncompressed content
                 FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
             )
             "#,
@@ -97,7 +130,10 @@ impl Database {
                 documents_accessed TEXT NOT NULL DEFAULT '[]', -- JSON array
                 categories_accessed TEXT NOT NULL DEFAULT '[]', -- JSON array
                 conversation_ids TEXT NOT NULL DEFAULT '[]', -- JSON array
-                metadata TEXT -- JSON metadata
+                metadata TEXT, -- JSON metadata
+                focus_score REAL, -- 0-100, computed from the action stream
+                template_id TEXT,
+                target_duration INTEGER -- Seconds
             )
             "#,
         )
@@ -158,6 +194,7 @@ impl Database {
                 is_shared BOOLEAN NOT NULL DEFAULT FALSE,
                 tags TEXT NOT NULL DEFAULT '[]', -- JSON array
                 metadata TEXT, -- JSON metadata
+                new_card_order TEXT NOT NULL DEFAULT 'created', -- 'created', 'random', 'document', 'difficulty'
                 FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
             )
             "#,
@@ -272,6 +309,7 @@ impl Database {
                 started_at TEXT,
                 completed_at TEXT,
                 metadata TEXT, -- JSON metadata
+                source_url TEXT, -- Normalized URL for URL imports
                 FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL,
                 FOREIGN KEY (result_document_id) REFERENCES documents (id) ON DELETE SET NULL
             )
@@ -293,6 +331,555 @@ impl Database {
             .execute(&pool)
             .await?;
 
+        // Practice question generation: per-category settings, generated quizzes and their questions
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS category_practice_settings (
+                category_id TEXT PRIMARY KEY,
+                enabled BOOLEAN NOT NULL DEFAULT FALSE,
+                question_count INTEGER NOT NULL DEFAULT 5,
+                flashcard_count INTEGER NOT NULL DEFAULT 3,
+                provider_id TEXT,
+                provider_type TEXT, -- 'openai', 'custom', 'anthropic', 'ollama'
+                base_url TEXT,
+                model TEXT,
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS quizzes (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                document_id TEXT,
+                category_id TEXT,
+                created_at TEXT NOT NULL,
+                metadata TEXT, -- JSON metadata
+                FOREIGN KEY (document_id) REFERENCES documents (id) ON DELETE CASCADE,
+                FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS quiz_questions (
+                id TEXT PRIMARY KEY,
+                quiz_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                question TEXT NOT NULL,
+                answer TEXT NOT NULL,
+                explanation TEXT,
+                FOREIGN KEY (quiz_id) REFERENCES quizzes (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Answers to quiz questions, for concept mastery
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS quiz_answers (
+                id TEXT PRIMARY KEY,
+                question_id TEXT NOT NULL,
+                correct BOOLEAN NOT NULL,
+                answered_at TEXT NOT NULL,
+                FOREIGN KEY (question_id) REFERENCES quiz_questions (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Concepts extracted from practice material, and the cards and questions testing them
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS concepts (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                normalized_name TEXT NOT NULL,
+                category_id TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_concepts_name ON concepts(normalized_name, COALESCE(category_id, ''))")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS concept_links (
+                concept_id TEXT NOT NULL,
+                item_type TEXT NOT NULL, -- 'flashcard' or 'quiz_question'
+                item_id TEXT NOT NULL,
+                PRIMARY KEY (concept_id, item_type, item_id),
+                FOREIGN KEY (concept_id) REFERENCES concepts (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Question bank: past exam papers split into questions
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS exam_papers (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                year INTEGER,
+                category_id TEXT,
+                file_path TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (category_id) REFERENCES categories (id) ON DELETE SET NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS exam_questions (
+                id TEXT PRIMARY KEY,
+                paper_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                number TEXT NOT NULL,
+                text TEXT NOT NULL,
+                marks INTEGER,
+                topic TEXT,
+                FOREIGN KEY (paper_id) REFERENCES exam_papers (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exam_questions_paper ON exam_questions(paper_id, position)")
+            .execute(&pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_exam_questions_topic ON exam_questions(topic COLLATE NOCASE)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_concept_links_item ON concept_links(item_type, item_id)")
+            .execute(&pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_quiz_answers_question ON quiz_answers(question_id)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_quizzes_document_id ON quizzes(document_id)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_quiz_questions_quiz_id ON quiz_questions(quiz_id)")
+            .execute(&pool)
+            .await?;
+
+        // AI conversations, their messages, and the context each message was grounded on
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversations (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                model TEXT,
+                provider_id TEXT,
+                session_id TEXT,
+                conversation_type TEXT NOT NULL DEFAULT 'general',
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                metadata TEXT, -- JSON metadata
+                summary TEXT, -- Rolling summary of older messages
+                summarized_message_count INTEGER NOT NULL DEFAULT 0 -- Messages folded into the summary
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS conversation_messages (
+                id TEXT PRIMARY KEY,
+                conversation_id TEXT NOT NULL,
+                role TEXT NOT NULL, -- 'user', 'assistant', 'system'
+                content TEXT NOT NULL,
+                model TEXT,
+                provider_id TEXT,
+                created_at TEXT NOT NULL,
+                metadata TEXT, -- JSON metadata
+                FOREIGN KEY (conversation_id) REFERENCES conversations (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS message_sources (
+                id TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL,
+                source_type TEXT NOT NULL, -- 'document', 'chunk', 'highlight'
+                document_id TEXT,
+                chunk_id TEXT,
+                content TEXT, -- Snapshot of the text the model was shown
+                start_offset INTEGER,
+                end_offset INTEGER,
+                score REAL,
+                metadata TEXT, -- JSON metadata
+                FOREIGN KEY (message_id) REFERENCES conversation_messages (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_conversation_messages_conversation_id ON conversation_messages(conversation_id)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_message_sources_message_id ON message_sources(message_id)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_message_sources_document_id ON message_sources(document_id)")
+            .execute(&pool)
+            .await?;
+
+        // AI tutor persona profiles
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS ai_profiles (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL UNIQUE,
+                description TEXT,
+                system_prompt TEXT NOT NULL,
+                default_model TEXT,
+                temperature REAL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                metadata TEXT -- JSON metadata
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Sections a document was split into; offsets are character positions in the parent
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS document_sections (
+                document_id TEXT PRIMARY KEY,
+                parent_document_id TEXT NOT NULL,
+                section_index INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                start_offset INTEGER NOT NULL,
+                end_offset INTEGER NOT NULL,
+                page_start INTEGER,
+                page_end INTEGER,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents (id) ON DELETE CASCADE,
+                FOREIGN KEY (parent_document_id) REFERENCES documents (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_document_sections_parent ON document_sections(parent_document_id)")
+            .execute(&pool)
+            .await?;
+
+        // Key/value application settings (JSON values)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL, -- JSON value
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Model capabilities cached from the models.dev catalog
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS model_capabilities (
+                provider_id TEXT NOT NULL,
+                model_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                context_window INTEGER,
+                max_output_tokens INTEGER,
+                supports_tools BOOLEAN NOT NULL DEFAULT 0,
+                supports_reasoning BOOLEAN NOT NULL DEFAULT 0,
+                supports_attachments BOOLEAN NOT NULL DEFAULT 0,
+                input_modalities TEXT NOT NULL DEFAULT '[]', -- JSON array
+                cost_input REAL,
+                cost_output REAL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (provider_id, model_id)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_model_capabilities_model ON model_capabilities(model_id)")
+            .execute(&pool)
+            .await?;
+
+        // History of library maintenance runs
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS maintenance_runs (
+                id TEXT PRIMARY KEY,
+                trigger TEXT NOT NULL, -- 'scheduled', 'manual'
+                status TEXT NOT NULL, -- 'running', 'completed', 'failed'
+                started_at TEXT NOT NULL,
+                completed_at TEXT,
+                tasks TEXT NOT NULL DEFAULT '[]', -- JSON array of task results
+                error_message TEXT
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // In-progress flashcard review sessions, so one can be resumed after the app closes
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS review_session_states (
+                id TEXT PRIMARY KEY,
+                study_session_id TEXT,
+                mix_strategy TEXT NOT NULL,
+                card_ids TEXT NOT NULL DEFAULT '[]', -- JSON array, in the order cards were drawn
+                results TEXT NOT NULL DEFAULT '[]', -- JSON array of per-card results
+                status TEXT NOT NULL DEFAULT 'active', -- 'active', 'completed', 'abandoned', 'expired'
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                expires_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_review_session_states_status ON review_session_states(status)")
+            .execute(&pool)
+            .await?;
+
+        // Computed per-day activity summaries; see activity.rs for when an entry is reused
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS daily_activity_cache (
+                date TEXT PRIMARY KEY, -- YYYY-MM-DD, local time
+                payload TEXT NOT NULL, -- JSON DailyActivity
+                computed_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Presets for starting study sessions
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                session_type TEXT NOT NULL DEFAULT 'mixed',
+                target_duration_minutes INTEGER NOT NULL,
+                document_ids TEXT NOT NULL DEFAULT '[]', -- JSON array
+                deck_ids TEXT NOT NULL DEFAULT '[]', -- JSON array
+                is_builtin BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Formats for new notes, with {{variable}} placeholders
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS note_templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                title_template TEXT NOT NULL,
+                body TEXT NOT NULL,
+                is_builtin BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Grading rubrics; criteria are a JSON array of RubricCriterion
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS rubrics (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                criteria TEXT NOT NULL,
+                is_builtin BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Rubric feedback on essays, one row per graded version
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS essay_feedback (
+                id TEXT PRIMARY KEY,
+                document_id TEXT,
+                rubric_id TEXT,
+                rubric_name TEXT NOT NULL,
+                essay_text TEXT NOT NULL,
+                word_count INTEGER NOT NULL,
+                total_score REAL NOT NULL,
+                max_score REAL NOT NULL,
+                criteria TEXT NOT NULL,
+                summary TEXT NOT NULL,
+                strengths TEXT NOT NULL,
+                improvements TEXT NOT NULL,
+                model TEXT,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents (id) ON DELETE CASCADE,
+                FOREIGN KEY (rubric_id) REFERENCES rubrics (id) ON DELETE SET NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_essay_feedback_document ON essay_feedback(document_id, created_at)")
+            .execute(&pool)
+            .await?;
+
+        // User scripts run on app events
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS automation_scripts (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                event TEXT NOT NULL, -- 'document_imported', 'review_completed'
+                source TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                last_run_at TEXT,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Translated chunks, keyed by a hash of the source text, so re-translating skips work
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS translation_cache (
+                text_hash TEXT NOT NULL, -- SHA-256 of the source chunk
+                target_language TEXT NOT NULL,
+                model TEXT NOT NULL,
+                translation TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (text_hash, target_language, model)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Documents saved as translations of other documents
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS document_translations (
+                source_document_id TEXT NOT NULL,
+                target_language TEXT NOT NULL,
+                translated_document_id TEXT NOT NULL,
+                source_content_hash TEXT NOT NULL, -- Content the translation was made from
+                model TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (source_document_id, target_language),
+                FOREIGN KEY (source_document_id) REFERENCES documents (id) ON DELETE CASCADE,
+                FOREIGN KEY (translated_document_id) REFERENCES documents (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        // Fenced code blocks found in documents, for searching listings by language
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS code_snippets (
+                id TEXT PRIMARY KEY,
+                document_id TEXT NOT NULL,
+                snippet_index INTEGER NOT NULL,
+                language TEXT, -- From the fence, or guessed from the code
+                content TEXT NOT NULL,
+                start_offset INTEGER NOT NULL, -- Char offsets of the block in the document
+                end_offset INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_code_snippets_document_id ON code_snippets(document_id)")
+            .execute(&pool)
+            .await?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_code_snippets_language ON code_snippets(language)")
+            .execute(&pool)
+            .await?;
+
+        // Table of contents per document, rebuilt when the content changes
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS document_outlines (
+                document_id TEXT PRIMARY KEY,
+                source TEXT NOT NULL, -- 'headings' or 'bookmarks'
+                content_hash TEXT NOT NULL,
+                entries TEXT NOT NULL, -- JSON array of outline entries
+                updated_at TEXT NOT NULL,
+                FOREIGN KEY (document_id) REFERENCES documents (id) ON DELETE CASCADE
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+
         // Migration: Add category_id column to documents table if it doesn't exist
         let columns = sqlx::query("PRAGMA table_info(documents)")
             .fetch_all(&pool)
@@ -310,6 +897,147 @@ impl Database {
                 .await?;
         }
 
+        // Migration: Add recent/pinned columns to documents table if they don't exist
+        let has_last_opened_at = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "last_opened_at"
+        });
+        if !has_last_opened_at {
+            println!("Migrating database: Adding last_opened_at and pinned_at columns to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN last_opened_at TEXT")
+                .execute(&pool)
+                .await?;
+            sqlx::query("ALTER TABLE documents ADD COLUMN pinned_at TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add parent_document_id column to documents table if it doesn't exist
+        let has_parent_document_id = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "parent_document_id"
+        });
+        if !has_parent_document_id {
+            println!("Migrating database: Adding parent_document_id column to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN parent_document_id TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add metadata column to documents table if it doesn't exist
+        let has_metadata = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "metadata"
+        });
+        if !has_metadata {
+            println!("Migrating database: Adding metadata column to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN metadata TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add source_url column to documents table if it doesn't exist
+        let has_source_url = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "source_url"
+        });
+        if !has_source_url {
+            println!("Migrating database: Adding source_url column to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN source_url TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add language column to documents table if it doesn't exist
+        let has_language = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "language"
+        });
+        if !has_language {
+            println!("Migrating database: Adding language column to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN language TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add math_terms column to documents table if it doesn't exist
+        let has_math_terms = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "math_terms"
+        });
+        if !has_math_terms {
+            println!("Migrating database: Adding math_terms column to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN math_terms TEXT")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add content_zstd and content_length columns to documents table if they don't exist
+        let has_content_zstd = columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "content_zstd"
+        });
+        if !has_content_zstd {
+            println!("Migrating database: Adding content_zstd and content_length columns to documents table");
+            sqlx::query("ALTER TABLE documents ADD COLUMN content_zstd BLOB")
+                .execute(&pool)
+                .await?;
+            sqlx::query("ALTER TABLE documents ADD COLUMN content_length INTEGER")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add source_url column to processing_jobs table if it doesn't exist
+        let job_columns = sqlx::query("PRAGMA table_info(processing_jobs)")
+            .fetch_all(&pool)
+            .await?;
+        let job_has_source_url = job_columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "source_url"
+        });
+        if !job_has_source_url {
+            println!("Migrating database: Adding source_url column to processing_jobs table");
+            sqlx::query("ALTER TABLE processing_jobs ADD COLUMN source_url TEXT")
+                .execute(&pool)
+                .await?;
+            // URL jobs already kept their URL in source_path; store it in the normalized form new
+            // imports are looked up by, so re-importing an old link still finds it
+            let url_jobs = sqlx::query("SELECT id, source_path FROM processing_jobs WHERE source_type = 'url' AND source_path IS NOT NULL")
+                .fetch_all(&pool)
+                .await?;
+            for row in url_jobs {
+                let id: String = row.get("id");
+                let source_path: String = row.get("source_path");
+                sqlx::query("UPDATE processing_jobs SET source_url = ? WHERE id = ?")
+                    .bind(crate::importers::url::normalize_source_url(&source_path))
+                    .bind(&id)
+                    .execute(&pool)
+                    .await?;
+            }
+            // Documents those jobs created get the same URL
+            sqlx::query(
+                r#"
+                UPDATE documents SET source_url = (
+                    SELECT j.source_url FROM processing_jobs j
+                    WHERE j.result_document_id = documents.id AND j.source_url IS NOT NULL
+                    ORDER BY j.created_at ASC LIMIT 1
+                )
+                WHERE source_url IS NULL
+                  AND id IN (SELECT result_document_id FROM processing_jobs WHERE source_url IS NOT NULL)
+                "#,
+            )
+            .execute(&pool)
+            .await?;
+        }
+
+        // Indexes on migrated columns can only be created once the columns exist
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_documents_source_url ON documents(source_url)")
+            .execute(&pool)
+            .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_processing_jobs_source_url ON processing_jobs(source_url)")
+            .execute(&pool)
+            .await?;
+
         // Migration: Add parent_id column to categories table if it doesn't exist
         let cat_columns = sqlx::query("PRAGMA table_info(categories)")
             .fetch_all(&pool)
@@ -324,8 +1052,131 @@ impl Database {
                 .execute(&pool)
                 .await?;
         }
+        let has_archived = cat_columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "archived"
+        });
+        if !has_archived {
+            println!("Migrating database: Adding archived column to categories table");
+            sqlx::query("ALTER TABLE categories ADD COLUMN archived INTEGER NOT NULL DEFAULT 0")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add focus_score column to study_sessions table if it doesn't exist
+        let session_columns = sqlx::query("PRAGMA table_info(study_sessions)")
+            .fetch_all(&pool)
+            .await?;
+        let has_focus_score = session_columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "focus_score"
+        });
+        if !has_focus_score {
+            println!("Migrating database: Adding focus_score column to study_sessions table");
+            sqlx::query("ALTER TABLE study_sessions ADD COLUMN focus_score REAL")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add template columns to study_sessions table if they don't exist
+        let has_template_id = session_columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "template_id"
+        });
+        if !has_template_id {
+            println!("Migrating database: Adding template_id and target_duration columns to study_sessions table");
+            sqlx::query("ALTER TABLE study_sessions ADD COLUMN template_id TEXT")
+                .execute(&pool)
+                .await?;
+            sqlx::query("ALTER TABLE study_sessions ADD COLUMN target_duration INTEGER")
+                .execute(&pool)
+                .await?;
+
+            // Free-form session types from before templates
+            sqlx::query("UPDATE study_sessions SET session_type = 'reading' WHERE session_type = 'focused'")
+                .execute(&pool)
+                .await?;
+            sqlx::query("UPDATE study_sessions SET session_type = 'mixed' WHERE session_type NOT IN ('reading', 'review', 'ai_tutoring', 'mixed')")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add new_card_order column to flashcard_decks table if it doesn't exist
+        let deck_columns = sqlx::query("PRAGMA table_info(flashcard_decks)")
+            .fetch_all(&pool)
+            .await?;
+        let has_new_card_order = deck_columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "new_card_order"
+        });
+        if !has_new_card_order {
+            println!("Migrating database: Adding new_card_order column to flashcard_decks table");
+            sqlx::query("ALTER TABLE flashcard_decks ADD COLUMN new_card_order TEXT NOT NULL DEFAULT 'created'")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Migration: Add rolling summary columns to conversations table if they don't exist
+        let conversation_columns = sqlx::query("PRAGMA table_info(conversations)")
+            .fetch_all(&pool)
+            .await?;
+        let has_summary = conversation_columns.iter().any(|row| {
+            let column_name: String = row.get("name");
+            column_name == "summary"
+        });
+        if !has_summary {
+            println!("Migrating database: Adding summary columns to conversations table");
+            sqlx::query("ALTER TABLE conversations ADD COLUMN summary TEXT")
+                .execute(&pool)
+                .await?;
+            sqlx::query("ALTER TABLE conversations ADD COLUMN summarized_message_count INTEGER NOT NULL DEFAULT 0")
+                .execute(&pool)
+                .await?;
+        }
+
+        // Plain text of every document for search, since compressed content can't be matched
+        // in SQL; document_text_fts indexes it by trigram so substring search doesn't scan
+        let has_document_text: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'document_text'")
+            .fetch_one(&pool)
+            .await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS document_text (
+                id INTEGER PRIMARY KEY, -- Stable rowid for the FTS index, which VACUUM can't renumber
+                document_id TEXT NOT NULL UNIQUE,
+                content TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query("CREATE VIRTUAL TABLE IF NOT EXISTS document_text_fts USING fts5(content, content='document_text', content_rowid='id', tokenize='trigram')")
+            .execute(&pool)
+            .await?;
+        for trigger in [
+            "CREATE TRIGGER IF NOT EXISTS document_text_ai AFTER INSERT ON document_text BEGIN \
+                INSERT INTO document_text_fts (rowid, content) VALUES (new.id, new.content); END",
+            "CREATE TRIGGER IF NOT EXISTS document_text_ad AFTER DELETE ON document_text BEGIN \
+                INSERT INTO document_text_fts (document_text_fts, rowid, content) VALUES ('delete', old.id, old.content); END",
+            "CREATE TRIGGER IF NOT EXISTS document_text_au AFTER UPDATE ON document_text BEGIN \
+                INSERT INTO document_text_fts (document_text_fts, rowid, content) VALUES ('delete', old.id, old.content); \
+                INSERT INTO document_text_fts (rowid, content) VALUES (new.id, new.content); END",
+            "CREATE TRIGGER IF NOT EXISTS documents_text_ad AFTER DELETE ON documents BEGIN \
+                DELETE FROM document_text WHERE document_id = old.id; END",
+        ] {
+            sqlx::query(trigger).execute(&pool).await?;
+        }
+
+        let database = Database { pool, cache: LibraryCache::new() };
+        if !has_document_text {
+            println!("Migrating database: Indexing document text for search");
+            database.backfill_document_text().await?;
+        }
+        database.seed_builtin_session_templates().await?;
+        database.seed_builtin_note_templates().await?;
+        database.seed_builtin_rubrics().await?;
 
-        Ok(Database { pool })
+        Ok(database)
     }
 
     /// Simple XOR encryption for API keys (not production-grade, but better than plaintext)
@@ -373,12 +1224,15 @@ impl Database {
             end_time: end_time.and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
                 .map(|t| t.with_timezone(&Utc)),
             is_active: row.get("is_active"),
-            session_type: row.get("session_type"),
+            session_type: super::types::SessionType::parse(&row.get::<String, _>("session_type")),
             total_duration: row.get("total_duration"),
+            template_id: row.get("template_id"),
+            target_duration: row.get("target_duration"),
             documents_accessed: serde_json::from_str(&documents_accessed).unwrap_or_default(),
             categories_accessed: serde_json::from_str(&categories_accessed).unwrap_or_default(),
             conversation_ids: serde_json::from_str(&conversation_ids).unwrap_or_default(),
             metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+            focus_score: row.get("focus_score"),
         })
     }
 
@@ -464,9 +1318,11 @@ impl Database {
             category_id: row.get("category_id"),
             is_shared: row.get("is_shared"),
             tags: serde_json::from_str(&tags).unwrap_or_default(),
-            card_count: 0, // Will be populated by queries that join with flashcards
-            due_count: 0, // Will be populated by queries that join with flashcards
+            // Only present when the query joins with flashcards (see DECK_WITH_COUNTS_SELECT)
+            card_count: row.try_get::<i64, _>("card_count").map(|count| count as i32).unwrap_or(0),
+            due_count: row.try_get::<i64, _>("due_count").map(|count| count as i32).unwrap_or(0),
             metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+            new_card_order: super::types::NewCardOrder::parse(&row.get::<String, _>("new_card_order")),
         })
     }
 
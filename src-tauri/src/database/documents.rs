@@ -1,8 +1,10 @@
 use sqlx::Row;
+use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use sha2::{Sha256, Digest};
-use super::{Database, types::{Document, CreateDocumentRequest}};
+use crate::{language, math};
+use super::{Database, cache::DocumentListKey, compression::{decompress_content, row_content, store_content, write_document_text}, types::{Document, CreateDocumentRequest, BulkDocumentChanges, ContentReplacementResult, DocumentContentRange}};
 
 impl Database {
     pub async fn create_document(&self, req: CreateDocumentRequest) -> Result<Document, sqlx::Error> {
@@ -13,6 +15,9 @@ impl Database {
         
         // Calculate content hash if not provided
         let content_hash = req.content_hash.unwrap_or_else(|| Self::calculate_content_hash(&req.content));
+        let language = language::detect_language(&req.content);
+        let math_terms = math::index_terms(&req.content);
+        let stored = store_content(&req.content);
 
         let document = Document {
             id: id.clone(),
@@ -26,17 +31,26 @@ impl Database {
             updated_at: now,
             status: status.clone(),
             category_id: req.category_id.clone(),
+            last_opened_at: None,
+            pinned_at: None,
+            parent_document_id: None,
+            metadata: None,
+            source_url: None,
+            language: language.clone(),
+            content_length: stored.length,
         };
 
         sqlx::query(
             r#"
-            INSERT INTO documents (id, title, content, content_hash, file_path, doc_type, tags, created_at, updated_at, status, category_id)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO documents (id, title, content, content_zstd, content_length, content_hash, file_path, doc_type, tags, created_at, updated_at, status, category_id, language, math_terms)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
         .bind(&req.title)
-        .bind(&req.content)
+        .bind(&stored.text)
+        .bind(&stored.compressed)
+        .bind(stored.length)
         .bind(&content_hash)
         .bind(&req.file_path)
         .bind(&req.doc_type)
@@ -45,77 +59,109 @@ impl Database {
         .bind(now.to_rfc3339())
         .bind(&status)
         .bind(&req.category_id)
+        .bind(&language)
+        .bind(&math_terms)
         .execute(&self.pool)
         .await?;
+        write_document_text(&self.pool, &id, &req.content).await?;
+        self.cache.invalidate();
+
+        self.index_code_snippets(&id, &req.content).await?;
 
         Ok(document)
     }
 
     pub async fn get_all_documents(&self) -> Result<Vec<Document>, sqlx::Error> {
+        if let Some(documents) = self.cache.document_list(&DocumentListKey::All) {
+            return Ok(documents);
+        }
+        let generation = self.cache.generation();
+
         let rows = sqlx::query("SELECT * FROM documents ORDER BY updated_at DESC")
             .fetch_all(&self.pool)
             .await?;
 
         let mut documents = Vec::new();
         for row in rows {
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-            
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            documents.push(Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_hash: row.get("content_hash"),
-                file_path: row.get("file_path"),
-                doc_type: row.get("doc_type"),
-                tags,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                status: row.get("status"),
-                category_id: row.get("category_id"),
-            });
+            documents.push(self.row_to_document(row));
         }
 
+        self.cache.put_document_list(generation, DocumentListKey::All, &documents);
         Ok(documents)
     }
 
+    /// A document without its content, which can be megabytes for long books; the content is
+    /// then read in pieces with `get_document_content_range`
+    pub async fn get_document_without_content(&self, id: &str) -> Result<Option<Document>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, title, COALESCE(content_length, length(content)) AS content_length, content_hash, file_path, doc_type, tags, created_at,
+                   updated_at, status, category_id, last_opened_at, pinned_at, parent_document_id, metadata, source_url, language
+            FROM documents WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| self.row_to_document(row)))
+    }
+
+    /// `length` characters of a document's content from char `offset`. Uncompressed content is
+    /// sliced by SQLite so the rest of it isn't loaded; compressed content is sliced after
+    /// decompressing it.
+    pub async fn get_document_content_range(&self, id: &str, offset: i64, length: i64) -> Result<Option<DocumentContentRange>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT substr(content, ? + 1, ?) AS content, content_zstd, COALESCE(content_length, length(content)) AS content_length, content_hash
+            FROM documents WHERE id = ?
+            "#,
+        )
+        .bind(offset)
+        .bind(length)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let content = match row.get::<Option<Vec<u8>>, _>("content_zstd") {
+                Some(compressed) => decompress_content(&compressed)
+                    .unwrap_or_else(|e| {
+                        eprintln!("⚠️ {} (document {})", e, id);
+                        String::new()
+                    })
+                    .chars()
+                    .skip(offset as usize)
+                    .take(length as usize)
+                    .collect(),
+                None => row.get("content"),
+            };
+
+            DocumentContentRange {
+                document_id: id.to_string(),
+                offset,
+                content,
+                content_length: row.get("content_length"),
+                content_hash: row.get("content_hash"),
+            }
+        }))
+    }
+
     pub async fn get_document(&self, id: &str) -> Result<Option<Document>, sqlx::Error> {
+        if let Some(document) = self.cache.document(id) {
+            return Ok(Some(document));
+        }
+        let generation = self.cache.generation();
+
         let row = sqlx::query("SELECT * FROM documents WHERE id = ?")
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
 
         if let Some(row) = row {
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-            
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            Ok(Some(Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_hash: row.get("content_hash"),
-                file_path: row.get("file_path"),
-                doc_type: row.get("doc_type"),
-                tags,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                status: row.get("status"),
-                category_id: row.get("category_id"),
-            }))
+            let document = self.row_to_document(row);
+            self.cache.put_document(generation, &document);
+            Ok(Some(document))
         } else {
             Ok(None)
         }
@@ -128,16 +174,21 @@ impl Database {
         
         // Calculate content hash if not provided
         let content_hash = req.content_hash.unwrap_or_else(|| Self::calculate_content_hash(&req.content));
+        let language = language::detect_language(&req.content);
+        let math_terms = math::index_terms(&req.content);
+        let stored = store_content(&req.content);
 
         let result = sqlx::query(
             r#"
             UPDATE documents 
-            SET title = ?, content = ?, content_hash = ?, file_path = ?, doc_type = ?, tags = ?, updated_at = ?, status = ?, category_id = ?
+            SET title = ?, content = ?, content_zstd = ?, content_length = ?, content_hash = ?, file_path = ?, doc_type = ?, tags = ?, updated_at = ?, status = ?, category_id = ?, language = ?, math_terms = ?
             WHERE id = ?
             "#,
         )
         .bind(&req.title)
-        .bind(&req.content)
+        .bind(&stored.text)
+        .bind(&stored.compressed)
+        .bind(stored.length)
         .bind(&content_hash)
         .bind(&req.file_path)
         .bind(&req.doc_type)
@@ -145,11 +196,16 @@ impl Database {
         .bind(now.to_rfc3339())
         .bind(&status)
         .bind(&req.category_id)
+        .bind(&language)
+        .bind(&math_terms)
         .bind(id)
         .execute(&self.pool)
         .await?;
+        self.cache.invalidate();
 
         if result.rows_affected() > 0 {
+            write_document_text(&self.pool, id, &req.content).await?;
+            self.index_code_snippets(id, &req.content).await?;
             self.get_document(id).await
         } else {
             Ok(None)
@@ -161,11 +217,26 @@ impl Database {
             .bind(id)
             .execute(&self.pool)
             .await?;
+        self.cache.invalidate();
 
         Ok(result.rows_affected() > 0)
     }
 
+    pub async fn get_all_document_ids(&self) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query("SELECT id FROM documents")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
+
     pub async fn get_documents_by_category(&self, category_id: &str) -> Result<Vec<Document>, sqlx::Error> {
+        let key = DocumentListKey::Category(category_id.to_string());
+        if let Some(documents) = self.cache.document_list(&key) {
+            return Ok(documents);
+        }
+        let generation = self.cache.generation();
+
         let rows = sqlx::query("SELECT * FROM documents WHERE category_id = ? ORDER BY updated_at DESC")
             .bind(category_id)
             .fetch_all(&self.pool)
@@ -173,79 +244,49 @@ impl Database {
 
         let mut documents = Vec::new();
         for row in rows {
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-            
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            documents.push(Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_hash: row.get("content_hash"),
-                file_path: row.get("file_path"),
-                doc_type: row.get("doc_type"),
-                tags,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                status: row.get("status"),
-                category_id: row.get("category_id"),
-            });
+            documents.push(self.row_to_document(row));
         }
 
+        self.cache.put_document_list(generation, key, &documents);
         Ok(documents)
     }
 
     pub async fn get_uncategorized_documents(&self) -> Result<Vec<Document>, sqlx::Error> {
+        if let Some(documents) = self.cache.document_list(&DocumentListKey::Uncategorized) {
+            return Ok(documents);
+        }
+        let generation = self.cache.generation();
+
         let rows = sqlx::query("SELECT * FROM documents WHERE category_id IS NULL ORDER BY updated_at DESC")
             .fetch_all(&self.pool)
             .await?;
 
         let mut documents = Vec::new();
         for row in rows {
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-            
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            documents.push(Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_hash: row.get("content_hash"),
-                file_path: row.get("file_path"),
-                doc_type: row.get("doc_type"),
-                tags,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                status: row.get("status"),
-                category_id: row.get("category_id"),
-            });
+            documents.push(self.row_to_document(row));
         }
 
+        self.cache.put_document_list(generation, DocumentListKey::Uncategorized, &documents);
         Ok(documents)
     }
 
-    /// Search documents by title, content, or tags (simple LIKE search)
-    pub async fn search_documents(&self, query: &str, limit: i64) -> Result<Vec<Document>, sqlx::Error> {
+    /// Search documents by title, content, or tags (simple LIKE search). With `normalize_math`
+    /// the query is also matched as math, so "∇·E", "\nabla \cdot E" and "div E" find the same
+    /// documents.
+    pub async fn search_documents(&self, query: &str, limit: i64, normalize_math: bool) -> Result<Vec<Document>, sqlx::Error> {
         let like = format!("%{}%", query);
+        let math_query = if normalize_math { math::normalize_math(query) } else { String::new() };
+        // An empty pattern would match every document with math
+        let math_like = (!math_query.is_empty()).then(|| format!("%{}%", math_query));
 
+        // Content is matched in document_text, which covers compressed documents too
         let rows = sqlx::query(
             r#"
             SELECT * FROM documents 
             WHERE title LIKE ? COLLATE NOCASE 
-               OR content LIKE ? COLLATE NOCASE 
+               OR id IN (SELECT t.document_id FROM document_text t WHERE t.id IN (SELECT rowid FROM document_text_fts WHERE content LIKE ?))
                OR tags LIKE ? COLLATE NOCASE 
+               OR (? IS NOT NULL AND math_terms LIKE ?)
             ORDER BY updated_at DESC
             LIMIT ?
             "#,
@@ -253,38 +294,409 @@ impl Database {
         .bind(&like)
         .bind(&like)
         .bind(&like)
+        .bind(&math_like)
+        .bind(&math_like)
         .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
         let mut documents = Vec::new();
         for row in rows {
+            documents.push(self.row_to_document(row));
+        }
+
+        Ok(documents)
+    }
+
+    // === BULK OPERATIONS ===
+
+    /// Every tag used in the library, alphabetically
+    pub async fn get_all_document_tags(&self) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT DISTINCT tag.value FROM documents, json_each(documents.tags) AS tag ORDER BY tag.value")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Apply the same changes to many documents in one transaction. Returns the updated
+    /// documents (ids that don't exist are skipped) alongside their previous status.
+    pub async fn bulk_update_documents(&self, ids: &[String], changes: &BulkDocumentChanges) -> Result<Vec<(Document, String)>, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+        let mut updated_ids = Vec::new();
+
+        for id in ids {
+            let row = sqlx::query("SELECT tags, status, category_id FROM documents WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+            let row = match row {
+                Some(row) => row,
+                None => continue,
+            };
+
             let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+            let mut tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+            tags.retain(|tag| !changes.remove_tags.contains(tag));
+            for tag in &changes.add_tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+
+            let previous_status: String = row.get("status");
+            let status = changes.status.clone().unwrap_or_else(|| previous_status.clone());
+            let category_id: Option<String> = if changes.clear_category {
+                None
+            } else {
+                changes.category_id.clone().or_else(|| row.get("category_id"))
+            };
+
+            sqlx::query("UPDATE documents SET tags = ?, status = ?, category_id = ?, updated_at = ? WHERE id = ?")
+                .bind(serde_json::to_string(&tags).unwrap_or_else(|_| "[]".to_string()))
+                .bind(&status)
+                .bind(&category_id)
+                .bind(&now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+
+            updated_ids.push((id.clone(), previous_status));
+        }
 
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
+        tx.commit().await?;
+        self.cache.invalidate();
 
-            documents.push(Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_hash: row.get("content_hash"),
-                file_path: row.get("file_path"),
-                doc_type: row.get("doc_type"),
-                tags,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                status: row.get("status"),
-                category_id: row.get("category_id"),
+        let mut updated = Vec::new();
+        for (id, previous_status) in updated_ids {
+            if let Some(document) = self.get_document(&id).await? {
+                updated.push((document, previous_status));
+            }
+        }
+
+        Ok(updated)
+    }
+
+    /// Delete many documents in one transaction, returning the rows that were removed
+    /// so the caller can clean up their files and embeddings
+    pub async fn bulk_delete_documents(&self, ids: &[String]) -> Result<Vec<Document>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = Vec::new();
+
+        for id in ids {
+            let row = sqlx::query("SELECT * FROM documents WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+            if let Some(row) = row {
+                let document = self.row_to_document(row);
+                sqlx::query("DELETE FROM documents WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                deleted.push(document);
+            }
+        }
+
+        tx.commit().await?;
+        self.cache.invalidate();
+
+        Ok(deleted)
+    }
+
+    // === SOURCE URLS ===
+
+    /// Record the (normalized) URL a document was imported from
+    pub async fn set_document_source_url(&self, id: &str, source_url: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE documents SET source_url = ? WHERE id = ?")
+            .bind(source_url)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        self.cache.invalidate();
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Find the earliest document imported from a normalized URL
+    pub async fn find_document_by_source_url(&self, source_url: &str) -> Result<Option<Document>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM documents WHERE source_url = ? ORDER BY created_at ASC LIMIT 1")
+            .bind(source_url)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_document(row)))
+    }
+
+    // === LANGUAGES ===
+
+    /// Detect the language of documents stored before languages were tracked. Documents whose
+    /// language can't be told get an empty string so they aren't checked again.
+    pub async fn detect_missing_document_languages(&self) -> Result<usize, sqlx::Error> {
+        let rows = sqlx::query("SELECT id, content, content_zstd FROM documents WHERE language IS NULL")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let changed = !rows.is_empty();
+        let mut detected = 0;
+        for row in rows {
+            let id: String = row.get("id");
+            let content = row_content(&row);
+            let language = language::detect_language(&content);
+            if language.is_some() {
+                detected += 1;
+            }
+            sqlx::query("UPDATE documents SET language = ? WHERE id = ?")
+                .bind(language.unwrap_or_default())
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+        }
+        if changed {
+            self.cache.invalidate();
+        }
+
+        Ok(detected)
+    }
+
+    /// Number of documents per detected language
+    pub async fn get_document_language_counts(&self) -> Result<HashMap<String, i64>, sqlx::Error> {
+        let rows = sqlx::query("SELECT language, COUNT(*) as count FROM documents WHERE language IS NOT NULL AND language != '' GROUP BY language")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| (row.get("language"), row.get("count")))
+            .collect())
+    }
+
+    // === MATH ===
+
+    /// Index the math of documents stored before math terms were tracked. Documents without
+    /// math get an empty string so they aren't checked again.
+    pub async fn index_missing_math_terms(&self) -> Result<usize, sqlx::Error> {
+        let rows = sqlx::query("SELECT id, content, content_zstd FROM documents WHERE math_terms IS NULL")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut indexed = 0;
+        for row in rows {
+            let id: String = row.get("id");
+            let content = row_content(&row);
+            let math_terms = math::index_terms(&content);
+            if !math_terms.is_empty() {
+                indexed += 1;
+            }
+            sqlx::query("UPDATE documents SET math_terms = ? WHERE id = ?")
+                .bind(&math_terms)
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(indexed)
+    }
+
+    // === RECENT & PINNED ===
+
+    /// Record that a document was just opened
+    pub async fn touch_document(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE documents SET last_opened_at = ? WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        self.cache.invalidate();
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_recent_documents(&self, limit: i64) -> Result<Vec<Document>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM documents WHERE last_opened_at IS NOT NULL ORDER BY last_opened_at DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_document(row)).collect())
+    }
+
+    pub async fn set_document_pinned(&self, id: &str, pinned: bool) -> Result<Option<Document>, sqlx::Error> {
+        // Re-pinning keeps the original pin time so the pinned list order stays stable
+        let result = if pinned {
+            sqlx::query("UPDATE documents SET pinned_at = COALESCE(pinned_at, ?) WHERE id = ?")
+                .bind(Utc::now().to_rfc3339())
+                .bind(id)
+                .execute(&self.pool)
+                .await?
+        } else {
+            sqlx::query("UPDATE documents SET pinned_at = NULL WHERE id = ?")
+                .bind(id)
+                .execute(&self.pool)
+                .await?
+        };
+        self.cache.invalidate();
+
+        if result.rows_affected() > 0 {
+            self.get_document(id).await
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Swap in freshly extracted content, recalculating the hash. Highlight sources are
+    /// re-anchored by searching the new content for their text, preferring the match closest
+    /// to their old relative position; ones that can't be found lose their offsets.
+    pub async fn replace_document_content(&self, id: &str, content: &str) -> Result<Option<(Document, ContentReplacementResult)>, sqlx::Error> {
+        let Some(document) = self.get_document(id).await? else {
+            return Ok(None);
+        };
+
+        let old_len = document.content.chars().count().max(1);
+        let new_len = content.chars().count();
+        let mut tx = self.pool.begin().await?;
+
+        let stored = store_content(content);
+        sqlx::query("UPDATE documents SET content = ?, content_zstd = ?, content_length = ?, content_hash = ?, language = ?, math_terms = ?, updated_at = ? WHERE id = ?")
+            .bind(&stored.text)
+            .bind(&stored.compressed)
+            .bind(stored.length)
+            .bind(Self::calculate_content_hash(content))
+            .bind(language::detect_language(content))
+            .bind(math::index_terms(content))
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        write_document_text(&mut *tx, id, content).await?;
+
+        let highlight_rows = sqlx::query(
+            "SELECT id, content, start_offset, end_offset, metadata FROM message_sources WHERE document_id = ? AND source_type = 'highlight' AND start_offset IS NOT NULL"
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut result = ContentReplacementResult::default();
+        for row in highlight_rows {
+            let source_id: String = row.get("id");
+            let start: i64 = row.get("start_offset");
+            let end: Option<i64> = row.get("end_offset");
+            let snapshot: Option<String> = row.get("content");
+
+            // Prefer the stored snapshot; otherwise read the text back out of the old content
+            let anchor_text = snapshot.filter(|text| !text.trim().is_empty()).unwrap_or_else(|| {
+                let start = start.max(0) as usize;
+                let end = end.map(|e| e.max(0) as usize).unwrap_or(start);
+                document.content.chars().skip(start).take(end.saturating_sub(start)).collect()
             });
+            let expected = (start.max(0) as usize * new_len) / old_len;
+
+            match find_anchor(content, anchor_text.trim(), expected) {
+                Some((new_start, new_end)) => {
+                    sqlx::query("UPDATE message_sources SET start_offset = ?, end_offset = ? WHERE id = ?")
+                        .bind(new_start as i64)
+                        .bind(new_end as i64)
+                        .bind(&source_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    result.highlights_reanchored += 1;
+                }
+                None => {
+                    let metadata_json: Option<String> = row.get("metadata");
+                    let mut metadata = metadata_json
+                        .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                        .and_then(|value| value.as_object().cloned())
+                        .unwrap_or_default();
+                    metadata.insert("orphaned_offsets".to_string(), serde_json::json!({ "start": start, "end": end }));
+
+                    sqlx::query("UPDATE message_sources SET start_offset = NULL, end_offset = NULL, metadata = ? WHERE id = ?")
+                        .bind(serde_json::Value::Object(metadata).to_string())
+                        .bind(&source_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    result.highlights_orphaned += 1;
+                }
+            }
         }
 
-        Ok(documents)
+        tx.commit().await?;
+        self.cache.invalidate();
+        self.index_code_snippets(id, content).await?;
+
+        Ok(self.get_document(id).await?.map(|document| (document, result)))
+    }
+
+    /// Merge `key` into the document's metadata object, replacing any previous value for it
+    pub async fn set_document_metadata_field(&self, id: &str, key: &str, value: serde_json::Value) -> Result<Option<Document>, sqlx::Error> {
+        let Some(document) = self.get_document(id).await? else {
+            return Ok(None);
+        };
+
+        let mut metadata = match document.metadata {
+            Some(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        metadata.insert(key.to_string(), value);
+
+        sqlx::query("UPDATE documents SET metadata = ? WHERE id = ?")
+            .bind(serde_json::Value::Object(metadata).to_string())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        self.cache.invalidate();
+
+        self.get_document(id).await
+    }
+
+    pub async fn get_pinned_documents(&self) -> Result<Vec<Document>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM documents WHERE pinned_at IS NOT NULL ORDER BY pinned_at DESC")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_document(row)).collect())
+    }
+
+    // Helper function to convert database row to Document
+    pub(crate) fn row_to_document(&self, row: sqlx::sqlite::SqliteRow) -> Document {
+        let tags_json: String = row.get("tags");
+        let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+
+        let created_at: String = row.get("created_at");
+        let updated_at: String = row.get("updated_at");
+        let parse_optional = |value: Option<String>| value.and_then(|v| {
+            DateTime::parse_from_rfc3339(&v).ok().map(|dt| dt.with_timezone(&Utc))
+        });
+
+        // Rows selected without their content carry its length instead
+        let content = row_content(&row);
+        let content_length = row.try_get::<Option<i64>, _>("content_length").ok().flatten()
+            .unwrap_or_else(|| content.chars().count() as i64);
+
+        Document {
+            id: row.get("id"),
+            title: row.get("title"),
+            content,
+            content_hash: row.get("content_hash"),
+            file_path: row.get("file_path"),
+            doc_type: row.get("doc_type"),
+            tags,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            status: row.get("status"),
+            category_id: row.get("category_id"),
+            last_opened_at: parse_optional(row.get("last_opened_at")),
+            pinned_at: parse_optional(row.get("pinned_at")),
+            parent_document_id: row.get("parent_document_id"),
+            metadata: row.get::<Option<String>, _>("metadata")
+                .and_then(|json| serde_json::from_str(&json).ok()),
+            source_url: row.get("source_url"),
+            language: row.get::<Option<String>, _>("language").filter(|language| !language.is_empty()),
+            content_length,
+        }
     }
 
     /// Calculate SHA-256 hash of content for duplicate detection
@@ -302,29 +714,7 @@ impl Database {
             .await?;
 
         if let Some(row) = row {
-            let tags_json: String = row.get("tags");
-            let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-            
-            let created_at: String = row.get("created_at");
-            let updated_at: String = row.get("updated_at");
-
-            Ok(Some(Document {
-                id: row.get("id"),
-                title: row.get("title"),
-                content: row.get("content"),
-                content_hash: row.get("content_hash"),
-                file_path: row.get("file_path"),
-                doc_type: row.get("doc_type"),
-                tags,
-                created_at: DateTime::parse_from_rfc3339(&created_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&updated_at)
-                    .unwrap_or_else(|_| Utc::now().into())
-                    .with_timezone(&Utc),
-                status: row.get("status"),
-                category_id: row.get("category_id"),
-            }))
+            Ok(Some(self.row_to_document(row)))
         } else {
             Ok(None)
         }
@@ -335,4 +725,31 @@ impl Database {
         let content_hash = Self::calculate_content_hash(content);
         self.find_document_by_hash(&content_hash).await
     }
-} 
\ No newline at end of file
+} 
+
+/// Find `needle` in `content`, returning the char range of the occurrence closest to `near`
+/// (a char offset). Falls back to a case-insensitive search.
+fn find_anchor(content: &str, needle: &str, near: usize) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+
+    let closest = |haystack: &str, needle: &str| -> Option<usize> {
+        haystack
+            .match_indices(needle)
+            .map(|(byte_index, _)| haystack[..byte_index].chars().count())
+            .min_by_key(|char_index| char_index.abs_diff(near))
+    };
+
+    let needle_len = needle.chars().count();
+    closest(content, needle)
+        .or_else(|| {
+            // Lowercasing can change byte lengths, but char counts stay stable for most scripts
+            let lowered = content.to_lowercase();
+            if lowered.chars().count() != content.chars().count() {
+                return None;
+            }
+            closest(&lowered, &needle.to_lowercase())
+        })
+        .map(|start| (start, start + needle_len))
+}
@@ -1,51 +1,55 @@
 use sqlx::Row;
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use uuid::Uuid;
 use super::types::*;
 use super::database::Database;
 
+// Decks with their card and due counts; due means next_review has passed, as in get_due_flashcards
+const DECK_WITH_COUNTS_SELECT: &str = r#"
+    SELECT d.*,
+        COUNT(f.id) AS card_count,
+        COALESCE(SUM(CASE WHEN f.next_review <= ? THEN 1 ELSE 0 END), 0) AS due_count
+    FROM flashcard_decks d
+    LEFT JOIN flashcards f ON f.deck_id = d.id
+"#;
+
+// Cards whose own category or deck's category is archived are left out of scheduling
+const CARD_NOT_ARCHIVED: &str = r#"
+    NOT EXISTS (
+        SELECT 1 FROM categories ac
+        WHERE ac.archived = 1
+          AND (ac.id = f.category_id OR ac.id = (SELECT category_id FROM flashcard_decks WHERE id = f.deck_id))
+    )
+"#;
+
+// Per-card times used until there are enough timed reviews, and the window they are taken from
+const DEFAULT_SECONDS_PER_DUE_CARD: f64 = 30.0;
+const DEFAULT_SECONDS_PER_NEW_CARD: f64 = 45.0;
+const MIN_TIMED_REVIEWS: usize = 20;
+const TIMED_REVIEW_WINDOW: i64 = 500;
+// Longer than this, the card was most likely left open while the user was away
+const MAX_SECONDS_PER_REVIEW: i64 = 300;
+
 impl Database {
     // === FLASHCARD CRUD METHODS ===
 
     pub async fn create_flashcard(&self, request: CreateFlashcardRequest) -> Result<Flashcard, sqlx::Error> {
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now().to_rfc3339();
-        
-        let row = sqlx::query(
-            r#"
-            INSERT INTO flashcards (
-                id, front, back, source_document_id, source_text, difficulty,
-                created_at, last_reviewed, next_review, review_count, success_rate,
-                tags, category_id, card_type, deck_id, ef_factor, interval, repetitions, metadata
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            RETURNING *
-            "#,
-        )
-        .bind(&id)
-        .bind(&request.front)
-        .bind(&request.back)
-        .bind(&request.source_document_id)
-        .bind(&request.source_text)
-        .bind(&request.difficulty.as_deref().unwrap_or("medium"))
-        .bind(&now)
-        .bind(None::<String>) // last_reviewed - null for new cards
-        .bind(None::<String>) // next_review - null for new cards
-        .bind(0) // review_count
-        .bind(0.0) // success_rate
-        .bind(serde_json::to_string(&request.tags).unwrap_or_else(|_| "[]".to_string()))
-        .bind(&request.category_id)
-        .bind(&request.card_type.as_deref().unwrap_or("basic"))
-        .bind(&request.deck_id)
-        .bind(2.5) // ef_factor default
-        .bind(1) // interval default
-        .bind(0) // repetitions default
-        .bind(&request.metadata)
-        .fetch_one(&self.pool)
-        .await?;
-
+        let row = insert_flashcard(&self.pool, &request).await?;
         self.row_to_flashcard(row)
     }
 
+    /// Create several cards in one transaction, so a failure part way leaves none of them behind
+    pub async fn create_flashcards(&self, requests: &[CreateFlashcardRequest]) -> Result<Vec<Flashcard>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut rows = Vec::with_capacity(requests.len());
+        for request in requests {
+            rows.push(insert_flashcard(&mut *tx, request).await?);
+        }
+        tx.commit().await?;
+
+        rows.into_iter().map(|row| self.row_to_flashcard(row)).collect()
+    }
+
     pub async fn get_flashcard(&self, id: &str) -> Result<Option<Flashcard>, sqlx::Error> {
         let row = sqlx::query("SELECT * FROM flashcards WHERE id = ?")
             .bind(id)
@@ -156,6 +160,20 @@ impl Database {
         }
     }
 
+    // Replace only the metadata, leaving content and scheduling untouched
+    pub async fn update_flashcard_metadata(&self, id: &str, metadata: &serde_json::Value) -> Result<Option<Flashcard>, sqlx::Error> {
+        let row = sqlx::query("UPDATE flashcards SET metadata = ? WHERE id = ? RETURNING *")
+            .bind(serde_json::to_string(metadata).unwrap_or_else(|_| "{}".to_string()))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_flashcard(row)?)),
+            None => Ok(None),
+        }
+    }
+
     pub async fn delete_flashcard(&self, id: &str) -> Result<bool, sqlx::Error> {
         let result = sqlx::query("DELETE FROM flashcards WHERE id = ?")
             .bind(id)
@@ -168,37 +186,31 @@ impl Database {
     // === FLASHCARD DECK METHODS ===
 
     pub async fn create_flashcard_deck(&self, request: CreateFlashcardDeckRequest) -> Result<FlashcardDeck, sqlx::Error> {
-        let id = Uuid::new_v4().to_string();
-        let now = Utc::now().to_rfc3339();
-        
-        let row = sqlx::query(
-            r#"
-            INSERT INTO flashcard_decks (
-                id, name, description, color, icon, created_at, updated_at,
-                category_id, is_shared, tags, metadata
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-            RETURNING *
-            "#,
-        )
-        .bind(&id)
-        .bind(&request.name)
-        .bind(&request.description)
-        .bind(&request.color)
-        .bind(&request.icon)
-        .bind(&now)
-        .bind(&now)
-        .bind(&request.category_id)
-        .bind(&request.is_shared.unwrap_or(false))
-        .bind(serde_json::to_string(&request.tags).unwrap_or_else(|_| "[]".to_string()))
-        .bind(&request.metadata)
-        .fetch_one(&self.pool)
-        .await?;
-
+        let row = insert_flashcard_deck(&self.pool, &request).await?;
         self.row_to_flashcard_deck(row)
     }
 
+    /// Create a deck and its cards in one transaction, so an import that fails part way leaves
+    /// no half-filled deck behind. Each card's deck_id is set to the new deck.
+    pub async fn create_flashcard_deck_with_cards(
+        &self,
+        request: CreateFlashcardDeckRequest,
+        cards: Vec<CreateFlashcardRequest>,
+    ) -> Result<FlashcardDeck, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let deck = self.row_to_flashcard_deck(insert_flashcard_deck(&mut *tx, &request).await?)?;
+        for mut card in cards {
+            card.deck_id = Some(deck.id.clone());
+            insert_flashcard(&mut *tx, &card).await?;
+        }
+        tx.commit().await?;
+
+        Ok(deck)
+    }
+
     pub async fn get_flashcard_deck(&self, id: &str) -> Result<Option<FlashcardDeck>, sqlx::Error> {
-        let row = sqlx::query("SELECT * FROM flashcard_decks WHERE id = ?")
+        let row = sqlx::query(&format!("{} WHERE d.id = ? GROUP BY d.id", DECK_WITH_COUNTS_SELECT))
+            .bind(Utc::now().to_rfc3339())
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
@@ -210,7 +222,8 @@ impl Database {
     }
 
     pub async fn get_flashcard_decks(&self) -> Result<Vec<FlashcardDeck>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM flashcard_decks ORDER BY created_at DESC")
+        let rows = sqlx::query(&format!("{} GROUP BY d.id ORDER BY d.created_at DESC", DECK_WITH_COUNTS_SELECT))
+            .bind(Utc::now().to_rfc3339())
             .fetch_all(&self.pool)
             .await?;
 
@@ -228,7 +241,8 @@ impl Database {
             r#"
             UPDATE flashcard_decks SET
                 name = ?, description = ?, color = ?, icon = ?, updated_at = ?,
-                category_id = ?, is_shared = ?, tags = ?, metadata = ?
+                category_id = ?, is_shared = ?, tags = ?, metadata = ?,
+                new_card_order = COALESCE(?, new_card_order)
             WHERE id = ?
             RETURNING *
             "#,
@@ -242,6 +256,7 @@ impl Database {
         .bind(&request.is_shared.unwrap_or(false))
         .bind(serde_json::to_string(&request.tags).unwrap_or_else(|_| "[]".to_string()))
         .bind(&request.metadata)
+        .bind(request.new_card_order.map(|order| order.as_str()))
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
@@ -252,6 +267,51 @@ impl Database {
         }
     }
 
+    /// Card states and review activity for one deck, using the same buckets as get_flashcard_stats
+    pub async fn get_flashcard_deck_stats(&self, deck_id: &str) -> Result<FlashcardDeckStats, sqlx::Error> {
+        let now = Utc::now();
+        let start_of_day = now.date_naive().and_hms_opt(0, 0, 0)
+            .map(|midnight| midnight.and_utc())
+            .unwrap_or(now);
+
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COUNT(*) AS card_count,
+                COALESCE(SUM(CASE WHEN next_review <= ? THEN 1 ELSE 0 END), 0) AS due_count,
+                COALESCE(SUM(CASE WHEN review_count = 0 THEN 1 ELSE 0 END), 0) AS new_count,
+                COALESCE(SUM(CASE WHEN review_count > 0 AND review_count < 3 THEN 1 ELSE 0 END), 0) AS learning_count,
+                COALESCE(SUM(CASE WHEN review_count >= 3 AND success_rate >= 0.8 THEN 1 ELSE 0 END), 0) AS mastered_count,
+                AVG(CASE WHEN review_count > 0 THEN success_rate END) AS average_success_rate
+            FROM flashcards
+            WHERE deck_id = ?
+            "#,
+        )
+        .bind(now.to_rfc3339())
+        .bind(deck_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let reviews_today: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM flashcard_reviews r JOIN flashcards f ON f.id = r.flashcard_id WHERE f.deck_id = ? AND r.timestamp >= ?"
+        )
+        .bind(deck_id)
+        .bind(start_of_day.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(FlashcardDeckStats {
+            deck_id: deck_id.to_string(),
+            card_count: row.get::<i64, _>("card_count") as i32,
+            due_count: row.get::<i64, _>("due_count") as i32,
+            new_count: row.get::<i64, _>("new_count") as i32,
+            learning_count: row.get::<i64, _>("learning_count") as i32,
+            mastered_count: row.get::<i64, _>("mastered_count") as i32,
+            average_success_rate: row.get::<Option<f64>, _>("average_success_rate").unwrap_or(0.0) as f32,
+            reviews_today: reviews_today as i32,
+        })
+    }
+
     pub async fn delete_flashcard_deck(&self, id: &str) -> Result<bool, sqlx::Error> {
         let result = sqlx::query("DELETE FROM flashcard_decks WHERE id = ?")
             .bind(id)
@@ -263,11 +323,35 @@ impl Database {
 
     // === FLASHCARD REVIEW METHODS ===
 
-    pub async fn record_flashcard_review(&self, request: CreateFlashcardReviewRequest) -> Result<FlashcardReview, sqlx::Error> {
+    /// Record a review and apply it in one transaction: the review row, the card's SM-2
+    /// scheduling and running stats, the session's review counters and the persisted review
+    /// session's progress all change together
+    pub async fn record_flashcard_review(&self, request: CreateFlashcardReviewRequest) -> Result<FlashcardReviewResult, sqlx::Error> {
         let id = Uuid::new_v4().to_string();
-        let now = Utc::now().to_rfc3339();
-        
-        let row = sqlx::query(
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        let card = sqlx::query("SELECT ef_factor, interval, repetitions, review_count, success_rate FROM flashcards WHERE id = ?")
+            .bind(&request.flashcard_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let previous_ef: f32 = card.get("ef_factor");
+        let previous_interval: i32 = card.get("interval");
+        let repetitions: i32 = card.get("repetitions");
+        let review_count: i32 = card.get("review_count");
+        let success_rate: f32 = card.get("success_rate");
+
+        let (new_ef, new_interval, new_repetitions) = sm2_schedule(request.quality, previous_ef, previous_interval, repetitions);
+        let next_review = now + Duration::days(new_interval as i64);
+        let outcome = match request.response.as_str() {
+            "correct" => 1.0,
+            "partial" => 0.5,
+            _ => 0.0,
+        };
+        let new_success_rate = (success_rate * review_count as f32 + outcome) / (review_count + 1) as f32;
+
+        let review_row = sqlx::query(
             r#"
             INSERT INTO flashcard_reviews (
                 id, flashcard_id, session_id, timestamp, response, time_spent, confidence, quality,
@@ -279,29 +363,92 @@ impl Database {
         .bind(&id)
         .bind(&request.flashcard_id)
         .bind(&request.session_id)
-        .bind(&now)
+        .bind(now.to_rfc3339())
         .bind(&request.response)
-        .bind(&request.time_spent)
-        .bind(&request.confidence)
-        .bind(&request.quality)
-        .bind(2.5) // default previous_ef
-        .bind(2.5) // default new_ef
-        .bind(1)   // default previous_interval
-        .bind(1)   // default new_interval
+        .bind(request.time_spent)
+        .bind(request.confidence)
+        .bind(request.quality)
+        .bind(previous_ef)
+        .bind(new_ef)
+        .bind(previous_interval)
+        .bind(new_interval)
         .bind(&request.metadata)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        self.row_to_flashcard_review(row)
+        let card_row = sqlx::query(
+            r#"
+            UPDATE flashcards SET
+                last_reviewed = ?, next_review = ?, review_count = ?, success_rate = ?,
+                ef_factor = ?, interval = ?, repetitions = ?
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(now.to_rfc3339())
+        .bind(next_review.to_rfc3339())
+        .bind(review_count + 1)
+        .bind(new_success_rate)
+        .bind(new_ef)
+        .bind(new_interval)
+        .bind(new_repetitions)
+        .bind(&request.flashcard_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Running review counters live under "flashcard_reviews" in the active session's metadata
+        let session = sqlx::query("SELECT metadata FROM study_sessions WHERE id = ? AND is_active = TRUE")
+            .bind(&request.session_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if let Some(session) = session {
+            let mut metadata = session.get::<Option<String>, _>("metadata")
+                .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+                .filter(|value| value.is_object())
+                .unwrap_or_else(|| serde_json::json!({}));
+            let stats = &metadata["flashcard_reviews"];
+            let counter = |key: &str| stats.get(key).and_then(|v| v.as_i64()).unwrap_or(0);
+            let updated = serde_json::json!({
+                "reviewed": counter("reviewed") + 1,
+                "correct": counter("correct") + i64::from(request.response == "correct"),
+                "incorrect": counter("incorrect") + i64::from(request.response == "incorrect"),
+                "partial": counter("partial") + i64::from(request.response == "partial"),
+                "time_spent": counter("time_spent") + request.time_spent as i64,
+            });
+            metadata["flashcard_reviews"] = updated;
+
+            sqlx::query("UPDATE study_sessions SET metadata = ? WHERE id = ?")
+                .bind(metadata.to_string())
+                .bind(&request.session_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        if let Some(review_session_id) = &request.review_session_id {
+            Self::advance_review_session_state(&mut tx, review_session_id, ReviewCardResult {
+                flashcard_id: request.flashcard_id.clone(),
+                review_id: id.clone(),
+                response: request.response.clone(),
+                quality: request.quality,
+            }).await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(FlashcardReviewResult {
+            flashcard: self.row_to_flashcard(card_row)?,
+            review: self.row_to_flashcard_review(review_row)?,
+        })
     }
 
     pub async fn get_due_flashcards(&self, limit: Option<i32>) -> Result<Vec<Flashcard>, sqlx::Error> {
         let limit = limit.unwrap_or(20);
         let now = Utc::now().to_rfc3339();
         
-        let rows = sqlx::query(
-            "SELECT * FROM flashcards WHERE next_review <= ? ORDER BY next_review ASC LIMIT ?"
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT f.* FROM flashcards f WHERE f.next_review <= ? AND {} ORDER BY f.next_review ASC LIMIT ?",
+            CARD_NOT_ARCHIVED
+        ))
         .bind(&now)
         .bind(limit)
         .fetch_all(&self.pool)
@@ -314,15 +461,47 @@ impl Database {
         Ok(flashcards)
     }
 
-    pub async fn get_new_flashcards(&self, limit: Option<i32>) -> Result<Vec<Flashcard>, sqlx::Error> {
+    pub async fn count_due_flashcards(&self) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM flashcards f WHERE f.next_review <= ? AND {}", CARD_NOT_ARCHIVED))
+            .bind(Utc::now().to_rfc3339())
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    /// Cards never reviewed, in `order`, or the deck's configured order when a deck is given
+    pub async fn get_new_flashcards(&self, limit: Option<i32>, deck_id: Option<&str>, order: Option<NewCardOrder>) -> Result<Vec<Flashcard>, sqlx::Error> {
         let limit = limit.unwrap_or(20);
-        
-        let rows = sqlx::query(
-            "SELECT * FROM flashcards WHERE review_count = 0 ORDER BY created_at DESC LIMIT ?"
-        )
-        .bind(limit)
-        .fetch_all(&self.pool)
-        .await?;
+        let order = match (order, deck_id) {
+            (Some(order), _) => order,
+            (None, Some(deck_id)) => self.get_flashcard_deck(deck_id).await?
+                .map(|deck| deck.new_card_order)
+                .unwrap_or_default(),
+            (None, None) => NewCardOrder::default(),
+        };
+
+        let order_by = match order {
+            NewCardOrder::Created => "f.created_at ASC",
+            NewCardOrder::Random => "RANDOM()",
+            // Cards without a source document go last; within a document, by where the source text appears
+            NewCardOrder::Document => "f.source_document_id IS NULL, d.created_at ASC, f.source_document_id, \
+                COALESCE(NULLIF(instr(t.content, f.source_text), 0), 9223372036854775807) ASC, f.created_at ASC",
+            NewCardOrder::Difficulty => "CASE f.difficulty WHEN 'easy' THEN 0 WHEN 'medium' THEN 1 WHEN 'hard' THEN 2 ELSE 1 END, f.created_at ASC",
+        };
+        // A deck asked for by id is studied even when archived
+        let deck_filter = if deck_id.is_some() { "AND f.deck_id = ?".to_string() } else { format!("AND {}", CARD_NOT_ARCHIVED) };
+        let sql = format!(
+            "SELECT f.* FROM flashcards f LEFT JOIN documents d ON d.id = f.source_document_id \
+             LEFT JOIN document_text t ON t.document_id = f.source_document_id WHERE f.review_count = 0 {} ORDER BY {} LIMIT ?",
+            deck_filter, order_by
+        );
+
+        let mut query = sqlx::query(&sql);
+        if let Some(deck_id) = deck_id {
+            query = query.bind(deck_id);
+        }
+        let rows = query.bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
 
         let mut flashcards = Vec::new();
         for row in rows {
@@ -331,6 +510,85 @@ impl Database {
         Ok(flashcards)
     }
 
+    /// Due cards first, then new cards, as many as fit in `minutes_available` at this user's
+    /// median seconds per card over their recent reviews
+    pub async fn get_adaptive_review_session(&self, minutes_available: i32) -> Result<AdaptiveReviewSession, sqlx::Error> {
+        let (seconds_per_due_card, seconds_per_new_card, timed_reviews) = self.get_seconds_per_card().await?;
+        let budget = minutes_available.max(0) as f64 * 60.0;
+
+        let due_capacity = (budget / seconds_per_due_card).floor() as i32;
+        let due_cards = if due_capacity > 0 {
+            self.get_due_flashcards(Some(due_capacity)).await?
+        } else {
+            Vec::new()
+        };
+
+        let remaining = budget - due_cards.len() as f64 * seconds_per_due_card;
+        let new_capacity = (remaining / seconds_per_new_card).floor() as i32;
+        let new_cards = if new_capacity > 0 {
+            self.get_new_flashcards(Some(new_capacity), None, None).await?
+        } else {
+            Vec::new()
+        };
+
+        let seconds = due_cards.len() as f64 * seconds_per_due_card + new_cards.len() as f64 * seconds_per_new_card;
+        let session = FlashcardReviewSession {
+            session_limit: (due_cards.len() + new_cards.len()) as i32,
+            estimated_time: (seconds / 60.0).ceil() as i32,
+            due_cards,
+            new_cards,
+            mix_strategy: "adaptive".to_string(),
+        };
+
+        Ok(AdaptiveReviewSession {
+            session,
+            minutes_available,
+            seconds_per_due_card,
+            seconds_per_new_card,
+            timed_reviews,
+        })
+    }
+
+    // (due, new, reviews counted): median seconds per review of a seen card and per first review
+    // of a new one, over the latest timed reviews. A kind with too few reviews uses its default.
+    async fn get_seconds_per_card(&self) -> Result<(f64, f64, i64), sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT r.time_spent,
+                NOT EXISTS (
+                    SELECT 1 FROM flashcard_reviews p
+                    WHERE p.flashcard_id = r.flashcard_id AND p.timestamp < r.timestamp
+                ) AS first_review
+            FROM flashcard_reviews r
+            WHERE r.time_spent > 0 AND r.time_spent <= ?
+            ORDER BY r.timestamp DESC
+            LIMIT ?
+            "#
+        )
+        .bind(MAX_SECONDS_PER_REVIEW)
+        .bind(TIMED_REVIEW_WINDOW)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut due_times = Vec::new();
+        let mut new_times = Vec::new();
+        for row in &rows {
+            let seconds = row.get::<i64, _>("time_spent") as f64;
+            if row.get::<bool, _>("first_review") {
+                new_times.push(seconds);
+            } else {
+                due_times.push(seconds);
+            }
+        }
+
+        let seconds_per_due_card = median(&mut due_times).unwrap_or(DEFAULT_SECONDS_PER_DUE_CARD);
+        // First reviews are rarer; scale the seen-card time by the defaults' ratio until there are enough
+        let seconds_per_new_card = median(&mut new_times)
+            .unwrap_or(seconds_per_due_card * DEFAULT_SECONDS_PER_NEW_CARD / DEFAULT_SECONDS_PER_DUE_CARD);
+
+        Ok((seconds_per_due_card, seconds_per_new_card, rows.len() as i64))
+    }
+
     pub async fn get_flashcard_review_session(&self, session_limit: i32, mix_strategy: &str) -> Result<FlashcardReviewSession, sqlx::Error> {
         let (due_cards, new_cards) = match mix_strategy {
             "due_first" => {
@@ -338,7 +596,7 @@ impl Database {
                 let due_cards = self.get_due_flashcards(Some(session_limit)).await?;
                 let remaining = session_limit - due_cards.len() as i32;
                 let new_cards = if remaining > 0 {
-                    self.get_new_flashcards(Some(remaining)).await?
+                    self.get_new_flashcards(Some(remaining), None, None).await?
                 } else {
                     Vec::new()
                 };
@@ -346,7 +604,7 @@ impl Database {
             },
             "new_first" => {
                 // Prioritize new cards, fill remaining with due cards
-                let new_cards = self.get_new_flashcards(Some(session_limit)).await?;
+                let new_cards = self.get_new_flashcards(Some(session_limit), None, None).await?;
                 let remaining = session_limit - new_cards.len() as i32;
                 let due_cards = if remaining > 0 {
                     self.get_due_flashcards(Some(remaining)).await?
@@ -359,7 +617,7 @@ impl Database {
                 // Mix both types evenly
                 let half_limit = session_limit / 2;
                 let due_cards = self.get_due_flashcards(Some(half_limit)).await?;
-                let new_cards = self.get_new_flashcards(Some(half_limit)).await?;
+                let new_cards = self.get_new_flashcards(Some(half_limit), None, None).await?;
                 
                 // If one type has fewer cards, get more of the other type
                 let total_found = due_cards.len() + new_cards.len();
@@ -367,7 +625,7 @@ impl Database {
                     let remaining = session_limit - total_found as i32;
                     if due_cards.len() < half_limit as usize {
                         // Get more new cards
-                        let additional_new = self.get_new_flashcards(Some(new_cards.len() as i32 + remaining)).await?;
+                        let additional_new = self.get_new_flashcards(Some(new_cards.len() as i32 + remaining), None, None).await?;
                         (due_cards, additional_new)
                     } else if new_cards.len() < half_limit as usize {
                         // Get more due cards
@@ -394,6 +652,88 @@ impl Database {
         })
     }
 
+    /// A review session built from a filter rather than the schedule, e.g. to cram one chapter's
+    /// cards ahead of an exam. Reviewed cards come back as due cards, never-reviewed ones as new.
+    pub async fn get_custom_review_session(&self, filter: &CustomReviewFilter) -> Result<FlashcardReviewSession, sqlx::Error> {
+        let now = Utc::now();
+        let limit = filter.limit.unwrap_or(50).clamp(1, 500);
+        let mut conditions: Vec<String> = Vec::new();
+        let mut binds: Vec<String> = Vec::new();
+
+        if !filter.tags.is_empty() {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM json_each(f.tags) WHERE json_each.value IN ({}))",
+                vec!["?"; filter.tags.len()].join(", ")
+            ));
+            binds.extend(filter.tags.iter().cloned());
+        }
+        if let Some(deck_id) = &filter.deck_id {
+            conditions.push("f.deck_id = ?".to_string());
+            binds.push(deck_id.clone());
+        }
+        if let Some(category_id) = &filter.category_id {
+            conditions.push("f.category_id = ?".to_string());
+            binds.push(category_id.clone());
+        }
+        if let Some(document_id) = &filter.source_document_id {
+            conditions.push("f.source_document_id = ?".to_string());
+            binds.push(document_id.clone());
+        }
+        if let Some(days) = filter.failed_within_days {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM flashcard_reviews r WHERE r.flashcard_id = f.id AND (r.response = 'incorrect' OR r.quality < 3) AND r.timestamp >= ?)".to_string()
+            );
+            binds.push((now - Duration::days(days.max(0))).to_rfc3339());
+        }
+        if let Some(days) = filter.due_within_days {
+            conditions.push("f.next_review <= ?".to_string());
+            binds.push((now + Duration::days(days.max(0))).to_rfc3339());
+        }
+        if !filter.include_new.unwrap_or(true) {
+            conditions.push("f.review_count > 0".to_string());
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT f.* FROM flashcards f {} ORDER BY COALESCE(f.next_review, f.created_at) ASC LIMIT ?",
+            where_clause
+        );
+
+        let mut query = sqlx::query(&sql);
+        for value in &binds {
+            query = query.bind(value);
+        }
+        let rows = query.bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut due_cards = Vec::new();
+        let mut new_cards = Vec::new();
+        for row in rows {
+            let card = self.row_to_flashcard(row)?;
+            if card.review_count == 0 {
+                new_cards.push(card);
+            } else {
+                due_cards.push(card);
+            }
+        }
+
+        // Estimate time (assuming 30 seconds per card on average)
+        let estimated_time = (due_cards.len() + new_cards.len()) as i32 * 30 / 60;
+
+        Ok(FlashcardReviewSession {
+            due_cards,
+            new_cards,
+            session_limit: limit,
+            estimated_time,
+            mix_strategy: "custom".to_string(),
+        })
+    }
+
     pub async fn get_flashcard_stats(&self) -> Result<FlashcardStats, sqlx::Error> {
         use std::collections::HashMap;
         
@@ -402,7 +742,7 @@ impl Database {
             .await?;
         let total_cards = total_cards_row.get::<i64, _>("count") as i32;
 
-        let cards_due_row = sqlx::query("SELECT COUNT(*) as count FROM flashcards WHERE next_review <= ?")
+        let cards_due_row = sqlx::query(&format!("SELECT COUNT(*) as count FROM flashcards f WHERE f.next_review <= ? AND {}", CARD_NOT_ARCHIVED))
             .bind(Utc::now().to_rfc3339())
             .fetch_one(&self.pool)
             .await?;
@@ -455,7 +795,7 @@ impl Database {
     }
 
     pub async fn get_flashcard_reviews(&self, flashcard_id: &str) -> Result<Vec<FlashcardReview>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM flashcard_reviews WHERE flashcard_id = ? ORDER BY created_at DESC")
+        let rows = sqlx::query("SELECT * FROM flashcard_reviews WHERE flashcard_id = ? ORDER BY timestamp DESC")
             .bind(flashcard_id)
             .fetch_all(&self.pool)
             .await?;
@@ -467,8 +807,55 @@ impl Database {
         Ok(reviews)
     }
 
+    /// Everything a card-info panel shows: review history (oldest first), how the interval grew,
+    /// average answer time and lapses (failed reviews after the card had been learned)
+    pub async fn get_flashcard_detail_stats(&self, flashcard_id: &str) -> Result<Option<FlashcardDetailStats>, sqlx::Error> {
+        let Some(flashcard) = self.get_flashcard(flashcard_id).await? else {
+            return Ok(None);
+        };
+        let mut reviews = self.get_flashcard_reviews(flashcard_id).await?;
+        reviews.reverse();
+
+        let interval_curve = reviews.iter()
+            .map(|review| IntervalPoint {
+                timestamp: review.timestamp,
+                interval: review.new_interval,
+                ease_factor: review.new_ef,
+            })
+            .collect();
+
+        let mut lapse_count = 0;
+        let mut learned = false;
+        for review in &reviews {
+            if review.quality >= 3 {
+                learned = true;
+            } else if learned {
+                lapse_count += 1;
+                learned = false;
+            }
+        }
+
+        let average_time_spent = if reviews.is_empty() {
+            0.0
+        } else {
+            reviews.iter().map(|r| r.time_spent as f32).sum::<f32>() / reviews.len() as f32
+        };
+        let correct_count = reviews.iter().filter(|r| r.response == "correct").count() as i32;
+
+        Ok(Some(FlashcardDetailStats {
+            flashcard,
+            total_reviews: reviews.len() as i32,
+            correct_count,
+            lapse_count,
+            average_time_spent,
+            first_reviewed: reviews.first().map(|r| r.timestamp),
+            interval_curve,
+            reviews,
+        }))
+    }
+
     pub async fn get_flashcard_reviews_by_session(&self, session_id: &str) -> Result<Vec<FlashcardReview>, sqlx::Error> {
-        let rows = sqlx::query("SELECT * FROM flashcard_reviews WHERE session_id = ? ORDER BY created_at DESC")
+        let rows = sqlx::query("SELECT * FROM flashcard_reviews WHERE session_id = ? ORDER BY timestamp DESC")
             .bind(session_id)
             .fetch_all(&self.pool)
             .await?;
@@ -479,4 +866,112 @@ impl Database {
         }
         Ok(reviews)
     }
-} 
\ No newline at end of file
+}
+
+/// SM-2 scheduling, matching the frontend's calculateSM2: returns (ease factor, interval in
+/// days, consecutive successful repetitions)
+fn sm2_schedule(quality: i32, ef_factor: f32, interval: i32, repetitions: i32) -> (f32, i32, i32) {
+    let quality = quality.clamp(0, 5);
+    let lapse = (5 - quality) as f32;
+    let ef_factor = (ef_factor + (0.1 - lapse * (0.08 + lapse * 0.02))).max(1.3);
+    let ef_factor = (ef_factor * 100.0).round() / 100.0;
+
+    if quality < 3 {
+        return (ef_factor, 1, 0);
+    }
+    let repetitions = repetitions + 1;
+    let interval = match repetitions {
+        1 => 1,
+        2 => 6,
+        _ => (interval as f32 * ef_factor).round() as i32,
+    };
+    (ef_factor, interval, repetitions)
+}
+
+// Median of at least MIN_TIMED_REVIEWS samples, so a handful of slow answers can't skew it
+fn median(samples: &mut [f64]) -> Option<f64> {
+    if samples.len() < MIN_TIMED_REVIEWS {
+        return None;
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let middle = samples.len() / 2;
+    Some(if samples.len() % 2 == 0 {
+        (samples[middle - 1] + samples[middle]) / 2.0
+    } else {
+        samples[middle]
+    })
+}
+
+/// Insert a new, never reviewed card and return its row
+async fn insert_flashcard<'e, E>(executor: E, request: &CreateFlashcardRequest) -> Result<sqlx::sqlite::SqliteRow, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO flashcards (
+            id, front, back, source_document_id, source_text, difficulty,
+            created_at, last_reviewed, next_review, review_count, success_rate,
+            tags, category_id, card_type, deck_id, ef_factor, interval, repetitions, metadata
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#,
+    )
+    .bind(&id)
+    .bind(&request.front)
+    .bind(&request.back)
+    .bind(&request.source_document_id)
+    .bind(&request.source_text)
+    .bind(request.difficulty.as_deref().unwrap_or("medium"))
+    .bind(&now)
+    .bind(None::<String>) // last_reviewed - null for new cards
+    .bind(None::<String>) // next_review - null for new cards
+    .bind(0) // review_count
+    .bind(0.0) // success_rate
+    .bind(serde_json::to_string(&request.tags).unwrap_or_else(|_| "[]".to_string()))
+    .bind(&request.category_id)
+    .bind(request.card_type.as_deref().unwrap_or("basic"))
+    .bind(&request.deck_id)
+    .bind(2.5) // ef_factor default
+    .bind(1) // interval default
+    .bind(0) // repetitions default
+    .bind(&request.metadata)
+    .fetch_one(executor)
+    .await
+}
+
+/// Insert a deck and return its row
+async fn insert_flashcard_deck<'e, E>(executor: E, request: &CreateFlashcardDeckRequest) -> Result<sqlx::sqlite::SqliteRow, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let id = Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO flashcard_decks (
+            id, name, description, color, icon, created_at, updated_at,
+            category_id, is_shared, tags, metadata, new_card_order
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        RETURNING *
+        "#,
+    )
+    .bind(&id)
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(&request.color)
+    .bind(&request.icon)
+    .bind(&now)
+    .bind(&now)
+    .bind(&request.category_id)
+    .bind(request.is_shared.unwrap_or(false))
+    .bind(serde_json::to_string(&request.tags).unwrap_or_else(|_| "[]".to_string()))
+    .bind(&request.metadata)
+    .bind(request.new_card_order.unwrap_or_default().as_str())
+    .fetch_one(executor)
+    .await
+}
@@ -0,0 +1,207 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use super::{
+    Database,
+    quick_search::{row_to_hit, SNIPPET_CONTEXT_CHARS},
+    types::{ConversationSearchHit, FlashcardSearchHit, HighlightSearchHit, QuickSearchHit},
+};
+
+impl Database {
+    // === GLOBAL SEARCH ===
+
+    /// Documents whose title, tags or content contain `query`, title matches first. Content is
+    /// matched through the document_text index, so compressed documents are found without
+    /// being decompressed, and only the text around a match is read back.
+    pub async fn search_document_hits(&self, query: &str, limit: i64) -> Result<Vec<QuickSearchHit>, sqlx::Error> {
+        let like = format!("%{}%", query);
+        let lowered = query.to_lowercase();
+
+        let rows = sqlx::query(
+            r#"
+            SELECT d.id, d.title, d.doc_type, d.category_id, d.tags, d.updated_at, d.rank,
+                   CASE WHEN d.rank = 2
+                        THEN substr(t.content, max(1, instr(lower(t.content), ?) - ?), ? + length(?) + ?)
+                   END AS snippet
+            FROM (
+                SELECT id, title, doc_type, category_id, tags, updated_at,
+                       CASE
+                           WHEN title LIKE ? COLLATE NOCASE THEN 0
+                           WHEN tags LIKE ? COLLATE NOCASE THEN 1
+                           ELSE 2
+                       END AS rank
+                FROM documents
+                WHERE title LIKE ? COLLATE NOCASE
+                   OR tags LIKE ? COLLATE NOCASE
+                   OR id IN (SELECT t.document_id FROM document_text t WHERE t.id IN (SELECT rowid FROM document_text_fts WHERE content LIKE ?))
+                ORDER BY rank, updated_at DESC
+                LIMIT ?
+            ) d
+            LEFT JOIN document_text t ON t.document_id = d.id
+            ORDER BY d.rank, d.updated_at DESC
+            "#,
+        )
+        .bind(&lowered)
+        .bind(SNIPPET_CONTEXT_CHARS)
+        .bind(SNIPPET_CONTEXT_CHARS)
+        .bind(&lowered)
+        .bind(SNIPPET_CONTEXT_CHARS)
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| {
+                let match_kind = match row.get::<i64, _>("rank") {
+                    0 => "title",
+                    1 => "tag",
+                    _ => "content",
+                };
+                let snippet = row.get::<Option<String>, _>("snippet")
+                    .map(|snippet| snippet.split_whitespace().collect::<Vec<_>>().join(" "));
+                row_to_hit(&row, match_kind, snippet)
+            })
+            .collect())
+    }
+
+    /// Highlighted passages (text sent from a document into a chat) containing `query`
+    pub async fn search_highlights(&self, query: &str, limit: i64) -> Result<Vec<HighlightSearchHit>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT ms.id, ms.document_id, ms.content, ms.start_offset, ms.end_offset,
+                   d.title AS document_title, cm.conversation_id, cm.created_at
+            FROM message_sources ms
+            JOIN conversation_messages cm ON cm.id = ms.message_id
+            LEFT JOIN documents d ON d.id = ms.document_id
+            WHERE ms.source_type = 'highlight' AND ms.content LIKE ? COLLATE NOCASE
+            ORDER BY cm.created_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(format!("%{}%", query))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| HighlightSearchHit {
+                id: row.get("id"),
+                document_id: row.get("document_id"),
+                document_title: row.get("document_title"),
+                conversation_id: row.get("conversation_id"),
+                text: row.get::<Option<String>, _>("content").unwrap_or_default(),
+                start_offset: row.get("start_offset"),
+                end_offset: row.get("end_offset"),
+                created_at: parse_timestamp(row.get("created_at")),
+            })
+            .collect())
+    }
+
+    /// Flashcards whose front, back or tags contain `query`, front matches first
+    pub async fn search_flashcard_hits(&self, query: &str, limit: i64) -> Result<Vec<FlashcardSearchHit>, sqlx::Error> {
+        let like = format!("%{}%", query);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, front, back, deck_id, source_document_id, tags, next_review
+            FROM flashcards
+            WHERE front LIKE ? COLLATE NOCASE OR back LIKE ? COLLATE NOCASE OR tags LIKE ? COLLATE NOCASE
+            ORDER BY CASE WHEN front LIKE ? COLLATE NOCASE THEN 0 ELSE 1 END, created_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| FlashcardSearchHit {
+                id: row.get("id"),
+                front: row.get("front"),
+                back: row.get("back"),
+                deck_id: row.get("deck_id"),
+                source_document_id: row.get("source_document_id"),
+                tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+                next_review: row.get::<Option<String>, _>("next_review")
+                    .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+                    .map(|dt| dt.with_timezone(&Utc)),
+            })
+            .collect())
+    }
+
+    /// Conversations whose title or one of whose messages contains `query`, most recent first
+    pub async fn search_conversation_hits(&self, query: &str, limit: i64) -> Result<Vec<ConversationSearchHit>, sqlx::Error> {
+        let like = format!("%{}%", query);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT c.id, c.title, c.conversation_type, c.updated_at,
+                   c.title LIKE ? COLLATE NOCASE AS title_match,
+                   (SELECT m.content FROM conversation_messages m
+                    WHERE m.conversation_id = c.id AND m.content LIKE ? COLLATE NOCASE
+                    ORDER BY m.created_at DESC LIMIT 1) AS matched_message
+            FROM conversations c
+            WHERE c.title LIKE ? COLLATE NOCASE
+               OR EXISTS (SELECT 1 FROM conversation_messages m WHERE m.conversation_id = c.id AND m.content LIKE ? COLLATE NOCASE)
+            ORDER BY c.updated_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(&like)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| {
+                let title_match: bool = row.get("title_match");
+                let snippet = row.get::<Option<String>, _>("matched_message")
+                    .map(|message| snippet_around(&message, query));
+                ConversationSearchHit {
+                    id: row.get("id"),
+                    title: row.get("title"),
+                    conversation_type: row.get("conversation_type"),
+                    match_kind: if title_match { "title" } else { "message" }.to_string(),
+                    snippet,
+                    updated_at: parse_timestamp(row.get("updated_at")),
+                }
+            })
+            .collect())
+    }
+}
+
+fn parse_timestamp(value: String) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+// Text around the first case-insensitive occurrence of `query`, on one line
+fn snippet_around(text: &str, query: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = text.to_lowercase().chars().collect();
+    let needle: Vec<char> = query.to_lowercase().chars().collect();
+    let context = SNIPPET_CONTEXT_CHARS as usize;
+
+    // Lowercasing can change the char count; fall back to the start of the text then
+    let start = if lowered.len() == chars.len() && !needle.is_empty() {
+        lowered.windows(needle.len()).position(|window| window == needle.as_slice()).unwrap_or(0)
+    } else {
+        0
+    };
+    let from = start.saturating_sub(context);
+    let to = (start + needle.len() + context).min(chars.len());
+
+    chars[from..to].iter().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,213 @@
+use sqlx::Row;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use std::path::Path;
+use super::{Database, types::{MaintenanceRun, MaintenanceTaskResult, ProcessingJob}};
+
+impl Database {
+    // === MAINTENANCE RUNS ===
+
+    pub async fn start_maintenance_run(&self, trigger: &str) -> Result<MaintenanceRun, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query("INSERT INTO maintenance_runs (id, trigger, status, started_at, tasks) VALUES (?, ?, 'running', ?, '[]')")
+            .bind(&id)
+            .bind(trigger)
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(MaintenanceRun {
+            id,
+            trigger: trigger.to_string(),
+            status: "running".to_string(),
+            started_at: now,
+            completed_at: None,
+            tasks: Vec::new(),
+            error_message: None,
+        })
+    }
+
+    pub async fn finish_maintenance_run(&self, run: &MaintenanceRun) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE maintenance_runs SET status = ?, completed_at = ?, tasks = ?, error_message = ? WHERE id = ?")
+            .bind(&run.status)
+            .bind(run.completed_at.map(|t| t.to_rfc3339()))
+            .bind(serde_json::to_string(&run.tasks).unwrap_or_else(|_| "[]".to_string()))
+            .bind(&run.error_message)
+            .bind(&run.id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_maintenance_runs(&self, limit: i64) -> Result<Vec<MaintenanceRun>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM maintenance_runs ORDER BY started_at DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| {
+            let started_at: String = row.get("started_at");
+            let completed_at: Option<String> = row.get("completed_at");
+            let tasks: String = row.get("tasks");
+
+            MaintenanceRun {
+                id: row.get("id"),
+                trigger: row.get("trigger"),
+                status: row.get("status"),
+                started_at: DateTime::parse_from_rfc3339(&started_at)
+                    .unwrap_or_else(|_| Utc::now().into())
+                    .with_timezone(&Utc),
+                completed_at: completed_at.and_then(|t| DateTime::parse_from_rfc3339(&t).ok().map(|t| t.with_timezone(&Utc))),
+                tasks: serde_json::from_str::<Vec<MaintenanceTaskResult>>(&tasks).unwrap_or_default(),
+                error_message: row.get("error_message"),
+            }
+        }).collect())
+    }
+
+    /// When the last successful scheduled or manual run finished
+    pub async fn get_last_maintenance_completed_at(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let row = sqlx::query("SELECT MAX(completed_at) AS completed_at FROM maintenance_runs WHERE status = 'completed'")
+            .fetch_one(&self.pool)
+            .await?;
+
+        let completed_at: Option<String> = row.get("completed_at");
+        Ok(completed_at.and_then(|t| DateTime::parse_from_rfc3339(&t).ok().map(|t| t.with_timezone(&Utc))))
+    }
+
+    // === MAINTENANCE TASKS ===
+
+    /// Write a consistent copy of the database to `path` without stopping the app
+    pub async fn backup_to(&self, path: &Path) -> Result<(), sqlx::Error> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy().to_string())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Fold the write-ahead log back into the database file and close every connection. Called
+    /// on shutdown; the database can't be used afterwards.
+    pub async fn close(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+            .execute(&self.pool)
+            .await?;
+        self.pool.close().await;
+
+        Ok(())
+    }
+
+    /// A trivial query, to check the database can be read at all
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Run SQLite's integrity check, returning the problems found (empty when healthy)
+    pub async fn integrity_check(&self) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| row.get::<String, _>(0))
+            .filter(|result| result != "ok")
+            .collect())
+    }
+
+    /// Fail jobs stuck in 'processing'; returns how many were stopped
+    pub async fn fail_stale_jobs(&self, stale_job_hours: i64) -> Result<u64, sqlx::Error> {
+        let now = Utc::now();
+        let stale_before = (now - Duration::hours(stale_job_hours)).to_rfc3339();
+
+        let failed = sqlx::query(
+            "UPDATE processing_jobs SET status = 'failed', error_message = 'Job stalled and was stopped by maintenance', completed_at = ? WHERE status = 'processing' AND started_at < ?"
+        )
+        .bind(now.to_rfc3339())
+        .bind(&stale_before)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(failed)
+    }
+
+    /// Delete jobs with one of `statuses` that finished more than `older_than_days` ago,
+    /// returning the deleted jobs so callers can archive them
+    pub async fn prune_processing_jobs(&self, older_than_days: i64, statuses: &[String]) -> Result<Vec<ProcessingJob>, sqlx::Error> {
+        if statuses.is_empty() {
+            return Ok(Vec::new());
+        }
+        let delete_before = (Utc::now() - Duration::days(older_than_days)).to_rfc3339();
+        let placeholders = vec!["?"; statuses.len()].join(", ");
+        let filter = format!("status IN ({}) AND COALESCE(completed_at, created_at) < ?", placeholders);
+
+        let mut tx = self.pool.begin().await?;
+
+        let select_sql = format!("SELECT * FROM processing_jobs WHERE {} ORDER BY created_at ASC", filter);
+        let mut select = sqlx::query(&select_sql);
+        for status in statuses {
+            select = select.bind(status);
+        }
+        let rows = select.bind(&delete_before).fetch_all(&mut *tx).await?;
+
+        let delete_sql = format!("DELETE FROM processing_jobs WHERE {}", filter);
+        let mut delete = sqlx::query(&delete_sql);
+        for status in statuses {
+            delete = delete.bind(status);
+        }
+        delete.bind(&delete_before).execute(&mut *tx).await?;
+
+        tx.commit().await?;
+
+        rows.into_iter().map(|row| self.row_to_processing_job(row)).collect()
+    }
+
+    /// File names still referenced by documents (PDFs) and flashcards (attachments)
+    pub async fn get_referenced_files(&self) -> Result<(Vec<String>, Vec<serde_json::Value>), sqlx::Error> {
+        let pdf_rows = sqlx::query("SELECT file_path FROM documents WHERE file_path IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?;
+        let metadata_rows = sqlx::query("SELECT metadata FROM flashcards WHERE metadata IS NOT NULL")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let pdf_files = pdf_rows.into_iter().map(|row| row.get("file_path")).collect();
+        let flashcard_metadata = metadata_rows.into_iter()
+            .filter_map(|row| {
+                let metadata: String = row.get("metadata");
+                serde_json::from_str(&metadata).ok()
+            })
+            .collect();
+
+        Ok((pdf_files, flashcard_metadata))
+    }
+
+    /// Attachment file names of document covers
+    pub async fn get_document_cover_files(&self) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT json_extract(metadata, '$.cover.file') AS file FROM documents WHERE metadata IS NOT NULL AND json_valid(metadata) = 1 AND json_extract(metadata, '$.cover.file') IS NOT NULL"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("file")).collect())
+    }
+
+    /// Attachment file names of photos handwritten notes were imported from
+    pub async fn get_handwriting_image_files(&self) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT json_extract(metadata, '$.handwriting.image') AS file FROM documents WHERE metadata IS NOT NULL AND json_valid(metadata) = 1 AND json_extract(metadata, '$.handwriting.image') IS NOT NULL"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("file")).collect())
+    }
+}
@@ -6,6 +6,29 @@ pub mod api_keys;
 pub mod sessions;
 pub mod flashcards;
 pub mod processing_jobs;
+pub mod practice;
+pub mod conversations;
+pub mod ai_profiles;
+pub mod sections;
+pub mod settings;
+pub mod maintenance;
+pub mod model_capabilities;
+pub mod review_sessions;
+pub mod session_templates;
+pub mod note_templates;
+pub mod concepts;
+pub mod question_bank;
+pub mod rubrics;
+pub mod activity;
+pub mod automation_scripts;
+pub mod translations;
+pub mod code_snippets;
+pub mod outlines;
+pub mod compression;
+pub mod cache;
+pub mod quick_search;
+pub mod global_search;
+pub mod analytics_export;
 
 // Re-export commonly used types and the main Database struct
 pub use types::*;
@@ -0,0 +1,95 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use super::{Database, types::ModelCapability};
+
+impl Database {
+    // === MODEL CAPABILITIES ===
+
+    /// Replace the cached catalog with a freshly fetched one
+    pub async fn replace_model_capabilities(&self, capabilities: &[ModelCapability]) -> Result<usize, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM model_capabilities")
+            .execute(&mut *tx)
+            .await?;
+
+        for capability in capabilities {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO model_capabilities (
+                    provider_id, model_id, name, context_window, max_output_tokens, supports_tools,
+                    supports_reasoning, supports_attachments, input_modalities, cost_input, cost_output, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&capability.provider_id)
+            .bind(&capability.model_id)
+            .bind(&capability.name)
+            .bind(capability.context_window)
+            .bind(capability.max_output_tokens)
+            .bind(capability.supports_tools)
+            .bind(capability.supports_reasoning)
+            .bind(capability.supports_attachments)
+            .bind(serde_json::to_string(&capability.input_modalities).unwrap_or_else(|_| "[]".to_string()))
+            .bind(capability.cost_input)
+            .bind(capability.cost_output)
+            .bind(capability.updated_at.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(capabilities.len())
+    }
+
+    /// Look a model up by id, preferring the entry from `provider_id` when several providers list it
+    pub async fn get_model_capability(&self, model_id: &str, provider_id: Option<&str>) -> Result<Option<ModelCapability>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM model_capabilities
+            WHERE model_id = ? COLLATE NOCASE
+            ORDER BY CASE WHEN provider_id = ? THEN 0 ELSE 1 END, context_window DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(model_id)
+        .bind(provider_id.unwrap_or_default())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| self.row_to_model_capability(row)))
+    }
+
+    /// When the catalog was last refreshed, or None if it has never been fetched
+    pub async fn get_model_capabilities_updated_at(&self) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let updated_at: Option<String> = sqlx::query_scalar("SELECT MAX(updated_at) FROM model_capabilities")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(updated_at
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&Utc)))
+    }
+
+    fn row_to_model_capability(&self, row: sqlx::sqlite::SqliteRow) -> ModelCapability {
+        let input_modalities: String = row.get("input_modalities");
+        let updated_at: String = row.get("updated_at");
+
+        ModelCapability {
+            provider_id: row.get("provider_id"),
+            model_id: row.get("model_id"),
+            name: row.get("name"),
+            context_window: row.get("context_window"),
+            max_output_tokens: row.get("max_output_tokens"),
+            supports_tools: row.get("supports_tools"),
+            supports_reasoning: row.get("supports_reasoning"),
+            supports_attachments: row.get("supports_attachments"),
+            input_modalities: serde_json::from_str(&input_modalities).unwrap_or_default(),
+            cost_input: row.get("cost_input"),
+            cost_output: row.get("cost_output"),
+            updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{NoteTemplate, SaveNoteTemplateRequest}};
+
+// (id, name, description, title template, body)
+const BUILTIN_TEMPLATES: &[(&str, &str, &str, &str, &str)] = &[
+    (
+        "cornell",
+        "Cornell Notes",
+        "Cue column, notes and a summary to write after class",
+        "{{course}} Lecture {{lecture_number}}: Cornell Notes",
+        "# {{course}} Lecture {{lecture_number}}\n\n**Date:** {{date}}\n**Topic:** {{topic}}\n\n## Cues\n\n- \n\n## Notes\n\n- \n\n## Summary\n\n",
+    ),
+    (
+        "lecture_summary",
+        "Lecture Summary",
+        "Key points, definitions and open questions from one lecture",
+        "{{course}} Lecture {{lecture_number}} Summary",
+        "# {{course}} Lecture {{lecture_number}} Summary\n\n**Date:** {{date}}\n**Topic:** {{topic}}\n\n## Key points\n\n- \n\n## Definitions\n\n- \n\n## Open questions\n\n- \n",
+    ),
+];
+
+impl Database {
+    // === NOTE TEMPLATES ===
+
+    /// Insert the built-in templates that are missing; edits to existing ones are kept
+    pub(crate) async fn seed_builtin_note_templates(&self) -> Result<(), sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        for (id, name, description, title_template, body) in BUILTIN_TEMPLATES {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO note_templates (id, name, description, title_template, body, is_builtin, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, TRUE, ?, ?)
+                "#,
+            )
+            .bind(id)
+            .bind(name)
+            .bind(description)
+            .bind(title_template)
+            .bind(body)
+            .bind(&now)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_note_templates(&self) -> Result<Vec<NoteTemplate>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM note_templates ORDER BY is_builtin DESC, name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_note_template(row)).collect())
+    }
+
+    pub async fn get_note_template(&self, id: &str) -> Result<Option<NoteTemplate>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM note_templates WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_note_template(row)))
+    }
+
+    /// Create a template, or update the one with `req.id`
+    pub async fn save_note_template(&self, req: SaveNoteTemplateRequest) -> Result<NoteTemplate, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+
+        let id = match req.id {
+            Some(id) => {
+                let result = sqlx::query(
+                    r#"
+                    UPDATE note_templates
+                    SET name = ?, description = ?, title_template = ?, body = ?, updated_at = ?
+                    WHERE id = ?
+                    "#,
+                )
+                .bind(&req.name)
+                .bind(&req.description)
+                .bind(&req.title_template)
+                .bind(&req.body)
+                .bind(&now)
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+                if result.rows_affected() == 0 {
+                    return Err(sqlx::Error::RowNotFound);
+                }
+                id
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    r#"
+                    INSERT INTO note_templates (id, name, description, title_template, body, is_builtin, created_at, updated_at)
+                    VALUES (?, ?, ?, ?, ?, FALSE, ?, ?)
+                    "#,
+                )
+                .bind(&id)
+                .bind(&req.name)
+                .bind(&req.description)
+                .bind(&req.title_template)
+                .bind(&req.body)
+                .bind(&now)
+                .bind(&now)
+                .execute(&self.pool)
+                .await?;
+                id
+            }
+        };
+
+        self.get_note_template(&id).await?.ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Delete a user template; built-in templates are left alone
+    pub async fn delete_note_template(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM note_templates WHERE id = ? AND is_builtin = FALSE")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Notes already created from a template in a category (or uncategorized), for numbering
+    /// lectures
+    pub async fn count_notes_from_template(&self, template_id: &str, category_id: Option<&str>) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT COUNT(*) FROM documents WHERE json_extract(metadata, '$.note_template_id') = ? AND category_id IS ?"
+        )
+        .bind(template_id)
+        .bind(category_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+
+    fn row_to_note_template(&self, row: sqlx::sqlite::SqliteRow) -> NoteTemplate {
+        let parse = |value: String| DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        NoteTemplate {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            title_template: row.get("title_template"),
+            body: row.get("body"),
+            is_builtin: row.get("is_builtin"),
+            created_at: parse(row.get("created_at")),
+            updated_at: parse(row.get("updated_at")),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use super::{Database, sections::page_at, types::{DocumentOutline, OutlineEntry}};
+
+// An outline entry before section ends and parents are known
+struct OutlineHeading {
+    level: usize,
+    title: String,
+    start: usize,
+    page: Option<i32>,
+}
+
+/// Outline from the markdown headings in `content`, skipping ones inside code blocks
+pub fn outline_from_headings(content: &str) -> Vec<OutlineEntry> {
+    let mut headings = Vec::new();
+    let mut char_offset = 0;
+    let mut in_code_block = false;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+        }
+
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if !in_code_block && (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+            let title = trimmed[level..].trim().trim_end_matches('#').trim().to_string();
+            if !title.is_empty() {
+                headings.push(OutlineHeading { level, title, start: char_offset, page: page_at(content, char_offset) });
+            }
+        }
+
+        char_offset += line.chars().count();
+    }
+
+    finish_outline(content, headings)
+}
+
+/// Outline from PDF bookmarks given as (level, title, page). Each bookmark is placed at the
+/// first occurrence of its title from the start of its page, or at the page start when the
+/// title isn't in the text; pages are only known when extraction kept page breaks.
+pub fn outline_from_bookmarks(content: &str, bookmarks: &[(usize, String, Option<usize>)]) -> Vec<OutlineEntry> {
+    let lower: Vec<char> = content.to_lowercase().chars().collect();
+    let mut headings = Vec::new();
+    let mut previous_start = 0;
+
+    for (level, title, page) in bookmarks {
+        let page_start = page.and_then(|page| page_start_offset(content, page)).unwrap_or(previous_start);
+        let search_from = page_start.max(previous_start);
+        let start = find_chars(&lower, &title.to_lowercase().chars().collect::<Vec<_>>(), search_from)
+            .unwrap_or(search_from);
+
+        headings.push(OutlineHeading {
+            level: (*level).max(1),
+            title: title.clone(),
+            start,
+            page: page.map(|page| page as i32).or_else(|| page_at(content, start)),
+        });
+        previous_start = start;
+    }
+
+    finish_outline(content, headings)
+}
+
+// A section runs until the next heading at its level or above
+fn finish_outline(content: &str, headings: Vec<OutlineHeading>) -> Vec<OutlineEntry> {
+    let total_chars = content.chars().count();
+
+    headings.iter().enumerate()
+        .map(|(i, heading)| {
+            let end = headings[i + 1..].iter()
+                .find(|next| next.level <= heading.level)
+                .map(|next| next.start)
+                .unwrap_or(total_chars);
+            let parent_index = headings[..i].iter().rposition(|previous| previous.level < heading.level);
+
+            OutlineEntry {
+                index: i as i32,
+                level: heading.level as i32,
+                title: heading.title.clone(),
+                start_offset: heading.start as i64,
+                end_offset: end.max(heading.start) as i64,
+                page: heading.page,
+                parent_index: parent_index.map(|index| index as i32),
+            }
+        })
+        .collect()
+}
+
+// Char offset where a 1-based page starts, from form-feed page breaks
+fn page_start_offset(content: &str, page: usize) -> Option<usize> {
+    if page <= 1 {
+        return Some(0);
+    }
+    content.chars()
+        .enumerate()
+        .filter(|(_, c)| *c == '\u{c}')
+        .nth(page - 2)
+        .map(|(offset, _)| offset + 1)
+}
+
+fn find_chars(haystack: &[char], needle: &[char], from: usize) -> Option<usize> {
+    if needle.is_empty() || from >= haystack.len() {
+        return None;
+    }
+    haystack[from..].windows(needle.len())
+        .position(|window| window == needle)
+        .map(|position| from + position)
+}
+
+impl Database {
+    // === OUTLINES ===
+
+    pub async fn get_document_outline(&self, document_id: &str) -> Result<Option<DocumentOutline>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM document_outlines WHERE document_id = ?")
+            .bind(document_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| DocumentOutline {
+            document_id: row.get("document_id"),
+            source: row.get("source"),
+            content_hash: row.get("content_hash"),
+            entries: serde_json::from_str(&row.get::<String, _>("entries")).unwrap_or_default(),
+            updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }))
+    }
+
+    /// Store a document's outline, replacing the previous one
+    pub async fn save_document_outline(
+        &self,
+        document_id: &str,
+        source: &str,
+        content_hash: &str,
+        entries: Vec<OutlineEntry>,
+    ) -> Result<DocumentOutline, sqlx::Error> {
+        let now = Utc::now();
+        let entries_json = serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string());
+
+        sqlx::query(
+            r#"
+            INSERT INTO document_outlines (document_id, source, content_hash, entries, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(document_id) DO UPDATE SET
+                source = excluded.source,
+                content_hash = excluded.content_hash,
+                entries = excluded.entries,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(document_id)
+        .bind(source)
+        .bind(content_hash)
+        .bind(&entries_json)
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(DocumentOutline {
+            document_id: document_id.to_string(),
+            source: source.to_string(),
+            content_hash: content_hash.to_string(),
+            entries,
+            updated_at: now,
+        })
+    }
+}
@@ -0,0 +1,256 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{
+    CategoryPracticeSettings, UpdatePracticeSettingsRequest, Quiz, QuizQuestion, CreateQuizRequest,
+    Document, ProcessingJob, CreateProcessingJobRequest,
+}};
+
+impl Database {
+    // === PRACTICE SETTINGS ===
+
+    pub async fn get_practice_settings(&self, category_id: &str) -> Result<Option<CategoryPracticeSettings>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM category_practice_settings WHERE category_id = ?")
+            .bind(category_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| {
+            let updated_at: String = row.get("updated_at");
+            CategoryPracticeSettings {
+                category_id: row.get("category_id"),
+                enabled: row.get("enabled"),
+                question_count: row.get("question_count"),
+                flashcard_count: row.get("flashcard_count"),
+                provider_id: row.get("provider_id"),
+                provider_type: row.get("provider_type"),
+                base_url: row.get("base_url"),
+                model: row.get("model"),
+                updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                    .unwrap_or_else(|_| Utc::now().into())
+                    .with_timezone(&Utc),
+            }
+        }))
+    }
+
+    pub async fn update_practice_settings(&self, category_id: &str, req: UpdatePracticeSettingsRequest) -> Result<CategoryPracticeSettings, sqlx::Error> {
+        let now = Utc::now();
+        let settings = CategoryPracticeSettings {
+            category_id: category_id.to_string(),
+            enabled: req.enabled,
+            question_count: req.question_count.unwrap_or(5).clamp(0, 50),
+            flashcard_count: req.flashcard_count.unwrap_or(3).clamp(0, 50),
+            provider_id: req.provider_id,
+            provider_type: req.provider_type,
+            base_url: req.base_url,
+            model: req.model,
+            updated_at: now,
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO category_practice_settings (category_id, enabled, question_count, flashcard_count, provider_id, provider_type, base_url, model, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(category_id) DO UPDATE SET
+                enabled = excluded.enabled,
+                question_count = excluded.question_count,
+                flashcard_count = excluded.flashcard_count,
+                provider_id = excluded.provider_id,
+                provider_type = excluded.provider_type,
+                base_url = excluded.base_url,
+                model = excluded.model,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(&settings.category_id)
+        .bind(settings.enabled)
+        .bind(settings.question_count)
+        .bind(settings.flashcard_count)
+        .bind(&settings.provider_id)
+        .bind(&settings.provider_type)
+        .bind(&settings.base_url)
+        .bind(&settings.model)
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(settings)
+    }
+
+    // Enqueue a practice generation job for a document if its category has it enabled.
+    // Returns None when practice generation is off, unconfigured, or already queued.
+    pub async fn enqueue_practice_generation(&self, document: &Document) -> Result<Option<ProcessingJob>, sqlx::Error> {
+        let category_id = match &document.category_id {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        let settings = match self.get_practice_settings(category_id).await? {
+            Some(settings) if settings.enabled && settings.provider_id.is_some() && settings.model.is_some() => settings,
+            _ => return Ok(None),
+        };
+
+        let already_queued: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM processing_jobs WHERE job_type = 'practice_generation' AND status IN ('pending', 'processing') AND json_extract(metadata, '$.document_id') = ?"
+        )
+        .bind(&document.id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if already_queued > 0 {
+            return Ok(None);
+        }
+
+        let request = CreateProcessingJobRequest {
+            job_type: "practice_generation".to_string(),
+            source_type: "document".to_string(),
+            source_path: None,
+            original_filename: document.title.clone(),
+            title: Some(format!("Practice: {}", document.title)),
+            tags: document.tags.clone(),
+            category_id: Some(category_id.clone()),
+            processing_options: Some(serde_json::json!({
+                "question_count": settings.question_count,
+                "flashcard_count": settings.flashcard_count,
+            })),
+            metadata: Some(serde_json::json!({
+                "document_id": document.id,
+            })),
+            source_url: None,
+        };
+
+        Ok(Some(self.create_processing_job(request).await?))
+    }
+
+    // === QUIZZES ===
+
+    pub async fn create_quiz(&self, req: CreateQuizRequest) -> Result<Quiz, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let metadata_json = req.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO quizzes (id, title, document_id, category_id, created_at, metadata) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&req.title)
+        .bind(&req.document_id)
+        .bind(&req.category_id)
+        .bind(now.to_rfc3339())
+        .bind(metadata_json)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut questions = Vec::new();
+        for (position, question) in req.questions.into_iter().enumerate() {
+            let question_id = Uuid::new_v4().to_string();
+            sqlx::query(
+                "INSERT INTO quiz_questions (id, quiz_id, position, question, answer, explanation) VALUES (?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&question_id)
+            .bind(&id)
+            .bind(position as i32)
+            .bind(&question.question)
+            .bind(&question.answer)
+            .bind(&question.explanation)
+            .execute(&mut *tx)
+            .await?;
+
+            questions.push(QuizQuestion {
+                id: question_id,
+                quiz_id: id.clone(),
+                position: position as i32,
+                question: question.question,
+                answer: question.answer,
+                explanation: question.explanation,
+            });
+        }
+
+        tx.commit().await?;
+
+        Ok(Quiz {
+            id,
+            title: req.title,
+            document_id: req.document_id,
+            category_id: req.category_id,
+            created_at: now,
+            questions,
+            metadata: req.metadata,
+        })
+    }
+
+    pub async fn get_quiz(&self, quiz_id: &str) -> Result<Option<Quiz>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM quizzes WHERE id = ?")
+            .bind(quiz_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => Ok(Some(self.row_to_quiz(row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_quizzes_by_document(&self, document_id: &str) -> Result<Vec<Quiz>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM quizzes WHERE document_id = ? ORDER BY created_at DESC")
+            .bind(document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut quizzes = Vec::new();
+        for row in rows {
+            quizzes.push(self.row_to_quiz(row).await?);
+        }
+
+        Ok(quizzes)
+    }
+
+    pub async fn delete_quiz(&self, quiz_id: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM quiz_questions WHERE quiz_id = ?")
+            .bind(quiz_id)
+            .execute(&self.pool)
+            .await?;
+
+        let result = sqlx::query("DELETE FROM quizzes WHERE id = ?")
+            .bind(quiz_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Helper function to convert a quiz row (plus its questions) to Quiz
+    async fn row_to_quiz(&self, row: sqlx::sqlite::SqliteRow) -> Result<Quiz, sqlx::Error> {
+        let id: String = row.get("id");
+        let created_at: String = row.get("created_at");
+        let metadata: Option<String> = row.get("metadata");
+
+        let question_rows = sqlx::query("SELECT * FROM quiz_questions WHERE quiz_id = ? ORDER BY position ASC")
+            .bind(&id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let questions = question_rows.into_iter().map(|q| QuizQuestion {
+            id: q.get("id"),
+            quiz_id: q.get("quiz_id"),
+            position: q.get("position"),
+            question: q.get("question"),
+            answer: q.get("answer"),
+            explanation: q.get("explanation"),
+        }).collect();
+
+        Ok(Quiz {
+            id,
+            title: row.get("title"),
+            document_id: row.get("document_id"),
+            category_id: row.get("category_id"),
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .unwrap_or_else(|_| Utc::now().into())
+                .with_timezone(&Utc),
+            questions,
+            metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        })
+    }
+}
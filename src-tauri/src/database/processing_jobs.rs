@@ -32,6 +32,7 @@ impl Database {
             started_at: None,
             completed_at: None,
             metadata: req.metadata.clone(),
+            source_url: req.source_url.clone(),
         };
 
         sqlx::query(
@@ -39,8 +40,8 @@ impl Database {
             INSERT INTO processing_jobs (
                 id, job_type, status, source_type, source_path, original_filename, title, tags, 
                 category_id, progress, error_message, result_document_id, processing_options, 
-                created_at, started_at, completed_at, metadata
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                created_at, started_at, completed_at, metadata, source_url
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
@@ -60,6 +61,7 @@ impl Database {
         .bind(None::<String>) // started_at
         .bind(None::<String>) // completed_at
         .bind(metadata_json)
+        .bind(&req.source_url)
         .execute(&self.pool)
         .await?;
 
@@ -193,6 +195,48 @@ impl Database {
         Ok(jobs)
     }
 
+    /// Jobs that completed or failed in the window (since, until]
+    pub async fn get_processing_jobs_finished_between(&self, since: DateTime<Utc>, until: DateTime<Utc>) -> Result<Vec<ProcessingJob>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM processing_jobs WHERE status IN ('completed', 'failed') AND completed_at > ? AND completed_at <= ? ORDER BY completed_at ASC"
+        )
+        .bind(since.to_rfc3339())
+        .bind(until.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut jobs = Vec::new();
+        for row in rows {
+            jobs.push(self.row_to_processing_job(row)?);
+        }
+
+        Ok(jobs)
+    }
+
+    /// Find the latest job for a normalized URL that is queued, running, or completed with its
+    /// document still in the library
+    pub async fn find_processing_job_by_source_url(&self, source_url: &str) -> Result<Option<ProcessingJob>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM processing_jobs
+            WHERE source_url = ?
+              AND (status IN ('pending', 'processing')
+                   OR (status = 'completed' AND result_document_id IN (SELECT id FROM documents)))
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(source_url)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        if let Some(row) = row {
+            Ok(Some(self.row_to_processing_job(row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
     /// Get next pending job for processing
     pub async fn get_next_pending_job(&self) -> Result<Option<ProcessingJob>, sqlx::Error> {
         let row = sqlx::query("SELECT * FROM processing_jobs WHERE status = 'pending' ORDER BY created_at ASC LIMIT 1")
@@ -206,6 +250,27 @@ impl Database {
         }
     }
 
+    /// Put jobs left in 'processing' by a previous run (the app quit or crashed mid-job) back
+    /// in the queue; returns how many were requeued
+    pub async fn requeue_interrupted_jobs(&self) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE processing_jobs SET status = 'pending', progress = 0, started_at = NULL WHERE status = 'processing'")
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Put a job that was still processing at shutdown back in the queue, so it runs again on
+    /// the next start
+    pub async fn requeue_processing_job(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE processing_jobs SET status = 'pending', progress = 0, started_at = NULL WHERE id = ? AND status = 'processing'")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     /// Delete a processing job
     pub async fn delete_processing_job(&self, id: &str) -> Result<bool, sqlx::Error> {
         let result = sqlx::query("DELETE FROM processing_jobs WHERE id = ?")
@@ -304,6 +369,7 @@ impl Database {
                 .unwrap_or_else(|_| Utc::now().into())
                 .with_timezone(&Utc)),
             metadata,
+            source_url: row.get("source_url"),
         })
     }
 } 
\ No newline at end of file
@@ -0,0 +1,197 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{CreateExamPaperRequest, ExamPaper, ExamQuestion, QuestionBankTopic}};
+
+const EXAM_QUESTION_COLUMNS: &str = "q.*, p.year, p.title AS paper_title";
+
+impl Database {
+    // === QUESTION BANK ===
+
+    pub async fn create_exam_paper(&self, req: CreateExamPaperRequest) -> Result<ExamPaper, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO exam_papers (id, title, year, category_id, file_path, created_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&id)
+        .bind(&req.title)
+        .bind(req.year)
+        .bind(&req.category_id)
+        .bind(&req.file_path)
+        .bind(now.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        for (position, question) in req.questions.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO exam_questions (id, paper_id, position, number, text, marks, topic) VALUES (?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&id)
+            .bind(position as i32)
+            .bind(&question.number)
+            .bind(&question.text)
+            .bind(question.marks)
+            .bind(question.topic.as_deref().map(str::trim).filter(|topic| !topic.is_empty()))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(ExamPaper {
+            id,
+            title: req.title,
+            year: req.year,
+            category_id: req.category_id,
+            file_path: req.file_path,
+            question_count: req.questions.len() as i64,
+            created_at: now,
+        })
+    }
+
+    /// Papers in a category (or all), newest exam first
+    pub async fn get_exam_papers(&self, category_id: Option<&str>) -> Result<Vec<ExamPaper>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT p.*, (SELECT COUNT(*) FROM exam_questions q WHERE q.paper_id = p.id) AS question_count
+            FROM exam_papers p
+            WHERE ? IS NULL OR p.category_id = ?
+            ORDER BY p.year IS NULL, p.year DESC, p.created_at DESC
+            "#
+        )
+        .bind(category_id)
+        .bind(category_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| ExamPaper {
+            id: row.get("id"),
+            title: row.get("title"),
+            year: row.get("year"),
+            category_id: row.get("category_id"),
+            file_path: row.get("file_path"),
+            question_count: row.get("question_count"),
+            created_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now()),
+        }).collect())
+    }
+
+    pub async fn get_exam_questions(&self, paper_id: &str) -> Result<Vec<ExamQuestion>, sqlx::Error> {
+        let sql = format!(
+            "SELECT {} FROM exam_questions q JOIN exam_papers p ON p.id = q.paper_id WHERE q.paper_id = ? ORDER BY q.position",
+            EXAM_QUESTION_COLUMNS
+        );
+        let rows = sqlx::query(&sql)
+            .bind(paper_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_exam_question(row)).collect())
+    }
+
+    pub async fn delete_exam_paper(&self, paper_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM exam_papers WHERE id = ?")
+            .bind(paper_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Correct the topic of one question
+    pub async fn set_exam_question_topic(&self, question_id: &str, topic: Option<&str>) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE exam_questions SET topic = ? WHERE id = ?")
+            .bind(topic.map(str::trim).filter(|topic| !topic.is_empty()))
+            .bind(question_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Topics of the questions in a category (or all), most asked first
+    pub async fn get_question_bank_topics(&self, category_id: Option<&str>) -> Result<Vec<QuestionBankTopic>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT MIN(q.topic) AS topic, COUNT(*) AS question_count,
+                GROUP_CONCAT(DISTINCT p.year) AS years
+            FROM exam_questions q
+            JOIN exam_papers p ON p.id = q.paper_id
+            WHERE q.topic IS NOT NULL AND (? IS NULL OR p.category_id = ?)
+            GROUP BY q.topic COLLATE NOCASE
+            ORDER BY question_count DESC, topic
+            "#
+        )
+        .bind(category_id)
+        .bind(category_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| {
+            let mut years: Vec<i32> = row.get::<Option<String>, _>("years")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|year| year.trim().parse().ok())
+                .collect();
+            years.sort_unstable_by(|a, b| b.cmp(a));
+            QuestionBankTopic {
+                topic: row.get("topic"),
+                question_count: row.get("question_count"),
+                years,
+            }
+        }).collect())
+    }
+
+    /// Up to `count` random questions on `topic` (case-insensitive; any topic when None),
+    /// optionally only from one category's papers
+    pub async fn get_practice_set(&self, topic: Option<&str>, count: i32, category_id: Option<&str>) -> Result<Vec<ExamQuestion>, sqlx::Error> {
+        let sql = format!(
+            r#"
+            SELECT {} FROM exam_questions q
+            JOIN exam_papers p ON p.id = q.paper_id
+            WHERE (? IS NULL OR q.topic = ? COLLATE NOCASE) AND (? IS NULL OR p.category_id = ?)
+            ORDER BY RANDOM()
+            LIMIT ?
+            "#,
+            EXAM_QUESTION_COLUMNS
+        );
+        let topic = topic.map(str::trim).filter(|topic| !topic.is_empty());
+        let rows = sqlx::query(&sql)
+            .bind(topic)
+            .bind(topic)
+            .bind(category_id)
+            .bind(category_id)
+            .bind(count.max(0))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_exam_question(row)).collect())
+    }
+
+    /// Topics already in the bank for a category, for naming new questions consistently
+    pub async fn get_exam_topic_names(&self, category_id: Option<&str>) -> Result<Vec<String>, sqlx::Error> {
+        Ok(self.get_question_bank_topics(category_id).await?
+            .into_iter()
+            .map(|topic| topic.topic)
+            .collect())
+    }
+
+    fn row_to_exam_question(&self, row: sqlx::sqlite::SqliteRow) -> ExamQuestion {
+        ExamQuestion {
+            id: row.get("id"),
+            paper_id: row.get("paper_id"),
+            position: row.get("position"),
+            number: row.get("number"),
+            text: row.get("text"),
+            marks: row.get("marks"),
+            topic: row.get("topic"),
+            year: row.get("year"),
+            paper_title: row.get("paper_title"),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use super::{Database, types::QuickSearchHit};
+
+// Characters of context on each side of a content match
+pub(super) const SNIPPET_CONTEXT_CHARS: i64 = 60;
+
+impl Database {
+    // === QUICK SEARCH ===
+
+    /// Documents whose title or one of whose tags starts with `prefix`, or whose title has a
+    /// word starting with it. Title starts rank first, then recently opened documents. Content
+    /// isn't read, so this stays fast on large libraries.
+    pub async fn quick_search_titles_and_tags(&self, prefix: &str, limit: i64) -> Result<Vec<QuickSearchHit>, sqlx::Error> {
+        let starts_with = format!("{}%", prefix);
+        let word_starts_with = format!("% {}%", prefix);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, title, doc_type, category_id, tags, updated_at,
+                   CASE
+                       WHEN title LIKE ? COLLATE NOCASE THEN 0
+                       WHEN title LIKE ? COLLATE NOCASE THEN 1
+                       ELSE 2
+                   END AS rank
+            FROM documents
+            WHERE title LIKE ? COLLATE NOCASE
+               OR title LIKE ? COLLATE NOCASE
+               OR EXISTS (SELECT 1 FROM json_each(documents.tags) AS tag WHERE tag.value LIKE ? COLLATE NOCASE)
+            ORDER BY rank, COALESCE(last_opened_at, updated_at) DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&starts_with)
+        .bind(&word_starts_with)
+        .bind(&starts_with)
+        .bind(&word_starts_with)
+        .bind(&starts_with)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter()
+            .map(|row| {
+                let match_kind = if row.get::<i64, _>("rank") < 2 { "title" } else { "tag" };
+                row_to_hit(&row, match_kind, None)
+            })
+            .collect())
+    }
+
+    /// Content hits for `query` from the last time it was searched, if nothing changed since
+    pub fn cached_content_matches(&self, query: &str) -> Option<Vec<QuickSearchHit>> {
+        self.cache.content_matches(&query.to_lowercase())
+    }
+
+    /// Documents whose content contains `query`, with the text around the first match, newest
+    /// first. Content is matched through the document_text index, which covers compressed
+    /// documents without decompressing them. Results are cached for `cached_content_matches`.
+    pub async fn search_content_matches(&self, query: &str, limit: i64) -> Result<Vec<QuickSearchHit>, sqlx::Error> {
+        let key = query.to_lowercase();
+        let generation = self.cache.generation();
+
+        let rows = sqlx::query(
+            r#"
+            SELECT d.id, d.title, d.doc_type, d.category_id, d.tags, d.updated_at,
+                   substr(t.content, max(1, instr(lower(t.content), ?) - ?), ? + length(?) + ?) AS snippet
+            FROM document_text t
+            JOIN documents d ON d.id = t.document_id
+            WHERE t.id IN (SELECT rowid FROM document_text_fts WHERE content LIKE ?)
+            ORDER BY d.updated_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&key)
+        .bind(SNIPPET_CONTEXT_CHARS)
+        .bind(SNIPPET_CONTEXT_CHARS)
+        .bind(&key)
+        .bind(SNIPPET_CONTEXT_CHARS)
+        .bind(format!("%{}%", query))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let hits: Vec<QuickSearchHit> = rows.into_iter()
+            .map(|row| {
+                let snippet = row.get::<Option<String>, _>("snippet")
+                    .map(|snippet| snippet.split_whitespace().collect::<Vec<_>>().join(" "));
+                row_to_hit(&row, "content", snippet)
+            })
+            .collect();
+
+        self.cache.put_content_matches(generation, &key, &hits);
+        Ok(hits)
+    }
+}
+
+pub(super) fn row_to_hit(row: &sqlx::sqlite::SqliteRow, match_kind: &str, snippet: Option<String>) -> QuickSearchHit {
+    QuickSearchHit {
+        document_id: row.get("id"),
+        title: row.get("title"),
+        doc_type: row.get("doc_type"),
+        category_id: row.get("category_id"),
+        tags: serde_json::from_str(&row.get::<String, _>("tags")).unwrap_or_default(),
+        match_kind: match_kind.to_string(),
+        snippet,
+        updated_at: DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now()),
+    }
+}
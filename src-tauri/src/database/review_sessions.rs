@@ -0,0 +1,178 @@
+use sqlx::Row;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use super::{Database, types::{PersistedReviewSession, ReviewCardResult, ResumedReviewSession, StartReviewSessionRequest}};
+
+// An untouched session can be resumed for this long after its last answer
+const REVIEW_SESSION_EXPIRY_HOURS: i64 = 24;
+// Finished, abandoned and expired sessions are kept this long before being deleted
+const REVIEW_SESSION_RETENTION_DAYS: i64 = 30;
+
+impl Database {
+    // === PERSISTED REVIEW SESSIONS ===
+
+    /// Save a freshly drawn review session. Only one session is resumable at a time, so any
+    /// session still active is marked abandoned.
+    pub async fn start_review_session_state(&self, req: StartReviewSessionRequest) -> Result<PersistedReviewSession, sqlx::Error> {
+        let now = Utc::now();
+        let session = PersistedReviewSession {
+            id: Uuid::new_v4().to_string(),
+            study_session_id: req.study_session_id,
+            mix_strategy: req.mix_strategy.unwrap_or_else(|| "mixed".to_string()),
+            card_ids: req.card_ids,
+            results: Vec::new(),
+            position: 0,
+            status: "active".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at: now + Duration::hours(REVIEW_SESSION_EXPIRY_HOURS),
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE review_session_states SET status = 'abandoned', updated_at = ? WHERE status = 'active'")
+            .bind(now.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO review_session_states (id, study_session_id, mix_strategy, card_ids, results, status, created_at, updated_at, expires_at)
+            VALUES (?, ?, ?, ?, '[]', 'active', ?, ?, ?)
+            "#,
+        )
+        .bind(&session.id)
+        .bind(&session.study_session_id)
+        .bind(&session.mix_strategy)
+        .bind(serde_json::to_string(&session.card_ids).unwrap_or_else(|_| "[]".to_string()))
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .bind(session.expires_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(session)
+    }
+
+    /// Append an answered card to an active session (called inside record_flashcard_review's
+    /// transaction). The session completes once every drawn card has a result.
+    pub(crate) async fn advance_review_session_state(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        id: &str,
+        result: ReviewCardResult,
+    ) -> Result<(), sqlx::Error> {
+        let row = sqlx::query("SELECT card_ids, results FROM review_session_states WHERE id = ? AND status = 'active'")
+            .bind(id)
+            .fetch_optional(&mut **tx)
+            .await?;
+        let Some(row) = row else {
+            return Ok(());
+        };
+
+        let card_ids: Vec<String> = serde_json::from_str(&row.get::<String, _>("card_ids")).unwrap_or_default();
+        let mut results: Vec<ReviewCardResult> = serde_json::from_str(&row.get::<String, _>("results")).unwrap_or_default();
+        results.push(result);
+
+        let now = Utc::now();
+        let status = if results.len() >= card_ids.len() { "completed" } else { "active" };
+
+        sqlx::query("UPDATE review_session_states SET results = ?, status = ?, updated_at = ?, expires_at = ? WHERE id = ?")
+            .bind(serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string()))
+            .bind(status)
+            .bind(now.to_rfc3339())
+            .bind((now + Duration::hours(REVIEW_SESSION_EXPIRY_HOURS)).to_rfc3339())
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The session to resume, if any, with the cards it has left. Expires stale sessions and
+    /// clears out old finished ones first.
+    pub async fn get_resumable_review_session(&self) -> Result<Option<ResumedReviewSession>, sqlx::Error> {
+        let now = Utc::now();
+
+        sqlx::query("UPDATE review_session_states SET status = 'expired' WHERE status = 'active' AND expires_at < ?")
+            .bind(now.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DELETE FROM review_session_states WHERE status != 'active' AND updated_at < ?")
+            .bind((now - Duration::days(REVIEW_SESSION_RETENTION_DAYS)).to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
+        let row = sqlx::query("SELECT * FROM review_session_states WHERE status = 'active' ORDER BY updated_at DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+        let session = self.row_to_review_session_state(row);
+
+        let mut remaining_cards = Vec::new();
+        for card_id in session.card_ids.iter().skip(session.position as usize) {
+            if let Some(card) = self.get_flashcard(card_id).await? {
+                remaining_cards.push(card);
+            }
+        }
+
+        Ok(Some(ResumedReviewSession { session, remaining_cards }))
+    }
+
+    pub async fn get_review_session_state(&self, id: &str) -> Result<Option<PersistedReviewSession>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM review_session_states WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_review_session_state(row)))
+    }
+
+    /// Whether a review session is being answered: still active and answered within the last
+    /// `idle_minutes`
+    pub async fn has_review_in_progress(&self, idle_minutes: i64) -> Result<bool, sqlx::Error> {
+        let since = Utc::now() - Duration::minutes(idle_minutes);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM review_session_states WHERE status = 'active' AND updated_at >= ?")
+            .bind(since.to_rfc3339())
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    /// End a session early ('abandoned') or mark it done ('completed')
+    pub async fn finish_review_session_state(&self, id: &str, status: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE review_session_states SET status = ?, updated_at = ? WHERE id = ? AND status = 'active'")
+            .bind(status)
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn row_to_review_session_state(&self, row: sqlx::sqlite::SqliteRow) -> PersistedReviewSession {
+        let parse = |value: String| DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        let card_ids: Vec<String> = serde_json::from_str(&row.get::<String, _>("card_ids")).unwrap_or_default();
+        let results: Vec<ReviewCardResult> = serde_json::from_str(&row.get::<String, _>("results")).unwrap_or_default();
+
+        PersistedReviewSession {
+            id: row.get("id"),
+            study_session_id: row.get("study_session_id"),
+            mix_strategy: row.get("mix_strategy"),
+            position: results.len() as i32,
+            card_ids,
+            results,
+            status: row.get("status"),
+            created_at: parse(row.get("created_at")),
+            updated_at: parse(row.get("updated_at")),
+            expires_at: parse(row.get("expires_at")),
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{CreateEssayFeedbackRequest, CriterionFeedback, EssayFeedback, Rubric, RubricCriterion, SaveRubricRequest}};
+
+// (id, name, description, [(criterion, description, max points)])
+const BUILTIN_RUBRICS: &[(&str, &str, &str, &[(&str, &str, f64)])] = &[
+    (
+        "argumentative_essay",
+        "Argumentative Essay",
+        "Thesis, evidence, organization and style, each out of 4",
+        &[
+            ("Thesis", "States a clear, arguable position and holds to it throughout", 4.0),
+            ("Evidence", "Supports claims with relevant, well-explained evidence and addresses counterarguments", 4.0),
+            ("Organization", "Paragraphs follow a logical order with clear topic sentences and transitions", 4.0),
+            ("Style and mechanics", "Precise wording, varied sentences, and correct grammar, spelling and citations", 4.0),
+        ],
+    ),
+];
+
+impl Database {
+    // === RUBRICS ===
+
+    /// Insert the built-in rubrics that are missing; edits to existing ones are kept
+    pub(crate) async fn seed_builtin_rubrics(&self) -> Result<(), sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        for (id, name, description, criteria) in BUILTIN_RUBRICS {
+            let criteria: Vec<RubricCriterion> = criteria.iter()
+                .map(|(name, description, max_points)| RubricCriterion {
+                    name: name.to_string(),
+                    description: description.to_string(),
+                    max_points: *max_points,
+                    levels: Vec::new(),
+                })
+                .collect();
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO rubrics (id, name, description, criteria, is_builtin, created_at, updated_at)
+                VALUES (?, ?, ?, ?, TRUE, ?, ?)
+                "#,
+            )
+            .bind(id)
+            .bind(name)
+            .bind(description)
+            .bind(serde_json::to_string(&criteria).unwrap_or_else(|_| "[]".to_string()))
+            .bind(&now)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_rubrics(&self) -> Result<Vec<Rubric>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM rubrics ORDER BY is_builtin DESC, name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_rubric(row)).collect())
+    }
+
+    pub async fn get_rubric(&self, id: &str) -> Result<Option<Rubric>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM rubrics WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_rubric(row)))
+    }
+
+    /// Create a rubric, or update the one with `req.id`
+    pub async fn save_rubric(&self, req: SaveRubricRequest) -> Result<Rubric, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        let criteria = serde_json::to_string(&req.criteria).unwrap_or_else(|_| "[]".to_string());
+
+        let id = match req.id {
+            Some(id) => {
+                let result = sqlx::query(
+                    "UPDATE rubrics SET name = ?, description = ?, criteria = ?, updated_at = ? WHERE id = ?"
+                )
+                .bind(&req.name)
+                .bind(&req.description)
+                .bind(&criteria)
+                .bind(&now)
+                .bind(&id)
+                .execute(&self.pool)
+                .await?;
+                if result.rows_affected() == 0 {
+                    return Err(sqlx::Error::RowNotFound);
+                }
+                id
+            }
+            None => {
+                let id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    r#"
+                    INSERT INTO rubrics (id, name, description, criteria, is_builtin, created_at, updated_at)
+                    VALUES (?, ?, ?, ?, FALSE, ?, ?)
+                    "#,
+                )
+                .bind(&id)
+                .bind(&req.name)
+                .bind(&req.description)
+                .bind(&criteria)
+                .bind(&now)
+                .bind(&now)
+                .execute(&self.pool)
+                .await?;
+                id
+            }
+        };
+
+        self.get_rubric(&id).await?.ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Delete a user rubric; built-in rubrics are left alone. Feedback given with it is kept.
+    pub async fn delete_rubric(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM rubrics WHERE id = ? AND is_builtin = FALSE")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // === ESSAY FEEDBACK ===
+
+    pub async fn create_essay_feedback(&self, req: CreateEssayFeedbackRequest) -> Result<EssayFeedback, sqlx::Error> {
+        let feedback = EssayFeedback {
+            id: Uuid::new_v4().to_string(),
+            document_id: req.document_id,
+            rubric_id: req.rubric_id,
+            rubric_name: req.rubric_name,
+            word_count: req.essay_text.split_whitespace().count() as i64,
+            total_score: req.criteria.iter().map(|criterion| criterion.score).sum(),
+            max_score: req.criteria.iter().map(|criterion| criterion.max_points).sum(),
+            criteria: req.criteria,
+            summary: req.summary,
+            strengths: req.strengths,
+            improvements: req.improvements,
+            model: req.model,
+            created_at: Utc::now(),
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO essay_feedback (
+                id, document_id, rubric_id, rubric_name, essay_text, word_count, total_score, max_score,
+                criteria, summary, strengths, improvements, model, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&feedback.id)
+        .bind(&feedback.document_id)
+        .bind(&feedback.rubric_id)
+        .bind(&feedback.rubric_name)
+        .bind(&req.essay_text)
+        .bind(feedback.word_count)
+        .bind(feedback.total_score)
+        .bind(feedback.max_score)
+        .bind(serde_json::to_string(&feedback.criteria).unwrap_or_else(|_| "[]".to_string()))
+        .bind(&feedback.summary)
+        .bind(serde_json::to_string(&feedback.strengths).unwrap_or_else(|_| "[]".to_string()))
+        .bind(serde_json::to_string(&feedback.improvements).unwrap_or_else(|_| "[]".to_string()))
+        .bind(&feedback.model)
+        .bind(feedback.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(feedback)
+    }
+
+    /// Feedback on a document, oldest first, to follow the scores across revisions
+    pub async fn get_essay_feedback(&self, document_id: &str) -> Result<Vec<EssayFeedback>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM essay_feedback WHERE document_id = ? ORDER BY created_at")
+            .bind(document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_essay_feedback(row)).collect())
+    }
+
+    /// The essay text a piece of feedback was given on
+    pub async fn get_essay_feedback_text(&self, feedback_id: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT essay_text FROM essay_feedback WHERE id = ?")
+            .bind(feedback_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    pub async fn delete_essay_feedback(&self, feedback_id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM essay_feedback WHERE id = ?")
+            .bind(feedback_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    fn row_to_rubric(&self, row: sqlx::sqlite::SqliteRow) -> Rubric {
+        Rubric {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            criteria: serde_json::from_str(&row.get::<String, _>("criteria")).unwrap_or_default(),
+            is_builtin: row.get("is_builtin"),
+            created_at: parse_timestamp(row.get("created_at")),
+            updated_at: parse_timestamp(row.get("updated_at")),
+        }
+    }
+
+    fn row_to_essay_feedback(&self, row: sqlx::sqlite::SqliteRow) -> EssayFeedback {
+        let criteria: Vec<CriterionFeedback> = serde_json::from_str(&row.get::<String, _>("criteria")).unwrap_or_default();
+        EssayFeedback {
+            id: row.get("id"),
+            document_id: row.get("document_id"),
+            rubric_id: row.get("rubric_id"),
+            rubric_name: row.get("rubric_name"),
+            word_count: row.get("word_count"),
+            total_score: row.get("total_score"),
+            max_score: row.get("max_score"),
+            criteria,
+            summary: row.get("summary"),
+            strengths: serde_json::from_str(&row.get::<String, _>("strengths")).unwrap_or_default(),
+            improvements: serde_json::from_str(&row.get::<String, _>("improvements")).unwrap_or_default(),
+            model: row.get("model"),
+            created_at: parse_timestamp(row.get("created_at")),
+        }
+    }
+}
+
+fn parse_timestamp(value: String) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
@@ -0,0 +1,278 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::math;
+use super::{Database, compression::{store_content, write_document_text}, types::{Document, DocumentSection, SplitDocumentRequest, SplitDocumentResult}};
+
+// A section boundary before the title-from-heading step: (title, start, end) in chars
+struct SectionBounds {
+    title: Option<String>,
+    start: usize,
+    end: usize,
+}
+
+/// Work out where to cut `content`. Explicit split points win; otherwise split before every
+/// markdown heading at `heading_level` or higher. Empty sections are dropped.
+fn compute_section_bounds(content: &str, request: &SplitDocumentRequest) -> Vec<SectionBounds> {
+    let total_chars = content.chars().count();
+    let mut starts: Vec<(usize, Option<String>)> = vec![(0, None)];
+
+    if let Some(points) = &request.split_points {
+        starts.extend(points.iter().filter(|p| **p > 0 && **p < total_chars).map(|p| (*p, None)));
+    } else if request.by_headings {
+        let max_level = request.heading_level.unwrap_or(1).max(1);
+        let mut char_offset = 0;
+        let mut in_code_block = false;
+
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            let is_heading = !in_code_block
+                && level >= 1
+                && level <= max_level
+                && trimmed[level..].starts_with(' ');
+            if is_heading {
+                let title = trimmed[level..].trim().to_string();
+                if char_offset == 0 {
+                    starts[0].1 = Some(title);
+                } else {
+                    starts.push((char_offset, Some(title)));
+                }
+            }
+
+            char_offset += line.chars().count();
+        }
+    }
+
+    starts.sort_by_key(|(start, _)| *start);
+    starts.dedup_by_key(|(start, _)| *start);
+
+    let mut sections = Vec::new();
+    for (i, (start, title)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map(|(next, _)| *next).unwrap_or(total_chars);
+        let text: String = content.chars().skip(*start).take(end - start).collect();
+        if !text.trim().is_empty() {
+            sections.push(SectionBounds { title: title.clone(), start: *start, end });
+        }
+    }
+
+    sections
+}
+
+// Pages are only recoverable when extraction kept form-feed page breaks
+pub(crate) fn page_at(content: &str, char_offset: usize) -> Option<i32> {
+    if !content.contains('\u{c}') {
+        return None;
+    }
+    Some(content.chars().take(char_offset).filter(|c| *c == '\u{c}').count() as i32 + 1)
+}
+
+fn char_slice(content: &str, start: usize, end: usize) -> String {
+    content.chars().skip(start).take(end.saturating_sub(start)).collect()
+}
+
+impl Database {
+    /// Split a document into child documents, one per section. The parent is kept; children
+    /// link back to it and record their character (and page, when known) range within it.
+    /// Flashcards whose source text falls in a section, and highlight sources with offsets,
+    /// are moved to the matching child. Returns None if the document doesn't exist or the
+    /// split would leave fewer than two sections.
+    pub async fn split_document(&self, document_id: &str, request: &SplitDocumentRequest) -> Result<Option<SplitDocumentResult>, sqlx::Error> {
+        let parent = match self.get_document(document_id).await? {
+            Some(parent) => parent,
+            None => return Ok(None),
+        };
+
+        let bounds = compute_section_bounds(&parent.content, request);
+        if bounds.len() < 2 {
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let tags_json = serde_json::to_string(&parent.tags).unwrap_or_else(|_| "[]".to_string());
+        let mut tx = self.pool.begin().await?;
+        let mut sections = Vec::new();
+
+        for (index, bound) in bounds.iter().enumerate() {
+            let id = Uuid::new_v4().to_string();
+            let content = char_slice(&parent.content, bound.start, bound.end);
+            let title = bound.title.clone()
+                .unwrap_or_else(|| format!("{} (part {})", parent.title, index + 1));
+            let stored = store_content(&content);
+
+            sqlx::query(
+                r#"
+                INSERT INTO documents (id, title, content, content_zstd, content_length, content_hash, file_path, doc_type, tags, created_at, updated_at, status, category_id, parent_document_id, language, math_terms)
+                VALUES (?, ?, ?, ?, ?, ?, NULL, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&id)
+            .bind(&title)
+            .bind(&stored.text)
+            .bind(&stored.compressed)
+            .bind(stored.length)
+            .bind(Self::calculate_content_hash(&content))
+            .bind(&parent.doc_type)
+            .bind(&tags_json)
+            .bind(now.to_rfc3339())
+            .bind(now.to_rfc3339())
+            .bind(&parent.status)
+            .bind(&parent.category_id)
+            .bind(&parent.id)
+            .bind(&parent.language) // Same language as the document they were split from
+            .bind(math::index_terms(&content))
+            .execute(&mut *tx)
+            .await?;
+            write_document_text(&mut *tx, &id, &content).await?;
+
+            let section = DocumentSection {
+                document_id: id,
+                parent_document_id: parent.id.clone(),
+                section_index: index as i32,
+                title,
+                start_offset: bound.start as i64,
+                end_offset: bound.end as i64,
+                page_start: page_at(&parent.content, bound.start),
+                page_end: page_at(&parent.content, bound.end.saturating_sub(1)),
+                created_at: now,
+            };
+
+            sqlx::query(
+                r#"
+                INSERT INTO document_sections (document_id, parent_document_id, section_index, title, start_offset, end_offset, page_start, page_end, created_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&section.document_id)
+            .bind(&section.parent_document_id)
+            .bind(section.section_index)
+            .bind(&section.title)
+            .bind(section.start_offset)
+            .bind(section.end_offset)
+            .bind(section.page_start)
+            .bind(section.page_end)
+            .bind(now.to_rfc3339())
+            .execute(&mut *tx)
+            .await?;
+
+            sections.push(section);
+        }
+
+        let section_for = |offset: usize| sections.iter()
+            .find(|s| offset >= s.start_offset as usize && offset < s.end_offset as usize);
+
+        // Flashcards: locate their source text in the parent
+        let flashcard_rows = sqlx::query("SELECT id, source_text FROM flashcards WHERE source_document_id = ?")
+            .bind(&parent.id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+        let mut flashcards_moved = 0;
+        for row in flashcard_rows {
+            let source_text: Option<String> = row.get("source_text");
+            let offset = source_text
+                .filter(|t| !t.trim().is_empty())
+                .and_then(|t| parent.content.find(t.trim()))
+                .map(|byte_offset| parent.content[..byte_offset].chars().count());
+
+            if let Some(section) = offset.and_then(section_for) {
+                let flashcard_id: String = row.get("id");
+                sqlx::query("UPDATE flashcards SET source_document_id = ? WHERE id = ?")
+                    .bind(&section.document_id)
+                    .bind(&flashcard_id)
+                    .execute(&mut *tx)
+                    .await?;
+                flashcards_moved += 1;
+            }
+        }
+
+        // Highlights: re-base their offsets onto the child
+        let highlight_rows = sqlx::query(
+            "SELECT id, start_offset, end_offset FROM message_sources WHERE document_id = ? AND source_type = 'highlight' AND start_offset IS NOT NULL"
+        )
+        .bind(&parent.id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut highlights_moved = 0;
+        for row in highlight_rows {
+            let start: i64 = row.get("start_offset");
+            let end: Option<i64> = row.get("end_offset");
+
+            if let Some(section) = section_for(start.max(0) as usize) {
+                let source_id: String = row.get("id");
+                sqlx::query("UPDATE message_sources SET document_id = ?, start_offset = ?, end_offset = ? WHERE id = ?")
+                    .bind(&section.document_id)
+                    .bind(start - section.start_offset)
+                    .bind(end.map(|e| e.min(section.end_offset) - section.start_offset))
+                    .bind(&source_id)
+                    .execute(&mut *tx)
+                    .await?;
+                highlights_moved += 1;
+            }
+        }
+
+        tx.commit().await?;
+        self.cache.invalidate();
+
+        let mut children = Vec::new();
+        for section in &sections {
+            if let Some(child) = self.get_document(&section.document_id).await? {
+                self.index_code_snippets(&child.id, &child.content).await?;
+                children.push(child);
+            }
+        }
+
+        Ok(Some(SplitDocumentResult {
+            parent,
+            children,
+            sections,
+            flashcards_moved,
+            highlights_moved,
+        }))
+    }
+
+    pub async fn get_document_sections(&self, parent_document_id: &str) -> Result<Vec<DocumentSection>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM document_sections WHERE parent_document_id = ? ORDER BY section_index ASC")
+            .bind(parent_document_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| {
+            let created_at: String = row.get("created_at");
+            DocumentSection {
+                document_id: row.get("document_id"),
+                parent_document_id: row.get("parent_document_id"),
+                section_index: row.get("section_index"),
+                title: row.get("title"),
+                start_offset: row.get("start_offset"),
+                end_offset: row.get("end_offset"),
+                page_start: row.get("page_start"),
+                page_end: row.get("page_end"),
+                created_at: DateTime::parse_from_rfc3339(&created_at)
+                    .unwrap_or_else(|_| Utc::now().into())
+                    .with_timezone(&Utc),
+            }
+        }).collect())
+    }
+
+    pub async fn get_child_documents(&self, parent_document_id: &str) -> Result<Vec<Document>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT d.* FROM documents d
+            LEFT JOIN document_sections s ON s.document_id = d.id
+            WHERE d.parent_document_id = ?
+            ORDER BY s.section_index ASC, d.created_at ASC
+            "#,
+        )
+        .bind(parent_document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_document(row)).collect())
+    }
+}
@@ -0,0 +1,189 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use super::{Database, types::{CreateSessionRequest, SaveSessionTemplateRequest, SessionTemplate, SessionType, StudySession}};
+
+// (id, name, description, type, target minutes)
+const BUILTIN_TEMPLATES: &[(&str, &str, &str, SessionType, i64)] = &[
+    ("reading", "Reading", "Work through documents with notes and highlights", SessionType::Reading, 45),
+    ("review", "Review", "Flashcard review of the attached decks", SessionType::Review, 20),
+    ("ai_tutoring", "AI Tutoring", "Talk through the attached documents with the assistant", SessionType::AiTutoring, 30),
+    ("mixed", "Mixed", "Reading, chat and review in one session", SessionType::Mixed, 60),
+];
+
+impl Database {
+    // === SESSION TEMPLATES ===
+
+    /// Insert the built-in templates that are missing; edits to existing ones are kept
+    pub(crate) async fn seed_builtin_session_templates(&self) -> Result<(), sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        for (id, name, description, session_type, minutes) in BUILTIN_TEMPLATES {
+            sqlx::query(
+                r#"
+                INSERT OR IGNORE INTO session_templates (id, name, description, session_type, target_duration_minutes, is_builtin, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, TRUE, ?, ?)
+                "#,
+            )
+            .bind(id)
+            .bind(name)
+            .bind(description)
+            .bind(session_type.as_str())
+            .bind(minutes)
+            .bind(&now)
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_session_templates(&self) -> Result<Vec<SessionTemplate>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM session_templates ORDER BY is_builtin DESC, name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_session_template(row)).collect())
+    }
+
+    pub async fn get_session_template(&self, id: &str) -> Result<Option<SessionTemplate>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM session_templates WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_session_template(row)))
+    }
+
+    /// Create a template, or update the one with `req.id`
+    pub async fn save_session_template(&self, req: SaveSessionTemplateRequest) -> Result<SessionTemplate, sqlx::Error> {
+        let id = write_session_template(&self.pool, &req).await?;
+        self.get_session_template(&id).await?.ok_or(sqlx::Error::RowNotFound)
+    }
+
+    /// Delete a user template; built-in templates are left alone
+    pub async fn delete_session_template(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM session_templates WHERE id = ? AND is_builtin = FALSE")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// End the active session and start one from a template. Attached documents and decks that
+    /// have since been deleted are dropped; the rest are recorded in the session metadata.
+    pub async fn start_session_from_template(&self, template_id: &str) -> Result<Option<StudySession>, sqlx::Error> {
+        let Some(template) = self.get_session_template(template_id).await? else {
+            return Ok(None);
+        };
+
+        let mut document_ids = Vec::new();
+        for id in &template.document_ids {
+            if sqlx::query("SELECT 1 FROM documents WHERE id = ?").bind(id).fetch_optional(&self.pool).await?.is_some() {
+                document_ids.push(id.clone());
+            }
+        }
+        let mut deck_ids = Vec::new();
+        for id in &template.deck_ids {
+            if sqlx::query("SELECT 1 FROM flashcard_decks WHERE id = ?").bind(id).fetch_optional(&self.pool).await?.is_some() {
+                deck_ids.push(id.clone());
+            }
+        }
+
+        if let Some(active) = self.get_active_session().await? {
+            self.end_session(&active.id).await?;
+        }
+
+        let session = self.create_session(CreateSessionRequest {
+            title: template.name.clone(),
+            session_type: Some(template.session_type),
+            metadata: Some(serde_json::json!({
+                "attached_document_ids": document_ids,
+                "attached_deck_ids": deck_ids,
+            })),
+            template_id: Some(template.id.clone()),
+            target_duration: Some(template.target_duration_minutes * 60),
+        }).await?;
+
+        println!("📋 Started '{}' session from template {}", session.title, template.id);
+        Ok(Some(session))
+    }
+
+    fn row_to_session_template(&self, row: sqlx::sqlite::SqliteRow) -> SessionTemplate {
+        let parse = |value: String| DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        SessionTemplate {
+            id: row.get("id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            session_type: SessionType::parse(&row.get::<String, _>("session_type")),
+            target_duration_minutes: row.get("target_duration_minutes"),
+            document_ids: serde_json::from_str(&row.get::<String, _>("document_ids")).unwrap_or_default(),
+            deck_ids: serde_json::from_str(&row.get::<String, _>("deck_ids")).unwrap_or_default(),
+            is_builtin: row.get("is_builtin"),
+            created_at: parse(row.get("created_at")),
+            updated_at: parse(row.get("updated_at")),
+        }
+    }
+}
+
+/// Insert or update a template row, returning its id
+pub(super) async fn write_session_template<'e, E>(executor: E, req: &SaveSessionTemplateRequest) -> Result<String, sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let now = Utc::now().to_rfc3339();
+    let document_ids = serde_json::to_string(&req.document_ids).unwrap_or_else(|_| "[]".to_string());
+    let deck_ids = serde_json::to_string(&req.deck_ids).unwrap_or_else(|_| "[]".to_string());
+
+    let id = match &req.id {
+        Some(id) => {
+            let result = sqlx::query(
+                r#"
+                UPDATE session_templates
+                SET name = ?, description = ?, session_type = ?, target_duration_minutes = ?, document_ids = ?, deck_ids = ?, updated_at = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(&req.name)
+            .bind(&req.description)
+            .bind(req.session_type.as_str())
+            .bind(req.target_duration_minutes)
+            .bind(&document_ids)
+            .bind(&deck_ids)
+            .bind(&now)
+            .bind(id)
+            .execute(executor)
+            .await?;
+            if result.rows_affected() == 0 {
+                return Err(sqlx::Error::RowNotFound);
+            }
+            id.clone()
+        }
+        None => {
+            let id = Uuid::new_v4().to_string();
+            sqlx::query(
+                r#"
+                INSERT INTO session_templates (id, name, description, session_type, target_duration_minutes, document_ids, deck_ids, is_builtin, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, FALSE, ?, ?)
+                "#,
+            )
+            .bind(&id)
+            .bind(&req.name)
+            .bind(&req.description)
+            .bind(req.session_type.as_str())
+            .bind(req.target_duration_minutes)
+            .bind(&document_ids)
+            .bind(&deck_ids)
+            .bind(&now)
+            .bind(&now)
+            .execute(executor)
+            .await?;
+            id
+        }
+    };
+
+    Ok(id)
+}
@@ -1,15 +1,30 @@
 use sqlx::Row;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, Utc};
 use uuid::Uuid;
 use std::collections::HashMap;
-use super::{Database, types::{StudySession, UserAction, CreateSessionRequest, CreateActionRequest, ActionStats}};
+use super::{Database, types::{StudySession, UserAction, CreateSessionRequest, CreateActionRequest, ActionStats, SessionTimeline, SessionTimelineEntry, SessionFocusAnalysis, SessionSegmentationSettings, SessionSegmentationResult}};
+
+// Gaps longer than this are treated as idle time rather than time spent on the previous entry
+const TIMELINE_IDLE_CAP_SECONDS: i64 = 30 * 60;
+
+// Gaps between actions longer than this count as idle time for focus scoring
+const FOCUS_IDLE_GAP_SECONDS: i64 = 5 * 60;
+
+/// Whether activity at `next` belongs to a new session after activity at `previous`
+fn is_session_boundary(previous: DateTime<Utc>, next: DateTime<Utc>, settings: &SessionSegmentationSettings) -> bool {
+    if (next - previous).num_minutes() >= settings.idle_gap_minutes {
+        return true;
+    }
+    settings.split_at_day_boundary
+        && previous.with_timezone(&Local).date_naive() != next.with_timezone(&Local).date_naive()
+}
 
 impl Database {
     // Create a new study session
     pub async fn create_session(&self, req: CreateSessionRequest) -> Result<StudySession, sqlx::Error> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
-        let session_type = req.session_type.unwrap_or_else(|| "mixed".to_string());
+        let session_type = req.session_type.unwrap_or_default();
         let metadata_json = req.metadata.as_ref().map(|m| serde_json::to_string(m).unwrap_or_else(|_| "{}".to_string()));
 
         let session = StudySession {
@@ -18,18 +33,21 @@ impl Database {
             start_time: now,
             end_time: None,
             is_active: true,
-            session_type: session_type.clone(),
+            session_type,
             total_duration: 0,
+            template_id: req.template_id.clone(),
+            target_duration: req.target_duration,
             documents_accessed: Vec::new(),
             categories_accessed: Vec::new(),
             conversation_ids: Vec::new(),
             metadata: req.metadata.clone(),
+            focus_score: None,
         };
 
         sqlx::query(
             r#"
-            INSERT INTO study_sessions (id, title, start_time, end_time, is_active, session_type, total_duration, documents_accessed, categories_accessed, conversation_ids, metadata)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO study_sessions (id, title, start_time, end_time, is_active, session_type, total_duration, documents_accessed, categories_accessed, conversation_ids, metadata, template_id, target_duration)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id)
@@ -37,12 +55,14 @@ impl Database {
         .bind(now.to_rfc3339())
         .bind(None::<String>)
         .bind(true)
-        .bind(&session_type)
+        .bind(session_type.as_str())
         .bind(0)
         .bind("[]")
         .bind("[]")
         .bind("[]")
         .bind(metadata_json)
+        .bind(&req.template_id)
+        .bind(req.target_duration)
         .execute(&self.pool)
         .await?;
 
@@ -64,12 +84,15 @@ impl Database {
 
     // End a study session
     pub async fn end_session(&self, session_id: &str) -> Result<bool, sqlx::Error> {
-        let now = Utc::now();
-        
+        self.end_session_at(session_id, Utc::now()).await
+    }
+
+    /// End a session at a given time, e.g. its last activity when it went idle
+    pub async fn end_session_at(&self, session_id: &str, end_time: DateTime<Utc>) -> Result<bool, sqlx::Error> {
         // Calculate total duration
         let session = self.get_session(session_id).await?;
         let total_duration = if let Some(session) = session {
-            (now - session.start_time).num_seconds()
+            (end_time - session.start_time).num_seconds().max(0)
         } else {
             0
         };
@@ -77,12 +100,19 @@ impl Database {
         let result = sqlx::query(
             "UPDATE study_sessions SET is_active = FALSE, end_time = ?, total_duration = ? WHERE id = ?"
         )
-        .bind(now.to_rfc3339())
+        .bind(end_time.to_rfc3339())
         .bind(total_duration)
         .bind(session_id)
         .execute(&self.pool)
         .await?;
 
+        // Score the finished session so focus trends are available without a separate pass
+        if result.rows_affected() > 0 {
+            if let Err(e) = self.analyze_session_focus(session_id).await {
+                eprintln!("⚠️ Failed to compute focus score for session {}: {}", session_id, e);
+            }
+        }
+
         Ok(result.rows_affected() > 0)
     }
 
@@ -119,6 +149,162 @@ impl Database {
         Ok(sessions)
     }
 
+    /// When the session last saw activity: its latest action, or its start if it has none
+    async fn last_session_activity(&self, session: &StudySession) -> Result<DateTime<Utc>, sqlx::Error> {
+        let latest: Option<String> = sqlx::query_scalar("SELECT MAX(timestamp) FROM user_actions WHERE session_id = ?")
+            .bind(&session.id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(latest
+            .and_then(|timestamp| DateTime::parse_from_rfc3339(&timestamp).ok())
+            .map(|timestamp| timestamp.with_timezone(&Utc))
+            .unwrap_or(session.start_time))
+    }
+
+    /// The session an action happening now should be recorded in. An active session that has
+    /// been idle too long (or was last used on another day) is ended at its last activity and a
+    /// new one is started, so always-on tracking still produces meaningful sessions.
+    pub async fn resolve_action_session(&self, requested_id: Option<&str>, settings: &SessionSegmentationSettings) -> Result<String, sqlx::Error> {
+        let now = Utc::now();
+
+        let requested = match requested_id {
+            Some(id) => self.get_session(id).await?,
+            None => None,
+        };
+        let current = match requested {
+            Some(session) if session.is_active => Some(session),
+            // Clients may still hold the id of a session that was ended by segmentation
+            _ => self.get_active_session().await?,
+        };
+
+        if let Some(session) = current {
+            let last_activity = self.last_session_activity(&session).await?;
+            if !is_session_boundary(last_activity, now, settings) {
+                return Ok(session.id);
+            }
+            println!("✂️ Ending idle session '{}' at its last activity", session.title);
+            self.end_session_at(&session.id, last_activity).await?;
+        }
+
+        let session = self.create_session(CreateSessionRequest {
+            title: "Study Session".to_string(),
+            session_type: None,
+            metadata: Some(serde_json::json!({ "auto_segmented": true })),
+            template_id: None,
+            target_duration: None,
+        }).await?;
+
+        Ok(session.id)
+    }
+
+    /// Split recorded sessions wherever their actions have an idle gap or cross a day boundary.
+    /// The original session keeps the first stretch; each later stretch becomes a new session.
+    pub async fn segment_sessions(&self, settings: &SessionSegmentationSettings) -> Result<SessionSegmentationResult, sqlx::Error> {
+        // Two queries up front rather than two per session
+        let session_rows = sqlx::query("SELECT * FROM study_sessions ORDER BY start_time")
+            .fetch_all(&self.pool)
+            .await?;
+        let action_rows = sqlx::query("SELECT * FROM user_actions WHERE session_id IS NOT NULL ORDER BY timestamp ASC")
+            .fetch_all(&self.pool)
+            .await?;
+        let mut actions_by_session: HashMap<String, Vec<UserAction>> = HashMap::new();
+        for row in action_rows {
+            let action = self.row_to_action(row)?;
+            actions_by_session.entry(action.session_id.clone()).or_default().push(action);
+        }
+        let mut result = SessionSegmentationResult::default();
+
+        for row in session_rows {
+            result.sessions_checked += 1;
+            let session = self.row_to_session(row)?;
+            let actions = actions_by_session.remove(&session.id).unwrap_or_default();
+
+            let mut segments: Vec<Vec<&UserAction>> = Vec::new();
+            let mut previous: Option<DateTime<Utc>> = None;
+            for action in &actions {
+                let boundary = match previous {
+                    Some(previous) => is_session_boundary(previous, action.timestamp, settings),
+                    None => true,
+                };
+                if boundary {
+                    segments.push(Vec::new());
+                }
+                if let Some(segment) = segments.last_mut() {
+                    segment.push(action);
+                }
+                previous = Some(action.timestamp);
+            }
+            if segments.len() < 2 {
+                continue;
+            }
+
+            let mut tx = self.pool.begin().await?;
+            let mut segment_session_ids = vec![session.id.clone()];
+            let last_index = segments.len() - 1;
+
+            for (index, segment) in segments.iter().enumerate() {
+                let (Some(first), Some(last)) = (segment.first(), segment.last()) else {
+                    continue;
+                };
+                // The last stretch of an active session is the one still in progress
+                let is_active = session.is_active && index == last_index;
+                let end_time = if is_active { None } else { Some(last.timestamp.to_rfc3339()) };
+
+                if index == 0 {
+                    sqlx::query("UPDATE study_sessions SET is_active = FALSE, end_time = ?, total_duration = ? WHERE id = ?")
+                        .bind(last.timestamp.to_rfc3339())
+                        .bind((last.timestamp - session.start_time).num_seconds().max(0))
+                        .bind(&session.id)
+                        .execute(&mut *tx)
+                        .await?;
+                    continue;
+                }
+
+                let id = Uuid::new_v4().to_string();
+                let metadata = serde_json::json!({ "auto_segmented": true, "split_from": session.id });
+                sqlx::query(
+                    r#"
+                    INSERT INTO study_sessions (id, title, start_time, end_time, is_active, session_type, total_duration, documents_accessed, categories_accessed, conversation_ids, metadata)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, '[]', '[]', '[]', ?)
+                    "#,
+                )
+                .bind(&id)
+                .bind(&session.title)
+                .bind(first.timestamp.to_rfc3339())
+                .bind(end_time)
+                .bind(is_active)
+                .bind(session.session_type.as_str())
+                .bind((last.timestamp - first.timestamp).num_seconds())
+                .bind(metadata.to_string())
+                .execute(&mut *tx)
+                .await?;
+
+                let placeholders = vec!["?"; segment.len()].join(", ");
+                let sql = format!("UPDATE user_actions SET session_id = ? WHERE id IN ({})", placeholders);
+                let mut query = sqlx::query(&sql).bind(&id);
+                for action in segment {
+                    query = query.bind(&action.id);
+                }
+                query.execute(&mut *tx).await?;
+                segment_session_ids.push(id);
+            }
+
+            tx.commit().await?;
+            println!("✂️ Split session '{}' into {} sessions", session.title, segment_session_ids.len());
+            result.sessions_split += 1;
+            result.sessions_created += segment_session_ids.len() as i64 - 1;
+
+            for id in &segment_session_ids {
+                if let Err(e) = self.analyze_session_focus(id).await {
+                    eprintln!("⚠️ Failed to compute focus score for session {}: {}", id, e);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
     // Record a user action
     pub async fn record_action(&self, req: CreateActionRequest) -> Result<UserAction, sqlx::Error> {
         let id = Uuid::new_v4().to_string();
@@ -275,4 +461,206 @@ impl Database {
             average_session_duration: avg_duration.unwrap_or(0.0),
         })
     }
-} 
\ No newline at end of file
+
+    // Reconstruct an ordered timeline of everything that happened in a session
+    pub async fn get_session_timeline(&self, session_id: &str) -> Result<Option<SessionTimeline>, sqlx::Error> {
+        let session = match self.get_session(session_id).await? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+
+        let actions = self.get_actions_by_session(session_id).await?;
+
+        let review_rows = sqlx::query("SELECT * FROM flashcard_reviews WHERE session_id = ? ORDER BY timestamp ASC")
+            .bind(session_id)
+            .fetch_all(&self.pool)
+            .await?;
+        let mut reviews = Vec::new();
+        for row in review_rows {
+            reviews.push(self.row_to_flashcard_review(row)?);
+        }
+
+        let mut entries: Vec<SessionTimelineEntry> = Vec::new();
+
+        for action in actions {
+            // Review rows carry the full SM-2 detail, so skip the duplicate action when both exist
+            if action.action_type == "flashcard_review" && !reviews.is_empty() {
+                continue;
+            }
+
+            let kind = if action.action_type.starts_with("document_") {
+                "document"
+            } else if action.action_type.starts_with("chat_") {
+                "conversation"
+            } else if action.action_type.starts_with("flashcard_") {
+                "flashcard"
+            } else {
+                "action"
+            };
+
+            let conversation_id = action.data.get("conversationId")
+                .or_else(|| action.data.get("conversation_id"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            entries.push(SessionTimelineEntry {
+                id: action.id,
+                kind: kind.to_string(),
+                action_type: action.action_type,
+                timestamp: action.timestamp,
+                duration: action.duration.unwrap_or(-1), // Derived from the next entry below
+                document_ids: action.document_ids.unwrap_or_default(),
+                document_title: None,
+                conversation_id,
+                data: action.data,
+            });
+        }
+
+        for review in reviews {
+            entries.push(SessionTimelineEntry {
+                id: review.id,
+                kind: "flashcard_review".to_string(),
+                action_type: "flashcard_review".to_string(),
+                timestamp: review.timestamp,
+                duration: review.time_spent as i64,
+                document_ids: Vec::new(),
+                document_title: None,
+                conversation_id: None,
+                data: serde_json::json!({
+                    "flashcard_id": review.flashcard_id,
+                    "response": review.response,
+                    "quality": review.quality,
+                    "confidence": review.confidence,
+                }),
+            });
+        }
+
+        entries.sort_by_key(|entry| entry.timestamp);
+
+        // Fill in missing durations from the gap to the next entry (or the end of the session)
+        let session_end = session.end_time.unwrap_or_else(Utc::now);
+        let next_timestamps: Vec<DateTime<Utc>> = entries.iter()
+            .skip(1)
+            .map(|entry| entry.timestamp)
+            .chain(std::iter::once(session_end))
+            .collect();
+        for (entry, next) in entries.iter_mut().zip(next_timestamps) {
+            if entry.duration < 0 {
+                entry.duration = (next - entry.timestamp).num_seconds().clamp(0, TIMELINE_IDLE_CAP_SECONDS);
+            }
+        }
+
+        // Resolve document titles once per document
+        let mut document_titles: HashMap<String, String> = HashMap::new();
+        let mut documents_opened: Vec<String> = Vec::new();
+        let mut conversation_ids: Vec<String> = session.conversation_ids.clone();
+        for entry in entries.iter_mut() {
+            for document_id in &entry.document_ids {
+                if !document_titles.contains_key(document_id) {
+                    if let Some(document) = self.get_document(document_id).await? {
+                        document_titles.insert(document_id.clone(), document.title);
+                    }
+                }
+                if entry.kind == "document" && !documents_opened.contains(document_id) {
+                    documents_opened.push(document_id.clone());
+                }
+            }
+            entry.document_title = entry.document_ids.first()
+                .and_then(|id| document_titles.get(id).cloned());
+
+            if let Some(conversation_id) = &entry.conversation_id {
+                if !conversation_ids.contains(conversation_id) {
+                    conversation_ids.push(conversation_id.clone());
+                }
+            }
+        }
+
+        let flashcards_reviewed = entries.iter().filter(|e| e.kind == "flashcard_review").count() as i64;
+        let active_duration = entries.iter().map(|e| e.duration).sum();
+        let total_duration = (session_end - session.start_time).num_seconds().max(0);
+
+        Ok(Some(SessionTimeline {
+            session,
+            entries,
+            total_duration,
+            active_duration,
+            documents_opened,
+            conversation_ids,
+            flashcards_reviewed,
+        }))
+    }
+
+    // Score how focused a session was from context switches and idle gaps, and store it on the session
+    pub async fn analyze_session_focus(&self, session_id: &str) -> Result<Option<SessionFocusAnalysis>, sqlx::Error> {
+        let session = match self.get_session(session_id).await? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+
+        let actions = self.get_actions_by_session(session_id).await?;
+        let session_end = session.end_time
+            .or_else(|| actions.last().map(|a| a.timestamp))
+            .unwrap_or_else(Utc::now);
+        let total_time = (session_end - session.start_time).num_seconds().max(0);
+
+        // A context is the document being worked on, falling back to the kind of activity
+        let context_of = |action: &UserAction| -> String {
+            action.document_ids.as_ref()
+                .and_then(|ids| ids.first().cloned())
+                .unwrap_or_else(|| action.action_type.split('_').next().unwrap_or_default().to_string())
+        };
+
+        let mut context_switches = 0i64;
+        let mut idle_gaps = 0i64;
+        let mut idle_time = 0i64;
+        let mut longest_idle_gap = 0i64;
+
+        let mut previous: Option<(&UserAction, String)> = None;
+        for action in &actions {
+            let context = context_of(action);
+            if let Some((prev_action, prev_context)) = &previous {
+                if *prev_context != context {
+                    context_switches += 1;
+                }
+                let gap = (action.timestamp - prev_action.timestamp).num_seconds();
+                if gap > FOCUS_IDLE_GAP_SECONDS {
+                    idle_gaps += 1;
+                    idle_time += gap;
+                    longest_idle_gap = longest_idle_gap.max(gap);
+                }
+            }
+            previous = Some((action, context));
+        }
+
+        let active_time = (total_time - idle_time).max(0);
+        let active_hours = (active_time as f64 / 3600.0).max(1.0 / 60.0);
+        let switches_per_hour = context_switches as f64 / active_hours;
+        let idle_ratio = if total_time > 0 { idle_time as f64 / total_time as f64 } else { 0.0 };
+
+        // Up to 50 points lost for frequent switching (30+/hour) and up to 50 for idling
+        let switch_penalty = (switches_per_hour / 30.0).min(1.0) * 50.0;
+        let idle_penalty = idle_ratio.min(1.0) * 50.0;
+        let focus_score = if actions.is_empty() {
+            0.0
+        } else {
+            ((100.0 - switch_penalty - idle_penalty) * 10.0).round() / 10.0
+        };
+
+        sqlx::query("UPDATE study_sessions SET focus_score = ? WHERE id = ?")
+            .bind(focus_score)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(Some(SessionFocusAnalysis {
+            session_id: session_id.to_string(),
+            focus_score,
+            context_switches,
+            switches_per_hour,
+            idle_gaps,
+            idle_time,
+            longest_idle_gap,
+            active_time,
+        }))
+    }
+}
@@ -0,0 +1,143 @@
+use sqlx::Row;
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+use crate::network::{ProxySettings, PROXY_PASSWORD_KEY_ID, PROXY_SETTINGS_KEY};
+use super::{
+    Database,
+    ai_profiles::{insert_ai_profile, update_ai_profile_row},
+    automation_scripts::write_automation_script,
+    session_templates::write_session_template,
+    types::SettingsBundleWrites,
+};
+
+impl Database {
+    // === APP SETTINGS ===
+
+    pub async fn get_setting(&self, key: &str) -> Result<Option<serde_json::Value>, sqlx::Error> {
+        let row = sqlx::query("SELECT value FROM app_settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.and_then(|row| {
+            let value: String = row.get("value");
+            serde_json::from_str(&value).ok()
+        }))
+    }
+
+    pub async fn set_setting(&self, key: &str, value: &serde_json::Value) -> Result<(), sqlx::Error> {
+        write_setting(&self.pool, key, value).await
+    }
+
+    /// Write everything a settings bundle import changes in one transaction, so a failure
+    /// part way leaves this machine's configuration as it was
+    pub async fn apply_settings_bundle(&self, writes: &SettingsBundleWrites) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        for (key, value) in &writes.settings {
+            write_setting(&mut *tx, key, value).await?;
+        }
+        for (id, profile) in &writes.ai_profiles {
+            match id {
+                Some(id) => {
+                    update_ai_profile_row(&mut *tx, id, profile).await?;
+                }
+                None => {
+                    insert_ai_profile(&mut *tx, &Uuid::new_v4().to_string(), profile).await?;
+                }
+            }
+        }
+        for template in &writes.session_templates {
+            write_session_template(&mut *tx, template).await?;
+        }
+        for (deck_id, new_card_order) in &writes.deck_orders {
+            sqlx::query("UPDATE flashcard_decks SET new_card_order = ?, updated_at = ? WHERE id = ?")
+                .bind(new_card_order.as_str())
+                .bind(Utc::now().to_rfc3339())
+                .bind(deck_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for script in &writes.automation_scripts {
+            write_automation_script(&mut *tx, script).await?;
+        }
+
+        tx.commit().await
+    }
+
+    /// Every setting whose key starts with `prefix`, e.g. all per-provider connection settings
+    pub async fn get_settings_with_prefix(&self, prefix: &str) -> Result<Vec<(String, serde_json::Value)>, sqlx::Error> {
+        let rows = sqlx::query("SELECT key, value FROM app_settings WHERE substr(key, 1, length(?)) = ? ORDER BY key")
+            .bind(prefix)
+            .bind(prefix)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter()
+            .filter_map(|row| {
+                let value: String = row.get("value");
+                serde_json::from_str(&value).ok().map(|value| (row.get("key"), value))
+            })
+            .collect())
+    }
+
+    /// Read a typed setting, falling back to its default when missing or unreadable
+    pub async fn get_typed_setting<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T, sqlx::Error> {
+        Ok(self.get_setting(key).await?
+            .and_then(|value| serde_json::from_value(value).ok())
+            .unwrap_or_default())
+    }
+
+    /// Proxy settings with the password decrypted from api_keys. A password left in the
+    /// settings row by older versions is moved there on first read.
+    pub async fn get_proxy_settings(&self) -> Result<ProxySettings, sqlx::Error> {
+        let mut settings: ProxySettings = self.get_typed_setting(PROXY_SETTINGS_KEY).await?;
+
+        if let Some(password) = settings.password.take().filter(|p| !p.is_empty()) {
+            self.store_api_key(PROXY_PASSWORD_KEY_ID, &password).await?;
+            self.set_typed_setting(PROXY_SETTINGS_KEY, &settings).await?;
+        }
+
+        settings.password = self.get_api_key(PROXY_PASSWORD_KEY_ID).await?;
+        settings.has_password = settings.password.is_some();
+        Ok(settings)
+    }
+
+    /// Save proxy settings, encrypting a new password and keeping the stored one when none is given
+    pub async fn save_proxy_settings(&self, settings: &ProxySettings) -> Result<ProxySettings, sqlx::Error> {
+        match settings.password.as_deref() {
+            Some("") => {
+                self.delete_api_key(PROXY_PASSWORD_KEY_ID).await?;
+            }
+            Some(password) => self.store_api_key(PROXY_PASSWORD_KEY_ID, password).await?,
+            None => {}
+        }
+        self.set_typed_setting(PROXY_SETTINGS_KEY, settings).await?;
+        self.get_proxy_settings().await
+    }
+
+    pub async fn set_typed_setting<T: Serialize>(&self, key: &str, value: &T) -> Result<(), sqlx::Error> {
+        let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+        self.set_setting(key, &value).await
+    }
+}
+
+pub(super) async fn write_setting<'e, E>(executor: E, key: &str, value: &serde_json::Value) -> Result<(), sqlx::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO app_settings (key, value, updated_at) VALUES (?, ?, ?)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(key)
+    .bind(value.to_string())
+    .bind(Utc::now().to_rfc3339())
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use sqlx::Row;
+use chrono::{DateTime, Utc};
+use super::{Database, types::DocumentTranslation};
+
+impl Database {
+    // === TRANSLATION CACHE ===
+
+    pub async fn get_cached_translation(&self, text_hash: &str, target_language: &str, model: &str) -> Result<Option<String>, sqlx::Error> {
+        let row = sqlx::query("SELECT translation FROM translation_cache WHERE text_hash = ? AND target_language = ? AND model = ?")
+            .bind(text_hash)
+            .bind(target_language)
+            .bind(model)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("translation")))
+    }
+
+    pub async fn cache_translation(&self, text_hash: &str, target_language: &str, model: &str, translation: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO translation_cache (text_hash, target_language, model, translation, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(text_hash, target_language, model) DO UPDATE SET translation = excluded.translation, created_at = excluded.created_at
+            "#,
+        )
+        .bind(text_hash)
+        .bind(target_language)
+        .bind(model)
+        .bind(translation)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    // === DOCUMENT TRANSLATIONS ===
+
+    pub async fn get_document_translation(&self, source_document_id: &str, target_language: &str) -> Result<Option<DocumentTranslation>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM document_translations WHERE source_document_id = ? AND target_language = ?")
+            .bind(source_document_id)
+            .bind(target_language)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| self.row_to_document_translation(row)))
+    }
+
+    /// Translations of a document, and the translation a document is of
+    pub async fn get_document_translations(&self, document_id: &str) -> Result<Vec<DocumentTranslation>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM document_translations WHERE source_document_id = ? OR translated_document_id = ? ORDER BY target_language"
+        )
+        .bind(document_id)
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| self.row_to_document_translation(row)).collect())
+    }
+
+    /// Link `translated_document_id` as the translation of a source document, replacing the
+    /// previous link for that language
+    pub async fn save_document_translation(
+        &self,
+        source_document_id: &str,
+        target_language: &str,
+        translated_document_id: &str,
+        source_content_hash: &str,
+        model: &str,
+    ) -> Result<DocumentTranslation, sqlx::Error> {
+        let now = Utc::now().to_rfc3339();
+        sqlx::query(
+            r#"
+            INSERT INTO document_translations (source_document_id, target_language, translated_document_id, source_content_hash, model, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(source_document_id, target_language) DO UPDATE SET
+                translated_document_id = excluded.translated_document_id,
+                source_content_hash = excluded.source_content_hash,
+                model = excluded.model,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(source_document_id)
+        .bind(target_language)
+        .bind(translated_document_id)
+        .bind(source_content_hash)
+        .bind(model)
+        .bind(&now)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_document_translation(source_document_id, target_language).await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    fn row_to_document_translation(&self, row: sqlx::sqlite::SqliteRow) -> DocumentTranslation {
+        let parse = |value: String| DateTime::parse_from_rfc3339(&value)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        DocumentTranslation {
+            source_document_id: row.get("source_document_id"),
+            target_language: row.get("target_language"),
+            translated_document_id: row.get("translated_document_id"),
+            source_content_hash: row.get("source_content_hash"),
+            model: row.get("model"),
+            created_at: parse(row.get("created_at")),
+            updated_at: parse(row.get("updated_at")),
+        }
+    }
+}
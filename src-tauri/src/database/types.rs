@@ -15,6 +15,24 @@ pub struct Document {
     pub updated_at: DateTime<Utc>,
     pub status: String, // "draft", "reading", "completed"
     pub category_id: Option<String>, // Link to category
+    pub last_opened_at: Option<DateTime<Utc>>,
+    pub pinned_at: Option<DateTime<Utc>>, // Set while the document is pinned
+    pub parent_document_id: Option<String>, // Set on sections split out of a larger document
+    pub metadata: Option<serde_json::Value>, // e.g. {"extraction": {"method": "marker", ...}}
+    pub source_url: Option<String>, // Normalized URL for documents imported from the web
+    pub language: Option<String>, // ISO 639-3 code detected from the content, e.g. "eng", "cmn"
+    #[serde(default)]
+    pub content_length: i64, // Characters in the content, also set when the content was left out
+}
+
+/// A slice of a document's content, in characters
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentContentRange {
+    pub document_id: String,
+    pub offset: i64,
+    pub content: String,
+    pub content_length: i64, // Of the whole document
+    pub content_hash: Option<String>, // Changes when the content does, so stale ranges can be refetched
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,6 +43,8 @@ pub struct Category {
     pub color: Option<String>, // Hex color for UI theming
     pub icon: Option<String>, // Icon name/emoji
     pub parent_id: Option<String>, // Parent category for nesting
+    #[serde(default)]
+    pub archived: bool, // Finished course: left out of default listings, due counts and search
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub document_count: i64, // Virtual field for UI
@@ -42,6 +62,113 @@ pub struct CreateDocumentRequest {
     pub category_id: Option<String>, // Category assignment
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentSection {
+    pub document_id: String,
+    pub parent_document_id: String,
+    pub section_index: i32,
+    pub title: String,
+    pub start_offset: i64, // Character offsets into the parent's content
+    pub end_offset: i64,
+    pub page_start: Option<i32>, // Only known when the parent kept page breaks
+    pub page_end: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Outlines
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutlineEntry {
+    pub index: i32,
+    pub level: i32, // 1 for top-level headings
+    pub title: String,
+    pub start_offset: i64, // Character offsets of the section, subsections included
+    pub end_offset: i64,
+    pub page: Option<i32>,
+    pub parent_index: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentOutline {
+    pub document_id: String,
+    pub source: String, // 'headings' or 'bookmarks'
+    pub content_hash: String, // Content the outline was built from
+    pub entries: Vec<OutlineEntry>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OutlineSectionContent {
+    pub entry: OutlineEntry,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SplitDocumentRequest {
+    pub split_points: Option<Vec<usize>>, // Character offsets to split at
+    #[serde(default)]
+    pub by_headings: bool, // Or split before each markdown heading...
+    pub heading_level: Option<usize>, // ...of this level or higher (default 1)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitDocumentResult {
+    pub parent: Document,
+    pub children: Vec<Document>,
+    pub sections: Vec<DocumentSection>,
+    pub flashcards_moved: usize,
+    pub highlights_moved: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ContentReplacementResult {
+    pub highlights_reanchored: usize, // Found again in the new content and moved
+    pub highlights_orphaned: usize,   // Text no longer found; offsets cleared, old range kept in metadata
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ContentCompressionResult {
+    pub documents_compressed: usize,
+    pub bytes_before: i64, // Uncompressed size of the content that was compressed
+    pub bytes_after: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReprocessDocumentResult {
+    pub document: Document,
+    pub method: String,
+    pub quality_score: f64,
+    pub highlights_reanchored: usize,
+    pub highlights_orphaned: usize,
+    pub embeddings_updated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct BulkDocumentChanges {
+    pub category_id: Option<String>, // Move into this category
+    #[serde(default)]
+    pub clear_category: bool, // Or move to uncategorized
+    pub status: Option<String>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkUpdateResult {
+    pub updated: Vec<Document>,
+    pub missing_ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkDeleteResult {
+    pub deleted_ids: Vec<String>,
+    pub missing_ids: Vec<String>,
+    pub files_removed: usize,
+    pub embeddings_removed: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateCategoryRequest {
     pub name: String,
@@ -72,12 +199,15 @@ pub struct StudySession {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub is_active: bool,
-    pub session_type: String, // 'focused', 'exploratory', 'review', 'mixed'
+    pub session_type: SessionType,
     pub total_duration: i64, // Duration in seconds
+    pub template_id: Option<String>,
+    pub target_duration: Option<i64>, // Seconds, from the template the session was started with
     pub documents_accessed: Vec<String>,
     pub categories_accessed: Vec<String>,
     pub conversation_ids: Vec<String>,
     pub metadata: Option<serde_json::Value>,
+    pub focus_score: Option<f64>, // 0-100, set by focus analysis
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -94,8 +224,137 @@ pub struct CreateActionRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateSessionRequest {
     pub title: String,
-    pub session_type: Option<String>,
+    pub session_type: Option<SessionType>,
     pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub template_id: Option<String>,
+    #[serde(default)]
+    pub target_duration: Option<i64>,
+}
+
+/// How continuous activity tracking is split into sessions for users who never start one
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SessionSegmentationSettings {
+    pub enabled: bool,
+    pub idle_gap_minutes: i64, // A gap this long between actions starts a new session
+    pub split_at_day_boundary: bool, // Activity on a new local day starts a new session
+}
+
+// Off until the user opts in: splitting rewrites sessions they started on purpose
+impl Default for SessionSegmentationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_gap_minutes: 30,
+            split_at_day_boundary: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SessionSegmentationResult {
+    pub sessions_checked: i64,
+    pub sessions_split: i64,
+    pub sessions_created: i64,
+}
+
+/// What a study session is for. Stored as a string; older sessions used 'focused' and
+/// 'exploratory', which are read as reading and mixed.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(from = "String", into = "String")]
+pub enum SessionType {
+    Reading,
+    Review,
+    AiTutoring,
+    #[default]
+    Mixed,
+}
+
+impl SessionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionType::Reading => "reading",
+            SessionType::Review => "review",
+            SessionType::AiTutoring => "ai_tutoring",
+            SessionType::Mixed => "mixed",
+        }
+    }
+
+    /// Unknown values fall back to mixed
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "reading" | "focused" => SessionType::Reading,
+            "review" => SessionType::Review,
+            "ai_tutoring" => SessionType::AiTutoring,
+            _ => SessionType::Mixed,
+        }
+    }
+}
+
+impl From<String> for SessionType {
+    fn from(value: String) -> Self {
+        SessionType::parse(&value)
+    }
+}
+
+impl From<SessionType> for String {
+    fn from(value: SessionType) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+/// A preset for starting a study session: its type, how long it should run, and the
+/// documents and decks to open with it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub session_type: SessionType,
+    pub target_duration_minutes: i64,
+    pub document_ids: Vec<String>,
+    pub deck_ids: Vec<String>,
+    pub is_builtin: bool, // Built-in templates can be edited but not deleted
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A format for new notes (Cornell notes, lecture summaries, ...). `title_template` and `body`
+/// contain `{{variable}}` placeholders; date, time, course and lecture_number are filled in
+/// automatically.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NoteTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub title_template: String,
+    pub body: String,
+    pub is_builtin: bool, // Built-in templates can be edited but not deleted
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveNoteTemplateRequest {
+    pub id: Option<String>, // None creates a new template
+    pub name: String,
+    pub description: Option<String>,
+    pub title_template: String,
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveSessionTemplateRequest {
+    pub id: Option<String>, // None creates a new template
+    pub name: String,
+    pub description: Option<String>,
+    pub session_type: SessionType,
+    pub target_duration_minutes: i64,
+    #[serde(default)]
+    pub document_ids: Vec<String>,
+    #[serde(default)]
+    pub deck_ids: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -107,6 +366,89 @@ pub struct ActionStats {
     pub average_session_duration: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionTimelineEntry {
+    pub id: String, // ID of the underlying action or review
+    pub kind: String, // 'document', 'conversation', 'flashcard_review', 'flashcard', 'action'
+    pub action_type: String,
+    pub timestamp: DateTime<Utc>,
+    pub duration: i64, // Duration in seconds (explicit, or derived from the gap to the next entry)
+    pub document_ids: Vec<String>,
+    pub document_title: Option<String>,
+    pub conversation_id: Option<String>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionTimeline {
+    pub session: StudySession,
+    pub entries: Vec<SessionTimelineEntry>,
+    pub total_duration: i64, // Wall-clock session length in seconds
+    pub active_duration: i64, // Sum of entry durations in seconds
+    pub documents_opened: Vec<String>,
+    pub conversation_ids: Vec<String>,
+    pub flashcards_reviewed: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionFocusAnalysis {
+    pub session_id: String,
+    pub focus_score: f64, // 0-100
+    pub context_switches: i64,
+    pub switches_per_hour: f64,
+    pub idle_gaps: i64, // Gaps longer than the idle threshold
+    pub idle_time: i64, // Seconds spent in idle gaps
+    pub longest_idle_gap: i64, // Seconds
+    pub active_time: i64, // Seconds
+}
+
+/// Minutes spent on each kind of activity
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ActivityBreakdown {
+    pub reading: f64,
+    pub reviewing: f64,
+    pub chatting: f64,
+    pub note_taking: f64,
+    pub other: f64,
+}
+
+/// One day of study activity for the dashboard, screen-time style
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DailyActivity {
+    pub date: String, // YYYY-MM-DD, local time
+    pub total_minutes: f64,
+    pub minutes_by_activity: ActivityBreakdown,
+    pub hourly: Vec<ActivityBreakdown>, // 24 entries, by local hour the activity started in
+    pub sessions_count: i64,
+    pub flashcards_reviewed: i64,
+    pub first_activity: Option<DateTime<Utc>>,
+    pub last_activity: Option<DateTime<Utc>>,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Raw study data that can be exported as CSV
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalyticsExportKind {
+    FlashcardReviews,
+    StudySessions,
+    UserActions,
+}
+
+/// Local calendar days, both inclusive; open ends are unbounded
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DateRange {
+    pub start: Option<chrono::NaiveDate>,
+    pub end: Option<chrono::NaiveDate>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalyticsExportResult {
+    pub kind: AnalyticsExportKind,
+    pub path: String,
+    pub rows: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StudyInsights {
     pub total_study_time: i64,
@@ -159,6 +501,32 @@ pub struct FlashcardReview {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntervalPoint {
+    pub timestamp: DateTime<Utc>,
+    pub interval: i32, // Days until the next review, as scheduled by this review
+    pub ease_factor: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlashcardDetailStats {
+    pub flashcard: Flashcard,
+    pub reviews: Vec<FlashcardReview>, // Oldest first
+    pub interval_curve: Vec<IntervalPoint>,
+    pub total_reviews: i32,
+    pub correct_count: i32,
+    pub lapse_count: i32, // Failed reviews after the card had been answered well
+    pub average_time_spent: f32, // Seconds
+    pub first_reviewed: Option<DateTime<Utc>>,
+}
+
+/// What record_flashcard_review returns: the stored review and the rescheduled card
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlashcardReviewResult {
+    pub flashcard: Flashcard,
+    pub review: FlashcardReview,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FlashcardDeck {
     pub id: String,
@@ -174,6 +542,39 @@ pub struct FlashcardDeck {
     pub card_count: i32, // Virtual field for UI
     pub due_count: i32, // Virtual field for UI
     pub metadata: Option<serde_json::Value>,
+    pub new_card_order: NewCardOrder,
+}
+
+/// The order in which a deck introduces cards that have never been reviewed
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NewCardOrder {
+    #[default]
+    Created, // Oldest first
+    Random,
+    Document, // Source document, then where the card's source text appears in it
+    Difficulty, // Easy before medium before hard
+}
+
+impl NewCardOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NewCardOrder::Created => "created",
+            NewCardOrder::Random => "random",
+            NewCardOrder::Document => "document",
+            NewCardOrder::Difficulty => "difficulty",
+        }
+    }
+
+    /// Unknown values (e.g. from a newer app version) fall back to creation order
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "random" => NewCardOrder::Random,
+            "document" => NewCardOrder::Document,
+            "difficulty" => NewCardOrder::Difficulty,
+            _ => NewCardOrder::Created,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -190,6 +591,31 @@ pub struct CreateFlashcardRequest {
     pub metadata: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OcclusionMask {
+    pub id: String,
+    // Position and size as fractions (0.0-1.0) of the image dimensions
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub label: Option<String>, // Answer shown on the back of the card for this mask
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateImageOcclusionRequest {
+    pub image_path: Option<String>, // Local file to copy into the attachments dir
+    pub image_data: Option<String>, // Or base64 / data URL image data
+    pub image_extension: Option<String>, // Needed with image_data when it isn't a data URL
+    pub header: Option<String>, // Prompt shown above the image
+    pub masks: Vec<OcclusionMask>,
+    pub mode: Option<String>, // 'hide_one' (default) or 'hide_all'
+    pub source_document_id: Option<String>,
+    pub tags: Vec<String>,
+    pub category_id: Option<String>,
+    pub deck_id: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateFlashcardDeckRequest {
     pub name: String,
@@ -200,6 +626,8 @@ pub struct CreateFlashcardDeckRequest {
     pub tags: Vec<String>,
     pub is_shared: Option<bool>,
     pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub new_card_order: Option<NewCardOrder>, // None keeps the current order on update
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -211,6 +639,31 @@ pub struct CreateFlashcardReviewRequest {
     pub confidence: i32,
     pub quality: i32, // 0-5 for SM-2 algorithm
     pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub review_session_id: Option<String>, // Persisted review session to advance, if any
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypedAnswerGrade {
+    pub flashcard_id: String,
+    pub quality: i32, // 0-5, pass straight to record_flashcard_review
+    pub response: String, // 'correct', 'incorrect', 'partial'
+    pub similarity: Option<f32>, // Embedding similarity to the card back, when available
+    pub feedback: Option<String>,
+    pub graded_by: String, // 'model', 'embeddings', 'exact'
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlashcardDeckStats {
+    pub deck_id: String,
+    pub card_count: i32,
+    pub due_count: i32,
+    pub new_count: i32, // Never reviewed
+    pub learning_count: i32, // Reviewed 1-2 times
+    pub mastered_count: i32, // 3+ reviews with at least 80% success
+    pub average_success_rate: f32, // Over reviewed cards
+    pub reviews_today: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -240,7 +693,71 @@ pub struct FlashcardReviewSession {
     #[serde(rename = "estimatedTime")]
     pub estimated_time: i32, // in minutes
     #[serde(rename = "mixStrategy")]
-    pub mix_strategy: String, // 'due_first', 'mixed', 'new_first'
+    pub mix_strategy: String, // 'due_first', 'mixed', 'new_first', 'custom'
+}
+
+/// A review session sized to fit a time budget, using how long this user takes per card
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdaptiveReviewSession {
+    #[serde(flatten)]
+    pub session: FlashcardReviewSession,
+    pub minutes_available: i32,
+    pub seconds_per_due_card: f64,
+    pub seconds_per_new_card: f64,
+    pub timed_reviews: i64, // Recent timed reviews looked at; with fewer than 20 of a kind its default is used
+}
+
+/// One answered card in a persisted review session
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReviewCardResult {
+    pub flashcard_id: String,
+    pub review_id: String,
+    pub response: String, // 'correct', 'incorrect', 'partial'
+    pub quality: i32,
+}
+
+/// A review session saved as it progresses; `position` is the index of the next card to show
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PersistedReviewSession {
+    pub id: String,
+    pub study_session_id: Option<String>,
+    pub mix_strategy: String,
+    pub card_ids: Vec<String>,
+    pub results: Vec<ReviewCardResult>,
+    pub position: i32,
+    pub status: String, // 'active', 'completed', 'abandoned', 'expired'
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>, // Pushed back on every answer
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartReviewSessionRequest {
+    pub card_ids: Vec<String>,
+    pub study_session_id: Option<String>,
+    pub mix_strategy: Option<String>,
+}
+
+/// A session to pick up again, with the cards it still has to show
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResumedReviewSession {
+    pub session: PersistedReviewSession,
+    pub remaining_cards: Vec<Flashcard>, // Cards deleted since are skipped
+}
+
+/// Which cards a custom (cram) session draws. All set filters must match.
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CustomReviewFilter {
+    pub tags: Vec<String>, // Card has any of these tags
+    pub deck_id: Option<String>,
+    pub category_id: Option<String>,
+    pub source_document_id: Option<String>,
+    pub failed_within_days: Option<i64>, // Answered incorrectly (or quality < 3) in the last N days
+    pub due_within_days: Option<i64>, // Due now or within the next N days
+    pub include_new: Option<bool>, // Include never-reviewed cards (default: true)
+    pub limit: Option<i32>, // Default: 50
 }
 
 // Background Processing Job Types
@@ -263,6 +780,7 @@ pub struct ProcessingJob {
     pub started_at: Option<DateTime<Utc>>,
     pub completed_at: Option<DateTime<Utc>>,
     pub metadata: Option<serde_json::Value>,
+    pub source_url: Option<String>, // Normalized URL for URL imports
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -276,6 +794,8 @@ pub struct CreateProcessingJobRequest {
     pub category_id: Option<String>,
     pub processing_options: Option<serde_json::Value>,
     pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub source_url: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -298,4 +818,825 @@ pub struct ProcessingJobStats {
     pub completed_jobs: i64,
     pub failed_jobs: i64,
     pub average_processing_time: f64, // in seconds
-} 
\ No newline at end of file
+}
+
+// Practice questions generated after a document is completed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CategoryPracticeSettings {
+    pub category_id: String,
+    pub enabled: bool,
+    pub question_count: i32,
+    pub flashcard_count: i32,
+    pub provider_id: Option<String>,
+    pub provider_type: Option<String>, // 'openai', 'custom', 'anthropic', 'ollama'
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdatePracticeSettingsRequest {
+    pub enabled: bool,
+    pub question_count: Option<i32>,
+    pub flashcard_count: Option<i32>,
+    pub provider_id: Option<String>,
+    pub provider_type: Option<String>,
+    pub base_url: Option<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Quiz {
+    pub id: String,
+    pub title: String,
+    pub document_id: Option<String>,
+    pub category_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub questions: Vec<QuizQuestion>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuizQuestion {
+    pub id: String,
+    pub quiz_id: String,
+    pub position: i32,
+    pub question: String,
+    pub answer: String,
+    pub explanation: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateQuizQuestionRequest {
+    pub question: String,
+    pub answer: String,
+    pub explanation: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateQuizRequest {
+    pub title: String,
+    pub document_id: Option<String>,
+    pub category_id: Option<String>,
+    pub questions: Vec<CreateQuizQuestionRequest>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+// AI conversations persisted backend-side
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Conversation {
+    pub id: String,
+    pub title: String,
+    pub model: Option<String>,
+    pub provider_id: Option<String>,
+    pub session_id: Option<String>,
+    pub conversation_type: String, // 'question', 'explanation', 'review', 'brainstorm', 'general'
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub message_count: i64, // Virtual field for UI
+    pub summary: Option<String>, // Rolling summary of older messages
+    pub summarized_message_count: i64, // How many leading messages the summary covers
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateConversationRequest {
+    pub id: Option<String>, // Allows the frontend to keep its own conversation IDs
+    pub title: String,
+    pub model: Option<String>,
+    pub provider_id: Option<String>,
+    pub session_id: Option<String>,
+    pub conversation_type: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationMessage {
+    pub id: String,
+    pub conversation_id: String,
+    pub role: String, // 'user', 'assistant', 'system'
+    pub content: String,
+    pub model: Option<String>,
+    pub provider_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub source_count: i64, // Virtual field for UI
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateConversationMessageRequest {
+    pub id: Option<String>,
+    pub conversation_id: String,
+    pub role: String,
+    pub content: String,
+    pub model: Option<String>,
+    pub provider_id: Option<String>,
+    pub sources: Vec<CreateMessageSourceRequest>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageSource {
+    pub id: String,
+    pub message_id: String,
+    pub source_type: String, // 'document', 'chunk', 'highlight'
+    pub document_id: Option<String>,
+    pub document_title: Option<String>, // Resolved from documents when available
+    pub chunk_id: Option<String>,
+    pub content: Option<String>, // Snapshot of the text the model was shown
+    pub start_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub score: Option<f64>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// A numbered footnote of an answer, pointing at the excerpt that backs it. Stored as the
+/// message source's "footnote" and "page" metadata.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageFootnote {
+    pub number: i64, // As shown in the answer: [^1]
+    pub source_id: String, // message_sources id
+    pub document_id: Option<String>,
+    pub document_title: Option<String>,
+    pub chunk_id: Option<String>,
+    pub page: Option<i64>, // Only known for documents that kept their page breaks
+    pub start_offset: Option<i64>, // Char range of the excerpt in the document, when it could be found
+    pub end_offset: Option<i64>,
+    pub excerpt: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateMessageSourceRequest {
+    pub source_type: String,
+    pub document_id: Option<String>,
+    pub chunk_id: Option<String>,
+    pub content: Option<String>,
+    pub start_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub score: Option<f64>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentChatResponse {
+    pub conversation_id: String,
+    pub message: ConversationMessage,
+    pub sources: Vec<MessageSource>, // Excerpts the model was shown
+    pub footnotes: Vec<MessageFootnote>, // The excerpts the answer cites, by footnote number
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContinueConversationRequest {
+    pub conversation_id: String,
+    pub content: String, // The new user message
+    #[serde(default)]
+    pub sources: Vec<CreateMessageSourceRequest>,
+    pub system_prompt: Option<String>,
+    pub profile_id: Option<String>, // AI profile whose system prompt/defaults apply
+    pub keep_last: Option<usize>, // Recent messages sent verbatim alongside the summary
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+}
+
+// AI tutor persona profiles ("Socratic tutor", "Exam grader", ...)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AIProfile {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub system_prompt: String,
+    pub default_model: Option<String>,
+    pub temperature: Option<f32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAIProfileRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub system_prompt: String,
+    pub default_model: Option<String>,
+    pub temperature: Option<f32>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+// Scheduled library maintenance (backups, integrity checks, cleanup)
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    pub interval_hours: i64, // How often scheduled maintenance runs (default: daily)
+    pub backup_retention: i64, // Number of database backups to keep
+    pub job_retention_days: i64, // Completed processing jobs older than this are removed
+    pub failed_job_retention_days: i64, // Failed jobs are kept longer for troubleshooting
+    pub archive_pruned_jobs: bool, // Append removed jobs to a JSONL archive before deleting them
+    pub stale_job_hours: i64, // Jobs stuck in 'processing' longer than this are failed
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            backup_retention: 7,
+            job_retention_days: 30,
+            failed_job_retention_days: 90,
+            archive_pruned_jobs: true,
+            stale_job_hours: 6,
+        }
+    }
+}
+
+// Reminders and OS notifications
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ReminderSettings {
+    pub enabled: bool,
+    pub due_reviews: bool, // Remind when flashcards are due
+    pub due_review_threshold: i64, // Only remind once at least this many cards are due
+    pub due_review_interval_hours: i64, // At most one due-review reminder per this many hours
+    pub study_goal_minutes: i64, // Daily study goal; 0 turns goal nudges off
+    pub study_goal_nudge_hour: u32, // Local hour after which to nudge if the goal isn't met yet
+    pub job_notifications: bool, // Notify when processing jobs finish or fail
+    pub quiet_hours_enabled: bool,
+    pub quiet_hours_start: u32, // Local hour, 0-23
+    pub quiet_hours_end: u32, // Local hour; earlier than the start when quiet hours span midnight
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            due_reviews: true,
+            due_review_threshold: 10,
+            due_review_interval_hours: 4,
+            study_goal_minutes: 30,
+            study_goal_nudge_hour: 19,
+            job_notifications: true,
+            quiet_hours_enabled: true,
+            quiet_hours_start: 22,
+            quiet_hours_end: 8,
+        }
+    }
+}
+
+/// What the reminder scheduler last sent, so restarts don't repeat notifications
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ReminderState {
+    pub last_due_review_reminder: Option<DateTime<Utc>>,
+    pub last_goal_nudge_date: Option<String>, // YYYY-MM-DD, local time
+    pub jobs_checked_at: Option<DateTime<Utc>>, // Jobs finished after this haven't been announced
+}
+
+/// Opt-in clipboard monitoring that offers copied URLs and long passages for import
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClipboardWatcherSettings {
+    pub enabled: bool,
+    pub detect_urls: bool,
+    pub min_text_chars: usize, // Shorter copied text is ignored; 0 turns text offers off
+}
+
+impl Default for ClipboardWatcherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            detect_urls: true,
+            min_text_chars: 280,
+        }
+    }
+}
+
+/// Opt-in localhost endpoint for the browser extension
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CompanionServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: Option<String>, // Generated on first use; the extension sends it as a bearer token
+    pub default_tags: Vec<String>, // Added to everything saved from the browser
+}
+
+impl Default for CompanionServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17823,
+            token: None,
+            default_tags: vec!["web".to_string()],
+        }
+    }
+}
+
+/// Opt-in Model Context Protocol endpoint for external AI clients
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct McpServerSettings {
+    pub enabled: bool,
+    pub port: u16,
+    pub token: Option<String>, // Generated on first use; clients send it as a bearer token
+    pub allow_writes: bool, // Off: tools that change the library (create_flashcard) are refused
+}
+
+impl Default for McpServerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 17824,
+            token: None,
+            allow_writes: true,
+        }
+    }
+}
+
+/// Events user automation scripts can run on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptEvent {
+    DocumentImported,
+    ReviewCompleted,
+}
+
+impl ScriptEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScriptEvent::DocumentImported => "document_imported",
+            ScriptEvent::ReviewCompleted => "review_completed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "document_imported" => Some(ScriptEvent::DocumentImported),
+            "review_completed" => Some(ScriptEvent::ReviewCompleted),
+            _ => None,
+        }
+    }
+}
+
+/// A Rhai script run whenever `event` fires (see scripting.rs for the API it gets)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutomationScript {
+    pub id: String,
+    pub name: String,
+    pub event: ScriptEvent,
+    pub source: String,
+    pub enabled: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>, // Cleared by the next successful run
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveAutomationScriptRequest {
+    pub id: Option<String>, // None creates a new script
+    pub name: String,
+    pub event: ScriptEvent,
+    pub source: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// What a settings bundle import writes, resolved against this machine's rows beforehand so
+/// it can be applied in one transaction (see `Database::apply_settings_bundle`)
+#[derive(Debug, Default)]
+pub struct SettingsBundleWrites {
+    pub settings: Vec<(String, serde_json::Value)>,
+    pub ai_profiles: Vec<(Option<String>, CreateAIProfileRequest)>, // Existing profile id, if any
+    pub session_templates: Vec<SaveSessionTemplateRequest>,
+    pub deck_orders: Vec<(String, NewCardOrder)>, // (deck id, new card order)
+    pub automation_scripts: Vec<SaveAutomationScriptRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScriptRunResult {
+    pub script_id: String,
+    pub success: bool,
+    pub output: Vec<String>, // Lines from print() and log()
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+/// Opt-in user automations, and the model scripts reach through ask_ai()
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ScriptingSettings {
+    pub enabled: bool,
+    pub provider_id: Option<String>,
+    pub provider_type: Option<String>, // 'openai', 'custom', 'anthropic', 'ollama'
+    pub base_url: Option<String>,
+    pub model: Option<String>, // Unset: ask_ai() fails
+    pub max_operations: u64, // Rhai operation budget per run, against runaway loops
+    pub max_ai_calls: u32, // Per run
+}
+
+impl Default for ScriptingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider_id: None,
+            provider_type: None,
+            base_url: None,
+            model: None,
+            max_operations: 1_000_000,
+            max_ai_calls: 3,
+        }
+    }
+}
+
+// Code snippets
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CodeSnippet {
+    pub id: String,
+    pub document_id: String,
+    pub document_title: String,
+    pub snippet_index: i32,
+    pub language: Option<String>,
+    pub content: String,
+    pub start_offset: i64, // Char offsets of the fenced block in the document
+    pub end_offset: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+// Quick search
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuickSearchHit {
+    pub document_id: String,
+    pub title: String,
+    pub doc_type: String,
+    pub category_id: Option<String>,
+    pub tags: Vec<String>,
+    pub match_kind: String, // 'title', 'tag' or 'content'
+    pub snippet: Option<String>, // Text around the first content match
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuickSearchResults {
+    pub query: String,
+    pub hits: Vec<QuickSearchHit>,
+    pub complete: bool, // False while content matches are still being looked up; ask again shortly
+    pub elapsed_ms: u64,
+}
+
+// Global search
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HighlightSearchHit {
+    pub id: String, // message_sources id
+    pub document_id: Option<String>,
+    pub document_title: Option<String>,
+    pub conversation_id: String,
+    pub text: String,
+    pub start_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FlashcardSearchHit {
+    pub id: String,
+    pub front: String,
+    pub back: String,
+    pub deck_id: Option<String>,
+    pub source_document_id: Option<String>,
+    pub tags: Vec<String>,
+    pub next_review: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConversationSearchHit {
+    pub id: String,
+    pub title: String,
+    pub conversation_type: String,
+    pub match_kind: String, // 'title' or 'message'
+    pub snippet: Option<String>, // Text around the match in the latest matching message
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How many results of each type a global search returns
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GlobalSearchLimits {
+    pub documents: i64,
+    pub highlights: i64,
+    pub flashcards: i64,
+    pub conversations: i64,
+}
+
+impl Default for GlobalSearchLimits {
+    fn default() -> Self {
+        Self { documents: 8, highlights: 5, flashcards: 5, conversations: 5 }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct GlobalSearchResults {
+    pub query: String,
+    pub documents: Vec<QuickSearchHit>,
+    pub highlights: Vec<HighlightSearchHit>,
+    pub flashcards: Vec<FlashcardSearchHit>,
+    pub conversations: Vec<ConversationSearchHit>,
+}
+
+// Translations
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentTranslation {
+    pub source_document_id: String,
+    pub target_language: String, // As requested, lowercased, e.g. 'english' or 'de'
+    pub translated_document_id: String,
+    pub source_content_hash: String, // Stale once the source document's content_hash differs
+    pub model: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranslationResult {
+    pub translation: String,
+    pub source_language: Option<String>, // ISO 639-3 code detected from the source text
+    pub target_language: String,
+    pub chunks: usize,
+    pub cached_chunks: usize, // Chunks served from the translation cache
+    pub document: Option<Document>, // The saved translation, for document sources
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobPruneResult {
+    pub deleted: u64,
+    pub archive_path: Option<String>, // Set when the removed jobs were archived
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaintenanceTaskResult {
+    pub task: String, // 'backup', 'integrity_check', 'vector_optimize', 'trash_purge', 'stale_jobs', 'orphaned_embeddings'
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MaintenanceRun {
+    pub id: String,
+    pub trigger: String, // 'scheduled', 'manual'
+    pub status: String, // 'running', 'completed', 'failed'
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub tasks: Vec<MaintenanceTaskResult>,
+    pub error_message: Option<String>,
+}
+
+// Embedding provider selection used by init_embedding_service
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EmbeddingProviderCandidate {
+    pub provider: String, // 'ollama', 'openai', 'openai-compatible', 'gemini', 'voyage', 'fastembed', 'rust-bert'
+    pub model: String, // 'auto' for fastembed picks a model from the documents' languages
+    pub base_url: Option<String>,
+    pub api_key_id: Option<String>, // api_keys provider id; candidates without a stored key are skipped
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EmbeddingFallbackSettings {
+    pub order: Vec<EmbeddingProviderCandidate>, // Tried first to last until one passes a health check
+}
+
+impl Default for EmbeddingFallbackSettings {
+    fn default() -> Self {
+        Self {
+            order: vec![
+                EmbeddingProviderCandidate {
+                    provider: "ollama".to_string(),
+                    model: "mxbai-embed-large".to_string(),
+                    base_url: Some("http://localhost:11434".to_string()),
+                    api_key_id: None,
+                },
+                EmbeddingProviderCandidate {
+                    provider: "openai".to_string(),
+                    model: "text-embedding-3-small".to_string(),
+                    base_url: None,
+                    api_key_id: Some("openai-default".to_string()),
+                },
+                EmbeddingProviderCandidate {
+                    provider: "rust-bert".to_string(),
+                    model: "fallback".to_string(),
+                    base_url: None,
+                    api_key_id: None,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct QueryEmbeddingCacheSettings {
+    pub persist: bool, // Keep query embeddings in embeddings.db so they survive restarts
+}
+
+/// The provider init_embedding_service settled on; retried first on the next start so the
+/// library keeps using one embedding space
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SelectedEmbeddingProvider {
+    pub candidate: EmbeddingProviderCandidate,
+    pub selected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DocumentLanguageSummary {
+    pub counts: HashMap<String, i64>, // ISO 639-3 code -> documents
+    pub recommended_local_model: String, // fastembed model id
+}
+
+// Model limits and features from the models.dev catalog
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelCapability {
+    pub provider_id: String, // models.dev provider key, e.g. 'openai', 'anthropic'
+    pub model_id: String,
+    pub name: String,
+    pub context_window: Option<i64>,
+    pub max_output_tokens: Option<i64>,
+    pub supports_tools: bool,
+    pub supports_reasoning: bool,
+    pub supports_attachments: bool,
+    pub input_modalities: Vec<String>, // 'text', 'image', 'audio', 'pdf'...
+    pub cost_input: Option<f64>, // USD per million tokens
+    pub cost_output: Option<f64>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a concept is linked to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConceptItemType {
+    Flashcard,
+    QuizQuestion,
+}
+
+impl ConceptItemType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConceptItemType::Flashcard => "flashcard",
+            ConceptItemType::QuizQuestion => "quiz_question",
+        }
+    }
+}
+
+/// An idea a card or quiz question tests, e.g. "Krebs cycle", named once per category
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Concept {
+    pub id: String,
+    pub name: String,
+    pub category_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// How well a concept is known, from the reviews of its cards and answers to its quiz questions
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConceptMastery {
+    pub concept: Concept,
+    pub mastery: f64, // 0-1; recent results weigh more, and little evidence stays close to 0.5
+    pub evidence_count: i64, // Reviews and quiz answers counted
+    pub flashcard_count: i64,
+    pub quiz_question_count: i64,
+    pub last_practiced_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuizAnswer {
+    pub id: String,
+    pub question_id: String,
+    pub correct: bool,
+    pub answered_at: DateTime<Utc>,
+}
+
+// Question bank of past exam papers
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExamPaper {
+    pub id: String,
+    pub title: String,
+    pub year: Option<i32>,
+    pub category_id: Option<String>,
+    pub file_path: Option<String>,
+    pub question_count: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExamQuestion {
+    pub id: String,
+    pub paper_id: String,
+    pub position: i32,
+    pub number: String, // As printed on the paper
+    pub text: String,
+    pub marks: Option<i32>,
+    pub topic: Option<String>,
+    pub year: Option<i32>, // The paper's year
+    pub paper_title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateExamQuestionRequest {
+    pub number: String,
+    pub text: String,
+    pub marks: Option<i32>,
+    pub topic: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateExamPaperRequest {
+    pub title: String,
+    pub year: Option<i32>,
+    pub category_id: Option<String>,
+    pub file_path: Option<String>,
+    pub questions: Vec<CreateExamQuestionRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionBankTopic {
+    pub topic: String,
+    pub question_count: i64,
+    pub years: Vec<i32>, // Years the topic came up, newest first
+}
+
+/// One row of a grading rubric, e.g. "Thesis" worth 4 points
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RubricCriterion {
+    pub name: String,
+    pub description: String,
+    pub max_points: f64,
+    #[serde(default)]
+    pub levels: Vec<String>, // Optional descriptors, e.g. "4: clear, arguable thesis"
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Rubric {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub criteria: Vec<RubricCriterion>,
+    pub is_builtin: bool, // Built-in rubrics can be edited but not deleted
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveRubricRequest {
+    pub id: Option<String>, // Update this rubric instead of creating one
+    pub name: String,
+    pub description: Option<String>,
+    pub criteria: Vec<RubricCriterion>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CriterionFeedback {
+    pub criterion: String,
+    pub score: f64,
+    pub max_points: f64,
+    pub comment: String,
+}
+
+/// Rubric feedback on one version of an essay. Feedback on a document is kept, so revisions
+/// can be compared.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EssayFeedback {
+    pub id: String,
+    pub document_id: Option<String>,
+    pub rubric_id: Option<String>,
+    pub rubric_name: String,
+    pub word_count: i64,
+    pub total_score: f64,
+    pub max_score: f64,
+    pub criteria: Vec<CriterionFeedback>,
+    pub summary: String,
+    pub strengths: Vec<String>,
+    pub improvements: Vec<String>,
+    pub model: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateEssayFeedbackRequest {
+    pub document_id: Option<String>,
+    pub rubric_id: Option<String>,
+    pub rubric_name: String,
+    pub essay_text: String,
+    pub criteria: Vec<CriterionFeedback>,
+    pub summary: String,
+    pub strengths: Vec<String>,
+    pub improvements: Vec<String>,
+    pub model: Option<String>,
+}
@@ -0,0 +1,201 @@
+//! `stellar://` links from browsers and external notes. Supported forms:
+//!
+//! - `stellar://document/{id}?page=N` opens a document, optionally at a page
+//! - `stellar://review/deck/{id}` starts reviewing a flashcard deck
+//! - `stellar://import?url=...` offers to import a web page or PDF
+//!
+//! Links are parsed and checked against the library here, then handed to the frontend as a
+//! `deep-link` event. Links that arrive before the frontend is listening (the one that launched
+//! the app) are queued until it calls `take_pending_deep_links`.
+
+use std::sync::Mutex as StdMutex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::commands::database::DatabaseState;
+
+pub const DEEP_LINK_SCHEME: &str = "stellar";
+pub const DEEP_LINK_EVENT: &str = "deep-link";
+pub const DEEP_LINK_ERROR_EVENT: &str = "deep-link-error";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeepLinkTarget {
+    Document {
+        document_id: String,
+        page: Option<u32>,
+    },
+    ReviewDeck {
+        deck_id: String,
+    },
+    Import {
+        url: String,
+        existing_document_id: Option<String>, // The URL was already imported as this document
+    },
+}
+
+/// Managed state: links received before the frontend was ready for them
+#[derive(Default)]
+pub struct DeepLinkState {
+    inner: StdMutex<DeepLinkQueue>,
+}
+
+#[derive(Default)]
+struct DeepLinkQueue {
+    frontend_ready: bool,
+    pending: Vec<DeepLinkTarget>,
+}
+
+impl DeepLinkState {
+    /// Mark the frontend as listening and hand over anything queued until now
+    pub fn take_pending(&self) -> Vec<DeepLinkTarget> {
+        match self.inner.lock() {
+            Ok(mut queue) => {
+                queue.frontend_ready = true;
+                std::mem::take(&mut queue.pending)
+            }
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Queue `target` if the frontend isn't listening yet; returns false when it should be emitted
+    fn queue_if_not_ready(&self, target: &DeepLinkTarget) -> bool {
+        match self.inner.lock() {
+            Ok(mut queue) if !queue.frontend_ready => {
+                queue.pending.push(target.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Register the scheme with the OS where that happens at runtime and start handling links,
+/// including the one the app was launched with
+pub fn init(app: &AppHandle) {
+    // Linux and Windows dev builds have no installer to register the scheme
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("⚠️ Failed to register {}:// links: {}", DEEP_LINK_SCHEME, e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            let app = handle.clone();
+            tauri::async_runtime::spawn(async move { handle_deep_link(&app, &url).await });
+        }
+    });
+
+    match app.deep_link().get_current() {
+        Ok(Some(urls)) => {
+            for url in urls {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { handle_deep_link(&app, &url).await });
+            }
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("⚠️ Failed to read launch link: {}", e),
+    }
+}
+
+pub fn focus_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+pub async fn handle_deep_link(app: &AppHandle, url: &Url) {
+    println!("🔗 Opening link {}", url);
+
+    let target = match parse_deep_link(url) {
+        Ok(target) => resolve_target(app, target).await,
+        Err(e) => Err(e),
+    };
+    let target = match target {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("⚠️ Ignoring link {}: {}", url, e);
+            let _ = app.emit(DEEP_LINK_ERROR_EVENT, e);
+            return;
+        }
+    };
+
+    focus_main_window(app);
+    if !app.state::<DeepLinkState>().queue_if_not_ready(&target) {
+        let _ = app.emit(DEEP_LINK_EVENT, target);
+    }
+}
+
+/// Work out what a `stellar://` link points to without touching the library
+pub fn parse_deep_link(url: &Url) -> Result<DeepLinkTarget, String> {
+    if url.scheme() != DEEP_LINK_SCHEME {
+        return Err(format!("Unsupported link scheme '{}'", url.scheme()));
+    }
+
+    // In stellar://document/abc the first part ("document") is the URL host
+    let mut parts: Vec<String> = url.host_str().map(|host| host.to_string()).into_iter().collect();
+    parts.extend(
+        url.path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .map(|segment| segment.to_string()),
+    );
+    let query = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.into_owned());
+
+    match parts.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["document", id] => {
+            let page = match query("page") {
+                Some(page) => Some(page.parse::<u32>().ok().filter(|&page| page > 0)
+                    .ok_or_else(|| format!("Invalid page '{}'", page))?),
+                None => None,
+            };
+            Ok(DeepLinkTarget::Document { document_id: id.to_string(), page })
+        }
+        ["review", "deck", id] => Ok(DeepLinkTarget::ReviewDeck { deck_id: id.to_string() }),
+        ["import"] => {
+            let target = query("url").ok_or("Import link has no url parameter")?;
+            let parsed = Url::parse(&target).map_err(|e| format!("Invalid import URL '{}': {}", target, e))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(format!("Only web pages can be imported, not '{}'", target));
+            }
+            Ok(DeepLinkTarget::Import { url: parsed.to_string(), existing_document_id: None })
+        }
+        _ => Err(format!("Unknown link '{}'", url)),
+    }
+}
+
+/// Check the target against the library. Before the database is loaded links pass through
+/// unchecked; the views report missing items themselves.
+async fn resolve_target(app: &AppHandle, target: DeepLinkTarget) -> Result<DeepLinkTarget, String> {
+    let state = app.state::<DatabaseState>();
+    let db_guard = state.lock().await;
+    let Some(database) = db_guard.as_ref() else {
+        return Ok(target);
+    };
+
+    match target {
+        DeepLinkTarget::Document { ref document_id, .. } => {
+            database.get_document(document_id).await
+                .map_err(|e| format!("Failed to look up document: {}", e))?
+                .ok_or_else(|| format!("Document {} no longer exists", document_id))?;
+            Ok(target)
+        }
+        DeepLinkTarget::ReviewDeck { ref deck_id } => {
+            database.get_flashcard_deck(deck_id).await
+                .map_err(|e| format!("Failed to look up deck: {}", e))?
+                .ok_or_else(|| format!("Deck {} no longer exists", deck_id))?;
+            Ok(target)
+        }
+        DeepLinkTarget::Import { url, .. } => {
+            let existing_document_id = database.find_document_by_source_url(&url).await
+                .map_err(|e| format!("Failed to look up imported URL: {}", e))?
+                .map(|document| document.id);
+            Ok(DeepLinkTarget::Import { url, existing_document_id })
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Streaming file downloads for URL imports. Bodies go straight to a `.part` file instead of
+//! memory, interrupted transfers resume with an HTTP Range request, and a size cap stops
+//! runaway downloads.
+
+use crate::network::{shared_client, HttpPurpose};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// Largest file a URL import will fetch (2 GiB; scanned textbooks reach 1 GB)
+pub const DEFAULT_MAX_DOWNLOAD_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+const MAX_ATTEMPTS: usize = 4;
+const PROGRESS_INTERVAL_BYTES: u64 = 1024 * 1024;
+/// A transfer with no data for this long counts as dropped and is resumed (or fails)
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDownloadProgress {
+    pub url: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>, // When the server reports a length
+    pub attempt: usize,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub content_type: Option<String>,
+}
+
+/// Download `url` to `target`, retrying from where it stopped when the connection drops.
+/// The file only appears at `target` once it is complete.
+pub async fn download_to_file<F>(url: &str, target: &Path, max_bytes: u64, on_progress: F) -> Result<DownloadedFile, String>
+where
+    F: Fn(FileDownloadProgress),
+{
+    crate::network::ensure_reachable(url, "Downloading a file")?;
+    let client = shared_client(HttpPurpose::Download)?;
+
+    let partial = PathBuf::from(format!("{}.part", target.display()));
+    let mut downloaded_bytes = 0u64;
+    let mut total_bytes = None;
+    let mut content_type = None;
+    let mut last_error = String::new();
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        if attempt > 1 {
+            println!("🔁 Resuming download of {} at {} bytes (attempt {}/{})", url, downloaded_bytes, attempt, MAX_ATTEMPTS);
+            tokio::time::sleep(Duration::from_secs(attempt as u64)).await;
+        }
+
+        let mut request = client.get(url);
+        if downloaded_bytes > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", downloaded_bytes));
+        }
+        let response = match tokio::time::timeout(IDLE_TIMEOUT, request.send()).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                last_error = format!("Failed to download {}: {}", url, e);
+                continue;
+            }
+            Err(_) => {
+                last_error = format!("Failed to download {}: no response for {} seconds", url, IDLE_TIMEOUT.as_secs());
+                continue;
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            // Client errors won't change on retry
+            if status.is_client_error() {
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(format!("Failed to download {}: HTTP {}", url, status));
+            }
+            last_error = format!("Failed to download {}: HTTP {}", url, status);
+            continue;
+        }
+
+        // A server that ignores Range sends the whole file again
+        let resumed = status == reqwest::StatusCode::PARTIAL_CONTENT;
+        if !resumed {
+            downloaded_bytes = 0;
+        }
+        if let Some(length) = response.content_length() {
+            total_bytes = Some(if resumed { downloaded_bytes + length } else { length });
+        }
+        if let Some(total) = total_bytes.filter(|&total| total > max_bytes) {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(format!("{} is too large ({} bytes, limit {} bytes)", url, total, max_bytes));
+        }
+        if let Some(total) = total_bytes {
+            crate::storage::ensure_space(&partial, total.saturating_sub(downloaded_bytes))?;
+        }
+        content_type = content_type.or_else(|| {
+            response.headers().get(reqwest::header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        });
+
+        let mut output = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(&partial)
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+
+        let mut last_reported = downloaded_bytes;
+        let mut stream = response.bytes_stream();
+        let mut interrupted = false;
+        loop {
+            let chunk = match tokio::time::timeout(IDLE_TIMEOUT, stream.next()).await {
+                Ok(None) => break,
+                Ok(Some(Ok(chunk))) => chunk,
+                Ok(Some(Err(e))) => {
+                    last_error = format!("Download of {} interrupted: {}", url, e);
+                    interrupted = true;
+                    break;
+                }
+                Err(_) => {
+                    last_error = format!("Download of {} stalled: no data for {} seconds", url, IDLE_TIMEOUT.as_secs());
+                    interrupted = true;
+                    break;
+                }
+            };
+            output.write_all(&chunk).await
+                .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+            downloaded_bytes += chunk.len() as u64;
+
+            if downloaded_bytes > max_bytes {
+                drop(output);
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(format!("{} exceeded the {} byte download limit", url, max_bytes));
+            }
+            if downloaded_bytes - last_reported >= PROGRESS_INTERVAL_BYTES {
+                last_reported = downloaded_bytes;
+                on_progress(FileDownloadProgress {
+                    url: url.to_string(),
+                    downloaded_bytes,
+                    total_bytes,
+                    attempt,
+                    done: false,
+                });
+            }
+        }
+        output.flush().await
+            .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        drop(output);
+
+        if interrupted {
+            continue;
+        }
+
+        tokio::fs::rename(&partial, target).await
+            .map_err(|e| format!("Failed to finish {}: {}", target.display(), e))?;
+        on_progress(FileDownloadProgress {
+            url: url.to_string(),
+            downloaded_bytes,
+            total_bytes,
+            attempt,
+            done: true,
+        });
+
+        return Ok(DownloadedFile {
+            path: target.to_path_buf(),
+            size: downloaded_bytes,
+            content_type,
+        });
+    }
+
+    let _ = tokio::fs::remove_file(&partial).await;
+    Err(last_error)
+}
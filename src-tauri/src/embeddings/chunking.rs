@@ -1,4 +1,5 @@
 use super::types::{DocumentChunk, EmbeddingError};
+use crate::language;
 use std::collections::HashMap;
 use uuid::Uuid;
 
@@ -54,9 +55,9 @@ impl DocumentChunker {
             
             // If adding this paragraph would exceed max size, finalize current chunk
             if !current_chunk.is_empty() && 
-               current_chunk.len() + paragraph.len() + 2 > self.strategy.max_chunk_size {
+               char_len(&current_chunk) + char_len(paragraph) + 2 > self.strategy.max_chunk_size {
                 
-                if current_chunk.trim().len() >= self.strategy.min_chunk_size {
+                if char_len(current_chunk.trim()) >= self.strategy.min_chunk_size {
                     chunks.push(self.create_chunk(
                         document_id,
                         &current_chunk,
@@ -78,7 +79,7 @@ impl DocumentChunker {
         }
 
         // Add final chunk if it has content
-        if current_chunk.trim().len() >= self.strategy.min_chunk_size {
+        if char_len(current_chunk.trim()) >= self.strategy.min_chunk_size {
             chunks.push(self.create_chunk(
                 document_id,
                 &current_chunk,
@@ -100,12 +101,15 @@ impl DocumentChunker {
         Ok(chunks)
     }
 
-    /// Create overlap between chunks by taking the last N words from previous chunk
+    /// Create overlap between chunks by taking the last N words from previous chunk, or the
+    /// last sentences when the text doesn't separate words with spaces (Chinese, Japanese)
     fn create_overlap(&self, previous_chunk: &str, new_paragraph: &str) -> String {
         let words: Vec<&str> = previous_chunk.split_whitespace().collect();
         let overlap_words = words.len().saturating_sub(self.strategy.overlap / 10); // Rough word count
         
-        let overlap_text = if overlap_words > 0 && overlap_words < words.len() {
+        let overlap_text = if language::approximate_word_count(previous_chunk) > words.len() * 2 {
+            self.trailing_sentences(previous_chunk)
+        } else if overlap_words > 0 && overlap_words < words.len() {
             words[overlap_words..].join(" ")
         } else {
             String::new()
@@ -118,6 +122,26 @@ impl DocumentChunker {
         }
     }
 
+    // Whole sentences from the end of the chunk, up to the overlap size in characters
+    fn trailing_sentences(&self, chunk: &str) -> String {
+        let sentences = language::split_sentences(chunk);
+        if sentences.len() < 2 {
+            return String::new(); // Repeating the whole chunk isn't overlap
+        }
+
+        let mut kept = Vec::new();
+        let mut length = 0;
+        for sentence in sentences.iter().rev() {
+            length += char_len(sentence);
+            if length > self.strategy.overlap {
+                break;
+            }
+            kept.push(*sentence);
+        }
+        kept.reverse();
+        join_sentences(&kept)
+    }
+
     fn create_chunk(
         &self,
         document_id: &str,
@@ -149,9 +173,9 @@ impl DocumentChunker {
 
         for sentence in sentences {
             if !current_chunk.is_empty() && 
-               current_chunk.len() + sentence.len() + 1 > self.strategy.max_chunk_size {
+               char_len(&current_chunk) + char_len(sentence) + 1 > self.strategy.max_chunk_size {
                 
-                if current_chunk.trim().len() >= self.strategy.min_chunk_size {
+                if char_len(current_chunk.trim()) >= self.strategy.min_chunk_size {
                     chunks.push(self.create_chunk(
                         document_id,
                         &current_chunk,
@@ -162,14 +186,14 @@ impl DocumentChunker {
                 }
                 current_chunk = sentence.to_string();
             } else {
-                if !current_chunk.is_empty() {
+                if !current_chunk.is_empty() && language::needs_space_between(current_chunk.chars().last(), sentence.chars().next()) {
                     current_chunk.push(' ');
                 }
                 current_chunk.push_str(sentence);
             }
         }
 
-        if current_chunk.trim().len() >= self.strategy.min_chunk_size {
+        if char_len(current_chunk.trim()) >= self.strategy.min_chunk_size {
             chunks.push(self.create_chunk(
                 document_id,
                 &current_chunk,
@@ -181,12 +205,143 @@ impl DocumentChunker {
         Ok(chunks)
     }
 
-    /// Simple sentence splitting - could be enhanced with proper NLP
+    /// Sentence splitting that also handles CJK full stops, which aren't followed by a space
     fn split_sentences<'a>(&self, text: &'a str) -> Vec<&'a str> {
-        let sentence_endings = regex::Regex::new(r"[.!?]+\s+").unwrap();
-        sentence_endings
-            .split(text)
-            .filter(|s| !s.trim().is_empty())
-            .collect()
+        language::split_sentences(text)
+    }
+}
+
+// Sizes are measured in characters so a CJK chunk isn't cut at a third of an English one
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn join_sentences(sentences: &[&str]) -> String {
+    let mut joined = String::new();
+    for sentence in sentences {
+        if !joined.is_empty() && language::needs_space_between(joined.chars().last(), sentence.chars().next()) {
+            joined.push(' ');
+        }
+        joined.push_str(sentence);
     }
-} 
\ No newline at end of file
+    joined
+}
+
+/// A `$...$` (inline) or `$$...$$` (display) math span, as char offsets into the text
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MathRegion {
+    pub start: usize,
+    pub end: usize,
+    pub display: bool,
+}
+
+/// Find markdown math spans. Inline math follows pandoc's rule (no space just inside the
+/// dollars, closing `$` not followed by a digit) so prices like "$5 and $10" aren't matched.
+pub fn find_math_regions(text: &str) -> Vec<MathRegion> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut regions = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2; // Skip escaped characters such as \$
+            continue;
+        }
+        if chars[i] != '$' {
+            i += 1;
+            continue;
+        }
+
+        let display = chars.get(i + 1) == Some(&'$');
+        let open_len = if display { 2 } else { 1 };
+        let content_start = i + open_len;
+        let mut j = content_start;
+        let mut close = None;
+
+        while j < chars.len() {
+            if chars[j] == '\\' {
+                j += 2;
+                continue;
+            }
+            if display {
+                if chars[j] == '$' && chars.get(j + 1) == Some(&'$') {
+                    close = Some(j);
+                    break;
+                }
+            } else if chars[j] == '$' {
+                let valid = j > content_start
+                    && !chars[content_start].is_whitespace()
+                    && !chars[j - 1].is_whitespace()
+                    && !chars.get(j + 1).map_or(false, |c| c.is_ascii_digit());
+                if valid {
+                    close = Some(j);
+                }
+                break;
+            } else if chars[j] == '\n' && chars.get(j + 1) == Some(&'\n') {
+                break; // Inline math never spans paragraphs
+            }
+            j += 1;
+        }
+
+        match close {
+            Some(close) => {
+                let end = close + open_len;
+                regions.push(MathRegion { start: i, end, display });
+                i = end;
+            }
+            None => i += open_len,
+        }
+    }
+
+    regions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_inline_and_display_math() {
+        let regions = find_math_regions("Let $x^2$ and $$\\sum_i$$ end");
+        assert_eq!(regions, vec![
+            MathRegion { start: 4, end: 9, display: false },
+            MathRegion { start: 14, end: 24, display: true },
+        ]);
+    }
+
+    #[test]
+    fn ignores_prices_and_escaped_dollars() {
+        assert!(find_math_regions("Cost $5 and $10").is_empty());
+        assert!(find_math_regions("\\$5 and \\$6").is_empty());
+    }
+
+    #[test]
+    fn inline_math_stops_at_paragraph_breaks() {
+        assert!(find_math_regions("$a\n\nb$").is_empty());
+    }
+
+    fn chunker(max_chunk_size: usize, overlap: usize) -> DocumentChunker {
+        DocumentChunker::new(ChunkingStrategy { max_chunk_size, overlap, min_chunk_size: 5 })
+    }
+
+    #[test]
+    fn next_chunk_repeats_the_last_words() {
+        let words = |prefix: &str| (1..=12).map(|i| format!("{}{}", prefix, i)).collect::<Vec<_>>().join(" ");
+        let content = format!("{}\n\n{}", words("one"), words("two"));
+
+        // An overlap of 50 characters is about 5 words
+        let chunks = chunker(100, 50).chunk_document("doc", &content, HashMap::new()).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].content, words("one"));
+        assert_eq!(chunks[1].content, format!("one8 one9 one10 one11 one12\n\n{}", words("two")));
+    }
+
+    #[test]
+    fn cjk_overlap_repeats_whole_sentences() {
+        let content = "线粒体是细胞的动力工厂。它们产生能量。核糖体合成蛋白质。\n\n叶绿体在植物细胞中进行光合作用。";
+
+        let chunks = chunker(40, 20).chunk_document("doc", content, HashMap::new()).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].content, "它们产生能量。核糖体合成蛋白质。\n\n叶绿体在植物细胞中进行光合作用。");
+    }
+}
@@ -1,5 +1,6 @@
 use super::EmbeddingGenerator;
 use async_trait::async_trait;
+use crate::network::{shared_client, HttpPurpose};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
@@ -28,7 +29,7 @@ pub struct OpenAIEmbeddings {
 impl OpenAIEmbeddings {
     pub fn new(api_key: String, model: String) -> Result<Self, Box<dyn std::error::Error>> {
         Ok(Self {
-            client: Client::new(),
+            client: shared_client(HttpPurpose::Ai)?,
             api_key,
             model,
         })
@@ -38,6 +39,7 @@ impl OpenAIEmbeddings {
 #[async_trait]
 impl EmbeddingGenerator for OpenAIEmbeddings {
     async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        crate::network::ensure_online("OpenAI embeddings")?;
         let request = OpenAIEmbeddingRequest {
             input: texts.to_vec(),
             model: self.model.clone(),
@@ -79,7 +81,7 @@ impl OpenAICompatibleEmbeddings {
         // Ensure base_url doesn't end with a slash
         let base_url = base_url.trim_end_matches('/').to_string();
         Ok(Self {
-            client: Client::new(),
+            client: shared_client(HttpPurpose::Ai)?,
             api_key,
             base_url,
             model,
@@ -94,6 +96,7 @@ impl OpenAICompatibleEmbeddings {
 #[async_trait]
 impl EmbeddingGenerator for OpenAICompatibleEmbeddings {
     async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        crate::network::ensure_reachable(&self.base_url, "Embedding endpoint")?;
         let request = OpenAIEmbeddingRequest {
             input: texts.to_vec(),
             model: self.model.clone(),
@@ -148,7 +151,7 @@ pub struct OllamaEmbeddings {
 impl OllamaEmbeddings {
     pub fn new(base_url: String, model: String) -> Result<Self, Box<dyn std::error::Error>> {
         Ok(Self {
-            client: Client::new(),
+            client: shared_client(HttpPurpose::Ai)?,
             base_url,
             model,
         })
@@ -160,6 +163,7 @@ impl OllamaEmbeddings {
 #[async_trait]
 impl EmbeddingGenerator for OllamaEmbeddings {
     async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        crate::network::ensure_reachable(&self.base_url, "Ollama embeddings")?;
         let mut embeddings = Vec::new();
         
         for text in texts {
@@ -197,4 +201,195 @@ impl EmbeddingGenerator for OllamaEmbeddings {
             _ => 384, // Default
         }
     }
-} 
\ No newline at end of file
+} 
+
+// Google Gemini implementation (batchEmbedContents)
+const GEMINI_MAX_BATCH: usize = 100;
+
+#[derive(Serialize)]
+struct GeminiContent {
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Serialize)]
+struct GeminiPart {
+    text: String,
+}
+
+#[derive(Serialize)]
+struct GeminiEmbedRequest {
+    model: String,
+    content: GeminiContent,
+}
+
+#[derive(Serialize)]
+struct GeminiBatchRequest {
+    requests: Vec<GeminiEmbedRequest>,
+}
+
+#[derive(Deserialize)]
+struct GeminiBatchResponse {
+    embeddings: Vec<GeminiEmbedding>,
+}
+
+#[derive(Deserialize)]
+struct GeminiEmbedding {
+    values: Vec<f32>,
+}
+
+pub struct GeminiEmbeddings {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl GeminiEmbeddings {
+    pub fn new(api_key: String, base_url: Option<String>, model: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let base_url = base_url
+            .unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1beta".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        // The API expects "models/<name>"; accept either form from settings
+        let model = model.trim_start_matches("models/").to_string();
+        Ok(Self {
+            client: shared_client(HttpPurpose::Ai)?,
+            api_key,
+            base_url,
+            model,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingGenerator for GeminiEmbeddings {
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        crate::network::ensure_reachable(&self.base_url, "Gemini embeddings")?;
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let model_name = format!("models/{}", self.model);
+
+        for batch in texts.chunks(GEMINI_MAX_BATCH) {
+            let request = GeminiBatchRequest {
+                requests: batch
+                    .iter()
+                    .map(|text| GeminiEmbedRequest {
+                        model: model_name.clone(),
+                        content: GeminiContent { parts: vec![GeminiPart { text: text.clone() }] },
+                    })
+                    .collect(),
+            };
+
+            let response = self
+                .client
+                .post(format!("{}/{}:batchEmbedContents", self.base_url, model_name))
+                .header("x-goog-api-key", &self.api_key)
+                .header("Content-Type", "application/json")
+                .json(&request)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(format!("Gemini HTTP {}: {}", status, error_text).into());
+            }
+
+            let batch_response: GeminiBatchResponse = response.json().await?;
+            embeddings.extend(batch_response.embeddings.into_iter().map(|e| e.values));
+        }
+
+        Ok(embeddings)
+    }
+
+    fn dimensions(&self) -> usize {
+        match self.model.as_str() {
+            "gemini-embedding-001" | "gemini-embedding-exp-03-07" => 3072,
+            "text-embedding-004" | "embedding-001" => 768,
+            _ => 768, // Default
+        }
+    }
+}
+
+// Voyage AI implementation
+#[derive(Serialize)]
+struct VoyageEmbeddingRequest {
+    input: Vec<String>,
+    model: String,
+}
+
+pub struct VoyageEmbeddings {
+    client: Client,
+    api_key: String,
+    base_url: String,
+    model: String,
+}
+
+impl VoyageEmbeddings {
+    pub fn new(api_key: String, base_url: Option<String>, model: String) -> Result<Self, Box<dyn std::error::Error>> {
+        let base_url = base_url
+            .unwrap_or_else(|| "https://api.voyageai.com/v1".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        Ok(Self {
+            client: shared_client(HttpPurpose::Ai)?,
+            api_key,
+            base_url,
+            model,
+        })
+    }
+
+    // Voyage caps inputs per request by model family
+    fn max_batch(&self) -> usize {
+        match self.model.as_str() {
+            "voyage-3-lite" | "voyage-3.5-lite" => 1000,
+            "voyage-2" | "voyage-02" => 72,
+            _ => 128,
+        }
+    }
+}
+
+#[async_trait]
+impl EmbeddingGenerator for VoyageEmbeddings {
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        crate::network::ensure_reachable(&self.base_url, "Voyage embeddings")?;
+        let mut embeddings = Vec::with_capacity(texts.len());
+
+        for batch in texts.chunks(self.max_batch()) {
+            let request = VoyageEmbeddingRequest {
+                input: batch.to_vec(),
+                model: self.model.clone(),
+            };
+
+            let response = self
+                .client
+                .post(format!("{}/embeddings", self.base_url))
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .json(&request)
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                return Err(format!("Voyage HTTP {}: {}", status, error_text).into());
+            }
+
+            // Same response shape as OpenAI: { data: [{ embedding: [...] }] }
+            let embedding_response: OpenAIEmbeddingResponse = response.json().await?;
+            embeddings.extend(embedding_response.data.into_iter().map(|d| d.embedding));
+        }
+
+        Ok(embeddings)
+    }
+
+    fn dimensions(&self) -> usize {
+        match self.model.as_str() {
+            "voyage-3-lite" => 512,
+            "voyage-large-2" | "voyage-code-2" => 1536,
+            "voyage-3" | "voyage-3-large" | "voyage-3.5" | "voyage-3.5-lite" | "voyage-code-3"
+            | "voyage-finance-2" | "voyage-law-2" | "voyage-multilingual-2" | "voyage-2" => 1024,
+            _ => 1024, // Default
+        }
+    }
+}
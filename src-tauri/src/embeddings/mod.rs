@@ -2,6 +2,7 @@ pub mod types;
 pub mod chunking;
 pub mod local; // Re-enable local embeddings for rust-bert fallback
 pub mod cloud;
+pub mod onnx; // fastembed / ONNX Runtime local backend
 pub mod vector;
 
 use async_trait::async_trait;
@@ -30,6 +31,12 @@ pub enum EmbeddingProvider {
     LocalModel,
     #[serde(rename = "ollama")]
     Ollama,
+    #[serde(rename = "gemini")]
+    Gemini,
+    #[serde(rename = "voyage")]
+    Voyage,
+    #[serde(rename = "fastembed")]
+    FastEmbed, // Local ONNX models, see `onnx::supported_models`
     #[serde(rename = "rust-bert")]
     RustBert, // Fallback provider
 }
@@ -40,6 +47,64 @@ pub trait EmbeddingGenerator: Send + Sync {
     fn dimensions(&self) -> usize;
 }
 
+/// Embed one short text and check the returned vector; returns (latency in ms, dimensions)
+pub async fn probe_generator(generator: &dyn EmbeddingGenerator) -> Result<(u64, usize), String> {
+    let started = std::time::Instant::now();
+    let embeddings = generator
+        .generate_embeddings(&["Stellar embedding health check".to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let embedding = embeddings.first().ok_or("Provider returned no embedding")?;
+    if embedding.is_empty() || embedding.iter().any(|v| !v.is_finite()) {
+        return Err("Provider returned an empty or non-finite embedding".to_string());
+    }
+    if embedding.len() != generator.dimensions() {
+        return Err(format!(
+            "Provider returned {} dimensions, expected {}",
+            embedding.len(),
+            generator.dimensions()
+        ));
+    }
+
+    Ok((latency_ms, embedding.len()))
+}
+
+/// Build the generator for `config` and run a real embedding round trip through it
+pub async fn probe_embedding_provider(provider_name: &str, config: &EmbeddingConfig) -> ProviderProbe {
+    let mut probe = ProviderProbe {
+        provider: provider_name.to_string(),
+        model: config.model.clone(),
+        available: false,
+        latency_ms: None,
+        dimensions: None,
+        expected_dimensions: config.dimensions,
+        error: None,
+        has_api_key: None,
+    };
+
+    let generator = match create_embedding_generator(config) {
+        Ok(generator) => generator,
+        Err(e) => {
+            probe.error = Some(e.to_string());
+            return probe;
+        }
+    };
+    probe.expected_dimensions = generator.dimensions();
+
+    match probe_generator(generator.as_ref()).await {
+        Ok((latency_ms, dimensions)) => {
+            probe.available = true;
+            probe.latency_ms = Some(latency_ms);
+            probe.dimensions = Some(dimensions);
+        }
+        Err(e) => probe.error = Some(e),
+    }
+
+    probe
+}
+
 pub fn create_embedding_generator(config: &EmbeddingConfig) -> Result<Box<dyn EmbeddingGenerator>, Box<dyn std::error::Error>> {
     match config.provider {
         EmbeddingProvider::OpenAI => {
@@ -90,6 +155,45 @@ pub fn create_embedding_generator(config: &EmbeddingConfig) -> Result<Box<dyn Em
                 }
             }
         }
+        EmbeddingProvider::FastEmbed => {
+            match onnx::FastEmbedEmbeddings::new(&config.model) {
+                Ok(embeddings) => Ok(Box::new(embeddings)),
+                Err(e) => {
+                    eprintln!("Failed to load fastembed model '{}': {}, falling back to rust-bert", config.model, e);
+                    Ok(Box::new(local::RustBertEmbeddings::new()?))
+                }
+            }
+        }
+        EmbeddingProvider::Gemini => {
+            match config.api_key.as_ref() {
+                Some(api_key) => {
+                    Ok(Box::new(cloud::GeminiEmbeddings::new(
+                        api_key.clone(),
+                        config.base_url.clone(),
+                        config.model.clone(),
+                    )?))
+                }
+                None => {
+                    eprintln!("Gemini API key not provided, falling back to rust-bert");
+                    Ok(Box::new(local::RustBertEmbeddings::new()?))
+                }
+            }
+        }
+        EmbeddingProvider::Voyage => {
+            match config.api_key.as_ref() {
+                Some(api_key) => {
+                    Ok(Box::new(cloud::VoyageEmbeddings::new(
+                        api_key.clone(),
+                        config.base_url.clone(),
+                        config.model.clone(),
+                    )?))
+                }
+                None => {
+                    eprintln!("Voyage API key not provided, falling back to rust-bert");
+                    Ok(Box::new(local::RustBertEmbeddings::new()?))
+                }
+            }
+        }
         EmbeddingProvider::RustBert => {
             Ok(Box::new(local::RustBertEmbeddings::new()?))
         }
@@ -0,0 +1,157 @@
+use super::EmbeddingGenerator;
+use async_trait::async_trait;
+use ::fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A model the fastembed backend can run, with its id as stored in `EmbeddingConfig.model`
+pub struct LocalModelSpec {
+    pub id: &'static str,
+    pub model: EmbeddingModel,
+    pub dimensions: usize,
+    pub description: &'static str,
+}
+
+pub fn supported_models() -> Vec<LocalModelSpec> {
+    vec![
+        LocalModelSpec {
+            id: "bge-small-en-v1.5",
+            model: EmbeddingModel::BGESmallENV15,
+            dimensions: 384,
+            description: "BAAI bge-small, fast English retrieval model (default)",
+        },
+        LocalModelSpec {
+            id: "all-MiniLM-L6-v2",
+            model: EmbeddingModel::AllMiniLML6V2,
+            dimensions: 384,
+            description: "Sentence-transformers MiniLM, smallest and fastest",
+        },
+        LocalModelSpec {
+            id: "bge-base-en-v1.5",
+            model: EmbeddingModel::BGEBaseENV15,
+            dimensions: 768,
+            description: "BAAI bge-base, better quality at roughly 3x the cost of bge-small",
+        },
+        LocalModelSpec {
+            id: "nomic-embed-text-v1.5",
+            model: EmbeddingModel::NomicEmbedTextV15,
+            dimensions: 768,
+            description: "Nomic long-context (8192 tokens) English model",
+        },
+        LocalModelSpec {
+            id: "multilingual-e5-small",
+            model: EmbeddingModel::MultilingualE5Small,
+            dimensions: 384,
+            description: "Multilingual E5, for notes that are not in English",
+        },
+    ]
+}
+
+pub fn find_model(id: &str) -> Option<LocalModelSpec> {
+    let id = id.trim_start_matches("BAAI/").trim_start_matches("sentence-transformers/");
+    supported_models().into_iter().find(|spec| spec.id.eq_ignore_ascii_case(id))
+}
+
+/// Where downloaded ONNX models are cached
+pub fn models_dir() -> Result<PathBuf, String> {
+    let models_dir = crate::models::models_root()?.join("fastembed");
+
+    std::fs::create_dir_all(&models_dir)
+        .map_err(|e| format!("Failed to create models directory: {}", e))?;
+
+    Ok(models_dir)
+}
+
+// fastembed caches through hf-hub, one "models--<org>--<name>" directory per repository
+pub fn model_cache_path(spec: &LocalModelSpec) -> Result<PathBuf, String> {
+    let info = TextEmbedding::get_model_info(&spec.model).map_err(|e| e.to_string())?;
+    Ok(models_dir()?.join(format!("models--{}", info.model_code.replace('/', "--"))))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LocalModelStatus {
+    pub id: String,
+    pub dimensions: usize,
+    pub description: String,
+    pub downloaded: bool,
+    pub size_bytes: u64,
+}
+
+pub fn model_statuses() -> Result<Vec<LocalModelStatus>, String> {
+    supported_models()
+        .iter()
+        .map(|spec| {
+            let path = model_cache_path(spec)?;
+            let size_bytes = crate::models::directory_size(&path);
+            Ok(LocalModelStatus {
+                id: spec.id.to_string(),
+                dimensions: spec.dimensions,
+                description: spec.description.to_string(),
+                downloaded: size_bytes > 0,
+                size_bytes,
+            })
+        })
+        .collect()
+}
+
+/// Download (if needed) and load a model; blocking, so call from `spawn_blocking`
+pub fn load_model(spec: &LocalModelSpec) -> Result<TextEmbedding, String> {
+    // A missing model would be downloaded on load
+    if crate::models::directory_size(&model_cache_path(spec)?) == 0 {
+        crate::network::ensure_online(&format!("Downloading local embedding model '{}'", spec.id))?;
+    }
+    TextEmbedding::try_new(
+        InitOptions::new(spec.model.clone())
+            .with_cache_dir(models_dir()?)
+            .with_show_download_progress(false),
+    )
+    .map_err(|e| format!("Failed to load local embedding model '{}': {}", spec.id, e))
+}
+
+pub fn delete_model(spec: &LocalModelSpec) -> Result<bool, String> {
+    let path = model_cache_path(spec)?;
+    if !path.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_dir_all(&path)
+        .map_err(|e| format!("Failed to delete model '{}': {}", spec.id, e))?;
+    Ok(true)
+}
+
+/// ONNX Runtime embeddings via fastembed
+pub struct FastEmbedEmbeddings {
+    model: Arc<TextEmbedding>,
+    dimensions: usize,
+}
+
+impl FastEmbedEmbeddings {
+    pub fn new(model_name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let spec = find_model(model_name)
+            .ok_or_else(|| format!("Unsupported local embedding model '{}'", model_name))?;
+        println!("🔧 Loading fastembed model {} from {:?}", spec.id, models_dir()?);
+        Ok(Self {
+            model: Arc::new(load_model(&spec)?),
+            dimensions: spec.dimensions,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingGenerator for FastEmbedEmbeddings {
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        let model = self.model.clone();
+        let texts = texts.to_vec();
+
+        // Inference is CPU-bound; keep it off the async runtime
+        let embeddings = tokio::task::spawn_blocking(move || model.embed(texts, None))
+            .await?
+            .map_err(|e| e.to_string())?;
+
+        Ok(embeddings)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+}
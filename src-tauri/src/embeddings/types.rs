@@ -32,6 +32,29 @@ pub struct EmbeddingStats {
     pub average_chunk_size: f32,
 }
 
+/// Result of embedding a probe text with a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderProbe {
+    pub provider: String,
+    pub model: String,
+    pub available: bool,
+    pub latency_ms: Option<u64>,
+    pub dimensions: Option<usize>, // Length of the vector actually returned
+    pub expected_dimensions: usize, // What the generator reports, and the vec table is sized for
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_api_key: Option<bool>,
+}
+
+/// Outcome of `optimize_embeddings_database`; the latest one is kept in app settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingsCompactionReport {
+    pub orphaned_chunks_removed: usize,
+    pub size_before: u64, // Database file plus WAL, in bytes
+    pub size_after: u64,
+    pub optimized_at: chrono::DateTime<chrono::Utc>,
+}
+
 #[derive(Debug)]
 pub enum EmbeddingError {
     ModelError(String),
@@ -1,9 +1,17 @@
-use super::{EmbeddingGenerator, EmbeddingConfig, create_embedding_generator, DocumentChunk, EmbeddingSearchResult};
-use rusqlite::{Connection, Result as SqliteResult, params};
+use super::{EmbeddingGenerator, EmbeddingConfig, create_embedding_generator, DocumentChunk, EmbeddingSearchResult, find_math_regions, probe_generator};
+use rusqlite::{Connection, OptionalExtension, Result as SqliteResult, params};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use moka::policy::EvictionPolicy;
+use moka::sync::Cache;
+use futures_util::{StreamExt, TryStreamExt};
 use sqlite_vec::sqlite3_vec_init;
 
+// Query embeddings kept in memory, and on disk when persistence is on
+const QUERY_CACHE_CAPACITY: u64 = 512;
+const PERSISTED_QUERY_CAPACITY: i64 = 5_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub document_id: String,
@@ -17,10 +25,31 @@ pub struct VectorService {
     conn: Connection,
     embedding_generator: Box<dyn EmbeddingGenerator>,
     dimensions: usize,
+    // Query text -> embedding, so search-as-you-type and repeated questions skip the provider
+    query_cache: Cache<String, Arc<Vec<f32>>>,
+    persist_query_embeddings: bool,
+    model_key: String, // Persisted query embeddings are only reused with the model that made them
 }
 
 impl VectorService {
     pub async fn new(db_path: &str, embedding_config: EmbeddingConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let embedding_generator = create_embedding_generator(&embedding_config)?;
+        let model_key = format!(
+            "{:?}/{}/{}",
+            embedding_config.provider,
+            embedding_config.model,
+            embedding_config.base_url.as_deref().unwrap_or_default()
+        );
+        Self::with_generator(db_path, embedding_generator, model_key)
+    }
+
+    /// Open the embeddings database at `db_path` (":memory:" works) around an existing
+    /// generator, e.g. the mock one in test_support.rs
+    pub fn with_generator(
+        db_path: &str,
+        embedding_generator: Box<dyn EmbeddingGenerator>,
+        model_key: String,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         // Initialize sqlite-vec extension
         unsafe {
             rusqlite::ffi::sqlite3_auto_extension(Some(std::mem::transmute(
@@ -41,7 +70,6 @@ impl VectorService {
             }
         }
         
-        let embedding_generator = create_embedding_generator(&embedding_config)?;
         let dimensions = embedding_generator.dimensions();
         
         // Create vector table - using a compatible structure
@@ -64,21 +92,117 @@ impl VectorService {
             "CREATE INDEX IF NOT EXISTS idx_document_embeddings_document_id ON document_embeddings(document_id)",
             [],
         )?;
+
+        // Older versions probed the provider by embedding a "connection_test" document and
+        // deleting it afterwards, which left rows behind whenever the delete failed
+        conn.execute("DELETE FROM document_embeddings WHERE document_id = 'connection_test'", [])?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS query_embeddings (
+                model_key TEXT NOT NULL,
+                query TEXT NOT NULL,
+                embedding BLOB NOT NULL,
+                last_used_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (model_key, query)
+            )",
+            [],
+        )?;
         
         Ok(Self {
             conn,
             embedding_generator,
             dimensions,
+            query_cache: Cache::builder()
+                .max_capacity(QUERY_CACHE_CAPACITY)
+                .eviction_policy(EvictionPolicy::lru())
+                .build(),
+            persist_query_embeddings: false,
+            model_key,
         })
     }
+
+    /// Keep query embeddings in the database too, so they survive restarts. Turning it off
+    /// removes the ones stored for this model.
+    pub fn set_persist_query_embeddings(&mut self, persist: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if !persist && self.persist_query_embeddings {
+            self.conn.execute("DELETE FROM query_embeddings WHERE model_key = ?", [&self.model_key])?;
+        }
+        self.persist_query_embeddings = persist;
+        Ok(())
+    }
+
+    /// Forget cached query embeddings, in memory and on disk
+    pub fn clear_query_cache(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        self.query_cache.invalidate_all();
+        Ok(self.conn.execute("DELETE FROM query_embeddings", [])?)
+    }
+
+    // Embedding of a search query, from the cache when the same text was embedded before
+    async fn query_embedding(&mut self, query: &str) -> Result<Arc<Vec<f32>>, Box<dyn std::error::Error>> {
+        let key = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some(embedding) = self.query_cache.get(&key) {
+            return Ok(embedding);
+        }
+
+        if self.persist_query_embeddings {
+            let stored: Option<Vec<u8>> = self.conn.query_row(
+                "SELECT embedding FROM query_embeddings WHERE model_key = ? AND query = ?",
+                params![&self.model_key, &key],
+                |row| row.get(0),
+            ).optional()?;
+            if let Some(embedding) = stored.and_then(|bytes| bincode::deserialize::<Vec<f32>>(&bytes).ok()) {
+                self.conn.execute(
+                    "UPDATE query_embeddings SET last_used_at = CURRENT_TIMESTAMP WHERE model_key = ? AND query = ?",
+                    params![&self.model_key, &key],
+                )?;
+                let embedding = Arc::new(embedding);
+                self.query_cache.insert(key, embedding.clone());
+                return Ok(embedding);
+            }
+        }
+
+        let embedding = self.embedding_generator.generate_embeddings(&[key.clone()]).await?
+            .into_iter()
+            .next()
+            .ok_or("Embedding provider returned no embedding")?;
+
+        if self.persist_query_embeddings {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO query_embeddings (model_key, query, embedding) VALUES (?, ?, ?)",
+                params![&self.model_key, &key, bincode::serialize(&embedding)?],
+            )?;
+            self.conn.execute(
+                "DELETE FROM query_embeddings WHERE model_key = ? AND query NOT IN (
+                    SELECT query FROM query_embeddings WHERE model_key = ? ORDER BY last_used_at DESC LIMIT ?
+                )",
+                params![&self.model_key, &self.model_key, PERSISTED_QUERY_CAPACITY],
+            )?;
+        }
+
+        let embedding = Arc::new(embedding);
+        self.query_cache.insert(key, embedding.clone());
+        Ok(embedding)
+    }
     
+    /// Embed a probe text with the configured provider without writing anything to the
+    /// database; returns (latency in ms, dimensions)
+    pub async fn health_check(&self) -> Result<(u64, usize), String> {
+        probe_generator(self.embedding_generator.as_ref()).await
+    }
+
     pub async fn add_document_chunks(&mut self, chunks: &[DocumentChunk]) -> Result<(), Box<dyn std::error::Error>> {
         if chunks.is_empty() {
             return Ok(());
         }
         
+        // Each row holds the text, its metadata and the f32 vector, plus index overhead
+        if let Some(path) = self.conn.path() {
+            let estimated_bytes: usize = chunks.iter().map(|chunk| chunk.content.len() * 2 + self.dimensions * 4 + 512).sum();
+            crate::storage::ensure_space(std::path::Path::new(path), estimated_bytes as u64)?;
+        }
+
         let texts: Vec<String> = chunks.iter().map(|c| c.content.clone()).collect();
-        let embeddings = self.embedding_generator.generate_embeddings(&texts).await?;
+        let embeddings = generate_batched(self.embedding_generator.as_ref(), &texts).await?;
         
         let mut stmt = self.conn.prepare(
             "INSERT OR REPLACE INTO document_embeddings (id, document_id, chunk_text, chunk_index, metadata, embedding) 
@@ -88,13 +212,25 @@ impl VectorService {
         for (chunk, embedding) in chunks.iter().zip(embeddings.iter()) {
             // Convert embedding to bytes for storage
             let embedding_bytes = bincode::serialize(embedding)?;
+
+            // Flag math so search can favour (and the UI can render) formula-bearing chunks
+            let mut metadata = chunk.metadata.clone();
+            let math_regions = find_math_regions(&chunk.content);
+            if !math_regions.is_empty() {
+                metadata.insert("has_math".to_string(), "true".to_string());
+                metadata.insert("math_regions".to_string(), serde_json::to_string(&math_regions)?);
+            }
+            let math_terms = crate::math::index_terms(&chunk.content);
+            if !math_terms.is_empty() {
+                metadata.insert("math_terms".to_string(), math_terms);
+            }
             
             stmt.execute(params![
                 &chunk.id,
                 &chunk.document_id,
                 &chunk.content,
                 &chunk.chunk_index,
-                &serde_json::to_string(&chunk.metadata)?,
+                &serde_json::to_string(&metadata)?,
                 &embedding_bytes,
             ])?;
         }
@@ -104,31 +240,43 @@ impl VectorService {
     }
     
     pub async fn search_similar(&mut self, query: &str, limit: usize, document_ids: Option<&[String]>) -> Result<Vec<EmbeddingSearchResult>, Box<dyn std::error::Error>> {
-        let query_embeddings = self.embedding_generator.generate_embeddings(&[query.to_string()]).await?;
-        let query_embedding = &query_embeddings[0];
+        self.search_filtered(query, limit, document_ids.map(|ids| ("IN", ids))).await
+    }
+
+    /// Search every chunk except those of `excluded_document_ids`
+    pub async fn search_similar_excluding(&mut self, query: &str, limit: usize, excluded_document_ids: &[String]) -> Result<Vec<EmbeddingSearchResult>, Box<dyn std::error::Error>> {
+        if excluded_document_ids.is_empty() {
+            return self.search_filtered(query, limit, None).await;
+        }
+        self.search_filtered(query, limit, Some(("NOT IN", excluded_document_ids))).await
+    }
+
+    // `filter` is an operator ("IN" or "NOT IN") applied to document_id with the given ids
+    async fn search_filtered(&mut self, query: &str, limit: usize, filter: Option<(&str, &[String])>) -> Result<Vec<EmbeddingSearchResult>, Box<dyn std::error::Error>> {
+        let query_embedding = self.query_embedding(query).await?;
+        let query_embedding = query_embedding.as_slice();
         
         // Build the SQL query
-        let (sql, params_vec): (String, Vec<Box<dyn rusqlite::ToSql>>) = if let Some(doc_ids) = document_ids {
+        let (sql, params_vec): (String, Vec<Box<dyn rusqlite::ToSql>>) = if let Some((operator, doc_ids)) = filter {
             let placeholders = doc_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
             let mut query_params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
             for doc_id in doc_ids {
                 query_params.push(Box::new(doc_id.to_string()));
             }
-            query_params.push(Box::new(limit as i64));
             
+            // Every candidate chunk must be scored before the limit is applied below
             (format!(
                 "SELECT id, document_id, chunk_text, chunk_index, metadata, embedding 
                  FROM document_embeddings 
-                 WHERE document_id IN ({})
-                 LIMIT ?",
-                placeholders
+                 WHERE document_id {} ({})",
+                operator, placeholders
             ), query_params)
         } else {
-            (format!(
+            (
                 "SELECT id, document_id, chunk_text, chunk_index, metadata, embedding 
-                 FROM document_embeddings 
-                 LIMIT ?"
-            ), vec![Box::new(limit as i64)])
+                 FROM document_embeddings".to_string(),
+                Vec::new(),
+            )
         };
         
         let mut stmt = self.conn.prepare(&sql)?;
@@ -185,6 +333,59 @@ impl VectorService {
         Ok(())
     }
     
+    /// Remove chunks whose document no longer exists; chunks newer than `min_age_hours` are
+    /// kept since their document may still be mid-import. Returns the number of chunks removed.
+    pub fn delete_orphaned_chunks(&mut self, document_ids: &std::collections::HashSet<String>, min_age_hours: i64) -> Result<usize, Box<dyn std::error::Error>> {
+        let embedded_ids: Vec<String> = {
+            let mut stmt = self.conn.prepare(
+                "SELECT DISTINCT document_id FROM document_embeddings WHERE created_at < datetime('now', ?)"
+            )?;
+            let rows = stmt.query_map(params![format!("-{} hours", min_age_hours)], |row| row.get(0))?;
+            rows.collect::<SqliteResult<Vec<String>>>()?
+        };
+
+        let mut removed = 0;
+        for document_id in embedded_ids.iter().filter(|id| !document_ids.contains(*id)) {
+            removed += self.conn.execute(
+                "DELETE FROM document_embeddings WHERE document_id = ?",
+                params![document_id],
+            )?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Let SQLite refresh its query planner statistics for the embeddings table
+    pub fn optimize(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute_batch("PRAGMA optimize;")?;
+        Ok(())
+    }
+
+    /// Rebuild the database file to reclaim space left by deleted chunks, then refresh statistics
+    pub fn vacuum(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute_batch("VACUUM; ANALYZE;")?;
+        Ok(())
+    }
+
+    /// Fold the write-ahead log back into the database file, before the connection is dropped
+    /// on shutdown
+    pub fn checkpoint(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+        Ok(())
+    }
+
+    /// On-disk size of the database, including its write-ahead log
+    pub fn database_size(&self) -> u64 {
+        let Some(path) = self.conn.path() else {
+            return 0;
+        };
+        [path.to_string(), format!("{}-wal", path)]
+            .iter()
+            .filter_map(|file| std::fs::metadata(file).ok())
+            .map(|metadata| metadata.len())
+            .sum()
+    }
+
     pub fn get_stats(&self) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
         let mut stmt = self.conn.prepare("SELECT COUNT(*) FROM document_embeddings")?;
         let total_chunks: i64 = stmt.query_row([], |row| row.get(0))?;
@@ -249,6 +450,15 @@ impl VectorService {
         }))
     }
     
+    // Semantic similarity between two pieces of text using the configured embedding model
+    pub async fn text_similarity(&mut self, a: &str, b: &str) -> Result<f32, Box<dyn std::error::Error>> {
+        let embeddings = self.embedding_generator.generate_embeddings(&[a.to_string(), b.to_string()]).await?;
+        if embeddings.len() < 2 {
+            return Err("Embedding provider returned too few embeddings".into());
+        }
+        Ok(self.cosine_similarity(&embeddings[0], &embeddings[1]))
+    }
+    
     // Helper function to calculate cosine similarity
     fn cosine_similarity(&self, a: &[f32], b: &[f32]) -> f32 {
         if a.len() != b.len() {
@@ -265,4 +475,16 @@ impl VectorService {
         
         dot_product / (norm_a * norm_b)
     }
-} 
\ No newline at end of file
+} 
+
+// Embed in batches, at most `max_embedding_batches` of them at a time. Errors become strings
+// inside the stream so the future stays Send.
+async fn generate_batched(generator: &dyn EmbeddingGenerator, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+    let max_batches = crate::throttle::resource_settings().max_embedding_batches.max(1);
+    let batches: Vec<Vec<Vec<f32>>> = futures_util::stream::iter(texts.chunks(crate::throttle::EMBEDDING_BATCH_SIZE))
+        .map(|batch| async move { generator.generate_embeddings(batch).await.map_err(|e| e.to_string()) })
+        .buffered(max_batches)
+        .try_collect()
+        .await?;
+    Ok(batches.into_iter().flatten().collect())
+}
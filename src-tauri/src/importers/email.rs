@@ -0,0 +1,132 @@
+use std::path::Path;
+use mail_parser::{Address, MessageParser};
+use mail_parser::mailbox::mbox::MessageIterator;
+
+use super::html;
+
+/// A single message from an .eml file or .mbox archive, rendered as markdown
+#[derive(Debug, Clone)]
+pub struct ParsedEmail {
+    pub subject: Option<String>,
+    pub from: Option<String>, // "Name <address>" or just the address
+    pub from_address: Option<String>,
+    pub to: Vec<String>,
+    pub date: Option<String>, // RFC3339
+    pub message_id: Option<String>,
+    pub content: String,
+}
+
+impl ParsedEmail {
+    /// Tags applied to imported messages: "email", the sender and the day it was sent
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = vec!["email".to_string()];
+        if let Some(address) = &self.from_address {
+            tags.push(format!("from:{}", address.to_lowercase()));
+        }
+        if let Some(date) = self.date.as_deref().and_then(|d| d.get(..10)) {
+            tags.push(format!("date:{}", date));
+        }
+        tags
+    }
+
+    pub fn metadata(&self) -> serde_json::Value {
+        serde_json::json!({
+            "from": self.from,
+            "to": self.to,
+            "date": self.date,
+            "message_id": self.message_id,
+        })
+    }
+}
+
+fn format_addresses(address: Option<&Address<'_>>) -> Vec<String> {
+    address
+        .map(|address| {
+            address
+                .iter()
+                .map(|addr| match (addr.name(), addr.address()) {
+                    (Some(name), Some(email)) => format!("{} <{}>", name, email),
+                    (None, Some(email)) => email.to_string(),
+                    (Some(name), None) => name.to_string(),
+                    (None, None) => String::new(),
+                })
+                .filter(|formatted| !formatted.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse a raw RFC 5322 message. The body prefers the plain-text part and falls back to
+/// the HTML part run through the article extractor.
+pub fn parse_email(raw: &[u8]) -> Option<ParsedEmail> {
+    let message = MessageParser::default().parse(raw)?;
+
+    let from = format_addresses(message.from()).into_iter().next();
+    let from_address = message
+        .from()
+        .and_then(|address| address.first())
+        .and_then(|addr| addr.address())
+        .map(str::to_string);
+    let to = format_addresses(message.to());
+    let date = message.date().map(|date| date.to_rfc3339());
+    let subject = message.subject().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let text_body = (0..message.text_body_count())
+        .filter_map(|index| message.body_text(index))
+        .map(|body| body.replace("\r\n", "\n"))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let body = if text_body.trim().is_empty() {
+        message
+            .body_html(0)
+            .map(|html_body| html::extract_article(&html_body).markdown)
+            .unwrap_or_default()
+    } else {
+        text_body
+    };
+
+    let mut content = String::new();
+    if let Some(subject) = &subject {
+        content.push_str(&format!("# {}\n\n", subject));
+    }
+    if let Some(from) = &from {
+        content.push_str(&format!("**From:** {}  \n", from));
+    }
+    if !to.is_empty() {
+        content.push_str(&format!("**To:** {}  \n", to.join(", ")));
+    }
+    if let Some(date) = &date {
+        content.push_str(&format!("**Date:** {}  \n", date));
+    }
+    content.push('\n');
+    content.push_str(body.trim());
+
+    Some(ParsedEmail {
+        subject,
+        from,
+        from_address,
+        to,
+        date,
+        message_id: message.message_id().map(str::to_string),
+        content,
+    })
+}
+
+/// Read every message in an mbox archive, skipping ones that fail to parse
+pub fn read_mailbox(path: &Path) -> Result<Vec<ParsedEmail>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open mailbox: {}", e))?;
+    let mut messages = Vec::new();
+    let mut skipped = 0;
+
+    for message in MessageIterator::new(std::io::BufReader::new(file)) {
+        match message.ok().and_then(|message| parse_email(message.contents())) {
+            Some(parsed) => messages.push(parsed),
+            None => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        eprintln!("⚠️ Skipped {} unreadable messages in {}", skipped, path.display());
+    }
+    Ok(messages)
+}
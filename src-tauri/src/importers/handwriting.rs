@@ -0,0 +1,190 @@
+//! Handwritten notes from phone photos, read either by a vision-capable chat model or by a
+//! local TrOCR script. Both give markdown plus a 0-1 confidence, so the UI can point out
+//! pages worth proofreading.
+
+use image::{imageops::FilterType, DynamicImage, ImageOutputFormat};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use crate::ai::reading::json_object;
+use crate::ai::types::ChatMessage;
+
+// Phone photos are 12MP and up; models read handwriting fine at this size and requests stay small
+const MAX_IMAGE_SIDE: u32 = 2048;
+const LOCAL_OCR_TIMEOUT_SECS: u64 = 600;
+/// Marker the model is asked to put where it can't read the writing
+pub const ILLEGIBLE_MARKER: &str = "[illegible]";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandwritingEngine {
+    #[default]
+    Vision, // A vision-capable chat model (GPT-4o, Claude, Gemini, llava on Ollama)
+    Trocr, // Microsoft's TrOCR through a local script; nothing leaves the machine
+}
+
+impl HandwritingEngine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HandwritingEngine::Vision => "vision",
+            HandwritingEngine::Trocr => "trocr",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HandwritingTranscription {
+    #[serde(default)]
+    pub title: Option<String>, // A heading written on the page, when there is one
+    #[serde(default)]
+    pub markdown: String,
+    #[serde(default)]
+    pub confidence: f32, // 0-1
+}
+
+/// The photo scaled down to MAX_IMAGE_SIDE and re-encoded as JPEG, with its MIME type. The
+/// original stays untouched; this copy is only sent for recognition.
+pub fn prepare_image(path: &Path) -> Result<(Vec<u8>, String), String> {
+    let image = image::open(path)
+        .map_err(|e| format!("Failed to open image '{}': {}", path.display(), e))?;
+    let image = if image.width().max(image.height()) > MAX_IMAGE_SIDE {
+        image.resize(MAX_IMAGE_SIDE, MAX_IMAGE_SIDE, FilterType::Triangle)
+    } else {
+        image
+    };
+
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Jpeg(85))
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok((bytes, "image/jpeg".to_string()))
+}
+
+pub fn build_handwriting_messages() -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You transcribe photographed handwritten study notes into clean markdown. Respond with JSON only, no prose and no code fences.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Transcribe the handwritten notes in this photo exactly, without adding or correcting content. Keep the structure: \
+                 headings for titles and underlined headers, bullet lists for listed points, numbered lists where the writer numbered, \
+                 LaTeX between $ signs for formulas, and a markdown table for anything drawn as a table. Describe diagrams briefly in \
+                 italics. Write {} for words you cannot read. Give your confidence that the transcription is accurate, from 0 to 1.\n\
+                 Respond with a JSON object of the form \
+                 {{\"title\": \"heading on the page, or null\", \"markdown\": \"...\", \"confidence\": 0.9}}.",
+                ILLEGIBLE_MARKER
+            ),
+        },
+    ]
+}
+
+/// Parse the model's transcription. Its own confidence is lowered by the share of words it
+/// marked illegible, since models tend to overrate themselves.
+pub fn parse_handwriting_response(text: &str) -> Result<HandwritingTranscription, String> {
+    let mut transcription: HandwritingTranscription = serde_json::from_str(json_object(text)?)
+        .map_err(|e| format!("Failed to parse handwriting transcription JSON: {}", e))?;
+    transcription.markdown = transcription.markdown.replace("\r\n", "\n").trim().to_string();
+    if transcription.markdown.is_empty() {
+        return Err("No handwriting was recognised in the photo".to_string());
+    }
+    transcription.title = transcription.title
+        .map(|title| title.trim().trim_start_matches('#').trim().to_string())
+        .filter(|title| !title.is_empty() && title != "null");
+
+    let words = transcription.markdown.split_whitespace().count().max(1) as f32;
+    let illegible = transcription.markdown.matches(ILLEGIBLE_MARKER).count() as f32;
+    transcription.confidence = transcription.confidence.clamp(0.0, 1.0) * (1.0 - illegible / words).max(0.0);
+    Ok(transcription)
+}
+
+/// Resolve the TrOCR runner: STELLAR_TROCR_BIN, then the venv from scripts/setup_trocr.sh
+pub fn resolve_trocr_command() -> PathBuf {
+    if let Ok(explicit_command) = std::env::var("STELLAR_TROCR_BIN") {
+        let explicit_path = PathBuf::from(explicit_command);
+        if explicit_path.exists() {
+            return explicit_path;
+        }
+    }
+
+    let candidates = [
+        PathBuf::from("trocr_env/bin/stellar-trocr"),
+        PathBuf::from("../trocr_env/bin/stellar-trocr"),
+        PathBuf::from("trocr_env/Scripts/stellar-trocr.exe"),
+        PathBuf::from("../trocr_env/Scripts/stellar-trocr.exe"),
+    ];
+
+    for candidate in candidates {
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+
+    PathBuf::from("stellar-trocr")
+}
+
+#[derive(Debug, Deserialize)]
+struct TrocrOutput {
+    lines: Vec<TrocrLine>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrocrLine {
+    text: String,
+    confidence: f32,
+}
+
+/// Read the photo with the local TrOCR runner, which prints the recognised lines as
+/// `{"lines": [{"text": "...", "confidence": 0.93}]}`. Confidence is the mean over lines,
+/// weighted by their length.
+pub async fn transcribe_locally(path: &Path) -> Result<HandwritingTranscription, String> {
+    let trocr_command = resolve_trocr_command();
+    let mut cmd = tokio::process::Command::new(&trocr_command);
+    cmd.arg(path);
+    crate::throttle::lower_priority(&mut cmd);
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(LOCAL_OCR_TIMEOUT_SECS), cmd.output())
+        .await
+        .map_err(|_| format!("Handwriting recognition timed out for '{}'", path.display()))?
+        .map_err(|e| {
+            let details = format!("Failed to run TrOCR at '{}': {}", trocr_command.display(), e);
+            if e.kind() == std::io::ErrorKind::NotFound {
+                format!("{}. Install it with ./scripts/setup_trocr.sh or set STELLAR_TROCR_BIN.", details)
+            } else {
+                details
+            }
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Handwriting recognition failed for '{}': {}", path.display(), stderr.trim()));
+    }
+
+    let parsed: TrocrOutput = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse TrOCR output: {}", e))?;
+    trocr_transcription(parsed.lines)
+}
+
+// TrOCR reads line by line and knows nothing of layout, so lines become paragraphs as written
+fn trocr_transcription(lines: Vec<TrocrLine>) -> Result<HandwritingTranscription, String> {
+    let lines: Vec<TrocrLine> = lines.into_iter()
+        .filter(|line| !line.text.trim().is_empty())
+        .collect();
+    if lines.is_empty() {
+        return Err("No handwriting was recognised in the photo".to_string());
+    }
+
+    let total_chars: usize = lines.iter().map(|line| line.text.trim().len()).sum();
+    let confidence = lines.iter()
+        .map(|line| line.confidence.clamp(0.0, 1.0) * line.text.trim().len() as f32)
+        .sum::<f32>() / total_chars.max(1) as f32;
+
+    Ok(HandwritingTranscription {
+        title: None,
+        markdown: lines.iter().map(|line| line.text.trim()).collect::<Vec<_>>().join("\n\n"),
+        confidence,
+    })
+}
@@ -0,0 +1,289 @@
+use scraper::{ElementRef, Html, Node, Selector};
+
+// Elements that never hold article text
+const SKIPPED_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "canvas", "iframe", "form", "button",
+    "nav", "header", "footer", "aside", "head",
+];
+
+// class/id fragments that mark boilerplate blocks (cookie banners, share bars, comments...)
+const BOILERPLATE_HINTS: &[&str] = &[
+    "cookie", "banner", "share", "social", "comment", "related", "sidebar", "advert", "promo",
+    "newsletter", "subscribe", "breadcrumb", "menu", "popup", "modal",
+];
+
+/// Readable content pulled out of an HTML page
+#[derive(Debug, Clone)]
+pub struct HtmlArticle {
+    pub title: Option<String>,
+    pub markdown: String,
+}
+
+/// Extract the main article of an HTML page as markdown, readability-style: prefer
+/// <article>/<main>, otherwise the block with the most paragraph text, and drop navigation,
+/// scripts and boilerplate along the way.
+pub fn extract_article(html: &str) -> HtmlArticle {
+    let document = Html::parse_document(html);
+
+    let title = select_first_text(&document, "meta[property='og:title']", Some("content"))
+        .or_else(|| select_first_text(&document, "title", None))
+        .or_else(|| select_first_text(&document, "h1", None));
+
+    let root = find_main_content(&document);
+    let mut markdown = String::new();
+    if let Some(root) = root {
+        render_children(root, &mut markdown, &mut RenderState::default());
+    }
+
+    HtmlArticle {
+        title,
+        markdown: tidy_markdown(&markdown),
+    }
+}
+
+fn select_first_text(document: &Html, selector: &str, attr: Option<&str>) -> Option<String> {
+    let selector = Selector::parse(selector).ok()?;
+    let element = document.select(&selector).next()?;
+    let text = match attr {
+        Some(attr) => element.value().attr(attr)?.to_string(),
+        None => element.text().collect::<String>(),
+    };
+    let text = collapse_whitespace(&text);
+    if text.is_empty() { None } else { Some(text) }
+}
+
+fn find_main_content(document: &Html) -> Option<ElementRef<'_>> {
+    for candidate in ["article", "main", "[role='main']", "#content", ".post-content", ".entry-content"] {
+        if let Ok(selector) = Selector::parse(candidate) {
+            let best = document
+                .select(&selector)
+                .max_by_key(|element| paragraph_text_len(*element));
+            if let Some(element) = best {
+                if paragraph_text_len(element) > 200 {
+                    return Some(element);
+                }
+            }
+        }
+    }
+
+    // Score blocks by the paragraph text directly inside them
+    let blocks = Selector::parse("div, section, td").ok()?;
+    let best_block = document
+        .select(&blocks)
+        .filter(|element| !is_boilerplate(element))
+        .map(|element| {
+            let direct: usize = element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|child| child.value().name() == "p")
+                .map(|p| p.text().map(str::len).sum::<usize>())
+                .sum();
+            (element, direct)
+        })
+        .max_by_key(|(_, score)| *score)
+        .filter(|(_, score)| *score > 200)
+        .map(|(element, _)| element);
+
+    best_block.or_else(|| {
+        let body = Selector::parse("body").ok()?;
+        document.select(&body).next()
+    })
+}
+
+fn paragraph_text_len(element: ElementRef<'_>) -> usize {
+    Selector::parse("p")
+        .map(|p| element.select(&p).map(|p| p.text().map(str::len).sum::<usize>()).sum())
+        .unwrap_or(0)
+}
+
+fn is_boilerplate(element: &ElementRef<'_>) -> bool {
+    let value = element.value();
+    let markers = format!(
+        "{} {}",
+        value.attr("class").unwrap_or_default(),
+        value.attr("id").unwrap_or_default()
+    )
+    .to_lowercase();
+    BOILERPLATE_HINTS.iter().any(|hint| markers.contains(hint))
+        || value.attr("aria-hidden") == Some("true")
+        || value.attr("hidden").is_some()
+}
+
+#[derive(Default)]
+struct RenderState {
+    list_depth: usize,
+    in_pre: bool,
+}
+
+fn render_children(element: ElementRef<'_>, out: &mut String, state: &mut RenderState) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                if state.in_pre {
+                    out.push_str(text);
+                } else {
+                    let collapsed = collapse_inline(text);
+                    if !collapsed.is_empty() {
+                        if collapsed.starts_with(' ') && out.ends_with([' ', '\n']) {
+                            out.push_str(collapsed.trim_start());
+                        } else {
+                            out.push_str(&collapsed);
+                        }
+                    }
+                }
+            }
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    render_element(child, out, state);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render_element(element: ElementRef<'_>, out: &mut String, state: &mut RenderState) {
+    let name = element.value().name();
+    if SKIPPED_TAGS.contains(&name) || is_boilerplate(&element) {
+        return;
+    }
+
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse::<usize>().unwrap_or(1);
+            let text = collapse_whitespace(&element.text().collect::<String>());
+            if !text.is_empty() {
+                out.push_str(&format!("\n\n{} {}\n\n", "#".repeat(level), text));
+            }
+        }
+        "p" | "div" | "section" | "article" | "main" | "figure" | "figcaption" | "dl" | "table" => {
+            out.push_str("\n\n");
+            render_children(element, out, state);
+            out.push_str("\n\n");
+        }
+        "tr" => {
+            out.push('\n');
+            render_children(element, out, state);
+        }
+        "td" | "th" => {
+            out.push_str(" | ");
+            render_children(element, out, state);
+        }
+        "br" => out.push('\n'),
+        "hr" => out.push_str("\n\n---\n\n"),
+        "ul" | "ol" => {
+            state.list_depth += 1;
+            out.push('\n');
+            let ordered = name == "ol";
+            for (index, item) in element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .filter(|child| child.value().name() == "li")
+                .enumerate()
+            {
+                let marker = if ordered { format!("{}.", index + 1) } else { "-".to_string() };
+                out.push_str(&format!("\n{}{} ", "  ".repeat(state.list_depth - 1), marker));
+                render_children(item, out, state);
+            }
+            state.list_depth -= 1;
+            out.push('\n');
+        }
+        "pre" => {
+            let was_in_pre = state.in_pre;
+            state.in_pre = true;
+            out.push_str("\n\n```\n");
+            render_children(element, out, state);
+            out.push_str("\n```\n\n");
+            state.in_pre = was_in_pre;
+        }
+        "code" if !state.in_pre => {
+            out.push('`');
+            render_children(element, out, state);
+            out.push('`');
+        }
+        "blockquote" => {
+            let mut inner = String::new();
+            render_children(element, &mut inner, state);
+            let quoted = tidy_markdown(&inner)
+                .lines()
+                .map(|line| format!("> {}", line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            out.push_str(&format!("\n\n{}\n\n", quoted));
+        }
+        "strong" | "b" => wrap_inline(element, out, state, "**"),
+        "em" | "i" => wrap_inline(element, out, state, "*"),
+        "a" => {
+            let mut text = String::new();
+            render_children(element, &mut text, state);
+            let text = text.trim().to_string();
+            match element.value().attr("href") {
+                Some(href) if !text.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:") => {
+                    out.push_str(&format!("[{}]({})", text, href));
+                }
+                _ => out.push_str(&text),
+            }
+        }
+        "img" => {
+            if let Some(alt) = element.value().attr("alt").filter(|alt| !alt.trim().is_empty()) {
+                out.push_str(&format!("![{}]({})", alt.trim(), element.value().attr("src").unwrap_or_default()));
+            }
+        }
+        _ => render_children(element, out, state),
+    }
+}
+
+fn wrap_inline(element: ElementRef<'_>, out: &mut String, state: &mut RenderState, marker: &str) {
+    let mut inner = String::new();
+    render_children(element, &mut inner, state);
+    let trimmed = inner.trim();
+    if !trimmed.is_empty() {
+        out.push_str(&format!("{}{}{}", marker, trimmed, marker));
+    }
+}
+
+fn collapse_inline(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut last_was_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !last_was_space {
+                collapsed.push(' ');
+            }
+            last_was_space = true;
+        } else {
+            collapsed.push(c);
+            last_was_space = false;
+        }
+    }
+    collapsed
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Trim trailing spaces and collapse runs of blank lines (code block contents included)
+fn tidy_markdown(markdown: &str) -> String {
+    let mut result = String::new();
+    let mut blank_lines = 0;
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines == 1 && !result.is_empty() {
+                result.push('\n');
+            }
+            continue;
+        }
+        blank_lines = 0;
+        // Table cells are rendered as " | cell", so rows need their leading space dropped
+        if line.trim_start().starts_with('|') {
+            result.push_str(line.trim_start());
+        } else {
+            result.push_str(line);
+        }
+        result.push('\n');
+    }
+    result.trim().to_string()
+}
@@ -0,0 +1,430 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+// Environments whose contents are display math and are kept verbatim inside $$...$$
+const MATH_ENVIRONMENTS: &[&str] = &[
+    "equation", "equation*", "align", "align*", "gather", "gather*", "multline", "multline*",
+    "eqnarray", "eqnarray*", "displaymath", "math", "flalign", "flalign*",
+];
+
+// Environments dropped entirely
+const SKIPPED_ENVIRONMENTS: &[&str] = &["figure", "figure*", "tikzpicture", "thebibliography", "comment"];
+
+/// Markdown converted from a LaTeX project
+#[derive(Debug, Clone)]
+pub struct LatexDocument {
+    pub title: Option<String>,
+    pub markdown: String,
+    pub included_files: Vec<PathBuf>,
+}
+
+/// Convert a .tex file (following \input/\include relative to it) to markdown. Math is
+/// preserved as `$...$` / `$$...$$` with the LaTeX source untouched.
+pub fn convert_latex_file(path: &Path) -> Result<LatexDocument, String> {
+    let mut included_files = Vec::new();
+    let mut visited = HashSet::new();
+    let source = read_with_includes(path, &mut visited, &mut included_files, 0)?;
+
+    let title = extract_command_argument(&source, "title").map(|t| clean_inline(&t));
+    let body = match (source.find("\\begin{document}"), source.find("\\end{document}")) {
+        (Some(start), Some(end)) if end > start => &source[start + "\\begin{document}".len()..end],
+        (Some(start), None) => &source[start + "\\begin{document}".len()..],
+        _ => source.as_str(),
+    };
+
+    Ok(LatexDocument {
+        title,
+        markdown: latex_to_markdown(body),
+        included_files,
+    })
+}
+
+fn read_with_includes(
+    path: &Path,
+    visited: &mut HashSet<PathBuf>,
+    included_files: &mut Vec<PathBuf>,
+    depth: usize,
+) -> Result<String, String> {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if depth > 16 || !visited.insert(canonical) {
+        return Ok(String::new()); // Include cycle or runaway nesting
+    }
+
+    let source = strip_comments(
+        &std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+    );
+    let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let include_regex = regex::Regex::new(r"\\(?:input|include|subfile)\{([^}]+)\}").map_err(|e| e.to_string())?;
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for captures in include_regex.captures_iter(&source) {
+        let whole = captures.get(0).unwrap();
+        result.push_str(&source[last..whole.start()]);
+        last = whole.end();
+
+        let mut include_path = base_dir.join(captures[1].trim());
+        if include_path.extension().is_none() {
+            include_path.set_extension("tex");
+        }
+        if include_path.exists() {
+            included_files.push(include_path.clone());
+            result.push('\n');
+            result.push_str(&read_with_includes(&include_path, visited, included_files, depth + 1)?);
+            result.push('\n');
+        } else {
+            eprintln!("⚠️ LaTeX include not found: {}", include_path.display());
+        }
+    }
+    result.push_str(&source[last..]);
+
+    Ok(result)
+}
+
+/// Drop `%` comments, keeping escaped `\%`
+fn strip_comments(source: &str) -> String {
+    source
+        .lines()
+        .map(|line| {
+            let mut previous = '\0';
+            for (index, c) in line.char_indices() {
+                if c == '%' && previous != '\\' {
+                    return &line[..index];
+                }
+                previous = c;
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Argument of the first `\name{...}`, honouring nested braces
+fn extract_command_argument(source: &str, name: &str) -> Option<String> {
+    let start = source.find(&format!("\\{}{{", name))? + name.len() + 2;
+    let (argument, _) = read_braced(&source[start..])?;
+    Some(argument)
+}
+
+/// Read up to the brace closing an already-opened group; returns (contents, bytes consumed)
+fn read_braced(text: &str) -> Option<(String, usize)> {
+    let mut depth = 1;
+    let mut previous = '\0';
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' if previous != '\\' => depth += 1,
+            '}' if previous != '\\' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((text[..index].to_string(), index + 1));
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+/// Convert a LaTeX document body to markdown
+pub fn latex_to_markdown(body: &str) -> String {
+    let mut out = String::new();
+    let mut rest = body;
+    let mut list_stack: Vec<(bool, usize)> = Vec::new(); // (ordered, next item number)
+
+    while !rest.is_empty() {
+        // Display math: \[...\] and $$...$$
+        if let Some(after) = rest.strip_prefix("\\[") {
+            if let Some(end) = after.find("\\]") {
+                push_display_math(&mut out, &after[..end]);
+                rest = &after[end + 2..];
+                continue;
+            }
+        }
+        if let Some(after) = rest.strip_prefix("$$") {
+            if let Some(end) = after.find("$$") {
+                push_display_math(&mut out, &after[..end]);
+                rest = &after[end + 2..];
+                continue;
+            }
+        }
+        // Inline math: \(...\) and $...$ are copied through untouched
+        if let Some(after) = rest.strip_prefix("\\(") {
+            if let Some(end) = after.find("\\)") {
+                out.push_str(&format!("${}$", after[..end].trim()));
+                rest = &after[end + 2..];
+                continue;
+            }
+        }
+        if rest.starts_with('$') {
+            if let Some(end) = rest[1..].find('$') {
+                out.push_str(&rest[..end + 2]);
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+
+        if rest.starts_with("\\begin{") {
+            if let Some((name, consumed)) = read_braced(&rest[7..]) {
+                let after_begin = &rest[7 + consumed..];
+                let end_tag = format!("\\end{{{}}}", name);
+
+                if MATH_ENVIRONMENTS.contains(&name.as_str()) {
+                    if let Some(end) = after_begin.find(&end_tag) {
+                        // Keep the environment so alignment (&, \\) still renders in KaTeX/MathJax
+                        let math = if name.starts_with("equation") || name == "displaymath" || name == "math" {
+                            after_begin[..end].to_string()
+                        } else {
+                            format!("\\begin{{{}}}{}\\end{{{}}}", name, &after_begin[..end], name)
+                        };
+                        push_display_math(&mut out, &math);
+                        rest = &after_begin[end + end_tag.len()..];
+                        continue;
+                    }
+                }
+                if SKIPPED_ENVIRONMENTS.contains(&name.as_str()) {
+                    rest = after_begin.find(&end_tag).map(|end| &after_begin[end + end_tag.len()..]).unwrap_or("");
+                    continue;
+                }
+                if name == "verbatim" || name == "lstlisting" || name == "minted" {
+                    if let Some(end) = after_begin.find(&end_tag) {
+                        let code = after_begin[..end].trim_start_matches(|c| c != '\n').trim_matches('\n');
+                        out.push_str(&format!("\n\n```\n{}\n```\n\n", code));
+                        rest = &after_begin[end + end_tag.len()..];
+                        continue;
+                    }
+                }
+
+                match name.as_str() {
+                    "itemize" => list_stack.push((false, 1)),
+                    "enumerate" => list_stack.push((true, 1)),
+                    "abstract" => out.push_str("\n\n**Abstract.** "),
+                    "quote" | "quotation" => out.push_str("\n\n> "),
+                    "theorem" | "lemma" | "proposition" | "corollary" | "definition" | "proof" | "example" | "remark" => {
+                        let mut label = name.clone();
+                        label[..1].make_ascii_uppercase();
+                        out.push_str(&format!("\n\n**{}.** ", label));
+                    }
+                    _ => {}
+                }
+                rest = after_begin;
+                continue;
+            }
+        }
+
+        if rest.starts_with("\\end{") {
+            if let Some((name, consumed)) = read_braced(&rest[5..]) {
+                if name == "itemize" || name == "enumerate" {
+                    list_stack.pop();
+                }
+                out.push_str("\n\n");
+                rest = &rest[5 + consumed..];
+                continue;
+            }
+        }
+
+        if let Some(after) = rest.strip_prefix("\\item") {
+            let indent = "  ".repeat(list_stack.len().saturating_sub(1));
+            let marker = match list_stack.last_mut() {
+                Some((true, number)) => {
+                    *number += 1;
+                    format!("{}.", *number - 1)
+                }
+                _ => "-".to_string(),
+            };
+            // \item[label] overrides the marker text
+            let after = after.trim_start();
+            let (marker, after) = match after.strip_prefix('[').and_then(|a| a.find(']').map(|end| (a, end))) {
+                Some((a, end)) => (format!("- **{}**", clean_inline(&a[..end])), &a[end + 1..]),
+                None => (marker, after),
+            };
+            out.push_str(&format!("\n{}{} ", indent, marker));
+            rest = after;
+            continue;
+        }
+
+        if let Some((markdown, consumed)) = convert_command(rest) {
+            out.push_str(&markdown);
+            rest = &rest[consumed..];
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        match c {
+            '~' => out.push(' '),
+            '{' | '}' => {}
+            _ => out.push(c),
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+
+    tidy(&out)
+}
+
+fn push_display_math(out: &mut String, math: &str) {
+    // Blank lines inside $$ would break paragraph-based chunking and markdown renderers
+    let math = math
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    out.push_str(&format!("\n\n$$\n{}\n$$\n\n", math.trim()));
+}
+
+/// Convert a single `\command{...}` at the start of `text`; returns (markdown, bytes consumed)
+fn convert_command(text: &str) -> Option<(String, usize)> {
+    let rest = text.strip_prefix('\\')?;
+
+    // Escaped characters and line breaks
+    if let Some(c) = rest.chars().next() {
+        if "%$&#_{}".contains(c) {
+            return Some((c.to_string(), 1 + c.len_utf8()));
+        }
+        if c == '\\' {
+            return Some(("\n".to_string(), 2));
+        }
+    }
+
+    let name_len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+    if name_len == 0 {
+        return None;
+    }
+    let name = &rest[..name_len];
+    let mut consumed = 1 + name_len;
+
+    // Starred variants (\section*) behave the same here
+    if rest[name_len..].starts_with('*') {
+        consumed += 1;
+    }
+    // Skip an optional [..] argument
+    if text[consumed..].starts_with('[') {
+        if let Some(end) = text[consumed..].find(']') {
+            consumed += end + 1;
+        }
+    }
+
+    let mut argument = None;
+    if text[consumed..].starts_with('{') {
+        if let Some((arg, arg_len)) = read_braced(&text[consumed + 1..]) {
+            argument = Some(arg);
+            consumed += 1 + arg_len;
+        }
+    }
+
+    let heading = |level: usize, arg: &Option<String>| {
+        format!("\n\n{} {}\n\n", "#".repeat(level), clean_inline(arg.as_deref().unwrap_or_default()))
+    };
+
+    let markdown = match name {
+        "part" | "chapter" => heading(1, &argument),
+        "section" => heading(2, &argument),
+        "subsection" => heading(3, &argument),
+        "subsubsection" => heading(4, &argument),
+        "paragraph" | "subparagraph" => format!("\n\n**{}** ", clean_inline(argument.as_deref().unwrap_or_default())),
+        "textbf" => format!("**{}**", latex_to_markdown_inline(argument.as_deref().unwrap_or_default())),
+        "emph" | "textit" => format!("*{}*", latex_to_markdown_inline(argument.as_deref().unwrap_or_default())),
+        "texttt" | "verb" => format!("`{}`", argument.unwrap_or_default()),
+        "underline" | "text" | "textrm" | "textsf" | "mbox" => latex_to_markdown_inline(argument.as_deref().unwrap_or_default()),
+        "footnote" => format!(" ({})", latex_to_markdown_inline(argument.as_deref().unwrap_or_default())),
+        "href" => {
+            let url = argument.unwrap_or_default();
+            let (label, label_len) = text[consumed..]
+                .strip_prefix('{')
+                .and_then(read_braced)
+                .unwrap_or_default();
+            consumed += if label_len > 0 { 1 + label_len } else { 0 };
+            format!("[{}]({})", latex_to_markdown_inline(&label), url)
+        }
+        "url" => argument.unwrap_or_default(),
+        "cite" | "citep" | "citet" => format!("[{}]", argument.unwrap_or_default()),
+        "ref" | "eqref" | "autoref" | "cref" => format!("({})", argument.unwrap_or_default()),
+        "maketitle" | "label" | "tableofcontents" | "newpage" | "clearpage" | "centering"
+        | "noindent" | "vspace" | "hspace" | "includegraphics" | "bibliography"
+        | "bibliographystyle" | "usepackage" | "documentclass" | "title" | "author" | "date"
+        | "newcommand" | "renewcommand" | "setlength" | "caption" | "medskip" | "bigskip" | "smallskip" => String::new(),
+        "LaTeX" => "LaTeX".to_string(),
+        "TeX" => "TeX".to_string(),
+        "ldots" | "dots" => "…".to_string(),
+        _ => argument.map(|arg| latex_to_markdown_inline(&arg)).unwrap_or_default(),
+    };
+
+    Some((markdown, consumed))
+}
+
+fn latex_to_markdown_inline(text: &str) -> String {
+    latex_to_markdown(text).replace('\n', " ").trim().to_string()
+}
+
+fn clean_inline(text: &str) -> String {
+    latex_to_markdown_inline(text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collapse whitespace-only lines and runs of blank lines
+fn tidy(markdown: &str) -> String {
+    let mut result = String::new();
+    let mut blank_lines = 0;
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            if blank_lines == 1 && !result.is_empty() {
+                result.push('\n');
+            }
+            continue;
+        }
+        blank_lines = 0;
+        result.push_str(line);
+        result.push('\n');
+    }
+    result.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_headings_and_emphasis() {
+        let markdown = latex_to_markdown("\\section{Intro}\nSome \\textbf{bold} and \\emph{slanted} text.");
+        assert_eq!(markdown, "## Intro\n\nSome **bold** and *slanted* text.");
+    }
+
+    #[test]
+    fn keeps_math_source() {
+        assert_eq!(latex_to_markdown("Let $x$ be \\(y+1\\)."), "Let $x$ be $y+1$.");
+
+        let equation = latex_to_markdown("\\begin{equation}\nE = mc^2\n\\end{equation}");
+        assert_eq!(equation, "$$\nE = mc^2\n$$");
+
+        // Alignment environments stay wrapped so & and \\ still render
+        let align = latex_to_markdown("\\begin{align}a &= b \\\\\nc &= d\\end{align}");
+        assert!(align.contains("\\begin{align}a &= b \\\\\nc &= d\\end{align}"));
+    }
+
+    #[test]
+    fn converts_lists_and_skips_figures() {
+        assert_eq!(latex_to_markdown("\\begin{enumerate}\\item One \\item Two\\end{enumerate}"), "1. One\n2. Two");
+        assert_eq!(latex_to_markdown("Before\\begin{figure}\\includegraphics{x}\\end{figure}After"), "BeforeAfter");
+    }
+
+    #[test]
+    fn strips_comments_but_not_escaped_percent() {
+        assert_eq!(strip_comments("50\\% done % note\n% whole line"), "50\\% done \n");
+    }
+
+    #[test]
+    fn follows_includes_without_looping() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main.tex");
+        std::fs::write(&main, "\\title{My \\emph{Notes}}\n\\begin{document}\n\\input{chapter}\n\\end{document}").unwrap();
+        // Including main again must not recurse forever
+        std::fs::write(dir.path().join("chapter.tex"), "\\section{One}\nText\n\\input{main}").unwrap();
+
+        let document = convert_latex_file(&main).unwrap();
+        assert_eq!(document.title.as_deref(), Some("My *Notes*"));
+        assert_eq!(document.markdown, "## One\n\nText");
+        assert!(document.included_files[0].ends_with("chapter.tex"));
+    }
+}
@@ -0,0 +1,173 @@
+//! Converters that turn files dropped onto the library into markdown documents.
+//! `convert_file` dispatches on the file extension.
+
+pub mod email;
+pub mod handwriting;
+pub mod html;
+pub mod latex;
+pub mod ocr;
+pub mod paste;
+pub mod url;
+
+use std::path::Path;
+use crate::pdf_processor::{ExtractOptions, PdfProcessor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Pdf,
+    Html,
+    Image,
+    Markdown,
+    Text,
+    Office, // docx/pptx/xlsx/odt/epub..., converted with MarkItDown
+    Email,
+    Mailbox, // .mbox archives hold many messages; see `convert_mailbox`
+    Latex,
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+
+        match extension.as_str() {
+            "pdf" => Some(ImportFormat::Pdf),
+            "html" | "htm" | "xhtml" => Some(ImportFormat::Html),
+            "png" | "jpg" | "jpeg" | "tif" | "tiff" | "bmp" | "webp" | "gif" => Some(ImportFormat::Image),
+            "md" | "markdown" => Some(ImportFormat::Markdown),
+            "txt" | "text" | "csv" | "tsv" => Some(ImportFormat::Text),
+            "eml" => Some(ImportFormat::Email),
+            "mbox" => Some(ImportFormat::Mailbox),
+            "tex" | "latex" => Some(ImportFormat::Latex),
+            "docx" | "pptx" | "xlsx" | "xls" | "doc" | "ppt" | "odt" | "epub" | "rtf" => Some(ImportFormat::Office),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportFormat::Pdf => "pdf",
+            ImportFormat::Html => "html",
+            ImportFormat::Image => "image",
+            ImportFormat::Markdown => "markdown",
+            ImportFormat::Text => "text",
+            ImportFormat::Office => "office",
+            ImportFormat::Email => "email",
+            ImportFormat::Mailbox => "mailbox",
+            ImportFormat::Latex => "latex",
+        }
+    }
+}
+
+/// Markdown produced from an imported file
+#[derive(Debug, Clone)]
+pub struct ImportedContent {
+    pub format: ImportFormat,
+    pub title: Option<String>, // From the file itself (e.g. <title>), when it has one
+    pub content: String,
+    pub method: String, // Converter used, recorded in document metadata
+    pub tags: Vec<String>, // Tags derived from the file (e.g. email sender/date)
+    pub details: Option<serde_json::Value>, // Format-specific metadata such as email headers
+}
+
+impl ImportedContent {
+    fn from_email(email: email::ParsedEmail) -> Self {
+        ImportedContent {
+            format: ImportFormat::Email,
+            title: email.subject.clone(),
+            tags: email.tags(),
+            details: Some(email.metadata()),
+            content: email.content,
+            method: "mail-parser".to_string(),
+        }
+    }
+}
+
+/// Convert a file to markdown according to its extension
+pub async fn convert_file(path: &Path) -> Result<ImportedContent, String> {
+    if !path.exists() {
+        return Err(format!("File not found: {}", path.display()));
+    }
+
+    let format = ImportFormat::from_path(path)
+        .ok_or_else(|| format!("Unsupported file type: {}", path.display()))?;
+    let path_str = path.to_string_lossy().to_string();
+
+    let (title, content, method) = match format {
+        ImportFormat::Pdf => {
+            let extraction = PdfProcessor::new()
+                .extract_with_options(&path_str, &ExtractOptions::default())
+                .await
+                .map_err(|e| e.to_string())?;
+            (None, extraction.content, extraction.method.as_str().to_string())
+        }
+        ImportFormat::Html => {
+            let bytes = std::fs::read(path).map_err(|e| format!("Failed to read HTML file: {}", e))?;
+            let article = html::extract_article(&String::from_utf8_lossy(&bytes));
+            (article.title, article.markdown, "readability".to_string())
+        }
+        ImportFormat::Image => (None, ocr::ocr_image_detecting_language(path).await?, "tesseract".to_string()),
+        ImportFormat::Markdown | ImportFormat::Text => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read text document: {}", e))?;
+            (None, content.replace("\r\n", "\n"), "direct".to_string())
+        }
+        ImportFormat::Office => {
+            let content = PdfProcessor::new()
+                .extract_with_markitdown(&path_str)
+                .await
+                .map_err(|e| e.to_string())?;
+            (None, content, "markitdown".to_string())
+        }
+        ImportFormat::Email => {
+            let raw = std::fs::read(path).map_err(|e| format!("Failed to read email: {}", e))?;
+            let parsed = email::parse_email(&raw)
+                .ok_or_else(|| format!("Could not parse email: {}", path.display()))?;
+            return Ok(ImportedContent::from_email(parsed));
+        }
+        ImportFormat::Latex => {
+            let latex = latex::convert_latex_file(path)?;
+            if latex.markdown.trim().is_empty() {
+                return Err(format!("No text could be extracted from '{}'", path.display()));
+            }
+            let included: Vec<String> = latex.included_files.iter().map(|p| p.to_string_lossy().to_string()).collect();
+            return Ok(ImportedContent {
+                format,
+                title: latex.title,
+                content: latex.markdown,
+                method: "latex".to_string(),
+                tags: Vec::new(),
+                details: Some(serde_json::json!({ "included_files": included })),
+            });
+        }
+        ImportFormat::Mailbox => {
+            return Err("Mailbox archives contain many messages; import them with import_mailbox".to_string());
+        }
+    };
+
+    if content.trim().is_empty() {
+        return Err(format!("No text could be extracted from '{}'", path.display()));
+    }
+
+    Ok(ImportedContent {
+        format,
+        title,
+        content,
+        method,
+        tags: Vec::new(),
+        details: None,
+    })
+}
+
+/// Convert each message of an .mbox archive into its own piece of content
+pub fn convert_mailbox(path: &Path) -> Result<Vec<ImportedContent>, String> {
+    Ok(email::read_mailbox(path)?
+        .into_iter()
+        .filter(|message| !message.content.trim().is_empty())
+        .map(ImportedContent::from_email)
+        .collect())
+}
@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+/// Resolve the tesseract binary: STELLAR_TESSERACT_BIN, then common install paths, then PATH
+pub fn resolve_tesseract_command() -> PathBuf {
+    if let Ok(explicit_command) = std::env::var("STELLAR_TESSERACT_BIN") {
+        let explicit_path = PathBuf::from(explicit_command);
+        if explicit_path.exists() {
+            return explicit_path;
+        }
+    }
+
+    let candidates = [
+        PathBuf::from("/opt/homebrew/bin/tesseract"),
+        PathBuf::from("/usr/local/bin/tesseract"),
+        PathBuf::from("C:\\Program Files\\Tesseract-OCR\\tesseract.exe"),
+    ];
+
+    for candidate in candidates {
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+
+    PathBuf::from("tesseract")
+}
+
+/// OCR an image file with tesseract, returning the recognised text
+pub async fn ocr_image(path: &Path, languages: Option<&str>) -> Result<String, String> {
+    let tesseract_command = resolve_tesseract_command();
+    let mut cmd = tokio::process::Command::new(&tesseract_command);
+    cmd.arg(path).arg("stdout");
+    crate::throttle::lower_priority(&mut cmd);
+    if let Some(languages) = languages {
+        cmd.arg("-l").arg(languages);
+    }
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(300), cmd.output())
+        .await
+        .map_err(|_| format!("OCR timed out for '{}'", path.display()))?
+        .map_err(|e| {
+            let details = format!("Failed to run tesseract at '{}': {}", tesseract_command.display(), e);
+            if e.kind() == std::io::ErrorKind::NotFound {
+                format!("{}. Install Tesseract OCR or set STELLAR_TESSERACT_BIN.", details)
+            } else {
+                details
+            }
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("OCR failed for '{}': {}", path.display(), stderr.trim()));
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n");
+    if text.trim().is_empty() {
+        return Err(format!("No text was recognised in '{}'", path.display()));
+    }
+
+    Ok(text.trim().to_string())
+}
+
+/// OCR with tesseract's default (English) model, then again with the model for the language
+/// detected in that first pass, as long as the first pass recognised enough text to tell.
+/// Falls back to the first pass when the language's traineddata isn't installed.
+pub async fn ocr_image_detecting_language(path: &Path) -> Result<String, String> {
+    let text = ocr_image(path, None).await?;
+    let Some(languages) = crate::language::detect_language(&text).and_then(|language| crate::language::tesseract_languages(&language)) else {
+        return Ok(text);
+    };
+
+    match ocr_image(path, Some(&languages)).await {
+        Ok(text) => Ok(text),
+        Err(e) => {
+            eprintln!("⚠️ OCR with '{}' failed, keeping the default model's text: {}", languages, e);
+            Ok(text)
+        }
+    }
+}
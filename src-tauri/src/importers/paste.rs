@@ -0,0 +1,333 @@
+//! Smart paste: work out what a pasted block of text is (a link, BibTeX entries, a table copied
+//! from a spreadsheet, code, or just text) so it can be turned into the right kind of document
+//! rather than one long plain note.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::code;
+
+// Markers of a single language that make a block code even without code-shaped lines
+const MIN_CODE_MARKERS: usize = 3;
+// Share of lines that must look like code when only a marker or two were found
+const MIN_CODE_LINE_RATIO: f32 = 0.5;
+// Comma and semicolon separated text needs a header and two rows; prose rarely keeps that up
+const MIN_DELIMITED_ROWS: usize = 3;
+const MAX_CELL_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PasteKind {
+    Url,
+    Bibtex,
+    Csv,
+    Code,
+    Text,
+}
+
+impl PasteKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PasteKind::Url => "url",
+            PasteKind::Bibtex => "bibtex",
+            PasteKind::Csv => "csv",
+            PasteKind::Code => "code",
+            PasteKind::Text => "text",
+        }
+    }
+}
+
+/// What kind of content `text` is, checked from the most to the least specific
+pub fn detect_paste_kind(text: &str) -> PasteKind {
+    let trimmed = text.trim();
+    if is_url(trimmed) {
+        PasteKind::Url
+    } else if trimmed.starts_with('@') && !parse_bibtex(trimmed).is_empty() {
+        PasteKind::Bibtex
+    } else if looks_like_code(trimmed) {
+        PasteKind::Code
+    } else if parse_table(trimmed).is_some() {
+        PasteKind::Csv
+    } else {
+        PasteKind::Text
+    }
+}
+
+pub fn is_url(text: &str) -> bool {
+    !text.is_empty()
+        && !text.contains(char::is_whitespace)
+        && reqwest::Url::parse(text).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+}
+
+// === BibTeX ===
+
+/// One BibTeX entry, with field names lowercased and braces removed from the values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BibtexEntry {
+    pub entry_type: String, // article, book, inproceedings...
+    pub key: String,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl BibtexEntry {
+    pub fn title(&self) -> String {
+        self.fields.get("title").cloned().unwrap_or_else(|| self.key.clone())
+    }
+
+    /// "Last, First and Other, Name" as "Last, First; Other, Name"
+    pub fn authors(&self) -> Vec<String> {
+        self.fields.get("author")
+            .map(|authors| authors.split(" and ").map(str::trim).filter(|author| !author.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// A reference note: title, authors, venue and links, then the abstract
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("# {}", self.title()), String::new()];
+        let authors = self.authors();
+        if !authors.is_empty() {
+            lines.push(format!("**Authors:** {}", authors.join("; ")));
+        }
+        if let Some(year) = self.fields.get("year") {
+            lines.push(format!("**Year:** {}", year));
+        }
+        let venue = ["journal", "booktitle", "publisher", "school", "howpublished"].iter()
+            .find_map(|field| self.fields.get(*field));
+        if let Some(venue) = venue {
+            lines.push(format!("**Published in:** {}", venue));
+        }
+        if let Some(doi) = self.fields.get("doi") {
+            let doi = doi.trim_start_matches("https://doi.org/");
+            lines.push(format!("**DOI:** [{}](https://doi.org/{})", doi, doi));
+        }
+        if let Some(url) = self.fields.get("url") {
+            lines.push(format!("**URL:** {}", url));
+        }
+        lines.push(format!("**Citation key:** `{}`", self.key));
+
+        if let Some(abstract_text) = self.fields.get("abstract") {
+            lines.push(String::new());
+            lines.push("## Abstract".to_string());
+            lines.push(String::new());
+            lines.push(abstract_text.clone());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Entries in BibTeX text. @comment, @preamble and @string blocks are skipped, as is anything
+/// that doesn't parse.
+pub fn parse_bibtex(text: &str) -> Vec<BibtexEntry> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut entries = Vec::new();
+    let mut index = 0;
+
+    while let Some(at) = chars[index..].iter().position(|c| *c == '@').map(|offset| index + offset) {
+        let type_end = chars[at + 1..].iter()
+            .position(|c| !c.is_ascii_alphanumeric())
+            .map_or(chars.len(), |offset| at + 1 + offset);
+        let entry_type: String = chars[at + 1..type_end].iter().collect::<String>().to_lowercase();
+        let open = chars[type_end..].iter().position(|c| !c.is_whitespace()).map(|offset| type_end + offset);
+
+        let Some(open) = open.filter(|open| matches!(chars[*open], '{' | '(')) else {
+            index = at + 1;
+            continue;
+        };
+        let Some(close) = matching_close(&chars, open) else {
+            break;
+        };
+        index = close + 1;
+
+        if entry_type.is_empty() || matches!(entry_type.as_str(), "comment" | "preamble" | "string") {
+            continue;
+        }
+        let body: String = chars[open + 1..close].iter().collect();
+        if let Some(entry) = parse_bibtex_body(&entry_type, &body) {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+// Index of the brace closing the one at `open`, accounting for nesting
+fn matching_close(chars: &[char], open: usize) -> Option<usize> {
+    let (opening, closing) = if chars[open] == '(' { ('(', ')') } else { ('{', '}') };
+    let mut depth = 0;
+    for (index, c) in chars.iter().enumerate().skip(open) {
+        if *c == opening {
+            depth += 1;
+        } else if *c == closing {
+            depth -= 1;
+            if depth == 0 {
+                return Some(index);
+            }
+        }
+    }
+    None
+}
+
+fn parse_bibtex_body(entry_type: &str, body: &str) -> Option<BibtexEntry> {
+    let (key, rest) = body.split_once(',')?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(char::is_whitespace) || key.contains('=') {
+        return None;
+    }
+
+    let mut fields = BTreeMap::new();
+    for field in split_top_level(rest) {
+        let Some((name, value)) = field.split_once('=') else {
+            continue;
+        };
+        let name = name.trim().to_lowercase();
+        let value = clean_bibtex_value(value);
+        if !name.is_empty() && !value.is_empty() {
+            fields.insert(name, value);
+        }
+    }
+    if fields.is_empty() {
+        return None;
+    }
+
+    Some(BibtexEntry { entry_type: entry_type.to_string(), key: key.to_string(), fields })
+}
+
+// Split fields at commas outside braces and quotes
+fn split_top_level(text: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut in_quotes = false;
+
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' if depth == 0 => in_quotes = !in_quotes,
+            ',' if depth == 0 && !in_quotes => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current);
+    parts.into_iter().filter(|part| !part.trim().is_empty()).collect()
+}
+
+fn clean_bibtex_value(value: &str) -> String {
+    let value = value.trim();
+    let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+    value.replace(['{', '}'], "")
+        .replace("\\&", "&")
+        .replace("--", "–")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// === Tables ===
+
+/// Rows of delimited text: tab separated (spreadsheet copies), then comma or semicolon
+/// separated. Every row needs the same number of columns, at least two.
+pub fn parse_table(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    if lines.len() < 2 {
+        return None;
+    }
+
+    for (delimiter, min_rows) in [('\t', 2), (',', MIN_DELIMITED_ROWS), (';', MIN_DELIMITED_ROWS)] {
+        if lines.len() < min_rows {
+            continue;
+        }
+        let rows: Vec<Vec<String>> = lines.iter().map(|line| split_delimited(line, delimiter)).collect();
+        let columns = rows[0].len();
+        let consistent = columns >= 2
+            && rows.iter().all(|row| row.len() == columns)
+            && rows.iter().flatten().all(|cell| cell.chars().count() <= MAX_CELL_CHARS);
+        if consistent {
+            return Some(rows);
+        }
+    }
+    None
+}
+
+// One line of delimited text; double quotes group cells and "" is a literal quote
+fn split_delimited(line: &str, delimiter: char) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => cells.push(std::mem::take(&mut current).trim().to_string()),
+            c => current.push(c),
+        }
+    }
+    cells.push(current.trim().to_string());
+    cells
+}
+
+/// Rows as a markdown table, the first row as the header
+pub fn table_to_markdown(rows: &[Vec<String>]) -> String {
+    let escape = |cell: &String| cell.replace('|', "\\|");
+    let mut lines = Vec::new();
+    if let Some(header) = rows.first() {
+        lines.push(format!("| {} |", header.iter().map(escape).collect::<Vec<_>>().join(" | ")));
+        lines.push(format!("|{}|", vec![" --- "; header.len()].join("|")));
+    }
+    for row in rows.iter().skip(1) {
+        lines.push(format!("| {} |", row.iter().map(escape).collect::<Vec<_>>().join(" | ")));
+    }
+    lines.join("\n")
+}
+
+// === Code ===
+
+/// Whether text is source code: fenced, full of one language's markers, or mostly lines
+/// shaped like code (ending in braces or semicolons, indented, comments). Markdown headings
+/// and prose ending in colons don't count.
+pub fn looks_like_code(text: &str) -> bool {
+    if !code::find_code_blocks(text).is_empty() {
+        return true;
+    }
+    let markers = code::language_score(text);
+    if markers == 0 {
+        return false;
+    }
+    if markers >= MIN_CODE_MARKERS {
+        return true;
+    }
+
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    let code_lines = lines.iter()
+        .filter(|line| {
+            let trimmed = line.trim();
+            trimmed.ends_with([';', '{', '}'])
+                || line.starts_with("    ")
+                || line.starts_with('\t')
+                || trimmed.starts_with("//")
+                || trimmed.starts_with("#include")
+                || trimmed.starts_with("#!")
+        })
+        .count();
+    !lines.is_empty() && code_lines as f32 / lines.len() as f32 >= MIN_CODE_LINE_RATIO
+}
+
+/// The code in a fence tagged with its language, keeping a fence the paste already had
+pub fn code_to_markdown(text: &str) -> (String, Option<String>) {
+    let text = text.trim_matches('\n').trim_end();
+    if let Some(block) = code::find_code_blocks(text).into_iter().next() {
+        return (text.to_string(), block.language);
+    }
+
+    let language = code::guess_language(text);
+    let fence = if text.contains("```") { "~~~" } else { "```" };
+    (format!("{}{}\n{}\n{}", fence, language.as_deref().unwrap_or(""), text, fence), language)
+}
@@ -0,0 +1,153 @@
+//! Work out what a pasted URL actually points at. Links to papers are often landing pages
+//! (arXiv abstracts, DOI resolvers, publisher sites) rather than the PDF itself.
+
+use crate::network::{shared_client, HttpPurpose};
+use futures_util::StreamExt;
+use scraper::{Html, Selector};
+
+// Meta-refresh and citation_pdf_url hops; HTTP redirects are followed by reqwest itself
+const MAX_HOPS: usize = 5;
+// Landing pages larger than this are not worth parsing
+const MAX_HTML_BYTES: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub enum ResolvedUrl {
+    Pdf { url: String },
+    WebPage { url: String, html: String },
+}
+
+/// Rewrite links to known landing pages into their direct PDF link
+pub fn rewrite_known_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else {
+        return url.to_string();
+    };
+    let host = parsed.host_str().unwrap_or_default().trim_start_matches("www.").to_string();
+
+    match host.as_str() {
+        // arxiv.org/abs/2401.01234v2 -> arxiv.org/pdf/2401.01234v2
+        "arxiv.org" | "export.arxiv.org" if parsed.path().starts_with("/abs/") => {
+            let id = parsed.path().trim_start_matches("/abs/").to_string();
+            parsed.set_path(&format!("/pdf/{}", id));
+            parsed.to_string()
+        }
+        // openreview.net/forum?id=X -> openreview.net/pdf?id=X
+        "openreview.net" if parsed.path() == "/forum" => {
+            parsed.set_path("/pdf");
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Canonical form used to recognise a URL that was already imported: known landing pages map
+/// to their PDF link, the fragment is dropped, http becomes https, and "www." and trailing
+/// slashes are ignored
+pub fn normalize_source_url(url: &str) -> String {
+    let url = url.trim();
+    let Ok(mut parsed) = reqwest::Url::parse(&rewrite_known_url(url)) else {
+        return url.to_string();
+    };
+    parsed.set_fragment(None);
+    if parsed.scheme() == "http" {
+        let _ = parsed.set_scheme("https");
+    }
+    if let Some(host) = parsed.host_str().and_then(|host| host.strip_prefix("www.")).map(str::to_string) {
+        let _ = parsed.set_host(Some(&host));
+    }
+    if parsed.path().len() > 1 && parsed.path().ends_with('/') {
+        let path = parsed.path().trim_end_matches('/').to_string();
+        parsed.set_path(&path);
+    }
+    parsed.to_string()
+}
+
+/// Follow a URL to a PDF, or to the HTML page it ends at when there is no PDF to be found
+pub async fn resolve_url(url: &str) -> Result<ResolvedUrl, String> {
+    let client = shared_client(HttpPurpose::Download)?;
+    let mut current = rewrite_known_url(url);
+
+    for _ in 0..MAX_HOPS {
+        crate::network::ensure_reachable(&current, "Importing from a URL")?;
+        let response = client.get(&current).send().await
+            .map_err(|e| format!("Failed to fetch {}: {}", current, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to fetch {}: HTTP {}", current, response.status()));
+        }
+
+        let final_url = response.url().to_string();
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if content_type.contains("application/pdf") {
+            return Ok(ResolvedUrl::Pdf { url: final_url });
+        }
+
+        // Peek at the body: servers often send PDFs as octet-stream or with no type at all
+        let mut stream = response.bytes_stream();
+        let mut body = Vec::new();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to read {}: {}", final_url, e))?;
+            body.extend_from_slice(&chunk);
+            if body.starts_with(b"%PDF") {
+                return Ok(ResolvedUrl::Pdf { url: final_url });
+            }
+            if !content_type.contains("html") && body.len() >= 1024 {
+                break;
+            }
+            if body.len() > MAX_HTML_BYTES {
+                return Err(format!("{} is a page too large to import", final_url));
+            }
+        }
+
+        if !content_type.contains("html") && !looks_like_html(&body) {
+            return Err(format!(
+                "{} is neither a PDF nor a web page (content type '{}')",
+                final_url,
+                if content_type.is_empty() { "unknown" } else { &content_type }
+            ));
+        }
+
+        let html = String::from_utf8_lossy(&body).to_string();
+        match next_hop(&html, &final_url) {
+            Some(next) if next != final_url => {
+                println!("🔗 {} -> {}", final_url, next);
+                current = rewrite_known_url(&next);
+            }
+            _ => return Ok(ResolvedUrl::WebPage { url: final_url, html }),
+        }
+    }
+
+    Err(format!("Too many redirects while resolving {}", url))
+}
+
+fn looks_like_html(body: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&body[..body.len().min(512)]).to_ascii_lowercase();
+    start.contains("<html") || start.contains("<!doctype html")
+}
+
+/// Where a landing page points next: a meta refresh, or the PDF advertised to citation
+/// managers (Google Scholar's citation_pdf_url, used by most publishers)
+fn next_hop(html: &str, base_url: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let base = reqwest::Url::parse(base_url).ok()?;
+
+    let attr = |selector: &str, name: &str| -> Option<String> {
+        let selector = Selector::parse(selector).ok()?;
+        document.select(&selector)
+            .find_map(|element| element.value().attr(name).map(|value| value.trim().to_string()))
+            .filter(|value| !value.is_empty())
+    };
+
+    let target = attr("meta[http-equiv='refresh' i]", "content")
+        .and_then(|content| {
+            // content="0; url=https://..."
+            let lower = content.to_ascii_lowercase();
+            let start = lower.find("url=")? + 4;
+            Some(content[start..].trim_matches(|c| c == '\'' || c == '"' || c == ' ').to_string())
+        })
+        .or_else(|| attr("meta[name='citation_pdf_url']", "content"))?;
+
+    base.join(&target).ok().map(|url| url.to_string())
+}
@@ -0,0 +1,247 @@
+//! Language detection and the script-aware text helpers used by extraction and chunking.
+//! Documents store the ISO 639-3 code whatlang detects ("eng", "deu", "cmn", ...), which
+//! is then mapped to the codes OCR engines and embedding models expect.
+
+use std::collections::HashMap;
+
+// Detection only needs a sample; whole books would just cost time
+const DETECTION_SAMPLE_CHARS: usize = 10_000;
+const MIN_DETECTION_CHARS: usize = 20;
+const MIN_CONFIDENCE: f64 = 0.5;
+
+pub const ENGLISH: &str = "eng";
+/// Local embedding model for libraries that are mostly not English
+pub const MULTILINGUAL_EMBEDDING_MODEL: &str = "multilingual-e5-small";
+pub const ENGLISH_EMBEDDING_MODEL: &str = "bge-small-en-v1.5";
+
+/// Detect the main language of `text` as an ISO 639-3 code; None when the text is too short
+/// or too mixed to tell
+pub fn detect_language(text: &str) -> Option<String> {
+    let sample: String = text.chars().take(DETECTION_SAMPLE_CHARS).collect();
+    if sample.chars().filter(|c| c.is_alphabetic()).count() < MIN_DETECTION_CHARS {
+        return None;
+    }
+
+    let info = whatlang::detect(&sample)?;
+    if !info.is_reliable() && info.confidence() < MIN_CONFIDENCE {
+        return None;
+    }
+    Some(info.lang().code().to_string())
+}
+
+/// Characters of scripts that don't separate words with spaces (Chinese, Japanese, Thai, ...)
+pub fn is_unspaced_script(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4DBF}'   // CJK Extension A
+        | '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}'   // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}'   // Halfwidth Katakana
+        | '\u{20000}'..='\u{2FA1F}' // CJK Extensions B-F
+        | '\u{0E00}'..='\u{0EFF}'   // Thai, Lao
+        | '\u{1000}'..='\u{109F}'   // Myanmar
+        | '\u{1780}'..='\u{17FF}'   // Khmer
+    )
+}
+
+/// Sentence-ending punctuation of CJK text, which is not followed by a space
+pub fn is_fullwidth_sentence_end(c: char) -> bool {
+    matches!(c, '。' | '！' | '？' | '｡' | '．')
+}
+
+/// Clause or sentence punctuation that ends a line of running text rather than a heading
+pub fn is_trailing_punctuation(c: char) -> bool {
+    matches!(c, '.' | ',' | ';' | '!' | '?' | '。' | '，' | '；' | '！' | '？' | '、' | '｡' | '．' | '।')
+}
+
+/// Rough word count that doesn't assume spaces: whitespace-separated words count once, runs
+/// of Chinese/Japanese/Thai characters count one word per two characters
+pub fn approximate_word_count(text: &str) -> usize {
+    let mut words = 0;
+    for token in text.split_whitespace() {
+        let unspaced = token.chars().filter(|c| is_unspaced_script(*c)).count();
+        let has_spaced_letters = token.chars().any(|c| c.is_alphanumeric() && !is_unspaced_script(c));
+        words += unspaced.div_ceil(2) + usize::from(has_spaced_letters || unspaced == 0);
+    }
+    words
+}
+
+/// Whether joining two lines of the same paragraph needs a space between them
+pub fn needs_space_between(previous: Option<char>, next: Option<char>) -> bool {
+    match (previous, next) {
+        (Some(previous), Some(next)) => !(
+            (is_unspaced_script(previous) || is_fullwidth_sentence_end(previous) || previous == '、' || previous == '，')
+                && is_unspaced_script(next)
+        ),
+        _ => true,
+    }
+}
+
+/// Split text into sentences on ". ! ?" followed by whitespace and on CJK full stops, which
+/// need no whitespace after them. Sentences keep their ending punctuation.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let end = if is_fullwidth_sentence_end(c) || c == '।' {
+            // Keep closing quotes and brackets with the sentence they end
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if matches!(next, '」' | '』' | '）' | '"' | '”' | '’') || is_fullwidth_sentence_end(next) {
+                    end = j + next.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            Some(end)
+        } else if matches!(c, '.' | '!' | '?') {
+            let mut end = i + c.len_utf8();
+            while let Some(&(j, next)) = chars.peek() {
+                if matches!(next, '.' | '!' | '?') {
+                    end = j + next.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            chars.peek().map_or(false, |(_, next)| next.is_whitespace()).then_some(end)
+        } else {
+            None
+        };
+
+        if let Some(end) = end {
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+    }
+
+    let rest = text[start..].trim();
+    if !rest.is_empty() {
+        sentences.push(rest);
+    }
+    sentences
+}
+
+/// Tesseract traineddata name for a detected language; English is always added as a
+/// fallback since most documents quote some
+pub fn tesseract_languages(language: &str) -> Option<String> {
+    let code = match language {
+        ENGLISH => return None,
+        "cmn" => "chi_sim",
+        "nob" => "nor",
+        "pes" => "fas",
+        "ydd" => "yid",
+        other => other,
+    };
+    Some(format!("{}+eng", code))
+}
+
+/// ISO 639-1 code for the languages Marker's OCR (surya) recognises by name
+pub fn iso_639_1(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "eng" => "en",
+        "cmn" => "zh",
+        "jpn" => "ja",
+        "kor" => "ko",
+        "deu" => "de",
+        "fra" => "fr",
+        "spa" => "es",
+        "por" => "pt",
+        "ita" => "it",
+        "nld" => "nl",
+        "rus" => "ru",
+        "ukr" => "uk",
+        "pol" => "pl",
+        "ces" => "cs",
+        "swe" => "sv",
+        "dan" => "da",
+        "nob" => "no",
+        "fin" => "fi",
+        "tur" => "tr",
+        "ell" => "el",
+        "heb" => "he",
+        "ara" => "ar",
+        "pes" => "fa",
+        "hin" => "hi",
+        "ben" => "bn",
+        "tha" => "th",
+        "vie" => "vi",
+        "ind" => "id",
+        "hun" => "hu",
+        "ron" => "ro",
+        _ => return None,
+    })
+}
+
+/// English name of a language given by ISO 639-1 or 639-3 code or by its English or native
+/// name, so "de", "deu", "German" and "Deutsch" all give "German"
+pub fn language_name(language: &str) -> Option<&'static str> {
+    let language = language.trim();
+    whatlang::Lang::all().iter()
+        .find(|lang| {
+            lang.code().eq_ignore_ascii_case(language)
+                || iso_639_1(lang.code()).is_some_and(|code| code.eq_ignore_ascii_case(language))
+                || lang.eng_name().eq_ignore_ascii_case(language)
+                || lang.name().to_lowercase() == language.to_lowercase()
+        })
+        .map(|lang| lang.eng_name())
+}
+
+/// Local embedding model suited to a library with these document counts per language:
+/// the English model unless most documents are in other languages
+pub fn recommended_embedding_model(language_counts: &HashMap<String, i64>) -> &'static str {
+    let total: i64 = language_counts.values().sum();
+    let english = language_counts.get(ENGLISH).copied().unwrap_or(0);
+    if total > 0 && english * 2 < total {
+        MULTILINGUAL_EMBEDDING_MODEL
+    } else {
+        ENGLISH_EMBEDDING_MODEL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_sentences_on_spaced_punctuation() {
+        assert_eq!(split_sentences("Hello world. How are you? Fine!"), vec!["Hello world.", "How are you?", "Fine!"]);
+        // A full stop inside a number isn't a sentence end
+        assert_eq!(split_sentences("Pi is 3.14 roughly. Yes"), vec!["Pi is 3.14 roughly.", "Yes"]);
+    }
+
+    #[test]
+    fn splits_cjk_sentences_without_spaces() {
+        assert_eq!(split_sentences("线粒体是动力工厂。它产生能量！"), vec!["线粒体是动力工厂。", "它产生能量！"]);
+        assert_eq!(split_sentences("他说：「好。」然后走了。"), vec!["他说：「好。」", "然后走了。"]);
+    }
+
+    #[test]
+    fn counts_words_across_scripts() {
+        assert_eq!(approximate_word_count("three plain words"), 3);
+        assert_eq!(approximate_word_count("线粒体是细胞"), 3);
+        assert_eq!(approximate_word_count("DNA复制"), 2);
+    }
+
+    #[test]
+    fn names_languages_from_codes_and_names() {
+        for given in ["de", "deu", "German", "german", " Deutsch "] {
+            assert_eq!(language_name(given), Some("German"), "{}", given);
+        }
+        assert_eq!(language_name("Klingon"), None);
+    }
+
+    #[test]
+    fn joins_unspaced_scripts_without_spaces() {
+        assert!(needs_space_between(Some('a'), Some('b')));
+        assert!(!needs_space_between(Some('细'), Some('胞')));
+        assert!(!needs_space_between(Some('。'), Some('它')));
+        assert!(needs_space_between(None, Some('它')));
+    }
+}
@@ -9,44 +9,130 @@ pub mod database;
 pub mod pdf_processor;
 pub mod embeddings;
 pub mod background_processor;
+pub mod media;
+pub mod importers;
+pub mod maintenance;
+pub mod models;
+pub mod network;
+pub mod downloads;
+pub mod reminders;
+pub mod clipboard;
+pub mod deep_link;
+pub mod companion;
+pub mod mcp;
+pub mod local_server;
+pub mod services;
+pub mod paths;
+pub mod profiles;
+pub mod scripting;
+pub mod language;
+pub mod math;
+pub mod code;
+pub mod redaction;
+pub mod crash_reports;
+pub mod metrics;
+pub mod shutdown;
+pub mod throttle;
+pub mod storage;
+pub mod temp_files;
+#[cfg(any(test, feature = "test-support"))]
+#[doc(hidden)]
+pub mod test_support;
 
 use commands::*;
-use database::Database;
+use database::{Database, ClipboardWatcherSettings, QueryEmbeddingCacheSettings};
 use embeddings::{VectorService, EmbeddingConfig, EmbeddingProvider};
-use background_processor::BackgroundProcessor;
+use background_processor::{BackgroundProcessor, BackgroundProcessorState};
+use maintenance::MaintenanceScheduler;
+use reminders::ReminderScheduler;
+use clipboard::{ClipboardWatcher, CLIPBOARD_WATCHER_SETTINGS_KEY};
+use deep_link::DeepLinkState;
+use companion::CompanionServer;
+use mcp::McpServer;
 
 // Re-export types and functions
 pub use ai::*;
 // Import specific items from commands to avoid conflicts
 pub use commands::{
     greet, fetch_models_dev_data, ai_test_connection, ai_chat_completion, ai_chat_completion_stream, ai_get_models,
-    init_database, create_document, get_all_documents, get_document, update_document, delete_document,
-    create_category, get_all_categories, get_category, update_category, delete_category, 
+    get_ai_provider_settings, update_ai_provider_settings, cancel_ai_stream, list_active_ai_streams,
+    get_ai_guardrail_settings, update_ai_guardrail_settings, prepare_rag_context, filter_ai_output,
+    init_database, create_document, quick_capture, get_all_documents, get_document, update_document, delete_document,
+    create_category, get_all_categories, get_category, update_category, delete_category, archive_category, unarchive_category, 
+    get_note_templates, save_note_template, delete_note_template, create_note_from_template, 
     get_documents_by_category, get_uncategorized_documents,
+    bulk_update_documents, bulk_delete_documents,
+    split_document, get_document_sections, get_child_documents, get_document_outline, get_document_outline_section, get_document_content_range,
+    get_document_thumbnail,
+    touch_document, get_recent_documents, pin_document, list_pinned_documents,
     upload_and_process_pdf, upload_and_process_pdf_from_data, upload_and_process_pdf_from_url,
-    get_pdf_file_path, get_pdf_file_content, delete_pdf_file,
+    get_pdf_file_path, get_pdf_file_content, delete_pdf_file, reprocess_document,
+    import_file, import_mailbox, import_handwritten_notes, get_handwriting_image, smart_paste,
     check_marker_availability, get_marker_config,
     create_study_session, get_active_session, end_study_session, get_study_session, get_study_sessions,
+    get_session_timeline,
     record_user_action, get_actions_by_session, get_actions_by_document, get_recent_actions,
-    get_action_statistics, start_new_session, record_simple_action, debug_database_state,
+    get_action_statistics, analyze_session_focus, start_new_session, record_simple_action,
+    get_daily_activity, export_analytics_csv, get_session_segmentation_settings, update_session_segmentation_settings, segment_study_sessions,
+    get_session_templates, save_session_template, delete_session_template, start_session_from_template, debug_database_state,
     store_api_key, get_api_key, delete_api_key,
     create_flashcard, get_flashcard, get_flashcards, get_flashcards_by_deck, get_flashcards_by_category,
-    get_flashcards_by_document, update_flashcard, delete_flashcard, create_flashcard_deck,
-    get_flashcard_deck, get_flashcard_decks, update_flashcard_deck, delete_flashcard_deck,
-    record_flashcard_review, get_due_flashcards, get_new_flashcards, get_flashcard_review_session,
-    get_flashcard_stats, get_flashcard_reviews, get_flashcard_reviews_by_session,
-    cleanup_all_data, cleanup_database_only, get_data_usage_info,
+    get_flashcards_by_document, update_flashcard, delete_flashcard,
+    create_image_occlusion_cards, get_occluded_image, record_card_audio, get_card_audio, create_flashcard_deck,
+    get_flashcard_deck, get_flashcard_decks, get_flashcard_deck_stats, update_flashcard_deck, delete_flashcard_deck,
+    export_deck_package, import_deck_package,
+    record_flashcard_review, grade_typed_answer, get_due_flashcards, get_new_flashcards, get_flashcard_review_session, get_adaptive_review_session, get_custom_review_session,
+    start_review_session, resume_review_session, finish_review_session,
+    get_flashcard_stats, get_flashcard_detail_stats, get_flashcard_reviews, get_flashcard_reviews_by_session,
+    cleanup_all_data, cleanup_database_only, get_data_usage_info, get_storage_breakdown,
+    run_maintenance_now, get_maintenance_history, get_maintenance_settings, update_maintenance_settings, prune_processing_jobs, compress_document_contents,
+    get_reminder_settings, update_reminder_settings, send_test_notification,
+    get_clipboard_watcher_settings, update_clipboard_watcher_settings, toggle_clipboard_watcher, is_clipboard_watcher_running,
+    take_pending_deep_links,
+    get_companion_settings, update_companion_settings, regenerate_companion_token, is_companion_server_running,
+    get_mcp_settings, update_mcp_settings, regenerate_mcp_token, is_mcp_server_running, get_mcp_client_config,
+    get_scripting_settings, update_scripting_settings, get_automation_scripts, save_automation_script,
+    delete_automation_script, run_automation_script,
+    list_profiles, get_active_profile, create_profile, switch_profile,
+    export_settings_bundle, import_settings_bundle, get_redaction_settings, update_redaction_settings,
+    get_crash_reports, clear_crash_reports, report_command_error, get_performance_metrics,
+    get_onboarding_state, seed_sample_content,
+    translate_text, get_document_translations,
+    summarize_section, explain_selection, structure_transcript, global_search,
+    get_category_practice_settings, update_category_practice_settings, generate_document_practice,
+    get_document_quizzes, get_quiz, delete_quiz,
+    record_quiz_answer, link_concepts, get_concept_mastery, get_weak_concepts,
+    import_exam_paper, get_exam_papers, get_exam_questions, delete_exam_paper, set_exam_question_topic,
+    get_question_bank_topics, get_practice_set,
+    grade_essay, get_essay_feedback, delete_essay_feedback, get_rubrics, save_rubric, delete_rubric,
+    convert_image_to_latex,
+    create_conversation, get_conversation, get_conversations, delete_conversation,
+    add_conversation_message, get_conversation_messages, get_message_sources,
+    ai_continue_conversation, summarize_conversation, ai_chat_about_document, ai_ask_library, get_message_footnotes,
+    create_ai_profile, get_ai_profiles, get_ai_profile, update_ai_profile, delete_ai_profile,
+    export_ai_profiles, import_ai_profiles,
+    create_background_pdf_job_from_file, create_background_pdf_job_from_data, create_background_pdf_job_from_url,
+    get_processing_jobs, get_processing_jobs_by_status, get_processing_job, delete_processing_job,
+    get_processing_job_stats, cancel_processing_job, retry_processing_job,
+    get_document_processing_status, get_processing_jobs_by_document_id,
+    get_resource_settings, update_resource_settings, get_background_pause_reason,
 };
 pub use commands::embeddings::{
     init_vector_service, init_embedding_service, process_document_embeddings,
-    search_document_embeddings, delete_document_embeddings, get_embedding_stats,
+    search_document_embeddings, search_embeddings_by_category, delete_document_embeddings, get_embedding_stats,
     check_embedding_health, debug_embedding_service, list_embedded_documents,
     get_document_embedding_info, get_embedding_database_info, 
     bulk_reprocess_documents_for_embeddings, copy_document_embeddings,
-    test_embedding_provider_availability
+    test_embedding_provider_availability, list_local_embedding_models,
+    download_local_embedding_model, delete_local_embedding_model, get_document_languages,
+    get_embedding_fallback_settings, update_embedding_fallback_settings, optimize_embeddings_database,
+    get_query_embedding_cache_settings, update_query_embedding_cache_settings, clear_query_embedding_cache
 };
+pub use commands::models::get_models_disk_usage;
+pub use commands::network::{get_offline_mode, set_offline_mode, get_proxy_settings, update_proxy_settings};
 pub use database::{Document, CreateDocumentRequest, Category, CreateCategoryRequest};
-pub use pdf_processor::{PdfProcessor, MarkerOptions, ExtractOptions, ExtractionMethod};
+pub use services::{Services, DocumentService, FlashcardService, AiService, SettingsService, TranslationService};
+pub use pdf_processor::{PdfProcessor, MarkerOptions, ExtractOptions, ExtractionMethod, ExtractionResult};
 
 // State types
 type DatabaseState = Arc<Mutex<Option<Database>>>;
@@ -54,8 +140,22 @@ type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    crash_reports::install_panic_hook();
+    metrics::install_slow_query_log();
+
+    let builder = tauri::Builder::default();
+    // Links opened while Stellar is running start a second instance; it hands them to this one
+    // (the deep-link feature forwards the URLs) and exits
+    #[cfg(desktop)]
+    let builder = builder.plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+        deep_link::focus_main_window(app);
+    }));
+
+    builder
         .setup(|app| {
+            // stellar:// links, including the one the app was launched with
+            deep_link::init(app.handle());
+
             // Get managed state
             let db_state: tauri::State<DatabaseState> = app.state();
             let vector_state: tauri::State<VectorServiceState> = app.state();
@@ -63,17 +163,15 @@ pub fn run() {
             // Initialize database and services in background
             let db_init = db_state.inner().clone();
             let vector_init = vector_state.inner().clone();
+            let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
-                // Use same location as database commands: ~/stellar_data/documents.db
-                let db_path = match dirs::home_dir() {
-                    Some(home) => {
-                        let app_dir = home.join("stellar_data");
-                        std::fs::create_dir_all(&app_dir).expect("Failed to create app data directory");
-                        app_dir.join("documents.db")
-                    }
-                    None => {
-                        eprintln!("Failed to resolve home directory, using current directory");
-                        std::path::PathBuf::from("documents.db")
+                // Use same location as database commands: documents.db in the active profile's directory
+                println!("👤 Using profile '{}'", profiles::active_profile_id());
+                let db_path = match paths::database_path() {
+                    Ok(path) => path,
+                    Err(e) => {
+                        eprintln!("{}, using current directory", e);
+                        std::path::PathBuf::from(paths::DATABASE_FILE)
                     }
                 };
                 
@@ -82,7 +180,47 @@ pub fn run() {
                 match Database::new(&db_url).await {
                     Ok(database) => {
                         println!("✅ Database initialized successfully");
-                        
+
+                        // Apply offline mode and proxy settings before anything tries the network
+                        match database.get_typed_setting::<bool>(network::OFFLINE_MODE_KEY).await {
+                            Ok(enabled) => network::set_offline(enabled),
+                            Err(e) => eprintln!("⚠️ Failed to read offline mode setting: {}", e),
+                        }
+                        match database.get_proxy_settings().await {
+                            Ok(settings) => {
+                                if let Err(e) = network::set_proxy_settings(settings) {
+                                    eprintln!("⚠️ Saved proxy settings are invalid, using system proxy: {}", e);
+                                }
+                            }
+                            Err(e) => eprintln!("⚠️ Failed to read proxy settings: {}", e),
+                        }
+                        match database.get_typed_setting::<throttle::ResourceSettings>(throttle::RESOURCE_SETTINGS_KEY).await {
+                            Ok(settings) => {
+                                if let Err(e) = throttle::set_resource_settings(settings) {
+                                    eprintln!("⚠️ Saved resource limits are invalid, using defaults: {}", e);
+                                }
+                            }
+                            Err(e) => eprintln!("⚠️ Failed to read resource limits: {}", e),
+                        }
+
+                        match database.detect_missing_document_languages().await {
+                            Ok(0) => {}
+                            Ok(count) => println!("✅ Detected the language of {} existing documents", count),
+                            Err(e) => eprintln!("⚠️ Failed to detect document languages: {}", e),
+                        }
+
+                        match database.index_missing_math_terms().await {
+                            Ok(0) => {}
+                            Ok(count) => println!("✅ Indexed math in {} existing documents", count),
+                            Err(e) => eprintln!("⚠️ Failed to index document math: {}", e),
+                        }
+
+                        match database.index_missing_code_snippets().await {
+                            Ok(0) => {}
+                            Ok(count) => println!("✅ Indexed {} code snippets in existing documents", count),
+                            Err(e) => eprintln!("⚠️ Failed to index code snippets: {}", e),
+                        }
+
                         // Initialize vector service
                         let embedding_config = EmbeddingConfig {
                             provider: EmbeddingProvider::RustBert,
@@ -91,8 +229,22 @@ pub fn run() {
                             base_url: None,
                             dimensions: 384,
                         };
-                        let vector_service = VectorService::new(&db_path.to_string_lossy(), embedding_config).await
-                            .expect("Failed to initialize vector service");
+                        // A failing vector service must not take the database and job processing down with it
+                        let vector_service = match VectorService::new(&db_path.to_string_lossy(), embedding_config).await {
+                            Ok(mut vector_service) => {
+                                let query_cache: QueryEmbeddingCacheSettings = database
+                                    .get_typed_setting(QUERY_EMBEDDING_CACHE_KEY).await
+                                    .unwrap_or_default();
+                                if let Err(e) = vector_service.set_persist_query_embeddings(query_cache.persist) {
+                                    eprintln!("⚠️ Failed to apply query embedding cache settings: {}", e);
+                                }
+                                Some(vector_service)
+                            }
+                            Err(e) => {
+                                eprintln!("❌ Failed to initialize vector service: {}", e);
+                                None
+                            }
+                        };
                         
                         // Set the initialized services
                         {
@@ -100,18 +252,89 @@ pub fn run() {
                             *db_guard = Some(database);
                         }
                         
-                        {
+                        if let Some(vector_service) = vector_service {
                             let mut vector_guard = vector_init.lock().await;
                             *vector_guard = Some(vector_service);
+                            println!("✅ Vector service initialized successfully");
                         }
                         
-                        println!("✅ Vector service initialized successfully");
-                        
+                        // Temp files left behind by a crash; sources of jobs still queued are kept,
+                        // so nothing is swept when they can't be loaded
+                        {
+                            let db_guard = db_init.lock().await;
+                            if let Some(database) = db_guard.as_ref() {
+                                let mut in_use = std::collections::HashSet::new();
+                                let mut jobs_loaded = true;
+                                for status in ["pending", "processing"] {
+                                    match database.get_processing_jobs_by_status(status).await {
+                                        Ok(jobs) => in_use.extend(jobs.into_iter().filter_map(|job| job.source_path).map(std::path::PathBuf::from)),
+                                        Err(e) => {
+                                            eprintln!("⚠️ Failed to load {} jobs, skipping temp file cleanup: {}", status, e);
+                                            jobs_loaded = false;
+                                        }
+                                    }
+                                }
+                                if jobs_loaded {
+                                    let removed = temp_files::sweep_stale_temp_files(&in_use);
+                                    if removed > 0 {
+                                        println!("🧹 Removed {} stale temp files", removed);
+                                    }
+                                }
+                            }
+                        }
+
                         // Initialize and start background processor
                         let background_processor = BackgroundProcessor::new(db_init.clone(), vector_init.clone());
                         background_processor.start().await;
+                        *app_handle.state::<BackgroundProcessorState>().lock().await = Some(background_processor);
                         
                         println!("✅ Background processor started successfully");
+
+                        // Daily (configurable) backups, integrity checks and cleanup
+                        MaintenanceScheduler::new(db_init.clone(), vector_init.clone()).start();
+
+                        // Clipboard watching is opt-in; resume it if it was left on
+                        let clipboard_settings = {
+                            let db_guard = db_init.lock().await;
+                            match db_guard.as_ref() {
+                                Some(database) => database.get_typed_setting::<ClipboardWatcherSettings>(CLIPBOARD_WATCHER_SETTINGS_KEY).await.ok(),
+                                None => None,
+                            }
+                        };
+                        if let Some(settings) = clipboard_settings.filter(|settings| settings.enabled) {
+                            app_handle.state::<ClipboardWatcher>().start(app_handle.clone(), settings);
+                        }
+
+                        // Browser extension endpoint, also opt-in
+                        let companion_settings = {
+                            let db_guard = db_init.lock().await;
+                            match db_guard.as_ref() {
+                                Some(database) => companion::load_companion_settings(database).await.ok(),
+                                None => None,
+                            }
+                        };
+                        if let Some(settings) = companion_settings.filter(|settings| settings.enabled) {
+                            if let Err(e) = app_handle.state::<CompanionServer>().start(db_init.clone(), &settings).await {
+                                eprintln!("⚠️ Failed to start browser extension endpoint: {}", e);
+                            }
+                        }
+
+                        // MCP server for external AI clients, also opt-in
+                        let mcp_settings = {
+                            let db_guard = db_init.lock().await;
+                            match db_guard.as_ref() {
+                                Some(database) => mcp::load_mcp_settings(database).await.ok(),
+                                None => None,
+                            }
+                        };
+                        if let Some(settings) = mcp_settings.filter(|settings| settings.enabled) {
+                            if let Err(e) = app_handle.state::<McpServer>().start(db_init.clone(), &settings).await {
+                                eprintln!("⚠️ Failed to start MCP server: {}", e);
+                            }
+                        }
+
+                        // Due reviews, study goal nudges and finished jobs as OS notifications
+                        ReminderScheduler::new(app_handle, db_init.clone()).start();
                     }
                     Err(e) => {
                         eprintln!("❌ Failed to initialize database: {}", e);
@@ -124,8 +347,16 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_deep_link::init())
         .manage(Arc::new(Mutex::new(None)) as DatabaseState)
         .manage(Arc::new(Mutex::new(None)) as VectorServiceState)
+        .manage(Arc::new(Mutex::new(None)) as BackgroundProcessorState)
+        .manage(ClipboardWatcher::default())
+        .manage(DeepLinkState::default())
+        .manage(CompanionServer::default())
+        .manage(McpServer::default())
         .invoke_handler(tauri::generate_handler![
             greet,
             fetch_models_dev_data,
@@ -135,7 +366,22 @@ pub fn run() {
             ai_test_connection,
             ai_chat_completion,
             ai_chat_completion_stream,
+            cancel_ai_stream,
+            list_active_ai_streams,
+            get_ai_guardrail_settings,
+            update_ai_guardrail_settings,
+            prepare_rag_context,
+            filter_ai_output,
             ai_get_models,
+            get_ai_provider_settings,
+            update_ai_provider_settings,
+            create_ai_profile,
+            get_ai_profiles,
+            get_ai_profile,
+            update_ai_profile,
+            delete_ai_profile,
+            export_ai_profiles,
+            import_ai_profiles,
             init_database,
             upload_and_process_pdf,
             upload_and_process_pdf_from_data,
@@ -147,33 +393,73 @@ pub fn run() {
             get_pdf_file_path,
             get_pdf_file_content,
             delete_pdf_file,
+            reprocess_document,
+            import_file,
+            import_mailbox,
+            import_handwritten_notes,
+            get_handwriting_image,
+            smart_paste,
             check_marker_availability,
             get_marker_config,
             create_document,
+            quick_capture,
             get_all_documents,
             get_document,
             update_document,
             delete_document,
             search_documents,
+            quick_search,
+            search_code_snippets,
+            get_document_code_snippets,
             create_category,
             get_all_categories,
             get_category,
             update_category,
             delete_category,
+            archive_category,
+            unarchive_category,
+            get_note_templates,
+            save_note_template,
+            delete_note_template,
+            create_note_from_template,
             get_documents_by_category,
             get_uncategorized_documents,
+            bulk_update_documents,
+            bulk_delete_documents,
+            split_document,
+            get_document_sections,
+            get_document_outline,
+            get_document_outline_section,
+            get_document_content_range,
+            get_document_thumbnail,
+            get_child_documents,
+            touch_document,
+            get_recent_documents,
+            pin_document,
+            list_pinned_documents,
             // Student Pro - Actions & Sessions commands
             create_study_session,
             get_active_session,
             end_study_session,
             get_study_session,
             get_study_sessions,
+            get_session_timeline,
             record_user_action,
             get_actions_by_session,
             get_actions_by_document,
             get_recent_actions,
             get_action_statistics,
+            analyze_session_focus,
             start_new_session,
+            get_daily_activity,
+            export_analytics_csv,
+            get_session_segmentation_settings,
+            update_session_segmentation_settings,
+            segment_study_sessions,
+            get_session_templates,
+            save_session_template,
+            delete_session_template,
+            start_session_from_template,
             record_simple_action,
             debug_database_state,
             // 🧠 PHASE 2: Flashcard System commands
@@ -193,26 +479,87 @@ pub fn run() {
             cancel_processing_job,
             retry_processing_job,
             get_processing_job_stats,
+            get_document_processing_status,
+            get_processing_jobs_by_document_id,
+            get_resource_settings,
+            update_resource_settings,
+            get_background_pause_reason,
             get_flashcards_by_document,
             update_flashcard,
             delete_flashcard,
+            create_image_occlusion_cards,
+            get_occluded_image,
+            record_card_audio,
+            get_card_audio,
             create_flashcard_deck,
             get_flashcard_deck,
             get_flashcard_decks,
+            get_flashcard_deck_stats,
             update_flashcard_deck,
             delete_flashcard_deck,
+            export_deck_package,
+            import_deck_package,
             record_flashcard_review,
+            grade_typed_answer,
             get_due_flashcards,
             get_new_flashcards,
             get_flashcard_review_session,
+            get_adaptive_review_session,
+            get_custom_review_session,
+            start_review_session,
+            resume_review_session,
+            finish_review_session,
             get_flashcard_stats,
+            get_flashcard_detail_stats,
             get_flashcard_reviews,
             get_flashcard_reviews_by_session,
+            // Practice question commands
+            get_category_practice_settings,
+            update_category_practice_settings,
+            generate_document_practice,
+            get_document_quizzes,
+            get_quiz,
+            delete_quiz,
+            record_quiz_answer,
+            link_concepts,
+            get_concept_mastery,
+            get_weak_concepts,
+            // Question bank commands
+            import_exam_paper,
+            get_exam_papers,
+            get_exam_questions,
+            delete_exam_paper,
+            set_exam_question_topic,
+            get_question_bank_topics,
+            get_practice_set,
+            // Essay feedback commands
+            grade_essay,
+            get_essay_feedback,
+            delete_essay_feedback,
+            get_rubrics,
+            save_rubric,
+            delete_rubric,
+            // Equation commands
+            convert_image_to_latex,
+            // Conversation commands
+            create_conversation,
+            get_conversation,
+            get_conversations,
+            delete_conversation,
+            add_conversation_message,
+            get_conversation_messages,
+            get_message_sources,
+            ai_continue_conversation,
+            summarize_conversation,
+            ai_chat_about_document,
+            ai_ask_library,
+            get_message_footnotes,
             // Embedding commands (new sqlite-vec based)
             init_vector_service,
             init_embedding_service, // Keep for backward compatibility
             process_document_embeddings,
             search_document_embeddings,
+            search_embeddings_by_category,
             delete_document_embeddings,
             get_embedding_stats,
             check_embedding_health,
@@ -223,23 +570,83 @@ pub fn run() {
             bulk_reprocess_documents_for_embeddings,
             copy_document_embeddings,
             test_embedding_provider_availability,
+            list_local_embedding_models,
+            download_local_embedding_model,
+            delete_local_embedding_model,
+            get_document_languages,
+            get_embedding_fallback_settings,
+            update_embedding_fallback_settings,
+            get_query_embedding_cache_settings,
+            update_query_embedding_cache_settings,
+            clear_query_embedding_cache,
+            optimize_embeddings_database,
+            // Local model management
+            get_models_disk_usage,
+            // Network
+            get_offline_mode,
+            set_offline_mode,
+            get_proxy_settings,
+            update_proxy_settings,
             cleanup_all_data,
             cleanup_database_only,
             get_data_usage_info,
-            // Background processing commands
-            create_background_pdf_job_from_file,
-            create_background_pdf_job_from_data,
-            create_background_pdf_job_from_url,
-            get_processing_jobs,
-            get_processing_jobs_by_status,
-            get_processing_job,
-            delete_processing_job,
-            get_processing_job_stats,
-            cancel_processing_job,
-            retry_processing_job,
-            get_document_processing_status,
-            get_processing_jobs_by_document_id,
+            get_storage_breakdown,
+            // Maintenance commands
+            run_maintenance_now,
+            get_maintenance_history,
+            get_maintenance_settings,
+            update_maintenance_settings,
+            prune_processing_jobs,
+            compress_document_contents,
+            get_reminder_settings,
+            update_reminder_settings,
+            send_test_notification,
+            get_clipboard_watcher_settings,
+            update_clipboard_watcher_settings,
+            toggle_clipboard_watcher,
+            is_clipboard_watcher_running,
+            take_pending_deep_links,
+            get_companion_settings,
+            update_companion_settings,
+            regenerate_companion_token,
+            is_companion_server_running,
+            get_mcp_settings,
+            update_mcp_settings,
+            regenerate_mcp_token,
+            is_mcp_server_running,
+            get_mcp_client_config,
+            get_scripting_settings,
+            update_scripting_settings,
+            get_automation_scripts,
+            save_automation_script,
+            delete_automation_script,
+            run_automation_script,
+            list_profiles,
+            get_active_profile,
+            create_profile,
+            switch_profile,
+            export_settings_bundle,
+            import_settings_bundle,
+            get_redaction_settings,
+            update_redaction_settings,
+            get_crash_reports,
+            clear_crash_reports,
+            report_command_error,
+            get_performance_metrics,
+            get_onboarding_state,
+            seed_sample_content,
+            translate_text,
+            get_document_translations,
+            summarize_section,
+            explain_selection,
+            structure_transcript,
+            global_search,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { code, api, .. } = event {
+                shutdown::on_exit_requested(app, code, &api);
+            }
+        });
 }
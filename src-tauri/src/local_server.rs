@@ -0,0 +1,112 @@
+//! Scaffolding shared by the opt-in servers on 127.0.0.1 (the browser extension endpoint and the
+//! MCP server): bearer tokens generated on first use, and a task that is stopped gracefully so a
+//! restart can bind the same port again.
+
+use std::sync::Mutex as StdMutex;
+use std::time::Duration;
+use axum::http::HeaderMap;
+use axum::Router;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::database::Database;
+
+// In-flight requests get this long to finish when the server stops before they're cut off
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Settings of a localhost server, stored as one typed setting
+pub trait LocalServerSettings: Serialize + DeserializeOwned + Default {
+    fn token_mut(&mut self) -> &mut Option<String>;
+}
+
+pub fn generate_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Load the settings stored under `key`, generating and saving a token the first time
+pub async fn load_settings<T: LocalServerSettings>(database: &Database, key: &str) -> Result<T, String> {
+    let mut settings: T = database.get_typed_setting(key).await
+        .map_err(|e| format!("Failed to get {} settings: {}", key, e))?;
+    if settings.token_mut().is_none() {
+        *settings.token_mut() = Some(generate_token());
+        database.set_typed_setting(key, &settings).await
+            .map_err(|e| format!("Failed to save {} settings: {}", key, e))?;
+    }
+    Ok(settings)
+}
+
+pub fn validate_port(port: u16) -> Result<(), String> {
+    if port < 1024 {
+        return Err("Choose a port of 1024 or above".to_string());
+    }
+    Ok(())
+}
+
+/// Whether the request carries `Authorization: Bearer <token>`
+pub fn bearer_token_matches(headers: &HeaderMap, token: &str) -> bool {
+    let provided = headers.get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+
+    // Compare every byte so the response time doesn't reveal how much of the token matched
+    let expected = token.as_bytes();
+    provided.len() == expected.len()
+        && provided.bytes().zip(expected).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+struct RunningServer {
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+/// The running server task, if any
+#[derive(Default)]
+pub struct LocalServer {
+    running: StdMutex<Option<RunningServer>>,
+}
+
+impl LocalServer {
+    pub fn is_running(&self) -> bool {
+        self.running.lock().map(|running| running.is_some()).unwrap_or(false)
+    }
+
+    /// Serve `router` on 127.0.0.1:`port`, replacing a server that is already running. The old
+    /// server has released the port by the time this binds it again.
+    pub async fn start(&self, port: u16, router: Router, name: &'static str) -> Result<(), String> {
+        self.stop().await;
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await
+            .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let server = axum::serve(listener, router)
+                .with_graceful_shutdown(async move { let _ = shutdown_signal.await; });
+            if let Err(e) = server.await {
+                eprintln!("❌ {} stopped: {}", name, e);
+            }
+        });
+
+        if let Ok(mut running) = self.running.lock() {
+            *running = Some(RunningServer { shutdown, task });
+        }
+        Ok(())
+    }
+
+    /// Stop accepting connections and wait for the server to finish, aborting it if requests
+    /// are still running after `STOP_TIMEOUT`. Returns whether a server was running.
+    pub async fn stop(&self) -> bool {
+        let Some(RunningServer { shutdown, mut task }) = self.running.lock().ok().and_then(|mut running| running.take()) else {
+            return false;
+        };
+        let _ = shutdown.send(());
+        if tokio::time::timeout(STOP_TIMEOUT, &mut task).await.is_err() {
+            task.abort();
+            let _ = task.await;
+        }
+        true
+    }
+}
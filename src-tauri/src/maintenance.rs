@@ -0,0 +1,335 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tokio::sync::Mutex;
+use tokio::time;
+
+use crate::database::{Database, JobPruneResult, MaintenanceRun, MaintenanceSettings, MaintenanceTaskResult};
+use crate::embeddings::VectorService;
+use crate::commands::pdf::get_pdf_storage_dir;
+use crate::media::{card_attachments, get_attachments_dir};
+
+pub const MAINTENANCE_SETTINGS_KEY: &str = "maintenance";
+
+// How often the scheduler wakes up to see whether a run is due
+const SCHEDULER_CHECK_INTERVAL_SECS: u64 = 15 * 60;
+
+// Unreferenced files younger than this are left alone; they may belong to an import in progress
+const ORPHAN_GRACE_PERIOD_SECS: u64 = 24 * 60 * 60;
+
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+// Only one maintenance run at a time, whether scheduled or manual
+static RUN_LOCK: Mutex<()> = Mutex::const_new(());
+
+pub struct MaintenanceScheduler {
+    database: DatabaseState,
+    vector_service: VectorServiceState,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(database: DatabaseState, vector_service: VectorServiceState) -> Self {
+        Self { database, vector_service }
+    }
+
+    /// Start the scheduling loop; runs maintenance whenever the configured interval has elapsed
+    pub fn start(self) {
+        println!("🧹 Starting maintenance scheduler...");
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(SCHEDULER_CHECK_INTERVAL_SECS));
+
+            loop {
+                interval.tick().await;
+
+                match self.is_due().await {
+                    Ok(true) => {
+                        if let Err(e) = run_maintenance(&self.database, &self.vector_service, "scheduled").await {
+                            eprintln!("❌ Scheduled maintenance failed: {}", e);
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(e) => eprintln!("⚠️ Could not check maintenance schedule: {}", e),
+                }
+            }
+        });
+    }
+
+    async fn is_due(&self) -> Result<bool, String> {
+        let db_guard = self.database.lock().await;
+        let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+        let settings: MaintenanceSettings = database.get_typed_setting(MAINTENANCE_SETTINGS_KEY).await
+            .map_err(|e| format!("Failed to load maintenance settings: {}", e))?;
+        if !settings.enabled {
+            return Ok(false);
+        }
+
+        let last_run = database.get_last_maintenance_completed_at().await
+            .map_err(|e| format!("Failed to load maintenance history: {}", e))?;
+
+        Ok(match last_run {
+            Some(last_run) => Utc::now() - last_run >= chrono::Duration::hours(settings.interval_hours.max(1)),
+            None => true,
+        })
+    }
+}
+
+/// Run every maintenance task, recording the outcome of each in the run history.
+/// A failing task doesn't stop the others.
+pub async fn run_maintenance(
+    database_state: &DatabaseState,
+    vector_state: &VectorServiceState,
+    trigger: &str,
+) -> Result<MaintenanceRun, String> {
+    let _run_guard = RUN_LOCK.try_lock().map_err(|_| "Maintenance is already running".to_string())?;
+
+    let db_guard = database_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+
+    let settings: MaintenanceSettings = database.get_typed_setting(MAINTENANCE_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to load maintenance settings: {}", e))?;
+    let mut run = database.start_maintenance_run(trigger).await
+        .map_err(|e| format!("Failed to record maintenance run: {}", e))?;
+
+    println!("🧹 Running {} maintenance ({})", trigger, run.id);
+
+    run.tasks.push(task_result("backup", backup_database(database, settings.backup_retention).await));
+    run.tasks.push(task_result("integrity_check", check_integrity(database).await));
+    run.tasks.push(task_result("trash_purge", purge_orphaned_files(database).await));
+    run.tasks.push(task_result("stale_jobs", cleanup_jobs(database, &settings).await));
+    let document_ids = database.get_all_document_ids().await;
+    drop(db_guard);
+
+    run.tasks.push(task_result("orphaned_embeddings", purge_orphaned_embeddings(vector_state, document_ids).await));
+    run.tasks.push(task_result("vector_optimize", optimize_vectors(vector_state).await));
+
+    run.completed_at = Some(Utc::now());
+    run.status = if run.tasks.iter().all(|t| t.success) { "completed" } else { "failed" }.to_string();
+    run.error_message = run.tasks.iter()
+        .filter(|t| !t.success)
+        .map(|t| format!("{}: {}", t.task, t.message))
+        .reduce(|a, b| format!("{}; {}", a, b));
+
+    let db_guard = database_state.lock().await;
+    let database = db_guard.as_ref().ok_or("Database not initialized")?;
+    database.finish_maintenance_run(&run).await
+        .map_err(|e| format!("Failed to record maintenance run: {}", e))?;
+
+    println!("✅ Maintenance {} finished: {}", run.id, run.status);
+    Ok(run)
+}
+
+fn task_result(task: &str, outcome: Result<String, String>) -> MaintenanceTaskResult {
+    match outcome {
+        Ok(message) => MaintenanceTaskResult { task: task.to_string(), success: true, message },
+        Err(message) => {
+            eprintln!("⚠️ Maintenance task {} failed: {}", task, message);
+            MaintenanceTaskResult { task: task.to_string(), success: false, message }
+        }
+    }
+}
+
+fn get_backup_dir() -> Result<PathBuf, String> {
+    let backup_dir = crate::paths::app_data_dir()?.join("backups");
+
+    std::fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    Ok(backup_dir)
+}
+
+/// Write a timestamped copy of the database to the backups dir, keeping the newest `retention`
+pub async fn backup_database(database: &Database, retention: i64) -> Result<String, String> {
+    let backup_dir = get_backup_dir()?;
+    let backup_path = backup_dir.join(format!("documents-{}.db", Utc::now().format("%Y%m%d-%H%M%S")));
+
+    database.backup_to(&backup_path).await
+        .map_err(|e| format!("Backup failed: {}", e))?;
+
+    // Keep only the newest `retention` backups; names sort chronologically
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(&backup_dir)
+        .map_err(|e| format!("Failed to list backups: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("documents-") && n.ends_with(".db"))
+                .unwrap_or(false)
+        })
+        .collect();
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(retention.max(1) as usize);
+    for old_backup in backups.iter().take(excess) {
+        if let Err(e) = std::fs::remove_file(old_backup) {
+            eprintln!("⚠️ Failed to remove old backup {:?}: {}", old_backup, e);
+        }
+    }
+
+    Ok(format!("Backed up to {}", backup_path.to_string_lossy()))
+}
+
+async fn check_integrity(database: &Database) -> Result<String, String> {
+    let problems = database.integrity_check().await
+        .map_err(|e| format!("Integrity check failed to run: {}", e))?;
+
+    if problems.is_empty() {
+        Ok("Database integrity ok".to_string())
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+async fn purge_orphaned_files(database: &Database) -> Result<String, String> {
+    let (pdf_files, flashcard_metadata) = database.get_referenced_files().await
+        .map_err(|e| format!("Failed to load referenced files: {}", e))?;
+
+    let referenced_pdfs: HashSet<String> = pdf_files.iter()
+        .filter_map(|path| Path::new(path).file_name().and_then(|n| n.to_str()).map(|n| n.to_string()))
+        .collect();
+    let cover_files = database.get_document_cover_files().await
+        .map_err(|e| format!("Failed to load document covers: {}", e))?;
+    let handwriting_images = database.get_handwriting_image_files().await
+        .map_err(|e| format!("Failed to load handwriting images: {}", e))?;
+    let referenced_attachments: HashSet<String> = flashcard_metadata.iter()
+        .flat_map(card_attachments)
+        .chain(cover_files)
+        .chain(handwriting_images)
+        .collect();
+
+    let removed_pdfs = remove_unreferenced(&get_pdf_storage_dir()?, &referenced_pdfs)?;
+    let removed_attachments = remove_unreferenced(&get_attachments_dir()?, &referenced_attachments)?;
+
+    Ok(format!("Removed {} orphaned PDFs and {} orphaned attachments", removed_pdfs, removed_attachments))
+}
+
+fn remove_unreferenced(dir: &Path, referenced: &HashSet<String>) -> Result<usize, String> {
+    let mut removed = 0;
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read {:?}: {}", dir, e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = match path.file_name().and_then(|n| n.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        if !path.is_file() || referenced.contains(&name) {
+            continue;
+        }
+
+        let old_enough = entry.metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age.as_secs() >= ORPHAN_GRACE_PERIOD_SECS)
+            .unwrap_or(false);
+
+        if old_enough && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+async fn cleanup_jobs(database: &Database, settings: &MaintenanceSettings) -> Result<String, String> {
+    let failed = database.fail_stale_jobs(settings.stale_job_hours).await
+        .map_err(|e| format!("Failed to stop stalled jobs: {}", e))?;
+
+    let completed = prune_jobs(database, settings.job_retention_days, &["completed".to_string()], settings.archive_pruned_jobs).await?;
+    let failed_pruned = prune_jobs(database, settings.failed_job_retention_days, &["failed".to_string()], settings.archive_pruned_jobs).await?;
+
+    Ok(format!(
+        "Stopped {} stalled jobs and removed {} completed and {} failed old jobs",
+        failed, completed.deleted, failed_pruned.deleted
+    ))
+}
+
+fn get_job_archive_dir() -> Result<PathBuf, String> {
+    let archive_dir = crate::paths::app_data_dir()?.join("job_archive");
+
+    std::fs::create_dir_all(&archive_dir)
+        .map_err(|e| format!("Failed to create job archive directory: {}", e))?;
+
+    Ok(archive_dir)
+}
+
+/// Delete finished jobs with one of `statuses` older than `older_than_days`. When `archive` is
+/// set they are first appended to a monthly JSONL file in stellar_data/job_archive.
+pub async fn prune_jobs(database: &Database, older_than_days: i64, statuses: &[String], archive: bool) -> Result<JobPruneResult, String> {
+    // Open the archive before deleting anything so an unwritable archive loses no history
+    let archive_file = if archive {
+        let path = get_job_archive_dir()?.join(format!("jobs-{}.jsonl", Utc::now().format("%Y-%m")));
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(&path)
+            .map_err(|e| format!("Failed to open job archive {:?}: {}", path, e))?;
+        Some((path, file))
+    } else {
+        None
+    };
+
+    let jobs = database.prune_processing_jobs(older_than_days.max(0), statuses).await
+        .map_err(|e| format!("Failed to prune processing jobs: {}", e))?;
+
+    let archive_path = match archive_file {
+        Some((path, mut file)) if !jobs.is_empty() => {
+            for job in &jobs {
+                let line = serde_json::to_string(job)
+                    .map_err(|e| format!("Failed to serialize job {}: {}", job.id, e))?;
+                writeln!(file, "{}", line)
+                    .map_err(|e| format!("Failed to write job archive {:?}: {}", path, e))?;
+            }
+            Some(path.to_string_lossy().to_string())
+        }
+        _ => None,
+    };
+
+    if !jobs.is_empty() {
+        println!("🗄️ Pruned {} {} jobs older than {} days", jobs.len(), statuses.join("/"), older_than_days);
+    }
+
+    Ok(JobPruneResult {
+        deleted: jobs.len() as u64,
+        archive_path,
+    })
+}
+
+async fn purge_orphaned_embeddings(
+    vector_state: &VectorServiceState,
+    document_ids: Result<Vec<String>, sqlx::Error>,
+) -> Result<String, String> {
+    let document_ids: HashSet<String> = document_ids
+        .map_err(|e| format!("Failed to load document ids: {}", e))?
+        .into_iter()
+        .collect();
+
+    let mut vector_guard = vector_state.lock().await;
+    let vector_service = match vector_guard.as_mut() {
+        Some(service) => service,
+        None => return Ok("Vector service not initialized; skipped".to_string()),
+    };
+
+    let grace_hours = (ORPHAN_GRACE_PERIOD_SECS / 3600) as i64;
+    let removed = vector_service.delete_orphaned_chunks(&document_ids, grace_hours)
+        .map_err(|e| format!("Failed to remove orphaned embeddings: {}", e))?;
+
+    Ok(format!("Removed {} orphaned embedding chunks", removed))
+}
+
+async fn optimize_vectors(vector_state: &VectorServiceState) -> Result<String, String> {
+    let mut vector_guard = vector_state.lock().await;
+    let vector_service = match vector_guard.as_mut() {
+        Some(service) => service,
+        None => return Ok("Vector service not initialized; skipped".to_string()),
+    };
+
+    vector_service.optimize()
+        .map_err(|e| format!("Vector index optimization failed: {}", e))?;
+
+    Ok("Vector index optimized".to_string())
+}
@@ -0,0 +1,231 @@
+//! Math in document text: spotting bare equations in extracted text, and normalizing math so
+//! Unicode, LaTeX and spelled-out notation compare equal, e.g. "∇·E", "\nabla \cdot \mathbf{E}"
+//! and "div E" all become "div e". Normalized terms are stored per document for search.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::embeddings::find_math_regions;
+
+// Symbols that only show up in math, used to pick out equations and math-bearing lines
+const STRONG_MATH_CHARS: &[char] = &['∇', '∂', '∫', '∮', '∑', '∏', '√', '≤', '≥', '≠', '≈', '≡', '±', '∞', '∈', '∀', '∃', '⊂', '⊆', '∪', '∩'];
+const RELATION_CHARS: &[char] = &['=', '<', '>', '≤', '≥', '≠', '≈', '≡', '→', '∝'];
+// Short words allowed on an equation line without it reading as prose
+const MATH_WORDS: &[&str] = &["sin", "cos", "tan", "log", "ln", "exp", "lim", "max", "min", "det", "div", "curl", "grad", "rot", "mod", "arg"];
+const MAX_EQUATION_CHARS: usize = 200;
+
+const GREEK: &[(char, &str)] = &[
+    ('α', "alpha"), ('β', "beta"), ('γ', "gamma"), ('δ', "delta"), ('ε', "epsilon"), ('ϵ', "epsilon"),
+    ('ζ', "zeta"), ('η', "eta"), ('θ', "theta"), ('ϑ', "theta"), ('ι', "iota"), ('κ', "kappa"),
+    ('λ', "lambda"), ('μ', "mu"), ('ν', "nu"), ('ξ', "xi"), ('ο', "omicron"), ('π', "pi"),
+    ('ρ', "rho"), ('σ', "sigma"), ('ς', "sigma"), ('τ', "tau"), ('υ', "upsilon"), ('φ', "phi"),
+    ('ϕ', "phi"), ('χ', "chi"), ('ψ', "psi"), ('ω', "omega"),
+    ('Γ', "gamma"), ('Δ', "delta"), ('Θ', "theta"), ('Λ', "lambda"), ('Ξ', "xi"), ('Π', "pi"),
+    ('Σ', "sigma"), ('Φ', "phi"), ('Ψ', "psi"), ('Ω', "omega"),
+];
+
+const SYMBOLS: &[(char, &str)] = &[
+    ('∂', "partial"), ('∫', "integral"), ('∮', "integral"), ('∑', "sum"), ('∏', "prod"),
+    ('√', "sqrt"), ('∞', "infinity"), ('·', "dot"), ('⋅', "dot"), ('×', "cross"),
+    ('≤', "<="), ('≥', ">="), ('≠', "!="), ('≈', "~"), ('≡', "=="), ('→', "->"), ('±', "+-"),
+    ('−', "-"), ('∈', "in"), ('∝', "propto"),
+];
+
+// LaTeX commands that map to one of the symbols above (or a Greek letter, by name)
+const COMMANDS: &[(&str, &str)] = &[
+    ("nabla", "∇"), ("partial", "∂"), ("int", "∫"), ("oint", "∮"), ("sum", "∑"), ("prod", "∏"),
+    ("sqrt", "√"), ("infty", "∞"), ("cdot", "·"), ("times", "×"), ("leq", "≤"), ("le", "≤"),
+    ("geq", "≥"), ("ge", "≥"), ("neq", "≠"), ("ne", "≠"), ("approx", "≈"), ("equiv", "≡"),
+    ("to", "→"), ("rightarrow", "→"), ("pm", "±"), ("in", "∈"), ("propto", "∝"),
+    ("varepsilon", "epsilon"), ("varphi", "phi"), ("vartheta", "theta"), ("varsigma", "sigma"),
+];
+
+// Formatting that doesn't change what the math says
+const IGNORED_COMMANDS: &[&str] = &[
+    "mathbf", "mathrm", "mathit", "mathcal", "mathbb", "boldsymbol", "vec", "hat", "bar", "tilde",
+    "text", "textrm", "operatorname", "left", "right", "displaystyle", "big", "Big", "bigg", "Bigg",
+];
+
+const SYNONYMS: &[(&str, &str)] = &[
+    ("divergence", "div"), ("gradient", "grad"), ("rot", "curl"), ("nabla", "grad"), ("laplace", "laplacian"),
+];
+
+fn frac_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\\[dt]?frac\s*\{([^{}]*)\}\s*\{([^{}]*)\}").expect("valid frac pattern"))
+}
+
+fn command_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\\([A-Za-z]+)|\\[,;:! ]").expect("valid command pattern"))
+}
+
+// Vector calculus operators written with nabla: divergence, curl, Laplacian, gradient
+fn nabla_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"∇\s*(·|⋅|×|²|\^\s*\{?2\}?)?").expect("valid nabla pattern"))
+}
+
+/// Normalize math for comparison: LaTeX and Unicode notation become the same lowercase,
+/// space-separated tokens. Not meant for display.
+pub fn normalize_math(text: &str) -> String {
+    let text = text.replace('$', " ");
+    let text = frac_pattern().replace_all(&text, " ($1) / ($2) ");
+    let text = command_pattern().replace_all(&text, |caps: &regex::Captures| {
+        let Some(name) = caps.get(1).map(|m| m.as_str()) else {
+            return " ".to_string(); // \, \; and friends are spacing
+        };
+        if IGNORED_COMMANDS.contains(&name) {
+            return " ".to_string();
+        }
+        if let Some((_, replacement)) = COMMANDS.iter().find(|(command, _)| *command == name) {
+            return format!(" {} ", replacement);
+        }
+        format!(" {} ", name) // Greek letters and anything else keep their name
+    });
+    let text = nabla_pattern().replace_all(&text, |caps: &regex::Captures| {
+        match caps.get(1).map(|m| m.as_str()) {
+            Some("·") | Some("⋅") => " div ",
+            Some("×") => " curl ",
+            Some(_) => " laplacian ",
+            None => " grad ",
+        }.to_string()
+    });
+
+    let mut expanded = String::with_capacity(text.len());
+    for c in text.chars() {
+        if let Some((_, name)) = GREEK.iter().chain(SYMBOLS).find(|(symbol, _)| *symbol == c) {
+            expanded.push(' ');
+            expanded.push_str(name);
+            expanded.push(' ');
+        } else if let Some(digit) = subscript_digit(c) {
+            expanded.push(' ');
+            expanded.push(digit);
+        } else if let Some(digit) = superscript_digit(c) {
+            expanded.push_str(" ^ ");
+            expanded.push(digit);
+        } else if c == '_' {
+            expanded.push(' ');
+        } else {
+            expanded.push(c);
+        }
+    }
+
+    tokenize(&expanded).join(" ")
+}
+
+// Words, numbers and operators; braces only group and are dropped
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, tokens: &mut Vec<String>| {
+        if !word.is_empty() {
+            let lower = word.to_lowercase();
+            let token = SYNONYMS.iter().find(|(from, _)| *from == lower).map(|(_, to)| to.to_string()).unwrap_or(lower);
+            tokens.push(token);
+            word.clear();
+        }
+    };
+
+    for c in text.chars() {
+        if c.is_alphanumeric() || (c == '.' && word.chars().last().map_or(false, |last| last.is_ascii_digit())) {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut tokens);
+            if !c.is_whitespace() && !matches!(c, '{' | '}' | '\\') {
+                tokens.push(c.to_string());
+            }
+        }
+    }
+    flush(&mut word, &mut tokens);
+
+    // Multi-character operators were spaced apart above
+    let mut merged: Vec<String> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        let joined = merged.last().map(|last| format!("{}{}", last, token));
+        match joined.as_deref() {
+            Some("<=") | Some(">=") | Some("!=") | Some("==") | Some("->") | Some("+-") => {
+                merged.pop();
+                merged.push(joined.unwrap_or_default());
+            }
+            _ => merged.push(token),
+        }
+    }
+    merged
+}
+
+fn subscript_digit(c: char) -> Option<char> {
+    match c {
+        '₀'..='₉' => char::from_digit(c as u32 - '₀' as u32, 10),
+        _ => None,
+    }
+}
+
+fn superscript_digit(c: char) -> Option<char> {
+    match c {
+        '⁰' => Some('0'),
+        '¹' => Some('1'),
+        '²' => Some('2'),
+        '³' => Some('3'),
+        '⁴'..='⁹' => char::from_digit(c as u32 - '⁰' as u32, 10),
+        _ => None,
+    }
+}
+
+/// Whether an extracted line is a standalone equation rather than prose, e.g. "∇ · E = ρ/ε₀"
+pub fn looks_like_equation(line: &str) -> bool {
+    let line = line.trim();
+    if line.is_empty() || line.chars().count() > MAX_EQUATION_CHARS || line.contains('$') {
+        return false;
+    }
+    if !line.chars().any(|c| RELATION_CHARS.contains(&c)) {
+        return false;
+    }
+
+    let math_chars = line.chars()
+        .filter(|c| {
+            STRONG_MATH_CHARS.contains(c)
+                || GREEK.iter().any(|(greek, _)| greek == c)
+                || subscript_digit(*c).is_some()
+                || superscript_digit(*c).is_some()
+                || matches!(c, '·' | '⋅' | '×' | '→' | '∝' | '−')
+        })
+        .count();
+    if math_chars == 0 {
+        return false;
+    }
+
+    // Prose has real words; equations have single letters, numbers and function names
+    !line.split(|c: char| !c.is_alphabetic())
+        .filter(|word| word.chars().count() >= 3)
+        .any(|word| !MATH_WORDS.contains(&word.to_lowercase().as_str()))
+}
+
+/// Normalized terms for every math span in `content`, plus lines of plain text that use math
+/// symbols, joined with " | ". Empty when the content has no math.
+pub fn index_terms(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let regions = find_math_regions(content);
+    let mut terms: Vec<String> = regions.iter()
+        .map(|region| normalize_math(&chars[region.start..region.end].iter().collect::<String>()))
+        .filter(|term| !term.is_empty())
+        .collect();
+
+    let mut outside = String::with_capacity(content.len());
+    let mut position = 0;
+    for region in &regions {
+        outside.extend(&chars[position..region.start]);
+        outside.push('\n');
+        position = region.end;
+    }
+    outside.extend(&chars[position.min(chars.len())..]);
+    for line in outside.lines().filter(|line| line.chars().any(|c| STRONG_MATH_CHARS.contains(&c))) {
+        let term = normalize_math(line);
+        if !term.is_empty() {
+            terms.push(term);
+        }
+    }
+
+    terms.join(" | ")
+}
@@ -0,0 +1,183 @@
+//! Model Context Protocol server, so Claude Desktop and other MCP clients can search the
+//! library, read documents and work with flashcards. It uses the Streamable HTTP transport on
+//! 127.0.0.1 (`POST /mcp`, JSON responses only) inside the running app, sharing its database
+//! rather than opening a second connection. Clients limited to stdio can reach it through a
+//! bridge such as `npx mcp-remote http://127.0.0.1:<port>/mcp --header "Authorization: Bearer <token>"`.
+
+pub mod tools;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::commands::database::DatabaseState;
+use crate::local_server::{self, bearer_token_matches, LocalServer, LocalServerSettings};
+use crate::database::{Database, McpServerSettings};
+
+pub const MCP_SETTINGS_KEY: &str = "mcp_server";
+
+// Newest first; a client asking for another version is offered the newest
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26"];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Clone)]
+struct ServerContext {
+    database: DatabaseState,
+    token: String,
+    allow_writes: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcRequest {
+    #[serde(default)]
+    id: Option<Value>, // Absent for notifications
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// Managed state holding the running server task, if any
+#[derive(Default)]
+pub struct McpServer(LocalServer);
+
+impl McpServer {
+    pub fn is_running(&self) -> bool {
+        self.0.is_running()
+    }
+
+    /// Listen with `settings`, replacing a server that is already running
+    pub async fn start(&self, database: DatabaseState, settings: &McpServerSettings) -> Result<(), String> {
+        let token = settings.token.clone().ok_or("No MCP token has been generated")?;
+        let context = ServerContext {
+            database,
+            token,
+            allow_writes: settings.allow_writes,
+        };
+        // GET would open a server-to-client event stream, which this server never sends
+        let router = Router::new()
+            .route("/mcp", post(handle_post).get(|| async { StatusCode::METHOD_NOT_ALLOWED }))
+            .with_state(context);
+
+        self.0.start(settings.port, router, "MCP server").await?;
+        println!("🤖 MCP server listening on http://127.0.0.1:{}/mcp", settings.port);
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        if self.0.stop().await {
+            println!("🤖 Stopped MCP server");
+        }
+    }
+}
+
+impl LocalServerSettings for McpServerSettings {
+    fn token_mut(&mut self) -> &mut Option<String> {
+        &mut self.token
+    }
+}
+
+/// Load the settings, generating and saving a token the first time
+pub async fn load_mcp_settings(database: &Database) -> Result<McpServerSettings, String> {
+    local_server::load_settings(database, MCP_SETTINGS_KEY).await
+}
+
+/// Browsers send an Origin header; only pages served from this machine may call in, which
+/// stops DNS rebinding attacks from arbitrary websites
+fn origin_allowed(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(axum::http::header::ORIGIN).and_then(|value| value.to_str().ok()) else {
+        return true;
+    };
+    crate::network::is_local_url(origin)
+}
+
+fn rpc_result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn rpc_error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message.into() } })
+}
+
+async fn handle_post(State(context): State<ServerContext>, headers: HeaderMap, body: String) -> Response {
+    if !origin_allowed(&headers) {
+        return (StatusCode::FORBIDDEN, "Origin not allowed").into_response();
+    }
+    if !bearer_token_matches(&headers, &context.token) {
+        return (StatusCode::UNAUTHORIZED, "Missing or invalid token").into_response();
+    }
+
+    let message: Value = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(e) => return Json(rpc_error(Value::Null, PARSE_ERROR, format!("Invalid JSON: {}", e))).into_response(),
+    };
+    let request: JsonRpcRequest = match serde_json::from_value(message) {
+        Ok(request) => request,
+        Err(e) => return Json(rpc_error(Value::Null, INVALID_REQUEST, format!("Invalid request: {}", e))).into_response(),
+    };
+
+    // Notifications (initialized, cancelled) and client responses need no reply
+    let Some(id) = request.id else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+
+    let response = match request.method.as_str() {
+        "initialize" => rpc_result(id, initialize_result(&request.params)),
+        "ping" => rpc_result(id, json!({})),
+        "tools/list" => rpc_result(id, json!({ "tools": tools::tool_definitions(context.allow_writes) })),
+        "tools/call" => {
+            let Some(name) = request.params.get("name").and_then(|name| name.as_str()) else {
+                return Json(rpc_error(id, INVALID_PARAMS, "Missing tool name")).into_response();
+            };
+            let arguments = request.params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+            if !tools::is_known_tool(name) {
+                return Json(rpc_error(id, INVALID_PARAMS, format!("Unknown tool: {}", name))).into_response();
+            }
+
+            println!("🤖 MCP tool call: {}", name);
+            let db_guard = context.database.lock().await;
+            let outcome = match db_guard.as_ref() {
+                Some(database) => tools::call_tool(database, name, &arguments, context.allow_writes).await,
+                None => Err("The library is still loading".to_string()),
+            };
+            drop(db_guard);
+
+            // Tool failures are results the model can read, not protocol errors
+            rpc_result(id, match outcome {
+                Ok(value) => json!({
+                    "content": [{ "type": "text", "text": serde_json::to_string_pretty(&value).unwrap_or_default() }],
+                    "structuredContent": value,
+                    "isError": false,
+                }),
+                Err(message) => json!({
+                    "content": [{ "type": "text", "text": message }],
+                    "isError": true,
+                }),
+            })
+        }
+        method => rpc_error(id, METHOD_NOT_FOUND, format!("Method not found: {}", method)),
+    };
+
+    Json(response).into_response()
+}
+
+fn initialize_result(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(|version| version.as_str()).unwrap_or_default();
+    let version = SUPPORTED_PROTOCOL_VERSIONS.iter()
+        .find(|supported| **supported == requested)
+        .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": { "listChanged": false } },
+        "serverInfo": { "name": "stellar", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "Tools for the user's Stellar study library: search and read their documents, list flashcard decks and due cards, and add flashcards.",
+    })
+}
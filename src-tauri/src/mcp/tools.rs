@@ -0,0 +1,295 @@
+//! Tools exposed over MCP. Results are plain JSON built here rather than the app's own types,
+//! so the shape clients see stays stable and large fields (full content, SRS internals) are
+//! only sent when asked for.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::database::{CreateFlashcardRequest, CustomReviewFilter, Database, Document, Flashcard};
+
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+const MAX_SEARCH_LIMIT: i64 = 50;
+const DEFAULT_DOCUMENT_CHARS: usize = 20_000;
+const SNIPPET_CHARS: usize = 300;
+const DEFAULT_DUE_LIMIT: i32 = 20;
+
+// Tools that change the library; hidden and refused unless writes are allowed
+const WRITE_TOOLS: &[&str] = &["create_flashcard"];
+const TOOL_NAMES: &[&str] = &["search_documents", "get_document", "list_decks", "list_due_cards", "create_flashcard"];
+
+pub fn is_known_tool(name: &str) -> bool {
+    TOOL_NAMES.contains(&name)
+}
+
+pub fn tool_definitions(allow_writes: bool) -> Vec<Value> {
+    let tools = vec![
+        json!({
+            "name": "search_documents",
+            "title": "Search documents",
+            "description": "Find documents in the library whose title, content or tags contain the query. Returns ids, titles and a snippet around the match.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Text to look for" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_SEARCH_LIMIT, "description": "Most results to return (default 10)" },
+                    "math": { "type": "boolean", "description": "Also match the query as math, so \"∇·E\" and \"div E\" find the same equations" }
+                },
+                "required": ["query"]
+            },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": "get_document",
+            "title": "Read a document",
+            "description": "Get a document's markdown content and details by id. Long documents are cut off at max_chars.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "max_chars": { "type": "integer", "minimum": 1, "description": "Most characters of content to return (default 20000)" }
+                },
+                "required": ["id"]
+            },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": "list_decks",
+            "title": "List flashcard decks",
+            "description": "List flashcard decks with their card and due counts.",
+            "inputSchema": { "type": "object", "properties": {} },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": "list_due_cards",
+            "title": "List due flashcards",
+            "description": "List flashcards that are due for review, soonest first, optionally from one deck.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "deck_id": { "type": "string" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": 500, "description": "Most cards to return (default 20)" }
+                }
+            },
+            "annotations": { "readOnlyHint": true }
+        }),
+        json!({
+            "name": "create_flashcard",
+            "title": "Create a flashcard",
+            "description": "Add a basic front/back flashcard, optionally to a deck and linked to the document it came from.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "front": { "type": "string", "description": "Question or prompt" },
+                    "back": { "type": "string", "description": "Answer" },
+                    "deck_id": { "type": "string" },
+                    "source_document_id": { "type": "string" },
+                    "tags": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["front", "back"]
+            },
+            "annotations": { "readOnlyHint": false, "destructiveHint": false }
+        }),
+    ];
+
+    tools.into_iter()
+        .filter(|tool| allow_writes || !WRITE_TOOLS.contains(&tool["name"].as_str().unwrap_or_default()))
+        .collect()
+}
+
+pub async fn call_tool(database: &Database, name: &str, arguments: &Value, allow_writes: bool) -> Result<Value, String> {
+    if WRITE_TOOLS.contains(&name) && !allow_writes {
+        return Err(format!("{} is disabled: the library is shared read-only", name));
+    }
+
+    match name {
+        "search_documents" => search_documents(database, parse_arguments(arguments)?).await,
+        "get_document" => get_document(database, parse_arguments(arguments)?).await,
+        "list_decks" => list_decks(database).await,
+        "list_due_cards" => list_due_cards(database, parse_arguments(arguments)?).await,
+        "create_flashcard" => create_flashcard(database, parse_arguments(arguments)?).await,
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+fn parse_arguments<T: for<'de> Deserialize<'de>>(arguments: &Value) -> Result<T, String> {
+    serde_json::from_value(arguments.clone()).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+#[derive(Deserialize)]
+struct SearchArguments {
+    query: String,
+    limit: Option<i64>,
+    #[serde(default)]
+    math: bool,
+}
+
+#[derive(Deserialize)]
+struct GetDocumentArguments {
+    id: String,
+    max_chars: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct DueCardsArguments {
+    deck_id: Option<String>,
+    limit: Option<i32>,
+}
+
+#[derive(Deserialize)]
+struct CreateFlashcardArguments {
+    front: String,
+    back: String,
+    deck_id: Option<String>,
+    source_document_id: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// About SNIPPET_CHARS of content around the first case-insensitive match
+fn snippet(content: &str, query: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let lower: Vec<char> = content.to_lowercase().chars().collect();
+    let needle: Vec<char> = query.to_lowercase().chars().collect();
+
+    // Lowercasing can change the length of some characters; fall back to the start then
+    let position = if lower.len() == chars.len() && !needle.is_empty() {
+        lower.windows(needle.len()).position(|window| window == needle.as_slice())
+    } else {
+        None
+    };
+    let start = position.map(|position| position.saturating_sub(SNIPPET_CHARS / 3)).unwrap_or(0);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+
+    let mut snippet: String = chars[start..end].iter().collect();
+    snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+fn document_summary(document: &Document) -> Value {
+    json!({
+        "id": document.id,
+        "title": document.title,
+        "doc_type": document.doc_type,
+        "tags": document.tags,
+        "source_url": document.source_url,
+        "updated_at": document.updated_at,
+    })
+}
+
+fn flashcard_summary(card: &Flashcard) -> Value {
+    json!({
+        "id": card.id,
+        "front": card.front,
+        "back": card.back,
+        "deck_id": card.deck_id,
+        "tags": card.tags,
+        "next_review": card.next_review,
+        "review_count": card.review_count,
+    })
+}
+
+async fn search_documents(database: &Database, arguments: SearchArguments) -> Result<Value, String> {
+    let query = arguments.query.trim();
+    if query.is_empty() {
+        return Err("The query is empty".to_string());
+    }
+    let limit = arguments.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    let documents = database.search_documents(query, limit, arguments.math).await
+        .map_err(|e| format!("Search failed: {}", e))?;
+
+    let results: Vec<Value> = documents.iter()
+        .map(|document| {
+            let mut summary = document_summary(document);
+            summary["snippet"] = json!(snippet(&document.content, query));
+            summary
+        })
+        .collect();
+    Ok(json!({ "results": results }))
+}
+
+async fn get_document(database: &Database, arguments: GetDocumentArguments) -> Result<Value, String> {
+    let document = database.get_document(&arguments.id).await
+        .map_err(|e| format!("Failed to get document: {}", e))?
+        .ok_or_else(|| format!("No document with id {}", arguments.id))?;
+
+    let max_chars = arguments.max_chars.unwrap_or(DEFAULT_DOCUMENT_CHARS).max(1);
+    let total_chars = document.content.chars().count();
+    let content: String = document.content.chars().take(max_chars).collect();
+
+    let mut result = document_summary(&document);
+    result["created_at"] = json!(document.created_at);
+    result["status"] = json!(document.status);
+    result["content"] = json!(content);
+    result["total_chars"] = json!(total_chars);
+    result["truncated"] = json!(total_chars > max_chars);
+    Ok(result)
+}
+
+async fn list_decks(database: &Database) -> Result<Value, String> {
+    let decks = database.get_flashcard_decks().await
+        .map_err(|e| format!("Failed to list decks: {}", e))?;
+
+    let decks: Vec<Value> = decks.iter()
+        .map(|deck| json!({
+            "id": deck.id,
+            "name": deck.name,
+            "description": deck.description,
+            "card_count": deck.card_count,
+            "due_count": deck.due_count,
+        }))
+        .collect();
+    Ok(json!({ "decks": decks }))
+}
+
+async fn list_due_cards(database: &Database, arguments: DueCardsArguments) -> Result<Value, String> {
+    let filter = CustomReviewFilter {
+        deck_id: arguments.deck_id,
+        due_within_days: Some(0),
+        limit: Some(arguments.limit.unwrap_or(DEFAULT_DUE_LIMIT)),
+        ..Default::default()
+    };
+    let session = database.get_custom_review_session(&filter).await
+        .map_err(|e| format!("Failed to list due cards: {}", e))?;
+
+    let cards: Vec<Value> = session.due_cards.iter()
+        .chain(&session.new_cards)
+        .map(flashcard_summary)
+        .collect();
+    Ok(json!({ "cards": cards }))
+}
+
+async fn create_flashcard(database: &Database, arguments: CreateFlashcardArguments) -> Result<Value, String> {
+    let front = arguments.front.trim();
+    let back = arguments.back.trim();
+    if front.is_empty() || back.is_empty() {
+        return Err("Both front and back are required".to_string());
+    }
+    if let Some(deck_id) = &arguments.deck_id {
+        database.get_flashcard_deck(deck_id).await
+            .map_err(|e| format!("Failed to look up deck: {}", e))?
+            .ok_or_else(|| format!("No deck with id {}", deck_id))?;
+    }
+
+    let card = database.create_flashcard(CreateFlashcardRequest {
+        front: front.to_string(),
+        back: back.to_string(),
+        source_document_id: arguments.source_document_id,
+        source_text: None,
+        difficulty: None,
+        tags: arguments.tags,
+        category_id: None,
+        card_type: Some("basic".to_string()),
+        deck_id: arguments.deck_id,
+        metadata: Some(json!({ "created_via": "mcp" })),
+    }).await
+        .map_err(|e| format!("Failed to create flashcard: {}", e))?;
+
+    Ok(flashcard_summary(&card))
+}
@@ -0,0 +1,99 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+// Helper function to get the attachment storage directory (flashcard images, audio, etc.)
+pub fn get_attachments_dir() -> Result<PathBuf, String> {
+    let storage_dir = crate::paths::app_data_dir()?.join("attachments");
+
+    std::fs::create_dir_all(&storage_dir)
+        .map_err(|e| format!("Failed to create attachments directory: {}", e))?;
+
+    Ok(storage_dir)
+}
+
+/// Write bytes to a new attachment and return its file name (relative to the attachments dir)
+pub fn store_attachment(bytes: &[u8], extension: &str) -> Result<String, String> {
+    let extension = extension.trim_start_matches('.').to_lowercase();
+    let file_name = format!("{}.{}", Uuid::new_v4(), extension);
+    let path = get_attachments_dir()?.join(&file_name);
+
+    crate::storage::ensure_space(&path, bytes.len() as u64)?;
+    std::fs::write(&path, bytes)
+        .map_err(|e| format!("Failed to write attachment: {}", e))?;
+
+    Ok(file_name)
+}
+
+/// Copy an existing file into the attachments dir, keeping its extension
+pub fn import_attachment(source_path: &str) -> Result<String, String> {
+    let extension = Path::new(source_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin")
+        .to_string();
+    let bytes = std::fs::read(source_path)
+        .map_err(|e| format!("Failed to read {}: {}", source_path, e))?;
+
+    store_attachment(&bytes, &extension)
+}
+
+/// Resolve an attachment file name, refusing anything that would escape the attachments dir
+pub fn resolve_attachment(file_name: &str) -> Result<PathBuf, String> {
+    let is_plain_name = Path::new(file_name).file_name().and_then(|n| n.to_str()) == Some(file_name);
+    if !is_plain_name {
+        return Err(format!("Invalid attachment name: {}", file_name));
+    }
+
+    let path = get_attachments_dir()?.join(file_name);
+    if !path.exists() {
+        return Err(format!("Attachment not found: {}", file_name));
+    }
+
+    Ok(path)
+}
+
+pub fn delete_attachment(file_name: &str) -> Result<(), String> {
+    let path = resolve_attachment(file_name)?;
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Failed to delete attachment: {}", e))
+}
+
+pub fn mime_type_for(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "webm" => "audio/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Encode bytes as a data URL the frontend can drop straight into an <img> or <audio> tag
+pub fn to_data_url(bytes: &[u8], mime_type: &str) -> String {
+    format!("data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(bytes))
+}
+
+/// Decode either a bare base64 string or a data URL
+pub fn decode_base64_payload(data: &str) -> Result<Vec<u8>, String> {
+    let payload = match data.split_once(";base64,") {
+        Some((_, payload)) => payload,
+        None => data,
+    };
+
+    general_purpose::STANDARD
+        .decode(payload.trim())
+        .map_err(|e| format!("Invalid base64 data: {}", e))
+}
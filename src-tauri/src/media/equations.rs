@@ -0,0 +1,176 @@
+//! Equations in screenshots (textbook pages, slides, lecture recordings) converted to LaTeX,
+//! either by a vision-capable chat model or by a local pix2tex script, so math cards and notes
+//! don't have to be typed out by hand.
+
+use image::{imageops::FilterType, ImageOutputFormat};
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use crate::ai::types::ChatMessage;
+use crate::temp_files::{TempFileGuard, TempKind};
+use super::decode_base64_payload;
+
+// Screenshots are small; anything bigger is a whole page rather than an equation
+const MAX_EQUATION_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+const MAX_EQUATION_IMAGE_SIDE: u32 = 1600;
+const LOCAL_LATEX_TIMEOUT_SECS: u64 = 120;
+/// What the model is asked to answer when there is no equation in the image
+pub const NO_EQUATION_MARKER: &str = "NO_EQUATION";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EquationEngine {
+    #[default]
+    Vision, // A vision-capable chat model; better with surrounding text and multi-line derivations
+    Pix2tex, // LaTeX-OCR through a local script; fast and offline, one equation per image
+}
+
+impl EquationEngine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EquationEngine::Vision => "vision",
+            EquationEngine::Pix2tex => "pix2tex",
+        }
+    }
+}
+
+/// LaTeX read from a screenshot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatexConversion {
+    pub latex: String, // Without math delimiters
+    pub markdown: String, // A $$ block, ready to insert into a note or card
+    pub engine: EquationEngine,
+}
+
+impl LatexConversion {
+    pub fn new(latex: String, engine: EquationEngine) -> Self {
+        let markdown = format!("$$\n{}\n$$", latex);
+        Self { latex, markdown, engine }
+    }
+}
+
+/// Decode a screenshot sent as a data URL or bare base64 and re-encode it as PNG, scaled down
+/// to MAX_EQUATION_IMAGE_SIDE. Both engines read PNG, whatever the clipboard gave us.
+pub fn prepare_equation_image(image: &str) -> Result<Vec<u8>, String> {
+    let bytes = decode_base64_payload(image)?;
+    if bytes.len() > MAX_EQUATION_IMAGE_BYTES {
+        return Err("Image is too large; crop the screenshot to the equation".to_string());
+    }
+
+    let image = image::load_from_memory(&bytes)
+        .map_err(|e| format!("Unsupported image: {}", e))?;
+    let image = if image.width().max(image.height()) > MAX_EQUATION_IMAGE_SIDE {
+        image.resize(MAX_EQUATION_IMAGE_SIDE, MAX_EQUATION_IMAGE_SIDE, FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(png)
+}
+
+pub fn build_latex_messages() -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system".to_string(),
+            content: "You convert images of mathematical notation into LaTeX. Respond with the LaTeX only: no explanations, \
+                      no code fences and no surrounding $ or \\[ \\] delimiters.".to_string(),
+        },
+        ChatMessage {
+            role: "user".to_string(),
+            content: format!(
+                "Transcribe the equation in this image into LaTeX that compiles with amsmath. Reproduce it exactly as shown, \
+                 without simplifying or solving it. For several lines (a derivation or a system of equations) use an aligned \
+                 environment with & before the relation signs. If the image contains no equation, respond with {}.",
+                NO_EQUATION_MARKER
+            ),
+        },
+    ]
+}
+
+/// Clean up the LaTeX a model or pix2tex returned: code fences, math delimiters and a
+/// trailing full stop are removed, since the caller adds delimiters of its own
+pub fn clean_latex(text: &str) -> Result<String, String> {
+    let mut latex = text.trim();
+    if let Some(fenced) = latex.strip_prefix("```") {
+        latex = fenced.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        latex = latex.strip_suffix("```").unwrap_or(latex).trim();
+    }
+
+    for (open, close) in [("$$", "$$"), ("\\[", "\\]"), ("\\(", "\\)"), ("$", "$")] {
+        // "$a$ and $b$" is two inline equations, not one wrapped in $
+        let inner = latex.strip_prefix(open)
+            .and_then(|rest| rest.strip_suffix(close))
+            .filter(|inner| !inner.contains(open));
+        if let Some(inner) = inner {
+            latex = inner.trim();
+            break;
+        }
+    }
+    let latex = latex.strip_suffix('.').unwrap_or(latex).trim();
+
+    if latex.is_empty() || latex.contains(NO_EQUATION_MARKER) {
+        return Err("No equation was recognised in the image".to_string());
+    }
+    Ok(latex.to_string())
+}
+
+/// Resolve the pix2tex runner: STELLAR_PIX2TEX_BIN, then the venv from scripts/setup_pix2tex.sh
+pub fn resolve_pix2tex_command() -> PathBuf {
+    if let Ok(explicit_command) = std::env::var("STELLAR_PIX2TEX_BIN") {
+        let explicit_path = PathBuf::from(explicit_command);
+        if explicit_path.exists() {
+            return explicit_path;
+        }
+    }
+
+    let candidates = [
+        PathBuf::from("pix2tex_env/bin/stellar-pix2tex"),
+        PathBuf::from("../pix2tex_env/bin/stellar-pix2tex"),
+        PathBuf::from("pix2tex_env/Scripts/stellar-pix2tex.exe"),
+        PathBuf::from("../pix2tex_env/Scripts/stellar-pix2tex.exe"),
+    ];
+
+    for candidate in candidates {
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+
+    PathBuf::from("stellar-pix2tex")
+}
+
+/// Read the equation with the local pix2tex runner, which prints the LaTeX on stdout
+pub async fn latex_locally(png: &[u8]) -> Result<String, String> {
+    let image_file = TempFileGuard::new(TempKind::Screenshots, "equation.png")?;
+    std::fs::write(image_file.path(), png)
+        .map_err(|e| format!("Failed to write equation image: {}", e))?;
+
+    let pix2tex_command = resolve_pix2tex_command();
+    let mut cmd = tokio::process::Command::new(&pix2tex_command);
+    cmd.arg(image_file.path());
+    crate::throttle::lower_priority(&mut cmd);
+
+    let output = tokio::time::timeout(std::time::Duration::from_secs(LOCAL_LATEX_TIMEOUT_SECS), cmd.output())
+        .await
+        .map_err(|_| "Equation recognition timed out".to_string())?
+        .map_err(|e| {
+            let details = format!("Failed to run pix2tex at '{}': {}", pix2tex_command.display(), e);
+            if e.kind() == std::io::ErrorKind::NotFound {
+                format!("{}. Install it with ./scripts/setup_pix2tex.sh or set STELLAR_PIX2TEX_BIN.", details)
+            } else {
+                details
+            }
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Equation recognition failed: {}", stderr.trim()));
+    }
+
+    clean_latex(&String::from_utf8_lossy(&output.stdout))
+}
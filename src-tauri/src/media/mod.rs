@@ -0,0 +1,9 @@
+pub mod attachments;
+pub mod equations;
+pub mod occlusion;
+pub mod package;
+pub mod thumbnails;
+
+pub use attachments::*;
+pub use occlusion::*;
+pub use package::*;
@@ -0,0 +1,64 @@
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+use std::io::Cursor;
+use std::path::Path;
+
+use crate::database::OcclusionMask;
+
+// Colour of the mask being asked about, and of the other masks in "hide all" mode
+const ACTIVE_MASK_COLOR: Rgba<u8> = Rgba([231, 76, 60, 255]);
+const OTHER_MASK_COLOR: Rgba<u8> = Rgba([236, 240, 241, 255]);
+const MASK_BORDER_COLOR: Rgba<u8> = Rgba([44, 62, 80, 255]);
+
+/// Render the card image with its masks painted on and return it as PNG bytes.
+///
+/// Mask coordinates are fractions (0.0-1.0) of the image width/height so they survive resizing.
+/// In "hide_all" mode every mask is covered; in "hide_one" only the active one is. When
+/// `reveal` is set the active mask is left uncovered so the answer shows through.
+pub fn render_occluded_image(
+    image_path: &Path,
+    masks: &[OcclusionMask],
+    active_mask_id: &str,
+    hide_all: bool,
+    reveal: bool,
+) -> Result<Vec<u8>, String> {
+    let mut image = image::open(image_path)
+        .map_err(|e| format!("Failed to open occlusion image: {}", e))?
+        .to_rgba8();
+
+    for mask in masks {
+        let is_active = mask.id == active_mask_id;
+        if is_active && reveal {
+            continue;
+        }
+        if !is_active && !hide_all {
+            continue;
+        }
+
+        let color = if is_active { ACTIVE_MASK_COLOR } else { OTHER_MASK_COLOR };
+        paint_mask(&mut image, mask, color);
+    }
+
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode occluded image: {}", e))?;
+
+    Ok(bytes)
+}
+
+fn paint_mask(image: &mut RgbaImage, mask: &OcclusionMask, color: Rgba<u8>) {
+    let (width, height) = image.dimensions();
+    let to_px = |fraction: f32, size: u32| ((fraction.clamp(0.0, 1.0) * size as f32).round() as u32).min(size);
+
+    let left = to_px(mask.x, width);
+    let top = to_px(mask.y, height);
+    let right = to_px(mask.x + mask.width, width);
+    let bottom = to_px(mask.y + mask.height, height);
+
+    for y in top..bottom {
+        for x in left..right {
+            let on_border = x == left || y == top || x + 1 == right || y + 1 == bottom;
+            image.put_pixel(x, y, if on_border { MASK_BORDER_COLOR } else { color });
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Portable `.stellardeck` package format for sharing decks between stellar users.
+//!
+//! A package is a zip archive containing:
+//!
+//! - `manifest.json` — [`DeckPackageManifest`]: `format` (always `"stellar-deck"`), `version`
+//!   (currently 1), deck name/description/colour/icon/tags, optional author, export time and
+//!   the card count.
+//! - `cards.json` — array of [`DeckPackageCard`]: front, back, card type, difficulty, tags,
+//!   source text and metadata. Review history and scheduling are deliberately left out so the
+//!   recipient starts fresh.
+//! - `media/<file>` — every attachment a card's metadata references (occlusion images under
+//!   `image_occlusion.attachment`, audio under `audio.front|back.attachment`). References
+//!   in `cards.json` use the same bare file names as the entries in `media/`.
+//!
+//! Readers must reject packages whose `version` is newer than they understand.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+
+pub const DECK_PACKAGE_FORMAT: &str = "stellar-deck";
+pub const DECK_PACKAGE_VERSION: u32 = 1;
+pub const DECK_PACKAGE_EXTENSION: &str = "stellardeck";
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const CARDS_ENTRY: &str = "cards.json";
+const MEDIA_PREFIX: &str = "media/";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckPackageAuthor {
+    pub name: String,
+    pub email: Option<String>,
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckPackageManifest {
+    pub format: String,
+    pub version: u32,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub author: Option<DeckPackageAuthor>,
+    pub exported_at: String,
+    pub app_version: String,
+    pub card_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeckPackageCard {
+    pub front: String,
+    pub back: String,
+    pub card_type: String,
+    pub difficulty: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub source_text: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+}
+
+pub struct DeckPackage {
+    pub manifest: DeckPackageManifest,
+    pub cards: Vec<DeckPackageCard>,
+    pub media: HashMap<String, Vec<u8>>,
+}
+
+/// Every attachment file name a card's metadata points at
+pub fn card_attachments(metadata: &serde_json::Value) -> Vec<String> {
+    let mut attachments = Vec::new();
+
+    if let Some(name) = metadata.pointer("/image_occlusion/attachment").and_then(|v| v.as_str()) {
+        attachments.push(name.to_string());
+    }
+    for side in ["front", "back"] {
+        if let Some(name) = metadata.pointer(&format!("/audio/{}/attachment", side)).and_then(|v| v.as_str()) {
+            attachments.push(name.to_string());
+        }
+    }
+
+    attachments
+}
+
+/// Point a card's attachment references at new file names, e.g. after copying media on import
+pub fn rewrite_card_attachments(metadata: &mut serde_json::Value, renamed: &HashMap<String, String>) {
+    let pointers = [
+        "/image_occlusion/attachment",
+        "/audio/front/attachment",
+        "/audio/back/attachment",
+    ];
+
+    for pointer in pointers {
+        if let Some(value) = metadata.pointer_mut(pointer) {
+            if let Some(new_name) = value.as_str().and_then(|old| renamed.get(old)) {
+                *value = serde_json::Value::String(new_name.clone());
+            }
+        }
+    }
+}
+
+/// Write a package; `media` maps the names used in `cards` to files on disk
+pub fn write_deck_package(
+    path: &Path,
+    manifest: &DeckPackageManifest,
+    cards: &[DeckPackageCard],
+    media: &HashMap<String, std::path::PathBuf>,
+) -> Result<(), String> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create deck package: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let manifest_json = serde_json::to_vec_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    let cards_json = serde_json::to_vec_pretty(cards)
+        .map_err(|e| format!("Failed to serialize cards: {}", e))?;
+
+    for (name, bytes) in [(MANIFEST_ENTRY, manifest_json), (CARDS_ENTRY, cards_json)] {
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+        zip.write_all(&bytes)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+
+    for (name, source) in media {
+        let bytes = std::fs::read(source)
+            .map_err(|e| format!("Failed to read media {}: {}", name, e))?;
+        zip.start_file(format!("{}{}", MEDIA_PREFIX, name), options)
+            .map_err(|e| format!("Failed to write media {}: {}", name, e))?;
+        zip.write_all(&bytes)
+            .map_err(|e| format!("Failed to write media {}: {}", name, e))?;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish deck package: {}", e))?;
+
+    Ok(())
+}
+
+pub fn read_deck_package(path: &Path) -> Result<DeckPackage, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open deck package: {}", e))?;
+    let mut zip = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Not a valid deck package: {}", e))?;
+
+    let manifest: DeckPackageManifest = serde_json::from_slice(&read_entry(&mut zip, MANIFEST_ENTRY)?)
+        .map_err(|e| format!("Invalid deck manifest: {}", e))?;
+
+    if manifest.format != DECK_PACKAGE_FORMAT {
+        return Err(format!("Unsupported package format: {}", manifest.format));
+    }
+    if manifest.version > DECK_PACKAGE_VERSION {
+        return Err(format!(
+            "Deck package version {} is newer than this version of stellar supports ({})",
+            manifest.version, DECK_PACKAGE_VERSION
+        ));
+    }
+
+    let cards: Vec<DeckPackageCard> = serde_json::from_slice(&read_entry(&mut zip, CARDS_ENTRY)?)
+        .map_err(|e| format!("Invalid deck cards: {}", e))?;
+
+    let media_names: Vec<String> = zip.file_names()
+        .filter(|name| name.starts_with(MEDIA_PREFIX) && name.len() > MEDIA_PREFIX.len())
+        .map(|name| name.to_string())
+        .collect();
+
+    let mut media = HashMap::new();
+    for entry in media_names {
+        let bytes = read_entry(&mut zip, &entry)?;
+        media.insert(entry[MEDIA_PREFIX.len()..].to_string(), bytes);
+    }
+
+    Ok(DeckPackage { manifest, cards, media })
+}
+
+fn read_entry(zip: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Result<Vec<u8>, String> {
+    let mut entry = zip.by_name(name)
+        .map_err(|e| format!("Deck package is missing {}: {}", name, e))?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    Ok(bytes)
+}
@@ -0,0 +1,235 @@
+//! Cover images for the library grid: the first page of a PDF, or the preview image (or icon)
+//! a web page advertises. Covers are stored in the attachments dir; thumbnails at the sizes
+//! the grid asks for are cached next to them in a `thumbnails` subdirectory.
+
+use image::{imageops::FilterType, DynamicImage, ImageOutputFormat};
+use scraper::{Html, Selector};
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use crate::network::{shared_client, HttpPurpose};
+use super::{get_attachments_dir, resolve_attachment, store_attachment};
+
+// Covers are kept at this width; thumbnails are scaled down from them
+const COVER_WIDTH: u32 = 640;
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+const MIN_THUMBNAIL_SIZE: u32 = 32;
+const MAX_IMAGE_BYTES: usize = 10 * 1024 * 1024;
+// Icons smaller than this look worse than the placeholder the grid shows instead
+const MIN_ICON_SIZE: u32 = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverSource {
+    PdfPage,
+    PreviewImage, // og:image / twitter:image
+    Icon,         // apple-touch-icon or a large favicon
+}
+
+impl CoverSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CoverSource::PdfPage => "pdf_page",
+            CoverSource::PreviewImage => "preview_image",
+            CoverSource::Icon => "icon",
+        }
+    }
+}
+
+/// Resolve the pdftoppm binary (from poppler): STELLAR_PDFTOPPM_BIN, then common install
+/// paths, then PATH
+pub fn resolve_pdftoppm_command() -> PathBuf {
+    if let Ok(explicit_command) = std::env::var("STELLAR_PDFTOPPM_BIN") {
+        let explicit_path = PathBuf::from(explicit_command);
+        if explicit_path.exists() {
+            return explicit_path;
+        }
+    }
+
+    let candidates = [
+        PathBuf::from("/opt/homebrew/bin/pdftoppm"),
+        PathBuf::from("/usr/local/bin/pdftoppm"),
+        PathBuf::from("C:\\Program Files\\poppler\\Library\\bin\\pdftoppm.exe"),
+    ];
+
+    for candidate in candidates {
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+
+    PathBuf::from("pdftoppm")
+}
+
+/// Render the first page of a PDF as a PNG cover
+pub async fn render_pdf_cover(pdf_path: &Path) -> Result<Vec<u8>, String> {
+    let pdftoppm_command = resolve_pdftoppm_command();
+    let output = tokio::time::timeout(
+        std::time::Duration::from_secs(60),
+        tokio::process::Command::new(&pdftoppm_command)
+            .args(["-png", "-f", "1", "-l", "1", "-singlefile", "-scale-to-x"])
+            .arg(COVER_WIDTH.to_string())
+            .args(["-scale-to-y", "-1"])
+            .arg(pdf_path)
+            .arg("-") // PNG on stdout
+            .output(),
+    )
+    .await
+    .map_err(|_| format!("Rendering the cover of '{}' timed out", pdf_path.display()))?
+    .map_err(|e| {
+        let details = format!("Failed to run pdftoppm at '{}': {}", pdftoppm_command.display(), e);
+        if e.kind() == std::io::ErrorKind::NotFound {
+            format!("{}. Install poppler or set STELLAR_PDFTOPPM_BIN.", details)
+        } else {
+            details
+        }
+    })?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Failed to render the cover of '{}': {}", pdf_path.display(), stderr.trim()));
+    }
+
+    Ok(output.stdout)
+}
+
+/// The preview image of a web page, falling back to its touch icon or a large favicon
+pub async fn fetch_web_cover(page_url: &str, html: &str) -> Result<Option<(Vec<u8>, CoverSource)>, String> {
+    for (url, source) in cover_candidates(page_url, html) {
+        match download_image(&url).await {
+            Ok(image) if source != CoverSource::Icon || image.width().min(image.height()) >= MIN_ICON_SIZE => {
+                return Ok(Some((encode_png(&image)?, source)));
+            }
+            Ok(_) => continue,
+            Err(e) => eprintln!("⚠️ Skipping cover candidate {}: {}", url, e),
+        }
+    }
+    Ok(None)
+}
+
+/// Fetch a web page's HTML for `fetch_web_cover`
+pub async fn fetch_page_html(page_url: &str) -> Result<String, String> {
+    crate::network::ensure_reachable(page_url, "Fetching page covers")?;
+    let response = shared_client(HttpPurpose::Download)?.get(page_url).send().await
+        .map_err(|e| format!("Failed to fetch {}: {}", page_url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch {}: HTTP {}", page_url, response.status()));
+    }
+    response.text().await
+        .map_err(|e| format!("Failed to read {}: {}", page_url, e))
+}
+
+// Candidate images in order of preference, as absolute URLs
+fn cover_candidates(page_url: &str, html: &str) -> Vec<(String, CoverSource)> {
+    let Ok(base) = reqwest::Url::parse(page_url) else {
+        return Vec::new();
+    };
+    let document = Html::parse_document(html);
+    let mut candidates = Vec::new();
+
+    let mut push = |href: Option<&str>, source: CoverSource| {
+        if let Some(url) = href.map(str::trim).filter(|href| !href.is_empty()).and_then(|href| base.join(href).ok()) {
+            if matches!(url.scheme(), "http" | "https") && !candidates.iter().any(|(existing, _)| *existing == url.as_str()) {
+                candidates.push((url.to_string(), source));
+            }
+        }
+    };
+
+    for selector in ["meta[property='og:image']", "meta[property='og:image:url']", "meta[name='twitter:image']"] {
+        if let Ok(selector) = Selector::parse(selector) {
+            for element in document.select(&selector) {
+                push(element.value().attr("content"), CoverSource::PreviewImage);
+            }
+        }
+    }
+    for selector in ["link[rel='apple-touch-icon']", "link[rel='icon']", "link[rel='shortcut icon']"] {
+        if let Ok(selector) = Selector::parse(selector) {
+            for element in document.select(&selector) {
+                push(element.value().attr("href"), CoverSource::Icon);
+            }
+        }
+    }
+    push(Some("/favicon.ico"), CoverSource::Icon);
+
+    candidates
+}
+
+async fn download_image(url: &str) -> Result<DynamicImage, String> {
+    crate::network::ensure_reachable(url, "Fetching page covers")?;
+    let response = shared_client(HttpPurpose::Download)?.get(url).send().await
+        .map_err(|e| format!("Failed to fetch image: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    if response.content_length().map_or(false, |length| length as usize > MAX_IMAGE_BYTES) {
+        return Err("Image is too large".to_string());
+    }
+
+    let bytes = response.bytes().await
+        .map_err(|e| format!("Failed to read image: {}", e))?;
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err("Image is too large".to_string());
+    }
+    image::load_from_memory(&bytes)
+        .map_err(|e| format!("Unsupported image: {}", e))
+}
+
+/// Scale an image down to the cover width and store it as a PNG attachment, returning the
+/// attachment's file name
+pub fn store_cover(bytes: &[u8]) -> Result<String, String> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| format!("Failed to read cover image: {}", e))?;
+    let image = if image.width() > COVER_WIDTH {
+        image.resize(COVER_WIDTH, u32::MAX, FilterType::Lanczos3)
+    } else {
+        image
+    };
+    store_attachment(&encode_png(&image)?, "png")
+}
+
+/// PNG thumbnail of a stored cover that fits in `size` x `size`, cached per size
+pub fn cover_thumbnail(cover_file: &str, size: u32) -> Result<Vec<u8>, String> {
+    let size = size.clamp(MIN_THUMBNAIL_SIZE, COVER_WIDTH);
+    let cover_path = resolve_attachment(cover_file)?;
+    let stem = Path::new(cover_file).file_stem().and_then(|stem| stem.to_str()).unwrap_or(cover_file);
+
+    let cache_dir = get_attachments_dir()?.join("thumbnails");
+    let cached_path = cache_dir.join(format!("{}-{}.png", stem, size));
+    if let Ok(bytes) = std::fs::read(&cached_path) {
+        return Ok(bytes);
+    }
+
+    let image = image::open(&cover_path)
+        .map_err(|e| format!("Failed to open cover: {}", e))?;
+    let bytes = encode_png(&image.thumbnail(size, size))?;
+
+    std::fs::create_dir_all(&cache_dir)
+        .map_err(|e| format!("Failed to create thumbnail cache: {}", e))?;
+    if let Err(e) = std::fs::write(&cached_path, &bytes) {
+        eprintln!("⚠️ Failed to cache thumbnail {}: {}", cached_path.display(), e);
+    }
+    Ok(bytes)
+}
+
+/// Remove a cover and its cached thumbnails
+pub fn delete_cover(cover_file: &str) -> Result<(), String> {
+    let stem = Path::new(cover_file).file_stem().and_then(|stem| stem.to_str()).unwrap_or(cover_file).to_string();
+    if let Ok(entries) = std::fs::read_dir(get_attachments_dir()?.join("thumbnails")) {
+        for entry in entries.flatten() {
+            let is_variant = entry.file_name().to_str()
+                .and_then(|name| name.strip_prefix(&stem))
+                .map_or(false, |rest| rest.starts_with('-'));
+            if is_variant {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+    super::delete_attachment(cover_file)
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(bytes)
+}
@@ -0,0 +1,175 @@
+//! Timings of Tauri commands since start and a log of slow database statements, for diagnosing
+//! "the app feels slow" reports. Commands are timed by a `CommandSpan` held for their whole
+//! body. Statements are timed by sqlx, which reports the ones over `SLOW_QUERY_THRESHOLD`
+//! through `log`; `install_slow_query_log` collects those.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const SLOW_QUERY_THRESHOLD: Duration = Duration::from_millis(200);
+// Commands slower than this are logged as they finish
+const SLOW_COMMAND_THRESHOLD: Duration = Duration::from_secs(1);
+// Per command; percentiles are over the most recent calls once a command has made this many
+const MAX_SAMPLES: usize = 10_000;
+const MAX_SLOW_QUERIES: usize = 200;
+const MAX_QUERY_CHARS: usize = 2_000;
+
+#[derive(Default)]
+struct CommandStats {
+    calls: u64,
+    total: Duration,
+    max: Duration,
+    samples: VecDeque<Duration>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandMetrics {
+    pub command: String,
+    pub calls: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowQuery {
+    pub message: String, // sqlx's summary: statement, rows and elapsed time
+    pub logged_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceMetrics {
+    pub since: DateTime<Utc>,
+    pub commands: Vec<CommandMetrics>, // Most total time first
+    pub slow_queries: Vec<SlowQuery>, // Newest first
+    pub slow_query_threshold_ms: u64,
+}
+
+static STARTED_AT: OnceLock<DateTime<Utc>> = OnceLock::new();
+static COMMANDS: Mutex<Option<HashMap<&'static str, CommandStats>>> = Mutex::new(None);
+static SLOW_QUERIES: Mutex<VecDeque<SlowQuery>> = Mutex::new(VecDeque::new());
+
+/// Times a command from creation until dropped. Hold it for the whole body:
+/// `let _span = crate::metrics::command_span("get_documents");`
+pub struct CommandSpan {
+    command: &'static str,
+    started: Instant,
+}
+
+pub fn command_span(command: &'static str) -> CommandSpan {
+    CommandSpan { command, started: Instant::now() }
+}
+
+impl Drop for CommandSpan {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        if elapsed >= SLOW_COMMAND_THRESHOLD {
+            println!("🐢 Command {} took {:.2?}", self.command, elapsed);
+        }
+        record_command(self.command, elapsed);
+    }
+}
+
+fn record_command(command: &'static str, elapsed: Duration) {
+    let Ok(mut commands) = COMMANDS.lock() else {
+        return;
+    };
+    let stats = commands.get_or_insert_with(HashMap::new).entry(command).or_default();
+    stats.calls += 1;
+    stats.total += elapsed;
+    stats.max = stats.max.max(elapsed);
+    if stats.samples.len() == MAX_SAMPLES {
+        stats.samples.pop_front();
+    }
+    stats.samples.push_back(elapsed);
+}
+
+struct SlowQueryLogger;
+
+static SLOW_QUERY_LOGGER: SlowQueryLogger = SlowQueryLogger;
+
+impl log::Log for SlowQueryLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target().starts_with("sqlx::query") && metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message: String = record.args().to_string().chars().take(MAX_QUERY_CHARS).collect();
+        eprintln!("🐢 Slow query: {}", message);
+        if let Ok(mut slow_queries) = SLOW_QUERIES.lock() {
+            if slow_queries.len() == MAX_SLOW_QUERIES {
+                slow_queries.pop_front();
+            }
+            slow_queries.push_back(SlowQuery { message, logged_at: Utc::now() });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Start collecting the statements sqlx reports as slow (see `Database::new`). Called once at
+/// startup; nothing else installs a `log` logger.
+pub fn install_slow_query_log() {
+    STARTED_AT.get_or_init(Utc::now);
+    match log::set_logger(&SLOW_QUERY_LOGGER) {
+        Ok(()) => log::set_max_level(log::LevelFilter::Warn),
+        Err(e) => eprintln!("⚠️ Failed to install slow query log: {}", e),
+    }
+}
+
+pub fn performance_metrics() -> PerformanceMetrics {
+    let mut commands: Vec<CommandMetrics> = COMMANDS.lock()
+        .map(|commands| {
+            commands.iter()
+                .flat_map(|commands| commands.iter())
+                .map(|(command, stats)| command_metrics(command, stats))
+                .collect()
+        })
+        .unwrap_or_default();
+    commands.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+    let slow_queries = SLOW_QUERIES.lock()
+        .map(|slow_queries| slow_queries.iter().rev().cloned().collect())
+        .unwrap_or_default();
+
+    PerformanceMetrics {
+        since: *STARTED_AT.get_or_init(Utc::now),
+        commands,
+        slow_queries,
+        slow_query_threshold_ms: SLOW_QUERY_THRESHOLD.as_millis() as u64,
+    }
+}
+
+fn command_metrics(command: &str, stats: &CommandStats) -> CommandMetrics {
+    let mut samples: Vec<Duration> = stats.samples.iter().copied().collect();
+    samples.sort();
+
+    CommandMetrics {
+        command: command.to_string(),
+        calls: stats.calls,
+        p50_ms: millis(percentile(&samples, 0.50)),
+        p95_ms: millis(percentile(&samples, 0.95)),
+        max_ms: millis(stats.max),
+        total_ms: millis(stats.total),
+    }
+}
+
+// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
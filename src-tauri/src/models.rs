@@ -0,0 +1,36 @@
+//! Local model storage under ~/stellar_data/models, shared by all profiles. The fastembed
+//! backend is the only consumer today; its downloads report progress through these helpers.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Root of all downloaded models (fastembed keeps its cache in a subdirectory)
+pub fn models_root() -> Result<PathBuf, String> {
+    let root = crate::paths::root_data_dir()?.join("models");
+
+    std::fs::create_dir_all(&root)
+        .map_err(|e| format!("Failed to create models directory: {}", e))?;
+
+    Ok(root)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub model_id: String,
+    pub downloaded_bytes: u64, // Size of the model's cache so far
+    pub done: bool,
+}
+
+pub fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => directory_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
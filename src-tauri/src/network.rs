@@ -0,0 +1,216 @@
+//! Outbound network policy. In offline mode only loopback services (Ollama, a local marker
+//! server) may be contacted; everything else fails fast with `NetworkError::Offline` instead of
+//! waiting for a connection timeout. HTTP clients are shared per purpose so connections are
+//! pooled, and all of them carry the configured proxy and user agent.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+pub const OFFLINE_MODE_KEY: &str = "offline_mode";
+pub const PROXY_SETTINGS_KEY: &str = "proxy";
+/// The proxy password lives in the encrypted api_keys table under this id, not in app_settings
+pub const PROXY_PASSWORD_KEY_ID: &str = "network-proxy";
+
+const USER_AGENT: &str = concat!("Stellar/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT_SECS: u64 = 15;
+
+// Local services are never sent through a manual proxy
+const LOCAL_NO_PROXY: &str = "localhost,127.0.0.1,::1";
+
+/// Every offline error message starts with this, so the frontend can recognise it
+pub const OFFLINE_ERROR_PREFIX: &str = "offline_mode:";
+
+static OFFLINE_MODE: AtomicBool = AtomicBool::new(false);
+
+pub fn is_offline() -> bool {
+    OFFLINE_MODE.load(Ordering::Relaxed)
+}
+
+pub fn set_offline(enabled: bool) {
+    OFFLINE_MODE.store(enabled, Ordering::Relaxed);
+    println!("🌐 Offline mode {}", if enabled { "enabled" } else { "disabled" });
+}
+
+#[derive(Debug, Clone)]
+pub enum NetworkError {
+    Offline { feature: String },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Offline { feature } => write!(
+                f,
+                "{} {} needs network access, which is disabled in offline mode",
+                OFFLINE_ERROR_PREFIX, feature
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<NetworkError> for String {
+    fn from(error: NetworkError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Fail if offline mode is on
+pub fn ensure_online(feature: &str) -> Result<(), NetworkError> {
+    if is_offline() {
+        return Err(NetworkError::Offline { feature: feature.to_string() });
+    }
+    Ok(())
+}
+
+/// Fail if offline mode is on and `url` is not a loopback address
+pub fn ensure_reachable(url: &str, feature: &str) -> Result<(), NetworkError> {
+    if is_local_url(url) {
+        return Ok(());
+    }
+    ensure_online(feature)
+}
+
+pub fn is_local_url(url: &str) -> bool {
+    // No URL configured (Ollama's default) can't reach anything off this machine
+    if url.trim().is_empty() {
+        return true;
+    }
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = parsed.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().map(|ip| ip.is_loopback()).unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    #[default]
+    System, // HTTP(S)_PROXY / ALL_PROXY and the OS proxy settings
+    Manual,
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProxySettings {
+    #[serde(default)]
+    pub mode: ProxyMode,
+    #[serde(default)]
+    pub url: Option<String>, // Manual mode: http://host:port, https://... or socks5://host:port
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>, // Write-only: empty clears it, None keeps the stored one
+    #[serde(default)]
+    pub has_password: bool,
+    #[serde(default)]
+    pub no_proxy: Option<String>, // Comma-separated hosts that bypass a manual proxy
+}
+
+static PROXY_SETTINGS: RwLock<Option<ProxySettings>> = RwLock::new(None);
+
+pub fn proxy_settings() -> ProxySettings {
+    PROXY_SETTINGS.read()
+        .map(|settings| settings.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Validate and apply proxy settings; clients built afterwards use them
+pub fn set_proxy_settings(settings: ProxySettings) -> Result<(), String> {
+    if let Some(proxy) = build_proxy(&settings)? {
+        // Building a client is the only way reqwest reports an unusable proxy
+        reqwest::Client::builder().proxy(proxy).build()
+            .map_err(|e| format!("Invalid proxy settings: {}", e))?;
+    }
+    println!("🌐 Proxy mode: {:?}", settings.mode);
+    if let Ok(mut current) = PROXY_SETTINGS.write() {
+        *current = Some(settings);
+    }
+    // Pooled clients hold the old proxy; rebuild them on next use
+    if let Ok(mut clients) = SHARED_CLIENTS.write() {
+        *clients = None;
+    }
+    Ok(())
+}
+
+fn build_proxy(settings: &ProxySettings) -> Result<Option<reqwest::Proxy>, String> {
+    if !matches!(settings.mode, ProxyMode::Manual) {
+        return Ok(None);
+    }
+    let url = settings.url.as_deref()
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .ok_or("A proxy URL is required in manual mode")?;
+
+    let mut proxy = reqwest::Proxy::all(url)
+        .map_err(|e| format!("Invalid proxy URL '{}': {}", url, e))?;
+    if let Some(username) = settings.username.as_deref().filter(|u| !u.is_empty()) {
+        proxy = proxy.basic_auth(username, settings.password.as_deref().unwrap_or_default());
+    }
+    let no_proxy = match settings.no_proxy.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(extra) => format!("{},{}", LOCAL_NO_PROXY, extra),
+        None => LOCAL_NO_PROXY.to_string(),
+    };
+    Ok(Some(proxy.no_proxy(reqwest::NoProxy::from_string(&no_proxy))))
+}
+
+/// A reqwest client builder with the configured proxy and user agent applied. Prefer
+/// `shared_client`; build a dedicated client only for unusual settings.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let settings = proxy_settings();
+    let builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS));
+    match settings.mode {
+        ProxyMode::System => builder,
+        ProxyMode::None => builder.no_proxy(),
+        ProxyMode::Manual => match build_proxy(&settings) {
+            Ok(Some(proxy)) => builder.proxy(proxy),
+            Ok(None) => builder,
+            Err(e) => {
+                eprintln!("⚠️ Ignoring proxy settings: {}", e);
+                builder
+            }
+        },
+    }
+}
+
+/// What a shared client is used for. Neither sets an overall timeout, since streams and large
+/// downloads can legitimately run for minutes; callers set one per request where it makes sense.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HttpPurpose {
+    Ai, // Chat, embeddings and model catalog APIs
+    Download, // PDFs, imported files and model weights
+}
+
+static SHARED_CLIENTS: RwLock<Option<HashMap<HttpPurpose, reqwest::Client>>> = RwLock::new(None);
+
+/// The pooled client for `purpose`. Cloning a reqwest client is cheap and shares its pool.
+pub fn shared_client(purpose: HttpPurpose) -> Result<reqwest::Client, String> {
+    if let Some(client) = SHARED_CLIENTS.read().ok().and_then(|clients| clients.as_ref()?.get(&purpose).cloned()) {
+        return Ok(client);
+    }
+
+    let builder = client_builder();
+    let client = match purpose {
+        HttpPurpose::Ai => builder.pool_idle_timeout(Duration::from_secs(90)),
+        HttpPurpose::Download => builder.pool_max_idle_per_host(2),
+    }
+    .build()
+    .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    if let Ok(mut clients) = SHARED_CLIENTS.write() {
+        clients.get_or_insert_with(HashMap::new).insert(purpose, client.clone());
+    }
+    Ok(client)
+}
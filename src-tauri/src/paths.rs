@@ -0,0 +1,45 @@
+//! Where Stellar keeps its data. Everything lives under ~/stellar_data unless the
+//! `STELLAR_DATA_DIR` environment variable points somewhere else, e.g. the CLI working on a
+//! copy of the data dir on a server or NAS. Library data goes in the active profile's
+//! directory (see profiles.rs); the root itself is that directory for the default profile.
+
+use std::path::PathBuf;
+
+use crate::profiles;
+
+pub const DATA_DIR_ENV: &str = "STELLAR_DATA_DIR";
+pub const DATABASE_FILE: &str = "documents.db";
+
+/// The data root shared by all profiles, created if it doesn't exist yet
+pub fn root_data_dir() -> Result<PathBuf, String> {
+    let dir = match std::env::var_os(DATA_DIR_ENV).filter(|value| !value.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::home_dir()
+            .ok_or("Could not find home directory")?
+            .join("stellar_data"),
+    };
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    Ok(dir)
+}
+
+/// The active profile's data directory, created if it doesn't exist yet
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    let dir = profiles::profile_dir(&root_data_dir()?, &profiles::active_profile_id());
+
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create profile data directory: {}", e))?;
+
+    Ok(dir)
+}
+
+pub fn database_path() -> Result<PathBuf, String> {
+    Ok(app_data_dir()?.join(DATABASE_FILE))
+}
+
+/// SQLite URL for the main database, creating the file on first use
+pub fn database_url() -> Result<String, String> {
+    Ok(format!("sqlite://{}?mode=rwc", database_path()?.to_string_lossy()))
+}
@@ -5,6 +5,8 @@ use reqwest;
 use regex;
 use tokio;
 
+use crate::{language, math};
+
 /// Represents the type of marker installation detected
 #[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum MarkerInstallationType {
@@ -87,6 +89,16 @@ pub enum PdfError {
     NetworkError(String),
 }
 
+impl std::fmt::Display for PdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PdfError::IoError(e) => write!(f, "File system error: {}", e),
+            PdfError::ExtractionError(msg) => write!(f, "{}", msg),
+            PdfError::NetworkError(msg) => write!(f, "Network error: {}", msg),
+        }
+    }
+}
+
 impl From<std::io::Error> for PdfError {
     fn from(error: std::io::Error) -> Self {
         PdfError::IoError(error)
@@ -471,8 +483,7 @@ impl MarkerCommandResolver {
 }
 
 pub struct PdfProcessor {
-    #[allow(dead_code)] // Reserved for future marker server API support
-    marker_base_url: String,
+    marker_base_url: String, // Used in MarkerMode::Server
     marker_timeout: u64, // seconds
 }
 
@@ -508,6 +519,166 @@ impl PdfProcessor {
         Ok(markdown)
     }
 
+    /// Extract text with the raw text layer only, without structure detection
+    pub fn extract_basic_text(&self, file_path: &str) -> Result<String, PdfError> {
+        if !Path::new(file_path).exists() {
+            return Err(PdfError::ExtractionError(format!("File not found: {}", file_path)));
+        }
+
+        let text = extract_text(file_path)
+            .map_err(|e| PdfError::ExtractionError(format!("Failed to extract text: {}", e)))?;
+
+        Ok(text.replace("\r\n", "\n").trim().to_string())
+    }
+
+    /// Convert a document with MarkItDown (venv, STELLAR_MARKITDOWN_BIN or global install)
+    pub async fn extract_with_markitdown(&self, file_path: &str) -> Result<String, PdfError> {
+        let markitdown_command = Self::resolve_markitdown_command();
+        let output = tokio::process::Command::new(&markitdown_command)
+            .arg(file_path)
+            .output()
+            .await
+            .map_err(|e| {
+                let details = format!(
+                    "Failed to run MarkItDown converter at '{}': {}",
+                    markitdown_command.display(),
+                    e
+                );
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    PdfError::ExtractionError(format!(
+                        "{}. Install it with ./scripts/setup_markitdown.sh or set STELLAR_MARKITDOWN_BIN.",
+                        details
+                    ))
+                } else {
+                    PdfError::ExtractionError(details)
+                }
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let details = if !stderr.trim().is_empty() {
+                stderr.trim().to_string()
+            } else if !stdout.trim().is_empty() {
+                stdout.trim().to_string()
+            } else {
+                "no error output".to_string()
+            };
+
+            return Err(PdfError::ExtractionError(format!(
+                "MarkItDown conversion failed for '{}': {}",
+                file_path, details
+            )));
+        }
+
+        let markdown = String::from_utf8(output.stdout)
+            .map_err(|e| PdfError::ExtractionError(format!("MarkItDown produced non-UTF8 output: {}", e)))?;
+
+        if markdown.trim().is_empty() {
+            return Err(PdfError::ExtractionError(format!(
+                "MarkItDown returned empty output for '{}'.",
+                file_path
+            )));
+        }
+
+        Ok(markdown.replace("\r\n", "\n"))
+    }
+
+    pub fn resolve_markitdown_command() -> PathBuf {
+        if let Ok(explicit_command) = std::env::var("STELLAR_MARKITDOWN_BIN") {
+            let explicit_path = PathBuf::from(explicit_command);
+            if explicit_path.exists() {
+                return explicit_path;
+            }
+        }
+
+        let candidates = [
+            PathBuf::from("markitdown_env/bin/markitdown"),
+            PathBuf::from("../markitdown_env/bin/markitdown"),
+            PathBuf::from("markitdown_env/Scripts/markitdown.exe"),
+            PathBuf::from("../markitdown_env/Scripts/markitdown.exe"),
+        ];
+
+        for candidate in candidates {
+            if candidate.exists() {
+                return candidate;
+            }
+        }
+
+        PathBuf::from("markitdown")
+    }
+
+    /// Walk `options.preferred_methods` in order and return the first non-empty result
+    pub async fn extract_with_options(&self, file_path: &str, options: &ExtractOptions) -> Result<ExtractionResult, PdfError> {
+        self.extract_with_options_progress(file_path, options, None).await
+    }
+
+    /// Same as `extract_with_options`, forwarding Marker progress when Marker is tried
+    pub async fn extract_with_options_progress(
+        &self,
+        file_path: &str,
+        options: &ExtractOptions,
+        on_progress: Option<MarkerProgressCallback>,
+    ) -> Result<ExtractionResult, PdfError> {
+        if !Path::new(file_path).exists() {
+            return Err(PdfError::ExtractionError(format!("File not found: {}", file_path)));
+        }
+
+        let methods = if options.preferred_methods.is_empty() {
+            ExtractOptions::default().preferred_methods
+        } else {
+            options.preferred_methods.clone()
+        };
+
+        let started = std::time::Instant::now();
+        let mut failed_attempts = Vec::new();
+
+        for method in methods {
+            println!("📄 Trying {} extraction for {}", method.as_str(), file_path);
+            let result = match method {
+                ExtractionMethod::Marker => {
+                    let processor = PdfProcessor::with_config(self.marker_base_url.clone(), options.timeout_seconds.max(1));
+                    processor.extract_with_marker_progress(file_path, options.marker_options(), on_progress.clone()).await
+                }
+                ExtractionMethod::MarkItDown => self.extract_with_markitdown(file_path).await,
+                ExtractionMethod::Enhanced => self.extract_text_from_pdf(file_path),
+                ExtractionMethod::Basic => self.extract_basic_text(file_path),
+            };
+
+            match result {
+                Ok(content) if !content.trim().is_empty() => {
+                    println!("✅ {} extraction succeeded ({} chars)", method.as_str(), content.len());
+                    let quality = score_extraction_quality(&content);
+                    return Ok(ExtractionResult {
+                        content,
+                        method,
+                        failed_attempts,
+                        duration_ms: started.elapsed().as_millis() as u64,
+                        quality,
+                    });
+                }
+                Ok(_) => failed_attempts.push(ExtractionAttempt {
+                    method,
+                    error: "Produced empty output".to_string(),
+                }),
+                Err(e) => {
+                    eprintln!("⚠️ {} extraction failed: {}", method.as_str(), e);
+                    failed_attempts.push(ExtractionAttempt {
+                        method,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        let summary = failed_attempts
+            .iter()
+            .map(|attempt| format!("{}: {}", attempt.method.as_str(), attempt.error))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Err(PdfError::ExtractionError(format!("All extraction methods failed ({})", summary)))
+    }
+
     /// Enhanced text to markdown conversion with better structure detection
     fn text_to_markdown_enhanced(&self, text: &str) -> String {
         let mut markdown = String::new();
@@ -532,6 +703,23 @@ impl PdfProcessor {
                 continue;
             }
 
+            // Standalone equations are kept verbatim as display math so search and rendering
+            // see them as math instead of prose or list items
+            if math::looks_like_equation(trimmed) {
+                if in_paragraph {
+                    markdown.push_str("\n\n");
+                    in_paragraph = false;
+                }
+                if in_list {
+                    markdown.push('\n');
+                    in_list = false;
+                }
+                markdown.push_str("$$\n");
+                markdown.push_str(trimmed);
+                markdown.push_str("\n$$\n\n");
+                continue;
+            }
+
             // Detect lists
             if let Some(list_item) = self.detect_list_item(trimmed) {
                 if !in_list {
@@ -581,7 +769,8 @@ impl PdfProcessor {
             
             if !in_paragraph {
                 in_paragraph = true;
-            } else {
+            } else if language::needs_space_between(markdown.chars().last(), trimmed.chars().next()) {
+                // Chinese and Japanese lines are wrapped mid-sentence without a space to restore
                 markdown.push(' ');
             }
             markdown.push_str(trimmed);
@@ -623,8 +812,8 @@ impl PdfProcessor {
 
     /// Determine heading level based on various factors
     fn determine_heading_level(&self, line: &str, index: usize, lines: &[&str]) -> usize {
-        // Check for all caps (likely higher level heading)
-        if line.chars().all(|c| c.is_uppercase() || !c.is_alphabetic()) {
+        // Check for all caps (likely higher level heading); caseless scripts are never "all caps"
+        if line.chars().any(|c| c.is_uppercase()) && !line.chars().any(|c| c.is_lowercase()) {
             return 1;
         }
 
@@ -648,21 +837,19 @@ impl PdfProcessor {
         3
     }
 
-    /// Check if text is in title case
+    /// Check if text is in title case. Only words starting with a cased letter count, so
+    /// Chinese, Arabic or Hindi text is never title case.
     fn is_title_case(&self, text: &str) -> bool {
-        let words: Vec<&str> = text.split_whitespace().collect();
+        let words: Vec<char> = text.split_whitespace()
+            .filter_map(|word| word.chars().next())
+            .filter(|c| has_case(*c))
+            .collect();
         if words.is_empty() {
             return false;
         }
 
         let title_case_words = words.iter()
-            .filter(|word| {
-                if word.len() == 0 {
-                    return false;
-                }
-                let first_char = word.chars().next().unwrap();
-                first_char.is_uppercase()
-            })
+            .filter(|first_char| first_char.is_uppercase())
             .count();
 
         // At least 75% of words should start with uppercase
@@ -686,28 +873,31 @@ impl PdfProcessor {
     /// Enhanced heading detection heuristics
     fn looks_like_heading(&self, line: &str) -> bool {
         // Skip if too long
-        if line.len() > 100 {
+        if line.chars().count() > 100 {
             return false;
         }
 
-        // Skip if ends with sentence punctuation
-        if line.ends_with('.') || line.ends_with(',') || line.ends_with(';') || line.ends_with('!') || line.ends_with('?') {
+        // Skip if ends with sentence punctuation, including the full-width CJK forms
+        if line.chars().last().map_or(false, language::is_trailing_punctuation) {
             return false;
         }
 
-        // Must have some uppercase letters
-        if !line.chars().any(|c| c.is_uppercase()) {
+        // Must have some uppercase letters, unless the script has no case at all
+        let has_cased_letters = line.chars().any(has_case);
+        if has_cased_letters && !line.chars().any(|c| c.is_uppercase()) {
             return false;
         }
-
-        // Should be relatively short
-        let word_count = line.split_whitespace().count();
-        if word_count > 12 {
+        if !has_cased_letters && !line.chars().any(|c| c.is_alphabetic()) {
+            return false;
+        }
+        // Without case to go by, a clause break marks a wrapped sentence rather than a heading
+        if !has_cased_letters && line.chars().any(|c| matches!(c, ',' | ';' | '，' | '、' | '；')) {
             return false;
         }
 
-        // Check for common heading patterns
-        line.chars().any(|c| c.is_uppercase()) && word_count <= 8
+        // Should be relatively short; CJK text has no spaces to count words by
+        let word_count = language::approximate_word_count(line);
+        word_count <= 8
     }
 
     /// Enhanced markdown cleanup
@@ -752,13 +942,46 @@ impl PdfProcessor {
         })
     }
 
-    /// Extract text using marker_single command directly
+    /// The PDF's bookmarks (its outline), in document order. Empty when it has none.
+    pub fn extract_bookmarks(&self, file_path: &str) -> Result<Vec<PdfBookmark>, PdfError> {
+        let document = lopdf::Document::load(file_path)
+            .map_err(|e| PdfError::ExtractionError(format!("Failed to read PDF: {}", e)))?;
+        let toc = match document.get_toc() {
+            Ok(toc) => toc,
+            Err(_) => return Ok(Vec::new()), // No outline dictionary
+        };
+
+        Ok(toc.toc.into_iter()
+            .filter(|entry| !entry.title.trim().is_empty())
+            .map(|entry| PdfBookmark {
+                level: entry.level,
+                title: entry.title.trim().to_string(),
+                page: (entry.page > 0).then_some(entry.page),
+            })
+            .collect())
+    }
+
+    /// Extract text with Marker, either by running marker_single or via a marker server
     pub async fn extract_with_marker(&self, file_path: &str, options: MarkerOptions) -> Result<String, PdfError> {
+        self.extract_with_marker_progress(file_path, options, None).await
+    }
+
+    /// Same as `extract_with_marker`, reporting marker_single's progress as it runs
+    pub async fn extract_with_marker_progress(
+        &self,
+        file_path: &str,
+        options: MarkerOptions,
+        on_progress: Option<MarkerProgressCallback>,
+    ) -> Result<String, PdfError> {
         // Check if file exists
         if !Path::new(file_path).exists() {
             return Err(PdfError::ExtractionError(format!("File not found: {}", file_path)));
         }
 
+        if options.mode == MarkerMode::Server {
+            return self.extract_with_marker_server(file_path, &options).await;
+        }
+
         // Use MarkerCommandResolver to get the appropriate command path
         let resolver = MarkerCommandResolver::new().await;
         let marker_command_path = resolver.resolve_marker_command().await
@@ -768,17 +991,17 @@ impl PdfProcessor {
                 PdfError::ExtractionError(error_message)
             })?;
 
-        // Create temporary output directory
-        let temp_dir = std::env::temp_dir().join("stellar_marker_output");
-        if let Err(e) = std::fs::create_dir_all(&temp_dir) {
-            return Err(PdfError::ExtractionError(format!("Failed to create temporary directory: {}", e)));
-        }
+        // Temporary output directory of this run, deleted on every return below
+        let output_dir = crate::temp_files::TempFileGuard::dir(crate::temp_files::TempKind::MarkerOutput)
+            .map_err(PdfError::ExtractionError)?;
+        let temp_dir = output_dir.path().to_path_buf();
 
         // Build marker_single command using resolved path
         let mut cmd = tokio::process::Command::new(&marker_command_path);
         cmd.arg(file_path)
             .arg("--output_format").arg("markdown")
             .arg("--output_dir").arg(&temp_dir);
+        crate::throttle::lower_priority(&mut cmd);
 
         // Handle environment variable setup when using virtual environment
         if let Some(venv_path) = resolver.get_venv_path() {
@@ -803,12 +1026,52 @@ impl PdfProcessor {
             println!("Setting working directory to: {:?}", working_dir);
         }
 
-        // Add optional flags
-        // Note: format_lines, use_llm, and gemini_api_key are disabled
+        // Add optional flags, skipping any the installed marker_single doesn't know about
         if options.force_ocr {
             cmd.arg("--force_ocr");
         }
 
+        // Only older marker versions take OCR languages; newer ones detect the language themselves
+        let ocr_language = options.ocr_language.as_deref().and_then(language::iso_639_1);
+        let features = if options.format_lines || options.use_llm || ocr_language.is_some() {
+            self.probe_marker_features(&resolver, &marker_command_path).await
+        } else {
+            MarkerFeatureSupport::default()
+        };
+
+        if options.format_lines {
+            if features.format_lines {
+                cmd.arg("--format_lines");
+            } else {
+                println!("Installed marker_single does not support --format_lines, skipping");
+            }
+        }
+
+        if let Some(ocr_language) = ocr_language {
+            if features.languages {
+                cmd.arg("--languages").arg(ocr_language);
+            }
+        }
+
+        if options.use_llm {
+            if !features.use_llm {
+                return Err(PdfError::ExtractionError("LLM processing requested but the installed marker_single does not support --use_llm. Update marker-pdf with ./scripts/setup_marker.sh".to_string()));
+            }
+            let api_key = options.llm_api_key().ok_or_else(|| PdfError::ExtractionError(format!(
+                "LLM processing requested but no API key is stored for {}. Add one in settings or disable LLM features.",
+                options.llm_service.api_key_provider_id()
+            )))?;
+
+            // The key goes through the environment; arguments are visible to every local user in ps
+            cmd.arg("--use_llm")
+                .arg("--llm_service").arg(options.llm_service.service_class())
+                .env(options.llm_service.api_key_env_var(), api_key);
+
+            if options.inline_math && features.inline_math {
+                cmd.arg("--redo_inline_math");
+            }
+        }
+
         println!("Running marker_single command for file: {}", file_path);
         println!("Command path: {:?}", marker_command_path);
         println!("Output directory: {:?}", temp_dir);
@@ -817,16 +1080,8 @@ impl PdfProcessor {
         // Debug: Print the full command that will be executed
         println!("Full command: {:?} {:?}", marker_command_path, cmd.as_std().get_args().collect::<Vec<_>>());
 
-        // Execute command with timeout
-        let timeout_duration = std::time::Duration::from_secs(self.marker_timeout);
-        
-        let output = match tokio::time::timeout(timeout_duration, cmd.output()).await {
-            Ok(result) => match result {
-                Ok(output) => output,
-                Err(e) => return Err(PdfError::ExtractionError(format!("Failed to execute marker_single: {}", e))),
-            },
-            Err(_) => return Err(PdfError::ExtractionError(format!("Marker processing timed out ({} seconds). The PDF file may be too large or complex. Try processing it as a background job for longer files.", self.marker_timeout))),
-        };
+        // Execute command with timeout, streaming its progress output
+        let output = self.run_marker_process(cmd, on_progress.as_ref()).await?;
 
         if !output.status.success() {
             let error = String::from_utf8_lossy(&output.stderr);
@@ -899,11 +1154,6 @@ impl PdfProcessor {
             if let Some(markdown_path) = markdown_file {
                 if let Ok(markdown_content) = std::fs::read_to_string(&markdown_path) {
                     if !markdown_content.trim().is_empty() {
-                        // Best-effort cleanup of temp outputs
-                        let _ = std::fs::remove_file(&markdown_path);
-                        if temp_dir.exists() {
-                            let _ = std::fs::remove_dir_all(&temp_dir);
-                        }
                         println!("Marker returned non-zero exit but produced output; proceeding with extracted content (len={})", markdown_content.len());
                         return Ok(markdown_content);
                     }
@@ -1024,18 +1274,173 @@ impl PdfProcessor {
             ));
         }
 
-        // Clean up temporary files and directories
-        let _ = std::fs::remove_file(&markdown_file);
-        
-        // Clean up temp directory recursively (marker may create subdirectories)
-        if temp_dir.exists() {
-            let _ = std::fs::remove_dir_all(&temp_dir);
-        }
-
         println!("Successfully processed PDF with Marker, output length: {}", markdown_content.len());
         Ok(markdown_content)
     }
 
+    /// Which optional flags the resolved marker_single supports, from its --help output
+    pub async fn get_marker_feature_support(&self) -> MarkerFeatureSupport {
+        let resolver = MarkerCommandResolver::new().await;
+        match resolver.resolve_marker_command().await {
+            Some(command_path) => self.probe_marker_features(&resolver, &command_path).await,
+            None => MarkerFeatureSupport::default(),
+        }
+    }
+
+    async fn probe_marker_features(&self, resolver: &MarkerCommandResolver, command_path: &PathBuf) -> MarkerFeatureSupport {
+        if let Ok(cache) = MARKER_FEATURE_CACHE.lock() {
+            if let Some((cached_path, features)) = cache.as_ref() {
+                if cached_path == command_path {
+                    return *features;
+                }
+            }
+        }
+
+        let mut cmd = tokio::process::Command::new(command_path);
+        cmd.arg("--help")
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .stdin(std::process::Stdio::null())
+            .kill_on_drop(true);
+        if let Some(venv_path) = resolver.get_venv_path() {
+            self.setup_venv_environment(&mut cmd, venv_path);
+        }
+
+        let features = match tokio::time::timeout(std::time::Duration::from_secs(60), cmd.output()).await {
+            Ok(Ok(output)) => {
+                let help = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+                MarkerFeatureSupport::from_help_text(&help)
+            }
+            _ => {
+                eprintln!("⚠️ Could not read marker_single --help; assuming no optional features");
+                return MarkerFeatureSupport::default();
+            }
+        };
+
+        if let Ok(mut cache) = MARKER_FEATURE_CACHE.lock() {
+            *cache = Some((command_path.clone(), features));
+        }
+        features
+    }
+
+    /// Spawn marker_single and capture its output. stderr is read as it arrives so tqdm
+    /// progress lines can be reported; the process is killed if it exceeds the timeout.
+    async fn run_marker_process(
+        &self,
+        mut cmd: tokio::process::Command,
+        on_progress: Option<&MarkerProgressCallback>,
+    ) -> Result<std::process::Output, PdfError> {
+        use tokio::io::AsyncReadExt;
+
+        cmd.kill_on_drop(true);
+        let mut child = cmd.spawn()
+            .map_err(|e| PdfError::ExtractionError(format!("Failed to execute marker_single: {}", e)))?;
+
+        let mut stdout_pipe = child.stdout.take();
+        let mut stderr_pipe = child.stderr.take();
+
+        let read_stdout = async {
+            let mut stdout = Vec::new();
+            if let Some(pipe) = stdout_pipe.as_mut() {
+                let _ = pipe.read_to_end(&mut stdout).await;
+            }
+            stdout
+        };
+
+        let read_stderr = async {
+            let mut stderr = Vec::new();
+            let mut pending = String::new();
+            let mut chunk = [0u8; 4096];
+            let mut last_reported: Option<(String, u8)> = None;
+
+            if let Some(pipe) = stderr_pipe.as_mut() {
+                loop {
+                    let read = match pipe.read(&mut chunk).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => read,
+                    };
+                    stderr.extend_from_slice(&chunk[..read]);
+                    pending.push_str(&String::from_utf8_lossy(&chunk[..read]));
+
+                    // tqdm redraws with '\r', so treat it as a line break too
+                    while let Some(pos) = pending.find(['\r', '\n']) {
+                        let line: String = pending.drain(..=pos).collect();
+                        if let (Some(callback), Some(progress)) = (on_progress, parse_marker_progress(&line)) {
+                            let key = (progress.stage.clone(), progress.percent);
+                            if last_reported.as_ref() != Some(&key) {
+                                last_reported = Some(key);
+                                callback(progress);
+                            }
+                        }
+                    }
+                }
+            }
+            stderr
+        };
+
+        let run = async {
+            let (stdout, stderr) = tokio::join!(read_stdout, read_stderr);
+            let status = child.wait().await;
+            (status, stdout, stderr)
+        };
+
+        let timeout_duration = std::time::Duration::from_secs(self.marker_timeout);
+        match tokio::time::timeout(timeout_duration, run).await {
+            Ok((Ok(status), stdout, stderr)) => Ok(std::process::Output { status, stdout, stderr }),
+            Ok((Err(e), _, _)) => Err(PdfError::ExtractionError(format!("Failed to execute marker_single: {}", e))),
+            Err(_) => Err(PdfError::ExtractionError(format!("Marker processing timed out ({} seconds). The PDF file may be too large or complex. Try processing it as a background job for longer files.", self.marker_timeout))),
+        }
+    }
+
+    /// Extract text by uploading the PDF to a running marker server
+    async fn extract_with_marker_server(&self, file_path: &str, options: &MarkerOptions) -> Result<String, PdfError> {
+        crate::network::ensure_reachable(&self.marker_base_url, "The marker server")
+            .map_err(|e| PdfError::ExtractionError(e.to_string()))?;
+        let bytes = tokio::fs::read(file_path).await?;
+        let file_name = Path::new(file_path)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("document.pdf")
+            .to_string();
+
+        let form = reqwest::multipart::Form::new()
+            .part("file", reqwest::multipart::Part::bytes(bytes).file_name(file_name).mime_str("application/pdf")?)
+            .text("output_format", "markdown")
+            .text("force_ocr", options.force_ocr.to_string());
+
+        let client = crate::network::shared_client(crate::network::HttpPurpose::Download)
+            .map_err(PdfError::NetworkError)?;
+
+        println!("Uploading {} to marker server at {}", file_path, self.marker_base_url);
+        let response = client
+            .post(format!("{}/marker/upload", self.marker_base_url.trim_end_matches('/')))
+            .timeout(std::time::Duration::from_secs(self.marker_timeout))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| PdfError::NetworkError(format!("Marker server request failed ({}): {}", self.marker_base_url, e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(PdfError::NetworkError(format!("Marker server returned {}: {}", status, body)));
+        }
+
+        let result: serde_json::Value = response.json().await?;
+        if result.get("success").and_then(|v| v.as_bool()) == Some(false) {
+            let error = result.get("error").and_then(|v| v.as_str()).unwrap_or("unknown error");
+            return Err(PdfError::ExtractionError(format!("Marker server failed: {}", error)));
+        }
+
+        let markdown = result.get("output").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        if markdown.trim().is_empty() {
+            return Err(PdfError::ExtractionError("Marker server produced empty output.".to_string()));
+        }
+
+        println!("Successfully processed PDF with marker server, output length: {}", markdown.len());
+        Ok(markdown)
+    }
+
     /// Get detailed marker installation status synchronously using an existing resolver
     /// This is a helper method for use within other methods that already have a resolver
     fn get_marker_installation_status_sync(&self, resolver: &MarkerCommandResolver) -> MarkerInstallationStatus {
@@ -1271,24 +1676,183 @@ impl PdfProcessor {
     }
 }
 
+/// How Marker is invoked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerMode {
+    /// Run marker_single (venv or global) as a child process
+    #[default]
+    Subprocess,
+    /// Upload to a running marker server (marker_server) at `marker_base_url`
+    Server,
+}
+
+/// LLM backend Marker calls when `use_llm` is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerLlmService {
+    #[default]
+    Gemini,
+    #[serde(rename = "openai")]
+    OpenAI,
+}
+
+impl MarkerLlmService {
+    /// Key store id the API key is saved under (OpenAI shares the chat provider's key)
+    pub fn api_key_provider_id(&self) -> &'static str {
+        match self {
+            MarkerLlmService::Gemini => "gemini",
+            MarkerLlmService::OpenAI => "openai-default",
+        }
+    }
+
+    fn service_class(&self) -> &'static str {
+        match self {
+            MarkerLlmService::Gemini => "marker.services.gemini.GoogleGeminiService",
+            MarkerLlmService::OpenAI => "marker.services.openai.OpenAIService",
+        }
+    }
+
+    /// Environment variable marker reads the API key from when it isn't passed as a flag: marker's
+    /// settings read GOOGLE_API_KEY, and the openai client falls back to OPENAI_API_KEY
+    fn api_key_env_var(&self) -> &'static str {
+        match self {
+            MarkerLlmService::Gemini => "GOOGLE_API_KEY",
+            MarkerLlmService::OpenAI => "OPENAI_API_KEY",
+        }
+    }
+}
+
+fn default_format_lines() -> bool {
+    true
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct MarkerOptions {
     pub extract_images: bool,
     pub force_ocr: bool,
     pub prefer_marker: bool,
-    // Disabled options: use_llm, format_lines, gemini_api_key
+    #[serde(default)]
+    pub mode: MarkerMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_methods: Option<Vec<ExtractionMethod>>, // Extraction chain for background jobs
+    #[serde(default)]
+    pub use_llm: bool,
+    #[serde(default = "default_format_lines")]
+    pub format_lines: bool,
+    #[serde(default)]
+    pub inline_math: bool, // Needs use_llm
+    #[serde(default)]
+    pub llm_service: MarkerLlmService,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ocr_language: Option<String>, // ISO 639-3 code from language::detect_language
+    // Keys are looked up from the key store when a job runs and never persisted with it
+    #[serde(default, skip_serializing)]
+    pub gemini_api_key: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub openai_api_key: Option<String>,
+}
+
+impl MarkerOptions {
+    /// Fill in the API key for the selected LLM service from the key store
+    pub async fn with_llm_key(mut self, database: &crate::database::Database) -> Self {
+        if !self.use_llm {
+            return self;
+        }
+
+        let api_key = database
+            .get_api_key(self.llm_service.api_key_provider_id())
+            .await
+            .ok()
+            .flatten();
+        match self.llm_service {
+            MarkerLlmService::Gemini => self.gemini_api_key = self.gemini_api_key.or(api_key),
+            MarkerLlmService::OpenAI => self.openai_api_key = self.openai_api_key.or(api_key),
+        }
+        self
+    }
+
+    fn llm_api_key(&self) -> Option<&str> {
+        let key = match self.llm_service {
+            MarkerLlmService::Gemini => self.gemini_api_key.as_deref(),
+            MarkerLlmService::OpenAI => self.openai_api_key.as_deref(),
+        };
+        key.filter(|k| !k.trim().is_empty())
+    }
 }
 
+/// Optional marker_single flags the installed version understands
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct MarkerFeatureSupport {
+    pub use_llm: bool,
+    pub format_lines: bool,
+    pub inline_math: bool,
+    pub languages: bool,
+}
+
+impl MarkerFeatureSupport {
+    fn from_help_text(help: &str) -> Self {
+        MarkerFeatureSupport {
+            use_llm: help.contains("--use_llm"),
+            format_lines: help.contains("--format_lines"),
+            inline_math: help.contains("--redo_inline_math"),
+            languages: help.contains("--languages"),
+        }
+    }
+}
+
+// marker_single --help is slow (it imports torch), so probe each command path once
+static MARKER_FEATURE_CACHE: std::sync::Mutex<Option<(PathBuf, MarkerFeatureSupport)>> = std::sync::Mutex::new(None);
+
 impl Default for MarkerOptions {
     fn default() -> Self {
         MarkerOptions {
             extract_images: false,
             force_ocr: false,
             prefer_marker: true,
+            mode: MarkerMode::default(),
+            preferred_methods: None,
+            use_llm: false,
+            format_lines: true,
+            inline_math: false,
+            llm_service: MarkerLlmService::default(),
+            ocr_language: None,
+            gemini_api_key: None,
+            openai_api_key: None,
         }
     }
 }
 
+/// Progress reported by marker_single while it works through a PDF
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MarkerProgress {
+    pub stage: String, // e.g. "Recognizing layout"
+    pub percent: u8,
+    pub current: Option<u64>,
+    pub total: Option<u64>,
+}
+
+pub type MarkerProgressCallback = std::sync::Arc<dyn Fn(MarkerProgress) + Send + Sync>;
+
+// Compiled once; parse_marker_progress runs for every line marker prints
+static PROGRESS_RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+    regex::Regex::new(r"^\s*([^:|]+?):\s*(\d{1,3})%\|[^|]*\|\s*(?:(\d+)/(\d+))?")
+        .expect("marker progress pattern is valid")
+});
+
+/// Parse a tqdm-style progress line, e.g. "Recognizing layout:  45%|####5     | 9/20 [00:03<00:04]"
+pub fn parse_marker_progress(line: &str) -> Option<MarkerProgress> {
+    let captures = PROGRESS_RE.captures(line)?;
+
+    Some(MarkerProgress {
+        stage: captures.get(1)?.as_str().trim().to_string(),
+        percent: captures.get(2)?.as_str().parse::<u8>().ok()?.min(100),
+        current: captures.get(3).and_then(|m| m.as_str().parse().ok()),
+        total: captures.get(4).and_then(|m| m.as_str().parse().ok()),
+    })
+}
+
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct PdfMetadata {
@@ -1299,6 +1863,14 @@ pub struct PdfMetadata {
 }
 
 #[derive(Debug, Clone)]
+pub struct PdfBookmark {
+    pub level: usize, // 1 for top-level entries
+    pub title: String,
+    pub page: Option<usize>, // 1-based
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ExtractionMethod {
     Marker,      // High quality, slow
     MarkItDown,  // Microsoft's tool, balanced
@@ -1306,13 +1878,224 @@ pub enum ExtractionMethod {
     Basic,       // Simple text extraction
 }
 
-#[derive(Debug, Clone)]
+impl ExtractionMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExtractionMethod::Marker => "marker",
+            ExtractionMethod::MarkItDown => "markitdown",
+            ExtractionMethod::Enhanced => "enhanced",
+            ExtractionMethod::Basic => "basic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 pub struct ExtractOptions {
     pub preferred_methods: Vec<ExtractionMethod>,
     pub extract_images: bool,
     pub force_ocr: bool,
     pub timeout_seconds: u64,
-    // Disabled options: use_llm, format_lines
+    pub marker_mode: MarkerMode,
+    pub use_llm: bool,
+    pub format_lines: bool,
+    pub inline_math: bool,
+    pub llm_service: MarkerLlmService,
+    pub ocr_language: Option<String>, // Detected document language, passed to OCR
+    #[serde(skip_serializing)]
+    pub gemini_api_key: Option<String>,
+    #[serde(skip_serializing)]
+    pub openai_api_key: Option<String>,
+}
+
+impl ExtractOptions {
+    /// Options that try the given methods in order, keeping the other defaults
+    pub fn with_methods(preferred_methods: Vec<ExtractionMethod>) -> Self {
+        ExtractOptions {
+            preferred_methods,
+            ..Default::default()
+        }
+    }
+
+    /// Options for a background job; without an explicit chain, `prefer_marker: false` skips Marker
+    pub fn from_marker_options(options: &MarkerOptions, timeout_seconds: u64) -> Self {
+        let preferred_methods = match &options.preferred_methods {
+            Some(methods) if !methods.is_empty() => methods.clone(),
+            _ => ExtractOptions::default()
+                .preferred_methods
+                .into_iter()
+                .filter(|method| options.prefer_marker || *method != ExtractionMethod::Marker)
+                .collect(),
+        };
+
+        ExtractOptions {
+            preferred_methods,
+            extract_images: options.extract_images,
+            force_ocr: options.force_ocr,
+            timeout_seconds,
+            marker_mode: options.mode,
+            use_llm: options.use_llm,
+            format_lines: options.format_lines,
+            inline_math: options.inline_math,
+            llm_service: options.llm_service,
+            ocr_language: options.ocr_language.clone(),
+            gemini_api_key: options.gemini_api_key.clone(),
+            openai_api_key: options.openai_api_key.clone(),
+        }
+    }
+
+    fn marker_options(&self) -> MarkerOptions {
+        MarkerOptions {
+            extract_images: self.extract_images,
+            force_ocr: self.force_ocr,
+            prefer_marker: true,
+            mode: self.marker_mode,
+            preferred_methods: None,
+            use_llm: self.use_llm,
+            format_lines: self.format_lines,
+            inline_math: self.inline_math,
+            llm_service: self.llm_service,
+            ocr_language: self.ocr_language.clone(),
+            gemini_api_key: self.gemini_api_key.clone(),
+            openai_api_key: self.openai_api_key.clone(),
+        }
+    }
+}
+
+/// A failed step of the extraction chain
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExtractionAttempt {
+    pub method: ExtractionMethod,
+    pub error: String,
+}
+
+/// Content produced by `extract_with_options`, with the method that produced it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ExtractionResult {
+    pub content: String,
+    pub method: ExtractionMethod,
+    pub failed_attempts: Vec<ExtractionAttempt>,
+    pub duration_ms: u64,
+    pub quality: ExtractionQuality,
+}
+
+impl ExtractionResult {
+    /// Shape stored under the "extraction" key of a document's metadata
+    pub fn to_metadata(&self) -> serde_json::Value {
+        serde_json::json!({
+            "method": self.method,
+            "failed_attempts": self.failed_attempts,
+            "duration_ms": self.duration_ms,
+            "quality": self.quality,
+            "extracted_at": chrono::Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+/// Below this score a document is queued for re-extraction with OCR (and Marker's LLM mode if a key is stored)
+pub const EXTRACTION_QUALITY_THRESHOLD: f64 = 0.6;
+
+/// Heuristic quality of extracted text, 0.0 (unusable) to 1.0
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ExtractionQuality {
+    pub score: f64,
+    pub char_count: usize,
+    pub word_count: usize,
+    pub avg_word_length: f64,
+    pub garbled_ratio: f64,    // Replacement/control/private-use characters and (cid:N) glyph refs
+    pub word_like_ratio: f64,  // Tokens that look like words rather than symbol soup
+    pub page_count: usize,     // Pages separated by form feeds; 1 when none are present
+    pub empty_page_ratio: f64,
+}
+
+impl ExtractionQuality {
+    pub fn is_acceptable(&self) -> bool {
+        self.score >= EXTRACTION_QUALITY_THRESHOLD
+    }
+}
+
+// Letters of scripts with upper and lower case; CJK, Arabic, Hebrew and Indic letters have neither
+fn has_case(c: char) -> bool {
+    c.is_uppercase() || c.is_lowercase()
+}
+
+// pdf-extract emits "(cid:123)" for glyphs it can't map to text. Compiled once, as every
+// extractor in the fallback chain is scored on every page batch.
+static CID_RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+    regex::Regex::new(r"\(cid:\d+\)").expect("cid glyph pattern is valid")
+});
+
+/// Score extracted text using garbled-character, word and empty-page statistics
+pub fn score_extraction_quality(content: &str) -> ExtractionQuality {
+    let char_count = content.chars().filter(|c| !c.is_whitespace()).count();
+    if char_count == 0 {
+        return ExtractionQuality { page_count: 1, empty_page_ratio: 1.0, ..Default::default() };
+    }
+
+    let cid_chars: usize = CID_RE.find_iter(content).map(|m| m.as_str().len()).sum();
+    let garbled_chars = content
+        .chars()
+        .filter(|c| {
+            *c == '\u{FFFD}'
+                || ('\u{E000}'..='\u{F8FF}').contains(c)
+                || (c.is_control() && !matches!(c, '\n' | '\r' | '\t' | '\x0C'))
+        })
+        .count()
+        + cid_chars;
+    let garbled_ratio = (garbled_chars as f64 / char_count as f64).min(1.0);
+
+    // Chinese, Japanese and Thai don't put spaces between words, so whole sentences show up
+    // as one "word" and the word length checks below don't apply to them
+    let unspaced_chars = content.chars().filter(|c| language::is_unspaced_script(*c)).count();
+    let mostly_unspaced = unspaced_chars * 2 > char_count;
+
+    let words: Vec<&str> = content.split_whitespace().collect();
+    let word_count = words.len();
+    let word_like = words
+        .iter()
+        .filter(|w| {
+            let letters = w.chars().filter(|c| c.is_alphabetic()).count();
+            let len = w.chars().count();
+            letters > 0 && (len <= 30 || mostly_unspaced) && letters * 2 >= len
+        })
+        .count();
+    let word_like_ratio = if word_count > 0 { word_like as f64 / word_count as f64 } else { 0.0 };
+    let avg_word_length = if word_count > 0 {
+        words.iter().map(|w| w.chars().count()).sum::<usize>() as f64 / word_count as f64
+    } else {
+        0.0
+    };
+
+    let pages: Vec<&str> = content.split('\x0C').collect();
+    let page_count = pages.len();
+    let empty_pages = pages.iter().filter(|p| language::approximate_word_count(p) < 5).count();
+    let empty_page_ratio = empty_pages as f64 / page_count as f64;
+
+    // Typical prose averages 4-7 characters per word; glued or shredded text drifts far outside that
+    let length_score = if mostly_unspaced || (3.0..=9.0).contains(&avg_word_length) {
+        1.0
+    } else if avg_word_length < 3.0 {
+        (avg_word_length / 3.0).max(0.0)
+    } else {
+        (9.0 / avg_word_length).min(1.0)
+    };
+    let garbled_score = (1.0 - garbled_ratio * 10.0).max(0.0);
+    let volume_score = (char_count as f64 / 200.0).min(1.0);
+
+    // Blank pages usually mean scanned pages without a text layer, so they scale the whole score down
+    let text_score = garbled_score * 0.45 + word_like_ratio * 0.35 + length_score * 0.2;
+    let score = text_score * (1.0 - empty_page_ratio * 0.8) * volume_score;
+
+    ExtractionQuality {
+        score: (score * 1000.0).round() / 1000.0,
+        char_count,
+        word_count,
+        avg_word_length,
+        garbled_ratio,
+        word_like_ratio,
+        page_count,
+        empty_page_ratio,
+    }
 }
 
 impl Default for ExtractOptions {
@@ -1326,7 +2109,15 @@ impl Default for ExtractOptions {
             ],
             extract_images: false,
             force_ocr: false,
-            timeout_seconds: 120,
+            timeout_seconds: 1200, // Matches PdfProcessor::new()
+            marker_mode: MarkerMode::default(),
+            use_llm: false,
+            format_lines: true,
+            inline_math: false,
+            llm_service: MarkerLlmService::default(),
+            ocr_language: None,
+            gemini_api_key: None,
+            openai_api_key: None,
         }
     }
 }
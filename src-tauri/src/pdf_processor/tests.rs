@@ -182,6 +182,115 @@ mod unit_tests {
         assert!(options.gemini_api_key.is_none());
     }
 
+    #[test]
+    fn test_parse_marker_progress() {
+        let progress = parse_marker_progress("Recognizing layout:  45%|####5     | 9/20 [00:03<00:04,  2.51it/s]").unwrap();
+        assert_eq!(progress.stage, "Recognizing layout");
+        assert_eq!(progress.percent, 45);
+        assert_eq!(progress.current, Some(9));
+        assert_eq!(progress.total, Some(20));
+
+        assert!(parse_marker_progress("Loaded layout model on device cpu").is_none());
+        assert_eq!(MarkerOptions::default().mode, MarkerMode::Subprocess);
+    }
+
+    #[test]
+    fn test_extract_options_from_marker_options() {
+        let options = ExtractOptions::from_marker_options(&MarkerOptions::default(), 60);
+        assert_eq!(options.preferred_methods.first(), Some(&ExtractionMethod::Marker));
+        assert_eq!(options.timeout_seconds, 60);
+
+        let without_marker = MarkerOptions { prefer_marker: false, ..Default::default() };
+        let options = ExtractOptions::from_marker_options(&without_marker, 60);
+        assert!(!options.preferred_methods.contains(&ExtractionMethod::Marker));
+
+        let explicit = MarkerOptions {
+            preferred_methods: Some(vec![ExtractionMethod::Basic]),
+            ..Default::default()
+        };
+        let options = ExtractOptions::from_marker_options(&explicit, 60);
+        assert_eq!(options.preferred_methods, vec![ExtractionMethod::Basic]);
+    }
+
+    #[test]
+    fn test_score_extraction_quality() {
+        let prose = "The mitochondria is the powerhouse of the cell and produces most of its chemical energy. ".repeat(10);
+        let quality = score_extraction_quality(&prose);
+        assert!(quality.is_acceptable(), "clean prose scored {}", quality.score);
+
+        let garbled = "(cid:12)(cid:34)\u{FFFD}\u{FFFD} ".repeat(60);
+        assert!(!score_extraction_quality(&garbled).is_acceptable());
+
+        let mostly_empty = format!("{}\x0C \x0C \x0C ", prose);
+        let quality = score_extraction_quality(&mostly_empty);
+        assert_eq!(quality.page_count, 4);
+        assert!(!quality.is_acceptable());
+
+        assert_eq!(score_extraction_quality("").score, 0.0);
+    }
+
+    #[test]
+    fn test_score_extraction_quality_cjk() {
+        // No spaces between words; must not be mistaken for glued-together garbage
+        let chinese = "线粒体是细胞的动力工厂，负责产生细胞所需的大部分化学能量。".repeat(10);
+        let quality = score_extraction_quality(&chinese);
+        assert!(quality.is_acceptable(), "clean Chinese scored {}", quality.score);
+    }
+
+    #[test]
+    fn test_heading_heuristics_unicode() {
+        let processor = PdfProcessor::new();
+        assert!(processor.looks_like_heading("第一章 细胞结构"));
+        assert!(!processor.looks_like_heading("线粒体是细胞的动力工厂。"));
+        assert!(!processor.looks_like_heading("lowercase words only"));
+        assert!(!processor.is_title_case("细胞结构"));
+        assert!(processor.is_title_case("Cell Structure and Function"));
+
+        let lines = ["", "第一章 细胞结构", ""];
+        assert_eq!(processor.determine_heading_level(lines[1], 1, &lines), 2);
+    }
+
+    #[test]
+    fn test_paragraph_joining_cjk() {
+        let processor = PdfProcessor::new();
+        let markdown = processor.text_to_markdown_enhanced("线粒体是细胞的动力工厂，负责产生\n细胞所需的大部分化学能量。\n");
+        assert!(markdown.contains("负责产生细胞所需"), "got {:?}", markdown);
+    }
+
+    #[test]
+    fn test_equations_kept_as_display_math() {
+        let processor = PdfProcessor::new();
+        let markdown = processor.text_to_markdown_enhanced("Gauss's law states that\n∇ · E = ρ/ε₀\nin differential form.\n");
+        assert!(markdown.contains("$$\n∇ · E = ρ/ε₀\n$$"), "got {:?}", markdown);
+        assert!(!crate::math::looks_like_equation("The energy E = mc² is conserved in every frame"));
+    }
+
+    #[test]
+    fn test_math_normalization_matches_notations() {
+        let expected = crate::math::normalize_math("div E");
+        assert_eq!(crate::math::normalize_math("∇·E"), expected);
+        assert_eq!(crate::math::normalize_math("$\\nabla \\cdot \\mathbf{E}$"), expected);
+        assert!(crate::math::normalize_math("\\nabla \\cdot \\mathbf{E} = \\frac{\\rho}{\\varepsilon_0}").contains(&expected));
+    }
+
+    #[test]
+    fn test_marker_llm_key_env_var() {
+        assert_eq!(MarkerLlmService::Gemini.api_key_env_var(), "GOOGLE_API_KEY");
+        assert_eq!(MarkerLlmService::OpenAI.api_key_env_var(), "OPENAI_API_KEY");
+
+        // Only the selected service's key is handed over, and a blank key counts as missing
+        let options = MarkerOptions {
+            use_llm: true,
+            llm_service: MarkerLlmService::OpenAI,
+            gemini_api_key: Some("gemini-key".to_string()),
+            openai_api_key: Some("  ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(options.llm_api_key(), None);
+        let options = MarkerOptions { llm_service: MarkerLlmService::Gemini, ..options };
+        assert_eq!(options.llm_api_key(), Some("gemini-key"));
+    }
+
     #[test]
     fn test_pdf_error_types() {
         let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
@@ -0,0 +1,192 @@
+//! Profiles keep separate libraries apart, e.g. "PhD research" and "Spanish learning". Each
+//! profile has its own data directory and so its own database (with the API keys and settings
+//! stored in it), PDF storage, attachments, backups and embeddings; downloaded models are
+//! shared. The default profile is the data root itself, so a library from before profiles
+//! existed stays where it is; other profiles live under `profiles/<id>`.
+//!
+//! The registry is a small JSON file in the data root. Services open their files once at
+//! startup, so the app restarts to switch profiles.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+
+pub const DEFAULT_PROFILE_ID: &str = "default";
+/// Overrides the active profile for this process, e.g. the CLI's --profile
+pub const PROFILE_ENV: &str = "STELLAR_PROFILE";
+
+const REGISTRY_FILE: &str = "profiles.json";
+const PROFILES_DIR: &str = "profiles";
+const MAX_NAME_CHARS: usize = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub id: String, // Also the directory name under profiles/
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileInfo {
+    #[serde(flatten)]
+    pub profile: Profile,
+    pub is_active: bool,
+    pub data_dir: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ProfileRegistry {
+    active: Option<String>,
+    profiles: Vec<Profile>,
+}
+
+// Cached so the registry isn't read on every path lookup
+static ACTIVE_PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+/// The profile this process works in
+pub fn active_profile_id() -> String {
+    if let Some(id) = std::env::var(PROFILE_ENV).ok().filter(|id| !id.is_empty()) {
+        return id;
+    }
+    if let Some(id) = ACTIVE_PROFILE.read().ok().and_then(|active| active.clone()) {
+        return id;
+    }
+
+    let id = paths::root_data_dir().ok()
+        .and_then(|root| load_registry(&root).ok())
+        .and_then(|registry| registry.active.filter(|id| registry.profiles.iter().any(|p| &p.id == id)))
+        .unwrap_or_else(|| DEFAULT_PROFILE_ID.to_string());
+    if let Ok(mut active) = ACTIVE_PROFILE.write() {
+        *active = Some(id.clone());
+    }
+    id
+}
+
+/// Data directory of a profile (not created here)
+pub fn profile_dir(root: &Path, id: &str) -> PathBuf {
+    if id == DEFAULT_PROFILE_ID {
+        root.to_path_buf()
+    } else {
+        root.join(PROFILES_DIR).join(id)
+    }
+}
+
+/// Where non-default profiles live; excluded from the default profile's disk usage
+pub fn profiles_root(root: &Path) -> PathBuf {
+    root.join(PROFILES_DIR)
+}
+
+/// The default profile followed by the created ones, oldest first
+pub fn list_profiles() -> Result<Vec<ProfileInfo>, String> {
+    let root = paths::root_data_dir()?;
+    let registry = load_registry(&root)?;
+    let active = active_profile_id();
+
+    let default = Profile {
+        id: DEFAULT_PROFILE_ID.to_string(),
+        name: "Default".to_string(),
+        created_at: registry.profiles.iter().map(|p| p.created_at).min().unwrap_or_else(Utc::now),
+    };
+    Ok(std::iter::once(default).chain(registry.profiles)
+        .map(|profile| ProfileInfo {
+            is_active: profile.id == active,
+            data_dir: profile_dir(&root, &profile.id).to_string_lossy().to_string(),
+            profile,
+        })
+        .collect())
+}
+
+pub fn create_profile(name: &str) -> Result<Profile, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("Profile names are limited to {} characters", MAX_NAME_CHARS));
+    }
+
+    let root = paths::root_data_dir()?;
+    let mut registry = load_registry(&root)?;
+    if registry.profiles.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+        return Err(format!("A profile named '{}' already exists", name));
+    }
+
+    let id = unique_profile_id(name, &registry);
+    std::fs::create_dir_all(profile_dir(&root, &id))
+        .map_err(|e| format!("Failed to create profile directory: {}", e))?;
+
+    let profile = Profile { id, name: name.to_string(), created_at: Utc::now() };
+    registry.profiles.push(profile.clone());
+    save_registry(&root, &registry)?;
+
+    println!("👤 Created profile '{}' ({})", profile.name, profile.id);
+    Ok(profile)
+}
+
+/// Make `id` the profile used from the next start on
+pub fn set_active_profile(id: &str) -> Result<ProfileInfo, String> {
+    let root = paths::root_data_dir()?;
+    let mut registry = load_registry(&root)?;
+    if id != DEFAULT_PROFILE_ID && !registry.profiles.iter().any(|p| p.id == id) {
+        return Err(format!("Profile not found: {}", id));
+    }
+
+    registry.active = Some(id.to_string());
+    save_registry(&root, &registry)?;
+    if let Ok(mut active) = ACTIVE_PROFILE.write() {
+        *active = Some(id.to_string());
+    }
+
+    list_profiles()?.into_iter()
+        .find(|info| info.profile.id == id)
+        .ok_or_else(|| format!("Profile not found: {}", id))
+}
+
+// Readable directory name from the profile name, e.g. "PhD research" -> "phd-research"
+fn unique_profile_id(name: &str, registry: &ProfileRegistry) -> String {
+    let mut slug = String::new();
+    for c in name.to_lowercase().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    let base = if slug.is_empty() || slug == DEFAULT_PROFILE_ID || slug == PROFILES_DIR {
+        format!("profile-{}", &uuid::Uuid::new_v4().simple().to_string()[..8])
+    } else {
+        slug.to_string()
+    };
+
+    let taken = |id: &str| registry.profiles.iter().any(|p| p.id == id);
+    let mut id = base.clone();
+    let mut n = 2;
+    while taken(&id) {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+fn load_registry(root: &Path) -> Result<ProfileRegistry, String> {
+    let path = root.join(REGISTRY_FILE);
+    if !path.exists() {
+        return Ok(ProfileRegistry::default());
+    }
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read profiles: {}", e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse profiles: {}", e))
+}
+
+fn save_registry(root: &Path, registry: &ProfileRegistry) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(registry)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+    std::fs::write(root.join(REGISTRY_FILE), json)
+        .map_err(|e| format!("Failed to save profiles: {}", e))
+}
@@ -0,0 +1,113 @@
+//! Redaction of personal identifiers (email addresses, student IDs, ...) that end up embedded in
+//! imported documents. Rules are regex patterns applied to deck packages and markdown exports,
+//! and to prompts sent to AI providers that don't run on this machine.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+pub const REDACTION_SETTINGS_KEY: &str = "redaction_rules";
+
+const DEFAULT_REPLACEMENT: &str = "[redacted]";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    pub name: String,
+    pub pattern: String, // Regex syntax of the `regex` crate, e.g. (?i) for case-insensitive
+    #[serde(default)]
+    pub replacement: Option<String>, // Defaults to "[redacted]"
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionSettings {
+    #[serde(default = "default_true")]
+    pub apply_to_exports: bool, // Deck packages and markdown exports
+    #[serde(default = "default_true")]
+    pub apply_to_ai: bool, // Prompts sent to providers that aren't on localhost
+    #[serde(default = "default_rules")]
+    pub rules: Vec<RedactionRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_rules() -> Vec<RedactionRule> {
+    vec![
+        RedactionRule {
+            name: "Email addresses".to_string(),
+            pattern: r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b".to_string(),
+            replacement: Some("[email]".to_string()),
+            enabled: true,
+        },
+        // Only labelled IDs ("Student ID: 20231234"); bare numbers are too often page numbers,
+        // years or values in the study material itself
+        RedactionRule {
+            name: "Student IDs".to_string(),
+            pattern: r"(?i)\b(?:student|matriculation|matric)\s*(?:id|no\.?|number|#)\s*[:#]?\s*[a-z0-9-]*\d[a-z0-9-]{3,}".to_string(),
+            replacement: Some("[student id]".to_string()),
+            enabled: true,
+        },
+    ]
+}
+
+impl Default for RedactionSettings {
+    fn default() -> Self {
+        Self {
+            apply_to_exports: true,
+            apply_to_ai: true,
+            rules: default_rules(),
+        }
+    }
+}
+
+/// The enabled rules of a `RedactionSettings`, compiled once per export or prompt
+pub struct Redactor {
+    rules: Vec<(Regex, String)>,
+}
+
+impl Redactor {
+    /// Fails on the first rule whose pattern doesn't compile, naming it
+    pub fn new(settings: &RedactionSettings) -> Result<Self, String> {
+        let rules = settings.rules.iter()
+            .filter(|rule| rule.enabled && !rule.pattern.trim().is_empty())
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern)
+                    .map_err(|e| format!("Invalid redaction pattern '{}': {}", rule.name, e))?;
+                let replacement = rule.replacement.clone().unwrap_or_else(|| DEFAULT_REPLACEMENT.to_string());
+                Ok((regex, replacement))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Replace every match of every rule; returns the redacted text and how many matches were
+    /// replaced
+    pub fn redact(&self, text: &str) -> (String, usize) {
+        let mut redacted = text.to_string();
+        let mut count = 0;
+        for (regex, replacement) in &self.rules {
+            let matches = regex.find_iter(&redacted).count();
+            if matches > 0 {
+                count += matches;
+                // NoExpand: a replacement like "$1" is meant literally
+                redacted = regex.replace_all(&redacted, regex::NoExpand(replacement)).into_owned();
+            }
+        }
+        (redacted, count)
+    }
+
+    /// Redact `text` in place, adding the number of replacements to `count`
+    pub fn redact_in_place(&self, text: &mut String, count: &mut usize) {
+        let (redacted, replaced) = self.redact(text);
+        if replaced > 0 {
+            *text = redacted;
+            *count += replaced;
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Reminder scheduler: a backend tick loop that sends OS notifications for due flashcards, daily
+//! study goal nudges and finished processing jobs. Nothing is sent during quiet hours; due
+//! reminders and finished jobs are picked up once they end.
+
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{Local, Timelike, Utc};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::Mutex;
+use tokio::time;
+
+use crate::database::{Database, ProcessingJob, ReminderSettings, ReminderState};
+
+pub const REMINDER_SETTINGS_KEY: &str = "reminders";
+const REMINDER_STATE_KEY: &str = "reminder_state";
+
+// How often the scheduler checks whether anything should be sent
+const TICK_INTERVAL_SECS: u64 = 60;
+
+type DatabaseState = Arc<Mutex<Option<Database>>>;
+
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    pub title: String,
+    pub body: String,
+}
+
+pub struct ReminderScheduler {
+    app: AppHandle,
+    database: DatabaseState,
+}
+
+impl ReminderScheduler {
+    pub fn new(app: AppHandle, database: DatabaseState) -> Self {
+        Self { app, database }
+    }
+
+    pub fn start(self) {
+        println!("🔔 Starting reminder scheduler...");
+
+        tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_secs(TICK_INTERVAL_SECS));
+
+            loop {
+                interval.tick().await;
+
+                let reminders = {
+                    let db_guard = self.database.lock().await;
+                    match db_guard.as_ref() {
+                        Some(database) => collect_reminders(database).await,
+                        None => Ok(Vec::new()),
+                    }
+                };
+                match reminders {
+                    Ok(reminders) => {
+                        for reminder in reminders {
+                            send_notification(&self.app, &reminder);
+                        }
+                    }
+                    Err(e) => eprintln!("⚠️ Could not check reminders: {}", e),
+                }
+            }
+        });
+    }
+}
+
+pub fn send_notification(app: &AppHandle, reminder: &Reminder) {
+    match app.notification().builder().title(&reminder.title).body(&reminder.body).show() {
+        Ok(()) => println!("🔔 {}: {}", reminder.title, reminder.body),
+        Err(e) => eprintln!("⚠️ Failed to show notification '{}': {}", reminder.title, e),
+    }
+}
+
+/// Whether `hour` falls in quiet hours; the range may wrap past midnight (22 to 8)
+fn in_quiet_hours(settings: &ReminderSettings, hour: u32) -> bool {
+    let (start, end) = (settings.quiet_hours_start, settings.quiet_hours_end);
+    if !settings.quiet_hours_enabled || start == end {
+        return false;
+    }
+    if start < end {
+        hour >= start && hour < end
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// Work out which reminders are due now and record them as sent
+async fn collect_reminders(database: &Database) -> Result<Vec<Reminder>, String> {
+    let settings: ReminderSettings = database.get_typed_setting(REMINDER_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to load reminder settings: {}", e))?;
+    let mut state: ReminderState = database.get_typed_setting(REMINDER_STATE_KEY).await
+        .map_err(|e| format!("Failed to load reminder state: {}", e))?;
+
+    let now = Utc::now();
+    let local_now = Local::now();
+    let mut reminders = Vec::new();
+
+    // Jobs that finished before the first check (or while reminders were off) aren't announced
+    let jobs_since = state.jobs_checked_at.unwrap_or(now);
+    if !settings.enabled || !settings.job_notifications {
+        state.jobs_checked_at = Some(now);
+    }
+
+    if settings.enabled && !in_quiet_hours(&settings, local_now.hour()) {
+        if settings.job_notifications {
+            let mut jobs = database.get_processing_jobs_finished_between(jobs_since, now).await
+                .map_err(|e| format!("Failed to load finished jobs: {}", e))?;
+            // Tagging a quick capture happens in the background and isn't worth interrupting for
+            jobs.retain(|job| job.job_type != "auto_tagging");
+            reminders.extend(job_reminder(&jobs));
+            state.jobs_checked_at = Some(now);
+        }
+
+        let reminded_recently = state.last_due_review_reminder
+            .is_some_and(|last| now - last < chrono::Duration::hours(settings.due_review_interval_hours.max(1)));
+        if settings.due_reviews && !reminded_recently {
+            let due = database.count_due_flashcards().await
+                .map_err(|e| format!("Failed to count due flashcards: {}", e))?;
+            if due >= settings.due_review_threshold.max(1) {
+                reminders.push(Reminder {
+                    title: "Flashcards due".to_string(),
+                    body: format!("{} cards are ready for review", due),
+                });
+                state.last_due_review_reminder = Some(now);
+            }
+        }
+
+        let today = local_now.date_naive();
+        let today_key = today.format("%Y-%m-%d").to_string();
+        if settings.study_goal_minutes > 0
+            && local_now.hour() >= settings.study_goal_nudge_hour
+            && state.last_goal_nudge_date.as_deref() != Some(today_key.as_str())
+        {
+            let activity = database.get_daily_activity(today).await
+                .map_err(|e| format!("Failed to load today's activity: {}", e))?;
+            let remaining = (settings.study_goal_minutes as f64 - activity.total_minutes).ceil();
+            if remaining > 0.0 {
+                reminders.push(Reminder {
+                    title: "Study goal".to_string(),
+                    body: format!("{} more minutes to reach today's goal of {} minutes", remaining, settings.study_goal_minutes),
+                });
+            }
+            // Checked once a day, whether or not the goal was already met
+            state.last_goal_nudge_date = Some(today_key);
+        }
+    }
+
+    database.set_typed_setting(REMINDER_STATE_KEY, &state).await
+        .map_err(|e| format!("Failed to save reminder state: {}", e))?;
+
+    Ok(reminders)
+}
+
+/// One notification per finished job, or a summary when several finished together
+fn job_reminder(jobs: &[ProcessingJob]) -> Option<Reminder> {
+    let failed = jobs.iter().filter(|job| job.status == "failed").count();
+    let completed = jobs.len() - failed;
+
+    match jobs {
+        [] => None,
+        [job] => {
+            let name = job.title.clone().unwrap_or_else(|| job.original_filename.clone());
+            Some(if failed > 0 {
+                Reminder { title: "Processing failed".to_string(), body: format!("{} could not be processed", name) }
+            } else {
+                Reminder { title: "Document ready".to_string(), body: format!("{} has finished processing", name) }
+            })
+        }
+        _ if failed == 0 => Some(Reminder {
+            title: "Documents ready".to_string(),
+            body: format!("{} documents have finished processing", completed),
+        }),
+        _ => Some(Reminder {
+            title: "Processing finished".to_string(),
+            body: format!("{} documents finished processing, {} failed", completed, failed),
+        }),
+    }
+}
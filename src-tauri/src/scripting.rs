@@ -0,0 +1,263 @@
+//! User automation scripts. Enabled Rhai scripts run when their event fires (a document is
+//! imported, a review session is completed) with the event details in an `event` map and a
+//! small API over the library:
+//!
+//! - `get_document(id)` -> map, or `()` if there is no such document
+//! - `create_note(title, content)` / `create_note(title, content, tags)` -> new document id
+//! - `tag_document(id, tag)`
+//! - `ask_ai(prompt)` -> reply text, using the model from the scripting settings
+//! - `log(text)` (and `print`) -> kept in the run's output
+//!
+//! Scripts can't reach the file system, the network or modules, and each run has an operation
+//! budget so a runaway loop can't hang the app.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Instant;
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope};
+use tokio::runtime::Handle;
+
+use crate::ai::{AIProvider, ChatCompletionRequest, ChatMessage};
+use crate::database::{
+    AutomationScript, BulkDocumentChanges, CreateDocumentRequest, Document, ScriptEvent,
+    ScriptRunResult, ScriptingSettings,
+};
+use crate::services::{DatabaseState, Services, VectorServiceState};
+
+pub const SCRIPTING_SETTINGS_KEY: &str = "scripting";
+
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 1_000_000;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+const AI_MAX_TOKENS: u32 = 1024;
+
+/// Run the enabled scripts for `event` in the background. A failing script is logged and its
+/// error recorded on it; it never fails the action that fired the event.
+pub fn dispatch(database: DatabaseState, vectors: VectorServiceState, event: ScriptEvent, payload: serde_json::Value) {
+    tokio::spawn(async move {
+        let (settings, scripts) = {
+            let db_guard = database.lock().await;
+            let Some(db) = db_guard.as_ref() else {
+                return;
+            };
+            let settings: ScriptingSettings = match db.get_typed_setting(SCRIPTING_SETTINGS_KEY).await {
+                Ok(settings) => settings,
+                Err(e) => {
+                    eprintln!("⚠️ Failed to load scripting settings: {}", e);
+                    return;
+                }
+            };
+            if !settings.enabled {
+                return;
+            }
+            match db.get_enabled_scripts_for_event(event).await {
+                Ok(scripts) => (settings, scripts),
+                Err(e) => {
+                    eprintln!("⚠️ Failed to load {} scripts: {}", event.as_str(), e);
+                    return;
+                }
+            }
+        };
+        if scripts.is_empty() {
+            return;
+        }
+
+        let services = Services::new(database.clone(), vectors);
+        for script in scripts {
+            let result = run_script(&services, &script, &payload, &settings).await;
+            match &result.error {
+                Some(error) => eprintln!("❌ Script '{}' failed on {}: {}", script.name, event.as_str(), error),
+                None => println!("📜 Ran script '{}' on {} in {}ms", script.name, event.as_str(), result.duration_ms),
+            }
+
+            let db_guard = database.lock().await;
+            if let Some(db) = db_guard.as_ref() {
+                if let Err(e) = db.record_automation_script_run(&script.id, result.error.as_deref()).await {
+                    eprintln!("⚠️ Failed to record run of script {}: {}", script.id, e);
+                }
+            }
+        }
+    });
+}
+
+/// Fire `document_imported` for a stored document
+pub fn document_imported(database: DatabaseState, vectors: VectorServiceState, document: &Document) {
+    dispatch(database, vectors, ScriptEvent::DocumentImported, document_imported_payload(document));
+}
+
+pub fn document_imported_payload(document: &Document) -> serde_json::Value {
+    serde_json::json!({
+        "document_id": document.id,
+        "title": document.title,
+        "doc_type": document.doc_type,
+        "tags": document.tags,
+        "category_id": document.category_id,
+        "source_url": document.source_url,
+        "language": document.language,
+    })
+}
+
+/// Check a script for syntax errors without running it
+pub fn compile_check(source: &str) -> Result<(), String> {
+    Engine::new_raw().compile(source)
+        .map(|_| ())
+        .map_err(|e| format!("Script error: {}", e))
+}
+
+/// Run one script against an event payload
+pub async fn run_script(
+    services: &Services,
+    script: &AutomationScript,
+    payload: &serde_json::Value,
+    settings: &ScriptingSettings,
+) -> ScriptRunResult {
+    let started = Instant::now();
+    let output = Arc::new(StdMutex::new(Vec::new()));
+
+    let event = match rhai::serde::to_dynamic(payload) {
+        Ok(event) => event,
+        Err(e) => return finish(script, started, &output, Some(format!("Invalid event payload: {}", e))),
+    };
+
+    // Rhai is synchronous, so the script runs on a blocking thread and API calls block on the runtime
+    let engine = build_engine(services.clone(), settings.clone(), Handle::current(), output.clone());
+    let source = script.source.clone();
+    let outcome = tokio::task::spawn_blocking(move || {
+        let mut scope = Scope::new();
+        scope.push_constant("event", event);
+        engine.run_with_scope(&mut scope, &source)
+    }).await;
+
+    let error = match outcome {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(e) => Some(format!("Script task failed: {}", e)),
+    };
+    finish(script, started, &output, error)
+}
+
+fn finish(script: &AutomationScript, started: Instant, output: &StdMutex<Vec<String>>, error: Option<String>) -> ScriptRunResult {
+    ScriptRunResult {
+        script_id: script.id.clone(),
+        success: error.is_none(),
+        output: output.lock().map(|lines| lines.clone()).unwrap_or_default(),
+        error,
+        duration_ms: started.elapsed().as_millis() as i64,
+    }
+}
+
+fn build_engine(services: Services, settings: ScriptingSettings, runtime: Handle, output: Arc<StdMutex<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(settings.max_operations);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+
+    let print_output = output.clone();
+    engine.on_print(move |text| push_line(&print_output, text));
+    let debug_output = output.clone();
+    engine.on_debug(move |text, _, _| push_line(&debug_output, text));
+    let log_output = output;
+    engine.register_fn("log", move |text: &str| push_line(&log_output, text));
+
+    let (svc, rt) = (services.clone(), runtime.clone());
+    engine.register_fn("get_document", move |id: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+        let document = rt.block_on(svc.documents.get_document(id))?;
+        Ok(document.map(document_to_map).map(Dynamic::from_map).unwrap_or(Dynamic::UNIT))
+    });
+
+    let (svc, rt) = (services.clone(), runtime.clone());
+    engine.register_fn("create_note", move |title: &str, content: &str| -> Result<String, Box<EvalAltResult>> {
+        create_note(&svc, &rt, title, content, Vec::new())
+    });
+    let (svc, rt) = (services.clone(), runtime.clone());
+    engine.register_fn("create_note", move |title: &str, content: &str, tags: Array| -> Result<String, Box<EvalAltResult>> {
+        let tags = tags.into_iter().map(|tag| tag.to_string()).collect();
+        create_note(&svc, &rt, title, content, tags)
+    });
+
+    let (svc, rt) = (services.clone(), runtime.clone());
+    engine.register_fn("tag_document", move |id: &str, tag: &str| -> Result<(), Box<EvalAltResult>> {
+        let changes = BulkDocumentChanges { add_tags: vec![tag.trim().to_string()], ..Default::default() };
+        let result = rt.block_on(svc.documents.bulk_update_documents(vec![id.to_string()], &changes))?;
+        if result.updated.is_empty() {
+            return Err(format!("Document not found: {}", id).into());
+        }
+        Ok(())
+    });
+
+    let ai_calls = AtomicU32::new(0);
+    engine.register_fn("ask_ai", move |prompt: &str| -> Result<String, Box<EvalAltResult>> {
+        if ai_calls.fetch_add(1, Ordering::SeqCst) >= settings.max_ai_calls {
+            return Err(format!("A script can call ask_ai() at most {} times per run", settings.max_ai_calls).into());
+        }
+        let model = settings.model.clone().ok_or("No model configured for scripts")?;
+        let provider = AIProvider {
+            id: settings.provider_id.clone().unwrap_or_default(),
+            r#type: settings.provider_type.clone().unwrap_or_else(|| "openai".to_string()),
+            base_url: settings.base_url.clone().unwrap_or_default(),
+            api_key: None,
+            organization: None,
+            api_version: None,
+            extra_headers: Default::default(),
+        };
+        let request = ChatCompletionRequest {
+            messages: vec![ChatMessage { role: "user".to_string(), content: prompt.to_string() }],
+            model: model.clone(),
+            temperature: None,
+            max_tokens: Some(AI_MAX_TOKENS),
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stream: Some(false),
+        };
+
+        let response = runtime.block_on(services.ai.chat_completion(provider, model, request, None))?;
+        Ok(response.choices.first().map(|choice| choice.message.content.clone()).unwrap_or_default())
+    });
+
+    engine
+}
+
+fn push_line(output: &StdMutex<Vec<String>>, text: &str) {
+    println!("📜 {}", text);
+    if let Ok(mut lines) = output.lock() {
+        lines.push(text.to_string());
+    }
+}
+
+fn create_note(services: &Services, runtime: &Handle, title: &str, content: &str, tags: Vec<String>) -> Result<String, Box<EvalAltResult>> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("A note needs a title".into());
+    }
+    let document = runtime.block_on(services.documents.create_document(CreateDocumentRequest {
+        title: title.to_string(),
+        content: content.to_string(),
+        content_hash: None,
+        file_path: None,
+        doc_type: "note".to_string(),
+        tags,
+        status: None,
+        category_id: None,
+    }))?;
+    Ok(document.id)
+}
+
+fn document_to_map(document: Document) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), document.id.into());
+    map.insert("title".into(), document.title.into());
+    map.insert("content".into(), document.content.into());
+    map.insert("doc_type".into(), document.doc_type.into());
+    map.insert("status".into(), document.status.into());
+    map.insert("tags".into(), document.tags.into_iter().map(Dynamic::from).collect::<Array>().into());
+    map.insert("category_id".into(), document.category_id.map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map.insert("source_url".into(), document.source_url.map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map.insert("language".into(), document.language.map(Dynamic::from).unwrap_or(Dynamic::UNIT));
+    map
+}
@@ -0,0 +1,389 @@
+use crate::ai::*;
+use crate::ai::profiles::apply_ai_profile;
+use crate::database::{Database, AIProfile, CreateAIProfileRequest};
+use crate::redaction::Redactor;
+use super::settings::load_redaction_settings;
+use super::{DatabaseState, DATABASE_NOT_INITIALIZED};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ContextExcerpt {
+    pub label: String, // Shown to the model as the block's source, e.g. the document title
+    pub text: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct PreparedContext {
+    pub context: String, // Wrapped blocks, ready to append to a system prompt
+    pub instructions: String, // Tells the model to treat the blocks as data
+}
+
+/// A chat request resolved against saved settings and ready to send: provider connection
+/// settings and API key loaded, the profile applied, history pruned to the context window and,
+/// for providers not on this machine, personal identifiers redacted
+pub struct PreparedChat {
+    pub provider: AIProvider,
+    pub model: String,
+    pub request: ChatCompletionRequest,
+    pub api_key: Option<String>,
+    pub warning: Option<String>, // Set when history was dropped to fit the context window
+}
+
+/// Providers, chat completions, guardrails and AI profiles
+#[derive(Clone)]
+pub struct AiService {
+    database: DatabaseState,
+}
+
+impl AiService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self { database }
+    }
+
+    pub async fn test_connection(&self, mut provider: AIProvider) -> Result<bool, String> {
+        let api_key = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            load_provider_settings(database, &mut provider).await?;
+            database.get_api_key(&provider.id).await
+                .map_err(|e| format!("Failed to get API key: {}", e))?
+        };
+
+        chat_provider(&provider.r#type)?.test_connection(&provider, api_key).await
+    }
+
+    pub async fn prepare_chat(
+        &self,
+        mut provider: AIProvider,
+        mut model: String,
+        mut request: ChatCompletionRequest,
+        profile_id: Option<&str>,
+    ) -> Result<PreparedChat, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let api_key = database.get_api_key(&provider.id).await
+            .map_err(|e| format!("Failed to get API key: {}", e))?;
+        load_provider_settings(database, &mut provider).await?;
+        if let Some(profile) = load_ai_profile(database, profile_id).await? {
+            apply_ai_profile(&profile, &mut model, &mut request);
+        }
+        let warning = fit_request_to_context(database, &provider, &model, &mut request).await?;
+        redact_chat_request(database, &provider, &mut request).await?;
+
+        Ok(PreparedChat { provider, model, request, api_key, warning })
+    }
+
+    /// Non-streamed completion, with the output filter applied when it is turned on
+    pub async fn chat_completion(
+        &self,
+        provider: AIProvider,
+        model: String,
+        request: ChatCompletionRequest,
+        profile_id: Option<&str>,
+    ) -> Result<ChatCompletionResponse, String> {
+        let prepared = self.prepare_chat(provider, model, request, profile_id).await?;
+        let guardrail_settings = self.get_guardrail_settings().await?;
+
+        let mut response = chat_completion_for_provider(&prepared.provider, &prepared.model, &prepared.request, prepared.api_key).await?;
+        response.warning = prepared.warning;
+        if guardrail_settings.filter_output {
+            let system_prompt = prepared.request.messages.iter().find(|m| m.role == "system").map(|m| m.content.as_str());
+            for choice in response.choices.iter_mut() {
+                let report = guardrails::filter_output(&choice.message.content, system_prompt);
+                if !report.flags.is_empty() {
+                    println!("🛡️ Output filter flagged reply from {}: {:?}", prepared.model, report.flags);
+                }
+                choice.message.content = report.content;
+            }
+        }
+        Ok(response)
+    }
+
+    pub async fn get_models(&self, mut provider: AIProvider) -> Result<Vec<AIModel>, String> {
+        let api_key = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            load_provider_settings(database, &mut provider).await?;
+            database.get_api_key(&provider.id).await
+                .map_err(|e| format!("Failed to get API key: {}", e))?
+        };
+
+        let mut models = chat_provider(&provider.r#type)?.list_models(&provider, api_key).await?;
+
+        self.apply_model_capabilities(&provider, &mut models).await;
+        Ok(models)
+    }
+
+    /// Replace guessed context windows and output limits with models.dev data, refreshing the
+    /// cached catalog first when it is missing or stale. Lookup failures leave models untouched.
+    async fn apply_model_capabilities(&self, provider: &AIProvider, models: &mut [AIModel]) {
+        let stale = {
+            let db_state = self.database.lock().await;
+            let Some(database) = db_state.as_ref() else { return };
+            match database.get_model_capabilities_updated_at().await {
+                Ok(Some(updated_at)) => chrono::Utc::now() - updated_at > chrono::Duration::hours(models_dev::CATALOG_MAX_AGE_HOURS),
+                Ok(None) => true,
+                Err(_) => false,
+            }
+        };
+
+        // Fetch outside the lock; the request can take a while
+        let catalog = if stale && !crate::network::is_offline() {
+            match models_dev::fetch_catalog().await {
+                Ok(catalog) => Some(models_dev::parse_catalog(&catalog)),
+                Err(e) => {
+                    eprintln!("⚠️ Could not refresh models.dev catalog: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let db_state = self.database.lock().await;
+        let Some(database) = db_state.as_ref() else { return };
+        if let Some(capabilities) = catalog {
+            if let Err(e) = database.replace_model_capabilities(&capabilities).await {
+                eprintln!("⚠️ Failed to cache models.dev capabilities: {}", e);
+            }
+        }
+
+        for model in models.iter_mut() {
+            for id in models_dev::lookup_ids(&model.id) {
+                if let Ok(Some(capability)) = database.get_model_capability(&id, Some(&provider.r#type)).await {
+                    models_dev::apply_capability(model, &capability);
+                    break;
+                }
+            }
+        }
+    }
+
+    pub async fn get_provider_settings(&self, provider_id: &str) -> Result<ProviderConnectionSettings, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_typed_setting(&AIProvider::settings_key(provider_id)).await
+            .map_err(|e| format!("Failed to get provider settings: {}", e))
+    }
+
+    pub async fn update_provider_settings(&self, provider_id: &str, settings: ProviderConnectionSettings) -> Result<ProviderConnectionSettings, String> {
+        if let Some(name) = settings.extra_headers.keys().find(|name| reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err()) {
+            return Err(format!("Invalid header name: {}", name));
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.set_typed_setting(&AIProvider::settings_key(provider_id), &settings).await
+            .map_err(|e| format!("Failed to save provider settings: {}", e))?;
+
+        Ok(settings)
+    }
+
+    pub async fn get_guardrail_settings(&self) -> Result<guardrails::GuardrailSettings, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        load_guardrail_settings(database).await
+    }
+
+    pub async fn update_guardrail_settings(&self, settings: guardrails::GuardrailSettings) -> Result<guardrails::GuardrailSettings, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.set_typed_setting(guardrails::GUARDRAIL_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to save guardrail settings: {}", e))?;
+
+        Ok(settings)
+    }
+
+    /// Sanitize and wrap retrieved excerpts for a RAG prompt
+    pub async fn prepare_rag_context(&self, excerpts: &[ContextExcerpt]) -> Result<PreparedContext, String> {
+        let settings = self.get_guardrail_settings().await?;
+
+        let context = excerpts.iter()
+            .map(|excerpt| guardrails::prepare_context_block(&settings, &excerpt.label, &excerpt.text))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        Ok(PreparedContext {
+            context,
+            instructions: guardrails::CONTEXT_GUARD_INSTRUCTIONS.to_string(),
+        })
+    }
+
+    // AI profiles
+
+    pub async fn create_profile(&self, request: CreateAIProfileRequest) -> Result<AIProfile, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.create_ai_profile(request).await
+            .map_err(|e| format!("Failed to create AI profile: {}", e))
+    }
+
+    pub async fn get_profiles(&self) -> Result<Vec<AIProfile>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_ai_profiles().await
+            .map_err(|e| format!("Failed to get AI profiles: {}", e))
+    }
+
+    pub async fn get_profile(&self, id: &str) -> Result<Option<AIProfile>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_ai_profile(id).await
+            .map_err(|e| format!("Failed to get AI profile: {}", e))
+    }
+
+    pub async fn update_profile(&self, id: &str, request: CreateAIProfileRequest) -> Result<Option<AIProfile>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.update_ai_profile(id, request).await
+            .map_err(|e| format!("Failed to update AI profile: {}", e))
+    }
+
+    pub async fn delete_profile(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.delete_ai_profile(id).await
+            .map_err(|e| format!("Failed to delete AI profile: {}", e))
+    }
+
+    /// Profiles as portable JSON (no IDs or timestamps) for sharing
+    pub async fn export_profiles(&self) -> Result<serde_json::Value, String> {
+        let profiles = self.get_profiles().await?;
+
+        let exported: Vec<CreateAIProfileRequest> = profiles.into_iter()
+            .map(|p| CreateAIProfileRequest {
+                name: p.name,
+                description: p.description,
+                system_prompt: p.system_prompt,
+                default_model: p.default_model,
+                temperature: p.temperature,
+                metadata: p.metadata,
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "version": 1,
+            "profiles": exported,
+        }))
+    }
+
+    /// Import profiles exported by `export_profiles`; profiles with a matching name are updated
+    pub async fn import_profiles(&self, data: serde_json::Value) -> Result<Vec<AIProfile>, String> {
+        let profiles: Vec<CreateAIProfileRequest> = serde_json::from_value(
+            data.get("profiles").cloned().unwrap_or(data)
+        ).map_err(|e| format!("Invalid AI profile export: {}", e))?;
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let mut imported = Vec::new();
+        for request in profiles {
+            let existing = database.get_ai_profile_by_name(&request.name).await
+                .map_err(|e| format!("Failed to look up AI profile: {}", e))?;
+
+            let profile = match existing {
+                Some(existing) => database.update_ai_profile(&existing.id, request).await
+                    .map_err(|e| format!("Failed to update AI profile: {}", e))?,
+                None => Some(database.create_ai_profile(request).await
+                    .map_err(|e| format!("Failed to create AI profile: {}", e))?),
+            };
+            imported.extend(profile);
+        }
+
+        Ok(imported)
+    }
+}
+
+/// Prune `request` to the model's context window: the one configured for the provider, else the
+/// models.dev limit when cached. Unknown windows are left alone rather than guessed, so the
+/// provider reports an overlong request itself. Returns a user-facing warning when history was dropped.
+pub(crate) async fn fit_request_to_context(
+    database: &Database,
+    provider: &AIProvider,
+    model: &str,
+    request: &mut ChatCompletionRequest,
+) -> Result<Option<String>, String> {
+    let settings: ProviderConnectionSettings = database.get_typed_setting(&AIProvider::settings_key(&provider.id)).await
+        .map_err(|e| format!("Failed to get provider settings: {}", e))?;
+
+    let mut context_window = settings.context_window.map(i64::from);
+    if context_window.is_none() {
+        for id in models_dev::lookup_ids(model) {
+            if let Ok(Some(capability)) = database.get_model_capability(&id, Some(&provider.r#type)).await {
+                context_window = capability.context_window;
+                break;
+            }
+        }
+    }
+    let Some(context_window) = context_window.map(|n| n.clamp(0, u32::MAX as i64) as u32) else {
+        return Ok(None);
+    };
+
+    let pruning = context::fit_to_context(request, context_window)?;
+    if let Some(pruning) = &pruning {
+        println!(
+            "✂️ Dropped {} messages to fit {} (context {} tokens)",
+            pruning.dropped_messages, model, context_window
+        );
+    }
+    Ok(pruning.map(|pruning| pruning.warning()))
+}
+
+/// Fill in the organization, api-version and extra headers saved for this provider
+pub(crate) async fn load_provider_settings(database: &Database, provider: &mut AIProvider) -> Result<(), String> {
+    let settings: ProviderConnectionSettings = database.get_typed_setting(&AIProvider::settings_key(&provider.id)).await
+        .map_err(|e| format!("Failed to get provider settings: {}", e))?;
+    provider.apply_connection_settings(settings);
+    Ok(())
+}
+
+/// The redactor for text bound for `provider`, or None when it runs on this machine (local
+/// models like Ollama or LM Studio see the text as it is) or AI redaction is off
+pub(crate) async fn ai_redactor(database: &Database, provider: &AIProvider) -> Result<Option<Redactor>, String> {
+    if crate::network::is_local_url(&provider.base_url) {
+        return Ok(None);
+    }
+    let settings = load_redaction_settings(database).await?;
+    if !settings.apply_to_ai {
+        return Ok(None);
+    }
+    Ok(Some(Redactor::new(&settings)?).filter(|redactor| !redactor.is_empty()))
+}
+
+/// Apply the redaction rules to every message bound for a provider that isn't on this machine
+pub(crate) async fn redact_chat_request(database: &Database, provider: &AIProvider, request: &mut ChatCompletionRequest) -> Result<(), String> {
+    let Some(redactor) = ai_redactor(database, provider).await? else {
+        return Ok(());
+    };
+    let mut redacted = 0;
+    for message in request.messages.iter_mut() {
+        redactor.redact_in_place(&mut message.content, &mut redacted);
+    }
+    if redacted > 0 {
+        println!("🔒 Redacted {} personal identifiers from the prompt for {}", redacted, provider.id);
+    }
+    Ok(())
+}
+
+pub(crate) async fn load_guardrail_settings(database: &Database) -> Result<guardrails::GuardrailSettings, String> {
+    database.get_typed_setting(guardrails::GUARDRAIL_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get guardrail settings: {}", e))
+}
+
+// Resolve an optional profile ID, treating an unknown ID as an error so typos don't silently drop the persona
+pub(crate) async fn load_ai_profile(database: &Database, profile_id: Option<&str>) -> Result<Option<AIProfile>, String> {
+    match profile_id {
+        Some(id) => database.get_ai_profile(id).await
+            .map_err(|e| format!("Failed to get AI profile: {}", e))?
+            .map(Some)
+            .ok_or_else(|| format!("AI profile not found: {}", id)),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,1419 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::ai::AIProvider;
+use crate::ai::vision::{complete_with_image_for_provider, ImageInput};
+use crate::code;
+use crate::commands::pdf::{
+    delete_pdf_file, describe_pdf_error, generate_pdf_filename, get_pdf_storage_dir, process_document_embeddings_internal,
+};
+use crate::database::{
+    BulkDeleteResult, BulkDocumentChanges, BulkUpdateResult, Category, CodeSnippet, CreateCategoryRequest,
+    CreateDocumentRequest, CreateProcessingJobRequest, Database, Document, DocumentContentRange, DocumentOutline, DocumentSection,
+    NoteTemplate, OutlineSectionContent, QuickSearchHit, QuickSearchResults, ReprocessDocumentResult, SaveNoteTemplateRequest, SplitDocumentRequest, SplitDocumentResult,
+};
+use crate::database::outlines::{outline_from_bookmarks, outline_from_headings};
+use crate::importers::{self, ImportFormat, ImportedContent};
+use crate::importers::handwriting::{self, HandwritingEngine};
+use crate::importers::paste::{self, PasteKind};
+use crate::media::{delete_attachment, import_attachment, mime_type_for, resolve_attachment, thumbnails, to_data_url};
+use crate::pdf_processor::{ExtractOptions, ExtractionMethod, MarkerOptions, PdfProcessor};
+use crate::scripting;
+use super::reading::completion_request;
+use super::settings::export_redactor;
+use super::{AiService, DatabaseState, VectorServiceState, DATABASE_NOT_INITIALIZED};
+
+/// Tag given to quick captures so they can be filed later
+pub const INBOX_TAG: &str = "inbox";
+const CAPTURE_TITLE_MAX_CHARS: usize = 60;
+const DEFAULT_RECENT_LIMIT: i64 = 10;
+const DEFAULT_SEARCH_LIMIT: i64 = 25;
+const DEFAULT_QUICK_SEARCH_LIMIT: i64 = 10;
+// Quick search answers from titles, tags and cached content hits within this budget; content
+// lookups that overrun it finish in the background and show up on the next keystroke
+const QUICK_SEARCH_BUDGET: Duration = Duration::from_millis(30);
+// The trigram index behind content search can't narrow down anything shorter
+const MIN_CONTENT_SEARCH_CHARS: usize = 3;
+const EXPORT_FILENAME_MAX_CHARS: usize = 80;
+// Keeps a single range request well below what the webview handles comfortably
+const MAX_CONTENT_RANGE_CHARS: i64 = 1_000_000;
+/// Document metadata field recording the cover image, see media/thumbnails.rs
+pub const COVER_METADATA_KEY: &str = "cover";
+/// Document metadata field recording the photo handwritten notes were read from, and how
+pub const HANDWRITING_METADATA_KEY: &str = "handwriting";
+// Below this, imported handwriting is flagged for proofreading
+const HANDWRITING_REVIEW_CONFIDENCE: f32 = 0.8;
+/// Document metadata field recording what kind of paste a note was made from
+pub const PASTE_METADATA_KEY: &str = "paste";
+/// Tag given to reference notes made from pasted BibTeX
+pub const REFERENCE_TAG: &str = "reference";
+/// Document metadata field recording the note template a note was created from
+pub const NOTE_TEMPLATE_METADATA_KEY: &str = "note_template_id";
+
+/// Notes read from a photo of handwriting
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HandwritingImport {
+    pub document: Document,
+    pub engine: HandwritingEngine,
+    pub confidence: f32, // 0-1
+    pub needs_review: bool, // Confidence is low enough that the text should be proofread
+}
+
+/// Documents created from one paste
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SmartPasteResult {
+    pub kind: PasteKind,
+    pub documents: Vec<Document>, // One per BibTeX entry; a single document otherwise
+}
+
+/// Documents, categories and sections
+#[derive(Clone)]
+pub struct DocumentService {
+    database: DatabaseState,
+    vectors: VectorServiceState,
+}
+
+impl DocumentService {
+    pub fn new(database: DatabaseState, vectors: VectorServiceState) -> Self {
+        Self { database, vectors }
+    }
+
+    pub async fn create_document(&self, request: CreateDocumentRequest) -> Result<Document, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.create_document(request).await
+            .map_err(|e| format!("Failed to create document: {}", e))
+    }
+
+    pub async fn get_note_templates(&self) -> Result<Vec<NoteTemplate>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_note_templates().await
+            .map_err(|e| format!("Failed to get note templates: {}", e))
+    }
+
+    pub async fn save_note_template(&self, request: SaveNoteTemplateRequest) -> Result<NoteTemplate, String> {
+        if request.name.trim().is_empty() {
+            return Err("Template name cannot be empty".to_string());
+        }
+        if request.body.trim().is_empty() {
+            return Err("Template body cannot be empty".to_string());
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.save_note_template(request).await
+            .map_err(|e| format!("Failed to save note template: {}", e))
+    }
+
+    pub async fn delete_note_template(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.delete_note_template(id).await
+            .map_err(|e| format!("Failed to delete note template: {}", e))
+    }
+
+    /// Create a note from a template. date, time, course (the category's name) and
+    /// lecture_number (one more than the notes made from this template in the category) are
+    /// filled in unless `variables` sets them; placeholders nothing fills are left empty.
+    pub async fn create_note_from_template(
+        &self,
+        template_id: &str,
+        variables: HashMap<String, String>,
+        category_id: Option<String>,
+    ) -> Result<Document, String> {
+        let document = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let template = database.get_note_template(template_id).await
+                .map_err(|e| format!("Failed to get note template: {}", e))?
+                .ok_or_else(|| format!("Note template not found: {}", template_id))?;
+            let course = match &category_id {
+                Some(id) => database.get_category(id).await
+                    .map_err(|e| format!("Failed to get category: {}", e))?
+                    .ok_or_else(|| format!("Category not found: {}", id))?
+                    .name,
+                None => String::new(),
+            };
+            let previous_notes = database.count_notes_from_template(template_id, category_id.as_deref()).await
+                .map_err(|e| format!("Failed to count notes: {}", e))?;
+
+            let now = chrono::Local::now();
+            let mut values = HashMap::from([
+                ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+                ("time".to_string(), now.format("%H:%M").to_string()),
+                ("course".to_string(), course),
+                ("lecture_number".to_string(), (previous_notes + 1).to_string()),
+            ]);
+            values.extend(variables.into_iter().map(|(name, value)| (name.trim().to_string(), value)));
+
+            let (title, content) = render_note_template(&template, &values);
+            let document = database.create_document(CreateDocumentRequest {
+                title: if title.is_empty() { template.name.clone() } else { title },
+                content,
+                content_hash: None,
+                file_path: None,
+                doc_type: "note".to_string(),
+                tags: Vec::new(),
+                status: None,
+                category_id,
+            }).await
+                .map_err(|e| format!("Failed to create note: {}", e))?;
+
+            database.set_document_metadata_field(&document.id, NOTE_TEMPLATE_METADATA_KEY, serde_json::json!(template.id)).await
+                .map_err(|e| format!("Failed to save note template: {}", e))?
+                .unwrap_or(document)
+        };
+
+        if let Some(vector_service) = self.vectors.lock().await.as_mut() {
+            if let Err(e) = process_document_embeddings_internal(vector_service, &document).await {
+                eprintln!("⚠️ Failed to embed note {}: {}", document.id, e);
+            }
+        }
+
+        println!("📝 Created note {} from template {}", document.id, template_id);
+        Ok(document)
+    }
+
+    /// Save a short note tagged "inbox". With a provider and model, an auto-tagging job is
+    /// queued to add topic tags.
+    pub async fn quick_capture(
+        &self,
+        text: &str,
+        source_url: Option<String>,
+        provider: Option<AIProvider>,
+        model: Option<String>,
+    ) -> Result<Document, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("Nothing to capture".to_string());
+        }
+        let source_url = source_url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty());
+
+        // First line, shortened, as the title
+        let first_line = text.lines().next().unwrap_or(text).trim();
+        let mut title: String = first_line.chars().take(CAPTURE_TITLE_MAX_CHARS).collect();
+        if first_line.chars().count() > CAPTURE_TITLE_MAX_CHARS {
+            title.push('…');
+        }
+        let content = match &source_url {
+            Some(url) => format!("{}\n\nSource: {}", text, url),
+            None => text.to_string(),
+        };
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let mut document = database.create_document(CreateDocumentRequest {
+            title,
+            content,
+            content_hash: None,
+            file_path: None,
+            doc_type: "note".to_string(),
+            tags: vec![INBOX_TAG.to_string()],
+            status: None,
+            category_id: None,
+        }).await
+            .map_err(|e| format!("Failed to save capture: {}", e))?;
+
+        // Kept in metadata rather than source_url, which marks a document as an import of that page
+        if let Some(url) = &source_url {
+            if let Some(updated) = database.set_document_metadata_field(&document.id, "capture_source_url", serde_json::json!(url)).await
+                .map_err(|e| format!("Failed to save capture source: {}", e))?
+            {
+                document = updated;
+            }
+        }
+
+        if let (Some(mut provider), Some(model)) = (provider, model) {
+            // The key is looked up by provider id when the job runs; don't store it in the job
+            provider.api_key = None;
+            let request = CreateProcessingJobRequest {
+                job_type: "auto_tagging".to_string(),
+                source_type: "document".to_string(),
+                source_path: None,
+                original_filename: document.title.clone(),
+                title: Some(format!("Tag: {}", document.title)),
+                tags: document.tags.clone(),
+                category_id: None,
+                processing_options: Some(serde_json::json!({
+                    "provider": provider,
+                    "model": model,
+                })),
+                metadata: Some(serde_json::json!({
+                    "document_id": document.id,
+                })),
+                source_url: None,
+            };
+            if let Err(e) = database.create_processing_job(request).await {
+                eprintln!("⚠️ Failed to queue auto-tagging for capture {}: {}", document.id, e);
+            }
+        }
+
+        println!("📥 Captured note {}", document.id);
+        Ok(document)
+    }
+
+    /// Turn pasted text that isn't a URL into documents: BibTeX becomes one reference note per
+    /// entry, delimited text a markdown table, code a fenced snippet, and anything else a quick
+    /// capture in the inbox. URLs need the download path of the URL import and are handled by
+    /// the `smart_paste` command. Embeddings are left to the caller.
+    pub async fn paste_content(
+        &self,
+        kind: PasteKind,
+        text: &str,
+        title: Option<String>,
+        tags: Vec<String>,
+        category_id: Option<String>,
+    ) -> Result<Vec<Document>, String> {
+        let text = text.trim();
+        let title = title.map(|title| title.trim().to_string()).filter(|title| !title.is_empty());
+
+        // (title, content, extra tags, extra metadata)
+        let notes: Vec<(String, String, Vec<String>, Option<serde_json::Value>)> = match kind {
+            PasteKind::Url => return Err("Pasted URLs are imported with smart_paste".to_string()),
+            PasteKind::Text => return Ok(vec![self.quick_capture(text, None, None, None).await?]),
+            PasteKind::Bibtex => {
+                let entries = paste::parse_bibtex(text);
+                if entries.is_empty() {
+                    return Err("No BibTeX entries found".to_string());
+                }
+                let single = entries.len() == 1;
+                entries.into_iter()
+                    .map(|entry| {
+                        let note_title = title.clone().filter(|_| single).unwrap_or_else(|| entry.title());
+                        let metadata = serde_json::to_value(&entry).ok();
+                        (note_title, entry.to_markdown(), vec![REFERENCE_TAG.to_string(), entry.entry_type.clone()], metadata)
+                    })
+                    .collect()
+            }
+            PasteKind::Csv => {
+                let rows = paste::parse_table(text).ok_or("Pasted text is not a table")?;
+                let note_title = title.unwrap_or_else(|| format!("Pasted table ({} rows)", rows.len().saturating_sub(1)));
+                vec![(note_title, paste::table_to_markdown(&rows), Vec::new(), None)]
+            }
+            PasteKind::Code => {
+                let (content, language) = paste::code_to_markdown(text);
+                let first_line = text.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+                let mut note_title = title.unwrap_or_else(|| {
+                    let snippet: String = first_line.chars().take(CAPTURE_TITLE_MAX_CHARS).collect();
+                    match &language {
+                        Some(language) => format!("{} snippet: {}", language, snippet),
+                        None => format!("Snippet: {}", snippet),
+                    }
+                });
+                if note_title.trim().is_empty() {
+                    note_title = "Pasted code".to_string();
+                }
+                vec![(note_title, content, language.into_iter().collect(), None)]
+            }
+        };
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let mut documents = Vec::new();
+        for (note_title, content, extra_tags, details) in notes {
+            let mut note_tags = tags.clone();
+            for tag in extra_tags {
+                if !note_tags.contains(&tag) {
+                    note_tags.push(tag);
+                }
+            }
+
+            let document = database.create_document(CreateDocumentRequest {
+                title: note_title,
+                content,
+                content_hash: None,
+                file_path: None,
+                doc_type: "note".to_string(),
+                tags: note_tags,
+                status: Some("ready".to_string()),
+                category_id: category_id.clone(),
+            }).await
+                .map_err(|e| format!("Failed to save pasted {}: {}", kind.as_str(), e))?;
+
+            let mut paste_metadata = serde_json::json!({ "kind": kind });
+            if let Some(details) = details {
+                paste_metadata[kind.as_str()] = details;
+            }
+            let document = database.set_document_metadata_field(&document.id, PASTE_METADATA_KEY, paste_metadata).await
+                .map_err(|e| format!("Failed to record paste details: {}", e))?
+                .unwrap_or(document);
+            documents.push(document);
+        }
+
+        println!("📋 Pasted {} as {} document(s)", kind.as_str(), documents.len());
+        Ok(documents)
+    }
+
+    /// Every document, leaving out archived categories' unless `include_archived`
+    pub async fn get_all_documents(&self, include_archived: bool) -> Result<Vec<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let documents = database.get_all_documents().await
+            .map_err(|e| format!("Failed to get documents: {}", e))?;
+        if include_archived {
+            return Ok(documents);
+        }
+        without_archived(database, documents).await
+    }
+
+    pub async fn get_document(&self, id: &str) -> Result<Option<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document(id).await
+            .map_err(|e| format!("Failed to get document: {}", e))
+    }
+
+    /// The document with an empty `content`; `content_length` says how much there is to load
+    /// with `get_document_content_range`
+    pub async fn get_document_without_content(&self, id: &str) -> Result<Option<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document_without_content(id).await
+            .map_err(|e| format!("Failed to get document: {}", e))
+    }
+
+    /// Up to `length` characters of a document's content starting at char `offset`
+    pub async fn get_document_content_range(&self, id: &str, offset: i64, length: i64) -> Result<DocumentContentRange, String> {
+        if offset < 0 || length < 0 {
+            return Err("Offset and length must not be negative".to_string());
+        }
+        let length = length.min(MAX_CONTENT_RANGE_CHARS);
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document_content_range(id, offset, length).await
+            .map_err(|e| format!("Failed to get document content: {}", e))?
+            .ok_or_else(|| format!("Document not found: {}", id))
+    }
+
+    pub async fn update_document(&self, id: &str, request: CreateDocumentRequest) -> Result<Option<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let previous_status = database.get_document(id).await
+            .map_err(|e| format!("Failed to get document: {}", e))?
+            .map(|doc| doc.status);
+
+        let updated = database.update_document(id, request).await
+            .map_err(|e| format!("Failed to update document: {}", e))?;
+
+        if let Some(document) = &updated {
+            queue_practice_if_completed(database, document, previous_status.as_deref()).await;
+        }
+
+        Ok(updated)
+    }
+
+    /// Delete a document along with its stored PDF and embeddings
+    pub async fn delete_document(&self, id: &str) -> Result<bool, String> {
+        let (document, deleted) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            // First, get the document to check if it has a PDF file to clean up
+            let document = database.get_document(id).await
+                .map_err(|e| format!("Failed to get document for deletion: {}", e))?;
+
+            let deleted = database.delete_document(id).await
+                .map_err(|e| format!("Failed to delete document: {}", e))?;
+
+            (document, deleted)
+        };
+
+        if deleted {
+            if let Some(doc) = document {
+                if let Some(cover) = cover_file(&doc) {
+                    if let Err(e) = thumbnails::delete_cover(&cover) {
+                        eprintln!("⚠️ Failed to delete cover of document {}: {}", id, e);
+                    }
+                }
+                if let Some(image_file) = handwriting_image_file(&doc) {
+                    if let Err(e) = delete_attachment(&image_file) {
+                        eprintln!("⚠️ Failed to delete handwriting image of document {}: {}", id, e);
+                    }
+                }
+                if doc.doc_type == "pdf" {
+                    if let Some(file_path) = doc.file_path {
+                        // Attempt to delete the PDF file, but don't fail the entire operation if this fails
+                        match delete_pdf_file(file_path).await {
+                            Ok(_) => println!("DEBUG: Successfully cleaned up PDF file for document {}", id),
+                            Err(e) => println!("DEBUG: Failed to clean up PDF file for document {}: {}", id, e),
+                        }
+                    }
+                }
+            }
+
+            // Chunks of deleted documents would otherwise keep turning up in search results;
+            // anything missed here is swept by the orphaned_embeddings maintenance task
+            let mut vector_guard = self.vectors.lock().await;
+            if let Some(vector_service) = vector_guard.as_mut() {
+                if let Err(e) = vector_service.delete_document(id) {
+                    eprintln!("⚠️ Failed to delete embeddings for document {}: {}", id, e);
+                }
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    /// Move, retag, or change the status of many documents at once
+    pub async fn bulk_update_documents(&self, ids: Vec<String>, changes: &BulkDocumentChanges) -> Result<BulkUpdateResult, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let results = database.bulk_update_documents(&ids, changes).await
+            .map_err(|e| format!("Failed to update documents: {}", e))?;
+
+        for (document, previous_status) in &results {
+            queue_practice_if_completed(database, document, Some(previous_status)).await;
+        }
+
+        let updated: Vec<Document> = results.into_iter().map(|(document, _)| document).collect();
+        let missing_ids = ids.into_iter()
+            .filter(|id| !updated.iter().any(|d| &d.id == id))
+            .collect();
+
+        println!("📚 Bulk updated {} documents", updated.len());
+        Ok(BulkUpdateResult { updated, missing_ids })
+    }
+
+    /// Delete many documents at once, then remove their PDF files and embeddings
+    pub async fn bulk_delete_documents(&self, ids: Vec<String>) -> Result<BulkDeleteResult, String> {
+        let deleted = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            database.bulk_delete_documents(&ids).await
+                .map_err(|e| format!("Failed to delete documents: {}", e))?
+        };
+
+        let mut files_removed = 0;
+        for document in &deleted {
+            if document.doc_type != "pdf" {
+                continue;
+            }
+            if let Some(file_path) = document.file_path.clone() {
+                match delete_pdf_file(file_path).await {
+                    Ok(true) => files_removed += 1,
+                    Ok(false) => {}
+                    Err(e) => eprintln!("⚠️ Failed to clean up PDF file for document {}: {}", document.id, e),
+                }
+            }
+        }
+
+        let mut embeddings_removed = 0;
+        let mut vector_guard = self.vectors.lock().await;
+        if let Some(vector_service) = vector_guard.as_mut() {
+            for document in &deleted {
+                match vector_service.delete_document(&document.id) {
+                    Ok(_) => embeddings_removed += 1,
+                    Err(e) => eprintln!("⚠️ Failed to delete embeddings for document {}: {}", document.id, e),
+                }
+            }
+        }
+
+        let deleted_ids: Vec<String> = deleted.into_iter().map(|d| d.id).collect();
+        let missing_ids = ids.into_iter().filter(|id| !deleted_ids.contains(id)).collect();
+
+        println!("🗑️ Bulk deleted {} documents ({} PDF files removed)", deleted_ids.len(), files_removed);
+        Ok(BulkDeleteResult { deleted_ids, missing_ids, files_removed, embeddings_removed })
+    }
+
+    /// Title, content and tag search; a blank query finds nothing. `math` also matches the
+    /// query against documents' normalized math, regardless of notation.
+    pub async fn search_documents(&self, query: &str, limit: Option<i64>, math: bool) -> Result<Vec<Document>, String> {
+        if query.trim().is_empty() {
+            return Ok(vec![]);
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.search_documents(query, limit.unwrap_or(DEFAULT_SEARCH_LIMIT), math).await
+            .map_err(|e| format!("Failed to search documents: {}", e))
+    }
+
+    /// Command-palette search as the user types: documents whose title, a word of the title or
+    /// a tag starts with `prefix`, plus content matches once they've been looked up. Results are
+    /// `complete` when nothing is still being searched.
+    pub async fn quick_search(&self, prefix: &str, limit: Option<i64>) -> Result<QuickSearchResults, String> {
+        let started = Instant::now();
+        let query = prefix.trim().to_string();
+        let limit = limit.unwrap_or(DEFAULT_QUICK_SEARCH_LIMIT).clamp(1, DEFAULT_SEARCH_LIMIT);
+        if query.is_empty() {
+            return Ok(QuickSearchResults { query, hits: Vec::new(), complete: true, elapsed_ms: 0 });
+        }
+
+        let (mut hits, content_hits) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            let hits = database.quick_search_titles_and_tags(&query, limit).await
+                .map_err(|e| format!("Failed to search documents: {}", e))?;
+            (hits, database.cached_content_matches(&query))
+        };
+
+        let wants_content = query.chars().count() >= MIN_CONTENT_SEARCH_CHARS && (hits.len() as i64) < limit;
+        let complete = match content_hits {
+            Some(content_hits) => {
+                merge_quick_search_hits(&mut hits, content_hits, limit);
+                true
+            }
+            None if wants_content => {
+                // Whatever is left of the budget goes to the index lookup; past it, the lookup
+                // finishes in the background and fills the cache for the next call
+                let service = self.clone();
+                let background_query = query.clone();
+                let scan = tokio::spawn(async move {
+                    let db_state = service.database.lock().await;
+                    match db_state.as_ref() {
+                        Some(database) => database.search_content_matches(&background_query, limit).await
+                            .map_err(|e| format!("Failed to search document content: {}", e)),
+                        None => Err(DATABASE_NOT_INITIALIZED.to_string()),
+                    }
+                });
+                match tokio::time::timeout(QUICK_SEARCH_BUDGET.saturating_sub(started.elapsed()), scan).await {
+                    Ok(Ok(Ok(content_hits))) => {
+                        merge_quick_search_hits(&mut hits, content_hits, limit);
+                        true
+                    }
+                    Ok(Ok(Err(e))) => return Err(e),
+                    Ok(Err(e)) => return Err(format!("Content search failed: {}", e)),
+                    Err(_) => false,
+                }
+            }
+            None => true,
+        };
+
+        Ok(QuickSearchResults {
+            query,
+            hits,
+            complete,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Code blocks containing `query`, optionally in one language ("py" and "python" are the
+    /// same); a blank query lists the snippets in that language
+    pub async fn search_code_snippets(&self, query: &str, language: Option<&str>, limit: Option<i64>) -> Result<Vec<CodeSnippet>, String> {
+        let language = language.and_then(code::normalize_language);
+        if query.trim().is_empty() && language.is_none() {
+            return Ok(vec![]);
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.search_code_snippets(query, language.as_deref(), limit.unwrap_or(DEFAULT_SEARCH_LIMIT)).await
+            .map_err(|e| format!("Failed to search code snippets: {}", e))
+    }
+
+    pub async fn get_document_code_snippets(&self, document_id: &str) -> Result<Vec<CodeSnippet>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document_code_snippets(document_id).await
+            .map_err(|e| format!("Failed to get code snippets: {}", e))
+    }
+
+    /// Import any supported file (PDF, HTML, image, markdown/text, Office documents, .eml, .tex)
+    /// as a document. The original is kept in storage, the converted markdown becomes the content
+    /// and the converter used is recorded under the "import" key of its metadata. Embeddings are
+    /// left to the caller (see `embed_document`).
+    pub async fn import_file(
+        &self,
+        source_path: &Path,
+        title: Option<String>,
+        tags: Vec<String>,
+        category_id: Option<String>,
+    ) -> Result<Document, String> {
+        let format = ImportFormat::from_path(source_path)
+            .ok_or_else(|| format!("Unsupported file type: {}", source_path.display()))?;
+        let original_filename = source_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("document")
+            .to_string();
+
+        println!("📥 Importing {} as {}", source_path.display(), format.as_str());
+        let imported = importers::convert_file(source_path).await?;
+        let stored_filename = store_original(source_path, &original_filename)?;
+
+        let default_title = source_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Untitled Document")
+            .to_string();
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let document = save_imported_document(
+            database,
+            &imported,
+            title.or(imported.title.clone()).unwrap_or(default_title),
+            tags,
+            category_id,
+            Some(stored_filename),
+            &original_filename,
+        ).await?;
+
+        scripting::document_imported(self.database.clone(), self.vectors.clone(), &document);
+        self.generate_cover_in_background(document.id.clone());
+        Ok(document)
+    }
+
+    /// Import a photo of handwritten notes as an editable markdown note, read by a vision model
+    /// (`provider` and `model` required) or by local TrOCR. The photo is kept as an attachment
+    /// recorded under the "handwriting" metadata key, with the engine and confidence. Embeddings
+    /// are left to the caller.
+    pub async fn import_handwritten_notes(
+        &self,
+        source_path: &Path,
+        engine: HandwritingEngine,
+        provider: Option<AIProvider>,
+        model: Option<String>,
+        title: Option<String>,
+        tags: Vec<String>,
+        category_id: Option<String>,
+    ) -> Result<HandwritingImport, String> {
+        if ImportFormat::from_path(source_path) != Some(ImportFormat::Image) {
+            return Err(format!("Not an image: {}", source_path.display()));
+        }
+        if !source_path.exists() {
+            return Err(format!("File not found: {}", source_path.display()));
+        }
+
+        println!("✍️ Reading handwriting in {} with {}", source_path.display(), engine.as_str());
+        let (transcription, model_used) = match engine {
+            HandwritingEngine::Vision => {
+                let provider = provider.ok_or("A vision-capable provider is required to read handwriting")?;
+                let model = model.ok_or("A vision-capable model is required to read handwriting")?;
+                let path = source_path.to_path_buf();
+                let (data, mime_type) = tokio::task::spawn_blocking(move || handwriting::prepare_image(&path)).await
+                    .map_err(|e| format!("Image task failed: {}", e))??;
+
+                let request = completion_request(&model, handwriting::build_handwriting_messages(), 4096);
+                let prepared = AiService::new(self.database.clone()).prepare_chat(provider, model, request, None).await?;
+                let response = complete_with_image_for_provider(
+                    &prepared.provider, &prepared.model, &prepared.request, &ImageInput { mime_type, data }, prepared.api_key,
+                ).await?;
+                (handwriting::parse_handwriting_response(&response)?, Some(prepared.model))
+            }
+            // TrOCR's checkpoint is chosen by the local script
+            HandwritingEngine::Trocr => (handwriting::transcribe_locally(source_path).await?, None),
+        };
+
+        let original_filename = source_path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("notes")
+            .to_string();
+        let image_file = import_attachment(&source_path.to_string_lossy())?;
+        let default_title = source_path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Handwritten notes")
+            .to_string();
+        let imported = ImportedContent {
+            format: ImportFormat::Image,
+            title: transcription.title.clone(),
+            content: transcription.markdown.clone(),
+            method: engine.as_str().to_string(),
+            tags: Vec::new(),
+            details: None,
+        };
+
+        let saved = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let document = save_imported_document(
+                database,
+                &imported,
+                title.or(imported.title.clone()).unwrap_or(default_title),
+                tags,
+                category_id,
+                None,
+                &original_filename,
+            ).await;
+            match document {
+                Ok(document) => database.set_document_metadata_field(&document.id, HANDWRITING_METADATA_KEY, serde_json::json!({
+                    "image": image_file,
+                    "engine": engine,
+                    "model": model_used,
+                    "confidence": transcription.confidence,
+                })).await
+                    .map_err(|e| format!("Failed to record handwriting details: {}", e))
+                    .map(|updated| updated.unwrap_or(document)),
+                Err(e) => Err(e),
+            }
+        };
+        let document = match saved {
+            Ok(document) => document,
+            Err(e) => {
+                let _ = delete_attachment(&image_file);
+                return Err(e);
+            }
+        };
+
+        scripting::document_imported(self.database.clone(), self.vectors.clone(), &document);
+        println!("✍️ Imported handwritten notes {} (confidence {:.2})", document.id, transcription.confidence);
+        Ok(HandwritingImport {
+            document,
+            engine,
+            confidence: transcription.confidence,
+            needs_review: transcription.confidence < HANDWRITING_REVIEW_CONFIDENCE,
+        })
+    }
+
+    /// The photo handwritten notes were read from, as a data URL; None for other documents
+    pub async fn get_handwriting_image(&self, document_id: &str) -> Result<Option<String>, String> {
+        let document = self.get_document(document_id).await?
+            .ok_or_else(|| format!("Document not found: {}", document_id))?;
+        let Some(image_file) = handwriting_image_file(&document) else {
+            return Ok(None);
+        };
+
+        let path = resolve_attachment(&image_file)?;
+        let bytes = tokio::fs::read(&path).await
+            .map_err(|e| format!("Failed to read handwriting image: {}", e))?;
+        Ok(Some(to_data_url(&bytes, mime_type_for(&image_file))))
+    }
+
+    /// Chunk and embed a document with the loaded vector service. Returns false, without
+    /// embedding anything, when no vector service is loaded.
+    pub async fn embed_document(&self, document: &Document) -> Result<bool, String> {
+        let mut vector_guard = self.vectors.lock().await;
+        match vector_guard.as_mut() {
+            Some(vector_service) => {
+                process_document_embeddings_internal(vector_service, document).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Re-run extraction on a document's stored PDF, optionally forcing a single method, then
+    /// replace its content (re-anchoring highlights) and rebuild its embeddings
+    pub async fn reprocess_document(
+        &self,
+        document_id: &str,
+        method: Option<ExtractionMethod>,
+        options: Option<MarkerOptions>,
+    ) -> Result<ReprocessDocumentResult, String> {
+        let (document, extract_options) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let document = database.get_document(document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or("Document not found")?;
+            // Only PDFs keep their original file in PDF storage to extract from again
+            if document.doc_type != "pdf" {
+                return Err(format!("Only PDF documents can be reprocessed (this one is {})", document.doc_type));
+            }
+
+            let mut marker_options = options.unwrap_or_default().with_llm_key(database).await;
+            if marker_options.ocr_language.is_none() {
+                marker_options.ocr_language = document.language.clone();
+            }
+            let mut extract_options = ExtractOptions::from_marker_options(&marker_options, ExtractOptions::default().timeout_seconds);
+            if let Some(method) = method {
+                extract_options.preferred_methods = vec![method];
+            }
+
+            (document, extract_options)
+        };
+
+        let file_name = document.file_path.clone()
+            .ok_or("Document has no stored file to reprocess")?;
+        let source_path = get_pdf_storage_dir()?.join(&file_name);
+        if !source_path.exists() {
+            return Err(format!("Stored file not found: {}", file_name));
+        }
+
+        println!("🔄 Reprocessing document {} with {:?}", document_id, extract_options.preferred_methods);
+        let processor = PdfProcessor::new();
+        let extraction = processor.extract_with_options(&source_path.to_string_lossy(), &extract_options).await
+            .map_err(|e| {
+                eprintln!("❌ Reprocessing error: {:?}", e);
+                describe_pdf_error(e)
+            })?;
+
+        let (document, replacement) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let (_, replacement) = database.replace_document_content(document_id, &extraction.content).await
+                .map_err(|e| format!("Failed to update document content: {}", e))?
+                .ok_or("Document not found")?;
+            let document = database.set_document_metadata_field(document_id, "extraction", extraction.to_metadata()).await
+                .map_err(|e| format!("Failed to record extraction method: {}", e))?
+                .ok_or("Document not found")?;
+
+            (document, replacement)
+        };
+
+        // Old chunks point at the previous content, so drop them before re-embedding
+        {
+            let mut vector_guard = self.vectors.lock().await;
+            if let Some(vector_service) = vector_guard.as_mut() {
+                if let Err(e) = vector_service.delete_document(document_id) {
+                    eprintln!("⚠️ Failed to delete old embeddings for {}: {}", document_id, e);
+                }
+            }
+        }
+        let embeddings_updated = match self.embed_document(&document).await {
+            Ok(updated) => updated,
+            Err(e) => {
+                eprintln!("⚠️ Re-embedding failed for {}: {}", document_id, e);
+                false
+            }
+        };
+
+        println!(
+            "✅ Reprocessed document {} with {} (quality {:.2}, {} highlights re-anchored, {} orphaned)",
+            document_id, extraction.method.as_str(), extraction.quality.score,
+            replacement.highlights_reanchored, replacement.highlights_orphaned
+        );
+
+        Ok(ReprocessDocumentResult {
+            document,
+            method: extraction.method.as_str().to_string(),
+            quality_score: extraction.quality.score,
+            highlights_reanchored: replacement.highlights_reanchored,
+            highlights_orphaned: replacement.highlights_orphaned,
+            embeddings_updated,
+        })
+    }
+
+    /// Write documents (all, or one category's) into `dir` as markdown files with a small
+    /// front matter block, returning the paths written. Content goes through the export
+    /// redaction rules.
+    pub async fn export_markdown(&self, dir: &Path, category_id: Option<&str>) -> Result<Vec<PathBuf>, String> {
+        let mut documents = match category_id {
+            Some(category_id) => self.get_documents_by_category(category_id).await?,
+            None => self.get_all_documents(true).await?,
+        };
+        let redactor = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            export_redactor(database).await?
+        };
+        if let Some(redactor) = &redactor {
+            let mut redacted = 0;
+            for document in documents.iter_mut() {
+                redactor.redact_in_place(&mut document.content, &mut redacted);
+            }
+            if redacted > 0 {
+                println!("🔒 Redacted {} personal identifiers from the export", redacted);
+            }
+        }
+
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+        let mut written = Vec::new();
+        for document in &documents {
+            let path = dir.join(export_filename(document));
+            std::fs::write(&path, export_markdown(document))
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            written.push(path);
+        }
+
+        println!("📤 Exported {} documents to {}", written.len(), dir.display());
+        Ok(written)
+    }
+
+    // Sections
+
+    pub async fn split_document(&self, document_id: &str, request: &SplitDocumentRequest) -> Result<SplitDocumentResult, String> {
+        if request.split_points.is_none() && !request.by_headings {
+            return Err("Provide split_points or set by_headings".to_string());
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document(document_id).await
+            .map_err(|e| format!("Failed to get document: {}", e))?
+            .ok_or("Document not found")?;
+
+        let result = database.split_document(document_id, request).await
+            .map_err(|e| format!("Failed to split document: {}", e))?
+            .ok_or("Nothing to split: the document would produce fewer than two sections")?;
+
+        println!(
+            "✂️ Split document {} into {} sections ({} flashcards, {} highlights moved)",
+            document_id, result.children.len(), result.flashcards_moved, result.highlights_moved
+        );
+        Ok(result)
+    }
+
+    pub async fn get_document_sections(&self, document_id: &str) -> Result<Vec<DocumentSection>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document_sections(document_id).await
+            .map_err(|e| format!("Failed to get document sections: {}", e))
+    }
+
+    /// The document's table of contents: its PDF bookmarks when the stored PDF has any,
+    /// otherwise its markdown headings. Rebuilt whenever the content has changed.
+    pub async fn get_document_outline(&self, document_id: &str) -> Result<DocumentOutline, String> {
+        let (document, stored) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let document = database.get_document(document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or_else(|| format!("Document not found: {}", document_id))?;
+            let stored = database.get_document_outline(document_id).await
+                .map_err(|e| format!("Failed to get document outline: {}", e))?;
+            (document, stored)
+        };
+
+        let content_hash = document.content_hash.clone()
+            .unwrap_or_else(|| Database::calculate_content_hash(&document.content));
+        if let Some(stored) = stored.filter(|stored| stored.content_hash == content_hash) {
+            return Ok(stored);
+        }
+
+        let bookmarks = self.pdf_bookmarks(&document);
+        let (source, entries) = if bookmarks.is_empty() {
+            ("headings", outline_from_headings(&document.content))
+        } else {
+            ("bookmarks", outline_from_bookmarks(&document.content, &bookmarks))
+        };
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        let outline = database.save_document_outline(document_id, source, &content_hash, entries).await
+            .map_err(|e| format!("Failed to save document outline: {}", e))?;
+        println!("📑 Built outline for document {} from {} ({} entries)", document_id, source, outline.entries.len());
+        Ok(outline)
+    }
+
+    /// Text of one outline entry's section, subsections included, for section-scoped chat
+    /// and search
+    pub async fn get_document_outline_section(&self, document_id: &str, entry_index: i32) -> Result<OutlineSectionContent, String> {
+        let outline = self.get_document_outline(document_id).await?;
+        let entry = outline.entries.into_iter()
+            .find(|entry| entry.index == entry_index)
+            .ok_or_else(|| format!("Outline entry not found: {}", entry_index))?;
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        let document = database.get_document(document_id).await
+            .map_err(|e| format!("Failed to get document: {}", e))?
+            .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+        let content = document.content.chars()
+            .skip(entry.start_offset as usize)
+            .take((entry.end_offset - entry.start_offset).max(0) as usize)
+            .collect();
+        Ok(OutlineSectionContent { entry, content })
+    }
+
+    // Bookmarks of the document's stored PDF as (level, title, page); empty when there is
+    // no PDF or it has no outline
+    fn pdf_bookmarks(&self, document: &Document) -> Vec<(usize, String, Option<usize>)> {
+        let Some(file_name) = document.file_path.as_ref().filter(|name| name.to_lowercase().ends_with(".pdf")) else {
+            return Vec::new();
+        };
+        let Ok(storage_dir) = get_pdf_storage_dir() else {
+            return Vec::new();
+        };
+        let path = storage_dir.join(file_name);
+        if !path.exists() {
+            return Vec::new();
+        }
+
+        match PdfProcessor::new().extract_bookmarks(&path.to_string_lossy()) {
+            Ok(bookmarks) => bookmarks.into_iter()
+                .map(|bookmark| (bookmark.level, bookmark.title, bookmark.page))
+                .collect(),
+            Err(e) => {
+                eprintln!("⚠️ Failed to read PDF bookmarks of {}: {}", document.id, e);
+                Vec::new()
+            }
+        }
+    }
+
+    // Covers & thumbnails
+
+    /// Make the document's cover from its stored PDF's first page, or from the preview image
+    /// or icon of the page it was imported from, replacing any previous cover. Returns the
+    /// cover's attachment file name, None when there is nothing to make one from.
+    pub async fn generate_document_cover(&self, document_id: &str) -> Result<Option<String>, String> {
+        let document = self.get_document(document_id).await?
+            .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+        let pdf_path = document.file_path.as_ref()
+            .filter(|name| name.to_lowercase().ends_with(".pdf"))
+            .and_then(|name| get_pdf_storage_dir().ok().map(|dir| dir.join(name)))
+            .filter(|path| path.exists());
+        let cover = match (pdf_path, &document.source_url) {
+            (Some(pdf_path), _) => thumbnails::render_pdf_cover(&pdf_path).await
+                .map(|bytes| Some((bytes, thumbnails::CoverSource::PdfPage))),
+            (None, Some(source_url)) => match thumbnails::fetch_page_html(source_url).await {
+                Ok(html) => thumbnails::fetch_web_cover(source_url, &html).await,
+                Err(e) => Err(e),
+            },
+            (None, None) => Ok(None),
+        };
+        // A missing pdftoppm or an unreachable page means no cover, not a failed import
+        let cover = cover.unwrap_or_else(|e| {
+            eprintln!("⚠️ No cover for document {}: {}", document_id, e);
+            None
+        });
+
+        let stored_file = match &cover {
+            Some((bytes, _)) => Some(thumbnails::store_cover(bytes)?),
+            None => None,
+        };
+        if let Some(previous) = cover_file(&document) {
+            let _ = thumbnails::delete_cover(&previous);
+        }
+
+        // Recorded even without a cover so thumbnails don't retry on every request
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.set_document_metadata_field(document_id, COVER_METADATA_KEY, serde_json::json!({
+            "file": stored_file,
+            "source": cover.as_ref().map(|(_, source)| source.as_str()),
+            "created_at": chrono::Utc::now().to_rfc3339(),
+        })).await
+            .map_err(|e| format!("Failed to record document cover: {}", e))?;
+
+        if let Some((_, source)) = &cover {
+            println!("🖼️ Made cover for document {} from {}", document_id, source.as_str());
+        }
+        Ok(stored_file)
+    }
+
+    /// Make the cover of a newly imported document without holding up the import
+    pub fn generate_cover_in_background(&self, document_id: String) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service.generate_document_cover(&document_id).await {
+                eprintln!("⚠️ Failed to make cover for document {}: {}", document_id, e);
+            }
+        });
+    }
+
+    /// Cover thumbnail that fits in `size` x `size` pixels as a data URL, None for documents
+    /// without a cover. Covers of documents imported before covers existed are made on first use.
+    pub async fn get_document_thumbnail(&self, document_id: &str, size: Option<u32>) -> Result<Option<String>, String> {
+        let document = self.get_document(document_id).await?
+            .ok_or_else(|| format!("Document not found: {}", document_id))?;
+
+        let attempted = document.metadata.as_ref().and_then(|metadata| metadata.get(COVER_METADATA_KEY)).is_some();
+        let cover = match cover_file(&document) {
+            Some(file) => file,
+            None if !attempted => match self.generate_document_cover(document_id).await? {
+                Some(file) => file,
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+
+        let size = size.unwrap_or(thumbnails::DEFAULT_THUMBNAIL_SIZE);
+        let bytes = tokio::task::spawn_blocking(move || thumbnails::cover_thumbnail(&cover, size)).await
+            .map_err(|e| format!("Thumbnail task failed: {}", e))??;
+        Ok(Some(to_data_url(&bytes, "image/png")))
+    }
+
+    pub async fn get_child_documents(&self, document_id: &str) -> Result<Vec<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_child_documents(document_id).await
+            .map_err(|e| format!("Failed to get child documents: {}", e))
+    }
+
+    // Recent & pinned
+
+    pub async fn touch_document(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.touch_document(id).await
+            .map_err(|e| format!("Failed to record document open: {}", e))
+    }
+
+    pub async fn get_recent_documents(&self, limit: Option<i64>) -> Result<Vec<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let documents = database.get_recent_documents(limit.unwrap_or(DEFAULT_RECENT_LIMIT)).await
+            .map_err(|e| format!("Failed to get recent documents: {}", e))?;
+        without_archived(database, documents).await
+    }
+
+    pub async fn pin_document(&self, id: &str, pinned: bool) -> Result<Option<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.set_document_pinned(id, pinned).await
+            .map_err(|e| format!("Failed to update document pin: {}", e))
+    }
+
+    pub async fn list_pinned_documents(&self) -> Result<Vec<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_pinned_documents().await
+            .map_err(|e| format!("Failed to get pinned documents: {}", e))
+    }
+
+    // Categories
+
+    pub async fn create_category(&self, request: CreateCategoryRequest) -> Result<Category, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.create_category(request).await
+            .map_err(|e| format!("Failed to create category: {}", e))
+    }
+
+    pub async fn get_all_categories(&self) -> Result<Vec<Category>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_all_categories().await
+            .map_err(|e| format!("Failed to get categories: {}", e))
+    }
+
+    pub async fn get_category(&self, id: &str) -> Result<Option<Category>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_category(id).await
+            .map_err(|e| format!("Failed to get category: {}", e))
+    }
+
+    pub async fn update_category(&self, id: &str, request: CreateCategoryRequest) -> Result<Option<Category>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.update_category(id, request).await
+            .map_err(|e| format!("Failed to update category: {}", e))
+    }
+
+    /// Freeze a finished course: the category and its subcategories drop out of default
+    /// document and deck listings, due counts and search, but stay browsable by category
+    pub async fn archive_category(&self, id: &str) -> Result<Option<Category>, String> {
+        self.set_category_archived(id, true).await
+    }
+
+    pub async fn unarchive_category(&self, id: &str) -> Result<Option<Category>, String> {
+        self.set_category_archived(id, false).await
+    }
+
+    async fn set_category_archived(&self, id: &str, archived: bool) -> Result<Option<Category>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let category = database.set_category_archived(id, archived).await
+            .map_err(|e| format!("Failed to update category: {}", e))?;
+        if category.is_some() {
+            println!("🗄️ {} category {}", if archived { "Archived" } else { "Unarchived" }, id);
+        }
+        Ok(category)
+    }
+
+    pub async fn delete_category(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.delete_category(id).await
+            .map_err(|e| format!("Failed to delete category: {}", e))
+    }
+
+    pub async fn get_documents_by_category(&self, category_id: &str) -> Result<Vec<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_documents_by_category(category_id).await
+            .map_err(|e| format!("Failed to get documents by category: {}", e))
+    }
+
+    pub async fn get_uncategorized_documents(&self) -> Result<Vec<Document>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_uncategorized_documents().await
+            .map_err(|e| format!("Failed to get uncategorized documents: {}", e))
+    }
+}
+
+/// (title, body) with every `{{name}}` replaced by its value, or by nothing when unset
+fn render_note_template(template: &NoteTemplate, values: &HashMap<String, String>) -> (String, String) {
+    let title = fill_placeholders(&template.title_template, values);
+    (title.split_whitespace().collect::<Vec<_>>().join(" "), fill_placeholders(&template.body, values))
+}
+
+fn fill_placeholders(text: &str, values: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let name = rest[start + 2..start + 2 + end].trim();
+        if let Some(value) = values.get(name) {
+            result.push_str(value);
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    result.push_str(rest);
+    result
+}
+
+async fn without_archived(database: &Database, mut documents: Vec<Document>) -> Result<Vec<Document>, String> {
+    let archived = database.get_archived_category_ids().await
+        .map_err(|e| format!("Failed to get archived categories: {}", e))?;
+    if !archived.is_empty() {
+        documents.retain(|document| document.category_id.as_ref().map_or(true, |id| !archived.contains(id)));
+    }
+    Ok(documents)
+}
+
+/// Finishing a document kicks off "now test yourself" practice if its category opts in
+async fn queue_practice_if_completed(database: &Database, document: &Document, previous_status: Option<&str>) {
+    if document.status != "completed" || previous_status == Some("completed") {
+        return;
+    }
+    match database.enqueue_practice_generation(document).await {
+        Ok(Some(job)) => println!("📝 Queued practice generation job {} for document {}", job.id, document.id),
+        Ok(None) => {}
+        Err(e) => eprintln!("⚠️ Failed to queue practice generation: {}", e),
+    }
+}
+
+// Helper function to copy an imported file into storage, returning the stored filename
+pub(crate) fn store_original(source_path: &Path, original_filename: &str) -> Result<String, String> {
+    let stored_filename = generate_pdf_filename(original_filename);
+    let stored_path = get_pdf_storage_dir()?.join(&stored_filename);
+    crate::storage::ensure_space_for_copy(source_path, &stored_path)?;
+    std::fs::copy(source_path, &stored_path)
+        .map_err(|e| format!("Failed to copy file to storage: {}", e))?;
+    Ok(stored_filename)
+}
+
+// Helper function to save converted content as a ready document, recording how it was imported
+pub(crate) async fn save_imported_document(
+    database: &Database,
+    imported: &ImportedContent,
+    title: String,
+    mut tags: Vec<String>,
+    category_id: Option<String>,
+    stored_filename: Option<String>,
+    original_filename: &str,
+) -> Result<Document, String> {
+    for tag in &imported.tags {
+        if !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    let request = CreateDocumentRequest {
+        title,
+        content: imported.content.clone(),
+        content_hash: None,
+        file_path: stored_filename,
+        doc_type: if imported.format == ImportFormat::Pdf { "pdf".to_string() } else { "markdown".to_string() },
+        tags,
+        status: Some("ready".to_string()),
+        category_id,
+    };
+
+    let document = database.create_document(request).await
+        .map_err(|e| format!("Failed to save document: {}", e))?;
+
+    Ok(database.set_document_metadata_field(&document.id, "import", serde_json::json!({
+        "format": imported.format,
+        "method": imported.method,
+        "original_filename": original_filename,
+        "details": imported.details,
+        "imported_at": chrono::Utc::now().to_rfc3339(),
+    })).await
+        .map_err(|e| format!("Failed to record import details: {}", e))?
+        .unwrap_or(document))
+}
+
+// Title made safe for file names, plus the start of the id so equal titles don't collide
+fn export_filename(document: &Document) -> String {
+    let title: String = document.title.chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' })
+        .take(EXPORT_FILENAME_MAX_CHARS)
+        .collect();
+    let title = title.trim();
+    let short_id: String = document.id.chars().take(8).collect();
+    if title.is_empty() {
+        format!("{}.md", short_id)
+    } else {
+        format!("{} ({}).md", title, short_id)
+    }
+}
+
+fn export_markdown(document: &Document) -> String {
+    let mut front_matter = vec![
+        format!("id: {}", document.id),
+        format!("title: {}", serde_json::json!(document.title)),
+        format!("type: {}", document.doc_type),
+        format!("status: {}", document.status),
+        format!("tags: {}", serde_json::json!(document.tags)),
+        format!("created_at: {}", document.created_at.to_rfc3339()),
+        format!("updated_at: {}", document.updated_at.to_rfc3339()),
+    ];
+    if let Some(category_id) = &document.category_id {
+        front_matter.push(format!("category_id: {}", category_id));
+    }
+    if let Some(source_url) = &document.source_url {
+        front_matter.push(format!("source_url: {}", source_url));
+    }
+
+    format!("---\n{}\n---\n\n{}\n", front_matter.join("\n"), document.content.trim_end())
+}
+
+// Attachment file name of the document's cover, if it has one
+fn handwriting_image_file(document: &Document) -> Option<String> {
+    document.metadata.as_ref()?
+        .get(HANDWRITING_METADATA_KEY)?
+        .get("image")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn cover_file(document: &Document) -> Option<String> {
+    document.metadata.as_ref()?
+        .get(COVER_METADATA_KEY)?
+        .get("file")?
+        .as_str()
+        .map(str::to_string)
+}
+
+// Append content hits for documents that didn't already match by title or tag
+fn merge_quick_search_hits(hits: &mut Vec<QuickSearchHit>, content_hits: Vec<QuickSearchHit>, limit: i64) {
+    for hit in content_hits {
+        if hits.len() as i64 >= limit {
+            break;
+        }
+        if !hits.iter().any(|existing| existing.document_id == hit.document_id) {
+            hits.push(hit);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::ai::AIProvider;
+use crate::ai::vision::{complete_with_image_for_provider, ImageInput};
+use crate::media::equations::{self, EquationEngine, LatexConversion};
+use super::reading::completion_request;
+use super::{AiService, DatabaseState};
+
+// Long derivations in an aligned environment run to a few hundred tokens
+const MAX_LATEX_TOKENS: u32 = 1024;
+
+/// Converting screenshots of equations to LaTeX for notes and flashcards
+#[derive(Clone)]
+pub struct EquationService {
+    ai: AiService,
+}
+
+impl EquationService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self { ai: AiService::new(database) }
+    }
+
+    /// LaTeX of the equation in `image` (a data URL or base64). The vision engine needs
+    /// `provider` and a vision-capable `model`; pix2tex runs locally.
+    pub async fn convert_image_to_latex(
+        &self,
+        image: &str,
+        engine: EquationEngine,
+        provider: Option<AIProvider>,
+        model: Option<String>,
+    ) -> Result<LatexConversion, String> {
+        let image = image.to_string();
+        let png = tokio::task::spawn_blocking(move || equations::prepare_equation_image(&image)).await
+            .map_err(|e| format!("Image task failed: {}", e))??;
+
+        let latex = match engine {
+            EquationEngine::Vision => {
+                let provider = provider.ok_or("A vision-capable provider is required to read equations")?;
+                let model = model.ok_or("A vision-capable model is required to read equations")?;
+                let mut request = completion_request(&model, equations::build_latex_messages(), MAX_LATEX_TOKENS);
+                request.temperature = Some(0.0);
+
+                let prepared = self.ai.prepare_chat(provider, model, request, None).await?;
+                let response = complete_with_image_for_provider(
+                    &prepared.provider,
+                    &prepared.model,
+                    &prepared.request,
+                    &ImageInput { mime_type: "image/png".to_string(), data: png },
+                    prepared.api_key,
+                ).await?;
+                equations::clean_latex(&response)?
+            }
+            EquationEngine::Pix2tex => equations::latex_locally(&png).await?,
+        };
+
+        println!("🧮 Converted equation with {} ({} chars of LaTeX)", engine.as_str(), latex.len());
+        Ok(LatexConversion::new(latex, engine))
+    }
+}
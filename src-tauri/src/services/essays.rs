@@ -0,0 +1,184 @@
+use serde::Serialize;
+
+use crate::ai::essays::{
+    build_essay_assessment_messages, build_essay_merge_messages, parse_essay_assessment_response, score_criteria,
+    split_essay, MAX_ESSAY_CHUNKS, MAX_ESSAY_CHUNK_CHARS,
+};
+use crate::ai::{AIProvider, ChatCompletionRequest};
+use crate::database::{CreateEssayFeedbackRequest, EssayFeedback, Rubric, SaveRubricRequest};
+use super::reading::completion_request;
+use super::{AiService, DatabaseState, DATABASE_NOT_INITIALIZED};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EssayGradeResult {
+    pub feedback: EssayFeedback,
+    pub previous: Option<EssayFeedback>, // The last feedback on the same document with the same rubric
+    pub score_change: Option<f64>, // Against `previous`
+    pub chunks: usize,
+    pub truncated: bool, // The essay had more than MAX_ESSAY_CHUNKS chunks; the rest was not graded
+}
+
+/// Rubric feedback on essays, kept per document so revisions can be compared
+#[derive(Clone)]
+pub struct EssayService {
+    database: DatabaseState,
+    ai: AiService,
+}
+
+impl EssayService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self {
+            ai: AiService::new(database.clone()),
+            database,
+        }
+    }
+
+    /// Grade `text`, or the content of `document_id`, against a rubric. Feedback on a document
+    /// is stored with it; feedback on loose text is stored without one.
+    pub async fn grade_essay(
+        &self,
+        provider: AIProvider,
+        model: String,
+        text: Option<String>,
+        document_id: Option<String>,
+        rubric_id: &str,
+    ) -> Result<EssayGradeResult, String> {
+        let (title, essay, rubric, previous) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let (title, essay) = match (text, &document_id) {
+                (Some(_), Some(_)) => return Err("Give either the essay text or a document, not both".to_string()),
+                (Some(text), None) => ("Untitled essay".to_string(), text),
+                (None, Some(document_id)) => {
+                    let document = database.get_document(document_id).await
+                        .map_err(|e| format!("Failed to get document: {}", e))?
+                        .ok_or_else(|| format!("Document not found: {}", document_id))?;
+                    (document.title, document.content)
+                }
+                (None, None) => return Err("Give the essay text or a document to grade".to_string()),
+            };
+
+            let rubric = database.get_rubric(rubric_id).await
+                .map_err(|e| format!("Failed to get rubric: {}", e))?
+                .ok_or_else(|| format!("Rubric not found: {}", rubric_id))?;
+
+            let previous = match &document_id {
+                Some(document_id) => database.get_essay_feedback(document_id).await
+                    .map_err(|e| format!("Failed to get essay feedback: {}", e))?
+                    .into_iter()
+                    .rev()
+                    .find(|feedback| feedback.rubric_id.as_deref() == Some(rubric_id)),
+                None => None,
+            };
+
+            (title, essay, rubric, previous)
+        };
+
+        if rubric.criteria.is_empty() || rubric.criteria.iter().any(|criterion| criterion.max_points <= 0.0) {
+            return Err("The rubric needs at least one criterion, each worth more than 0 points".to_string());
+        }
+        let mut chunks = split_essay(&essay, MAX_ESSAY_CHUNK_CHARS);
+        if chunks.is_empty() {
+            return Err("The essay is empty".to_string());
+        }
+        let truncated = chunks.len() > MAX_ESSAY_CHUNKS;
+        chunks.truncate(MAX_ESSAY_CHUNKS);
+
+        let mut assessments = Vec::with_capacity(chunks.len());
+        for (index, chunk) in chunks.iter().enumerate() {
+            let messages = build_essay_assessment_messages(&title, &rubric.criteria, chunk, index, chunks.len());
+            let response = self.complete(provider.clone(), model.clone(), completion_request(&model, messages, 2000)).await
+                .map_err(|e| format!("Failed to grade part {} of the essay: {}", index + 1, e))?;
+            assessments.push(parse_essay_assessment_response(&response)?);
+        }
+        let assessment = if assessments.len() == 1 {
+            assessments.remove(0)
+        } else {
+            let messages = build_essay_merge_messages(&title, &rubric.criteria, &assessments);
+            let response = self.complete(provider, model.clone(), completion_request(&model, messages, 2000)).await?;
+            parse_essay_assessment_response(&response)?
+        };
+
+        let graded_text = if truncated { chunks.join("\n\n") } else { essay };
+        let feedback = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            database.create_essay_feedback(CreateEssayFeedbackRequest {
+                document_id,
+                rubric_id: Some(rubric.id.clone()),
+                rubric_name: rubric.name.clone(),
+                essay_text: graded_text,
+                criteria: score_criteria(&rubric.criteria, &assessment),
+                summary: assessment.summary.trim().to_string(),
+                strengths: assessment.strengths,
+                improvements: assessment.improvements,
+                model: Some(model),
+            }).await
+                .map_err(|e| format!("Failed to save essay feedback: {}", e))?
+        };
+
+        println!("📝 Graded essay \"{}\" with rubric \"{}\": {}/{}", title, rubric.name, feedback.total_score, feedback.max_score);
+        Ok(EssayGradeResult {
+            score_change: previous.as_ref().map(|previous| feedback.total_score - previous.total_score),
+            previous,
+            feedback,
+            chunks: chunks.len(),
+            truncated,
+        })
+    }
+
+    /// Feedback on a document across its revisions, oldest first
+    pub async fn get_essay_feedback(&self, document_id: &str) -> Result<Vec<EssayFeedback>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_essay_feedback(document_id).await
+            .map_err(|e| format!("Failed to get essay feedback: {}", e))
+    }
+
+    pub async fn delete_essay_feedback(&self, feedback_id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.delete_essay_feedback(feedback_id).await
+            .map_err(|e| format!("Failed to delete essay feedback: {}", e))
+    }
+
+    pub async fn get_rubrics(&self) -> Result<Vec<Rubric>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_rubrics().await
+            .map_err(|e| format!("Failed to get rubrics: {}", e))
+    }
+
+    pub async fn save_rubric(&self, request: SaveRubricRequest) -> Result<Rubric, String> {
+        if request.name.trim().is_empty() {
+            return Err("Rubric name cannot be empty".to_string());
+        }
+        if request.criteria.is_empty() {
+            return Err("A rubric needs at least one criterion".to_string());
+        }
+        if let Some(criterion) = request.criteria.iter().find(|criterion| criterion.name.trim().is_empty() || criterion.max_points <= 0.0) {
+            return Err(format!("Every criterion needs a name and more than 0 points (\"{}\" doesn't)", criterion.name));
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.save_rubric(request).await
+            .map_err(|e| format!("Failed to save rubric: {}", e))
+    }
+
+    pub async fn delete_rubric(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.delete_rubric(id).await
+            .map_err(|e| format!("Failed to delete rubric: {}", e))
+    }
+
+    async fn complete(&self, provider: AIProvider, model: String, request: ChatCompletionRequest) -> Result<String, String> {
+        let response = self.ai.chat_completion(provider, model, request, None).await?;
+        response.choices.first()
+            .map(|choice| choice.message.content.clone())
+            .filter(|content| !content.trim().is_empty())
+            .ok_or_else(|| "The model returned an empty response".to_string())
+    }
+}
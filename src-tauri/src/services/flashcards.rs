@@ -0,0 +1,820 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::ai::{AIProvider, ChatCompletionRequest, chat_completion_for_provider, text_to_speech_for_provider};
+use crate::ai::grading::{similarity_to_quality, quality_to_response, build_grading_messages, parse_grading_response};
+use crate::database::{
+    Database,
+    Flashcard, FlashcardDeck, FlashcardDeckStats, NewCardOrder, CustomReviewFilter,
+    PersistedReviewSession, ResumedReviewSession, StartReviewSessionRequest, FlashcardDetailStats, FlashcardReview, FlashcardReviewResult, FlashcardStats, FlashcardReviewSession, AdaptiveReviewSession,
+    CreateFlashcardRequest, CreateFlashcardDeckRequest, CreateFlashcardReviewRequest, TypedAnswerGrade,
+    CreateImageOcclusionRequest, OcclusionMask, ScriptEvent
+};
+use crate::media::{
+    import_attachment, store_attachment, resolve_attachment, decode_base64_payload, to_data_url,
+    delete_attachment, mime_type_for, render_occluded_image,
+    DeckPackageAuthor, DeckPackageCard, DeckPackageManifest, card_attachments, rewrite_card_attachments,
+    write_deck_package, read_deck_package, DECK_PACKAGE_FORMAT, DECK_PACKAGE_VERSION, DECK_PACKAGE_EXTENSION
+};
+use crate::scripting;
+use super::ai::{ai_redactor, load_provider_settings, redact_chat_request};
+use super::settings::export_redactor;
+use super::{DatabaseState, VectorServiceState, DATABASE_NOT_INITIALIZED};
+
+const DEFAULT_TTS_MODEL: &str = "tts-1";
+const DEFAULT_TTS_VOICE: &str = "alloy";
+
+/// Flashcards, decks, media, deck packages and reviews
+#[derive(Clone)]
+pub struct FlashcardService {
+    database: DatabaseState,
+    vectors: VectorServiceState,
+}
+
+impl FlashcardService {
+    pub fn new(database: DatabaseState, vectors: VectorServiceState) -> Self {
+        Self { database, vectors }
+    }
+
+    // Cards
+
+    pub async fn create_flashcard(&self, request: CreateFlashcardRequest) -> Result<Flashcard, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.create_flashcard(request).await
+            .map_err(|e| format!("Failed to create flashcard: {}", e))
+    }
+
+    pub async fn get_flashcard(&self, id: &str) -> Result<Option<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard(id).await
+            .map_err(|e| format!("Failed to get flashcard: {}", e))
+    }
+
+    pub async fn get_flashcards(&self, limit: Option<i32>, offset: Option<i32>) -> Result<Vec<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcards(limit, offset).await
+            .map_err(|e| format!("Failed to get flashcards: {}", e))
+    }
+
+    pub async fn get_flashcards_by_deck(&self, deck_id: &str) -> Result<Vec<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcards_by_deck(deck_id).await
+            .map_err(|e| format!("Failed to get flashcards by deck: {}", e))
+    }
+
+    pub async fn get_flashcards_by_category(&self, category_id: &str) -> Result<Vec<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcards_by_category(category_id).await
+            .map_err(|e| format!("Failed to get flashcards by category: {}", e))
+    }
+
+    pub async fn get_flashcards_by_document(&self, document_id: &str) -> Result<Vec<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcards_by_document(document_id).await
+            .map_err(|e| format!("Failed to get flashcards by document: {}", e))
+    }
+
+    pub async fn update_flashcard(&self, id: &str, request: CreateFlashcardRequest) -> Result<Option<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.update_flashcard(id, request).await
+            .map_err(|e| format!("Failed to update flashcard: {}", e))
+    }
+
+    pub async fn delete_flashcard(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.delete_flashcard(id).await
+            .map_err(|e| format!("Failed to delete flashcard: {}", e))
+    }
+
+    // Image occlusion
+
+    /// Store the source image once and create one 'image_occlusion' card per mask.
+    /// Every card shares the image and full mask list in metadata and points at its own mask.
+    pub async fn create_image_occlusion_cards(&self, request: CreateImageOcclusionRequest) -> Result<Vec<Flashcard>, String> {
+        if request.masks.is_empty() {
+            return Err("At least one occlusion mask is required".to_string());
+        }
+
+        let mode = request.mode.clone().unwrap_or_else(|| "hide_one".to_string());
+        if mode != "hide_one" && mode != "hide_all" {
+            return Err(format!("Unknown occlusion mode: {}", mode));
+        }
+
+        let attachment = match (&request.image_path, &request.image_data) {
+            (Some(path), _) => import_attachment(path)?,
+            (None, Some(data)) => {
+                let extension = request.image_extension.clone()
+                    .or_else(|| data.strip_prefix("data:image/")
+                        .and_then(|rest| rest.split(';').next())
+                        .map(|ext| if ext == "jpeg" { "jpg".to_string() } else { ext.to_string() }))
+                    .unwrap_or_else(|| "png".to_string());
+                store_attachment(&decode_base64_payload(data)?, &extension)?
+            }
+            (None, None) => return Err("Either image_path or image_data is required".to_string()),
+        };
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let group_id = uuid::Uuid::new_v4().to_string();
+        let front = request.header.clone().unwrap_or_else(|| "What is hidden?".to_string());
+        let requests: Vec<CreateFlashcardRequest> = request.masks.iter()
+            .map(|mask| CreateFlashcardRequest {
+                front: front.clone(),
+                back: mask.label.clone().unwrap_or_default(),
+                source_document_id: request.source_document_id.clone(),
+                source_text: None,
+                difficulty: None,
+                tags: request.tags.clone(),
+                category_id: request.category_id.clone(),
+                card_type: Some("image_occlusion".to_string()),
+                deck_id: request.deck_id.clone(),
+                metadata: Some(serde_json::json!({
+                    "image_occlusion": {
+                        "group_id": group_id,
+                        "attachment": attachment,
+                        "mode": mode,
+                        "mask_id": mask.id,
+                        "masks": request.masks,
+                    }
+                })),
+            })
+            .collect();
+
+        // All of a group's cards or none, so a failure can't leave masks without their cards
+        let cards = database.create_flashcards(&requests).await
+            .map_err(|e| format!("Failed to create occlusion cards: {}", e))?;
+
+        println!("🖼️ Created {} image occlusion cards from {}", cards.len(), attachment);
+        Ok(cards)
+    }
+
+    /// The card's image with its masks painted on, as PNG bytes. `reveal` uncovers the card's
+    /// own mask for the answer side.
+    pub async fn occluded_image(&self, flashcard_id: &str, reveal: bool) -> Result<Vec<u8>, String> {
+        let flashcard = self.get_flashcard(flashcard_id).await?
+            .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+        let occlusion = flashcard.metadata.as_ref()
+            .and_then(|m| m.get("image_occlusion"))
+            .ok_or("Flashcard is not an image occlusion card")?;
+
+        let attachment = occlusion.get("attachment").and_then(|v| v.as_str())
+            .ok_or("Occlusion card is missing its image")?;
+        let mask_id = occlusion.get("mask_id").and_then(|v| v.as_str()).unwrap_or_default();
+        let hide_all = occlusion.get("mode").and_then(|v| v.as_str()) == Some("hide_all");
+        let masks: Vec<OcclusionMask> = occlusion.get("masks")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| format!("Invalid occlusion masks: {}", e))?
+            .unwrap_or_default();
+
+        let image_path = resolve_attachment(attachment)?;
+        render_occluded_image(&image_path, &masks, mask_id, hide_all, reveal)
+    }
+
+    // Audio
+
+    /// Save a recording (base64 or data URL) for the front or back of a card
+    pub async fn record_card_audio(
+        &self,
+        flashcard_id: &str,
+        side: &str,
+        audio_data: &str,
+        extension: Option<String>,
+    ) -> Result<Flashcard, String> {
+        validate_card_side(side)?;
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let flashcard = database.get_flashcard(flashcard_id).await
+            .map_err(|e| format!("Failed to get flashcard: {}", e))?
+            .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+        let extension = extension
+            .or_else(|| audio_data.strip_prefix("data:audio/")
+                .and_then(|rest| rest.split([';', ',']).next())
+                .map(|ext| if ext == "mpeg" { "mp3".to_string() } else { ext.to_string() }))
+            .unwrap_or_else(|| "webm".to_string());
+        let attachment = store_attachment(&decode_base64_payload(audio_data)?, &extension)?;
+
+        set_card_audio(database, &flashcard, side, &attachment, "recorded").await
+    }
+
+    /// A card side's audio as a data URL. When nothing was recorded and a provider is given,
+    /// the side's text is synthesized with text-to-speech and saved for next time.
+    pub async fn get_card_audio(
+        &self,
+        flashcard_id: &str,
+        side: &str,
+        provider: Option<AIProvider>,
+        model: Option<String>,
+        voice: Option<String>,
+    ) -> Result<Option<String>, String> {
+        validate_card_side(side)?;
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let flashcard = database.get_flashcard(flashcard_id).await
+            .map_err(|e| format!("Failed to get flashcard: {}", e))?
+            .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+
+        if let Some(attachment) = card_audio_attachment(&flashcard, side) {
+            match resolve_attachment(&attachment) {
+                Ok(path) => {
+                    let bytes = std::fs::read(&path)
+                        .map_err(|e| format!("Failed to read card audio: {}", e))?;
+                    return Ok(Some(to_data_url(&bytes, mime_type_for(&attachment))));
+                }
+                Err(e) => eprintln!("⚠️ {}", e),
+            }
+        }
+
+        let mut provider = match provider {
+            Some(provider) => provider,
+            None => return Ok(None),
+        };
+
+        let text = if side == "front" { &flashcard.front } else { &flashcard.back };
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut text = text.clone();
+        let api_key = database.get_api_key(&provider.id).await
+            .map_err(|e| format!("Failed to get API key: {}", e))?;
+        load_provider_settings(database, &mut provider).await?;
+        if let Some(redactor) = ai_redactor(database, &provider).await? {
+            let mut redacted = 0;
+            redactor.redact_in_place(&mut text, &mut redacted);
+            if redacted > 0 {
+                println!("🔒 Redacted {} personal identifiers from the text sent to {}", redacted, provider.id);
+            }
+        }
+        let model = model.unwrap_or_else(|| DEFAULT_TTS_MODEL.to_string());
+        let voice = voice.unwrap_or_else(|| DEFAULT_TTS_VOICE.to_string());
+
+        // Don't hold the database across the provider request
+        drop(db_state);
+
+        println!("🔊 Generating {} audio for flashcard {} with {}", side, flashcard_id, model);
+        let audio = text_to_speech_for_provider(&provider, &model, &voice, &text, api_key).await?;
+        let attachment = store_attachment(&audio, "mp3")?;
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        // Re-read so metadata edited while the audio was generated isn't overwritten
+        let flashcard = database.get_flashcard(flashcard_id).await
+            .map_err(|e| format!("Failed to get flashcard: {}", e))?
+            .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+        set_card_audio(database, &flashcard, side, &attachment, "tts").await?;
+
+        Ok(Some(to_data_url(&audio, "audio/mpeg")))
+    }
+
+    // Decks
+
+    pub async fn create_deck(&self, request: CreateFlashcardDeckRequest) -> Result<FlashcardDeck, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.create_flashcard_deck(request).await
+            .map_err(|e| format!("Failed to create flashcard deck: {}", e))
+    }
+
+    pub async fn get_deck(&self, id: &str) -> Result<Option<FlashcardDeck>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_deck(id).await
+            .map_err(|e| format!("Failed to get flashcard deck: {}", e))
+    }
+
+    /// All decks, leaving out those in archived categories unless `include_archived`
+    pub async fn get_decks(&self, include_archived: bool) -> Result<Vec<FlashcardDeck>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let mut decks = database.get_flashcard_decks().await
+            .map_err(|e| format!("Failed to get flashcard decks: {}", e))?;
+        if !include_archived {
+            let archived = database.get_archived_category_ids().await
+                .map_err(|e| format!("Failed to get archived categories: {}", e))?;
+            decks.retain(|deck| deck.category_id.as_ref().map_or(true, |id| !archived.contains(id)));
+        }
+        Ok(decks)
+    }
+
+    pub async fn get_deck_stats(&self, deck_id: &str) -> Result<FlashcardDeckStats, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_deck_stats(deck_id).await
+            .map_err(|e| format!("Failed to get flashcard deck stats: {}", e))
+    }
+
+    pub async fn update_deck(&self, id: &str, request: CreateFlashcardDeckRequest) -> Result<Option<FlashcardDeck>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.update_flashcard_deck(id, request).await
+            .map_err(|e| format!("Failed to update flashcard deck: {}", e))
+    }
+
+    pub async fn delete_deck(&self, id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.delete_flashcard_deck(id).await
+            .map_err(|e| format!("Failed to delete flashcard deck: {}", e))
+    }
+
+    // Deck packages
+
+    /// Export a deck and its media as a `.stellardeck` package (see `media::package` for the
+    /// format), returning the path written. Card text goes through the export redaction rules.
+    pub async fn export_deck_package(&self, deck_id: &str, path: &str, author: Option<DeckPackageAuthor>) -> Result<PathBuf, String> {
+        let (deck, flashcards, redactor) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let deck = database.get_flashcard_deck(deck_id).await
+                .map_err(|e| format!("Failed to get flashcard deck: {}", e))?
+                .ok_or_else(|| format!("Flashcard deck not found: {}", deck_id))?;
+            let flashcards = database.get_flashcards_by_deck(deck_id).await
+                .map_err(|e| format!("Failed to get flashcards: {}", e))?;
+            (deck, flashcards, export_redactor(database).await?)
+        };
+
+        let mut media = HashMap::new();
+        for attachment in flashcards.iter().filter_map(|c| c.metadata.as_ref()).flat_map(card_attachments) {
+            match resolve_attachment(&attachment) {
+                Ok(file_path) => { media.insert(attachment, file_path); }
+                Err(e) => eprintln!("⚠️ Skipping missing deck media: {}", e),
+            }
+        }
+
+        let mut cards: Vec<DeckPackageCard> = flashcards.into_iter().map(|card| DeckPackageCard {
+            front: card.front,
+            back: card.back,
+            card_type: card.card_type,
+            difficulty: card.difficulty,
+            tags: card.tags,
+            source_text: card.source_text,
+            metadata: card.metadata,
+        }).collect();
+
+        if let Some(redactor) = &redactor {
+            let mut redacted = 0;
+            for card in cards.iter_mut() {
+                redactor.redact_in_place(&mut card.front, &mut redacted);
+                redactor.redact_in_place(&mut card.back, &mut redacted);
+                if let Some(source_text) = card.source_text.as_mut() {
+                    redactor.redact_in_place(source_text, &mut redacted);
+                }
+            }
+            if redacted > 0 {
+                println!("🔒 Redacted {} personal identifiers from deck '{}'", redacted, deck.name);
+            }
+        }
+
+        let manifest = DeckPackageManifest {
+            format: DECK_PACKAGE_FORMAT.to_string(),
+            version: DECK_PACKAGE_VERSION,
+            name: deck.name,
+            description: deck.description,
+            color: deck.color,
+            icon: deck.icon,
+            tags: deck.tags,
+            author,
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            card_count: cards.len(),
+        };
+
+        let mut output = PathBuf::from(path);
+        if output.extension().is_none() {
+            output.set_extension(DECK_PACKAGE_EXTENSION);
+        }
+
+        write_deck_package(&output, &manifest, &cards, &media)?;
+
+        println!("📦 Exported deck '{}' ({} cards, {} media files) to {:?}", manifest.name, cards.len(), media.len(), output);
+        Ok(output)
+    }
+
+    /// Import a `.stellardeck` package as a new deck, copying its media into the attachments dir
+    pub async fn import_deck_package(&self, path: &Path, category_id: Option<String>) -> Result<FlashcardDeck, String> {
+        let package = read_deck_package(path)?;
+
+        let mut renamed = HashMap::new();
+        for (name, bytes) in &package.media {
+            let extension = Path::new(name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("bin");
+            renamed.insert(name.clone(), store_attachment(bytes, extension)?);
+        }
+
+        let manifest = &package.manifest;
+        let deck_request = CreateFlashcardDeckRequest {
+            name: manifest.name.clone(),
+            description: manifest.description.clone(),
+            color: manifest.color.clone(),
+            icon: manifest.icon.clone(),
+            category_id: category_id.clone(),
+            tags: manifest.tags.clone(),
+            is_shared: Some(true),
+            metadata: Some(serde_json::json!({
+                "imported_package": {
+                    "author": manifest.author,
+                    "version": manifest.version,
+                    "exported_at": manifest.exported_at,
+                    "app_version": manifest.app_version,
+                }
+            })),
+            new_card_order: None,
+        };
+        let cards: Vec<CreateFlashcardRequest> = package.cards.into_iter()
+            .map(|card| CreateFlashcardRequest {
+                front: card.front,
+                back: card.back,
+                source_document_id: None,
+                source_text: card.source_text,
+                difficulty: Some(card.difficulty),
+                tags: card.tags,
+                category_id: category_id.clone(),
+                card_type: Some(card.card_type),
+                deck_id: None, // Set to the new deck
+                metadata: card.metadata.map(|mut metadata| {
+                    rewrite_card_attachments(&mut metadata, &renamed);
+                    metadata
+                }),
+            })
+            .collect();
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let deck = match database.create_flashcard_deck_with_cards(deck_request, cards).await {
+            Ok(deck) => deck,
+            Err(e) => {
+                // Nothing was imported, so the copied media belongs to no card
+                for attachment in renamed.values() {
+                    if let Err(e) = delete_attachment(attachment) {
+                        eprintln!("⚠️ {}", e);
+                    }
+                }
+                return Err(format!("Failed to import flashcard deck: {}", e));
+            }
+        };
+
+        println!("📦 Imported deck '{}' from {}", deck.name, path.display());
+
+        database.get_flashcard_deck(&deck.id).await
+            .map_err(|e| format!("Failed to get flashcard deck: {}", e))?
+            .ok_or_else(|| "Imported deck disappeared".to_string())
+    }
+
+    // Reviews
+
+    pub async fn record_review(&self, request: CreateFlashcardReviewRequest) -> Result<FlashcardReviewResult, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let review_session_id = request.review_session_id.clone();
+        let result = database.record_flashcard_review(request).await
+            .map_err(|e| format!("Failed to record flashcard review: {}", e))?;
+
+        // Answering the last drawn card completes the session
+        if let Some(id) = review_session_id {
+            match database.get_review_session_state(&id).await {
+                Ok(Some(session)) if session.status == "completed"
+                    && session.results.last().is_some_and(|last| last.review_id == result.review.id) =>
+                {
+                    self.review_completed(&session);
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("⚠️ Failed to check review session {}: {}", id, e),
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Grade a typed answer against the card back. Uses the chat model when a provider is given,
+    /// otherwise (or if the model fails) falls back to embedding similarity, then to exact matching.
+    pub async fn grade_typed_answer(
+        &self,
+        flashcard_id: String,
+        user_answer: &str,
+        mut provider: Option<AIProvider>,
+        model: Option<String>,
+    ) -> Result<TypedAnswerGrade, String> {
+        let (flashcard, api_key) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let flashcard = database.get_flashcard(&flashcard_id).await
+                .map_err(|e| format!("Failed to get flashcard: {}", e))?
+                .ok_or_else(|| format!("Flashcard not found: {}", flashcard_id))?;
+            let api_key = match provider.as_mut() {
+                Some(provider) => {
+                    load_provider_settings(database, provider).await?;
+                    database.get_api_key(&provider.id).await
+                        .map_err(|e| format!("Failed to get API key: {}", e))?
+                }
+                None => None,
+            };
+            (flashcard, api_key)
+        };
+
+        if user_answer.trim().is_empty() {
+            return Ok(TypedAnswerGrade {
+                flashcard_id,
+                quality: 0,
+                response: "incorrect".to_string(),
+                similarity: None,
+                feedback: Some("No answer given.".to_string()),
+                graded_by: "exact".to_string(),
+            });
+        }
+
+        let similarity = {
+            let mut vector_guard = self.vectors.lock().await;
+            match vector_guard.as_mut() {
+                Some(vector_service) => vector_service.text_similarity(user_answer, &flashcard.back).await
+                    .map_err(|e| eprintln!("⚠️ Embedding similarity failed: {}", e))
+                    .ok(),
+                None => None,
+            }
+        };
+
+        if let (Some(provider), Some(model)) = (&provider, &model) {
+            let mut request = ChatCompletionRequest {
+                messages: build_grading_messages(&flashcard.front, &flashcard.back, user_answer, similarity),
+                model: model.clone(),
+                temperature: Some(0.0),
+                max_tokens: Some(200),
+                top_p: None,
+                frequency_penalty: None,
+                presence_penalty: None,
+                stream: Some(false),
+            };
+            {
+                let db_state = self.database.lock().await;
+                let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+                redact_chat_request(database, provider, &mut request).await?;
+            }
+
+            let graded = chat_completion_for_provider(provider, model, &request, api_key).await
+                .and_then(|response| parse_grading_response(
+                    &response.choices.first().map(|c| c.message.content.clone()).unwrap_or_default()
+                ));
+
+            match graded {
+                Ok(grade) => {
+                    return Ok(TypedAnswerGrade {
+                        flashcard_id,
+                        quality: grade.quality,
+                        response: quality_to_response(grade.quality).to_string(),
+                        similarity,
+                        feedback: grade.feedback,
+                        graded_by: "model".to_string(),
+                    });
+                }
+                Err(e) => eprintln!("⚠️ Model grading failed, falling back: {}", e),
+            }
+        }
+
+        let (quality, graded_by) = match similarity {
+            Some(similarity) => (similarity_to_quality(similarity), "embeddings"),
+            None => {
+                let matches = user_answer.trim().eq_ignore_ascii_case(flashcard.back.trim());
+                (if matches { 5 } else { 0 }, "exact")
+            }
+        };
+
+        Ok(TypedAnswerGrade {
+            flashcard_id,
+            quality,
+            response: quality_to_response(quality).to_string(),
+            similarity,
+            feedback: None,
+            graded_by: graded_by.to_string(),
+        })
+    }
+
+    pub async fn get_due_flashcards(&self, limit: Option<i32>) -> Result<Vec<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_due_flashcards(limit).await
+            .map_err(|e| format!("Failed to get due flashcards: {}", e))
+    }
+
+    /// New cards, optionally from one deck. `order` overrides the deck's new-card order; without a
+    /// deck it defaults to creation order.
+    pub async fn get_new_flashcards(&self, limit: Option<i32>, deck_id: Option<&str>, order: Option<NewCardOrder>) -> Result<Vec<Flashcard>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_new_flashcards(limit, deck_id, order).await
+            .map_err(|e| format!("Failed to get new flashcards: {}", e))
+    }
+
+    pub async fn get_review_session(&self, session_limit: i32, mix_strategy: &str) -> Result<FlashcardReviewSession, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_review_session(session_limit, mix_strategy).await
+            .map_err(|e| format!("Failed to get flashcard review session: {}", e))
+    }
+
+    /// Fill `minutes_available` with due, then new cards, at the user's own pace per card
+    pub async fn get_adaptive_review_session(&self, minutes_available: i32) -> Result<AdaptiveReviewSession, String> {
+        if minutes_available <= 0 {
+            return Err("Minutes available must be positive".to_string());
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_adaptive_review_session(minutes_available).await
+            .map_err(|e| format!("Failed to get adaptive review session: {}", e))
+    }
+
+    /// Build a review session from a filter (tags, deck, category, source document, recent
+    /// failures, due soon) instead of the regular schedule
+    pub async fn get_custom_review_session(&self, filter: &CustomReviewFilter) -> Result<FlashcardReviewSession, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_custom_review_session(filter).await
+            .map_err(|e| format!("Failed to get custom review session: {}", e))
+    }
+
+    /// Save a drawn review session so it can be resumed if the app closes mid-session
+    pub async fn start_review_session(&self, request: StartReviewSessionRequest) -> Result<PersistedReviewSession, String> {
+        if request.card_ids.is_empty() {
+            return Err("A review session needs at least one card".to_string());
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.start_review_session_state(request).await
+            .map_err(|e| format!("Failed to save review session: {}", e))
+    }
+
+    /// The unfinished review session to continue, if one hasn't expired
+    pub async fn resume_review_session(&self) -> Result<Option<ResumedReviewSession>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_resumable_review_session().await
+            .map_err(|e| format!("Failed to load review session: {}", e))
+    }
+
+    /// Stop tracking a review session; `abandoned` marks one the user quit rather than finished
+    pub async fn finish_review_session(&self, id: &str, abandoned: bool) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let status = if abandoned { "abandoned" } else { "completed" };
+        let finished = database.finish_review_session_state(id, status).await
+            .map_err(|e| format!("Failed to finish review session: {}", e))?;
+
+        if finished && !abandoned {
+            if let Ok(Some(session)) = database.get_review_session_state(id).await {
+                self.review_completed(&session);
+            }
+        }
+        Ok(finished)
+    }
+
+    // Run the review_completed automation scripts with the session's tally
+    fn review_completed(&self, session: &PersistedReviewSession) {
+        let count = |response: &str| session.results.iter().filter(|result| result.response == response).count();
+        let average_quality = if session.results.is_empty() {
+            0.0
+        } else {
+            session.results.iter().map(|result| result.quality as f64).sum::<f64>() / session.results.len() as f64
+        };
+
+        scripting::dispatch(self.database.clone(), self.vectors.clone(), ScriptEvent::ReviewCompleted, serde_json::json!({
+            "review_session_id": session.id,
+            "study_session_id": session.study_session_id,
+            "cards_drawn": session.card_ids.len(),
+            "cards_reviewed": session.results.len(),
+            "correct": count("correct"),
+            "partial": count("partial"),
+            "incorrect": count("incorrect"),
+            "average_quality": average_quality,
+            "flashcard_ids": session.results.iter().map(|result| result.flashcard_id.clone()).collect::<Vec<_>>(),
+        }));
+    }
+
+    // Stats
+
+    pub async fn get_stats(&self) -> Result<FlashcardStats, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_stats().await
+            .map_err(|e| format!("Failed to get flashcard stats: {}", e))
+    }
+
+    /// Review history, interval growth, answer time and lapses for one card
+    pub async fn get_detail_stats(&self, card_id: &str) -> Result<FlashcardDetailStats, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_detail_stats(card_id).await
+            .map_err(|e| format!("Failed to get flashcard stats: {}", e))?
+            .ok_or_else(|| format!("Flashcard not found: {}", card_id))
+    }
+
+    pub async fn get_reviews(&self, flashcard_id: &str) -> Result<Vec<FlashcardReview>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_reviews(flashcard_id).await
+            .map_err(|e| format!("Failed to get flashcard reviews: {}", e))
+    }
+
+    pub async fn get_reviews_by_session(&self, session_id: &str) -> Result<Vec<FlashcardReview>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_flashcard_reviews_by_session(session_id).await
+            .map_err(|e| format!("Failed to get flashcard reviews by session: {}", e))
+    }
+}
+
+// Audio lives in the attachments dir and is referenced from metadata as
+// {"audio": {"front": {"attachment": "...", "source": "recorded" | "tts"}, "back": {...}}}
+fn validate_card_side(side: &str) -> Result<(), String> {
+    match side {
+        "front" | "back" => Ok(()),
+        _ => Err(format!("Invalid card side: {} (expected 'front' or 'back')", side)),
+    }
+}
+
+fn card_audio_attachment(flashcard: &Flashcard, side: &str) -> Option<String> {
+    flashcard.metadata.as_ref()
+        .and_then(|m| m.get("audio"))
+        .and_then(|a| a.get(side))
+        .and_then(|s| s.get("attachment"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+async fn set_card_audio(
+    database: &Database,
+    flashcard: &Flashcard,
+    side: &str,
+    attachment: &str,
+    source: &str,
+) -> Result<Flashcard, String> {
+    let previous = card_audio_attachment(flashcard, side);
+
+    let mut metadata = flashcard.metadata.clone()
+        .filter(|m| m.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    if !metadata.get("audio").map(|a| a.is_object()).unwrap_or(false) {
+        metadata["audio"] = serde_json::json!({});
+    }
+    metadata["audio"][side] = serde_json::json!({ "attachment": attachment, "source": source });
+
+    let updated = database.update_flashcard_metadata(&flashcard.id, &metadata).await
+        .map_err(|e| format!("Failed to update flashcard: {}", e))?
+        .ok_or_else(|| format!("Flashcard not found: {}", flashcard.id))?;
+
+    if let Some(previous) = previous.filter(|p| p != attachment) {
+        if let Err(e) = delete_attachment(&previous) {
+            eprintln!("⚠️ Failed to remove replaced card audio: {}", e);
+        }
+    }
+
+    Ok(updated)
+}
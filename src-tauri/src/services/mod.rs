@@ -0,0 +1,79 @@
+//! Business logic behind the Tauri commands, as plain async services. Each service holds the
+//! same shared handles the app manages as state, so commands are thin wrappers that clone their
+//! `State` into a service, and headless callers (a CLI, tests) build services around handles of
+//! their own with `Services::new`.
+
+pub mod ai;
+pub mod documents;
+pub mod flashcards;
+pub mod settings;
+pub mod translation;
+pub mod reading;
+pub mod search;
+pub mod onboarding;
+pub mod question_bank;
+pub mod essays;
+pub mod equations;
+
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::database::Database;
+use crate::embeddings::VectorService;
+
+pub use ai::{AiService, PreparedChat};
+pub use documents::DocumentService;
+pub use flashcards::FlashcardService;
+pub use settings::SettingsService;
+pub use translation::TranslationService;
+pub use reading::ReadingService;
+pub use search::SearchService;
+pub use onboarding::OnboardingService;
+pub use question_bank::QuestionBankService;
+pub use essays::EssayService;
+pub use equations::EquationService;
+
+pub type DatabaseState = Arc<Mutex<Option<Database>>>;
+pub type VectorServiceState = Arc<Mutex<Option<VectorService>>>;
+
+pub(crate) const DATABASE_NOT_INITIALIZED: &str = "Database not initialized";
+
+/// All services over one database and (optional) vector service
+#[derive(Clone)]
+pub struct Services {
+    pub documents: DocumentService,
+    pub flashcards: FlashcardService,
+    pub ai: AiService,
+    pub settings: SettingsService,
+    pub translation: TranslationService,
+    pub reading: ReadingService,
+    pub search: SearchService,
+    pub onboarding: OnboardingService,
+    pub question_bank: QuestionBankService,
+    pub essays: EssayService,
+    pub equations: EquationService,
+}
+
+impl Services {
+    pub fn new(database: DatabaseState, vectors: VectorServiceState) -> Self {
+        Self {
+            documents: DocumentService::new(database.clone(), vectors.clone()),
+            flashcards: FlashcardService::new(database.clone(), vectors.clone()),
+            ai: AiService::new(database.clone()),
+            settings: SettingsService::new(database.clone()),
+            translation: TranslationService::new(database.clone()),
+            reading: ReadingService::new(database.clone(), vectors.clone()),
+            search: SearchService::new(database.clone()),
+            question_bank: QuestionBankService::new(database.clone()),
+            essays: EssayService::new(database.clone()),
+            equations: EquationService::new(database.clone()),
+            onboarding: OnboardingService::new(database, vectors),
+        }
+    }
+
+    /// Services for an already opened database, without embeddings. Handy outside the app,
+    /// where nothing else needs to share the handles.
+    pub fn for_database(database: Database) -> Self {
+        Self::new(Arc::new(Mutex::new(Some(database))), Arc::new(Mutex::new(None)))
+    }
+}
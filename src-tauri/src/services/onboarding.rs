@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::commands::embeddings::EMBEDDING_FALLBACK_SETTINGS_KEY;
+use crate::commands::pdf::process_document_embeddings_internal;
+use crate::database::{EmbeddingFallbackSettings, CreateDocumentRequest, CreateFlashcardDeckRequest, CreateFlashcardRequest, Document, FlashcardDeck};
+use crate::pdf_processor::PdfProcessor;
+use super::{DatabaseState, VectorServiceState, DATABASE_NOT_INITIALIZED};
+
+/// Setting recording the sample document and deck, so seeding twice doesn't duplicate them
+pub const ONBOARDING_SAMPLE_KEY: &str = "onboarding_sample";
+const SAMPLE_TAG: &str = "sample";
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+// Onboarding polls this; a missing Ollama shouldn't make the page hang
+const OLLAMA_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SampleContentIds {
+    document_id: Option<String>,
+    deck_id: Option<String>,
+}
+
+/// What a first run still has to set up, for the guided setup in the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingState {
+    pub database_ok: bool,
+    pub database_error: Option<String>,
+    pub embedding_provider_available: bool, // The vector service came up with some provider
+    pub providers_with_keys: Vec<String>, // Provider ids with a stored API key; Ollama needs none
+    pub chat_provider_configured: bool, // A key for a chat provider, or Ollama configured or running
+    pub marker_installed: bool,
+    pub marker_suggested_action: Option<String>,
+    pub sample_document_id: Option<String>,
+    pub sample_deck_id: Option<String>,
+    pub sample_content_imported: bool,
+    pub complete: bool, // Database, embeddings and a chat provider; Marker and the sample are optional
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SampleContent {
+    pub document: Document,
+    pub deck: FlashcardDeck,
+    pub flashcards_created: usize,
+    pub already_existed: bool, // Both were seeded before; nothing new was created
+}
+
+/// First-run checks and the demo content for the guided setup
+#[derive(Clone)]
+pub struct OnboardingService {
+    database: DatabaseState,
+    vectors: VectorServiceState,
+}
+
+impl OnboardingService {
+    pub fn new(database: DatabaseState, vectors: VectorServiceState) -> Self {
+        Self { database, vectors }
+    }
+
+    pub async fn get_onboarding_state(&self) -> Result<OnboardingState, String> {
+        let embedding_provider_available = self.vectors.lock().await.is_some();
+        let marker_status = PdfProcessor::new().get_marker_installation_status().await;
+
+        let mut state = OnboardingState {
+            database_ok: false,
+            database_error: None,
+            embedding_provider_available,
+            providers_with_keys: Vec::new(),
+            chat_provider_configured: false,
+            marker_installed: marker_status.is_available,
+            marker_suggested_action: marker_status.suggested_action,
+            sample_document_id: None,
+            sample_deck_id: None,
+            sample_content_imported: false,
+            complete: false,
+        };
+
+        let db_state = self.database.lock().await;
+        let Some(database) = db_state.as_ref() else {
+            state.database_error = Some(DATABASE_NOT_INITIALIZED.to_string());
+            return Ok(state);
+        };
+        if let Err(e) = database.ping().await {
+            state.database_error = Some(format!("Failed to query database: {}", e));
+            return Ok(state);
+        }
+        state.database_ok = true;
+
+        state.providers_with_keys = database.list_api_key_providers().await
+            .map_err(|e| format!("Failed to list API keys: {}", e))?
+            .into_iter()
+            .filter(|id| id != crate::network::PROXY_PASSWORD_KEY_ID)
+            .collect();
+
+        // Keys stored for embedding-only providers (e.g. Voyage) can't be used to chat
+        let embedding_settings: EmbeddingFallbackSettings = database.get_typed_setting(EMBEDDING_FALLBACK_SETTINGS_KEY).await
+            .map_err(|e| format!("Failed to get embedding settings: {}", e))?;
+        let embedding_only_keys: HashSet<&str> = embedding_settings.order.iter()
+            .filter(|candidate| crate::ai::chat_provider(&candidate.provider).is_err())
+            .filter_map(|candidate| candidate.api_key_id.as_deref())
+            .collect();
+        let ollama_url = embedding_settings.order.iter()
+            .find(|candidate| candidate.provider == "ollama")
+            .and_then(|candidate| candidate.base_url.clone())
+            .unwrap_or_else(|| DEFAULT_OLLAMA_URL.to_string());
+        state.chat_provider_configured = state.providers_with_keys.iter()
+            .any(|id| !embedding_only_keys.contains(id.as_str()));
+
+        let sample: SampleContentIds = database.get_typed_setting(ONBOARDING_SAMPLE_KEY).await
+            .map_err(|e| format!("Failed to load sample content: {}", e))?;
+        if let Some(document_id) = &sample.document_id {
+            let exists = database.get_document(document_id).await
+                .map_err(|e| format!("Failed to get sample document: {}", e))?
+                .is_some();
+            if exists {
+                state.sample_document_id = Some(document_id.clone());
+            }
+        }
+        if let Some(deck_id) = &sample.deck_id {
+            let exists = database.get_flashcard_deck(deck_id).await
+                .map_err(|e| format!("Failed to get sample deck: {}", e))?
+                .is_some();
+            if exists {
+                state.sample_deck_id = Some(deck_id.clone());
+            }
+        }
+        state.sample_content_imported = state.sample_document_id.is_some();
+        drop(db_state);
+
+        // Ollama needs no key, so a running one is enough to chat
+        if !state.chat_provider_configured {
+            state.chat_provider_configured = ollama_reachable(&ollama_url).await;
+        }
+
+        state.complete = state.database_ok && state.embedding_provider_available && state.chat_provider_configured;
+        Ok(state)
+    }
+
+    /// Create the demo document (embedded when the vector service is up) and a deck of cards
+    /// about it. Whatever was seeded before and still exists is reused.
+    pub async fn seed_sample_content(&self) -> Result<SampleContent, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let mut sample: SampleContentIds = database.get_typed_setting(ONBOARDING_SAMPLE_KEY).await
+            .map_err(|e| format!("Failed to load sample content: {}", e))?;
+
+        let existing_document = match &sample.document_id {
+            Some(id) => database.get_document(id).await
+                .map_err(|e| format!("Failed to get sample document: {}", e))?,
+            None => None,
+        };
+        let existing_deck = match &sample.deck_id {
+            Some(id) => database.get_flashcard_deck(id).await
+                .map_err(|e| format!("Failed to get sample deck: {}", e))?,
+            None => None,
+        };
+        let already_existed = existing_document.is_some() && existing_deck.is_some();
+
+        let document = match existing_document {
+            Some(document) => document,
+            None => {
+                let document = database.create_document(CreateDocumentRequest {
+                    title: SAMPLE_DOCUMENT_TITLE.to_string(),
+                    content: SAMPLE_DOCUMENT_CONTENT.to_string(),
+                    content_hash: None,
+                    file_path: None,
+                    doc_type: "markdown".to_string(),
+                    tags: vec![SAMPLE_TAG.to_string()],
+                    status: Some("ready".to_string()),
+                    category_id: None,
+                }).await
+                    .map_err(|e| format!("Failed to create sample document: {}", e))?;
+
+                // Search and chat work on the sample right away when embeddings are available
+                if let Some(vector_service) = self.vectors.lock().await.as_mut() {
+                    if let Err(e) = process_document_embeddings_internal(vector_service, &document).await {
+                        eprintln!("⚠️ Failed to embed sample document: {}", e);
+                    }
+                }
+                document
+            }
+        };
+
+        let mut flashcards_created = 0;
+        let deck = match existing_deck {
+            Some(deck) => deck,
+            None => {
+                let deck = database.create_flashcard_deck(CreateFlashcardDeckRequest {
+                    name: SAMPLE_DECK_NAME.to_string(),
+                    description: Some(format!("Cards from \"{}\"", SAMPLE_DOCUMENT_TITLE)),
+                    color: None,
+                    icon: None,
+                    category_id: None,
+                    tags: vec![SAMPLE_TAG.to_string()],
+                    is_shared: None,
+                    metadata: Some(serde_json::json!({ "created_via": "onboarding" })),
+                    new_card_order: None,
+                }).await
+                    .map_err(|e| format!("Failed to create sample deck: {}", e))?;
+
+                for (front, back) in SAMPLE_FLASHCARDS {
+                    database.create_flashcard(CreateFlashcardRequest {
+                        front: front.to_string(),
+                        back: back.to_string(),
+                        source_document_id: Some(document.id.clone()),
+                        source_text: None,
+                        difficulty: None,
+                        tags: vec![SAMPLE_TAG.to_string()],
+                        category_id: None,
+                        card_type: Some("basic".to_string()),
+                        deck_id: Some(deck.id.clone()),
+                        metadata: Some(serde_json::json!({ "created_via": "onboarding" })),
+                    }).await
+                        .map_err(|e| format!("Failed to create sample flashcard: {}", e))?;
+                    flashcards_created += 1;
+                }
+                deck
+            }
+        };
+
+        sample.document_id = Some(document.id.clone());
+        sample.deck_id = Some(deck.id.clone());
+        database.set_typed_setting(ONBOARDING_SAMPLE_KEY, &sample).await
+            .map_err(|e| format!("Failed to save sample content: {}", e))?;
+
+        if !already_existed {
+            println!("🌱 Seeded sample document {} and deck {} ({} cards)", document.id, deck.id, flashcards_created);
+        }
+        Ok(SampleContent { document, deck, flashcards_created, already_existed })
+    }
+}
+
+async fn ollama_reachable(base_url: &str) -> bool {
+    let Ok(client) = crate::network::shared_client(crate::network::HttpPurpose::Ai) else {
+        return false;
+    };
+    client.get(format!("{}/api/tags", base_url.trim_end_matches('/')))
+        .timeout(OLLAMA_PROBE_TIMEOUT)
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success())
+}
+
+const SAMPLE_DOCUMENT_TITLE: &str = "Welcome to Stellar: The Water Cycle";
+const SAMPLE_DECK_NAME: &str = "Sample: The Water Cycle";
+
+const SAMPLE_DOCUMENT_CONTENT: &str = "# The Water Cycle
+
+This sample document shows what Stellar does with your study material. Select text to highlight it, ask the assistant about it in chat, or review the flashcards in the \"Sample: The Water Cycle\" deck. Delete it whenever you like.
+
+## Evaporation
+
+The sun heats water in oceans, lakes and rivers until it turns into water vapour and rises into the atmosphere. Plants add more vapour through transpiration, releasing water from their leaves. Together the two are called evapotranspiration.
+
+## Condensation
+
+As water vapour rises it cools. Cooler air holds less vapour, so the vapour condenses around tiny particles of dust and salt into droplets. Billions of droplets together form clouds and fog.
+
+## Precipitation
+
+When droplets in a cloud collide and grow too heavy to stay aloft, they fall as precipitation: rain, snow, sleet or hail, depending on the temperature of the air they fall through.
+
+## Collection
+
+Precipitation collects in oceans, lakes and rivers, soaks into the ground as groundwater, or is stored as ice in glaciers and snowpack. From there the water evaporates again and the cycle repeats.
+";
+
+const SAMPLE_FLASHCARDS: &[(&str, &str)] = &[
+    ("What drives evaporation in the water cycle?", "Heat from the sun, which turns liquid water into water vapour."),
+    ("What is transpiration?", "The release of water vapour from plant leaves."),
+    ("Why does water vapour condense as it rises?", "Rising air cools, and cooler air holds less vapour, so it condenses into droplets."),
+    ("Name four forms of precipitation.", "Rain, snow, sleet and hail."),
+    ("Where does precipitation collect?", "In oceans, lakes and rivers, as groundwater, and as ice in glaciers and snowpack."),
+];
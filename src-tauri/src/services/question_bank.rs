@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::ai::question_bank::{
+    build_question_extraction_messages, build_topic_messages, detect_exam_year, detect_questions,
+    parse_question_extraction_response, parse_topic_response, DetectedQuestion, MIN_DETECTED_QUESTIONS, TOPIC_BATCH_SIZE,
+};
+use crate::ai::{AIProvider, ChatCompletionRequest};
+use crate::database::{CreateExamPaperRequest, CreateExamQuestionRequest, ExamPaper, ExamQuestion, QuestionBankTopic};
+use crate::pdf_processor::PdfProcessor;
+use super::reading::completion_request;
+use super::{AiService, DatabaseState, DATABASE_NOT_INITIALIZED};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportExamPaperRequest {
+    pub file_path: String,
+    pub title: Option<String>, // Defaults to the file name
+    pub year: Option<i32>, // Detected from the title or the paper when not given
+    pub category_id: Option<String>,
+    // Model for splitting badly numbered papers and naming topics; without one, questions are
+    // found by pattern only and have no topic
+    pub provider: Option<AIProvider>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExamPaperImport {
+    pub paper: ExamPaper,
+    pub questions: Vec<ExamQuestion>,
+    pub ai_extracted: bool, // The patterns found too little, so the model split the paper
+    pub topics_assigned: usize,
+}
+
+/// Past exam papers split into questions with year and topic, served back as practice sets
+#[derive(Clone)]
+pub struct QuestionBankService {
+    database: DatabaseState,
+    ai: AiService,
+}
+
+impl QuestionBankService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self {
+            ai: AiService::new(database.clone()),
+            database,
+        }
+    }
+
+    /// Import a past paper (PDF, or plain text/markdown) into the question bank
+    pub async fn import_exam_paper(&self, req: ImportExamPaperRequest) -> Result<ExamPaperImport, String> {
+        let path = Path::new(&req.file_path);
+        let is_pdf = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        let text = if is_pdf {
+            let file_path = req.file_path.clone();
+            tokio::task::spawn_blocking(move || PdfProcessor::new().extract_basic_text(&file_path))
+                .await
+                .map_err(|e| format!("Failed to extract exam paper: {}", e))?
+                .map_err(|e| format!("Failed to extract exam paper: {}", e))?
+        } else {
+            tokio::fs::read_to_string(path).await
+                .map_err(|e| format!("Failed to read exam paper: {}", e))?
+        };
+        if text.trim().is_empty() {
+            return Err("The exam paper has no text (scanned papers need OCR first)".to_string());
+        }
+
+        let title = req.title.clone()
+            .filter(|title| !title.trim().is_empty())
+            .or_else(|| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .unwrap_or_else(|| "Exam paper".to_string());
+        let year = req.year.or_else(|| detect_exam_year(&title, &text));
+
+        let mut questions = detect_questions(&text);
+        let mut ai_extracted = false;
+        let ai = req.provider.clone().zip(req.model.clone());
+
+        if questions.len() < MIN_DETECTED_QUESTIONS {
+            if let Some((provider, model)) = &ai {
+                let request = completion_request(model, build_question_extraction_messages(&title, &text), 6000);
+                let response = self.complete(provider.clone(), model.clone(), request).await?;
+                let extracted = parse_question_extraction_response(&response)?;
+                if extracted.len() > questions.len() {
+                    questions = extracted;
+                    ai_extracted = true;
+                }
+            }
+        }
+        if questions.is_empty() {
+            return Err("No questions found in the exam paper".to_string());
+        }
+
+        let mut topics_assigned = 0;
+        if let Some((provider, model)) = &ai {
+            let known_topics = {
+                let db_state = self.database.lock().await;
+                let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+                database.get_exam_topic_names(req.category_id.as_deref()).await
+                    .map_err(|e| format!("Failed to get question bank topics: {}", e))?
+            };
+            for batch in questions.chunks_mut(TOPIC_BATCH_SIZE) {
+                let request = completion_request(model, build_topic_messages(&title, batch, &known_topics), 1500);
+                // Topics are a nicety; a failed batch leaves its questions untagged
+                let topics = match self.complete(provider.clone(), model.clone(), request).await
+                    .and_then(|response| parse_topic_response(&response, batch.len()))
+                {
+                    Ok(topics) => topics,
+                    Err(e) => {
+                        eprintln!("⚠️ Failed to name topics for exam questions: {}", e);
+                        continue;
+                    }
+                };
+                for (question, topic) in batch.iter_mut().zip(topics) {
+                    if topic.is_some() {
+                        question.topic = topic;
+                        topics_assigned += 1;
+                    }
+                }
+            }
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        let paper = database.create_exam_paper(CreateExamPaperRequest {
+            title,
+            year,
+            category_id: req.category_id,
+            file_path: Some(req.file_path),
+            questions: questions.into_iter()
+                .map(|DetectedQuestion { number, text, marks, topic }| CreateExamQuestionRequest { number, text, marks, topic })
+                .collect(),
+        }).await
+            .map_err(|e| format!("Failed to save exam paper: {}", e))?;
+        let questions = database.get_exam_questions(&paper.id).await
+            .map_err(|e| format!("Failed to get exam questions: {}", e))?;
+
+        println!("📄 Imported exam paper \"{}\" ({} questions, {} with topics)", paper.title, questions.len(), topics_assigned);
+        Ok(ExamPaperImport { paper, questions, ai_extracted, topics_assigned })
+    }
+
+    pub async fn get_exam_papers(&self, category_id: Option<&str>) -> Result<Vec<ExamPaper>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_exam_papers(category_id).await
+            .map_err(|e| format!("Failed to get exam papers: {}", e))
+    }
+
+    pub async fn get_exam_questions(&self, paper_id: &str) -> Result<Vec<ExamQuestion>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_exam_questions(paper_id).await
+            .map_err(|e| format!("Failed to get exam questions: {}", e))
+    }
+
+    pub async fn delete_exam_paper(&self, paper_id: &str) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.delete_exam_paper(paper_id).await
+            .map_err(|e| format!("Failed to delete exam paper: {}", e))
+    }
+
+    pub async fn set_exam_question_topic(&self, question_id: &str, topic: Option<&str>) -> Result<bool, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.set_exam_question_topic(question_id, topic).await
+            .map_err(|e| format!("Failed to update exam question: {}", e))
+    }
+
+    pub async fn get_question_bank_topics(&self, category_id: Option<&str>) -> Result<Vec<QuestionBankTopic>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_question_bank_topics(category_id).await
+            .map_err(|e| format!("Failed to get question bank topics: {}", e))
+    }
+
+    /// `count` random past questions on `topic`, from every year in the bank
+    pub async fn get_practice_set(&self, topic: Option<&str>, count: i32, category_id: Option<&str>) -> Result<Vec<ExamQuestion>, String> {
+        if count <= 0 {
+            return Err("The practice set needs at least one question".to_string());
+        }
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_practice_set(topic, count, category_id).await
+            .map_err(|e| format!("Failed to get practice set: {}", e))
+    }
+
+    async fn complete(&self, provider: AIProvider, model: String, request: ChatCompletionRequest) -> Result<String, String> {
+        let response = self.ai.chat_completion(provider, model, request, None).await?;
+        response.choices.first()
+            .map(|choice| choice.message.content.clone())
+            .filter(|content| !content.trim().is_empty())
+            .ok_or_else(|| "The model returned an empty response".to_string())
+    }
+}
@@ -0,0 +1,276 @@
+use crate::ai::reading::{
+    build_explanation_messages, build_section_summary_messages, parse_explanation_response, parse_section_summary_response,
+    ExplanationLevel, SectionSummaryResult, SelectionExplanationResult, TextRange, MAX_SECTION_CHARS, MAX_SELECTION_CHARS,
+    SELECTION_CONTEXT_CHARS,
+};
+use crate::ai::transcripts::{
+    build_outline_messages, build_segment_notes_messages, parse_outline_response, parse_segment_notes_response,
+    render_structured_notes, split_transcript, StructuredTranscriptResult, MAX_SEGMENTS, MAX_SEGMENT_CHARS,
+};
+use crate::ai::{AIProvider, ChatCompletionRequest, ChatMessage};
+use crate::commands::pdf::process_document_embeddings_internal;
+use crate::database::{CreateDocumentRequest, OutlineEntry};
+use super::{AiService, DatabaseState, DocumentService, VectorServiceState, DATABASE_NOT_INITIALIZED};
+
+/// Metadata field on a transcript naming the notes made from it
+pub const STRUCTURED_NOTES_METADATA_KEY: &str = "structured_notes_document_id";
+/// Metadata field on notes naming the transcript they were made from
+pub const TRANSCRIPT_METADATA_KEY: &str = "transcript_document_id";
+
+/// AI help while reading: summaries of outline sections, explanations of selected text and
+/// notes from lecture transcripts. The text is looked up here from the document, so callers
+/// only send ids and offsets.
+#[derive(Clone)]
+pub struct ReadingService {
+    database: DatabaseState,
+    vectors: VectorServiceState,
+    documents: DocumentService,
+    ai: AiService,
+}
+
+impl ReadingService {
+    pub fn new(database: DatabaseState, vectors: VectorServiceState) -> Self {
+        Self {
+            documents: DocumentService::new(database.clone(), vectors.clone()),
+            ai: AiService::new(database.clone()),
+            database,
+            vectors,
+        }
+    }
+
+    /// Summarize the section at `heading_path`, e.g. ["Chapter 3", "Entropy"], subsections
+    /// included. Titles are matched case-insensitively and levels in between may be skipped.
+    pub async fn summarize_section(
+        &self,
+        provider: AIProvider,
+        model: String,
+        document_id: &str,
+        heading_path: &[String],
+    ) -> Result<SectionSummaryResult, String> {
+        let outline = self.documents.get_document_outline(document_id).await?;
+        let entry = find_outline_entry(&outline.entries, heading_path)
+            .ok_or_else(|| format!("No section matches {}", heading_path.join(" > ")))?;
+        let full_path = entry_path(&outline.entries, entry);
+        let section = self.documents.get_document_outline_section(document_id, entry.index).await?;
+        let title = self.document_title(document_id).await?;
+
+        let truncated = section.content.chars().count() > MAX_SECTION_CHARS;
+        let text: String = section.content.chars().take(MAX_SECTION_CHARS).collect();
+        let request = completion_request(&model, build_section_summary_messages(&title, &full_path, &text), 1200);
+        let response = self.complete(provider, model, request).await?;
+        let summary = parse_section_summary_response(&response)?;
+
+        println!("📝 Summarized section \"{}\" of document {}", section.entry.title, document_id);
+        Ok(SectionSummaryResult {
+            document_id: document_id.to_string(),
+            heading_path: full_path,
+            entry: section.entry,
+            truncated,
+            summary,
+        })
+    }
+
+    /// Explain the text between the char offsets of `range`, with the surrounding text of the
+    /// document as context
+    pub async fn explain_selection(
+        &self,
+        provider: AIProvider,
+        model: String,
+        document_id: &str,
+        range: TextRange,
+        level: ExplanationLevel,
+    ) -> Result<SelectionExplanationResult, String> {
+        if range.end <= range.start {
+            return Err("The selection is empty".to_string());
+        }
+        if range.end - range.start > MAX_SELECTION_CHARS {
+            return Err(format!("The selection is too long to explain (at most {} characters)", MAX_SELECTION_CHARS));
+        }
+
+        let document = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            database.get_document(document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or_else(|| format!("Document not found: {}", document_id))?
+        };
+
+        let chars: Vec<char> = document.content.chars().collect();
+        if range.end > chars.len() {
+            return Err("The selection is outside the document".to_string());
+        }
+        let selection: String = chars[range.start..range.end].iter().collect();
+        if selection.trim().is_empty() {
+            return Err("The selection is empty".to_string());
+        }
+        let before: String = chars[range.start.saturating_sub(SELECTION_CONTEXT_CHARS)..range.start].iter().collect();
+        let after: String = chars[range.end..(range.end + SELECTION_CONTEXT_CHARS).min(chars.len())].iter().collect();
+
+        // The innermost section containing the selection
+        let outline = self.documents.get_document_outline(document_id).await?;
+        let section_title = outline.entries.iter()
+            .filter(|entry| entry.start_offset as usize <= range.start && range.start < entry.end_offset as usize)
+            .max_by_key(|entry| entry.level)
+            .map(|entry| entry.title.clone());
+
+        let messages = build_explanation_messages(&document.title, section_title.as_deref(), &before, &selection, &after, level);
+        let request = completion_request(&model, messages, 1000);
+        let response = self.complete(provider, model, request).await?;
+        let explanation = parse_explanation_response(&response)?;
+
+        println!("💡 Explained a {}-character selection of document {}", range.end - range.start, document_id);
+        Ok(SelectionExplanationResult {
+            document_id: document_id.to_string(),
+            range,
+            selection,
+            section_title,
+            level,
+            explanation,
+        })
+    }
+
+    /// Turn a transcript document into outlined notes: each segment is noted separately, then
+    /// the notes are merged into headings with timestamps, bullets, a summary and action
+    /// items. The result is saved as a new note in the transcript's category, and the two
+    /// documents point at each other through their metadata.
+    pub async fn structure_transcript(
+        &self,
+        provider: AIProvider,
+        model: String,
+        document_id: &str,
+    ) -> Result<StructuredTranscriptResult, String> {
+        let transcript = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+            database.get_document(document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or_else(|| format!("Document not found: {}", document_id))?
+        };
+
+        let mut segments = split_transcript(&transcript.content, MAX_SEGMENT_CHARS);
+        if segments.is_empty() {
+            return Err("The transcript is empty".to_string());
+        }
+        let truncated = segments.len() > MAX_SEGMENTS;
+        segments.truncate(MAX_SEGMENTS);
+
+        let mut segment_notes = Vec::with_capacity(segments.len());
+        for (index, segment) in segments.iter().enumerate() {
+            let messages = build_segment_notes_messages(&transcript.title, segment, index, segments.len());
+            let response = self.complete(provider.clone(), model.clone(), completion_request(&model, messages, 1500)).await
+                .map_err(|e| format!("Failed to take notes on part {} of the transcript: {}", index + 1, e))?;
+            segment_notes.push(parse_segment_notes_response(&response)?);
+            println!("🎙️ Noted part {}/{} of transcript {}", index + 1, segments.len(), document_id);
+        }
+
+        let request = completion_request(&model, build_outline_messages(&transcript.title, &segment_notes), 3000);
+        let response = self.complete(provider, model, request).await?;
+        let mut notes = parse_outline_response(&response)?;
+        if notes.title.trim().is_empty() {
+            notes.title = format!("{} (Notes)", transcript.title);
+        }
+
+        let document = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let document = database.create_document(CreateDocumentRequest {
+                title: notes.title.trim().to_string(),
+                content: render_structured_notes(&notes),
+                content_hash: None,
+                file_path: None,
+                doc_type: "note".to_string(),
+                tags: transcript.tags.clone(),
+                status: None,
+                category_id: transcript.category_id.clone(),
+            }).await
+                .map_err(|e| format!("Failed to save notes: {}", e))?;
+
+            database.set_document_metadata_field(&transcript.id, STRUCTURED_NOTES_METADATA_KEY, serde_json::json!(document.id)).await
+                .map_err(|e| format!("Failed to link transcript to notes: {}", e))?;
+            database.set_document_metadata_field(&document.id, TRANSCRIPT_METADATA_KEY, serde_json::json!(transcript.id)).await
+                .map_err(|e| format!("Failed to link notes to transcript: {}", e))?
+                .unwrap_or(document)
+        };
+
+        if let Some(vector_service) = self.vectors.lock().await.as_mut() {
+            if let Err(e) = process_document_embeddings_internal(vector_service, &document).await {
+                eprintln!("⚠️ Failed to embed notes {}: {}", document.id, e);
+            }
+        }
+
+        println!("🎙️ Structured transcript {} into notes {} ({} parts)", document_id, document.id, segments.len());
+        Ok(StructuredTranscriptResult {
+            transcript_document_id: transcript.id,
+            document,
+            notes,
+            segments: segments.len(),
+            truncated,
+        })
+    }
+
+    async fn document_title(&self, document_id: &str) -> Result<String, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.get_document(document_id).await
+            .map_err(|e| format!("Failed to get document: {}", e))?
+            .map(|document| document.title)
+            .ok_or_else(|| format!("Document not found: {}", document_id))
+    }
+
+    async fn complete(&self, provider: AIProvider, model: String, request: ChatCompletionRequest) -> Result<String, String> {
+        let response = self.ai.chat_completion(provider, model, request, None).await?;
+        response.choices.first()
+            .map(|choice| choice.message.content.clone())
+            .filter(|content| !content.trim().is_empty())
+            .ok_or_else(|| "The model returned an empty response".to_string())
+    }
+}
+
+pub(crate) fn completion_request(model: &str, messages: Vec<ChatMessage>, max_tokens: u32) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        messages,
+        model: model.to_string(),
+        temperature: Some(0.3),
+        max_tokens: Some(max_tokens),
+        top_p: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        stream: Some(false),
+    }
+}
+
+/// The entry `heading_path` leads to. Each title is looked for among the children of the
+/// previous match first, then anywhere below it.
+pub fn find_outline_entry<'a>(entries: &'a [OutlineEntry], heading_path: &[String]) -> Option<&'a OutlineEntry> {
+    let mut current: Option<&OutlineEntry> = None;
+
+    for title in heading_path.iter().map(|title| title.trim().to_lowercase()).filter(|title| !title.is_empty()) {
+        let parent = current;
+        let below = |entry: &&OutlineEntry| match parent {
+            Some(parent) => entry.index > parent.index && entry.end_offset <= parent.end_offset,
+            None => true,
+        };
+        let matches = |entry: &&OutlineEntry| entry.title.trim().to_lowercase() == title;
+
+        let child = entries.iter()
+            .filter(below)
+            .filter(|entry| entry.parent_index == parent.map(|parent| parent.index))
+            .find(matches);
+        current = Some(child.or_else(|| entries.iter().filter(below).find(matches))?);
+    }
+
+    current
+}
+
+// Titles from the top-level ancestor down to `entry`
+fn entry_path(entries: &[OutlineEntry], entry: &OutlineEntry) -> Vec<String> {
+    let mut path = vec![entry.title.clone()];
+    let mut parent_index = entry.parent_index;
+    while let Some(parent) = parent_index.and_then(|index| entries.iter().find(|candidate| candidate.index == index)) {
+        path.push(parent.title.clone());
+        parent_index = parent.parent_index;
+    }
+    path.reverse();
+    path
+}
@@ -0,0 +1,51 @@
+use crate::database::{GlobalSearchLimits, GlobalSearchResults};
+use super::{DatabaseState, DATABASE_NOT_INITIALIZED};
+
+// Keeps one search box from pulling whole tables into the UI
+const MAX_RESULTS_PER_TYPE: i64 = 50;
+
+/// One search over everything in the library: documents, highlights, flashcards and
+/// conversations, grouped by type
+#[derive(Clone)]
+pub struct SearchService {
+    database: DatabaseState,
+}
+
+impl SearchService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self { database }
+    }
+
+    /// Results for `query` grouped by type, each group capped by `limits`; a limit of 0
+    /// skips that type
+    pub async fn global_search(&self, query: &str, limits: GlobalSearchLimits) -> Result<GlobalSearchResults, String> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(GlobalSearchResults::default());
+        }
+        let limit = |limit: i64| limit.clamp(0, MAX_RESULTS_PER_TYPE);
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        let mut results = GlobalSearchResults { query: query.to_string(), ..Default::default() };
+
+        if limit(limits.documents) > 0 {
+            results.documents = database.search_document_hits(query, limit(limits.documents)).await
+                .map_err(|e| format!("Failed to search documents: {}", e))?;
+        }
+        if limit(limits.highlights) > 0 {
+            results.highlights = database.search_highlights(query, limit(limits.highlights)).await
+                .map_err(|e| format!("Failed to search highlights: {}", e))?;
+        }
+        if limit(limits.flashcards) > 0 {
+            results.flashcards = database.search_flashcard_hits(query, limit(limits.flashcards)).await
+                .map_err(|e| format!("Failed to search flashcards: {}", e))?;
+        }
+        if limit(limits.conversations) > 0 {
+            results.conversations = database.search_conversation_hits(query, limit(limits.conversations)).await
+                .map_err(|e| format!("Failed to search conversations: {}", e))?;
+        }
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,370 @@
+use std::path::Path;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::ai::guardrails::GUARDRAIL_SETTINGS_KEY;
+use crate::clipboard::CLIPBOARD_WATCHER_SETTINGS_KEY;
+use crate::commands::actions::SESSION_SEGMENTATION_SETTINGS_KEY;
+use crate::commands::embeddings::EMBEDDING_FALLBACK_SETTINGS_KEY;
+use crate::companion::COMPANION_SETTINGS_KEY;
+use crate::database::{
+    CreateAIProfileRequest, Database, NewCardOrder, SaveAutomationScriptRequest, SaveSessionTemplateRequest,
+    ScriptEvent, SessionType, SettingsBundleWrites,
+};
+use crate::maintenance::MAINTENANCE_SETTINGS_KEY;
+use crate::mcp::MCP_SETTINGS_KEY;
+use crate::network::{self, OFFLINE_MODE_KEY, PROXY_SETTINGS_KEY};
+use crate::redaction::{RedactionSettings, Redactor, REDACTION_SETTINGS_KEY};
+use crate::reminders::REMINDER_SETTINGS_KEY;
+use crate::scripting::SCRIPTING_SETTINGS_KEY;
+use crate::throttle::{self, RESOURCE_SETTINGS_KEY};
+use super::{DatabaseState, DATABASE_NOT_INITIALIZED};
+
+pub const SETTINGS_BUNDLE_FORMAT: &str = "stellar-settings";
+pub const SETTINGS_BUNDLE_VERSION: u32 = 1;
+
+// Configuration worth sharing between machines; state such as the selected embedding provider
+// or when reminders last fired stays local
+const BUNDLED_SETTINGS: &[&str] = &[
+    GUARDRAIL_SETTINGS_KEY,
+    OFFLINE_MODE_KEY,
+    PROXY_SETTINGS_KEY,
+    SCRIPTING_SETTINGS_KEY,
+    REMINDER_SETTINGS_KEY,
+    MAINTENANCE_SETTINGS_KEY,
+    COMPANION_SETTINGS_KEY,
+    MCP_SETTINGS_KEY,
+    SESSION_SEGMENTATION_SETTINGS_KEY,
+    EMBEDDING_FALLBACK_SETTINGS_KEY,
+    CLIPBOARD_WATCHER_SETTINGS_KEY,
+    REDACTION_SETTINGS_KEY,
+    RESOURCE_SETTINGS_KEY,
+];
+// Per-provider connection options (organization, API version, extra headers)
+const PROVIDER_CONNECTION_PREFIX: &str = "ai_provider_connection:";
+// (setting, field) pairs left out of bundles; importing keeps this machine's value. A setting
+// ending in ':' covers every key with that prefix.
+const SECRET_FIELDS: &[(&str, &str)] = &[
+    (COMPANION_SETTINGS_KEY, "token"),
+    (MCP_SETTINGS_KEY, "token"),
+    (PROXY_SETTINGS_KEY, "password"),
+    (PROVIDER_CONNECTION_PREFIX, "extra_headers"), // Custom auth headers carry bearer tokens and keys
+];
+
+/// App configuration without library data: settings, AI profiles, session templates, deck
+/// options and automation scripts. API keys, access tokens and custom provider headers are
+/// never included.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsBundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String,
+    #[serde(default)]
+    pub settings: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    pub ai_profiles: Vec<CreateAIProfileRequest>,
+    #[serde(default)]
+    pub session_templates: Vec<BundledSessionTemplate>,
+    #[serde(default)]
+    pub deck_options: Vec<BundledDeckOptions>,
+    #[serde(default)]
+    pub automation_scripts: Vec<BundledAutomationScript>,
+}
+
+/// A user session template without its attached documents and decks, which are library data
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundledSessionTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub session_type: SessionType,
+    pub target_duration_minutes: i64,
+}
+
+/// Options of a deck, applied on import to the deck with the same name
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundledDeckOptions {
+    pub deck_name: String,
+    pub new_card_order: NewCardOrder,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundledAutomationScript {
+    pub name: String,
+    pub event: ScriptEvent,
+    pub source: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SettingsBundleImportResult {
+    pub settings: Vec<String>, // Keys written
+    pub ai_profiles: usize,
+    pub session_templates: usize,
+    pub deck_options: usize,
+    pub missing_decks: Vec<String>, // Deck options with no deck of that name here
+    pub automation_scripts: usize, // Imported disabled unless identical to an enabled script here
+    pub restart_required: bool, // Watchers and servers pick up new settings on the next start
+}
+
+/// Export and import of app configuration
+#[derive(Clone)]
+pub struct SettingsService {
+    database: DatabaseState,
+}
+
+impl SettingsService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self { database }
+    }
+
+    pub async fn get_redaction_settings(&self) -> Result<RedactionSettings, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        load_redaction_settings(database).await
+    }
+
+    /// Save redaction rules; rejected when a pattern doesn't compile
+    pub async fn update_redaction_settings(&self, settings: RedactionSettings) -> Result<RedactionSettings, String> {
+        Redactor::new(&settings)?;
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        database.set_typed_setting(REDACTION_SETTINGS_KEY, &settings).await
+            .map_err(|e| format!("Failed to save redaction settings: {}", e))?;
+        Ok(settings)
+    }
+
+    pub async fn export_bundle(&self) -> Result<SettingsBundle, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let mut settings = serde_json::Map::new();
+        for key in BUNDLED_SETTINGS {
+            let value = database.get_setting(key).await
+                .map_err(|e| format!("Failed to read setting {}: {}", key, e))?;
+            if let Some(mut value) = value {
+                strip_secrets(key, &mut value);
+                settings.insert(key.to_string(), value);
+            }
+        }
+        let connections = database.get_settings_with_prefix(PROVIDER_CONNECTION_PREFIX).await
+            .map_err(|e| format!("Failed to read provider settings: {}", e))?;
+        for (key, mut value) in connections {
+            strip_secrets(&key, &mut value);
+            settings.insert(key, value);
+        }
+
+        let ai_profiles = database.get_ai_profiles().await
+            .map_err(|e| format!("Failed to get AI profiles: {}", e))?
+            .into_iter()
+            .map(|p| CreateAIProfileRequest {
+                name: p.name,
+                description: p.description,
+                system_prompt: p.system_prompt,
+                default_model: p.default_model,
+                temperature: p.temperature,
+                metadata: p.metadata,
+            })
+            .collect();
+
+        let session_templates = database.get_session_templates().await
+            .map_err(|e| format!("Failed to get session templates: {}", e))?
+            .into_iter()
+            .filter(|t| !t.is_builtin)
+            .map(|t| BundledSessionTemplate {
+                name: t.name,
+                description: t.description,
+                session_type: t.session_type,
+                target_duration_minutes: t.target_duration_minutes,
+            })
+            .collect();
+
+        let deck_options = database.get_flashcard_decks().await
+            .map_err(|e| format!("Failed to get decks: {}", e))?
+            .into_iter()
+            .map(|deck| BundledDeckOptions { deck_name: deck.name, new_card_order: deck.new_card_order })
+            .collect();
+
+        let automation_scripts = database.get_automation_scripts().await
+            .map_err(|e| format!("Failed to get automation scripts: {}", e))?
+            .into_iter()
+            .map(|script| BundledAutomationScript {
+                name: script.name,
+                event: script.event,
+                source: script.source,
+                enabled: script.enabled,
+            })
+            .collect();
+
+        Ok(SettingsBundle {
+            format: SETTINGS_BUNDLE_FORMAT.to_string(),
+            version: SETTINGS_BUNDLE_VERSION,
+            exported_at: Utc::now().to_rfc3339(),
+            settings,
+            ai_profiles,
+            session_templates,
+            deck_options,
+            automation_scripts,
+        })
+    }
+
+    /// Apply a bundle written by `export_bundle`. Settings are replaced; profiles, templates and
+    /// scripts with a matching name are updated and the rest created, all in one transaction.
+    /// Scripts run code on library events, so they arrive disabled unless this machine already
+    /// has the same script enabled.
+    pub async fn import_bundle(&self, path: &Path) -> Result<SettingsBundleImportResult, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read settings bundle: {}", e))?;
+        let bundle: SettingsBundle = serde_json::from_str(&json)
+            .map_err(|e| format!("Invalid settings bundle: {}", e))?;
+        if bundle.format != SETTINGS_BUNDLE_FORMAT {
+            return Err(format!("Not a settings bundle: {}", path.display()));
+        }
+        if bundle.version > SETTINGS_BUNDLE_VERSION {
+            return Err(format!("Settings bundle version {} is newer than this app supports", bundle.version));
+        }
+
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+        let mut result = SettingsBundleImportResult::default();
+        let mut writes = SettingsBundleWrites::default();
+
+        for (key, mut value) in bundle.settings {
+            if !BUNDLED_SETTINGS.contains(&key.as_str()) && !key.starts_with(PROVIDER_CONNECTION_PREFIX) {
+                println!("⚠️ Skipping unknown setting in bundle: {}", key);
+                continue;
+            }
+            let current = database.get_setting(&key).await
+                .map_err(|e| format!("Failed to read setting {}: {}", key, e))?;
+            keep_secrets(&key, current.as_ref(), &mut value);
+            writes.settings.push((key, value));
+        }
+
+        for request in bundle.ai_profiles {
+            let existing = database.get_ai_profile_by_name(&request.name).await
+                .map_err(|e| format!("Failed to look up AI profile: {}", e))?;
+            writes.ai_profiles.push((existing.map(|profile| profile.id), request));
+        }
+
+        let templates = database.get_session_templates().await
+            .map_err(|e| format!("Failed to get session templates: {}", e))?;
+        for template in bundle.session_templates {
+            let existing = templates.iter().find(|t| !t.is_builtin && t.name == template.name);
+            writes.session_templates.push(SaveSessionTemplateRequest {
+                id: existing.map(|t| t.id.clone()),
+                name: template.name,
+                description: template.description,
+                session_type: template.session_type,
+                target_duration_minutes: template.target_duration_minutes,
+                // Keep what an existing template has attached on this machine
+                document_ids: existing.map(|t| t.document_ids.clone()).unwrap_or_default(),
+                deck_ids: existing.map(|t| t.deck_ids.clone()).unwrap_or_default(),
+            });
+        }
+
+        let decks = database.get_flashcard_decks().await
+            .map_err(|e| format!("Failed to get decks: {}", e))?;
+        for options in bundle.deck_options {
+            match decks.iter().find(|deck| deck.name == options.deck_name) {
+                Some(deck) => writes.deck_orders.push((deck.id.clone(), options.new_card_order)),
+                None => result.missing_decks.push(options.deck_name),
+            }
+        }
+
+        let scripts = database.get_automation_scripts().await
+            .map_err(|e| format!("Failed to get automation scripts: {}", e))?;
+        for script in bundle.automation_scripts {
+            let existing = scripts.iter().find(|s| s.name == script.name);
+            writes.automation_scripts.push(SaveAutomationScriptRequest {
+                id: existing.map(|s| s.id.clone()),
+                enabled: existing.is_some_and(|s| s.enabled && s.source == script.source),
+                name: script.name,
+                event: script.event,
+                source: script.source,
+            });
+        }
+
+        database.apply_settings_bundle(&writes).await
+            .map_err(|e| format!("Failed to import settings bundle: {}", e))?;
+
+        // Running watchers and servers only see the new settings once they're saved
+        for (key, value) in &writes.settings {
+            match key.as_str() {
+                OFFLINE_MODE_KEY => network::set_offline(value.as_bool().unwrap_or(false)),
+                PROXY_SETTINGS_KEY => {
+                    // Re-read so the locally stored password is applied with the imported settings
+                    if let Ok(settings) = database.get_proxy_settings().await {
+                        if let Err(e) = network::set_proxy_settings(settings) {
+                            eprintln!("⚠️ Imported proxy settings not applied: {}", e);
+                        }
+                    }
+                }
+                RESOURCE_SETTINGS_KEY => {
+                    if let Ok(settings) = serde_json::from_value(value.clone()) {
+                        if let Err(e) = throttle::set_resource_settings(settings) {
+                            eprintln!("⚠️ Imported resource limits not applied: {}", e);
+                        }
+                    }
+                }
+                COMPANION_SETTINGS_KEY | MCP_SETTINGS_KEY | CLIPBOARD_WATCHER_SETTINGS_KEY | REMINDER_SETTINGS_KEY => {
+                    result.restart_required = true;
+                }
+                _ => {}
+            }
+            result.settings.push(key.clone());
+        }
+        result.ai_profiles = writes.ai_profiles.len();
+        result.session_templates = writes.session_templates.len();
+        result.deck_options = writes.deck_orders.len();
+        result.automation_scripts = writes.automation_scripts.len();
+
+        println!(
+            "📦 Imported settings bundle: {} settings, {} AI profiles, {} templates, {} deck options, {} scripts",
+            result.settings.len(), result.ai_profiles, result.session_templates, result.deck_options, result.automation_scripts
+        );
+        Ok(result)
+    }
+}
+
+pub(crate) async fn load_redaction_settings(database: &Database) -> Result<RedactionSettings, String> {
+    database.get_typed_setting(REDACTION_SETTINGS_KEY).await
+        .map_err(|e| format!("Failed to get redaction settings: {}", e))
+}
+
+/// The redactor for exports, or None when export redaction is off or has no enabled rules
+pub(crate) async fn export_redactor(database: &Database) -> Result<Option<Redactor>, String> {
+    let settings = load_redaction_settings(database).await?;
+    if !settings.apply_to_exports {
+        return Ok(None);
+    }
+    Ok(Some(Redactor::new(&settings)?).filter(|redactor| !redactor.is_empty()))
+}
+
+// Secret fields of the setting `key`
+fn secret_fields(key: &str) -> impl Iterator<Item = &'static str> + '_ {
+    SECRET_FIELDS.iter()
+        .filter(move |(setting, _)| *setting == key || (setting.ends_with(':') && key.starts_with(setting)))
+        .map(|(_, field)| *field)
+}
+
+fn strip_secrets(key: &str, value: &mut serde_json::Value) {
+    for field in secret_fields(key) {
+        if let Some(object) = value.as_object_mut() {
+            object.remove(field);
+        }
+    }
+}
+
+// Secrets that arrive in a bundle anyway are dropped, not imported
+fn keep_secrets(key: &str, current: Option<&serde_json::Value>, value: &mut serde_json::Value) {
+    for field in secret_fields(key) {
+        let Some(object) = value.as_object_mut() else {
+            continue;
+        };
+        match current.and_then(|current| current.get(field)) {
+            Some(secret) => object.insert(field.to_string(), secret.clone()),
+            None => object.remove(field),
+        };
+    }
+}
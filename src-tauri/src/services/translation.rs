@@ -0,0 +1,274 @@
+use crate::ai::{AIProvider, ChatCompletionRequest, ChatMessage};
+use crate::database::{CreateDocumentRequest, Database, Document, DocumentTranslation, TranslationResult};
+use crate::language;
+use super::{AiService, DatabaseState, DATABASE_NOT_INITIALIZED};
+
+// Paragraphs longer than this are split between sentences to fit every model's output limit
+const MAX_CHUNK_CHARS: usize = 3000;
+const MAX_TRANSLATION_TOKENS: u32 = 4096;
+
+/// Translating text and documents with a chat model. Each paragraph is translated and cached
+/// on its own, so translating an edited document again only sends the paragraphs that changed.
+#[derive(Clone)]
+pub struct TranslationService {
+    database: DatabaseState,
+    ai: AiService,
+}
+
+impl TranslationService {
+    pub fn new(database: DatabaseState) -> Self {
+        Self { ai: AiService::new(database.clone()), database }
+    }
+
+    pub async fn translate_text(
+        &self,
+        provider: AIProvider,
+        model: String,
+        text: &str,
+        target_language: &str,
+    ) -> Result<TranslationResult, String> {
+        let target_language = normalize_target(target_language)?;
+        if text.trim().is_empty() {
+            return Err("Nothing to translate".to_string());
+        }
+
+        let (translation, chunks, cached_chunks) = self.translate_chunks(&provider, &model, text, &target_language).await?;
+        Ok(TranslationResult {
+            translation,
+            source_language: language::detect_language(text),
+            target_language,
+            chunks,
+            cached_chunks,
+            document: None,
+        })
+    }
+
+    /// Translate a document and keep the result as a linked document, which is reused as
+    /// long as the source hasn't changed. `save: false` only returns the translated text.
+    pub async fn translate_document(
+        &self,
+        provider: AIProvider,
+        model: String,
+        document_id: &str,
+        target_language: &str,
+        save: bool,
+    ) -> Result<TranslationResult, String> {
+        let target_language = normalize_target(target_language)?;
+        let (document, existing) = {
+            let db_state = self.database.lock().await;
+            let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+            let document = database.get_document(document_id).await
+                .map_err(|e| format!("Failed to get document: {}", e))?
+                .ok_or_else(|| format!("Document not found: {}", document_id))?;
+            let existing = database.get_document_translation(document_id, &target_language).await
+                .map_err(|e| format!("Failed to get document translation: {}", e))?;
+            let existing = match existing {
+                Some(link) => database.get_document(&link.translated_document_id).await
+                    .map_err(|e| format!("Failed to get translated document: {}", e))?
+                    .map(|translated| (link, translated)),
+                None => None,
+            };
+            (document, existing)
+        };
+
+        let source_hash = document.content_hash.clone()
+            .unwrap_or_else(|| Database::calculate_content_hash(&document.content));
+        if let Some((link, translated)) = &existing {
+            if link.source_content_hash == source_hash {
+                println!("🌐 Reusing {} translation of document {}", target_language, document_id);
+                return Ok(TranslationResult {
+                    translation: translated.content.clone(),
+                    source_language: document.language.clone(),
+                    target_language,
+                    chunks: 0,
+                    cached_chunks: 0,
+                    document: Some(translated.clone()),
+                });
+            }
+        }
+
+        let (translation, chunks, cached_chunks) = self.translate_chunks(&provider, &model, &document.content, &target_language).await?;
+        println!("🌐 Translated document {} into {} ({} chunks, {} cached)", document_id, target_language, chunks, cached_chunks);
+
+        let saved = if save {
+            Some(self.save_translation(&document, existing.map(|(_, translated)| translated), &translation, &target_language, &source_hash, &model).await?)
+        } else {
+            None
+        };
+
+        Ok(TranslationResult {
+            translation,
+            source_language: document.language.clone(),
+            target_language,
+            chunks,
+            cached_chunks,
+            document: saved,
+        })
+    }
+
+    pub async fn get_document_translations(&self, document_id: &str) -> Result<Vec<DocumentTranslation>, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        database.get_document_translations(document_id).await
+            .map_err(|e| format!("Failed to get document translations: {}", e))
+    }
+
+    // Returns (translation, chunk count, chunks served from the cache)
+    async fn translate_chunks(
+        &self,
+        provider: &AIProvider,
+        model: &str,
+        text: &str,
+        target_language: &str,
+    ) -> Result<(String, usize, usize), String> {
+        let chunks = split_into_chunks(text);
+        let mut translated = Vec::with_capacity(chunks.len());
+        let mut cached_chunks = 0;
+
+        for chunk in &chunks {
+            let text_hash = Database::calculate_content_hash(chunk);
+            let cached = {
+                let db_state = self.database.lock().await;
+                let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+                database.get_cached_translation(&text_hash, target_language, model).await
+                    .map_err(|e| format!("Failed to read translation cache: {}", e))?
+            };
+            if let Some(cached) = cached {
+                cached_chunks += 1;
+                translated.push(cached);
+                continue;
+            }
+
+            let translation = self.translate_chunk(provider, model, chunk, target_language).await?;
+            {
+                let db_state = self.database.lock().await;
+                let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+                database.cache_translation(&text_hash, target_language, model, &translation).await
+                    .map_err(|e| format!("Failed to cache translation: {}", e))?;
+            }
+            translated.push(translation);
+        }
+
+        Ok((translated.join("\n\n"), chunks.len(), cached_chunks))
+    }
+
+    async fn translate_chunk(&self, provider: &AIProvider, model: &str, chunk: &str, target_language: &str) -> Result<String, String> {
+        let request = ChatCompletionRequest {
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: format!(
+                        "You are a translator. Translate the user's text into {}. Keep Markdown formatting, \
+                         code, math, citations and reference numbers exactly as they are. Reply with the \
+                         translation only, without notes or explanations.",
+                        target_language
+                    ),
+                },
+                ChatMessage { role: "user".to_string(), content: chunk.to_string() },
+            ],
+            model: model.to_string(),
+            temperature: Some(0.0),
+            max_tokens: Some(MAX_TRANSLATION_TOKENS),
+            top_p: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            stream: Some(false),
+        };
+
+        let response = self.ai.chat_completion(provider.clone(), model.to_string(), request, None).await?;
+        let translation = response.choices.first()
+            .map(|choice| choice.message.content.trim().to_string())
+            .unwrap_or_default();
+        if translation.is_empty() {
+            return Err("The model returned an empty translation".to_string());
+        }
+        Ok(translation)
+    }
+
+    // Store the translation as its own document next to the source, or refresh the one
+    // made from an earlier version of it
+    async fn save_translation(
+        &self,
+        source: &Document,
+        previous: Option<Document>,
+        translation: &str,
+        target_language: &str,
+        source_hash: &str,
+        model: &str,
+    ) -> Result<Document, String> {
+        let db_state = self.database.lock().await;
+        let database = db_state.as_ref().ok_or(DATABASE_NOT_INITIALIZED)?;
+
+        let document = match previous {
+            Some(previous) => database.replace_document_content(&previous.id, translation).await
+                .map_err(|e| format!("Failed to update translated document: {}", e))?
+                .map(|(document, _)| document)
+                .ok_or("Translated document not found")?,
+            None => database.create_document(CreateDocumentRequest {
+                title: format!("{} ({})", source.title, target_language),
+                content: translation.to_string(),
+                content_hash: None,
+                file_path: None,
+                doc_type: source.doc_type.clone(),
+                tags: source.tags.clone(),
+                status: None,
+                category_id: source.category_id.clone(),
+            }).await
+                .map_err(|e| format!("Failed to save translated document: {}", e))?,
+        };
+
+        database.save_document_translation(&source.id, target_language, &document.id, source_hash, model).await
+            .map_err(|e| format!("Failed to link translated document: {}", e))?;
+        database.set_document_metadata_field(&document.id, "translation", serde_json::json!({
+            "source_document_id": source.id,
+            "source_language": source.language,
+            "target_language": target_language,
+            "model": model,
+        })).await
+            .map_err(|e| format!("Failed to record translation source: {}", e))?
+            .ok_or_else(|| "Translated document not found".to_string())
+    }
+}
+
+// Known languages are keyed by their English name, so "en", "eng" and "English" share
+// translations and the cache
+fn normalize_target(target_language: &str) -> Result<String, String> {
+    let target = target_language.trim().to_lowercase();
+    if target.is_empty() {
+        return Err("A target language is required".to_string());
+    }
+    Ok(language::language_name(&target).map(str::to_lowercase).unwrap_or(target))
+}
+
+// One chunk per paragraph, so an edit never moves the boundaries of the chunks around it.
+// Paragraphs over MAX_CHUNK_CHARS are split between sentences.
+fn split_into_chunks(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if paragraph.chars().count() <= MAX_CHUNK_CHARS {
+            chunks.push(paragraph.to_string());
+            continue;
+        }
+
+        let mut current = String::new();
+        for sentence in language::split_sentences(paragraph) {
+            let separator = if language::needs_space_between(current.chars().last(), sentence.chars().next()) { " " } else { "" };
+            push_piece(&mut chunks, &mut current, sentence, separator);
+        }
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn push_piece(chunks: &mut Vec<String>, current: &mut String, piece: &str, separator: &str) {
+    if !current.is_empty() && current.chars().count() + piece.chars().count() > MAX_CHUNK_CHARS {
+        chunks.push(std::mem::take(current));
+    }
+    if !current.is_empty() {
+        current.push_str(separator);
+    }
+    current.push_str(piece);
+}
@@ -0,0 +1,70 @@
+//! Orderly exit. Quitting the app used to drop the runtime mid-write: jobs stayed marked as
+//! processing, the WAL was left unmerged and files could be half written. On exit we now stop
+//! the background processor, requeue the job it was on, checkpoint both databases and close
+//! them before letting the process end.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, ExitRequestApi, Manager};
+
+use crate::background_processor::BackgroundProcessorState;
+use crate::services::{DatabaseState, VectorServiceState};
+
+// How long the job in flight may take to finish before it is requeued instead
+const JOB_GRACE_PERIOD: Duration = Duration::from_secs(5);
+// Exit anyway if something holds a lock for longer than this
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Handle `RunEvent::ExitRequested`: hold the exit, shut down, then exit with the requested
+/// code. The exit we trigger ourselves comes back through here and is let through.
+pub fn on_exit_requested(app: &AppHandle, code: Option<i32>, api: &ExitRequestApi) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown(&app)).await.is_err() {
+            eprintln!("⚠️ Shutdown timed out, exiting anyway");
+        }
+        app.exit(code.unwrap_or(0));
+    });
+}
+
+/// Shut down as on exit, then restart the process (e.g. into another profile). Tauri ignores
+/// `prevent_exit` for restarts, so the shutdown has to finish before the restart is requested.
+pub async fn restart(app: &AppHandle) {
+    if SHUTTING_DOWN.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown(app)).await.is_err() {
+        eprintln!("⚠️ Shutdown timed out, restarting anyway");
+    }
+    app.request_restart();
+}
+
+async fn shutdown(app: &AppHandle) {
+    println!("🛑 Shutting down...");
+
+    let processor = app.state::<BackgroundProcessorState>().lock().await.take();
+    if let Some(processor) = processor {
+        processor.shutdown(JOB_GRACE_PERIOD).await;
+    }
+
+    if let Some(mut vector_service) = app.state::<VectorServiceState>().lock().await.take() {
+        if let Err(e) = vector_service.checkpoint() {
+            eprintln!("⚠️ Failed to checkpoint the embeddings database: {}", e);
+        }
+    }
+
+    if let Some(database) = app.state::<DatabaseState>().lock().await.take() {
+        if let Err(e) = database.close().await {
+            eprintln!("⚠️ Failed to checkpoint the database: {}", e);
+        }
+    }
+
+    println!("✅ Shut down cleanly");
+}
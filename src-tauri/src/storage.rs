@@ -0,0 +1,198 @@
+//! Disk space checks and a breakdown of what the data dir is spent on. Imports check for room
+//! before copying PDFs or writing temp files, and the embeddings database before it grows, so
+//! a full disk fails with `StorageError::InsufficientDiskSpace` up front instead of leaving a
+//! truncated file or a database that can't commit.
+
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+
+/// Every disk space error message starts with this, so the frontend can recognise it
+pub const STORAGE_ERROR_PREFIX: &str = "insufficient_disk_space:";
+
+// Left free after a write; SQLite needs room for its journal and the OS for everything else
+const MIN_FREE_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub enum StorageError {
+    InsufficientDiskSpace { path: PathBuf, needed: u64, available: u64 },
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::InsufficientDiskSpace { path, needed, available } => write!(
+                f,
+                "{} Not enough disk space for {}: {} needed, {} available",
+                STORAGE_ERROR_PREFIX, path.display(), format_size(*needed), format_size(*available)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<StorageError> for String {
+    fn from(error: StorageError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Fail unless writing `bytes` under `path` leaves MIN_FREE_BYTES free. `path` may not exist
+/// yet; its nearest existing ancestor is checked. When free space can't be read the write is
+/// allowed.
+pub fn ensure_space(path: &Path, bytes: u64) -> Result<(), StorageError> {
+    let Some(available) = available_space(path) else {
+        return Ok(());
+    };
+    let needed = bytes.saturating_add(MIN_FREE_BYTES);
+    if available < needed {
+        return Err(StorageError::InsufficientDiskSpace { path: path.to_path_buf(), needed: bytes, available });
+    }
+    Ok(())
+}
+
+/// `ensure_space` for copying the file at `source` to `target`
+pub fn ensure_space_for_copy(source: &Path, target: &Path) -> Result<(), StorageError> {
+    let size = std::fs::metadata(source).map(|metadata| metadata.len()).unwrap_or(0);
+    ensure_space(target, size)
+}
+
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|ancestor| ancestor.exists())?;
+    fs2::available_space(existing)
+        .map_err(|e| eprintln!("⚠️ Failed to read free space for {}: {}", existing.display(), e))
+        .ok()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageCategory {
+    pub key: String, // "database", "embeddings", "pdfs", ...
+    pub label: String,
+    pub size: u64,
+    pub size_formatted: String,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageBreakdown {
+    pub data_directory: String,
+    pub total_size: u64,
+    pub total_size_formatted: String,
+    pub available_space: Option<u64>,
+    pub available_space_formatted: Option<String>,
+    pub categories: Vec<StorageCategory>, // Largest first; "other" is whatever no category covers
+}
+
+// (key, label, files or directories relative to the profile dir)
+const CATEGORIES: &[(&str, &str, &[&str])] = &[
+    ("database", "Library database", &["documents.db", "documents.db-wal", "documents.db-shm"]),
+    ("embeddings", "Embeddings", &["embeddings.db", "embeddings.db-wal", "embeddings.db-shm"]),
+    ("pdfs", "PDFs", &["pdfs"]),
+    ("attachments", "Images, audio and covers", &["attachments"]),
+    ("backups", "Backups", &["backups"]),
+    ("job_archive", "Archived jobs", &["job_archive"]),
+];
+
+/// Sizes of the active profile's data by category, plus shared models and crash reports
+pub fn storage_breakdown() -> Result<StorageBreakdown, String> {
+    let profile_dir = paths::app_data_dir()?;
+    let root_dir = paths::root_data_dir()?;
+
+    let mut categories = Vec::new();
+    for (key, label, entries) in CATEGORIES {
+        let (size, file_count) = entries.iter()
+            .map(|entry| path_size(&profile_dir.join(entry)))
+            .fold((0, 0), |(size, count), (entry_size, entry_count)| (size + entry_size, count + entry_count));
+        categories.push(category(key, label, size, file_count));
+    }
+    for (key, label, dir) in [("models", "Downloaded models", "models"), ("crash_reports", "Crash reports", "crash_reports")] {
+        let (size, file_count) = path_size(&root_dir.join(dir));
+        categories.push(category(key, label, size, file_count));
+    }
+
+    // The default profile's dir is the root, which also holds the other profiles and the
+    // shared directories counted above
+    let mut excluded: Vec<PathBuf> = vec![root_dir.join("models"), root_dir.join("crash_reports")];
+    if profile_dir == root_dir {
+        excluded.push(crate::profiles::profiles_root(&root_dir));
+    }
+    let (profile_size, profile_files) = path_size_excluding(&profile_dir, &excluded);
+    let counted: u64 = categories.iter()
+        .filter(|category| category.key != "models" && category.key != "crash_reports")
+        .map(|category| category.size)
+        .sum();
+    let counted_files: u64 = categories.iter()
+        .filter(|category| category.key != "models" && category.key != "crash_reports")
+        .map(|category| category.file_count)
+        .sum();
+    categories.push(category("other", "Other", profile_size.saturating_sub(counted), profile_files.saturating_sub(counted_files)));
+
+    categories.retain(|category| category.size > 0);
+    categories.sort_by(|a, b| b.size.cmp(&a.size));
+    let total_size = categories.iter().map(|category| category.size).sum();
+    let available_space = available_space(&profile_dir);
+
+    Ok(StorageBreakdown {
+        data_directory: profile_dir.to_string_lossy().to_string(),
+        total_size,
+        total_size_formatted: format_size(total_size),
+        available_space,
+        available_space_formatted: available_space.map(format_size),
+        categories,
+    })
+}
+
+fn category(key: &str, label: &str, size: u64, file_count: u64) -> StorageCategory {
+    StorageCategory {
+        key: key.to_string(),
+        label: label.to_string(),
+        size,
+        size_formatted: format_size(size),
+        file_count,
+    }
+}
+
+// (bytes, files) of a file or directory tree; missing paths count as empty
+fn path_size(path: &Path) -> (u64, u64) {
+    path_size_excluding(path, &[])
+}
+
+fn path_size_excluding(path: &Path, excluded: &[PathBuf]) -> (u64, u64) {
+    if excluded.iter().any(|excluded| excluded == path) {
+        return (0, 0);
+    }
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), 1);
+    }
+
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries.flatten()
+                .map(|entry| path_size_excluding(&entry.path(), excluded))
+                .fold((0, 0), |(size, count), (entry_size, entry_count)| (size + entry_size, count + entry_count))
+        })
+        .unwrap_or((0, 0))
+}
+
+pub fn format_size(size: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut size_f = size as f64;
+    let mut unit_index = 0;
+
+    while size_f >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size_f /= 1024.0;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        format!("{} {}", size, UNITS[unit_index])
+    } else {
+        format!("{:.1} {}", size_f, UNITS[unit_index])
+    }
+}
@@ -0,0 +1,153 @@
+//! Temp files written while importing: copies of uploaded PDFs being extracted, background
+//! downloads, clipped pages waiting for their job and Marker's output. Each one gets a
+//! directory of its own under a `stellar_*` dir of the system temp dir and is owned by a
+//! `TempFileGuard`, which deletes it when dropped, so an import that fails halfway doesn't
+//! leave its copy behind. Whatever outlives a crash is swept at startup once it's a day old.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Anything older than this can't belong to an import that is still running
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempKind {
+    Processing,   // Copies of uploaded or downloaded PDFs handed to the extractors
+    Downloads,    // PDFs downloaded by URL jobs
+    Clips,        // Pages sent by the browser extension, read by their web clip job
+    MarkerOutput, // marker_single's output directories
+    Screenshots,  // Equation screenshots handed to pix2tex
+}
+
+const ALL_KINDS: [TempKind; 5] = [TempKind::Processing, TempKind::Downloads, TempKind::Clips, TempKind::MarkerOutput, TempKind::Screenshots];
+
+impl TempKind {
+    fn dir_name(self) -> &'static str {
+        match self {
+            TempKind::Processing => "stellar_processing",
+            TempKind::Downloads => "stellar_downloads",
+            TempKind::Clips => "stellar_clips",
+            TempKind::MarkerOutput => "stellar_marker_output",
+            TempKind::Screenshots => "stellar_screenshots",
+        }
+    }
+
+    fn root(self) -> PathBuf {
+        std::env::temp_dir().join(self.dir_name())
+    }
+}
+
+/// A temp file (or directory) that is deleted when the guard is dropped, unless `keep` was
+/// called
+#[derive(Debug)]
+pub struct TempFileGuard {
+    dir: PathBuf, // Deleted on drop; the file's own directory, or the file itself for files adopted from before guards
+    path: PathBuf,
+    keep: bool,
+}
+
+impl TempFileGuard {
+    /// Reserve `file_name` in a new directory, so two imports of files with the same name
+    /// don't overwrite each other. Nothing is written yet; the name is kept because the
+    /// extractors fall back to it for the title.
+    pub fn new(kind: TempKind, file_name: &str) -> Result<Self, String> {
+        let dir = create_unique_dir(kind)?;
+        let file_name = Path::new(file_name)
+            .file_name()
+            .map(|name| name.to_os_string())
+            .unwrap_or_else(|| "file".into());
+        Ok(Self { path: dir.join(file_name), dir, keep: false })
+    }
+
+    /// A new empty directory, for tools that write several output files
+    pub fn dir(kind: TempKind) -> Result<Self, String> {
+        let dir = create_unique_dir(kind)?;
+        Ok(Self { path: dir.clone(), dir, keep: false })
+    }
+
+    /// Take over a temp file created earlier, e.g. for the job that was queued with it. Paths
+    /// outside the temp dirs (like PDFs in the library) are never adopted.
+    pub fn adopt(path: &Path) -> Option<Self> {
+        let parent = path.parent()?;
+        for kind in ALL_KINDS {
+            let root = kind.root();
+            if parent == root {
+                return Some(Self { dir: path.to_path_buf(), path: path.to_path_buf(), keep: false });
+            }
+            if parent.parent() == Some(root.as_path()) {
+                return Some(Self { dir: parent.to_path_buf(), path: path.to_path_buf(), keep: false });
+            }
+        }
+        None
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Leave the file in place for whoever reads it next, e.g. a queued job that adopts it.
+    /// The startup sweep still removes it once it's stale.
+    pub fn keep(mut self) -> PathBuf {
+        self.keep = true;
+        self.path.clone()
+    }
+}
+
+impl Drop for TempFileGuard {
+    fn drop(&mut self) {
+        if self.keep {
+            return;
+        }
+        if let Err(e) = remove_path(&self.dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("⚠️ Failed to delete temp file {}: {}", self.dir.display(), e);
+            }
+        }
+    }
+}
+
+fn create_unique_dir(kind: TempKind) -> Result<PathBuf, String> {
+    let dir = kind.root().join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    Ok(dir)
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Delete temp files older than a day, except those in `in_use` (source files of jobs still
+/// queued). Returns how many entries were removed.
+pub fn sweep_stale_temp_files(in_use: &HashSet<PathBuf>) -> usize {
+    let now = SystemTime::now();
+    let mut removed = 0;
+
+    for kind in ALL_KINDS {
+        let Ok(entries) = std::fs::read_dir(kind.root()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_stale = entry.metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .map_or(false, |age| age > STALE_AFTER);
+            if !is_stale || in_use.iter().any(|used| used.starts_with(&path)) {
+                continue;
+            }
+            match remove_path(&path) {
+                Ok(()) => removed += 1,
+                Err(e) => eprintln!("⚠️ Failed to delete stale temp file {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    removed
+}
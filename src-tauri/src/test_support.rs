@@ -0,0 +1,229 @@
+//! Building blocks for integration tests (see `tests/`): an in-memory library database, an
+//! embeddings database with a deterministic `MockEmbeddingGenerator`, and a `MockChatProvider`
+//! that serves the OpenAI chat API on localhost, so the real provider code runs against canned
+//! replies. Nothing here touches the network or the user's data dir.
+
+use async_trait::async_trait;
+use axum::extract::State;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::task::AbortHandle;
+
+use crate::ai::types::AIProvider;
+use crate::database::{CreateDocumentRequest, Database, Document};
+use crate::embeddings::{DocumentChunker, EmbeddingGenerator, VectorService};
+use crate::services::{DatabaseState, VectorServiceState};
+
+pub const MOCK_DIMENSIONS: usize = 64;
+pub const MOCK_MODEL: &str = "mock-model";
+const DEFAULT_REPLY: &str = "Mock reply";
+
+/// Embeds text as hashed word counts, normalised: texts sharing words score higher, and the
+/// same text always gets the same vector
+pub struct MockEmbeddingGenerator {
+    dimensions: usize,
+    texts_embedded: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockEmbeddingGenerator {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions,
+            texts_embedded: Arc::new(AtomicUsize::new(0)),
+            requests: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Counters that stay readable after the generator is moved into a `VectorService`:
+    /// (texts embedded, `generate_embeddings` calls)
+    pub fn counters(&self) -> (Arc<AtomicUsize>, Arc<AtomicUsize>) {
+        (self.texts_embedded.clone(), self.requests.clone())
+    }
+
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+            // FNV-1a, so vectors don't change between runs or Rust versions
+            let hash = word.to_lowercase().bytes()
+                .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3));
+            vector[(hash % self.dimensions as u64) as usize] += 1.0;
+        }
+
+        let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm == 0.0 {
+            // Cosine similarity of a zero vector is NaN
+            vector[0] = 1.0;
+        } else {
+            vector.iter_mut().for_each(|v| *v /= norm);
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingGenerator for MockEmbeddingGenerator {
+    async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn std::error::Error>> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        self.texts_embedded.fetch_add(texts.len(), Ordering::SeqCst);
+        Ok(texts.iter().map(|text| self.embed(text)).collect())
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+}
+
+#[derive(Default)]
+struct MockChatState {
+    replies: Mutex<VecDeque<String>>,
+    requests: Mutex<Vec<Value>>,
+}
+
+/// An OpenAI-compatible endpoint on 127.0.0.1 answering `chat/completions` with queued replies
+/// (or "Mock reply" once they run out) and `models` with `MOCK_MODEL`. Streaming isn't
+/// supported. The server stops when this is dropped.
+pub struct MockChatProvider {
+    base_url: String,
+    state: Arc<MockChatState>,
+    task: AbortHandle,
+}
+
+impl MockChatProvider {
+    pub async fn start() -> Result<Self, String> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await
+            .map_err(|e| format!("Failed to start mock chat provider: {}", e))?;
+        let address = listener.local_addr()
+            .map_err(|e| format!("Failed to start mock chat provider: {}", e))?;
+
+        let state = Arc::new(MockChatState::default());
+        let router = Router::new()
+            .route("/v1/chat/completions", post(chat_completions))
+            .route("/v1/models", get(models))
+            .with_state(state.clone());
+        let task = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                eprintln!("❌ Mock chat provider stopped: {}", e);
+            }
+        });
+
+        Ok(Self {
+            base_url: format!("http://{}/v1", address),
+            state,
+            task: task.abort_handle(),
+        })
+    }
+
+    /// A "custom" (OpenAI-compatible) provider pointing at this server. Requests still need an
+    /// API key; any value is accepted.
+    pub fn provider(&self) -> AIProvider {
+        AIProvider {
+            id: "mock".to_string(),
+            r#type: "custom".to_string(),
+            base_url: self.base_url.clone(),
+            api_key: Some("mock-key".to_string()),
+            organization: None,
+            api_version: None,
+            extra_headers: HashMap::new(),
+        }
+    }
+
+    /// Queue the content of the next completion
+    pub fn push_reply(&self, reply: impl Into<String>) {
+        if let Ok(mut replies) = self.state.replies.lock() {
+            replies.push_back(reply.into());
+        }
+    }
+
+    /// Request bodies received so far, oldest first
+    pub fn requests(&self) -> Vec<Value> {
+        self.state.requests.lock().map(|requests| requests.clone()).unwrap_or_default()
+    }
+}
+
+impl Drop for MockChatProvider {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn chat_completions(State(state): State<Arc<MockChatState>>, Json(body): Json<Value>) -> Json<Value> {
+    let reply = state.replies.lock().ok()
+        .and_then(|mut replies| replies.pop_front())
+        .unwrap_or_else(|| DEFAULT_REPLY.to_string());
+    let model = body["model"].as_str().unwrap_or(MOCK_MODEL).to_string();
+    if let Ok(mut requests) = state.requests.lock() {
+        requests.push(body);
+    }
+
+    Json(json!({
+        "id": format!("chatcmpl-mock-{}", uuid::Uuid::new_v4()),
+        "object": "chat.completion",
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": reply },
+            "finish_reason": "stop",
+        }],
+        "usage": { "prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0 },
+    }))
+}
+
+async fn models() -> Json<Value> {
+    Json(json!({ "object": "list", "data": [{ "id": MOCK_MODEL, "object": "model" }] }))
+}
+
+// ===== Fixtures =====
+
+pub async fn test_database() -> Database {
+    Database::new_in_memory().await.expect("Failed to create in-memory database")
+}
+
+pub fn test_vector_service() -> VectorService {
+    VectorService::with_generator(":memory:", Box::new(MockEmbeddingGenerator::new(MOCK_DIMENSIONS)), "mock".to_string())
+        .expect("Failed to create in-memory vector service")
+}
+
+/// Both services in the shared state the commands and services take
+pub async fn test_states() -> (DatabaseState, VectorServiceState) {
+    (
+        Arc::new(tokio::sync::Mutex::new(Some(test_database().await))),
+        Arc::new(tokio::sync::Mutex::new(Some(test_vector_service()))),
+    )
+}
+
+/// A ready markdown document
+pub async fn insert_document(database: &Database, title: &str, content: &str) -> Document {
+    database.create_document(CreateDocumentRequest {
+        title: title.to_string(),
+        content: content.to_string(),
+        content_hash: None,
+        file_path: None,
+        doc_type: "markdown".to_string(),
+        tags: Vec::new(),
+        status: Some("ready".to_string()),
+        category_id: None,
+    }).await.expect("Failed to insert document")
+}
+
+/// Chunk and embed a document the way imports do, returning the number of chunks
+pub async fn index_document(vector_service: &mut VectorService, document: &Document) -> usize {
+    let chunks = DocumentChunker::with_default_strategy()
+        .chunk_document(&document.id, &document.content, HashMap::new())
+        .expect("Failed to chunk document");
+    vector_service.add_document_chunks(&chunks).await.expect("Failed to embed document");
+    chunks.len()
+}
+
+/// (title, content) of documents on unrelated topics, a chunk each
+pub fn sample_documents() -> Vec<(&'static str, String)> {
+    vec![
+        ("Photosynthesis", "Photosynthesis converts light energy into chemical energy. Chlorophyll in the chloroplasts absorbs light, and the plant uses it to turn carbon dioxide and water into glucose and oxygen.\n\nThe light reactions take place in the thylakoid membranes, the Calvin cycle in the stroma.".to_string()),
+        ("French Revolution", "The French Revolution began in 1789 with the storming of the Bastille. The monarchy was abolished and King Louis XVI was executed in 1793.\n\nThe revolution ended with Napoleon Bonaparte seizing power in 1799.".to_string()),
+        ("Binary search", "Binary search finds an element in a sorted array by repeatedly halving the search interval. Each comparison discards half of the remaining elements, so it runs in logarithmic time.\n\nIt requires random access and a sorted array.".to_string()),
+    ]
+}
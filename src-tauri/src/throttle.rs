@@ -0,0 +1,173 @@
+//! Limits on background work so imports and indexing don't make the UI stutter on laptops:
+//! how many embedding batches run at once, the niceness OCR subprocesses (tesseract, marker)
+//! start with, and pausing the background processor on battery or while a review session is
+//! being answered.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::database::Database;
+
+pub const RESOURCE_SETTINGS_KEY: &str = "resource_limits";
+
+// Texts per embedding request; batches are what `max_embedding_batches` limits
+pub const EMBEDDING_BATCH_SIZE: usize = 32;
+const MAX_NICENESS: i32 = 19;
+// A review counts as in progress while its last answer is at most this old
+const REVIEW_IDLE_MINUTES: i64 = 10;
+// Checking the power source spawns a process on macOS and Windows
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceSettings {
+    #[serde(default = "default_max_embedding_batches")]
+    pub max_embedding_batches: usize,
+    #[serde(default = "default_ocr_niceness")]
+    pub ocr_niceness: i32, // 0 (normal priority) to 19 (lowest); below-normal priority on Windows when > 0
+    #[serde(default = "default_true")]
+    pub pause_on_battery: bool,
+    #[serde(default = "default_true")]
+    pub pause_during_review: bool,
+}
+
+fn default_max_embedding_batches() -> usize {
+    2
+}
+
+fn default_ocr_niceness() -> i32 {
+    10
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ResourceSettings {
+    fn default() -> Self {
+        Self {
+            max_embedding_batches: default_max_embedding_batches(),
+            ocr_niceness: default_ocr_niceness(),
+            pause_on_battery: true,
+            pause_during_review: true,
+        }
+    }
+}
+
+static RESOURCE_SETTINGS: RwLock<Option<ResourceSettings>> = RwLock::new(None);
+static POWER_STATE: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+pub fn resource_settings() -> ResourceSettings {
+    RESOURCE_SETTINGS.read()
+        .map(|settings| settings.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Validate and apply resource settings; running work picks them up at its next batch or job
+pub fn set_resource_settings(settings: ResourceSettings) -> Result<(), String> {
+    if settings.max_embedding_batches == 0 {
+        return Err("At least one embedding batch must be allowed".to_string());
+    }
+    if !(0..=MAX_NICENESS).contains(&settings.ocr_niceness) {
+        return Err(format!("OCR niceness must be between 0 and {}", MAX_NICENESS));
+    }
+    if let Ok(mut current) = RESOURCE_SETTINGS.write() {
+        *current = Some(settings);
+    }
+    Ok(())
+}
+
+/// Start an OCR subprocess at the configured lower priority
+pub fn lower_priority(cmd: &mut tokio::process::Command) {
+    let niceness = resource_settings().ocr_niceness;
+    if niceness <= 0 {
+        return;
+    }
+
+    #[cfg(unix)]
+    unsafe {
+        // Runs in the forked child before exec; nice(2) is async-signal-safe
+        cmd.pre_exec(move || {
+            libc::nice(niceness);
+            Ok(())
+        });
+    }
+    #[cfg(windows)]
+    {
+        const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+        cmd.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+    }
+}
+
+/// Why background processing should wait right now, if it should
+pub async fn pause_reason(database: &Database) -> Option<&'static str> {
+    let settings = resource_settings();
+    if settings.pause_on_battery && on_battery() {
+        return Some("running on battery");
+    }
+    if settings.pause_during_review {
+        match database.has_review_in_progress(REVIEW_IDLE_MINUTES).await {
+            Ok(true) => return Some("a review session is in progress"),
+            Ok(false) => {}
+            Err(e) => eprintln!("⚠️ Failed to check for a review in progress: {}", e),
+        }
+    }
+    None
+}
+
+/// Whether the machine runs on battery, checked at most every 30 seconds. Unknown counts as
+/// plugged in.
+pub fn on_battery() -> bool {
+    if let Ok(state) = POWER_STATE.lock() {
+        if let Some((checked_at, on_battery)) = *state {
+            if checked_at.elapsed() < POWER_CHECK_INTERVAL {
+                return on_battery;
+            }
+        }
+    }
+
+    let on_battery = detect_on_battery().unwrap_or(false);
+    if let Ok(mut state) = POWER_STATE.lock() {
+        *state = Some((Instant::now(), on_battery));
+    }
+    on_battery
+}
+
+#[cfg(target_os = "linux")]
+fn detect_on_battery() -> Option<bool> {
+    let mut has_battery = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let read = |name: &str| std::fs::read_to_string(entry.path().join(name)).map(|value| value.trim().to_string()).unwrap_or_default();
+        match read("type").as_str() {
+            "Mains" | "USB" if read("online") == "1" => return Some(false),
+            "Battery" => has_battery |= read("status") == "Discharging",
+            _ => {}
+        }
+    }
+    Some(has_battery)
+}
+
+#[cfg(target_os = "macos")]
+fn detect_on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+}
+
+#[cfg(target_os = "windows")]
+fn detect_on_battery() -> Option<bool> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    // BatteryStatus 1 means discharging; machines without a battery print nothing
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", "(Get-CimInstance Win32_Battery).BatteryStatus"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).lines().any(|line| line.trim() == "1"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn detect_on_battery() -> Option<bool> {
+    None
+}
@@ -0,0 +1,494 @@
+//! Integration tests over an in-memory library and the mock providers in test_support.rs
+
+use stellar_lib::ai::chat_completion_for_provider;
+use stellar_lib::ai::citations::{footnote_answer, locate_excerpt, source_label};
+use stellar_lib::ai::question_bank::{detect_exam_year, detect_questions};
+use stellar_lib::ai::types::{ChatCompletionRequest, ChatMessage};
+use stellar_lib::database::{
+    ConceptItemType, CreateCategoryRequest, CreateExamPaperRequest, CreateExamQuestionRequest, CreateFlashcardDeckRequest,
+    CreateFlashcardRequest, CreateFlashcardReviewRequest, CreateQuizQuestionRequest, CreateQuizRequest, CreateSessionRequest,
+    GlobalSearchLimits, NewCardOrder, SaveAutomationScriptRequest, ScriptEvent, StartReviewSessionRequest,
+};
+use stellar_lib::importers::handwriting::parse_handwriting_response;
+use stellar_lib::importers::paste::{detect_paste_kind, PasteKind};
+use stellar_lib::media::equations::clean_latex;
+use stellar_lib::services::Services;
+use stellar_lib::test_support::{
+    index_document, insert_document, sample_documents, test_database, test_states, test_vector_service,
+    MockChatProvider, MockEmbeddingGenerator, MOCK_MODEL,
+};
+use std::collections::HashMap;
+
+#[tokio::test]
+async fn test_in_memory_database_round_trip() {
+    let database = test_database().await;
+    let document = insert_document(&database, "Notes", "Some notes about cells.").await;
+
+    let loaded = database.get_document(&document.id).await.unwrap().expect("Document should exist");
+    assert_eq!(loaded.title, "Notes");
+    assert_eq!(loaded.content, "Some notes about cells.");
+    assert!(loaded.content_hash.is_some(), "Content hash should be calculated on insert");
+}
+
+#[tokio::test]
+async fn test_in_memory_databases_are_isolated() {
+    let first = test_database().await;
+    let second = test_database().await;
+    insert_document(&first, "Only here", "Content").await;
+
+    assert_eq!(first.get_all_documents().await.unwrap().len(), 1);
+    assert!(second.get_all_documents().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_search_ranks_matching_document_first() {
+    let database = test_database().await;
+    let mut vector_service = test_vector_service();
+
+    let mut binary_search_id = String::new();
+    for (title, content) in sample_documents() {
+        let document = insert_document(&database, title, &content).await;
+        assert!(index_document(&mut vector_service, &document).await > 0);
+        if title == "Binary search" {
+            binary_search_id = document.id;
+        }
+    }
+
+    let results = vector_service.search_similar("halving a sorted array", 3, None).await.unwrap();
+    assert_eq!(results.first().map(|result| result.chunk.document_id.as_str()), Some(binary_search_id.as_str()));
+}
+
+#[tokio::test]
+async fn test_compressed_documents_stay_searchable_and_ordered() {
+    let database = test_database().await;
+    let textbook = format!(
+        "Glycolysis splits glucose into pyruvate. {}The Krebs cycle runs in the mitochondrial matrix.",
+        "Cell biology chapter filler text. ".repeat(3_000)
+    );
+    let document = insert_document(&database, "Textbook", &textbook).await;
+
+    let found = database.search_documents("mitochondrial matrix", 10, false).await.unwrap();
+    assert_eq!(found.iter().map(|document| document.id.as_str()).collect::<Vec<_>>(), vec![document.id.as_str()]);
+
+    // New cards in document order follow where their source text appears in the content
+    for (front, source_text) in [("Krebs cycle", "The Krebs cycle"), ("Glycolysis", "Glycolysis splits")] {
+        database.create_flashcard(CreateFlashcardRequest {
+            front: front.to_string(),
+            back: "Back".to_string(),
+            source_document_id: Some(document.id.clone()),
+            source_text: Some(source_text.to_string()),
+            difficulty: None,
+            tags: Vec::new(),
+            category_id: None,
+            card_type: None,
+            deck_id: None,
+            metadata: None,
+        }).await.unwrap();
+    }
+    let cards = database.get_new_flashcards(None, None, Some(NewCardOrder::Document)).await.unwrap();
+    assert_eq!(cards.iter().map(|card| card.front.as_str()).collect::<Vec<_>>(), vec!["Glycolysis", "Krebs cycle"]);
+
+    database.delete_document(&document.id).await.unwrap();
+    assert!(database.search_documents("mitochondrial matrix", 10, false).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_content_search_finds_text_in_compressed_documents() {
+    let database = test_database().await;
+    let textbook = format!("{}The Krebs cycle runs in the mitochondrial matrix.", "Cell biology chapter filler text. ".repeat(3_000));
+    let document = insert_document(&database, "Textbook", &textbook).await;
+    insert_document(&database, "Short notes", "Nothing about it here.").await;
+
+    let hits = database.search_content_matches("krebs cycle", 10).await.unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].document_id, document.id);
+    assert!(hits[0].snippet.as_deref().unwrap().contains("The Krebs cycle runs"));
+}
+
+#[tokio::test]
+async fn test_global_search_finds_text_in_compressed_documents() {
+    let (database, vectors) = test_states().await;
+    let textbook = format!("{}Oxaloacetate is regenerated at the end of the Krebs cycle.", "Metabolism chapter filler text. ".repeat(3_000));
+    let document = insert_document(database.lock().await.as_ref().unwrap(), "Biochemistry textbook", &textbook).await;
+    let services = Services::new(database, vectors);
+
+    let results = services.search.global_search("oxaloacetate", GlobalSearchLimits::default()).await.unwrap();
+    assert_eq!(results.documents.len(), 1);
+    assert_eq!(results.documents[0].document_id, document.id);
+    assert_eq!(results.documents[0].match_kind, "content");
+    assert!(results.documents[0].snippet.as_deref().unwrap().contains("Oxaloacetate is regenerated"));
+}
+
+#[tokio::test]
+async fn test_mock_embeddings_are_deterministic() {
+    let generator = MockEmbeddingGenerator::new(32);
+    let a = generator.embed("The Calvin cycle");
+    let b = generator.embed("the calvin cycle");
+    assert_eq!(a, b);
+    assert_eq!(a.len(), 32);
+
+    let norm: f32 = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    assert!((norm - 1.0).abs() < 1e-5);
+}
+
+#[tokio::test]
+async fn test_chat_completion_against_mock_provider() {
+    let mock = MockChatProvider::start().await.unwrap();
+    mock.push_reply("Mitochondria are the powerhouse of the cell.");
+
+    let request = ChatCompletionRequest {
+        messages: vec![ChatMessage { role: "user".to_string(), content: "What are mitochondria?".to_string() }],
+        model: MOCK_MODEL.to_string(),
+        temperature: Some(0.2),
+        max_tokens: Some(100),
+        top_p: None,
+        frequency_penalty: None,
+        presence_penalty: None,
+        stream: Some(false),
+    };
+    let provider = mock.provider();
+    let response = chat_completion_for_provider(&provider, MOCK_MODEL, &request, provider.api_key.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(response.choices[0].message.content, "Mitochondria are the powerhouse of the cell.");
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0]["messages"][0]["content"], "What are mitochondria?");
+    assert_eq!(requests[0]["max_tokens"], 100);
+}
+
+#[tokio::test]
+async fn test_note_templates_number_lectures_per_course() {
+    let (database, vectors) = test_states().await;
+    let services = Services::new(database, vectors);
+    let category = services.documents.create_category(CreateCategoryRequest {
+        name: "Physics 101".to_string(),
+        description: None,
+        color: None,
+        icon: None,
+        parent_id: None,
+    }).await.unwrap();
+
+    let variables = HashMap::from([("topic".to_string(), "Kinematics".to_string())]);
+    let first = services.documents
+        .create_note_from_template("cornell", variables.clone(), Some(category.id.clone())).await.unwrap();
+    let second = services.documents
+        .create_note_from_template("cornell", variables, Some(category.id.clone())).await.unwrap();
+    let uncategorized = services.documents
+        .create_note_from_template("lecture_summary", HashMap::new(), None).await.unwrap();
+
+    assert_eq!(first.title, "Physics 101 Lecture 1: Cornell Notes");
+    assert_eq!(second.title, "Physics 101 Lecture 2: Cornell Notes");
+    assert!(second.content.contains("**Topic:** Kinematics"));
+    assert_eq!(uncategorized.title, "Lecture 1 Summary");
+    assert!(!uncategorized.content.contains("{{"));
+}
+
+#[tokio::test]
+async fn test_reviews_through_the_service_advance_the_saved_session() {
+    let (database, vectors) = test_states().await;
+    let study_session = {
+        let db_state = database.lock().await;
+        db_state.as_ref().unwrap().create_session(CreateSessionRequest {
+            title: "Evening review".to_string(),
+            session_type: None,
+            metadata: None,
+            template_id: None,
+            target_duration: None,
+        }).await.unwrap()
+    };
+    let services = Services::new(database, vectors);
+
+    let deck = services.flashcards.create_deck(CreateFlashcardDeckRequest {
+        name: "Cell division".to_string(),
+        description: None,
+        color: None,
+        icon: None,
+        category_id: None,
+        tags: Vec::new(),
+        is_shared: None,
+        metadata: None,
+        new_card_order: None,
+    }).await.unwrap();
+    let mut card_ids = Vec::new();
+    for front in ["Mitosis", "Meiosis"] {
+        let card = services.flashcards.create_flashcard(CreateFlashcardRequest {
+            front: front.to_string(),
+            back: "Back".to_string(),
+            source_document_id: None,
+            source_text: None,
+            difficulty: None,
+            tags: Vec::new(),
+            category_id: None,
+            card_type: None,
+            deck_id: Some(deck.id.clone()),
+            metadata: None,
+        }).await.unwrap();
+        card_ids.push(card.id);
+    }
+
+    let session = services.flashcards.start_review_session(StartReviewSessionRequest {
+        card_ids: card_ids.clone(),
+        study_session_id: Some(study_session.id.clone()),
+        mix_strategy: None,
+    }).await.unwrap();
+    let result = services.flashcards.record_review(CreateFlashcardReviewRequest {
+        flashcard_id: card_ids[0].clone(),
+        session_id: study_session.id.clone(),
+        response: "correct".to_string(),
+        time_spent: 12,
+        confidence: 4,
+        quality: 4,
+        metadata: None,
+        review_session_id: Some(session.id.clone()),
+    }).await.unwrap();
+    assert_eq!(result.flashcard.review_count, 1);
+
+    // Closing the app now picks up again at the second card
+    let resumed = services.flashcards.resume_review_session().await.unwrap().expect("Session should be resumable");
+    assert_eq!(resumed.session.id, session.id);
+    assert_eq!(resumed.session.position, 1);
+    assert_eq!(resumed.remaining_cards.iter().map(|card| card.id.as_str()).collect::<Vec<_>>(), vec![card_ids[1].as_str()]);
+
+    let stats = services.flashcards.get_deck_stats(&deck.id).await.unwrap();
+    assert_eq!((stats.card_count, stats.new_count, stats.reviews_today), (2, 1, 1));
+}
+
+#[tokio::test]
+async fn test_missed_quiz_questions_make_concepts_weak() {
+    let database = test_database().await;
+    let question = |text: &str| CreateQuizQuestionRequest {
+        question: text.to_string(),
+        answer: "...".to_string(),
+        explanation: None,
+    };
+    let quiz = database.create_quiz(CreateQuizRequest {
+        title: "Cell biology".to_string(),
+        document_id: None,
+        category_id: None,
+        questions: vec![question("What does ATP synthase do?"), question("Where does glycolysis happen?")],
+        metadata: None,
+    }).await.unwrap();
+    let (atp, glycolysis) = (&quiz.questions[0], &quiz.questions[1]);
+
+    database.link_concepts(ConceptItemType::QuizQuestion, &atp.id, None, &["ATP synthesis".to_string()]).await.unwrap();
+    database.link_concepts(ConceptItemType::QuizQuestion, &glycolysis.id, None, &["Glycolysis".to_string()]).await.unwrap();
+    // Same concept, named differently
+    let linked = database.link_concepts(ConceptItemType::QuizQuestion, &glycolysis.id, None, &["  glycolysis ".to_string()]).await.unwrap();
+    assert_eq!(linked[0].name, "Glycolysis");
+
+    for _ in 0..3 {
+        database.record_quiz_answer(&atp.id, false).await.unwrap();
+        database.record_quiz_answer(&glycolysis.id, true).await.unwrap();
+    }
+
+    let mastery = database.get_concept_mastery(None).await.unwrap();
+    assert_eq!(mastery.len(), 2);
+    assert_eq!(mastery[0].concept.name, "ATP synthesis");
+    assert_eq!(mastery[0].evidence_count, 3);
+    assert!(mastery[1].mastery > 0.8);
+
+    let weak = database.get_weak_concepts(None, 10).await.unwrap();
+    assert_eq!(weak.len(), 1);
+    assert_eq!(weak[0].concept.name, "ATP synthesis");
+}
+
+#[tokio::test]
+async fn test_exam_questions_are_detected_and_served_by_topic() {
+    let paper = "PHYS 101 Final Examination, June 2021\n\
+        Answer ALL questions.\n\
+        Question 1\n\
+        A ball is dropped from 20 m. (a) How long does it fall? [3 marks]\n\
+        (b) What is its speed on impact? [2 marks]\n\
+        2. State Newton's second law.\n\
+        1. This numbered line belongs to question 2\n\
+        Q3. Define entropy. (4 marks)\n";
+
+    let detected = detect_questions(paper);
+    assert_eq!(detected.len(), 3);
+    assert_eq!(detected[0].marks, Some(5));
+    assert!(detected[1].text.contains("belongs to question 2"));
+    assert_eq!(detected[2].number, "3");
+    assert_eq!(detect_exam_year("final.pdf", paper), Some(2021));
+
+    let database = test_database().await;
+    let topics = ["Kinematics", "Dynamics", "Thermodynamics"];
+    database.create_exam_paper(CreateExamPaperRequest {
+        title: "PHYS 101 Final".to_string(),
+        year: Some(2021),
+        category_id: None,
+        file_path: None,
+        questions: detected.into_iter().zip(topics)
+            .map(|(question, topic)| CreateExamQuestionRequest {
+                number: question.number,
+                text: question.text,
+                marks: question.marks,
+                topic: Some(topic.to_string()),
+            })
+            .collect(),
+    }).await.unwrap();
+
+    let set = database.get_practice_set(Some("thermodynamics"), 5, None).await.unwrap();
+    assert_eq!(set.len(), 1);
+    assert_eq!(set[0].year, Some(2021));
+    assert!(set[0].text.contains("entropy"));
+    assert_eq!(database.get_practice_set(None, 2, None).await.unwrap().len(), 2);
+    assert_eq!(database.get_question_bank_topics(None).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_essay_feedback_tracks_revisions() {
+    let mock = MockChatProvider::start().await.unwrap();
+    let (database, vectors) = test_states().await;
+    let document = {
+        let db = database.lock().await;
+        let db = db.as_ref().unwrap();
+        db.store_api_key("mock", "mock-key").await.unwrap();
+        insert_document(db, "On renewable energy", "Solar power is cheap.\n\nWind power is cheaper.").await
+    };
+    let services = Services::new(database, vectors);
+
+    let reply = |thesis: f64| serde_json::json!({
+        "criteria": [
+            { "criterion": "thesis", "score": thesis, "comment": "Position is stated." },
+            { "criterion": "Evidence", "score": 9, "comment": "Out of range." }
+        ],
+        "summary": "A short essay.",
+        "strengths": ["Clear"],
+        "improvements": ["Add sources", ""]
+    }).to_string();
+    mock.push_reply(reply(2.0));
+    mock.push_reply(reply(3.5));
+
+    let first = services.essays
+        .grade_essay(mock.provider(), MOCK_MODEL.to_string(), None, Some(document.id.clone()), "argumentative_essay").await.unwrap();
+    let second = services.essays
+        .grade_essay(mock.provider(), MOCK_MODEL.to_string(), None, Some(document.id.clone()), "argumentative_essay").await.unwrap();
+
+    let feedback = &first.feedback;
+    assert_eq!(feedback.criteria.len(), 4);
+    assert_eq!(feedback.criteria[0].score, 2.0);
+    assert_eq!(feedback.criteria[1].score, 4.0); // Clamped to the criterion's points
+    assert_eq!(feedback.criteria[2].comment, "Not assessed");
+    assert_eq!(feedback.max_score, 16.0);
+    assert_eq!(feedback.improvements, vec!["Add sources".to_string()]);
+    assert!(first.previous.is_none());
+
+    assert_eq!(second.previous.as_ref().map(|previous| previous.id.clone()), Some(feedback.id.clone()));
+    assert_eq!(second.score_change, Some(1.5));
+    assert_eq!(services.essays.get_essay_feedback(&document.id).await.unwrap().len(), 2);
+}
+
+#[test]
+fn test_illegible_handwriting_lowers_confidence() {
+    let reply = "```json\n{\"title\": \"# Cell biology\", \"markdown\": \"- Mitochondria [illegible] energy\\n- Ribosomes make proteins\", \"confidence\": 1.4}\n```";
+    let transcription = parse_handwriting_response(reply).unwrap();
+
+    assert_eq!(transcription.title.as_deref(), Some("Cell biology"));
+    assert!(transcription.markdown.starts_with("- Mitochondria"));
+    // One of eight words unreadable, after clamping the model's confidence to 1
+    assert!((transcription.confidence - 0.875).abs() < 1e-6);
+
+    assert!(parse_handwriting_response("{\"title\": null, \"markdown\": \"  \", \"confidence\": 0.9}").is_err());
+}
+
+#[test]
+fn test_rag_citations_become_footnotes() {
+    let content = "Cover page\u{c}Mitochondria produce most of the ATP a cell uses through oxidative phosphorylation.\n\nThey have their own DNA.";
+    let chunk = "Mitochondria produce most of the ATP a cell uses through oxidative phosphorylation.";
+    let (start, end) = locate_excerpt(content, chunk).unwrap();
+    assert_eq!(start, "Cover page\u{c}".chars().count());
+    assert_eq!(end - start, chunk.chars().count());
+    assert_eq!(locate_excerpt(content, "Ribosomes translate messenger RNA into proteins."), None);
+
+    let labels = vec![source_label("Cell Biology", Some(2)), source_label("Genetics", None)];
+    let answer = footnote_answer("Mitochondria make ATP [2]. They carry DNA [1, 2]. See [the notes](https://x.y) and [7].", &labels);
+    assert_eq!(answer.footnotes, vec![(1, 1), (2, 0)]);
+    assert_eq!(
+        answer.content,
+        "Mitochondria make ATP [^1]. They carry DNA [^2][^1]. See [the notes](https://x.y) and [7].\n\n[^1]: Genetics\n[^2]: Cell Biology, p. 2"
+    );
+}
+
+#[test]
+fn test_equation_latex_is_stripped_of_delimiters() {
+    assert_eq!(clean_latex("```latex\n\\[ E = mc^2 \\]\n```").unwrap(), "E = mc^2");
+    assert_eq!(clean_latex("$$\\frac{a}{b}.$$").unwrap(), "\\frac{a}{b}");
+    assert_eq!(clean_latex("$x^2$").unwrap(), "x^2");
+    assert!(clean_latex("NO_EQUATION").is_err());
+}
+
+#[tokio::test]
+async fn test_smart_paste_routes_by_content() {
+    let bibtex = "@article{smith2020,\n  title = {The {Cell} Cycle},\n  author = {Smith, Jane and Doe, John},\n  year = 2020,\n  doi = {10.1000/xyz}\n}\n@comment{ignored}\n@book{lee19, title = \"Genetics\", year = {2019}}";
+    let table = "Element\tSymbol\tNumber\nHydrogen\tH\t1\nHelium\tHe\t2";
+    let code = "fn main() {\n    println!(\"hi\");\n}";
+
+    assert_eq!(detect_paste_kind(" https://arxiv.org/abs/2401.01234 "), PasteKind::Url);
+    assert_eq!(detect_paste_kind(bibtex), PasteKind::Bibtex);
+    assert_eq!(detect_paste_kind(table), PasteKind::Csv);
+    assert_eq!(detect_paste_kind("a,b,c\n1,2,3\n4,5,6"), PasteKind::Csv);
+    assert_eq!(detect_paste_kind(code), PasteKind::Code);
+    assert_eq!(detect_paste_kind("# Lecture 3\n\nMitosis has four phases: prophase, metaphase."), PasteKind::Text);
+
+    let (database, vectors) = test_states().await;
+    let services = Services::new(database, vectors);
+
+    let references = services.documents.paste_content(PasteKind::Bibtex, bibtex, None, Vec::new(), None).await.unwrap();
+    assert_eq!(references.len(), 2);
+    assert_eq!(references[0].title, "The Cell Cycle");
+    assert!(references[0].tags.contains(&"reference".to_string()));
+    assert!(references[0].content.contains("**Authors:** Smith, Jane; Doe, John"));
+    assert!(references[0].content.contains("https://doi.org/10.1000/xyz"));
+
+    let tables = services.documents.paste_content(PasteKind::Csv, table, None, Vec::new(), None).await.unwrap();
+    assert_eq!(tables[0].title, "Pasted table (2 rows)");
+    assert!(tables[0].content.starts_with("| Element | Symbol | Number |\n| --- | --- | --- |"));
+
+    let snippets = services.documents.paste_content(PasteKind::Code, code, None, Vec::new(), None).await.unwrap();
+    assert!(snippets[0].content.starts_with("```rust\n"));
+    assert_eq!(snippets[0].title, "rust snippet: fn main() {");
+}
+
+#[tokio::test]
+async fn test_settings_bundle_leaves_out_secrets_and_disables_scripts() {
+    let (database, vectors) = test_states().await;
+    {
+        let db_state = database.lock().await;
+        let database = db_state.as_ref().unwrap();
+        database.set_setting(
+            "ai_provider_connection:custom",
+            &serde_json::json!({ "organization": "lab", "extra_headers": { "Authorization": "Bearer secret" } }),
+        ).await.unwrap();
+        database.save_automation_script(SaveAutomationScriptRequest {
+            id: None,
+            name: "Tag imports".to_string(),
+            event: ScriptEvent::DocumentImported,
+            source: "print(\"hi\")".to_string(),
+            enabled: true,
+        }).await.unwrap();
+    }
+    let services = Services::new(database.clone(), vectors);
+
+    let mut bundle = services.settings.export_bundle().await.unwrap();
+    let connection = &bundle.settings["ai_provider_connection:custom"];
+    assert_eq!(connection["organization"], "lab");
+    assert!(connection.get("extra_headers").is_none());
+
+    // A bundle from elsewhere: its headers are dropped and changed scripts need enabling again
+    bundle.settings["ai_provider_connection:custom"]["extra_headers"] = serde_json::json!({ "X-Api-Key": "theirs" });
+    bundle.automation_scripts[0].source = "print(\"changed\")".to_string();
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), serde_json::to_string(&bundle).unwrap()).unwrap();
+    let result = services.settings.import_bundle(file.path()).await.unwrap();
+    assert_eq!(result.automation_scripts, 1);
+
+    let db_state = database.lock().await;
+    let database = db_state.as_ref().unwrap();
+    let connection = database.get_setting("ai_provider_connection:custom").await.unwrap().unwrap();
+    assert_eq!(connection["extra_headers"]["Authorization"], "Bearer secret");
+    let scripts = database.get_automation_scripts().await.unwrap();
+    assert_eq!(scripts.len(), 1);
+    assert!(!scripts[0].enabled);
+}
@@ -13,7 +13,9 @@ import { getCurrentWindow } from "@tauri-apps/api/window"
 import { readFile } from "@tauri-apps/plugin-fs"
 import { ThemeManager } from "@/lib/config/theme-config"
 import { AppInitializationService } from "@/lib/core/app-initialization"
+import { DeepLinkService, type DeepLinkTarget } from "@/lib/services/deep-link-service"
 import { OnboardingService } from "@/lib/services/onboarding-service"
+import { useFlashcardStore } from "@/lib/stores/flashcard-store"
 import { useSettingsStore } from "@/lib/stores/settings-store"
 import { useStudyStore } from "@/lib/stores/study-store"
 import { isSupportedImportFile } from "@/lib/utils/document-import"
@@ -270,6 +272,9 @@ export function App() {
     setEditingNoteId,
     setShouldOpenUploadDialog,
     setPendingUploadFile,
+    setPendingUploadUrl,
+    setCurrentDocument,
+    setPendingJump,
     setSettingsTab,
     categories,
     currentCategory,
@@ -336,6 +341,69 @@ export function App() {
     initializeApp()
   }, []) // Empty dependency array = run once on mount
 
+  // stellar:// links from browsers and other apps
+  useEffect(() => {
+    let unlisten: null | (() => void) = null
+    let cancelled = false
+
+    const openDocument = (documentId: string, page?: number) => {
+      setPendingJump({ documentId, page })
+      setCurrentDocument(documentId)
+      setCurrentView("focus")
+    }
+
+    const handleLink = async (target: DeepLinkTarget) => {
+      switch (target.kind) {
+        case "document":
+          openDocument(target.document_id, target.page ?? undefined)
+          break
+        case "review_deck": {
+          const { getCustomReviewSession, startReviewSession } = useFlashcardStore.getState()
+          try {
+            const session = await getCustomReviewSession({ deckId: target.deck_id })
+            if (session.dueCards.length + session.newCards.length === 0) {
+              toast({ title: "Nothing to Review", description: "This deck has no cards due right now." })
+              return
+            }
+            startReviewSession(session)
+            setCurrentView("flashcards")
+          } catch (error) {
+            console.error("Failed to start review from link:", error)
+          }
+          break
+        }
+        case "import":
+          if (target.existing_document_id) {
+            openDocument(target.existing_document_id)
+            toast({ title: "Already Imported", description: "Opened the document imported from this link." })
+            return
+          }
+          // Open the import dialog rather than importing straight away, so links can't add documents unseen
+          setCurrentView("library")
+          setPendingUploadUrl(target.url)
+          setShouldOpenUploadDialog(true)
+          break
+      }
+    }
+
+    const handleError = (message: string) => {
+      toast({ title: "Couldn't Open Link", description: message, variant: "destructive" })
+    }
+
+    DeepLinkService.getInstance().subscribe(handleLink, handleError).then((stop) => {
+      if (cancelled) {
+        stop()
+      } else {
+        unlisten = stop
+      }
+    })
+
+    return () => {
+      cancelled = true
+      unlisten?.()
+    }
+  }, [setCurrentDocument, setCurrentView, setPendingJump, setPendingUploadUrl, setShouldOpenUploadDialog, toast])
+
   // Dynamic keyboard shortcuts using store keybindings
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
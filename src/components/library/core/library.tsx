@@ -79,6 +79,8 @@ export function Library() {
     handleShowProcessingStatus,
     pendingUploadFile,
     setPendingUploadFile,
+    pendingUploadUrl,
+    setPendingUploadUrl,
   } = useLibrary();
 
   if (isLoadingCategories && categories.length === 0) {
@@ -220,12 +222,14 @@ export function Library() {
           setShowUploadDialog(open);
           if (!open) {
             setPendingUploadFile(null);
+            setPendingUploadUrl(null);
           }
         }}
         onSuccess={handleUploadSuccess}
         categories={categories}
         currentCategoryId={currentCategory}
         initialFile={pendingUploadFile}
+        initialUrl={pendingUploadUrl}
       />
 
       {/* Processing Status Dialog */}
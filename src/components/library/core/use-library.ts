@@ -103,6 +103,8 @@ export function useLibrary() {
 		setShouldOpenUploadDialog,
 		pendingUploadFile,
 		setPendingUploadFile,
+		pendingUploadUrl,
+		setPendingUploadUrl,
 	} = useStudyStore();
 
 	const libraryService = LibraryService.getInstance();
@@ -666,5 +668,7 @@ export function useLibrary() {
 		handleShowProcessingStatus,
 		pendingUploadFile,
 		setPendingUploadFile,
+		pendingUploadUrl,
+		setPendingUploadUrl,
 	};
 }
@@ -14,7 +14,7 @@ import { type DocumentReference, useAIStore } from "@/lib/stores/ai-store";
 import { useSettingsStore } from "@/lib/stores/settings-store";
 import { useStudyStore } from "@/lib/stores/study-store";
 import { cn } from "@/lib/utils/utils";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/core/invoke";
 import {
   Clock,
   Code,
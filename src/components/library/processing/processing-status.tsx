@@ -20,7 +20,7 @@ import {
 } from "@/components/ui/table";
 import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
 import { useToast } from "@/hooks/use-toast";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/core/invoke";
 import {
   AlertCircle,
   CheckCircle,
@@ -36,6 +36,7 @@ interface PdfUploadDialogProps {
   categories?: Category[];
   currentCategoryId?: string | null;
   initialFile?: File | null;
+  initialUrl?: string | null;
 }
 
 type UploadType = "text" | "file" | "url";
@@ -47,6 +48,7 @@ export function PdfUploadDialog({
   categories = [],
   currentCategoryId = null,
   initialFile = null,
+  initialUrl = null,
 }: PdfUploadDialogProps) {
   const [title, setTitle] = useState("");
   const [tags, setTags] = useState<string[]>([]);
@@ -98,6 +100,13 @@ export function PdfUploadDialog({
     setTitle(initialFile.name.replace(/\.[^/.]+$/, ""));
   }, [open, initialFile]);
 
+  useEffect(() => {
+    if (!open || !initialUrl) return;
+
+    setUploadType("url");
+    setUrl(initialUrl);
+  }, [open, initialUrl]);
+
   const handleTextFileChange = async (e: React.ChangeEvent<HTMLInputElement>) => {
     const selected = e.target.files?.[0];
     if (!selected) return;
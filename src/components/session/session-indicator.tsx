@@ -18,7 +18,7 @@ import {
   Settings,
   Zap
 } from "lucide-react"
-import { useActionsStore, StudySession } from "@/lib/services/actions-service"
+import { useActionsStore, StudySession, SessionType } from "@/lib/services/actions-service"
 import { useToast } from "@/hooks/use-toast"
 import { SmartSessionDialog } from "./smart-session-dialog"
 
@@ -35,7 +35,7 @@ export function SessionIndicator() {
   const [sessionDuration, setSessionDuration] = useState<string>("0m")
   const [isStartingSession, setIsStartingSession] = useState(false)
   const [newSessionTitle, setNewSessionTitle] = useState("")
-  const [newSessionType, setNewSessionType] = useState("mixed")
+  const [newSessionType, setNewSessionType] = useState<SessionType>("mixed")
   
   const { toast } = useToast()
 
@@ -165,8 +165,8 @@ export function SessionIndicator() {
 
   const getSessionTypeIcon = (type: string) => {
     switch (type) {
-      case "focused": return <Zap className="h-4 w-4 text-muted-foreground" />
-      case "exploratory": return <BookOpen className="h-4 w-4 text-muted-foreground" />
+      case "reading": return <BookOpen className="h-4 w-4 text-muted-foreground" />
+      case "ai_tutoring": return <Zap className="h-4 w-4 text-muted-foreground" />
       case "review": return <BarChart3 className="h-4 w-4 text-muted-foreground" />
       default: return <Settings className="h-4 w-4 text-muted-foreground" />
     }
@@ -174,8 +174,8 @@ export function SessionIndicator() {
 
   const getSessionTypeColor = (type: string) => {
     switch (type) {
-      case "focused": return "bg-orange-500/10 text-orange-700 border-orange-200"
-      case "exploratory": return "bg-blue-500/10 text-blue-700 border-blue-200"
+      case "reading": return "bg-orange-500/10 text-orange-700 border-orange-200"
+      case "ai_tutoring": return "bg-blue-500/10 text-blue-700 border-blue-200"
       case "review": return "bg-green-500/10 text-green-700 border-green-200"
       default: return "bg-purple-500/10 text-purple-700 border-purple-200"
     }
@@ -213,7 +213,7 @@ export function SessionIndicator() {
                     className={`text-xs ${getSessionTypeColor(currentSession.session_type)}`}
                   >
                     {getSessionTypeIcon(currentSession.session_type)}
-                    <span className="ml-1 capitalize">{currentSession.session_type}</span>
+                    <span className="ml-1 capitalize">{currentSession.session_type.replace("_", " ")}</span>
                   </Badge>
                 </div>
                 <div className="text-xs text-muted-foreground">
@@ -284,7 +284,7 @@ export function SessionIndicator() {
           {/* Session Type */}
           <div className="space-y-2">
             <Label className="text-sm font-medium">Session Type</Label>
-            <Select value={newSessionType} onValueChange={setNewSessionType}>
+            <Select value={newSessionType} onValueChange={(value) => setNewSessionType(value as SessionType)}>
               <SelectTrigger className="h-11">
                 <div className="flex items-center gap-2">
                   <SelectValue />
@@ -297,16 +297,16 @@ export function SessionIndicator() {
                     Mixed Study
                   </div>
                 </SelectItem>
-                <SelectItem value="focused">
+                <SelectItem value="reading">
                   <div className="flex items-center gap-2">
-                    <Zap className="h-4 w-4" />
-                    Focused Study
+                    <BookOpen className="h-4 w-4" />
+                    Reading
                   </div>
                 </SelectItem>
-                <SelectItem value="exploratory">
+                <SelectItem value="ai_tutoring">
                   <div className="flex items-center gap-2">
-                    <BookOpen className="h-4 w-4" />
-                    Exploratory Learning
+                    <Zap className="h-4 w-4" />
+                    AI Tutoring
                   </div>
                 </SelectItem>
                 <SelectItem value="review">
@@ -233,8 +233,8 @@ Generated on ${sessionSummary.generatedAt.toLocaleString()}
 
   const getSessionTypeIcon = (type: string) => {
     switch (type) {
-      case "focused": return <Zap className="h-4 w-4" />
-      case "exploratory": return <BookOpen className="h-4 w-4" />
+      case "reading": return <BookOpen className="h-4 w-4" />
+      case "ai_tutoring": return <Zap className="h-4 w-4" />
       case "review": return <BarChart3 className="h-4 w-4" />
       default: return <Settings className="h-4 w-4" />
     }
@@ -242,8 +242,8 @@ Generated on ${sessionSummary.generatedAt.toLocaleString()}
 
   const getSessionTypeVariant = (type: string) => {
     switch (type) {
-      case "focused": return "destructive"
-      case "exploratory": return "secondary"
+      case "reading": return "destructive"
+      case "ai_tutoring": return "secondary"
       case "review": return "default"
       default: return "outline"
     }
@@ -309,8 +309,8 @@ Generated on ${sessionSummary.generatedAt.toLocaleString()}
           <SelectContent>
             <SelectItem value="all">All Types</SelectItem>
             <SelectItem value="mixed">Mixed Study</SelectItem>
-            <SelectItem value="focused">Focused Study</SelectItem>
-            <SelectItem value="exploratory">Exploratory Learning</SelectItem>
+            <SelectItem value="reading">Reading</SelectItem>
+            <SelectItem value="ai_tutoring">AI Tutoring</SelectItem>
             <SelectItem value="review">Review Session</SelectItem>
           </SelectContent>
         </Select>
@@ -374,7 +374,7 @@ Generated on ${sessionSummary.generatedAt.toLocaleString()}
                             className="text-xs"
                           >
                             {getSessionTypeIcon(session.session_type)}
-                            <span className="ml-1 capitalize">{session.session_type}</span>
+                            <span className="ml-1 capitalize">{session.session_type.replace("_", " ")}</span>
                           </Badge>
                           {session.is_active && (
                             <Badge variant="secondary" className="text-xs bg-green-100 text-green-700">
@@ -426,7 +426,7 @@ Generated on ${sessionSummary.generatedAt.toLocaleString()}
                       variant={getSessionTypeVariant(selectedSession.session_type)}
                     >
                       {getSessionTypeIcon(selectedSession.session_type)}
-                      <span className="ml-1 capitalize">{selectedSession.session_type}</span>
+                      <span className="ml-1 capitalize">{selectedSession.session_type.replace("_", " ")}</span>
                     </Badge>
                   </div>
                   <div className="flex gap-2">
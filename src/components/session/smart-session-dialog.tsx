@@ -105,8 +105,8 @@ export function SmartSessionDialog() {
 
   const getSessionTypeIcon = (type: string) => {
     switch (type) {
-      case "focused": return <Zap className="h-4 w-4" />
-      case "exploratory": return <BookOpen className="h-4 w-4" />
+      case "reading": return <BookOpen className="h-4 w-4" />
+      case "ai_tutoring": return <Zap className="h-4 w-4" />
       case "review": return <BarChart3 className="h-4 w-4" />
       default: return <Settings className="h-4 w-4" />
     }
@@ -114,8 +114,8 @@ export function SmartSessionDialog() {
 
   const getSessionTypeColor = (type: string) => {
     switch (type) {
-      case "focused": return "bg-orange-500/10 text-orange-700 border-orange-200"
-      case "exploratory": return "bg-blue-500/10 text-blue-700 border-blue-200"
+      case "reading": return "bg-orange-500/10 text-orange-700 border-orange-200"
+      case "ai_tutoring": return "bg-blue-500/10 text-blue-700 border-blue-200"
       case "review": return "bg-green-500/10 text-green-700 border-green-200"
       default: return "bg-purple-500/10 text-purple-700 border-purple-200"
     }
@@ -213,7 +213,7 @@ export function SmartSessionDialog() {
                               className={`text-xs ${getSessionTypeColor(suggestion.suggestedType)}`}
                             >
                               {getSessionTypeIcon(suggestion.suggestedType)}
-                              <span className="ml-1 capitalize">{suggestion.suggestedType}</span>
+                              <span className="ml-1 capitalize">{suggestion.suggestedType.replace("_", " ")}</span>
                             </Badge>
                             <div className="flex items-center gap-1">
                               <Star className={`h-3 w-3 ${getConfidenceColor(suggestion.confidence)}`} />
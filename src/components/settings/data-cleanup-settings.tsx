@@ -5,7 +5,7 @@ import { AlertDialog, AlertDialogAction, AlertDialogCancel, AlertDialogContent,
 import { Badge } from "@/components/ui/badge"
 import { Separator } from "@/components/ui/separator"
 import { Trash2, AlertTriangle, HardDrive, Database, FileText, Info } from "lucide-react"
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '@/lib/core/invoke'
 import { useToast } from "@/hooks/use-toast"
 
 interface DataUsageInfo {
@@ -18,6 +18,16 @@ interface DataUsageInfo {
   totalSizeFormatted: string
   databaseSizeFormatted: string
   pdfSizeFormatted: string
+  embeddingsSize: number
+  embeddingsSizeFormatted: string
+  embeddingChunks: number
+  embeddedDocuments: number
+  lastEmbeddingsOptimization: {
+    orphaned_chunks_removed: number
+    size_before: number
+    size_after: number
+    optimized_at: string
+  } | null
 }
 
 export function DataCleanupSettings() {
@@ -20,7 +20,7 @@ import {
 } from "@/components/ui/tooltip";
 import { useToast } from "@/hooks/use-toast";
 import { aiService } from "@/lib/services/ai-service";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/core/invoke";
 import {
   AlertCircle,
   Eye,
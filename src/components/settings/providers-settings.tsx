@@ -196,6 +196,7 @@ export function ProvidersSettings() {
                     <SelectItem value="openai">OpenAI Compatible</SelectItem>
                     <SelectItem value="anthropic">Anthropic</SelectItem>
                     <SelectItem value="ollama">Ollama</SelectItem>
+                    <SelectItem value="gemini">Gemini</SelectItem>
                     <SelectItem value="custom">Custom</SelectItem>
                   </SelectContent>
                 </Select>
@@ -156,11 +156,11 @@ export function useChat(options: UseChatOptions = {}) {
             assistantContent += delta
             setStreamingMessage(assistantContent)
           },
-          () => {
-            // Stream complete
+          (filteredContent) => {
+            // Stream complete; keep the filtered reply when the output filter rewrote it
             const assistantMessage: Omit<ChatMessage, "id"> = {
               role: "assistant",
-              content: assistantContent,
+              content: filteredContent ?? assistantContent,
               timestamp: new Date(),
               model: activeModel.id,
               providerId: activeProvider.id
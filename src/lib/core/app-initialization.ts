@@ -1,7 +1,7 @@
 import { LibraryService } from "@/lib/services/library-service"
 import { EmbeddingService } from "@/lib/services/embedding-service"
 import { DocumentContextParser } from './document-context'
-import { invoke } from "@tauri-apps/api/core"
+import { invoke } from "@/lib/core/invoke"
 
 export class AppInitializationService {
   private static instance: AppInitializationService
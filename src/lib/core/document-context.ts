@@ -1,5 +1,6 @@
 import { LibraryService, type Document } from "@/lib/services/library-service"
 import { EmbeddingService, type EmbeddingSearchResult, type EmbeddingConfig } from "@/lib/services/embedding-service"
+import { invoke } from "@/lib/core/invoke"
 
 export interface ParsedDocumentContext {
   mentionedDocuments: Document[]
@@ -60,7 +61,7 @@ export class DocumentContextParser {
     if (mentionedDocuments.length > 0) {
       // Use mentioned documents for context
       const documentContext = await this.buildDocumentContext(mentionedDocuments)
-      contextualMessage = `${message}\n\nRelevant document context:\n${documentContext}`
+      contextualMessage = `${message}\n\n${await this.guardContext("Mentioned documents", documentContext)}`
     } else if (this.embeddingService.isInitialized()) {
       // If no specific documents mentioned, try semantic search
       const semanticContext = await this.embeddingService.findRelevantContext(message, {
@@ -69,7 +70,7 @@ export class DocumentContextParser {
       })
       
       if (semanticContext) {
-        contextualMessage = `${message}\n\nRelevant context from your documents:\n${semanticContext}`
+        contextualMessage = `${message}\n\n${await this.guardContext("Related documents", semanticContext)}`
       }
     }
 
@@ -80,6 +81,22 @@ export class DocumentContextParser {
     }
   }
 
+  /**
+   * Sanitize retrieved text and wrap it in delimited context blocks so instructions
+   * embedded in imported documents are treated as data
+   */
+  private async guardContext(label: string, text: string): Promise<string> {
+    try {
+      const prepared = await invoke<{ context: string; instructions: string }>("prepare_rag_context", {
+        excerpts: [{ label, text }]
+      })
+      return `${prepared.instructions}\n\n${prepared.context}`
+    } catch (error) {
+      console.error('Failed to prepare document context:', error)
+      return `Relevant context from your documents:\n${text}`
+    }
+  }
+
   /**
    * Get document content for a specific document
    */
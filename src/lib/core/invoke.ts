@@ -0,0 +1,10 @@
+import type { InvokeArgs, InvokeOptions } from '@tauri-apps/api/core'
+import { CrashReportService } from '@/lib/services/crash-report-service'
+
+/**
+ * The app-wide `invoke`: a command that fails is recorded as a crash report before the
+ * error is rethrown. Import this rather than `invoke` from `@tauri-apps/api/core`.
+ */
+export function invoke<T>(command: string, args?: InvokeArgs, options?: InvokeOptions): Promise<T> {
+  return CrashReportService.getInstance().invoke<T>(command, args, options)
+}
@@ -1,6 +1,6 @@
 import { create } from "zustand"
 import { persist } from "zustand/middleware"
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '@/lib/core/invoke'
 import { sessionDetectionService, SessionSuggestion, SessionSummary } from './session-detection-service'
 
 // Action Types Enum
@@ -144,14 +144,77 @@ export interface StudySession {
   start_time: string // ISO string from backend
   end_time?: string // ISO string from backend
   is_active: boolean
-  session_type: string // 'focused', 'exploratory', 'review', 'mixed'
+  session_type: SessionType
   total_duration: number // Duration in seconds
+  template_id?: string
+  target_duration?: number // Seconds, from the template the session was started with
   documents_accessed: string[]
   categories_accessed: string[]
   conversation_ids: string[]
   metadata?: any // JSON metadata
 }
 
+export type SessionType = 'reading' | 'review' | 'ai_tutoring' | 'mixed'
+
+// Session preset (matches Rust SessionTemplate)
+export interface SessionTemplate {
+  id: string
+  name: string
+  description?: string
+  session_type: SessionType
+  target_duration_minutes: number
+  document_ids: string[]
+  deck_ids: string[]
+  is_builtin: boolean // Built-in templates can be edited but not deleted
+  created_at: string
+  updated_at: string
+}
+
+export interface SaveSessionTemplateRequest {
+  id?: string // Omit to create a new template
+  name: string
+  description?: string
+  session_type: SessionType
+  target_duration_minutes: number
+  document_ids: string[]
+  deck_ids: string[]
+}
+
+// Automatic session splitting (matches Rust SessionSegmentationSettings)
+export interface SessionSegmentationSettings {
+  enabled: boolean
+  idle_gap_minutes: number // A gap this long between actions starts a new session
+  split_at_day_boundary: boolean // Activity on a new local day starts a new session
+}
+
+export interface SessionSegmentationResult {
+  sessions_checked: number
+  sessions_split: number
+  sessions_created: number
+}
+
+// Minutes per activity type (matches Rust ActivityBreakdown)
+export interface ActivityBreakdown {
+  reading: number
+  reviewing: number
+  chatting: number
+  note_taking: number
+  other: number
+}
+
+// One day of study activity for the dashboard (matches Rust DailyActivity)
+export interface DailyActivity {
+  date: string // YYYY-MM-DD, local time
+  total_minutes: number
+  minutes_by_activity: ActivityBreakdown
+  hourly: ActivityBreakdown[] // 24 entries, by local hour
+  sessions_count: number
+  flashcards_reviewed: number
+  first_activity?: string
+  last_activity?: string
+  computed_at: string
+}
+
 // Action Context for recording actions
 export interface ActionContext {
   sessionId?: string
@@ -198,7 +261,15 @@ interface ActionsState {
   getRecentActions: (limit: number) => Promise<UserAction[]>
   
   // Session management
-  startNewSession: (title: string, sessionType?: string) => Promise<StudySession>
+  startNewSession: (title: string, sessionType?: SessionType) => Promise<StudySession>
+  startSessionFromTemplate: (templateId: string) => Promise<StudySession>
+  getSessionTemplates: () => Promise<SessionTemplate[]>
+  saveSessionTemplate: (req: SaveSessionTemplateRequest) => Promise<SessionTemplate>
+  deleteSessionTemplate: (templateId: string) => Promise<boolean>
+  getDailyActivity: (date: string) => Promise<DailyActivity | null>
+  getSegmentationSettings: () => Promise<SessionSegmentationSettings>
+  updateSegmentationSettings: (settings: SessionSegmentationSettings) => Promise<SessionSegmentationSettings>
+  segmentSessions: () => Promise<SessionSegmentationResult>
   getCurrentSession: () => Promise<StudySession | null>
   endCurrentSession: () => Promise<boolean>
   getStudySessions: (limit?: number, offset?: number) => Promise<StudySession[]>
@@ -287,7 +358,11 @@ export const useActionsStore = create<ActionsState>()(
             }
           }
           
-          await invoke('record_user_action', { req: actionData })
+          const action = await invoke<UserAction>('record_user_action', { req: actionData })
+          // The backend starts a new session after an idle gap or at a day boundary
+          if (action.session_id !== get().currentSessionId) {
+            set({ currentSessionId: action.session_id })
+          }
         } catch (error) {
           console.error('Failed to record action:', error)
           // Don't throw the error to prevent breaking the app
@@ -329,7 +404,7 @@ export const useActionsStore = create<ActionsState>()(
         }
       },
       
-      startNewSession: async (title: string, sessionType: string = 'mixed') => {
+      startNewSession: async (title: string, sessionType: SessionType = 'mixed') => {
         try {
           await ensureDatabaseInitialized()
           const session = await invoke<StudySession>('start_new_session', { 
@@ -351,6 +426,72 @@ export const useActionsStore = create<ActionsState>()(
           throw error
         }
       },
+
+      startSessionFromTemplate: async (templateId: string) => {
+        try {
+          await ensureDatabaseInitialized()
+          const session = await invoke<StudySession>('start_session_from_template', { templateId })
+          set({ currentSessionId: session.id })
+          return session
+        } catch (error) {
+          console.error('Failed to start session from template:', error)
+          set({ currentSessionId: null })
+          throw error
+        }
+      },
+
+      getSessionTemplates: async () => {
+        try {
+          await ensureDatabaseInitialized()
+          return await invoke<SessionTemplate[]>('get_session_templates')
+        } catch (error) {
+          console.error('Failed to get session templates:', error)
+          return []
+        }
+      },
+
+      saveSessionTemplate: async (req: SaveSessionTemplateRequest) => {
+        await ensureDatabaseInitialized()
+        return await invoke<SessionTemplate>('save_session_template', { req })
+      },
+
+      deleteSessionTemplate: async (templateId: string) => {
+        try {
+          await ensureDatabaseInitialized()
+          return await invoke<boolean>('delete_session_template', { templateId })
+        } catch (error) {
+          console.error('Failed to delete session template:', error)
+          return false
+        }
+      },
+
+      getDailyActivity: async (date: string) => {
+        try {
+          await ensureDatabaseInitialized()
+          return await invoke<DailyActivity>('get_daily_activity', { date })
+        } catch (error) {
+          console.error('Failed to get daily activity:', error)
+          return null
+        }
+      },
+
+      getSegmentationSettings: async () => {
+        await ensureDatabaseInitialized()
+        return await invoke<SessionSegmentationSettings>('get_session_segmentation_settings')
+      },
+
+      updateSegmentationSettings: async (settings: SessionSegmentationSettings) => {
+        await ensureDatabaseInitialized()
+        return await invoke<SessionSegmentationSettings>('update_session_segmentation_settings', { settings })
+      },
+
+      segmentSessions: async () => {
+        await ensureDatabaseInitialized()
+        const result = await invoke<SessionSegmentationResult>('segment_study_sessions')
+        // The active session may have been split off into a new one
+        await get().getCurrentSession()
+        return result
+      },
       
       getCurrentSession: async () => {
         try {
@@ -490,7 +631,7 @@ export const useActionsStore = create<ActionsState>()(
           const recentActions = await get().getRecentActions(50)
           
           let sessionTitle = "Study Session"
-          let sessionType = "mixed"
+          let sessionType: SessionType = "mixed"
           
           if (recentActions.length > 0) {
             const suggestion = sessionDetectionService.generateSessionTitle(recentActions)
@@ -1,5 +1,5 @@
 import type { AIModel, AIProvider, ChatMessage } from "@/lib/stores/ai-store";
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/lib/core/invoke";
 import { listen } from "@tauri-apps/api/event";
 
 export interface ChatCompletionRequest {
@@ -27,6 +27,8 @@ export interface ChatCompletionResponse {
 		completionTokens: number;
 		totalTokens: number;
 	};
+	/** Set when older messages were dropped to fit the model's context window */
+	warning?: string;
 }
 
 export interface ChatCompletionStreamChunk {
@@ -40,6 +42,20 @@ export interface ChatCompletionStreamChunk {
 	}>;
 }
 
+export type AIStreamEvent =
+	| { type: "start"; streamId: string; model: string; warning?: string }
+	| { type: "chunk"; streamId: string; chunk: ChatCompletionStreamChunk }
+	| { type: "filtered"; streamId: string; content: string; flags: string[] }
+	| { type: "end"; streamId: string; cancelled: boolean }
+	| { type: "error"; streamId: string; message: string };
+
+export interface ActiveAIStream {
+	streamId: string;
+	providerId: string;
+	model: string;
+	startedAt: string;
+}
+
 export class AIService {
 	private static instance: AIService;
 
@@ -97,85 +113,108 @@ export class AIService {
 	}
 
 	/**
-	 * Send a streaming chat completion request
+	 * Send a streaming chat completion request. Resolves with the stream id,
+	 * which can be passed to cancelStream.
 	 */
 	async chatCompletionStream(
 		provider: AIProvider,
 		model: AIModel,
 		request: ChatCompletionRequest,
 		onChunk: (chunk: ChatCompletionStreamChunk) => void,
-		onComplete: () => void,
+		onComplete: (filteredContent?: string) => void,
 		onError: (error: Error) => void,
-	): Promise<void> {
+	): Promise<string | undefined> {
 		try {
-			// Use Tauri's event system for streaming
-			const eventName = `ai_stream_${crypto.randomUUID()}`;
+			const streamId = crypto.randomUUID();
+			const channel = `ai-stream:${streamId}`;
 			console.debug(
-				"[AI][FE] chatCompletionStream → provider=%s type=%s model=%s messages=%d event=%s",
+				"[AI][FE] chatCompletionStream → provider=%s type=%s model=%s messages=%d stream=%s",
 				provider.id,
 				provider.type,
 				model.id,
 				request.messages?.length ?? 0,
-				eventName,
+				streamId,
 			);
 
-			// Register listeners BEFORE invoking to avoid missing early chunks
-			console.debug("[AI][FE] registering stream listeners for %s", eventName);
-			const [unlistenStream, unlistenError] = await Promise.all([
-				listen<ChatCompletionStreamChunk>(eventName, (event) => {
-					const chunk = event.payload;
-					console.debug("[AI][FE] stream evt", chunk);
-					if (chunk?.choices?.[0]?.delta?.content) {
-						console.debug(
-							"[AI][FE] stream Δ content len=%d",
-							chunk.choices[0].delta.content.length,
-						);
-					} else {
-						console.debug("[AI][FE] stream Δ (no content)");
-					}
-					if (chunk.choices[0]?.finishReason) {
-						console.debug("[AI][FE] stream ✓ complete");
-						onComplete();
-						try {
-							unlistenStream?.();
-						} catch {}
-						try {
-							unlistenError?.();
-						} catch {}
-					} else {
-						onChunk(chunk);
-					}
-				}),
-				listen<string>(`${eventName}_error`, (event) => {
-					console.error("[AI][FE] stream ✗ error:", event.payload);
-					onError(new Error(event.payload));
-					try {
-						unlistenError?.();
-					} catch {}
-					try {
-						unlistenStream?.();
-					} catch {}
-				}),
-			]);
-
-			// Start backend streaming after listeners are ready
-			await invoke<() => void>("ai_chat_completion_stream", {
-				provider: {
-					id: provider.id,
-					type: provider.type,
-					baseUrl: provider.baseUrl,
-					apiKey: await this.getSecureApiKey(provider.id),
-				},
-				model: model.id,
-				request: { ...request, stream: true },
-				eventName,
+			// Register the listener BEFORE invoking to avoid missing early chunks
+			let finished = false;
+			// Set when the output filter rewrote the reply; it replaces the streamed text
+			let filteredContent: string | undefined;
+			const unlisten = await listen<AIStreamEvent>(channel, (event) => {
+				const payload = event.payload;
+				if (finished) return;
+				switch (payload.type) {
+					case "start":
+						console.debug("[AI][FE] stream started model=%s", payload.model);
+						if (payload.warning) {
+							console.warn("[AI][FE] context pruned:", payload.warning);
+						}
+						break;
+					case "chunk":
+						if (payload.chunk.choices[0]?.finishReason) {
+							console.debug("[AI][FE] stream ✓ finish reason");
+						} else {
+							onChunk(payload.chunk);
+						}
+						break;
+					case "filtered":
+						console.warn("[AI][FE] output filter flagged reply:", payload.flags);
+						filteredContent = payload.content;
+						break;
+					case "end":
+						console.debug("[AI][FE] stream ✓ complete cancelled=%s", payload.cancelled);
+						finished = true;
+						onComplete(filteredContent);
+						unlisten();
+						break;
+					case "error":
+						console.error("[AI][FE] stream ✗ error:", payload.message);
+						finished = true;
+						onError(new Error(payload.message));
+						unlisten();
+						break;
+				}
 			});
+
+			// Start backend streaming after the listener is ready
+			try {
+				await invoke<string>("ai_chat_completion_stream", {
+					provider: {
+						id: provider.id,
+						type: provider.type,
+						baseUrl: provider.baseUrl,
+						apiKey: await this.getSecureApiKey(provider.id),
+					},
+					model: model.id,
+					request: { ...request, stream: true },
+					streamId,
+				});
+			} catch (error) {
+				unlisten();
+				throw error;
+			}
+			return streamId;
 		} catch (error) {
 			console.error("AI streaming failed:", error);
 			onError(new Error(`AI streaming error: ${error}`));
+			return undefined;
 		}
 	}
 
+	/**
+	 * Stop a running stream; its listener receives an "end" event with cancelled=true
+	 */
+	async cancelStream(streamId: string): Promise<boolean> {
+		return invoke<boolean>("cancel_ai_stream", { streamId });
+	}
+
+	/**
+	 * Streams currently running in any window
+	 */
+	async listActiveStreams(): Promise<ActiveAIStream[]> {
+		return invoke<ActiveAIStream[]>("list_active_ai_streams");
+	}
+
 	/**
 	 * Test connection to a provider
 	 */
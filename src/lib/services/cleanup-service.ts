@@ -1,4 +1,4 @@
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '@/lib/core/invoke'
 
 export interface DataUsageInfo {
   dataDirectory: string
@@ -10,6 +10,43 @@ export interface DataUsageInfo {
   totalSizeFormatted: string
   databaseSizeFormatted: string
   pdfSizeFormatted: string
+  embeddingsSize: number
+  embeddingsSizeFormatted: string
+  embeddingChunks: number
+  embeddedDocuments: number
+  lastEmbeddingsOptimization: {
+    orphaned_chunks_removed: number
+    size_before: number
+    size_after: number
+    optimized_at: string
+  } | null
+  availableSpace: number | null
+  availableSpaceFormatted: string | null
+}
+
+// Errors from imports that would fill the disk start with this
+export const STORAGE_ERROR_PREFIX = 'insufficient_disk_space:'
+
+export function isInsufficientDiskSpaceError(error: unknown): boolean {
+  const message = error instanceof Error ? error.message : String(error)
+  return message.includes(STORAGE_ERROR_PREFIX)
+}
+
+export interface StorageCategory {
+  key: string
+  label: string
+  size: number
+  size_formatted: string
+  file_count: number
+}
+
+export interface StorageBreakdown {
+  data_directory: string
+  total_size: number
+  total_size_formatted: string
+  available_space: number | null
+  available_space_formatted: string | null
+  categories: StorageCategory[]
 }
 
 export interface CleanupOptions {
@@ -45,6 +82,18 @@ export class CleanupService {
     }
   }
 
+  /**
+   * Data dir size by category, largest first, with the free disk space
+   */
+  async getStorageBreakdown(): Promise<StorageBreakdown> {
+    try {
+      return await invoke<StorageBreakdown>('get_storage_breakdown')
+    } catch (error) {
+      console.error('Failed to get storage breakdown:', error)
+      throw new Error('Failed to retrieve storage breakdown')
+    }
+  }
+
   /**
    * Clean up all application data
    */
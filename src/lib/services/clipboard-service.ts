@@ -0,0 +1,54 @@
+import { invoke } from '@/lib/core/invoke'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+// Matches Rust ClipboardWatcherSettings
+export interface ClipboardWatcherSettings {
+  enabled: boolean
+  detect_urls: boolean
+  min_text_chars: number // Shorter copied text is ignored; 0 turns text offers off
+}
+
+export interface ClipboardCapture {
+  kind: 'url' | 'text'
+  text: string
+  preview: string
+  char_count: number
+  existing_document_id: string | null // The URL was already imported as this document
+}
+
+export class ClipboardService {
+  private static instance: ClipboardService | null = null
+
+  private constructor() {}
+
+  static getInstance(): ClipboardService {
+    if (!ClipboardService.instance) {
+      ClipboardService.instance = new ClipboardService()
+    }
+    return ClipboardService.instance
+  }
+
+  async getSettings(): Promise<ClipboardWatcherSettings> {
+    return invoke<ClipboardWatcherSettings>('get_clipboard_watcher_settings')
+  }
+
+  async updateSettings(settings: ClipboardWatcherSettings): Promise<ClipboardWatcherSettings> {
+    return invoke<ClipboardWatcherSettings>('update_clipboard_watcher_settings', { settings })
+  }
+
+  async setEnabled(enabled: boolean): Promise<ClipboardWatcherSettings> {
+    return invoke<ClipboardWatcherSettings>('toggle_clipboard_watcher', { enabled })
+  }
+
+  async isRunning(): Promise<boolean> {
+    return invoke<boolean>('is_clipboard_watcher_running')
+  }
+
+  /**
+   * Called for each copied URL or long passage while the watcher is on, so the app can offer
+   * to import it or save it with quickCapture
+   */
+  async onCapture(handler: (capture: ClipboardCapture) => void): Promise<UnlistenFn> {
+    return listen<ClipboardCapture>('clipboard-capture', (event) => handler(event.payload))
+  }
+}
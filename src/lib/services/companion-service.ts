@@ -0,0 +1,46 @@
+import { invoke } from '@/lib/core/invoke'
+
+// Matches Rust CompanionServerSettings
+export interface CompanionServerSettings {
+  enabled: boolean
+  port: number
+  token: string | null // Paste into the browser extension; sent as a bearer token
+  default_tags: string[] // Added to everything saved from the browser
+}
+
+/**
+ * Settings for the localhost endpoint the browser extension saves pages, selections and PDF
+ * links through
+ */
+export class CompanionService {
+  private static instance: CompanionService | null = null
+
+  private constructor() {}
+
+  static getInstance(): CompanionService {
+    if (!CompanionService.instance) {
+      CompanionService.instance = new CompanionService()
+    }
+    return CompanionService.instance
+  }
+
+  async getSettings(): Promise<CompanionServerSettings> {
+    return invoke<CompanionServerSettings>('get_companion_settings')
+  }
+
+  async updateSettings(settings: CompanionServerSettings): Promise<CompanionServerSettings> {
+    return invoke<CompanionServerSettings>('update_companion_settings', { settings })
+  }
+
+  async regenerateToken(): Promise<CompanionServerSettings> {
+    return invoke<CompanionServerSettings>('regenerate_companion_token')
+  }
+
+  async isRunning(): Promise<boolean> {
+    return invoke<boolean>('is_companion_server_running')
+  }
+
+  endpointUrl(settings: CompanionServerSettings): string {
+    return `http://127.0.0.1:${settings.port}`
+  }
+}
@@ -0,0 +1,61 @@
+import { invoke, type InvokeArgs, type InvokeOptions } from '@tauri-apps/api/core'
+
+export interface CrashReport {
+  id: string
+  kind: 'panic' | 'command_error'
+  message: string
+  command?: string | null
+  location?: string | null
+  thread?: string | null
+  backtrace?: string | null
+  app_version: string
+  os: string
+  arch: string
+  created_at: string
+}
+
+export class CrashReportService {
+  private static instance: CrashReportService | null = null
+
+  private constructor() {}
+
+  static getInstance(): CrashReportService {
+    if (!CrashReportService.instance) {
+      CrashReportService.instance = new CrashReportService()
+    }
+    return CrashReportService.instance
+  }
+
+  /**
+   * Reports are stored in the data dir only; nothing is sent over the network
+   */
+  async getCrashReports(): Promise<CrashReport[]> {
+    return invoke<CrashReport[]>('get_crash_reports')
+  }
+
+  async clearCrashReports(): Promise<number> {
+    return invoke<number>('clear_crash_reports')
+  }
+
+  async reportCommandError(command: string, error: unknown): Promise<void> {
+    const message = error instanceof Error ? error.message : String(error)
+    try {
+      await invoke('report_command_error', { command, error: message })
+    } catch (reportError) {
+      console.error('Failed to record crash report:', reportError)
+    }
+  }
+
+  /**
+   * `invoke` that records a crash report when the command fails, then rethrows. The rest of
+   * the app reaches it through `@/lib/core/invoke`.
+   */
+  async invoke<T>(command: string, args?: InvokeArgs, options?: InvokeOptions): Promise<T> {
+    try {
+      return await invoke<T>(command, args, options)
+    } catch (error) {
+      await this.reportCommandError(command, error)
+      throw error
+    }
+  }
+}
@@ -0,0 +1,41 @@
+import { invoke } from '@/lib/core/invoke'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+// Matches Rust DeepLinkTarget
+export type DeepLinkTarget =
+  | { kind: 'document'; document_id: string; page: number | null }
+  | { kind: 'review_deck'; deck_id: string }
+  | { kind: 'import'; url: string; existing_document_id: string | null }
+
+export class DeepLinkService {
+  private static instance: DeepLinkService | null = null
+
+  private constructor() {}
+
+  static getInstance(): DeepLinkService {
+    if (!DeepLinkService.instance) {
+      DeepLinkService.instance = new DeepLinkService()
+    }
+    return DeepLinkService.instance
+  }
+
+  /**
+   * Handles stellar:// links: first any that arrived before the app was ready (such as the
+   * one it was launched with), then each new one. Invalid links are passed to onError.
+   */
+  async subscribe(
+    onLink: (target: DeepLinkTarget) => void,
+    onError?: (message: string) => void,
+  ): Promise<UnlistenFn> {
+    const unlistenLink = await listen<DeepLinkTarget>('deep-link', (event) => onLink(event.payload))
+    const unlistenError = await listen<string>('deep-link-error', (event) => onError?.(event.payload))
+
+    const pending = await invoke<DeepLinkTarget[]>('take_pending_deep_links')
+    pending.forEach(onLink)
+
+    return () => {
+      unlistenLink()
+      unlistenError()
+    }
+  }
+}
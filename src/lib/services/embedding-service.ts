@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core"
+import { invoke } from "@/lib/core/invoke"
 import { path } from "@tauri-apps/api"
 
 export interface EmbeddingConfig {
@@ -444,16 +444,19 @@ export class EmbeddingService {
   }
 
   /**
-   * Test which embedding providers are available
+   * Test which embedding providers are available by embedding a probe text with each
    */
   async testProviderAvailability(): Promise<{
     available_providers: string[];
     recommended_provider: string;
     test_results: Array<{
       provider: string;
+      model: string;
       available: boolean;
-      error?: string;
-      model?: string;
+      latency_ms: number | null;
+      dimensions: number | null;
+      expected_dimensions: number;
+      error: string | null;
       has_api_key?: boolean;
     }>;
     fallback_order: string[];
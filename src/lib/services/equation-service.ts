@@ -0,0 +1,40 @@
+import { invoke } from '@/lib/core/invoke'
+import type { AIProvider } from '@/lib/stores/ai-store'
+
+export type EquationEngine = 'vision' | 'pix2tex'
+
+// Matches Rust LatexConversion
+export interface LatexConversion {
+  latex: string // Without math delimiters
+  markdown: string // A $$ block, ready to insert into a note or flashcard
+  engine: EquationEngine
+}
+
+export class EquationService {
+  private static instance: EquationService | null = null
+
+  private constructor() {}
+
+  static getInstance(): EquationService {
+    if (!EquationService.instance) {
+      EquationService.instance = new EquationService()
+    }
+    return EquationService.instance
+  }
+
+  /**
+   * Converts an equation screenshot (data URL or base64) to LaTeX. The vision engine needs a
+   * provider and a vision-capable model; pix2tex runs locally.
+   */
+  async convertImageToLatex(
+    image: string,
+    options: { engine?: EquationEngine; provider?: AIProvider; model?: string } = {},
+  ): Promise<LatexConversion> {
+    return invoke<LatexConversion>('convert_image_to_latex', {
+      image,
+      engine: options.engine,
+      provider: options.provider,
+      model: options.model,
+    })
+  }
+}
@@ -0,0 +1,111 @@
+import { invoke } from '@/lib/core/invoke'
+
+// Matches Rust RubricCriterion
+export interface RubricCriterion {
+  name: string
+  description: string
+  max_points: number
+  levels?: string[] // Optional descriptors, e.g. "4: clear, arguable thesis"
+}
+
+export interface Rubric {
+  id: string
+  name: string
+  description: string | null
+  criteria: RubricCriterion[]
+  is_builtin: boolean // Built-in rubrics can be edited but not deleted
+  created_at: string
+  updated_at: string
+}
+
+export interface SaveRubricRequest {
+  id?: string // Update this rubric instead of creating one
+  name: string
+  description?: string | null
+  criteria: RubricCriterion[]
+}
+
+export interface CriterionFeedback {
+  criterion: string
+  score: number
+  max_points: number
+  comment: string
+}
+
+// Matches Rust EssayFeedback
+export interface EssayFeedback {
+  id: string
+  document_id: string | null
+  rubric_id: string | null
+  rubric_name: string
+  word_count: number
+  total_score: number
+  max_score: number
+  criteria: CriterionFeedback[]
+  summary: string
+  strengths: string[]
+  improvements: string[]
+  model: string | null
+  created_at: string
+}
+
+export interface EssayGradeResult {
+  feedback: EssayFeedback
+  previous: EssayFeedback | null // Last feedback on the same document with the same rubric
+  score_change: number | null
+  chunks: number
+  truncated: boolean
+}
+
+export type EssaySource = { text: string } | { documentId: string }
+
+export class EssayService {
+  private static instance: EssayService | null = null
+
+  private constructor() {}
+
+  static getInstance(): EssayService {
+    if (!EssayService.instance) {
+      EssayService.instance = new EssayService()
+    }
+    return EssayService.instance
+  }
+
+  /**
+   * Grades essay text or a document against a rubric; feedback on documents is kept for revisions
+   */
+  async gradeEssay(
+    provider: { id: string; type: string; base_url: string; [key: string]: unknown },
+    model: string,
+    source: EssaySource,
+    rubricId: string,
+  ): Promise<EssayGradeResult> {
+    return invoke<EssayGradeResult>('grade_essay', {
+      provider,
+      model,
+      text: 'text' in source ? source.text : null,
+      documentId: 'documentId' in source ? source.documentId : null,
+      rubricId,
+    })
+  }
+
+  async getFeedbackHistory(documentId: string): Promise<EssayFeedback[]> {
+    return invoke<EssayFeedback[]>('get_essay_feedback', { documentId })
+  }
+
+  async deleteFeedback(feedbackId: string): Promise<boolean> {
+    return invoke<boolean>('delete_essay_feedback', { feedbackId })
+  }
+
+  async getRubrics(): Promise<Rubric[]> {
+    return invoke<Rubric[]>('get_rubrics')
+  }
+
+  async saveRubric(request: SaveRubricRequest): Promise<Rubric> {
+    return invoke<Rubric>('save_rubric', { request })
+  }
+
+  async deleteRubric(rubricId: string): Promise<boolean> {
+    return invoke<boolean>('delete_rubric', { rubricId })
+  }
+}
@@ -2,9 +2,19 @@ import { extractTextFromHTML } from "@/lib/utils/tiptap-utils";
 import { convertDocxFileToMarkdown } from "@/lib/utils/docx-mammoth";
 import { getFileExtension } from "@/lib/utils/document-import";
 import { convertPdfFileToMarkdown } from "@/lib/utils/pdf2md-converter";
-import { invoke } from "@tauri-apps/api/core";
+import type { AIProvider } from "@/lib/stores/ai-store";
+import { invoke } from "@/lib/core/invoke";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { open } from "@tauri-apps/plugin-dialog";
 
+export interface PdfDownloadProgress {
+	url: string;
+	downloaded_bytes: number;
+	total_bytes: number | null;
+	attempt: number; // > 1 when the download resumed after a dropped connection
+	done: boolean;
+}
+
 export interface Document {
 	id: string;
 	title: string;
@@ -16,6 +26,92 @@ export interface Document {
 	updated_at: string;
 	status: string;
 	category_id?: string;
+	source_url?: string; // Normalized URL for documents imported from the web
+	language?: string; // ISO 639-3 code detected from the content, e.g. "eng", "cmn"
+	content_length: number; // Characters, also set when the content was left out
+}
+
+export interface DocumentContentRange {
+	document_id: string;
+	offset: number;
+	content: string;
+	content_length: number;
+	content_hash?: string;
+}
+
+export interface QuickSearchHit {
+	document_id: string;
+	title: string;
+	doc_type: string;
+	category_id?: string;
+	tags: string[];
+	match_kind: "title" | "tag" | "content";
+	snippet?: string;
+	updated_at: string;
+}
+
+export interface QuickSearchResults {
+	query: string;
+	hits: QuickSearchHit[];
+	complete: boolean; // False while content matches are still being looked up
+	elapsed_ms: number;
+}
+
+export interface HighlightSearchHit {
+	id: string;
+	document_id?: string;
+	document_title?: string;
+	conversation_id: string;
+	text: string;
+	start_offset?: number;
+	end_offset?: number;
+	created_at: string;
+}
+
+export interface FlashcardSearchHit {
+	id: string;
+	front: string;
+	back: string;
+	deck_id?: string;
+	source_document_id?: string;
+	tags: string[];
+	next_review?: string;
+}
+
+export interface ConversationSearchHit {
+	id: string;
+	title: string;
+	conversation_type: string;
+	match_kind: "title" | "message";
+	snippet?: string;
+	updated_at: string;
+}
+
+export interface GlobalSearchLimits {
+	documents?: number;
+	highlights?: number;
+	flashcards?: number;
+	conversations?: number;
+}
+
+export interface GlobalSearchResults {
+	query: string;
+	documents: QuickSearchHit[];
+	highlights: HighlightSearchHit[];
+	flashcards: FlashcardSearchHit[];
+	conversations: ConversationSearchHit[];
+}
+
+export interface CodeSnippet {
+	id: string;
+	document_id: string;
+	document_title: string;
+	snippet_index: number;
+	language?: string;
+	content: string;
+	start_offset: number;
+	end_offset: number;
+	created_at: string;
 }
 
 export interface Category {
@@ -25,11 +121,31 @@ export interface Category {
 	color?: string;
 	icon?: string;
 	parent_id?: string;
+	archived: boolean;
 	created_at: string;
 	updated_at: string;
 	document_count: number;
 }
 
+export interface NoteTemplate {
+	id: string;
+	name: string;
+	description?: string;
+	title_template: string; // {{variable}} placeholders, like body
+	body: string;
+	is_builtin: boolean;
+	created_at: string;
+	updated_at: string;
+}
+
+export interface SaveNoteTemplateRequest {
+	id?: string;
+	name: string;
+	description?: string;
+	title_template: string;
+	body: string;
+}
+
 export interface CreateDocumentRequest {
 	title: string;
 	content: string;
@@ -65,6 +181,25 @@ export interface UploadPdfFromUrlOptions {
 	title?: string;
 	tags?: string[];
 	categoryId?: string;
+	force?: boolean; // Import again even if this URL was imported before
+}
+
+export type HandwritingEngine = "vision" | "trocr";
+
+// Matches Rust HandwritingImport
+export interface HandwritingImport {
+	document: Document;
+	engine: HandwritingEngine;
+	confidence: number; // 0-1
+	needs_review: boolean; // Low confidence; the note should be proofread against the photo
+}
+
+export type PasteKind = "url" | "bibtex" | "csv" | "code" | "text";
+
+// Matches Rust SmartPasteResult
+export interface SmartPasteResult {
+	kind: PasteKind;
+	documents: Document[]; // One per BibTeX entry; a single document otherwise
 }
 
 // Minimal type for background processing job returned by background job creation commands
@@ -77,6 +212,7 @@ interface ProcessingJob {
 	progress: number;
 	error_message?: string;
 	result_document_id?: string;
+	source_url?: string;
 }
 
 export class LibraryService {
@@ -332,6 +468,7 @@ export class LibraryService {
 					tags: options.tags || null,
 					category_id: options.categoryId || null,
 					force_ocr: false,
+					force: options.force ?? null,
 				},
 			);
 
@@ -343,6 +480,17 @@ export class LibraryService {
 		}
 	}
 
+	/**
+	 * Listen for progress of PDFs being downloaded from URLs
+	 */
+	async onPdfDownloadProgress(
+		callback: (progress: PdfDownloadProgress) => void,
+	): Promise<UnlistenFn> {
+		return listen<PdfDownloadProgress>("pdf-download-progress", (event) =>
+			callback(event.payload),
+		);
+	}
+
 	async downloadPdfFromUrlAndProcessBackground(
 		options: UploadPdfFromUrlOptions,
 	): Promise<Document> {
@@ -360,6 +508,7 @@ export class LibraryService {
 					title: options.title || null,
 					tags: options.tags || null,
 					category_id: options.categoryId || null,
+					force: options.force ?? null,
 				},
 			);
 
@@ -458,6 +607,27 @@ export class LibraryService {
 		}
 	}
 
+	// Saves a short note tagged "inbox"; with a provider and model, topic tags are added in the background
+	async quickCapture(
+		text: string,
+		sourceUrl?: string,
+		autoTag?: { provider: AIProvider; model: string },
+	): Promise<Document> {
+		try {
+			const document = await invoke<Document>("quick_capture", {
+				text,
+				sourceUrl: sourceUrl ?? null,
+				provider: autoTag?.provider ?? null,
+				model: autoTag?.model ?? null,
+			});
+			console.log("Captured note:", document.id);
+			return document;
+		} catch (error) {
+			console.error("Failed to capture note:", error);
+			throw error;
+		}
+	}
+
 	async getAllDocuments(): Promise<Document[]> {
 		try {
 			const documents = await invoke<Document[]>("get_all_documents");
@@ -468,9 +638,15 @@ export class LibraryService {
 		}
 	}
 
-	async getDocument(id: string): Promise<Document | null> {
+	async getDocument(
+		id: string,
+		includeContent = true,
+	): Promise<Document | null> {
 		try {
-			const document = await invoke<Document | null>("get_document", { id });
+			const document = await invoke<Document | null>("get_document", {
+				id,
+				includeContent,
+			});
 			return document;
 		} catch (error) {
 			console.error("Failed to get document:", error);
@@ -478,6 +654,24 @@ export class LibraryService {
 		}
 	}
 
+	// Offsets and lengths are in characters
+	async getDocumentContentRange(
+		id: string,
+		offset: number,
+		length: number,
+	): Promise<DocumentContentRange> {
+		try {
+			return await invoke<DocumentContentRange>("get_document_content_range", {
+				id,
+				offset,
+				length,
+			});
+		} catch (error) {
+			console.error("Failed to get document content range:", error);
+			throw error;
+		}
+	}
+
 	async updateDocument(
 		id: string,
 		request: CreateDocumentRequest,
@@ -564,6 +758,28 @@ export class LibraryService {
 		}
 	}
 
+	/**
+	 * Archive a finished course: hides its documents and decks from default
+	 * listings, due counts and search (subcategories included)
+	 */
+	async archiveCategory(id: string): Promise<Category | null> {
+		try {
+			return await invoke<Category | null>("archive_category", { id });
+		} catch (error) {
+			console.error("Failed to archive category:", error);
+			throw error;
+		}
+	}
+
+	async unarchiveCategory(id: string): Promise<Category | null> {
+		try {
+			return await invoke<Category | null>("unarchive_category", { id });
+		} catch (error) {
+			console.error("Failed to unarchive category:", error);
+			throw error;
+		}
+	}
+
 	async deleteCategory(id: string): Promise<boolean> {
 		try {
 			const success = await invoke<boolean>("delete_category", { id });
@@ -575,6 +791,54 @@ export class LibraryService {
 		}
 	}
 
+	async getNoteTemplates(): Promise<NoteTemplate[]> {
+		try {
+			return await invoke<NoteTemplate[]>("get_note_templates");
+		} catch (error) {
+			console.error("Failed to get note templates:", error);
+			throw error;
+		}
+	}
+
+	async saveNoteTemplate(request: SaveNoteTemplateRequest): Promise<NoteTemplate> {
+		try {
+			return await invoke<NoteTemplate>("save_note_template", { request });
+		} catch (error) {
+			console.error("Failed to save note template:", error);
+			throw error;
+		}
+	}
+
+	async deleteNoteTemplate(templateId: string): Promise<boolean> {
+		try {
+			return await invoke<boolean>("delete_note_template", { templateId });
+		} catch (error) {
+			console.error("Failed to delete note template:", error);
+			throw error;
+		}
+	}
+
+	/**
+	 * Create a note from a template. date, time, course and lecture_number are
+	 * filled in by the backend unless given in `variables`.
+	 */
+	async createNoteFromTemplate(
+		templateId: string,
+		variables: Record<string, string> = {},
+		categoryId?: string,
+	): Promise<Document> {
+		try {
+			return await invoke<Document>("create_note_from_template", {
+				templateId,
+				variables,
+				categoryId,
+			});
+		} catch (error) {
+			console.error("Failed to create note from template:", error);
+			throw error;
+		}
+	}
+
 	async getDocumentsByCategory(categoryId: string): Promise<Document[]> {
 		try {
 			const documents = await invoke<Document[]>("get_documents_by_category", {
@@ -597,12 +861,17 @@ export class LibraryService {
 		}
 	}
 
-	async searchDocuments(query: string, limit = 25): Promise<Document[]> {
+	async searchDocuments(
+		query: string,
+		limit = 25,
+		math = false,
+	): Promise<Document[]> {
 		try {
 			if (!query.trim()) return [];
 			const documents = await invoke<Document[]>("search_documents", {
 				query,
 				limit,
+				math,
 			});
 			return documents;
 		} catch (error) {
@@ -611,6 +880,126 @@ export class LibraryService {
 		}
 	}
 
+	// Command-palette search; call again (debounced) while `complete` is false to pick up
+	// content matches
+	async quickSearch(prefix: string, limit = 10): Promise<QuickSearchResults> {
+		try {
+			return await invoke<QuickSearchResults>("quick_search", {
+				prefix,
+				limit,
+			});
+		} catch (error) {
+			console.error("Failed to quick search:", error);
+			throw error;
+		}
+	}
+
+	// Documents, highlights, flashcards and conversations in one call; a limit of 0 skips
+	// that group
+	async globalSearch(
+		query: string,
+		limits?: GlobalSearchLimits,
+	): Promise<GlobalSearchResults> {
+		try {
+			return await invoke<GlobalSearchResults>("global_search", {
+				query,
+				limits,
+			});
+		} catch (error) {
+			console.error("Failed to search:", error);
+			throw error;
+		}
+	}
+
+	async searchCodeSnippets(
+		query: string,
+		language?: string,
+		limit = 25,
+	): Promise<CodeSnippet[]> {
+		try {
+			return await invoke<CodeSnippet[]>("search_code_snippets", {
+				query,
+				language,
+				limit,
+			});
+		} catch (error) {
+			console.error("Failed to search code snippets:", error);
+			throw error;
+		}
+	}
+
+	async getDocumentCodeSnippets(documentId: string): Promise<CodeSnippet[]> {
+		try {
+			return await invoke<CodeSnippet[]>("get_document_code_snippets", {
+				documentId,
+			});
+		} catch (error) {
+			console.error("Failed to get code snippets:", error);
+			throw error;
+		}
+	}
+
+	async getDocumentThumbnail(
+		id: string,
+		size?: number,
+	): Promise<string | null> {
+		try {
+			return await invoke<string | null>("get_document_thumbnail", {
+				id,
+				size,
+			});
+		} catch (error) {
+			console.error("Failed to get document thumbnail:", error);
+			return null;
+		}
+	}
+
+	// Vision engines need a provider and a vision-capable model; "trocr" runs locally
+	async importHandwrittenNotes(
+		path: string,
+		options: {
+			engine?: HandwritingEngine;
+			provider?: AIProvider;
+			model?: string;
+			title?: string;
+			tags?: string[];
+			categoryId?: string;
+		} = {},
+	): Promise<HandwritingImport> {
+		return await invoke<HandwritingImport>("import_handwritten_notes", {
+			path,
+			engine: options.engine,
+			provider: options.provider,
+			model: options.model,
+			title: options.title,
+			tags: options.tags,
+			categoryId: options.categoryId,
+		});
+	}
+
+	// URLs are imported, BibTeX becomes reference notes, tables and code become notes, and
+	// plain text a quick capture in the inbox
+	async smartPaste(
+		payload: string,
+		options: { title?: string; tags?: string[]; categoryId?: string } = {},
+	): Promise<SmartPasteResult> {
+		return await invoke<SmartPasteResult>("smart_paste", {
+			payload,
+			title: options.title,
+			tags: options.tags,
+			categoryId: options.categoryId,
+		});
+	}
+
+	async getHandwritingImage(documentId: string): Promise<string | null> {
+		try {
+			return await invoke<string | null>("get_handwriting_image", { documentId });
+		} catch (error) {
+			console.error("Failed to get handwriting image:", error);
+			return null;
+		}
+	}
+
 	// Helper method to format document date
 	formatDate(dateString: string): string {
 		try {
@@ -0,0 +1,49 @@
+import { invoke } from '@/lib/core/invoke'
+
+// Matches Rust McpServerSettings
+export interface McpServerSettings {
+  enabled: boolean
+  port: number
+  token: string | null // Sent by clients as a bearer token
+  allow_writes: boolean // Off: create_flashcard is hidden and refused
+}
+
+/**
+ * Settings for the MCP server that lets Claude Desktop and other MCP clients search the
+ * library and work with flashcards
+ */
+export class McpService {
+  private static instance: McpService | null = null
+
+  private constructor() {}
+
+  static getInstance(): McpService {
+    if (!McpService.instance) {
+      McpService.instance = new McpService()
+    }
+    return McpService.instance
+  }
+
+  async getSettings(): Promise<McpServerSettings> {
+    return invoke<McpServerSettings>('get_mcp_settings')
+  }
+
+  async updateSettings(settings: McpServerSettings): Promise<McpServerSettings> {
+    return invoke<McpServerSettings>('update_mcp_settings', { settings })
+  }
+
+  async regenerateToken(): Promise<McpServerSettings> {
+    return invoke<McpServerSettings>('regenerate_mcp_token')
+  }
+
+  async isRunning(): Promise<boolean> {
+    return invoke<boolean>('is_mcp_server_running')
+  }
+
+  /**
+   * Config entry for the client's mcpServers section, e.g. claude_desktop_config.json
+   */
+  async getClientConfig(): Promise<Record<string, unknown>> {
+    return invoke<Record<string, unknown>>('get_mcp_client_config')
+  }
+}
@@ -1,5 +1,5 @@
 import { AIProvider, AIModel } from "@/lib/stores/ai-store"
-import { invoke } from "@tauri-apps/api/core"
+import { invoke } from "@/lib/core/invoke"
 
 // Types from models.dev API
 interface ModelsDevModel {
@@ -0,0 +1,54 @@
+import { invoke } from '@/lib/core/invoke'
+
+// Errors from commands that need the network start with this while offline mode is on
+export const OFFLINE_ERROR_PREFIX = 'offline_mode:'
+
+export function isOfflineError(error: unknown): boolean {
+  const message = error instanceof Error ? error.message : String(error)
+  return message.includes(OFFLINE_ERROR_PREFIX)
+}
+
+export interface ProxySettings {
+  mode: 'system' | 'manual' | 'none'
+  url?: string | null
+  username?: string | null
+  password?: string | null // Write-only: omit to keep the saved password, '' to clear it
+  has_password?: boolean
+  no_proxy?: string | null
+}
+
+export class NetworkService {
+  private static instance: NetworkService | null = null
+
+  private constructor() {}
+
+  static getInstance(): NetworkService {
+    if (!NetworkService.instance) {
+      NetworkService.instance = new NetworkService()
+    }
+    return NetworkService.instance
+  }
+
+  async getOfflineMode(): Promise<boolean> {
+    return invoke<boolean>('get_offline_mode')
+  }
+
+  /**
+   * Offline mode limits the app to local models (rust-bert/fastembed, Ollama)
+   * and makes network features fail immediately
+   */
+  async setOfflineMode(enabled: boolean): Promise<boolean> {
+    return invoke<boolean>('set_offline_mode', { enabled })
+  }
+
+  async getProxySettings(): Promise<ProxySettings> {
+    return invoke<ProxySettings>('get_proxy_settings')
+  }
+
+  /**
+   * Manual mode accepts http(s):// and socks5:// proxy URLs
+   */
+  async updateProxySettings(settings: ProxySettings): Promise<ProxySettings> {
+    return invoke<ProxySettings>('update_proxy_settings', { settings })
+  }
+}
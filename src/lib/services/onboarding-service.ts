@@ -1,6 +1,28 @@
+import { invoke } from "@/lib/core/invoke"
 import { useAIStore } from "@/lib/stores/ai-store"
 import { useSettingsStore } from "@/lib/stores/settings-store"
 
+export interface BackendOnboardingState {
+  database_ok: boolean
+  database_error: string | null
+  embedding_provider_available: boolean
+  providers_with_keys: string[]
+  chat_provider_configured: boolean
+  marker_installed: boolean
+  marker_suggested_action: string | null
+  sample_document_id: string | null
+  sample_deck_id: string | null
+  sample_content_imported: boolean
+  complete: boolean
+}
+
+export interface SampleContent {
+  document: { id: string; title: string; [key: string]: unknown }
+  deck: { id: string; name: string; [key: string]: unknown }
+  flashcards_created: number
+  already_existed: boolean
+}
+
 export class OnboardingService {
   private static instance: OnboardingService
 
@@ -74,4 +96,18 @@ export class OnboardingService {
       activeModel: aiStore.activeModelId,
     }
   }
-} 
\ No newline at end of file
+
+  /**
+   * What the backend has configured so far, to drive the guided setup
+   */
+  async getSetupState(): Promise<BackendOnboardingState> {
+    return invoke<BackendOnboardingState>("get_onboarding_state")
+  }
+
+  /**
+   * Create the demo document and flashcard deck (safe to call more than once)
+   */
+  async seedSampleContent(): Promise<SampleContent> {
+    return invoke<SampleContent>("seed_sample_content")
+  }
+}
@@ -0,0 +1,42 @@
+import { invoke } from '@/lib/core/invoke'
+
+export interface CommandMetrics {
+  command: string
+  calls: number
+  p50_ms: number
+  p95_ms: number
+  max_ms: number
+  total_ms: number
+}
+
+export interface SlowQuery {
+  message: string
+  logged_at: string
+}
+
+export interface PerformanceMetrics {
+  since: string
+  commands: CommandMetrics[]
+  slow_queries: SlowQuery[]
+  slow_query_threshold_ms: number
+}
+
+export class PerformanceService {
+  private static instance: PerformanceService | null = null
+
+  private constructor() {}
+
+  static getInstance(): PerformanceService {
+    if (!PerformanceService.instance) {
+      PerformanceService.instance = new PerformanceService()
+    }
+    return PerformanceService.instance
+  }
+
+  /**
+   * Command timings since the app started, most total time first, and recent slow queries
+   */
+  async getPerformanceMetrics(): Promise<PerformanceMetrics> {
+    return invoke<PerformanceMetrics>('get_performance_metrics')
+  }
+}
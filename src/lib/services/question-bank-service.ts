@@ -0,0 +1,92 @@
+import { invoke } from '@/lib/core/invoke'
+
+// Matches Rust ExamPaper
+export interface ExamPaper {
+  id: string
+  title: string
+  year: number | null
+  category_id: string | null
+  file_path: string | null
+  question_count: number
+  created_at: string
+}
+
+// Matches Rust ExamQuestion
+export interface ExamQuestion {
+  id: string
+  paper_id: string
+  position: number
+  number: string // As printed on the paper
+  text: string
+  marks: number | null
+  topic: string | null
+  year: number | null
+  paper_title: string
+}
+
+export interface QuestionBankTopic {
+  topic: string
+  question_count: number
+  years: number[] // Newest first
+}
+
+export interface ImportExamPaperRequest {
+  file_path: string
+  title?: string
+  year?: number
+  category_id?: string
+  // Without a model, questions are found by their numbering only and get no topic
+  provider?: { id: string; type: string; base_url: string; [key: string]: unknown }
+  model?: string
+}
+
+export interface ExamPaperImport {
+  paper: ExamPaper
+  questions: ExamQuestion[]
+  ai_extracted: boolean
+  topics_assigned: number
+}
+
+export class QuestionBankService {
+  private static instance: QuestionBankService | null = null
+
+  private constructor() {}
+
+  static getInstance(): QuestionBankService {
+    if (!QuestionBankService.instance) {
+      QuestionBankService.instance = new QuestionBankService()
+    }
+    return QuestionBankService.instance
+  }
+
+  async importExamPaper(request: ImportExamPaperRequest): Promise<ExamPaperImport> {
+    return invoke<ExamPaperImport>('import_exam_paper', { request })
+  }
+
+  async getExamPapers(categoryId?: string): Promise<ExamPaper[]> {
+    return invoke<ExamPaper[]>('get_exam_papers', { categoryId })
+  }
+
+  async getExamQuestions(paperId: string): Promise<ExamQuestion[]> {
+    return invoke<ExamQuestion[]>('get_exam_questions', { paperId })
+  }
+
+  async deleteExamPaper(paperId: string): Promise<boolean> {
+    return invoke<boolean>('delete_exam_paper', { paperId })
+  }
+
+  async setQuestionTopic(questionId: string, topic: string | null): Promise<boolean> {
+    return invoke<boolean>('set_exam_question_topic', { questionId, topic })
+  }
+
+  async getTopics(categoryId?: string): Promise<QuestionBankTopic[]> {
+    return invoke<QuestionBankTopic[]>('get_question_bank_topics', { categoryId })
+  }
+
+  /**
+   * Random past questions on a topic, across all years; any topic when none is given
+   */
+  async getPracticeSet(topic: string | null, count = 10, categoryId?: string): Promise<ExamQuestion[]> {
+    return invoke<ExamQuestion[]>('get_practice_set', { topic, count, categoryId })
+  }
+}
@@ -0,0 +1,43 @@
+import { invoke } from '@/lib/core/invoke'
+
+// Matches Rust ReminderSettings; hours are local, 0-23
+export interface ReminderSettings {
+  enabled: boolean
+  due_reviews: boolean
+  due_review_threshold: number // Only remind once at least this many cards are due
+  due_review_interval_hours: number // At most one due-review reminder per this many hours
+  study_goal_minutes: number // Daily study goal; 0 turns goal nudges off
+  study_goal_nudge_hour: number
+  job_notifications: boolean
+  quiet_hours_enabled: boolean
+  quiet_hours_start: number
+  quiet_hours_end: number // Earlier than the start when quiet hours span midnight
+}
+
+export class ReminderService {
+  private static instance: ReminderService | null = null
+
+  private constructor() {}
+
+  static getInstance(): ReminderService {
+    if (!ReminderService.instance) {
+      ReminderService.instance = new ReminderService()
+    }
+    return ReminderService.instance
+  }
+
+  async getSettings(): Promise<ReminderSettings> {
+    return invoke<ReminderSettings>('get_reminder_settings')
+  }
+
+  async updateSettings(settings: ReminderSettings): Promise<ReminderSettings> {
+    return invoke<ReminderSettings>('update_reminder_settings', { settings })
+  }
+
+  /**
+   * Shows a notification immediately, ignoring quiet hours
+   */
+  async sendTestNotification(): Promise<void> {
+    return invoke<void>('send_test_notification')
+  }
+}
@@ -1,5 +1,5 @@
-import { invoke } from '@tauri-apps/api/core'
-import { UserAction, ActionType } from './actions-service'
+import { invoke } from '@/lib/core/invoke'
+import { UserAction, ActionType, SessionType } from './actions-service'
 
 export interface SessionDetectionConfig {
   idleThresholdMinutes: number // Default: 30 minutes
@@ -18,7 +18,7 @@ export interface ActivityPattern {
 
 export interface SessionSuggestion {
   suggestedTitle: string
-  suggestedType: 'focused' | 'exploratory' | 'review' | 'mixed'
+  suggestedType: SessionType
   confidence: number // 0-1
   reasoning: string
 }
@@ -222,14 +222,14 @@ export class SessionDetectionService {
       if (docCount === 1) {
         suggestions.push({
           suggestedTitle: "Document Study Session",
-          suggestedType: "focused",
+          suggestedType: "reading",
           confidence: 0.8,
           reasoning: "Single document focus indicates concentrated study"
         })
       } else if (docCount > 1) {
         suggestions.push({
           suggestedTitle: `Multi-Document Research (${docCount} docs)`,
-          suggestedType: "exploratory",
+          suggestedType: "reading",
           confidence: 0.7,
           reasoning: "Multiple documents suggest research or comparison activity"
         })
@@ -242,7 +242,7 @@ export class SessionDetectionService {
       if (chatActions.length > 10) {
         suggestions.push({
           suggestedTitle: "Deep Learning Discussion",
-          suggestedType: "exploratory",
+          suggestedType: "ai_tutoring",
           confidence: 0.75,
           reasoning: "High chat activity suggests active learning through questions"
         })
@@ -270,7 +270,7 @@ export class SessionDetectionService {
     if (pattern.dominantAction === ActionType.NOTE_CREATE || pattern.dominantAction === ActionType.NOTE_EDIT) {
       suggestions.push({
         suggestedTitle: "Note-Taking Session",
-        suggestedType: "focused",
+        suggestedType: "reading",
         confidence: 0.8,
         reasoning: "Note-taking activity suggests focused content processing"
       })
@@ -280,7 +280,7 @@ export class SessionDetectionService {
     if (pattern.dominantAction === ActionType.SEARCH_QUERY) {
       suggestions.push({
         suggestedTitle: "Research & Discovery",
-        suggestedType: "exploratory",
+        suggestedType: "reading",
         confidence: 0.7,
         reasoning: "Search activity indicates exploration and discovery"
       })
@@ -315,7 +315,7 @@ export class SessionDetectionService {
     if (pattern.activityIntensity === 'high') {
       suggestions.push({
         suggestedTitle: "Intensive Study Session",
-        suggestedType: "focused",
+        suggestedType: "mixed",
         confidence: 0.6,
         reasoning: "High activity intensity suggests focused study"
       })
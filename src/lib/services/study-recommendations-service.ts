@@ -1,5 +1,5 @@
 import { aiService } from './ai-service'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '@/lib/core/invoke'
 import type { UserAction } from './actions-service'
 
 // 🧠 PHASE 2: Smart Study Recommendations System
@@ -7,9 +7,12 @@ import { ModelsService } from "@/lib/services/models-service"
 export interface AIProvider {
   id: string
   name: string
-  type: "openai" | "anthropic" | "ollama" | "custom"
+  type: "openai" | "anthropic" | "ollama" | "gemini" | "custom"
   baseUrl: string
   apiKey?: string
+  organization?: string
+  apiVersion?: string // Azure OpenAI
+  extraHeaders?: Record<string, string>
   enabled: boolean
   models: AIModel[]
 }
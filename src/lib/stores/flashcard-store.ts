@@ -1,6 +1,6 @@
 import { create } from 'zustand'
 import { persist } from 'zustand/middleware'
-import { invoke } from '@tauri-apps/api/core'
+import { invoke } from '@/lib/core/invoke'
 import { flashcardService } from '@/lib/services/flashcard-service'
 
 // 🧠 PHASE 2: Flashcard System - TypeScript Interfaces
@@ -43,6 +43,12 @@ export interface FlashcardReview {
   metadata?: Record<string, any>
 }
 
+// record_flashcard_review stores the review and reschedules the card in one transaction
+export interface FlashcardReviewResult {
+  flashcard: Flashcard
+  review: FlashcardReview
+}
+
 export interface FlashcardDeck {
   id: string
   name: string
@@ -57,8 +63,12 @@ export interface FlashcardDeck {
   cardCount: number
   dueCount: number
   metadata?: Record<string, any>
+  newCardOrder: NewCardOrder
 }
 
+// How a deck introduces cards that have never been reviewed
+export type NewCardOrder = 'created' | 'random' | 'document' | 'difficulty'
+
 export interface CreateFlashcardRequest {
   front: string
   back: string
@@ -81,6 +91,7 @@ export interface CreateFlashcardDeckRequest {
   tags: string[]
   isShared?: boolean
   metadata?: Record<string, any>
+  newCardOrder?: NewCardOrder // Omit on update to keep the current order
 }
 
 export interface CreateFlashcardReviewRequest {
@@ -91,6 +102,67 @@ export interface CreateFlashcardReviewRequest {
   confidence: 1 | 2 | 3 | 4 | 5
   quality: 0 | 1 | 2 | 3 | 4 | 5
   metadata?: Record<string, any>
+  reviewSessionId?: string // Persisted review session to advance
+}
+
+export interface FlashcardDetailStats {
+  flashcard: Flashcard
+  reviews: FlashcardReview[] // Oldest first
+  interval_curve: { timestamp: string; interval: number; ease_factor: number }[]
+  total_reviews: number
+  correct_count: number
+  lapse_count: number // Failed reviews after the card had been answered well
+  average_time_spent: number // Seconds
+  first_reviewed?: string
+}
+
+export interface ReviewCardResult {
+  flashcard_id: string
+  review_id: string
+  response: 'correct' | 'incorrect' | 'partial'
+  quality: number
+}
+
+// A review session saved as it progresses so it can be resumed after the app closes
+export interface PersistedReviewSession {
+  id: string
+  study_session_id?: string
+  mix_strategy: string
+  card_ids: string[]
+  results: ReviewCardResult[]
+  position: number // Index of the next card to show
+  status: 'active' | 'completed' | 'abandoned' | 'expired'
+  created_at: string
+  updated_at: string
+  expires_at: string
+}
+
+export interface ResumedReviewSession {
+  session: PersistedReviewSession
+  remaining_cards: Flashcard[]
+}
+
+// Filters for a custom (cram) session; every filter given must match
+export interface CustomReviewFilter {
+  tags?: string[] // Any of these tags
+  deckId?: string
+  categoryId?: string
+  sourceDocumentId?: string
+  failedWithinDays?: number
+  dueWithinDays?: number
+  includeNew?: boolean // Default: true
+  limit?: number // Default: 50
+}
+
+export interface FlashcardDeckStats {
+  deckId: string
+  cardCount: number
+  dueCount: number
+  newCount: number
+  learningCount: number
+  masteredCount: number
+  averageSuccessRate: number
+  reviewsToday: number
 }
 
 export interface FlashcardStats {
@@ -113,7 +185,15 @@ export interface FlashcardReviewSession {
   newCards: Flashcard[]
   sessionLimit: number
   estimatedTime: number // in minutes
-  mixStrategy: 'due_first' | 'mixed' | 'new_first'
+  mixStrategy: 'due_first' | 'mixed' | 'new_first' | 'custom' | 'adaptive'
+}
+
+// A session sized to the time available, at the user's own pace per card
+export interface AdaptiveReviewSession extends FlashcardReviewSession {
+  minutesAvailable: number
+  secondsPerDueCard: number
+  secondsPerNewCard: number
+  timedReviews: number
 }
 
 // Zustand Store for Flashcard Management
@@ -145,18 +225,25 @@ interface FlashcardState {
   deleteDeck: (id: string) => Promise<boolean>
   getDeck: (id: string) => Promise<FlashcardDeck | null>
   getDecks: () => Promise<FlashcardDeck[]>
+  getDeckStats: (deckId: string) => Promise<FlashcardDeckStats>
   setCurrentDeck: (deck: FlashcardDeck | null) => void
 
   // Review actions
-  recordReview: (request: CreateFlashcardReviewRequest) => Promise<FlashcardReview>
+  recordReview: (request: CreateFlashcardReviewRequest) => Promise<FlashcardReviewResult>
   getDueFlashcards: (limit?: number) => Promise<Flashcard[]>
-  getNewFlashcards: (limit?: number) => Promise<Flashcard[]>
+  getNewFlashcards: (limit?: number, deckId?: string, order?: NewCardOrder) => Promise<Flashcard[]>
   getReviewSession: (sessionLimit: number, mixStrategy: string) => Promise<FlashcardReviewSession>
+  getAdaptiveReviewSession: (minutesAvailable: number) => Promise<AdaptiveReviewSession>
+  getCustomReviewSession: (filter: CustomReviewFilter) => Promise<FlashcardReviewSession>
+  persistReviewSession: (cardIds: string[], studySessionId?: string, mixStrategy?: string) => Promise<PersistedReviewSession>
+  resumeReviewSession: () => Promise<ResumedReviewSession | null>
+  finishReviewSession: (id: string, abandoned?: boolean) => Promise<boolean>
   startReviewSession: (session: FlashcardReviewSession) => void
   endReviewSession: () => void
 
   // Statistics
   getStats: () => Promise<FlashcardStats>
+  getCardDetailStats: (cardId: string) => Promise<FlashcardDetailStats>
   refreshStats: () => Promise<void>
 
   // AI Generation
@@ -350,6 +437,15 @@ export const useFlashcardStore = create<FlashcardState>()(
         }
       },
 
+      getDeckStats: async (deckId: string) => {
+        try {
+          return await invoke<FlashcardDeckStats>('get_flashcard_deck_stats', { deckId })
+        } catch (error) {
+          set({ error: `Failed to get deck stats: ${error}` })
+          throw error
+        }
+      },
+
       setCurrentDeck: (deck: FlashcardDeck | null) => {
         set({ currentDeck: deck })
       },
@@ -357,10 +453,25 @@ export const useFlashcardStore = create<FlashcardState>()(
       // Review system
       recordReview: async (request: CreateFlashcardReviewRequest) => {
         try {
-          const review = await invoke<FlashcardReview>('record_flashcard_review', { request })
-          // Refresh flashcards to update review data
-          get().refreshFlashcards()
-          return review
+          const result = await invoke<FlashcardReviewResult>('record_flashcard_review', {
+            request: {
+              flashcard_id: request.flashcardId,
+              session_id: request.sessionId,
+              response: request.response,
+              time_spent: request.timeSpent,
+              confidence: request.confidence,
+              quality: request.quality,
+              metadata: request.metadata ?? null,
+              review_session_id: request.reviewSessionId ?? null,
+            },
+          })
+          // The response carries the rescheduled card, so no full refresh is needed
+          set({
+            flashcards: get().flashcards.map((card) =>
+              card.id === result.flashcard.id ? result.flashcard : card
+            ),
+          })
+          return result
         } catch (error) {
           set({ error: `Failed to record review: ${error}` })
           throw error
@@ -377,9 +488,9 @@ export const useFlashcardStore = create<FlashcardState>()(
         }
       },
 
-      getNewFlashcards: async (limit?: number) => {
+      getNewFlashcards: async (limit?: number, deckId?: string, order?: NewCardOrder) => {
         try {
-          const flashcards = await invoke<Flashcard[]>('get_new_flashcards', { limit })
+          const flashcards = await invoke<Flashcard[]>('get_new_flashcards', { limit, deckId, order })
           return flashcards
         } catch (error) {
           set({ error: `Failed to get new flashcards: ${error}` })
@@ -400,6 +511,57 @@ export const useFlashcardStore = create<FlashcardState>()(
         }
       },
 
+      getAdaptiveReviewSession: async (minutesAvailable: number) => {
+        try {
+          return await invoke<AdaptiveReviewSession>('get_adaptive_review_session', { minutesAvailable })
+        } catch (error) {
+          set({ error: `Failed to get adaptive review session: ${error}` })
+          throw error
+        }
+      },
+
+      getCustomReviewSession: async (filter: CustomReviewFilter) => {
+        try {
+          return await invoke<FlashcardReviewSession>('get_custom_review_session', { filter })
+        } catch (error) {
+          set({ error: `Failed to get custom review session: ${error}` })
+          throw error
+        }
+      },
+
+      persistReviewSession: async (cardIds: string[], studySessionId?: string, mixStrategy?: string) => {
+        try {
+          return await invoke<PersistedReviewSession>('start_review_session', {
+            request: {
+              card_ids: cardIds,
+              study_session_id: studySessionId ?? null,
+              mix_strategy: mixStrategy ?? null,
+            },
+          })
+        } catch (error) {
+          set({ error: `Failed to save review session: ${error}` })
+          throw error
+        }
+      },
+
+      resumeReviewSession: async () => {
+        try {
+          return await invoke<ResumedReviewSession | null>('resume_review_session')
+        } catch (error) {
+          set({ error: `Failed to resume review session: ${error}` })
+          throw error
+        }
+      },
+
+      finishReviewSession: async (id: string, abandoned?: boolean) => {
+        try {
+          return await invoke<boolean>('finish_review_session', { id, abandoned })
+        } catch (error) {
+          set({ error: `Failed to finish review session: ${error}` })
+          throw error
+        }
+      },
+
       startReviewSession: (session: FlashcardReviewSession) => {
         set({ currentReviewSession: session, isReviewMode: true })
       },
@@ -420,6 +582,15 @@ export const useFlashcardStore = create<FlashcardState>()(
         }
       },
 
+      getCardDetailStats: async (cardId: string) => {
+        try {
+          return await invoke<FlashcardDetailStats>('get_flashcard_detail_stats', { cardId })
+        } catch (error) {
+          set({ error: `Failed to get card stats: ${error}` })
+          throw error
+        }
+      },
+
       refreshStats: async () => {
         try {
           await get().getStats()
@@ -238,6 +238,7 @@ export interface StudyState {
 	shouldOpenCreateCategoryDialog: boolean;
 	shouldOpenUploadDialog: boolean;
 	pendingUploadFile: File | null;
+	pendingUploadUrl: string | null;
 
 	setCurrentView: (view: StudyState["currentView"]) => void;
 	setEditingNoteId: (noteId: string | null) => void;
@@ -285,6 +286,7 @@ export interface StudyState {
 	setShouldOpenCreateCategoryDialog: (open: boolean) => void;
 	setShouldOpenUploadDialog: (open: boolean) => void;
 	setPendingUploadFile: (file: File | null) => void;
+	setPendingUploadUrl: (url: string | null) => void;
 }
 
 export const useStudyStore = create<StudyState>()(
@@ -319,6 +321,7 @@ export const useStudyStore = create<StudyState>()(
 			shouldOpenCreateCategoryDialog: false,
 			shouldOpenUploadDialog: false,
 			pendingUploadFile: null,
+			pendingUploadUrl: null,
 
 			setCurrentView: (view) =>
 				set((state) => {
@@ -485,6 +488,8 @@ export const useStudyStore = create<StudyState>()(
 				set({ shouldOpenUploadDialog: open }),
 			setPendingUploadFile: (file: File | null) =>
 				set({ pendingUploadFile: file }),
+			setPendingUploadUrl: (url: string | null) =>
+				set({ pendingUploadUrl: url }),
 		}),
 		{
 			name: "stellar-study-store",